@@ -0,0 +1,306 @@
+use crate::criteria::noisy_ei::noise_variance;
+use crate::criteria::{InfillCriterion, candidate_points};
+use crate::errors::{EgoError, Result};
+use egobox_moe::{MixtureGpSurrogate, SamplePaths};
+use ndarray::{Array1, Array2, ArrayView, ArrayView2, Axis};
+use ndarray_rand::RandomExt;
+use ndarray_rand::rand::Rng;
+use ndarray_rand::rand_distr::StandardNormal;
+use rand_xoshiro::Xoshiro256Plus;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+/// Default number of fantasized observations used by the KG criterion
+pub const KG_N_FANTASIES: usize = 16;
+/// Default number of posterior sample paths used by the KG criterion
+/// to estimate posterior covariances
+pub const KG_N_PATHS: usize = 128;
+
+/// Surrogate quantities drawn when conditioning the KG criterion
+struct KgState {
+    /// Posterior sample paths of the objective function
+    paths: Box<dyn SamplePaths>,
+    /// Posterior mean at candidate points (n_d,)
+    mean: Array1<f64>,
+    /// Deviations of the sample paths from the posterior mean at candidate points (n_d, n_paths)
+    deviations: Array2<f64>,
+    /// Fixed standard normal base samples of the fantasized observations (n_fantasies,)
+    z: Array1<f64>,
+}
+
+/// Knowledge Gradient (KG) infill criterion.
+///
+/// The criterion is the expected decrease of the minimum of the posterior mean
+/// once the objective function is observed at `x`, the minimum being taken over
+/// a discretization of the design space made of the training points completed
+/// with LHS points and `x` itself.
+/// This is a discretized KG: unlike the one-shot KG of \[Balandat2020\], the minimum of
+/// the fantasized posterior mean is not optimized jointly with `x` but taken over that
+/// finite set of points.
+/// The expectation is estimated by sample average approximation as in \[Balandat2020\]:
+/// fantasized observations are drawn from standard normal base samples fixed at each
+/// iteration (see [InfillCriterion::condition]) and the posterior covariances required
+/// to update the posterior mean are estimated with posterior sample paths of the surrogate,
+/// so that the criterion is a deterministic function of `x` with analytic derivatives.
+/// KG requires posterior sample paths of the surrogate: conditioning fails when they are
+/// not available (e.g. smooth recombination of several clusters).
+///
+/// \[Balandat2020\]: M. Balandat, B. Karrer, D. R. Jiang, S. Daulton, B. Letham,
+/// A. G. Wilson and E. Bakshy,
+/// [BoTorch: a framework for efficient Monte-Carlo Bayesian optimization](https://arxiv.org/abs/1910.06403),
+/// NeurIPS 2020.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct KnowledgeGradient {
+    /// Number of fantasized observations
+    n_fantasies: usize,
+    /// Number of posterior sample paths
+    n_paths: usize,
+    /// Current conditioning state
+    #[serde(skip)]
+    state: Option<Arc<KgState>>,
+}
+
+impl KnowledgeGradient {
+    /// KG using `n_fantasies` fantasized observations and `n_paths` posterior sample paths
+    pub fn new(n_fantasies: usize, n_paths: usize) -> Self {
+        KnowledgeGradient {
+            n_fantasies: n_fantasies.max(1),
+            n_paths: n_paths.max(2),
+            state: None,
+        }
+    }
+
+    /// KG value and its derivatives wrt x components when `with_grad` is set
+    fn compute(
+        &self,
+        state: &KgState,
+        x: &[f64],
+        obj_model: &dyn MixtureGpSurrogate,
+        with_grad: bool,
+    ) -> Option<(f64, Array1<f64>)> {
+        let nx = x.len();
+        let pt = ArrayView::from_shape((1, nx), x).unwrap();
+        let mu = obj_model.predict(&pt).ok()?[0];
+        let var = obj_model.predict_var(&pt).ok()?[0].max(0.);
+        let s = (var + noise_variance(obj_model, &pt)[0]).sqrt();
+        if s < f64::EPSILON {
+            return Some((0., Array1::zeros(nx)));
+        }
+        let n_paths = state.deviations.ncols() as f64;
+        let path_dev = state.paths.predict(&pt).ok()?.row(0).mapv(|v| v - mu);
+        // posterior covariances between candidates and x, scaled by the fantasy std
+        let sigma_d = state.deviations.dot(&path_dev) / (n_paths * s);
+        let sigma_x = var / s;
+
+        let (_, mu_best_d) = argmin(&state.mean);
+        let baseline = mu_best_d.min(mu);
+        let argmins: Vec<(Option<usize>, f64)> = state
+            .z
+            .iter()
+            .map(|&z| {
+                let (d, v) = argmin(&(&state.mean + &(&sigma_d * z)));
+                let vx = mu + sigma_x * z;
+                if vx < v { (None, vx) } else { (Some(d), v) }
+            })
+            .collect();
+        let n_fantasies = state.z.len() as f64;
+        let value = baseline - argmins.iter().map(|(_, v)| v).sum::<f64>() / n_fantasies;
+        if !with_grad {
+            return Some((value, Array1::zeros(nx)));
+        }
+
+        let mu_prime = obj_model.predict_gradients(&pt).ok()?.row(0).to_owned();
+        let var_prime = obj_model.predict_var_gradients(&pt).ok()?.row(0).to_owned();
+        let s_prime = &var_prime / (2. * s);
+        // derivatives of path deviations at x (nx, n_paths)
+        let dev_prime = state
+            .paths
+            .predict_gradients(&pt)
+            .ok()?
+            .index_axis(Axis(0), 0)
+            .to_owned()
+            - mu_prime.view().insert_axis(Axis(1));
+        let sigma_d_prime = |d: usize| {
+            let c = sigma_d[d] * s;
+            let c_prime = dev_prime.dot(&state.deviations.row(d)) / n_paths;
+            c_prime / s - &s_prime * (c / (s * s))
+        };
+        let sigma_x_prime = &var_prime / s - &s_prime * (var / (s * s));
+
+        let mut grad = if mu < mu_best_d {
+            mu_prime.clone()
+        } else {
+            Array1::zeros(nx)
+        };
+        for ((d, _), z) in argmins.iter().zip(state.z.iter()) {
+            let min_prime = match d {
+                Some(d) => sigma_d_prime(*d) * *z,
+                None => &mu_prime + &(&sigma_x_prime * *z),
+            };
+            grad = grad - min_prime / n_fantasies;
+        }
+        Some((value, grad))
+    }
+}
+
+/// Index and value of the minimum of given values
+fn argmin(values: &Array1<f64>) -> (usize, f64) {
+    values.iter().enumerate().fold(
+        (0, f64::INFINITY),
+        |(i, m), (j, &v)| if v < m { (j, v) } else { (i, m) },
+    )
+}
+
+#[typetag::serde]
+impl InfillCriterion for KnowledgeGradient {
+    fn name(&self) -> &'static str {
+        "KG"
+    }
+
+    /// Compute KG infill criterion at given `x` point using the surrogate model `obj_model`,
+    /// the criterion is zero when it is not conditioned (see [InfillCriterion::condition]).
+    fn value(
+        &self,
+        x: &[f64],
+        obj_model: &dyn MixtureGpSurrogate,
+        _fmin: f64,
+        _sigma_weight: Option<f64>,
+        _scale: Option<f64>,
+    ) -> f64 {
+        self.state
+            .as_ref()
+            .and_then(|state| self.compute(state, x, obj_model, false))
+            .map(|(v, _)| v)
+            .unwrap_or(0.)
+    }
+
+    /// Computes derivatives of KG infill criterion wrt to x components at given `x` point
+    /// using the surrogate model `obj_model`
+    fn grad(
+        &self,
+        x: &[f64],
+        obj_model: &dyn MixtureGpSurrogate,
+        _fmin: f64,
+        _sigma_weight: Option<f64>,
+        _scale: Option<f64>,
+    ) -> Array1<f64> {
+        self.state
+            .as_ref()
+            .and_then(|state| self.compute(state, x, obj_model, true))
+            .map(|(_, g)| g)
+            .unwrap_or_else(|| Array1::zeros(x.len()))
+    }
+
+    /// Draw new posterior sample paths and fantasy base samples
+    ///
+    /// # Errors
+    ///
+    /// [EgoError::EgoError] when posterior sample paths are not available for `obj_model`
+    fn condition(
+        &mut self,
+        x_data: &ArrayView2<f64>,
+        xlimits: &ArrayView2<f64>,
+        obj_model: &dyn MixtureGpSurrogate,
+        rng: &mut Xoshiro256Plus,
+    ) -> Result<()> {
+        self.state = None;
+        let candidates = candidate_points(x_data, xlimits, rng);
+        let state = obj_model
+            .sample_paths(self.n_paths, rng.r#gen())
+            .and_then(|paths| {
+                let values = paths.predict(&candidates.view())?;
+                let mean = obj_model.predict(&candidates.view())?;
+                let deviations = values - mean.view().insert_axis(Axis(1));
+                Ok(KgState {
+                    paths,
+                    mean,
+                    deviations,
+                    z: Array1::random_using(self.n_fantasies, StandardNormal, rng),
+                })
+            });
+        let state = state.map_err(|err| {
+            EgoError::EgoError(format!(
+                "KG criterion requires posterior sample paths of the objective surrogate: {err}"
+            ))
+        })?;
+        self.state = Some(Arc::new(state));
+        Ok(())
+    }
+}
+
+/// Knowledge Gradient infill criterion
+pub const KG: KnowledgeGradient = KnowledgeGradient {
+    n_fantasies: KG_N_FANTASIES,
+    n_paths: KG_N_PATHS,
+    state: None,
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gpmix::mixint::{MixintContext, MoeBuilder};
+    use crate::types::*;
+    use approx::assert_abs_diff_eq;
+    use egobox_moe::{NbClusters, Recombination};
+    use finitediff::FiniteDiff;
+    use linfa::Dataset;
+    use ndarray::array;
+    use ndarray_rand::rand::SeedableRng;
+
+    #[test]
+    fn test_kg_gradients() {
+        let xtypes = vec![XType::Float(0., 25.)];
+        let mixi = MixintContext::new(&xtypes);
+        let surrogate_builder = MoeBuilder::new();
+        let xt = array![[0.], [2.], [5.], [10.], [25.]];
+        let yt = array![0., 0.2, -0.3, 0.5, -1.];
+        let ds = Dataset::new(xt.clone(), yt);
+        let moe = mixi
+            .create_surrogate(&surrogate_builder, &ds)
+            .expect("Mixint surrogate creation");
+
+        let mut kg = KG;
+        let mut rng = Xoshiro256Plus::seed_from_u64(42);
+        kg.condition(&xt.view(), &array![[0., 25.]].view(), &moe, &mut rng)
+            .unwrap();
+        assert!(kg.state.is_some());
+
+        let x = vec![17.];
+        let f = |x: &Vec<f64>| -> f64 { kg.value(x, &moe, -1., None, None) };
+        assert!(f(&x) > 0.);
+        // almost no knowledge gained at training points
+        assert_abs_diff_eq!(kg.value(&[5.], &moe, -1., None, None), 0., epsilon = 1e-3);
+        assert!(kg.value(&[5.], &moe, -1., None, None) < f(&x));
+        assert_abs_diff_eq!(
+            kg.grad(&x, &moe, -1., None, None)[0],
+            x.central_diff(&f)[0],
+            epsilon = 1e-6
+        );
+    }
+
+    #[test]
+    fn test_kg_without_sample_paths() {
+        let xtypes = vec![XType::Float(0., 25.)];
+        let mixi = MixintContext::new(&xtypes);
+        let surrogate_builder = MoeBuilder::new()
+            .n_clusters(NbClusters::fixed(2))
+            .recombination(Recombination::Smooth(Some(1.)))
+            .with_rng(Xoshiro256Plus::seed_from_u64(0));
+        let xt = Array1::linspace(0., 25., 20).insert_axis(Axis(1));
+        let yt = xt
+            .column(0)
+            .mapv(|v: f64| (v - 3.5) * ((v - 3.5) / std::f64::consts::PI).sin());
+        let ds = Dataset::new(xt.clone(), yt);
+        let moe = mixi
+            .create_surrogate(&surrogate_builder, &ds)
+            .expect("Mixint surrogate creation");
+
+        let mut kg = KG;
+        let mut rng = Xoshiro256Plus::seed_from_u64(42);
+        let res = kg.condition(&xt.view(), &array![[0., 25.]].view(), &moe, &mut rng);
+        assert!(matches!(res, Err(EgoError::EgoError(_))));
+        assert!(kg.state.is_none());
+        assert_eq!(kg.value(&[17.], &moe, -1., None, None), 0.);
+    }
+}
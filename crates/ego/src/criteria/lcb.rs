@@ -0,0 +1,177 @@
+use crate::criteria::InfillCriterion;
+use crate::errors::Result;
+use egobox_moe::MixtureGpSurrogate;
+use ndarray::{Array1, ArrayView, ArrayView2};
+use rand_xoshiro::Xoshiro256Plus;
+
+use serde::{Deserialize, Serialize};
+
+/// Default confidence level of the GP-UCB exploration weight schedule
+pub const LCB_GP_UCB_DELTA: f64 = 0.1;
+
+/// Exploration weight `beta` of the lower confidence bound `mu - sqrt(beta) * sigma`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum LcbBeta {
+    /// Constant exploration weight
+    Fixed(f64),
+    /// Exploration weight scheduled as in GP-UCB theory \[Srinivas2010\]:
+    /// `beta_t = 2 log(nx t^2 pi^2 / (6 delta))` where `t` is the number of evaluations,
+    /// `nx` the input dimension and `delta` in ]0, 1[ the given confidence level
+    GpUcb(f64),
+}
+
+/// Lower Confidence Bound (GP-LCB) infill criterion.
+///
+/// The criterion to be maximized is the opposite of the lower confidence bound
+/// `mu - sqrt(beta) * sigma` of the objective function, the larger `beta`
+/// the more exploratory the criterion.
+///
+/// \[Srinivas2010\]: N. Srinivas, A. Krause, S. Kakade and M. Seeger,
+/// [Gaussian process optimization in the bandit setting: no regret and experimental design](https://arxiv.org/abs/0912.3995),
+/// ICML 2010.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct LowerConfidenceBound {
+    /// Exploration weight
+    beta: LcbBeta,
+    /// Current value of the scheduled exploration weight
+    #[serde(skip)]
+    beta_t: Option<f64>,
+}
+
+impl LowerConfidenceBound {
+    /// LCB with the given exploration weight
+    pub fn new(beta: LcbBeta) -> Self {
+        LowerConfidenceBound { beta, beta_t: None }
+    }
+
+    /// Current exploration weight, scheduled weight at first evaluation when not conditioned yet
+    fn beta(&self, nx: usize) -> f64 {
+        match self.beta {
+            LcbBeta::Fixed(beta) => beta,
+            LcbBeta::GpUcb(delta) => self.beta_t.unwrap_or_else(|| gp_ucb_beta(nx, 1, delta)),
+        }
+    }
+}
+
+/// GP-UCB exploration weight after `t` evaluations in dimension `nx` with confidence level `delta`
+fn gp_ucb_beta(nx: usize, t: usize, delta: f64) -> f64 {
+    let t = t.max(1) as f64;
+    (2. * (nx as f64 * t * t * std::f64::consts::PI.powi(2) / (6. * delta)).ln()).max(0.)
+}
+
+#[typetag::serde]
+impl InfillCriterion for LowerConfidenceBound {
+    fn name(&self) -> &'static str {
+        "LCB"
+    }
+
+    /// Compute the opposite of the lower confidence bound at given `x` point
+    /// using the surrogate model `obj_model`.
+    fn value(
+        &self,
+        x: &[f64],
+        obj_model: &dyn MixtureGpSurrogate,
+        _fmin: f64,
+        _sigma_weight: Option<f64>,
+        _scale: Option<f64>,
+    ) -> f64 {
+        let pt = ArrayView::from_shape((1, x.len()), x).unwrap();
+        match (obj_model.predict(&pt), obj_model.predict_var(&pt)) {
+            (Ok(p), Ok(s)) => self.beta(x.len()).sqrt() * s[0].max(0.).sqrt() - p[0],
+            _ => f64::MIN,
+        }
+    }
+
+    /// Computes derivatives of the opposite of the lower confidence bound
+    /// wrt to x components at given `x` point using the surrogate model `obj_model`.
+    fn grad(
+        &self,
+        x: &[f64],
+        obj_model: &dyn MixtureGpSurrogate,
+        _fmin: f64,
+        _sigma_weight: Option<f64>,
+        _scale: Option<f64>,
+    ) -> Array1<f64> {
+        let pt = ArrayView::from_shape((1, x.len()), x).unwrap();
+        match (
+            obj_model.predict_var(&pt),
+            obj_model.predict_gradients(&pt),
+            obj_model.predict_var_gradients(&pt),
+        ) {
+            (Ok(s), Ok(y_prime), Ok(s2_prime)) => {
+                let y_prime = y_prime.row(0);
+                if s[0] < f64::EPSILON {
+                    -y_prime.to_owned()
+                } else {
+                    let sigma = s[0].sqrt();
+                    let k = self.beta(x.len()).sqrt();
+                    s2_prime.row(0).mapv(|v| k * v / (2. * sigma)) - y_prime
+                }
+            }
+            _ => Array1::zeros(x.len()),
+        }
+    }
+
+    /// Update the scheduled exploration weight wrt the number of evaluations
+    fn condition(
+        &mut self,
+        x_data: &ArrayView2<f64>,
+        _xlimits: &ArrayView2<f64>,
+        _obj_model: &dyn MixtureGpSurrogate,
+        _rng: &mut Xoshiro256Plus,
+    ) -> Result<()> {
+        if let LcbBeta::GpUcb(delta) = self.beta {
+            self.beta_t = Some(gp_ucb_beta(x_data.ncols(), x_data.nrows(), delta));
+        }
+        Ok(())
+    }
+}
+
+/// Lower Confidence Bound infill criterion with GP-UCB exploration weight schedule
+pub const LCB: LowerConfidenceBound = LowerConfidenceBound {
+    beta: LcbBeta::GpUcb(LCB_GP_UCB_DELTA),
+    beta_t: None,
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gpmix::mixint::{MixintContext, MoeBuilder};
+    use crate::types::*;
+    use approx::assert_abs_diff_eq;
+    use finitediff::FiniteDiff;
+    use linfa::Dataset;
+    use ndarray::array;
+    use ndarray_rand::rand::SeedableRng;
+
+    #[test]
+    fn test_lcb_gradients() {
+        let xtypes = vec![XType::Float(0., 25.)];
+        let mixi = MixintContext::new(&xtypes);
+        let surrogate_builder = MoeBuilder::new();
+        let xt = array![[0.], [2.], [5.], [10.], [25.]];
+        let yt = array![0., 0.2, -0.3, 0.5, -1.];
+        let ds = Dataset::new(xt.clone(), yt);
+        let moe = mixi
+            .create_surrogate(&surrogate_builder, &ds)
+            .expect("Mixint surrogate creation");
+
+        let x = vec![3.];
+        for lcb in [LCB, LowerConfidenceBound::new(LcbBeta::Fixed(4.))] {
+            let f = |x: &Vec<f64>| -> f64 { lcb.value(x, &moe, 0., None, None) };
+            assert_abs_diff_eq!(
+                lcb.grad(&x, &moe, 0., None, None)[0],
+                x.central_diff(&f)[0],
+                epsilon = 1e-6
+            );
+        }
+
+        // exploration weight increases with the number of evaluations
+        let mut lcb = LCB;
+        let mut rng = Xoshiro256Plus::seed_from_u64(42);
+        lcb.condition(&xt.view(), &array![[0., 25.]].view(), &moe, &mut rng)
+            .unwrap();
+        assert!(lcb.beta(1) > LCB.beta(1));
+        assert_abs_diff_eq!(lcb.beta(1), gp_ucb_beta(1, 5, LCB_GP_UCB_DELTA));
+    }
+}
@@ -0,0 +1,291 @@
+use crate::criteria::{InfillCriterion, candidate_points};
+use crate::errors::Result;
+use crate::utils::{norm_cdf, norm_pdf};
+use egobox_moe::MixtureGpSurrogate;
+use ndarray::{Array1, ArrayView, ArrayView2, Axis, Zip};
+use ndarray_rand::rand::Rng;
+use rand_xoshiro::Xoshiro256Plus;
+
+use serde::{Deserialize, Serialize};
+
+/// Default number of minimum value samples used by the MES criterion
+pub const MES_N_SAMPLES: usize = 10;
+
+/// Method used to draw samples of the minimum value of the objective function
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MinValueSampling {
+    /// Gumbel distribution fitted to the distribution of the minimum of the
+    /// independent posterior marginals at candidate points
+    Gumbel,
+    /// Minimum values of posterior sample paths at candidate points,
+    /// Gumbel sampling is used when sample paths are not available
+    SamplePaths,
+}
+
+/// Max-value Entropy Search (MES) infill criterion.
+///
+/// The criterion is the expected reduction of the entropy of the distribution of
+/// the minimum value of the objective function \[Wang2017\] written for minimization:
+/// `mean_k [gamma_k psi(gamma_k) / (2 Psi(gamma_k)) - ln Psi(gamma_k)]`
+/// where `gamma_k = (mu - m_k) / sigma` and `m_k` are samples of the minimum value
+/// drawn at each iteration (see [InfillCriterion::condition]) from a discretization
+/// of the design space made of the training points completed with LHS points.
+/// `fmin` is used as the only sample when the criterion is not conditioned yet.
+///
+/// \[Wang2017\]: Z. Wang and S. Jegelka,
+/// [Max-value entropy search for efficient Bayesian optimization](https://arxiv.org/abs/1703.01968),
+/// ICML 2017.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct MaxValueEntropySearch {
+    /// Number of minimum value samples
+    n_samples: usize,
+    /// Sampling method of the minimum values
+    sampling: MinValueSampling,
+    /// Current samples of the minimum value
+    #[serde(skip)]
+    fmins: Vec<f64>,
+}
+
+impl MaxValueEntropySearch {
+    /// MES using `n_samples` minimum values drawn with the given `sampling` method
+    pub fn new(n_samples: usize, sampling: MinValueSampling) -> Self {
+        MaxValueEntropySearch {
+            n_samples: n_samples.max(1),
+            sampling,
+            fmins: vec![],
+        }
+    }
+
+    /// Current samples of the minimum value, `fmin` when not conditioned yet
+    fn fmins(&self, fmin: f64) -> Vec<f64> {
+        if self.fmins.is_empty() {
+            vec![fmin]
+        } else {
+            self.fmins.clone()
+        }
+    }
+
+    /// Draw minimum values from the Gumbel approximation of the distribution of the
+    /// minimum of the independent marginals `(mean, sigma)` at candidate points
+    fn gumbel_fmins(
+        &self,
+        mean: &Array1<f64>,
+        sigma: &Array1<f64>,
+        rng: &mut Xoshiro256Plus,
+    ) -> Vec<f64> {
+        // work on the maximum of g = -f: ln P(max g <= y) = sum ln Phi((y + mean_i) / sigma_i)
+        let log_cdf = |y: f64| {
+            Zip::from(mean).and(sigma).fold(0., |acc, &m, &s| {
+                if s < f64::EPSILON {
+                    if y + m >= 0. { acc } else { f64::NEG_INFINITY }
+                } else {
+                    acc + norm_cdf((y + m) / s).max(f64::MIN_POSITIVE).ln()
+                }
+            })
+        };
+        let gmax = mean.fold(f64::NEG_INFINITY, |acc, &m| acc.max(-m));
+        let smax = sigma.fold(0., |acc: f64, &s| acc.max(s));
+        let quantile = |q: f64| {
+            let (mut lo, mut hi) = (gmax - 5. * smax, gmax + 5. * smax);
+            let target = q.ln();
+            for _ in 0..100 {
+                let mid = 0.5 * (lo + hi);
+                if log_cdf(mid) < target {
+                    lo = mid;
+                } else {
+                    hi = mid;
+                }
+                if hi - lo < 1e-8 * (1. + mid.abs()) {
+                    break;
+                }
+            }
+            0.5 * (lo + hi)
+        };
+        let (y1, y2, y3) = (quantile(0.25), quantile(0.5), quantile(0.75));
+        let b = (y1 - y3) / ((-(0.75f64).ln()).ln() - (-(0.25f64).ln()).ln());
+        let a = y2 + b * std::f64::consts::LN_2.ln();
+        (0..self.n_samples)
+            .map(|_| {
+                let u: f64 = rng.r#gen::<f64>().clamp(f64::EPSILON, 1. - f64::EPSILON);
+                -(a - b * (-u.ln()).ln()).max(gmax)
+            })
+            .collect()
+    }
+}
+
+/// MES summand `gamma psi(gamma) / (2 Psi(gamma)) - ln Psi(gamma)` and its derivative wrt gamma
+fn mes_term(gamma: f64) -> (f64, f64) {
+    let pdf = norm_pdf(gamma);
+    let cdf = norm_cdf(gamma).max(f64::MIN_POSITIVE);
+    let value = gamma * pdf / (2. * cdf) - cdf.ln();
+    let deriv = -pdf * (1. + gamma * gamma) / (2. * cdf) - gamma * pdf * pdf / (2. * cdf * cdf);
+    (value, deriv)
+}
+
+#[typetag::serde]
+impl InfillCriterion for MaxValueEntropySearch {
+    fn name(&self) -> &'static str {
+        "MES"
+    }
+
+    /// Compute MES infill criterion at given `x` point using the surrogate model `obj_model`,
+    /// `fmin` is used as minimum value when the criterion is not conditioned yet.
+    fn value(
+        &self,
+        x: &[f64],
+        obj_model: &dyn MixtureGpSurrogate,
+        fmin: f64,
+        _sigma_weight: Option<f64>,
+        _scale: Option<f64>,
+    ) -> f64 {
+        let pt = ArrayView::from_shape((1, x.len()), x).unwrap();
+        match (obj_model.predict(&pt), obj_model.predict_var(&pt)) {
+            (Ok(p), Ok(s)) => {
+                if s[0] < f64::EPSILON {
+                    0.0
+                } else {
+                    let sigma = s[0].sqrt();
+                    let fmins = self.fmins(fmin);
+                    fmins
+                        .iter()
+                        .map(|m| mes_term((p[0] - m) / sigma).0)
+                        .sum::<f64>()
+                        / fmins.len() as f64
+                }
+            }
+            _ => 0.0,
+        }
+    }
+
+    /// Computes derivatives of MES infill criterion wrt to x components at given `x` point
+    /// using the surrogate model `obj_model`.
+    fn grad(
+        &self,
+        x: &[f64],
+        obj_model: &dyn MixtureGpSurrogate,
+        fmin: f64,
+        _sigma_weight: Option<f64>,
+        _scale: Option<f64>,
+    ) -> Array1<f64> {
+        let pt = ArrayView::from_shape((1, x.len()), x).unwrap();
+        match (obj_model.predict(&pt), obj_model.predict_var(&pt)) {
+            (Ok(p), Ok(s)) => {
+                if s[0] < f64::EPSILON {
+                    Array1::zeros(x.len())
+                } else {
+                    let sigma = s[0].sqrt();
+                    let y_prime = obj_model.predict_gradients(&pt).unwrap();
+                    let sig_2_prime = obj_model.predict_var_gradients(&pt).unwrap();
+                    let sig_prime = sig_2_prime.row(0).mapv(|v| v / (2. * sigma));
+                    let fmins = self.fmins(fmin);
+                    fmins.iter().fold(Array1::zeros(x.len()), |acc, m| {
+                        let gamma = (p[0] - m) / sigma;
+                        let gamma_prime = (&y_prime.row(0) - &sig_prime * gamma) / sigma;
+                        acc + gamma_prime * mes_term(gamma).1
+                    }) / fmins.len() as f64
+                }
+            }
+            _ => Array1::zeros(x.len()),
+        }
+    }
+
+    /// Draw new samples of the minimum value of the objective function
+    fn condition(
+        &mut self,
+        x_data: &ArrayView2<f64>,
+        xlimits: &ArrayView2<f64>,
+        obj_model: &dyn MixtureGpSurrogate,
+        rng: &mut Xoshiro256Plus,
+    ) -> Result<()> {
+        let candidates = candidate_points(x_data, xlimits, rng);
+        let (mean, var) = match (
+            obj_model.predict(&candidates.view()),
+            obj_model.predict_var(&candidates.view()),
+        ) {
+            (Ok(mean), Ok(var)) => (mean, var),
+            (Err(err), _) | (_, Err(err)) => {
+                log::warn!("Posterior not available ({err}), MES uses current minimum");
+                self.fmins = vec![];
+                return Ok(());
+            }
+        };
+        // sampled minimum values can not be greater than the posterior minimum
+        let mean_min = mean.fold(f64::INFINITY, |acc, &m| acc.min(m));
+        let paths_fmins = match self.sampling {
+            MinValueSampling::Gumbel => None,
+            MinValueSampling::SamplePaths => {
+                match obj_model
+                    .sample_paths(self.n_samples, rng.r#gen())
+                    .and_then(|paths| paths.predict(&candidates.view()))
+                {
+                    Ok(values) => Some(
+                        values
+                            .map_axis(Axis(0), |c| c.fold(f64::INFINITY, |acc, &v| acc.min(v)))
+                            .mapv(|m| m.min(mean_min))
+                            .to_vec(),
+                    ),
+                    Err(err) => {
+                        log::warn!("Sample paths not available ({err}), MES uses Gumbel sampling");
+                        None
+                    }
+                }
+            }
+        };
+        self.fmins = paths_fmins.unwrap_or_else(|| {
+            let sigma = var.mapv(|v| v.max(0.).sqrt());
+            self.gumbel_fmins(&mean, &sigma, rng)
+        });
+        Ok(())
+    }
+}
+
+/// Max-value Entropy Search infill criterion with Gumbel sampling of the minimum values
+pub const MES: MaxValueEntropySearch = MaxValueEntropySearch {
+    n_samples: MES_N_SAMPLES,
+    sampling: MinValueSampling::Gumbel,
+    fmins: vec![],
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gpmix::mixint::{MixintContext, MoeBuilder};
+    use crate::types::*;
+    use approx::assert_abs_diff_eq;
+    use finitediff::FiniteDiff;
+    use linfa::Dataset;
+    use ndarray::array;
+    use ndarray_rand::rand::SeedableRng;
+
+    #[test]
+    fn test_mes_gradients() {
+        let xtypes = vec![XType::Float(0., 25.)];
+        let mixi = MixintContext::new(&xtypes);
+        let surrogate_builder = MoeBuilder::new();
+        let xt = array![[0.], [2.], [5.], [10.], [25.]];
+        let yt = array![0., 0.2, -0.3, 0.5, -1.];
+        let ds = Dataset::new(xt.clone(), yt);
+        let moe = mixi
+            .create_surrogate(&surrogate_builder, &ds)
+            .expect("Mixint surrogate creation");
+
+        let x = vec![3.];
+        for sampling in [MinValueSampling::Gumbel, MinValueSampling::SamplePaths] {
+            let mut mes = MaxValueEntropySearch::new(MES_N_SAMPLES, sampling);
+            let mut rng = Xoshiro256Plus::seed_from_u64(42);
+            mes.condition(&xt.view(), &array![[0., 25.]].view(), &moe, &mut rng)
+                .unwrap();
+            assert_eq!(mes.fmins.len(), MES_N_SAMPLES);
+            // sampled minimum values are lower than the best observed value
+            assert!(mes.fmins.iter().all(|m| *m <= -1. + 1e-6));
+
+            let f = |x: &Vec<f64>| -> f64 { mes.value(x, &moe, -1., None, None) };
+            assert!(f(&x) > 0.);
+            assert_abs_diff_eq!(
+                mes.grad(&x, &moe, -1., None, None)[0],
+                x.central_diff(&f)[0],
+                epsilon = 1e-6
+            );
+        }
+    }
+}
@@ -1,13 +1,34 @@
 //! Available infill criteria to be used by Egor solver
+mod ehvi;
 mod ei;
+mod kg;
+mod lcb;
+mod mes;
+mod noisy_ei;
+mod pi;
+mod thompson;
 mod wb2;
 
 pub use ei::{EI, ExpectedImprovement, LOG_EI, LogExpectedImprovement};
+pub use kg::{KG, KG_N_FANTASIES, KG_N_PATHS, KnowledgeGradient};
+pub use lcb::{LCB, LCB_GP_UCB_DELTA, LcbBeta, LowerConfidenceBound};
+pub use mes::{MES, MES_N_SAMPLES, MaxValueEntropySearch, MinValueSampling};
+pub use noisy_ei::{
+    AEI, AugmentedExpectedImprovement, SIEI_N_FANTASIES, SampledIncumbentExpectedImprovement,
+};
+pub use pi::{PI, PI_MARGIN, ProbabilityOfImprovement};
+pub use thompson::{TS, ThompsonSampling};
 pub use wb2::{WB2, WB2Criterion, WB2S};
 
+pub(crate) use ehvi::ExpectedHypervolumeImprovement;
+
+use crate::errors::Result;
 use dyn_clonable::*;
+use egobox_doe::{Lhs, LhsKind, SamplingMethod};
 use egobox_moe::MixtureGpSurrogate;
-use ndarray::{Array1, ArrayView2};
+use ndarray::{Array1, Array2, ArrayView2, Axis, concatenate, s};
+use ndarray_rand::rand::{Rng, SeedableRng};
+use rand_xoshiro::Xoshiro256Plus;
 
 /// A trait for infill criterion which maximmum location will
 /// determine the next most promising point expected to be the
@@ -51,6 +72,25 @@ pub trait InfillCriterion: Clone + Sync {
     ) -> f64 {
         1.0
     }
+
+    /// Update the criterion wrt the surrogate of the objective function
+    /// trained on the `x_data` points within `xlimits` design space bounds,
+    /// called before each criterion optimization.
+    /// Nothing is done by default, criteria depending on the training data
+    /// (e.g. fantasies of the objective values) are expected to override it.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned when the criterion can not be computed with the given surrogate.
+    fn condition(
+        &mut self,
+        _x_data: &ArrayView2<f64>,
+        _xlimits: &ArrayView2<f64>,
+        _obj_model: &dyn MixtureGpSurrogate,
+        _rng: &mut Xoshiro256Plus,
+    ) -> Result<()> {
+        Ok(())
+    }
 }
 
 impl std::fmt::Debug for dyn InfillCriterion {
@@ -58,3 +98,18 @@ impl std::fmt::Debug for dyn InfillCriterion {
         write!(f, "{}", self.name())
     }
 }
+
+/// Discrete set of candidate points used by criteria relying on a discretization
+/// of the design space: the `x_data` points completed with LHS points within `xlimits`
+pub(crate) fn candidate_points(
+    x_data: &ArrayView2<f64>,
+    xlimits: &ArrayView2<f64>,
+    rng: &mut Xoshiro256Plus,
+) -> Array2<f64> {
+    let n = (100 * xlimits.nrows()).min(1000);
+    let lhs = Lhs::new(xlimits)
+        .with_rng(Xoshiro256Plus::seed_from_u64(rng.r#gen()))
+        .kind(LhsKind::Maximin)
+        .sample(n);
+    concatenate![Axis(0), x_data.slice(s![.., ..]), lhs]
+}
@@ -0,0 +1,294 @@
+use crate::criteria::{EI, InfillCriterion};
+use crate::errors::Result;
+use egobox_moe::MixtureGpSurrogate;
+use ndarray::{Array1, Array2, ArrayView, ArrayView2};
+use ndarray_rand::rand::Rng;
+use ndarray_rand::rand_distr::StandardNormal;
+use rand_xoshiro::Xoshiro256Plus;
+
+use serde::{Deserialize, Serialize};
+
+/// Default number of fantasies used by the sampled incumbent EI criterion
+pub const SIEI_N_FANTASIES: usize = 32;
+
+/// Noise variance of the objective surrogate at given `x` points (n, nx):
+/// the noise variance of the expert of the cluster containing the point
+/// (zero for interpolating experts)
+pub(crate) fn noise_variance(
+    obj_model: &dyn MixtureGpSurrogate,
+    x: &ArrayView2<f64>,
+) -> Array1<f64> {
+    let experts = obj_model.experts();
+    if experts.len() == 1 {
+        Array1::from_elem(x.nrows(), experts[0].noise_variance())
+    } else {
+        obj_model
+            .to_clustering()
+            .predict_clusters(x)
+            .mapv(|k| experts[k].noise_variance())
+    }
+}
+
+/// Augmented Expected Improvement (AEI) infill criterion suited to noisy evaluations.
+///
+/// EI of the latent function wrt `fmin` (expected to be the best posterior mean
+/// among evaluated points) is multiplied by the factor `1 - tau / sqrt(s2 + tau2)`
+/// penalizing points where the noise variance `tau2` dominates the prediction variance `s2`,
+/// hence discouraging replications at already well known locations.
+/// The noise variance is estimated by the surrogate experts, AEI is EI
+/// when an interpolating surrogate is used.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct AugmentedExpectedImprovement;
+
+impl AugmentedExpectedImprovement {
+    /// Penalization factor and its derivatives wrt x components
+    fn penalty(&self, x: &[f64], obj_model: &dyn MixtureGpSurrogate) -> Option<(f64, Array1<f64>)> {
+        let pt = ArrayView::from_shape((1, x.len()), x).unwrap();
+        let s2 = obj_model.predict_var(&pt).ok()?[0].max(0.);
+        let tau2 = noise_variance(obj_model, &pt)[0];
+        if tau2 < f64::EPSILON {
+            Some((1., Array1::zeros(x.len())))
+        } else {
+            let tau = tau2.sqrt();
+            let total = s2 + tau2;
+            let s2_prime = obj_model.predict_var_gradients(&pt).ok()?.row(0).to_owned();
+            let factor_prime = s2_prime.mapv(|v| tau * v / (2. * total * total.sqrt()));
+            Some((1. - tau / total.sqrt(), factor_prime))
+        }
+    }
+}
+
+#[typetag::serde]
+impl InfillCriterion for AugmentedExpectedImprovement {
+    fn name(&self) -> &'static str {
+        "AEI"
+    }
+
+    /// Compute AEI infill criterion at given `x` point using the surrogate model `obj_model`
+    /// and the current best posterior mean of the objective function.
+    fn value(
+        &self,
+        x: &[f64],
+        obj_model: &dyn MixtureGpSurrogate,
+        fmin: f64,
+        sigma_weight: Option<f64>,
+        scale: Option<f64>,
+    ) -> f64 {
+        match self.penalty(x, obj_model) {
+            Some((factor, _)) => EI.value(x, obj_model, fmin, sigma_weight, scale) * factor,
+            None => 0.0,
+        }
+    }
+
+    /// Computes derivatives of AEI infill criterion wrt to x components at given `x` point
+    /// using the surrogate model `obj_model` and the current best posterior mean of the objective function.
+    fn grad(
+        &self,
+        x: &[f64],
+        obj_model: &dyn MixtureGpSurrogate,
+        fmin: f64,
+        sigma_weight: Option<f64>,
+        scale: Option<f64>,
+    ) -> Array1<f64> {
+        match self.penalty(x, obj_model) {
+            Some((factor, factor_prime)) => {
+                let ei = EI.value(x, obj_model, fmin, sigma_weight, scale);
+                let ei_prime = EI.grad(x, obj_model, fmin, sigma_weight, scale);
+                ei_prime * factor + factor_prime * ei
+            }
+            None => Array1::zeros(x.len()),
+        }
+    }
+}
+
+/// Augmented Expected Improvement infill criterion
+pub const AEI: AugmentedExpectedImprovement = AugmentedExpectedImprovement {};
+
+/// Sampled Incumbent Expected Improvement (SIEI) infill criterion suited to noisy evaluations.
+///
+/// The unknown current minimum is integrated out using fantasies: joint samples of
+/// the latent objective function at the evaluated points are drawn from the surrogate
+/// posterior and SIEI is the mean of the EIs wrt the minimum of each fantasy.
+/// Fantasies are drawn at each iteration (see [InfillCriterion::condition]),
+/// when the surrogate can not be jointly sampled (i.e. several clusters)
+/// fantasies are drawn independently at each point from the posterior marginals.
+/// Unlike the Noisy EI of \[Letham2019\], the posterior at `x` is not conditioned
+/// on each fantasy: only the incumbent minimum is sampled.
+///
+/// \[Letham2019\]: B. Letham, B. Karrer, G. Ottoni and E. Bakshy,
+/// [Constrained Bayesian optimization with noisy experiments](https://doi.org/10.1214/18-BA1110),
+/// Bayesian Analysis, 14(2), 495-519, 2019.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SampledIncumbentExpectedImprovement {
+    /// Number of fantasies
+    n_fantasies: usize,
+    /// Minima of the current fantasies
+    #[serde(skip)]
+    fmins: Vec<f64>,
+}
+
+impl SampledIncumbentExpectedImprovement {
+    /// SIEI using the given number of fantasies
+    pub fn new(n_fantasies: usize) -> Self {
+        SampledIncumbentExpectedImprovement {
+            n_fantasies: n_fantasies.max(1),
+            fmins: vec![],
+        }
+    }
+
+    /// Minima of the fantasies, `fmin` when not conditioned yet
+    fn fmins(&self, fmin: f64) -> Vec<f64> {
+        if self.fmins.is_empty() {
+            vec![fmin]
+        } else {
+            self.fmins.clone()
+        }
+    }
+}
+
+impl Default for SampledIncumbentExpectedImprovement {
+    fn default() -> Self {
+        Self::new(SIEI_N_FANTASIES)
+    }
+}
+
+#[typetag::serde]
+impl InfillCriterion for SampledIncumbentExpectedImprovement {
+    fn name(&self) -> &'static str {
+        "SIEI"
+    }
+
+    /// Compute SIEI infill criterion at given `x` point using the surrogate model `obj_model`.
+    /// `fmin` is only used when fantasies are not drawn yet.
+    fn value(
+        &self,
+        x: &[f64],
+        obj_model: &dyn MixtureGpSurrogate,
+        fmin: f64,
+        sigma_weight: Option<f64>,
+        scale: Option<f64>,
+    ) -> f64 {
+        let fmins = self.fmins(fmin);
+        fmins
+            .iter()
+            .map(|f| EI.value(x, obj_model, *f, sigma_weight, scale))
+            .sum::<f64>()
+            / fmins.len() as f64
+    }
+
+    /// Computes derivatives of SIEI infill criterion wrt to x components at given `x` point
+    /// using the surrogate model `obj_model`.
+    fn grad(
+        &self,
+        x: &[f64],
+        obj_model: &dyn MixtureGpSurrogate,
+        fmin: f64,
+        sigma_weight: Option<f64>,
+        scale: Option<f64>,
+    ) -> Array1<f64> {
+        let fmins = self.fmins(fmin);
+        fmins.iter().fold(Array1::zeros(x.len()), |acc, f| {
+            acc + EI.grad(x, obj_model, *f, sigma_weight, scale)
+        }) / fmins.len() as f64
+    }
+
+    /// Draw fantasies of the objective values at the evaluated points `x_data`
+    fn condition(
+        &mut self,
+        x_data: &ArrayView2<f64>,
+        _xlimits: &ArrayView2<f64>,
+        obj_model: &dyn MixtureGpSurrogate,
+        rng: &mut Xoshiro256Plus,
+    ) -> Result<()> {
+        let fantasies = obj_model.sample(x_data, self.n_fantasies).or_else(|_| {
+            let mean = obj_model.predict(x_data)?;
+            let var = obj_model.predict_var(x_data)?;
+            Ok::<_, egobox_moe::MoeError>(Array2::from_shape_fn(
+                (x_data.nrows(), self.n_fantasies),
+                |(i, _)| mean[i] + var[i].max(0.).sqrt() * rng.sample::<f64, _>(StandardNormal),
+            ))
+        });
+        self.fmins = match fantasies {
+            Ok(fantasies) => fantasies
+                .columns()
+                .into_iter()
+                .map(|c| c.fold(f64::INFINITY, |acc, v| acc.min(*v)))
+                .collect(),
+            Err(err) => {
+                log::warn!("Fantasies not available ({err}), SIEI falls back to EI");
+                vec![]
+            }
+        };
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gpmix::mixint::{MixintContext, MoeBuilder};
+    use crate::types::*;
+    use approx::assert_abs_diff_eq;
+    use egobox_moe::{GpType, RobustLikelihood};
+    use finitediff::FiniteDiff;
+    use linfa::Dataset;
+    use ndarray::array;
+    use ndarray_rand::rand::SeedableRng;
+
+    #[test]
+    fn test_noisy_criteria_gradients() {
+        let xtypes = vec![XType::Float(0., 1.)];
+        let mixi = MixintContext::new(&xtypes);
+        let mut surrogate_builder = MoeBuilder::new();
+        surrogate_builder.set_gp_type(GpType::RobustGp {
+            likelihood: RobustLikelihood::Gaussian,
+        });
+        let xt = array![
+            [0.],
+            [0.1],
+            [0.2],
+            [0.3],
+            [0.4],
+            [0.5],
+            [0.6],
+            [0.7],
+            [0.8],
+            [1.]
+        ];
+        let yt = array![0.1, -0.2, 0.25, -0.4, -0.1, -0.8, -0.3, -0.5, 0.1, 0.4];
+        let ds = Dataset::new(xt.clone(), yt);
+        let moe = mixi
+            .create_surrogate(&surrogate_builder, &ds)
+            .expect("Mixint surrogate creation");
+        assert!(moe.experts()[0].noise_variance() > 0.);
+
+        let x = vec![0.55];
+        let f = |x: &Vec<f64>| -> f64 { AEI.value(x, &moe, -0.5, None, None) };
+        assert!(f(&x) > 0.);
+        // noise penalization
+        assert!(f(&x) < EI.value(&x, &moe, -0.5, None, None));
+        assert_abs_diff_eq!(
+            AEI.grad(&x, &moe, -0.5, None, None)[0],
+            x.central_diff(&f)[0],
+            epsilon = 1e-6
+        );
+
+        let mut siei = SampledIncumbentExpectedImprovement::new(16);
+        // not conditioned: EI
+        assert_abs_diff_eq!(
+            siei.value(&x, &moe, -0.5, None, None),
+            EI.value(&x, &moe, -0.5, None, None)
+        );
+        let mut rng = Xoshiro256Plus::seed_from_u64(42);
+        siei.condition(&xt.view(), &array![[0., 1.]].view(), &moe, &mut rng)
+            .unwrap();
+        assert_eq!(siei.fmins.len(), 16);
+        let f = |x: &Vec<f64>| -> f64 { siei.value(x, &moe, 0., None, None) };
+        assert!(f(&x) > 0.);
+        assert_abs_diff_eq!(
+            siei.grad(&x, &moe, 0., None, None)[0],
+            x.central_diff(&f)[0],
+            epsilon = 1e-6
+        );
+    }
+}
@@ -0,0 +1,127 @@
+use crate::criteria::InfillCriterion;
+use crate::utils::{norm_cdf, norm_pdf};
+use egobox_moe::MixtureGpSurrogate;
+use ndarray::{Array1, ArrayView};
+
+use serde::{Deserialize, Serialize};
+
+/// Default margin of the probability of improvement criterion
+pub const PI_MARGIN: f64 = 0.01;
+
+/// Probability of Improvement (PI) infill criterion.
+///
+/// The criterion is the probability for the objective function to be lower than
+/// `fmin - margin` where `margin` is a non-negative improvement threshold:
+/// the larger the margin the more exploratory the criterion.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ProbabilityOfImprovement {
+    /// Improvement margin
+    margin: f64,
+}
+
+impl ProbabilityOfImprovement {
+    /// PI with the given improvement margin
+    pub fn new(margin: f64) -> Self {
+        ProbabilityOfImprovement {
+            margin: margin.max(0.),
+        }
+    }
+}
+
+#[typetag::serde]
+impl InfillCriterion for ProbabilityOfImprovement {
+    fn name(&self) -> &'static str {
+        "PI"
+    }
+
+    /// Compute PI infill criterion at given `x` point using the surrogate model `obj_model`
+    /// and the current minimum of the objective function.
+    fn value(
+        &self,
+        x: &[f64],
+        obj_model: &dyn MixtureGpSurrogate,
+        fmin: f64,
+        _sigma_weight: Option<f64>,
+        _scale: Option<f64>,
+    ) -> f64 {
+        let pt = ArrayView::from_shape((1, x.len()), x).unwrap();
+        match (obj_model.predict(&pt), obj_model.predict_var(&pt)) {
+            (Ok(p), Ok(s)) => {
+                if s[0] < f64::EPSILON {
+                    0.0
+                } else {
+                    norm_cdf((fmin - self.margin - p[0]) / s[0].sqrt())
+                }
+            }
+            _ => 0.0,
+        }
+    }
+
+    /// Computes derivatives of PI infill criterion wrt to x components at given `x` point
+    /// using the surrogate model `obj_model` and the current minimum of the objective function.
+    fn grad(
+        &self,
+        x: &[f64],
+        obj_model: &dyn MixtureGpSurrogate,
+        fmin: f64,
+        _sigma_weight: Option<f64>,
+        _scale: Option<f64>,
+    ) -> Array1<f64> {
+        let pt = ArrayView::from_shape((1, x.len()), x).unwrap();
+        match (obj_model.predict(&pt), obj_model.predict_var(&pt)) {
+            (Ok(p), Ok(s)) => {
+                if s[0] < f64::EPSILON {
+                    Array1::zeros(x.len())
+                } else {
+                    let sigma = s[0].sqrt();
+                    let arg = (fmin - self.margin - p[0]) / sigma;
+                    let y_prime = obj_model.predict_gradients(&pt).unwrap();
+                    let sig_2_prime = obj_model.predict_var_gradients(&pt).unwrap();
+                    let sig_prime = sig_2_prime.row(0).mapv(|v| v / (2. * sigma));
+                    let arg_prime = y_prime.row(0).mapv(|v| -v / sigma) - sig_prime * arg / sigma;
+                    arg_prime * norm_pdf(arg)
+                }
+            }
+            _ => Array1::zeros(x.len()),
+        }
+    }
+}
+
+/// Probability of Improvement infill criterion with default margin
+pub const PI: ProbabilityOfImprovement = ProbabilityOfImprovement { margin: PI_MARGIN };
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gpmix::mixint::{MixintContext, MoeBuilder};
+    use crate::types::*;
+    use approx::assert_abs_diff_eq;
+    use finitediff::FiniteDiff;
+    use linfa::Dataset;
+    use ndarray::array;
+
+    #[test]
+    fn test_pi_gradients() {
+        let xtypes = vec![XType::Float(0., 25.)];
+        let mixi = MixintContext::new(&xtypes);
+        let surrogate_builder = MoeBuilder::new();
+        let xt = array![[0.], [2.], [5.], [10.], [25.]];
+        let yt = array![0., 0.2, -0.3, 0.5, -1.];
+        let ds = Dataset::new(xt, yt);
+        let moe = mixi
+            .create_surrogate(&surrogate_builder, &ds)
+            .expect("Mixint surrogate creation");
+
+        let x = vec![3.];
+        let f = |x: &Vec<f64>| -> f64 { PI.value(x, &moe, -0.3, None, None) };
+        assert!(f(&x) > 0. && f(&x) < 1.);
+        assert_abs_diff_eq!(
+            PI.grad(&x, &moe, -0.3, None, None)[0],
+            x.central_diff(&f)[0],
+            epsilon = 1e-6
+        );
+        // larger margin, lower probability
+        let pi = ProbabilityOfImprovement::new(0.5);
+        assert!(pi.value(&x, &moe, -0.3, None, None) < f(&x));
+    }
+}
@@ -0,0 +1,154 @@
+use crate::criteria::InfillCriterion;
+use crate::errors::Result;
+use egobox_moe::{MixtureGpSurrogate, SamplePaths};
+use ndarray::{Array1, ArrayView, ArrayView2, Axis};
+use ndarray_rand::rand::Rng;
+use ndarray_rand::rand_distr::StandardNormal;
+use rand_xoshiro::Xoshiro256Plus;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+/// Thompson Sampling (TS) infill criterion.
+///
+/// A posterior sample path of the objective function is drawn from the surrogate
+/// at each iteration (see [InfillCriterion::condition]) and the next point is its minimum,
+/// the criterion to be maximized being the opposite of the path.
+/// When sample paths are not available (e.g. smooth recombination of several clusters),
+/// the path is approximated by the posterior quantile `mu + z * sigma` where `z`
+/// is drawn from the standard normal distribution.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct ThompsonSampling {
+    /// Current posterior sample path
+    #[serde(skip)]
+    path: Option<Arc<dyn SamplePaths>>,
+    /// Current quantile of the posterior marginals used when paths are not available
+    #[serde(skip)]
+    z: f64,
+}
+
+#[typetag::serde]
+impl InfillCriterion for ThompsonSampling {
+    fn name(&self) -> &'static str {
+        "TS"
+    }
+
+    /// Compute the opposite of the current sample path at given `x` point,
+    /// the surrogate model `obj_model` is used when no path is available.
+    fn value(
+        &self,
+        x: &[f64],
+        obj_model: &dyn MixtureGpSurrogate,
+        _fmin: f64,
+        _sigma_weight: Option<f64>,
+        _scale: Option<f64>,
+    ) -> f64 {
+        let pt = ArrayView::from_shape((1, x.len()), x).unwrap();
+        match &self.path {
+            Some(path) => path.predict(&pt).map(|v| -v[[0, 0]]).unwrap_or(f64::MIN),
+            None => match (obj_model.predict(&pt), obj_model.predict_var(&pt)) {
+                (Ok(p), Ok(s)) => -(p[0] + self.z * s[0].max(0.).sqrt()),
+                _ => f64::MIN,
+            },
+        }
+    }
+
+    /// Computes derivatives of the opposite of the current sample path wrt to x components
+    /// at given `x` point, the surrogate model `obj_model` is used when no path is available.
+    fn grad(
+        &self,
+        x: &[f64],
+        obj_model: &dyn MixtureGpSurrogate,
+        _fmin: f64,
+        _sigma_weight: Option<f64>,
+        _scale: Option<f64>,
+    ) -> Array1<f64> {
+        let pt = ArrayView::from_shape((1, x.len()), x).unwrap();
+        match &self.path {
+            Some(path) => path
+                .predict_gradients(&pt)
+                .map(|d| -d.index_axis(Axis(0), 0).column(0).to_owned())
+                .unwrap_or_else(|_| Array1::zeros(x.len())),
+            None => match (
+                obj_model.predict_var(&pt),
+                obj_model.predict_gradients(&pt),
+                obj_model.predict_var_gradients(&pt),
+            ) {
+                (Ok(s), Ok(y_prime), Ok(s2_prime)) => {
+                    let y_prime = y_prime.row(0);
+                    if s[0] < f64::EPSILON {
+                        -y_prime.to_owned()
+                    } else {
+                        let sigma = s[0].sqrt();
+                        s2_prime.row(0).mapv(|v| -self.z * v / (2. * sigma)) - y_prime
+                    }
+                }
+                _ => Array1::zeros(x.len()),
+            },
+        }
+    }
+
+    /// Draw a new posterior sample path of the objective function
+    fn condition(
+        &mut self,
+        _x_data: &ArrayView2<f64>,
+        _xlimits: &ArrayView2<f64>,
+        obj_model: &dyn MixtureGpSurrogate,
+        rng: &mut Xoshiro256Plus,
+    ) -> Result<()> {
+        self.path = match obj_model.sample_paths(1, rng.r#gen()) {
+            Ok(path) => Some(Arc::from(path)),
+            Err(err) => {
+                log::warn!("Sample paths not available ({err}), TS uses posterior quantiles");
+                None
+            }
+        };
+        self.z = rng.sample(StandardNormal);
+        Ok(())
+    }
+}
+
+/// Thompson Sampling infill criterion
+pub const TS: ThompsonSampling = ThompsonSampling { path: None, z: 0. };
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gpmix::mixint::{MixintContext, MoeBuilder};
+    use crate::types::*;
+    use approx::assert_abs_diff_eq;
+    use finitediff::FiniteDiff;
+    use linfa::Dataset;
+    use ndarray::array;
+    use ndarray_rand::rand::SeedableRng;
+
+    #[test]
+    fn test_ts_gradients() {
+        let xtypes = vec![XType::Float(0., 25.)];
+        let mixi = MixintContext::new(&xtypes);
+        let surrogate_builder = MoeBuilder::new();
+        let xt = array![[0.], [2.], [5.], [10.], [25.]];
+        let yt = array![0., 0.2, -0.3, 0.5, -1.];
+        let ds = Dataset::new(xt.clone(), yt);
+        let moe = mixi
+            .create_surrogate(&surrogate_builder, &ds)
+            .expect("Mixint surrogate creation");
+
+        let mut ts = TS;
+        let mut rng = Xoshiro256Plus::seed_from_u64(42);
+        ts.condition(&xt.view(), &array![[0., 25.]].view(), &moe, &mut rng)
+            .unwrap();
+        assert!(ts.path.is_some());
+
+        // path interpolates training data
+        assert_abs_diff_eq!(ts.value(&[5.], &moe, 0., None, None), 0.3, epsilon = 1e-2);
+
+        let x = vec![3.];
+        let f = |x: &Vec<f64>| -> f64 { ts.value(x, &moe, 0., None, None) };
+        assert_abs_diff_eq!(
+            ts.grad(&x, &moe, 0., None, None)[0],
+            x.central_diff(&f)[0],
+            epsilon = 1e-4
+        );
+    }
+}
@@ -105,6 +105,7 @@ use crate::HotStartMode;
 use crate::errors::Result;
 use crate::gpmix::mixint::*;
 use crate::types::*;
+use crate::utils::find_pareto_indices;
 use crate::{CHECKPOINT_FILE, CheckpointingFrequency, HotStartCheckpoint};
 use crate::{EgorSolver, to_xtypes};
 
@@ -112,7 +113,7 @@ use argmin::core::observers::ObserverMode;
 
 use egobox_moe::GpMixtureParams;
 use log::info;
-use ndarray::{Array2, ArrayBase, Axis, Data, Ix2, concatenate};
+use ndarray::{Array2, ArrayBase, Axis, Data, Ix2, concatenate, s};
 
 use argmin::core::{Error, Executor, KV, State, observers::Observe};
 use serde::de::DeserializeOwned;
@@ -180,6 +181,23 @@ impl<O: GroupFunc, C: CstrFn> EgorFactory<O, C> {
         }
     }
 
+    /// Build an Egor optimizer to minimize the function within the continuous `xlimits`
+    /// (see [`min_within()`](EgorFactory::min_within)) using surrogates built with the given
+    /// `SB` builder, for instance `GpMixtureParams<f32>` to train single precision surrogates.
+    pub fn min_within_using<SB: SurrogateBuilder + DeserializeOwned>(
+        self,
+        xlimits: &ArrayBase<impl Data<Elem = f64>, Ix2>,
+    ) -> Egor<O, C, SB> {
+        let config = EgorConfig {
+            xtypes: to_xtypes(xlimits),
+            ..self.config.clone()
+        };
+        Egor {
+            fobj: ObjFunc::new(self.fobj).subject_to(self.fcstrs),
+            solver: EgorSolver::new(config),
+        }
+    }
+
     /// Build an Egor optimizer to minimize the function R^n -> R^p taking
     /// inputs specified with given xtypes where some of components may be
     /// discrete variables (mixed-integer optimization).
@@ -210,6 +228,7 @@ pub struct Egor<
 impl<O: GroupFunc, C: CstrFn, SB: SurrogateBuilder + DeserializeOwned> Egor<O, C, SB> {
     /// Runs the (constrained) optimization of the objective function.
     pub fn run(&self) -> Result<OptimResult<f64>> {
+        self.solver.config.check()?;
         let xtypes = self.solver.config.xtypes.clone();
         info!("{:?}", self.solver.config);
         if let Some(outdir) = self.solver.config.outdir.as_ref() {
@@ -248,11 +267,22 @@ impl<O: GroupFunc, C: CstrFn, SB: SurrogateBuilder + DeserializeOwned> Egor<O, C
         info!("{result}");
         let (x_data, y_data, c_data) = result.state().clone().take_data().unwrap();
 
+        let n_obj = self.solver.config.n_obj;
+        let pareto_indices = (n_obj > 1)
+            .then(|| find_pareto_indices(&y_data, &c_data, n_obj, &result.state.cstr_tol));
+        let pareto_front = pareto_indices
+            .as_ref()
+            .map(|indices| y_data.slice(s![.., ..n_obj]).select(Axis(0), indices));
+
         let res = if !self.solver.config.discrete() {
             info!("Data: \n{}", concatenate![Axis(1), x_data, y_data, c_data]);
             OptimResult {
                 x_opt: result.state.get_best_param().unwrap().to_owned(),
                 y_opt: result.state.get_full_best_cost().unwrap().to_owned(),
+                pareto_set: pareto_indices
+                    .as_ref()
+                    .map(|indices| x_data.select(Axis(0), indices)),
+                pareto_front,
                 x_doe: x_data,
                 y_doe: y_data,
                 state: result.state,
@@ -271,12 +301,19 @@ impl<O: GroupFunc, C: CstrFn, SB: SurrogateBuilder + DeserializeOwned> Egor<O, C
             OptimResult {
                 x_opt: x_opt.row(0).to_owned(),
                 y_opt: result.state.get_full_best_cost().unwrap().to_owned(),
+                pareto_set: pareto_indices
+                    .as_ref()
+                    .map(|indices| x_data.select(Axis(0), indices)),
+                pareto_front,
                 x_doe: x_data,
                 y_doe: y_data,
                 state: result.state,
             }
         };
         info!("Optim Result: min f(x)={} at x={}", res.y_opt, res.x_opt);
+        if let Some(front) = res.pareto_front.as_ref() {
+            info!("Pareto front of {} points: \n{}", front.nrows(), front);
+        }
 
         Ok(res)
     }
@@ -351,13 +388,16 @@ pub type EgorBuilder<O> = EgorFactory<O, Cstr>;
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::errors::EgoError;
     use approx::assert_abs_diff_eq;
     use argmin_testfunctions::rosenbrock;
     use egobox_doe::{Lhs, SamplingMethod};
-    use egobox_moe::NbClusters;
-    use ndarray::{Array1, Array2, ArrayView2, Ix1, Zip, array, s};
-    use ndarray_rand::rand::SeedableRng;
+    use egobox_moe::{GpType, KplsSelection, NbClusters, Parallelism, RobustLikelihood};
+    use ndarray::{Array1, Array2, ArrayView2, Axis, Ix1, Zip, array, s};
+    use ndarray_rand::rand::{Rng, SeedableRng};
+    use ndarray_rand::rand_distr::Normal;
     use rand_xoshiro::Xoshiro256Plus;
+    use std::sync::{Arc, Mutex};
 
     use ndarray_npy::read_npy;
 
@@ -461,6 +501,163 @@ mod tests {
         assert_abs_diff_eq!(expected, res.y_opt, epsilon = 2e-3);
     }
 
+    #[test]
+    #[serial]
+    fn test_xsinx_f32_surrogate_egor_builder() {
+        let initial_doe = array![[0.], [7.], [25.]];
+        let res = EgorBuilder::optimize(xsinx)
+            .configure(|cfg| {
+                cfg.infill_strategy(InfillStrategy::EI)
+                    .infill_optimizer(InfillOptimizer::Slsqp)
+                    .max_iters(10)
+                    .doe(&initial_doe)
+                    .seed(42)
+            })
+            .min_within_using::<GpMixtureParams<f32>>(&array![[0.0, 25.0]])
+            .run()
+            .expect("Egor should minimize xsinx");
+        let expected = array![-15.125];
+        assert_abs_diff_eq!(expected, res.y_opt, epsilon = 1e-2);
+    }
+
+    #[test]
+    #[serial]
+    fn test_xsinx_robust_egor_builder() {
+        // Evaluation at x=7 fails and returns a spurious value
+        let xsinx_spurious = |x: &ArrayView2<f64>| {
+            let mut y = xsinx(x);
+            Zip::from(y.rows_mut())
+                .and(x.rows())
+                .for_each(|mut yi, xi| {
+                    if xi[0] == 7. {
+                        yi[0] = 30.
+                    }
+                });
+            y
+        };
+        let initial_doe = array![[0.], [7.], [15.], [25.]];
+        let res = EgorBuilder::optimize(xsinx_spurious)
+            .configure(|cfg| {
+                cfg.configure_gp(|gp| {
+                    gp.gp_type(GpType::RobustGp {
+                        likelihood: RobustLikelihood::default(),
+                    })
+                })
+                .infill_strategy(InfillStrategy::EI)
+                .infill_optimizer(InfillOptimizer::Slsqp)
+                .max_iters(15)
+                .doe(&initial_doe)
+                .seed(42)
+            })
+            .min_within(&array![[0.0, 25.0]])
+            .run()
+            .expect("Egor should minimize xsinx");
+        let expected = array![-15.125];
+        assert_abs_diff_eq!(expected, res.y_opt, epsilon = 1e-1);
+    }
+
+    fn check_noisy_xsinx(infill: InfillStrategy) {
+        // xsinx corrupted by a gaussian noise of standard deviation 0.7 drawn from a seeded rng
+        let rng = Arc::new(Mutex::new(Xoshiro256Plus::seed_from_u64(0)));
+        let noise = Normal::new(0., 0.7).unwrap();
+        let noisy_xsinx = move |x: &ArrayView2<f64>| {
+            let mut rng = rng.lock().unwrap();
+            xsinx(x) + x.mapv(|_| rng.sample(noise))
+        };
+        let initial_doe = array![[0.], [5.], [10.], [15.], [20.], [25.]];
+        let res = EgorBuilder::optimize(noisy_xsinx)
+            .configure(|cfg| {
+                cfg.configure_gp(|gp| {
+                    gp.gp_type(GpType::RobustGp {
+                        likelihood: RobustLikelihood::Gaussian,
+                    })
+                })
+                .infill_strategy(infill)
+                .noisy(true)
+                .max_iters(15)
+                .doe(&initial_doe)
+                .seed(42)
+            })
+            .min_within(&array![[0.0, 25.0]])
+            .run()
+            .expect("Egor should minimize noisy xsinx");
+        // reported optimum is the best posterior mean
+        assert_abs_diff_eq!(18.9, res.x_opt[0], epsilon = 1.);
+        assert_abs_diff_eq!(
+            -15.125,
+            xsinx(&res.x_opt.view().insert_axis(Axis(0)))[[0, 0]],
+            epsilon = 0.5
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn test_noisy_xsinx_aei_egor_builder() {
+        check_noisy_xsinx(InfillStrategy::AEI)
+    }
+
+    #[test]
+    #[serial]
+    fn test_noisy_xsinx_siei_egor_builder() {
+        check_noisy_xsinx(InfillStrategy::SampledIncumbentEI)
+    }
+
+    #[test]
+    fn test_noisy_interpolating_gp_egor_builder() {
+        let res = EgorBuilder::optimize(xsinx)
+            .configure(|cfg| cfg.noisy(true).max_iters(1))
+            .min_within(&array![[0.0, 25.0]])
+            .run();
+        assert!(matches!(res, Err(EgoError::InvalidValue(_))));
+    }
+
+    fn check_xsinx_egor_builder(infill: InfillStrategy, initial_doe: Array2<f64>) {
+        let res = EgorBuilder::optimize(xsinx)
+            .configure(|cfg| {
+                cfg.infill_strategy(infill)
+                    .infill_optimizer(InfillOptimizer::Slsqp)
+                    .max_iters(20)
+                    .doe(&initial_doe)
+                    .seed(42)
+            })
+            .min_within(&array![[0.0, 25.0]])
+            .run()
+            .expect("Egor should minimize xsinx");
+        let expected = array![-15.125];
+        assert_abs_diff_eq!(expected, res.y_opt, epsilon = 1e-2);
+    }
+
+    #[test]
+    #[serial]
+    fn test_xsinx_lcb_egor_builder() {
+        check_xsinx_egor_builder(InfillStrategy::LCB, array![[0.], [7.], [25.]])
+    }
+
+    #[test]
+    #[serial]
+    fn test_xsinx_pi_egor_builder() {
+        check_xsinx_egor_builder(InfillStrategy::PI, array![[0.], [7.], [25.]])
+    }
+
+    #[test]
+    #[serial]
+    fn test_xsinx_ts_egor_builder() {
+        // Thompson sampling is less exploratory, initial doe has to cover the domain
+        check_xsinx_egor_builder(InfillStrategy::TS, array![[0.], [6.], [12.], [18.], [25.]])
+    }
+
+    #[test]
+    #[serial]
+    fn test_xsinx_mes_egor_builder() {
+        check_xsinx_egor_builder(InfillStrategy::MES, array![[0.], [7.], [25.]])
+    }
+
+    #[test]
+    #[serial]
+    fn test_xsinx_kg_egor_builder() {
+        check_xsinx_egor_builder(InfillStrategy::KG, array![[0.], [7.], [25.]])
+    }
+
     #[test]
     #[serial]
     fn test_xsinx_logei_egor_builder() {
@@ -815,6 +1012,125 @@ mod tests {
         assert_abs_diff_eq!(expected, res.x_opt, epsilon = 3e-2);
     }
 
+    #[test]
+    fn test_egor_g24_kpls_selection_egor_builder() {
+        let xlimits = array![[0., 3.], [0., 4.]];
+        let doe = Lhs::new(&xlimits)
+            .with_rng(Xoshiro256Plus::seed_from_u64(42))
+            .sample(5);
+        let res = EgorBuilder::optimize(f_g24)
+            .configure(|config| {
+                config
+                    .n_cstr(2)
+                    .doe(&doe)
+                    .max_iters(20)
+                    .configure_gp(|gp| {
+                        gp.kpls_selection(Some(KplsSelection::ExplainedVariance {
+                            threshold: 0.99,
+                            max_dim: 2,
+                        }))
+                    })
+                    .cstr_tol(array![1e-5, 1e-5])
+                    .seed(42)
+            })
+            .min_within(&xlimits)
+            .run()
+            .expect("Minimize failure");
+        println!("G24 optim result = {res:?}");
+        let expected = array![2.3295, 3.1785];
+        assert_abs_diff_eq!(expected, res.x_opt, epsilon = 3e-2);
+    }
+
+    #[test]
+    fn test_egor_kpls_selection_10d_egor_builder() {
+        // Output only depends on the sum of the inputs: less PLS components than allowed are selected
+        let f_sum = |x: &ArrayView2<f64>| -> Array2<f64> {
+            x.sum_axis(Axis(1))
+                .mapv(|s| (s - 3.).powi(2))
+                .insert_axis(Axis(1))
+        };
+        let xlimits = Array2::from_shape_fn((10, 2), |(_, j)| j as f64);
+        let res = EgorBuilder::optimize(f_sum)
+            .configure(|config| {
+                config
+                    .n_doe(20)
+                    .max_iters(3)
+                    .configure_gp(|gp| {
+                        gp.kpls_selection(Some(KplsSelection::ExplainedVariance {
+                            threshold: 0.9,
+                            max_dim: 5,
+                        }))
+                    })
+                    .seed(42)
+            })
+            .min_within(&xlimits)
+            .run()
+            .expect("Minimize failure");
+        let theta_inits = res.state.theta_inits.expect("Surrogate hyperparameters");
+        let theta = theta_inits[0].as_ref().expect("Objective hyperparameters");
+        assert!(theta.ncols() < 5, "{} PLS components", theta.ncols());
+    }
+
+    #[test]
+    fn test_egor_g24_parallelism_egor_builder() {
+        let xlimits = array![[0., 3.], [0., 4.]];
+        let doe = Lhs::new(&xlimits)
+            .with_rng(Xoshiro256Plus::seed_from_u64(42))
+            .sample(5);
+        let optimize = |parallelism| {
+            EgorBuilder::optimize(f_g24)
+                .configure(|config| {
+                    config
+                        .n_cstr(2)
+                        .doe(&doe)
+                        .max_iters(5)
+                        .cstr_tol(array![1e-5, 1e-5])
+                        .parallelism(parallelism)
+                        .seed(42)
+                })
+                .min_within(&xlimits)
+                .run()
+                .expect("Minimize failure")
+        };
+        let res = optimize(Parallelism::Threads(1));
+        let res_par = optimize(Parallelism::Threads(4));
+        assert_eq!(res.x_doe, res_par.x_doe);
+        assert_eq!(res.y_doe, res_par.y_doe);
+    }
+
+    #[test]
+    fn test_egor_g24_shared_clustering_egor_builder() {
+        let xlimits = array![[0., 3.], [0., 4.]];
+        // Two hard clusters split the data, each expert being trained on about half of the points.
+        // With a 10-point doe the optimum feasible region is found after ~40 iterations,
+        // 20 points allow to keep the 20 iterations budget of other G24 tests.
+        // Note that without shared clustering, per-output clustering of constraints
+        // fails in this configuration with clusters of less than 3 points.
+        let doe = Lhs::new(&xlimits)
+            .with_rng(Xoshiro256Plus::seed_from_u64(42))
+            .sample(20);
+        let res = EgorBuilder::optimize(f_g24)
+            .configure(|config| {
+                config
+                    .configure_gp(|gp| {
+                        gp.n_clusters(NbClusters::fixed(2))
+                            .recombination(egobox_moe::Recombination::Hard)
+                            .shared_clustering(true)
+                    })
+                    .n_cstr(2)
+                    .doe(&doe)
+                    .max_iters(20)
+                    .cstr_tol(array![1e-5, 1e-5])
+                    .seed(42)
+            })
+            .min_within(&xlimits)
+            .run()
+            .expect("Minimize failure");
+        println!("G24 optim result = {res:?}");
+        let expected = array![2.3295, 3.1785];
+        assert_abs_diff_eq!(expected, res.x_opt, epsilon = 3e-2);
+    }
+
     #[test]
     fn test_egor_g24_basic_egor_builder_slsqp() {
         let xlimits = array![[0., 3.], [0., 4.]];
@@ -931,6 +1247,94 @@ mod tests {
         assert_abs_diff_eq!(expected, res.x_opt, epsilon = 2e-2);
     }
 
+    // Multi-objective tests
+
+    // ZDT1 function in 2D: Pareto front f2 = 1 - sqrt(f1) for x2 = 0
+    // An optional constraint x1 >= 0.2 is appended
+    fn zdt1(x: &ArrayView2<f64>, constrained: bool) -> Array2<f64> {
+        let ny = if constrained { 3 } else { 2 };
+        let mut y = Array2::zeros((x.nrows(), ny));
+        Zip::from(y.rows_mut())
+            .and(x.rows())
+            .for_each(|mut yi, xi| {
+                let f1 = xi[0];
+                let g = 1. + 9. * xi[1];
+                yi[0] = f1;
+                yi[1] = g * (1. - (f1 / g).sqrt());
+                if constrained {
+                    yi[2] = 0.2 - xi[0];
+                }
+            });
+        y
+    }
+
+    fn check_zdt1_front(res: &OptimResult<f64>, min_hv: f64) {
+        let front = res.pareto_front.as_ref().expect("Pareto front");
+        let set = res.pareto_set.as_ref().expect("Pareto set");
+        assert_eq!(front.nrows(), set.nrows());
+        assert!(front.nrows() > 3);
+        for (i, fi) in front.rows().into_iter().enumerate() {
+            for (j, fj) in front.rows().into_iter().enumerate() {
+                assert!(i == j || !crate::utils::dominates(&fj, &fi));
+            }
+        }
+        let hv = crate::hypervolume(front, &array![1.1, 1.1]);
+        println!("ZDT1 Pareto front ({} points) HV = {hv}", front.nrows());
+        assert!(hv > min_hv);
+    }
+
+    #[test]
+    #[serial]
+    fn test_zdt1_ehvi_egor_builder() {
+        let xlimits = array![[0., 1.], [0., 1.]];
+        let res = EgorBuilder::optimize(|x: &ArrayView2<f64>| zdt1(x, false))
+            .configure(|config| {
+                config
+                    .n_obj(2)
+                    .multi_obj_strategy(MultiObjStrategy::Ehvi)
+                    .n_doe(10)
+                    .max_iters(20)
+                    .seed(42)
+            })
+            .min_within(&xlimits)
+            .run()
+            .expect("Egor minimization");
+        // exact hypervolume of ZDT1 Pareto front wrt (1.1, 1.1) is ~0.877
+        check_zdt1_front(&res, 0.8);
+        assert_eq!(res.y_opt.len(), 2);
+    }
+
+    #[test]
+    #[serial]
+    fn test_zdt1_cstr_parego_egor_builder() {
+        let xlimits = array![[0., 1.], [0., 1.]];
+        let res = EgorBuilder::optimize(|x: &ArrayView2<f64>| zdt1(x, true))
+            .configure(|config| {
+                config
+                    .n_obj(2)
+                    .n_cstr(1)
+                    .multi_obj_strategy(MultiObjStrategy::ParEgo)
+                    .n_doe(10)
+                    .max_iters(20)
+                    .seed(42)
+            })
+            .min_within(&xlimits)
+            .run()
+            .expect("Egor minimization");
+        check_zdt1_front(&res, 0.6);
+        let front = res.pareto_front.as_ref().unwrap();
+        assert!(front.column(0).iter().all(|&f1| f1 >= 0.2 - 1e-4));
+    }
+
+    #[test]
+    fn test_n_obj_zero_egor_builder() {
+        let res = EgorBuilder::optimize(|x: &ArrayView2<f64>| zdt1(x, false))
+            .configure(|config| config.n_obj(0).max_iters(1))
+            .min_within(&array![[0., 1.], [0., 1.]])
+            .run();
+        assert!(matches!(res, Err(EgoError::InvalidValue(_))));
+    }
+
     // Mixed-integer tests
 
     fn mixsinx(x: &ArrayView2<f64>) -> Array2<f64> {
@@ -10,12 +10,14 @@ use egobox_gp::ThetaTuning;
 use egobox_gp::metrics::CrossValScore;
 use egobox_moe::{
     Clustered, Clustering, CorrelationSpec, FullGpSurrogate, GpMixture, GpMixtureParams,
-    GpSurrogate, GpSurrogateExt, MixtureGpSurrogate, NbClusters, Recombination, RegressionSpec,
+    GpSurrogate, GpSurrogateExt, GpType, KplsSelection, MixtureGpSurrogate, MixtureVariance,
+    NbClusters, Recombination, RegressionSpec, SamplePaths,
 };
 use linfa::traits::{Fit, PredictInplace};
 use linfa::{DatasetBase, Float, ParamGuard};
 use ndarray::{
-    Array, Array1, Array2, ArrayBase, ArrayView1, ArrayView2, Axis, Data, DataMut, Ix1, Ix2, Zip, s,
+    Array, Array1, Array2, Array3, ArrayBase, ArrayView1, ArrayView2, Axis, Data, DataMut, Ix1,
+    Ix2, Zip, s,
 };
 use ndarray_rand::rand::SeedableRng;
 use ndarray_stats::QuantileExt;
@@ -400,6 +402,15 @@ impl SurrogateBuilder for MixintGpMixtureParams {
         MixintGpMixtureParams::new(xtypes, &GpMixtureParams::new())
     }
 
+    /// Sets the type of gaussian processes used as experts.
+    fn set_gp_type(&mut self, gp_type: GpType<f64>) {
+        self.0 = MixintGpMixtureValidParams {
+            surrogate_builder: self.0.surrogate_builder.clone().gp_type(gp_type),
+            xtypes: self.0.xtypes.clone(),
+            work_in_folded_space: self.0.work_in_folded_space,
+        }
+    }
+
     /// Sets the allowed regression models used in gaussian processes.
     fn set_regression_spec(&mut self, regression_spec: RegressionSpec) {
         self.0 = MixintGpMixtureValidParams {
@@ -456,6 +467,18 @@ impl SurrogateBuilder for MixintGpMixtureParams {
         }
     }
 
+    fn set_mixture_variance(&mut self, mixture_variance: MixtureVariance) {
+        self.0 = MixintGpMixtureValidParams {
+            surrogate_builder: self
+                .0
+                .surrogate_builder
+                .clone()
+                .mixture_variance(mixture_variance),
+            xtypes: self.0.xtypes.clone(),
+            work_in_folded_space: self.0.work_in_folded_space,
+        }
+    }
+
     /// Sets the theta hyperparameter tuning strategy
     fn set_theta_tunings(&mut self, theta_tunings: &[ThetaTuning<f64>]) {
         self.0 = MixintGpMixtureValidParams {
@@ -501,6 +524,33 @@ impl SurrogateBuilder for MixintGpMixtureParams {
         let mixmoe = self.check_ref()?._train_on_clusters(&xt, &yt, clustering)?;
         Ok(mixmoe).map(|mixmoe| Box::new(mixmoe) as Box<dyn MixtureGpSurrogate>)
     }
+
+    fn select_kpls_dim(
+        &self,
+        kpls_selection: KplsSelection,
+        xt: ArrayView2<f64>,
+        yt: ArrayView1<f64>,
+    ) -> Result<Option<usize>> {
+        let mut xcast = if self.0.work_in_folded_space {
+            unfold_with_enum_mask(&self.0.xtypes, &xt)
+        } else {
+            xt.to_owned()
+        };
+        cast_to_discrete_values_mut(&self.0.xtypes, &mut xcast);
+        self.0
+            .surrogate_builder
+            .select_kpls_dim(kpls_selection, xcast.view(), yt)
+    }
+
+    fn make_clustering(&self, xt: ArrayView2<f64>, yt: ArrayView2<f64>) -> Result<Clustering> {
+        let mut xcast = if self.0.work_in_folded_space {
+            unfold_with_enum_mask(&self.0.xtypes, &xt)
+        } else {
+            xt.to_owned()
+        };
+        cast_to_discrete_values_mut(&self.0.xtypes, &mut xcast);
+        self.0.surrogate_builder.make_clustering(xcast.view(), yt)
+    }
 }
 
 impl<D: Data<Elem = f64>> Fit<ArrayBase<D, Ix2>, ArrayBase<D, Ix1>, EgoError>
@@ -576,7 +626,7 @@ impl Clustered for MixintGpMixture {
 
     /// Convert to clustering
     fn to_clustering(&self) -> Clustering {
-        Clustering::new(self.moe.gmx().clone(), self.moe.recombination())
+        self.moe.to_clustering()
     }
 }
 
@@ -641,6 +691,26 @@ impl GpSurrogateExt for MixintGpMixture {
         self.moe.predict_var_gradients(&xcast)
     }
 
+    fn predict_hessians(&self, x: &ArrayView2<f64>) -> egobox_moe::Result<Array3<f64>> {
+        let mut xcast = if self.work_in_folded_space {
+            unfold_with_enum_mask(&self.xtypes, x)
+        } else {
+            x.to_owned()
+        };
+        cast_to_discrete_values_mut(&self.xtypes, &mut xcast);
+        self.moe.predict_hessians(&xcast)
+    }
+
+    fn predict_var_hessians(&self, x: &ArrayView2<f64>) -> egobox_moe::Result<Array3<f64>> {
+        let mut xcast = if self.work_in_folded_space {
+            unfold_with_enum_mask(&self.xtypes, x)
+        } else {
+            x.to_owned()
+        };
+        cast_to_discrete_values_mut(&self.xtypes, &mut xcast);
+        self.moe.predict_var_hessians(&xcast)
+    }
+
     fn sample(&self, x: &ArrayView2<f64>, n_traj: usize) -> egobox_moe::Result<Array2<f64>> {
         let mut xcast = if self.work_in_folded_space {
             unfold_with_enum_mask(&self.xtypes, x)
@@ -650,6 +720,47 @@ impl GpSurrogateExt for MixintGpMixture {
         cast_to_discrete_values_mut(&self.xtypes, &mut xcast);
         self.moe.sample(&xcast.view(), n_traj)
     }
+
+    fn sample_paths(&self, n_traj: usize, seed: u64) -> egobox_moe::Result<Box<dyn SamplePaths>> {
+        Ok(Box::new(MixintSamplePaths {
+            paths: GpSurrogateExt::sample_paths(&self.moe, n_traj, seed)?,
+            xtypes: self.xtypes.clone(),
+            work_in_folded_space: self.work_in_folded_space,
+        }))
+    }
+}
+
+/// Sample paths of a [MixintGpMixture] evaluated at points cast to discrete values
+struct MixintSamplePaths {
+    paths: Box<dyn SamplePaths>,
+    xtypes: Vec<XType>,
+    work_in_folded_space: bool,
+}
+
+impl MixintSamplePaths {
+    fn cast(&self, x: &ArrayView2<f64>) -> Array2<f64> {
+        let mut xcast = if self.work_in_folded_space {
+            unfold_with_enum_mask(&self.xtypes, x)
+        } else {
+            x.to_owned()
+        };
+        cast_to_discrete_values_mut(&self.xtypes, &mut xcast);
+        xcast
+    }
+}
+
+impl SamplePaths for MixintSamplePaths {
+    fn n_traj(&self) -> usize {
+        self.paths.n_traj()
+    }
+
+    fn predict(&self, x: &ArrayView2<f64>) -> egobox_moe::Result<Array2<f64>> {
+        self.paths.predict(&self.cast(x).view())
+    }
+
+    fn predict_gradients(&self, x: &ArrayView2<f64>) -> egobox_moe::Result<Array3<f64>> {
+        self.paths.predict_gradients(&self.cast(x).view())
+    }
 }
 
 impl CrossValScore<f64, EgoError, MixintGpMixtureParams, Self> for MixintGpMixture {
@@ -5,16 +5,25 @@ pub mod spec;
 
 use egobox_gp::ThetaTuning;
 use egobox_moe::{
-    Clustering, CorrelationSpec, GpMixtureParams, MixtureGpSurrogate, NbClusters, RegressionSpec,
+    Clustering, CorrelationSpec, GpMixture, GpMixtureParams, GpType, KplsSelection,
+    MixtureGpSurrogate, MixtureVariance, NbClusters, RegressionSpec, SurrogateFloat,
 };
 use ndarray::{ArrayView1, ArrayView2};
+use serde::Serialize;
 
 use linfa::ParamGuard;
 
 use crate::Result;
 use crate::{SurrogateBuilder, XType};
 
-impl SurrogateBuilder for GpMixtureParams<f64> {
+/// Surrogate builder training mixtures of experts with `F` precision (`f64` or `f32`)
+/// while the optimizer works in double precision: training data and predictions
+/// are converted from/to `f64`. Single precision predictions without conversion
+/// are available from the trained mixture (see [GpMixture::predict]).
+impl<F: SurrogateFloat + Serialize> SurrogateBuilder for GpMixtureParams<F>
+where
+    GpMixture<F>: MixtureGpSurrogate + 'static,
+{
     /// Constructor from domain space specified with types
     /// **panic** if xtypes contains other types than continuous type `Float`
     fn new_with_xtypes(xtypes: &[XType]) -> Self {
@@ -24,6 +33,11 @@ impl SurrogateBuilder for GpMixtureParams<f64> {
         GpMixtureParams::new()
     }
 
+    /// Sets the type of gaussian processes used as experts.
+    fn set_gp_type(&mut self, gp_type: GpType<f64>) {
+        *self = self.clone().gp_type(gp_type.cast());
+    }
+
     /// Sets the allowed regression models used in gaussian processes.
     fn set_regression_spec(&mut self, regression_spec: RegressionSpec) {
         *self = self.clone().regression_spec(regression_spec);
@@ -47,14 +61,20 @@ impl SurrogateBuilder for GpMixtureParams<f64> {
     /// Sets the mode of recombination to get the output prediction from experts prediction
     /// Onlyused if nb clusters is greater than one
     fn set_recombination(&mut self, recombination: egobox_moe::Recombination<f64>) {
-        *self = self.clone().recombination(recombination);
+        *self = self.clone().recombination(recombination.cast());
+    }
+
+    /// Sets the variance computation mode of the mixture with smooth recombination
+    fn set_mixture_variance(&mut self, mixture_variance: MixtureVariance) {
+        *self = self.clone().mixture_variance(mixture_variance);
     }
 
     /// Sets the theta tuning used by the expert during training.
     /// When only one element tuning is used for all clusters
     /// When several elements, the length should match the number of clusters
     fn set_theta_tunings(&mut self, theta_tunings: &[ThetaTuning<f64>]) {
-        *self = self.clone().theta_tunings(theta_tunings);
+        let theta_tunings = theta_tunings.iter().map(|t| t.cast()).collect::<Vec<_>>();
+        *self = self.clone().theta_tunings(&theta_tunings);
     }
 
     /// Sets the number of clusters used by the mixture of surrogate experts.
@@ -68,7 +88,7 @@ impl SurrogateBuilder for GpMixtureParams<f64> {
         yt: ArrayView1<f64>,
     ) -> Result<Box<dyn MixtureGpSurrogate>> {
         let checked = self.check_ref()?;
-        let moe = checked.train(&xt, &yt)?;
+        let moe = checked.train(&xt.mapv(F::cast), &yt.mapv(F::cast))?;
         Ok(moe).map(|moe| Box::new(moe) as Box<dyn MixtureGpSurrogate>)
     }
 
@@ -79,7 +99,29 @@ impl SurrogateBuilder for GpMixtureParams<f64> {
         clustering: &Clustering,
     ) -> Result<Box<dyn MixtureGpSurrogate>> {
         let checked = self.check_ref()?;
-        let moe = checked.train_on_clusters(&xt, &yt, clustering)?;
+        let moe = checked.train_on_clusters(&xt.mapv(F::cast), &yt.mapv(F::cast), clustering)?;
         Ok(moe).map(|moe| Box::new(moe) as Box<dyn MixtureGpSurrogate>)
     }
+
+    fn select_kpls_dim(
+        &self,
+        kpls_selection: KplsSelection,
+        xt: ArrayView2<f64>,
+        yt: ArrayView1<f64>,
+    ) -> Result<Option<usize>> {
+        // selection done with one expert on the whole data
+        let checked = self
+            .clone()
+            .n_clusters(NbClusters::fixed(1))
+            .theta_tunings(&[ThetaTuning::default()])
+            .kpls_dims(&[])
+            .kpls_selection(Some(kpls_selection))
+            .check()?;
+        Ok(checked.select_kpls_dim(&xt.mapv(F::cast), &yt.mapv(F::cast))?)
+    }
+
+    fn make_clustering(&self, xt: ArrayView2<f64>, yt: ArrayView2<f64>) -> Result<Clustering> {
+        let checked = self.check_ref()?;
+        Ok(checked.make_clustering(&xt.mapv(F::cast), &yt.mapv(F::cast))?)
+    }
 }
@@ -128,6 +128,20 @@
 //!     egor_config.infill_strategy(InfillStrategy::EI);
 //! ```
 //!
+//! * Other infill strategies offer different levels of exploration: LCB (Lower Confidence Bound with GP-UCB
+//!   exploration weight schedule \[[Srinivas2010](#Srinivas2010)\]), PI (Probability of Improvement) and TS
+//!   (Thompson Sampling, minimizing a posterior sample path). Information-based strategies are also available:
+//!   MES (Max-value Entropy Search \[[Wang2017](#Wang2017)\]) and KG (Knowledge Gradient on a discretized
+//!   design space with fixed base samples \[[Balandat2020](#Balandat2020)\]) which may progress where EI stalls.
+//!   Use [`EgorConfig::infill_criterion`] to set their parameters (e.g. [`criteria::LowerConfidenceBound`]
+//!   exploration weight).
+//!
+//! ```no_run
+//! # use egobox_ego::{EgorConfig, criteria::{LcbBeta, LowerConfidenceBound}};
+//! # let egor_config = EgorConfig::default();
+//!     egor_config.infill_criterion(Box::new(LowerConfidenceBound::new(LcbBeta::Fixed(4.))));
+//! ```
+//!
 //! * Constraints modeled with a surrogate can be integrated in the infill criterion
 //!   through their probability of feasibility. See \[[Sasena2002](#Sasena2002)\]
 //!
@@ -185,6 +199,33 @@
 //!     egor_config.trego(true);
 //! ```
 //!
+//! * Several objectives can be optimized at once with `n_obj` option: the function is then expected to return
+//!   [obj_1, ..., obj_nobj, cstr_1, ..., cstr_n]. The next point is selected either by maximizing the Expected Hypervolume
+//!   Improvement of the current Pareto front \[[Emmerich2006](#Emmerich2006)\] (exact up to 3 objectives, Monte Carlo estimation beyond)
+//!   or using ParEGO random scalarizations \[[Knowles2006](#Knowles2006)\]. The Pareto front and set are returned in the result
+//!   while the reported optimum is only the best point wrt the first objective.
+//!
+//! ```no_run
+//! # use egobox_ego::{EgorConfig, MultiObjStrategy};
+//! # let egor_config = EgorConfig::default();
+//!     egor_config.n_obj(2).multi_obj_strategy(MultiObjStrategy::Ehvi);
+//! ```
+//!
+//! * Noisy function evaluations (e.g. stochastic simulations) can be handled with `noisy` option: surrogates have to estimate
+//!   the noise (e.g. robust GP with a gaussian likelihood) and the optimum is reported as the evaluated point with the best
+//!   posterior mean. Noise-aware infill criteria are Augmented EI \[[Huang2006](#Huang2006)\] and EI averaged over
+//!   sampled incumbent minima (a simplification of Noisy EI \[[Letham2019](#Letham2019)\]).
+//!
+//! ```no_run
+//! # use egobox_ego::{EgorConfig, InfillStrategy};
+//! # use egobox_moe::{GpType, RobustLikelihood};
+//! # let egor_config = EgorConfig::default();
+//!     egor_config
+//!         .configure_gp(|gp| gp.gp_type(GpType::RobustGp { likelihood: RobustLikelihood::Gaussian }))
+//!         .noisy(true)
+//!         .infill_strategy(InfillStrategy::SampledIncumbentEI);
+//! ```
+//!
 //! * Intermediate results can be logged at each iteration when `outdir` directory is specified.
 //!   The following files :
 //!   * egor_config.json: Egor configuration,
@@ -221,6 +262,8 @@
 //!   See \[[Zhan2024](#Zhan024)\] and \[[Pretsch2024](#Pretsch2024)\]
 //! * Theta bounds are implemented as in \[[Appriou2023](#Appriou2023)\]
 //! * Logirithm of Expected Improvement is implemented as in \[[Ament2025](#Ament2025)\]
+//! * Multi-objective optimization is implemented using EHVI \[[Emmerich2006](#Emmerich2006)\] or ParEGO \[[Knowles2006](#Knowles2006)\]
+//! * Noisy optimization is implemented using AEI \[[Huang2006](#Huang2006)\] or EI with sampled incumbents inspired by NEI \[[Letham2019](#Letham2019)\]
 //!
 //! # References
 //!
@@ -283,6 +326,35 @@
 //! [Unexpected improvements to expected improvement for bayesian optimization](https://arxiv.org/pdf/2310.20708),
 //! Advances in Neural Information Processing Systems, 2023
 //!
+//! \[<a id="Emmerich2006">Emmerich2006</a>\]: M. Emmerich, K. Giannakoglou, B. Naujoks,
+//! [Single- and multiobjective evolutionary optimization assisted by Gaussian random field metamodels](https://doi.org/10.1109/TEVC.2005.859463),
+//! IEEE Transactions on Evolutionary Computation, 10(4), 421-439, 2006.
+//!
+//! \[<a id="Knowles2006">Knowles2006</a>\]: J. Knowles,
+//! [ParEGO: a hybrid algorithm with on-line landscape approximation for expensive multiobjective optimization problems](https://doi.org/10.1109/TEVC.2005.851274),
+//! IEEE Transactions on Evolutionary Computation, 10(1), 50-66, 2006.
+//!
+//! \[<a id="Srinivas2010">Srinivas2010</a>\]: N. Srinivas, A. Krause, S. Kakade and M. Seeger,
+//! [Gaussian process optimization in the bandit setting: no regret and experimental design](https://arxiv.org/abs/0912.3995),
+//! Proceedings of the 27th International Conference on Machine Learning, 1015-1022, 2010.
+//!
+//! \[<a id="Huang2006">Huang2006</a>\]: D. Huang, T. T. Allen, W. I. Notz and N. Zeng,
+//! [Global optimization of stochastic black-box systems via sequential kriging meta-models](https://doi.org/10.1007/s10898-005-2454-3),
+//! Journal of Global Optimization, 34(3), 441-466, 2006.
+//!
+//! \[<a id="Letham2019">Letham2019</a>\]: B. Letham, B. Karrer, G. Ottoni and E. Bakshy,
+//! [Constrained Bayesian optimization with noisy experiments](https://doi.org/10.1214/18-BA1110),
+//! Bayesian Analysis, 14(2), 495-519, 2019.
+//!
+//! \[<a id="Wang2017">Wang2017</a>\]: Z. Wang and S. Jegelka,
+//! [Max-value entropy search for efficient Bayesian optimization](https://arxiv.org/abs/1703.01968),
+//! Proceedings of the 34th International Conference on Machine Learning, 3627-3635, 2017.
+//!
+//! \[<a id="Balandat2020">Balandat2020</a>\]: M. Balandat, B. Karrer, D. R. Jiang, S. Daulton, B. Letham,
+//! A. G. Wilson and E. Bakshy,
+//! [BoTorch: a framework for efficient Monte-Carlo Bayesian optimization](https://arxiv.org/abs/1910.06403),
+//! Advances in Neural Information Processing Systems 33, 21524-21538, 2020.
+//!
 //! smtorg. (2018). Surrogate modeling toolbox. In [GitHub repository](https://github.com/SMTOrg/smt)
 //!
 //!
@@ -302,6 +374,7 @@ pub use crate::types::*;
 pub use crate::utils::{
     CHECKPOINT_FILE, Checkpoint, CheckpointingFrequency, EGOBOX_LOG, EGOBOX_USE_GP_VAR_PORTFOLIO,
     EGOBOX_USE_MAX_PROBA_OF_FEASIBILITY, HotStartCheckpoint, HotStartMode, find_best_result_index,
+    find_pareto_indices, hypervolume,
 };
 
 mod optimizers;
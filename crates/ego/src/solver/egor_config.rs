@@ -1,9 +1,14 @@
 //! Egor optimizer configuration.
 use crate::HotStartMode;
 use crate::criteria::*;
+use crate::errors::{EgoError, Result};
 use crate::types::*;
 use egobox_gp::ThetaTuning;
+use egobox_moe::GpType;
+use egobox_moe::KplsSelection;
+use egobox_moe::MixtureVariance;
 use egobox_moe::NbClusters;
+use egobox_moe::Parallelism;
 use egobox_moe::Recombination;
 use egobox_moe::{CorrelationSpec, RegressionSpec};
 use ndarray::Array1;
@@ -17,18 +22,30 @@ pub const EGO_GP_OPTIM_MAX_EVAL: usize = 50;
 /// GP configuration
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct GpConfig {
+    /// Type of GP models used by mixture of experts (see [egobox_moe])
+    pub(crate) gp_type: GpType<f64>,
     /// Regression specification for GP models used by mixture of experts (see [egobox_moe])
     pub(crate) regression_spec: RegressionSpec,
     /// Correlation specification for GP models used by mixture of experts (see [egobox_moe])
     pub(crate) correlation_spec: CorrelationSpec,
     /// Optional dimension reduction (see [egobox_moe])
     pub(crate) kpls_dim: Option<usize>,
+    /// Optional automatic selection of PLS components for each output (see [egobox_moe])
+    #[serde(default)]
+    pub(crate) kpls_selection: Option<KplsSelection>,
     /// Number of clusters used by mixture of experts (see [egobox_moe])
     /// When set to Auto the clusters are computes automatically and refreshed
     /// every 10-points (tentative) additions
     pub(crate) n_clusters: NbClusters,
     /// The mode of recombination to get the output prediction from experts prediction
     pub(crate) recombination: Recombination<f64>,
+    /// Whether objective and constraints surrogates share the same clustering
+    /// computed wrt all the outputs
+    #[serde(default)]
+    pub(crate) shared_clustering: bool,
+    /// The variance computation mode of the mixture with smooth recombination
+    #[serde(default)]
+    pub(crate) mixture_variance: MixtureVariance,
     /// Parameter tuning hint of the autocorrelation model
     pub(crate) theta_tuning: ThetaTuning<f64>,
     /// Number of starts for multistart approach used for optimization
@@ -40,11 +57,15 @@ pub struct GpConfig {
 impl Default for GpConfig {
     fn default() -> Self {
         GpConfig {
+            gp_type: GpType::FullGp,
             regression_spec: RegressionSpec::CONSTANT,
             correlation_spec: CorrelationSpec::SQUAREDEXPONENTIAL,
             kpls_dim: None,
+            kpls_selection: None,
             n_clusters: NbClusters::default(),
             recombination: Recombination::Smooth(Some(1.)),
+            shared_clustering: false,
+            mixture_variance: MixtureVariance::default(),
             theta_tuning: ThetaTuning::default(),
             n_start: EGO_GP_OPTIM_N_START,
             max_eval: EGO_GP_OPTIM_MAX_EVAL,
@@ -53,6 +74,17 @@ impl Default for GpConfig {
 }
 
 impl GpConfig {
+    /// Sets the type of GP models used by the mixture of experts.
+    ///
+    /// Use [GpType::RobustGp] when objective or constraint evaluations
+    /// may return spurious values (or are noisy with a Gaussian likelihood,
+    /// see [EgorConfig::noisy]) and [GpType::BayesianGp] to account for
+    /// hyperparameters uncertainty when only few evaluations are available.
+    pub fn gp_type(mut self, gp_type: GpType<f64>) -> Self {
+        self.gp_type = gp_type;
+        self
+    }
+
     /// Sets the allowed regression models used in gaussian processes.
     pub fn regression_spec(mut self, regression_spec: RegressionSpec) -> Self {
         self.regression_spec = regression_spec;
@@ -82,6 +114,16 @@ impl GpConfig {
     /// Removes any PLS dimension reduction usage
     pub fn no_kpls(mut self) -> Self {
         self.kpls_dim = None;
+        self.kpls_selection = None;
+        self
+    }
+
+    /// Sets the automatic selection of the number of PLS components.
+    ///
+    /// The number of components is selected for each output (objective and constraints)
+    /// when the surrogate clustering is (re)computed and takes precedence over `kpls_dim`.
+    pub fn kpls_selection(mut self, kpls_selection: Option<KplsSelection>) -> Self {
+        self.kpls_selection = kpls_selection;
         self
     }
 
@@ -97,6 +139,24 @@ impl GpConfig {
         self
     }
 
+    /// Sets whether objective and constraints surrogates share the same clustering.
+    ///
+    /// When true, the training data are clustered once wrt the objective and constraints
+    /// values at once and each surrogate experts are trained on the same clusters.
+    pub fn shared_clustering(mut self, shared_clustering: bool) -> Self {
+        self.shared_clustering = shared_clustering;
+        self
+    }
+
+    /// Sets the variance computation mode of the mixture with smooth recombination.
+    ///
+    /// With [MixtureVariance::TotalVariance] the disagreement between experts is taken
+    /// into account by infill criteria at the transitions between clusters.
+    pub fn mixture_variance(mut self, mixture_variance: MixtureVariance) -> Self {
+        self.mixture_variance = mixture_variance;
+        self
+    }
+
     pub fn theta_tuning(mut self, theta_tuning: ThetaTuning<f64>) -> Self {
         self.theta_tuning = theta_tuning;
         self
@@ -166,6 +226,10 @@ impl Default for CoegoConfig {
     }
 }
 
+fn default_n_obj() -> usize {
+    1
+}
+
 /// Max number of iterations of EGO algorithm (aka iteration budget)
 pub const EGO_DEFAULT_MAX_ITERS: usize = 20;
 /// Number of restart for optimization of the infill criterion (aka multistart)
@@ -184,8 +248,15 @@ pub struct EgorConfig {
     /// Number of initial doe drawn using Latin hypercube sampling
     /// Note: n_doe > 0; otherwise n_doe = max(xdim + 1, 5)
     pub(crate) n_doe: usize,
+    /// Number of objectives
+    /// Note: dim function ouput = n_obj objectives + n_cstr constraints
+    #[serde(default = "default_n_obj")]
+    pub(crate) n_obj: usize,
+    /// Strategy used to select next point when several objectives are optimized
+    #[serde(default)]
+    pub(crate) multi_obj: MultiObjStrategy,
     /// Number of Constraints
-    /// Note: dim function ouput = 1 objective + n_cstr constraints
+    /// Note: dim function ouput = n_obj objectives + n_cstr constraints
     pub(crate) n_cstr: usize,
     /// Optional constraints violation tolerance meaning cstr < cstr_tol is considered valid
     pub(crate) cstr_tol: Option<Array1<f64>>,
@@ -218,6 +289,9 @@ pub struct EgorConfig {
     pub(crate) xtypes: Vec<XType>,
     /// A random generator seed used to get reproductible results.
     pub(crate) seed: Option<u64>,
+    /// Parallelism policy used to train surrogates and optimize the infill criterion
+    #[serde(skip)]
+    pub(crate) parallelism: Parallelism,
     /// TREGO parameterization
     pub(crate) trego: TregoConfig,
     /// CoEGO  parameterization
@@ -226,6 +300,9 @@ pub struct EgorConfig {
     pub(crate) cstr_infill: bool,
     /// Constraints criterion
     pub(crate) cstr_strategy: ConstraintStrategy,
+    /// Whether function evaluations are noisy
+    #[serde(default)]
+    pub(crate) noisy: bool,
 }
 
 impl Default for EgorConfig {
@@ -234,6 +311,8 @@ impl Default for EgorConfig {
             max_iters: EGO_DEFAULT_MAX_ITERS,
             n_start: EGO_DEFAULT_N_START,
             n_doe: 0,
+            n_obj: 1,
+            multi_obj: MultiObjStrategy::default(),
             n_cstr: 0,
             cstr_tol: None,
             doe: None,
@@ -249,10 +328,12 @@ impl Default for EgorConfig {
             hot_start: HotStartMode::Disabled,
             xtypes: vec![],
             seed: None,
+            parallelism: Parallelism::default(),
             trego: TregoConfig::default(),
             coego: CoegoConfig::default(),
             cstr_infill: false,
             cstr_strategy: ConstraintStrategy::MeanConstraint,
+            noisy: false,
         }
     }
 }
@@ -284,6 +365,25 @@ impl EgorConfig {
         self
     }
 
+    /// Sets the number of objectives (default 1)
+    ///
+    /// When greater than 1, the function output is expected to be
+    /// [obj_1, ..., obj_nobj, cstr_1, ..., cstr_n], each objective is modeled with its own
+    /// surrogate and the Pareto front is returned in the optimization result.
+    /// The reported optimum `x_opt`, `y_opt` is then the best point wrt the first objective only,
+    /// the meaningful outputs being the `pareto_set` and `pareto_front` of the result.
+    /// Note: TREGO and CoEGO are not available in multi-objective optimization.
+    pub fn n_obj(mut self, n_obj: usize) -> Self {
+        self.n_obj = n_obj;
+        self
+    }
+
+    /// Sets the strategy used to select next point in multi-objective optimization
+    pub fn multi_obj_strategy(mut self, multi_obj: MultiObjStrategy) -> Self {
+        self.multi_obj = multi_obj;
+        self
+    }
+
     /// Sets the number of constraint functions
     pub fn n_cstr(mut self, n_cstr: usize) -> Self {
         self.n_cstr = n_cstr;
@@ -339,6 +439,15 @@ impl EgorConfig {
             InfillStrategy::LogEI => Box::new(LOG_EI),
             InfillStrategy::WB2 => Box::new(WB2),
             InfillStrategy::WB2S => Box::new(WB2S),
+            InfillStrategy::AEI => Box::new(AEI),
+            InfillStrategy::SampledIncumbentEI => {
+                Box::new(SampledIncumbentExpectedImprovement::default())
+            }
+            InfillStrategy::LCB => Box::new(LCB),
+            InfillStrategy::PI => Box::new(PI),
+            InfillStrategy::TS => Box::new(TS),
+            InfillStrategy::MES => Box::new(MES),
+            InfillStrategy::KG => Box::new(KG),
         };
         self
     }
@@ -391,6 +500,15 @@ impl EgorConfig {
         self
     }
 
+    /// Sets the parallelism policy: surrogates training (experts, hyperparameters
+    /// optimization restarts) and infill criterion multistart optimization are run
+    /// within the specified thread pool. Results do not depend on the number of threads
+    /// for a given seed.
+    pub fn parallelism(mut self, parallelism: Parallelism) -> Self {
+        self.parallelism = parallelism;
+        self
+    }
+
     /// Define design space with given x types
     pub fn xtypes(mut self, xtypes: &[XType]) -> Self {
         self.xtypes = xtypes.into();
@@ -427,8 +545,40 @@ impl EgorConfig {
         self
     }
 
+    /// Sets whether function evaluations are noisy (e.g. stochastic simulations)
+    ///
+    /// When true, the current minimum used by infill criteria and the reported optimum
+    /// are the evaluated point with the best posterior mean instead of the best observed value
+    /// (the posterior of the surrogates trained at the current iteration to select the new points).
+    /// Surrogates have to estimate the noise: an interpolating [GpType::FullGp] is rejected,
+    /// use for instance a [GpType::RobustGp] with a [egobox_moe::RobustLikelihood::Gaussian] likelihood
+    /// (see [GpConfig::gp_type]). Noise-aware infill criteria are
+    /// [InfillStrategy::AEI] and [InfillStrategy::SampledIncumbentEI].
+    pub fn noisy(mut self, noisy: bool) -> Self {
+        self.noisy = noisy;
+        self
+    }
+
     /// Check whether we are in a discrete optimization context
     pub fn discrete(&self) -> bool {
         crate::utils::discrete(&self.xtypes)
     }
+
+    /// Check the consistency of the configuration
+    pub(crate) fn check(&self) -> Result<()> {
+        if self.n_obj == 0 {
+            return Err(EgoError::InvalidValue(
+                "Number of objectives `n_obj` should be at least 1".to_string(),
+            ));
+        }
+        if self.noisy && matches!(self.gp.gp_type, GpType::FullGp) {
+            return Err(EgoError::InvalidValue(
+                "Noisy evaluations require surrogates estimating the noise: \
+                 GP type should not be an interpolating `FullGp` \
+                 (e.g. use `RobustGp` with a `Gaussian` likelihood)"
+                    .to_string(),
+            ));
+        }
+        Ok(())
+    }
 }
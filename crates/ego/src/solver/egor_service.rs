@@ -31,7 +31,7 @@
 //!
 //! for _i in 0..10 {
 //!     // we tell function values and ask for next suggested optimum location
-//!     let x_suggested = egor.suggest(&doe, &y_doe);
+//!     let x_suggested = egor.suggest(&doe, &y_doe).expect("Suggestion");
 //!     
 //!     // we update the doe
 //!     doe = concatenate![Axis(0), doe, x_suggested];
@@ -43,6 +43,7 @@
 //!
 use std::marker::PhantomData;
 
+use crate::errors::Result;
 use crate::gpmix::mixint::*;
 use crate::{EgorConfig, EgorSolver, to_xtypes, types::*};
 
@@ -122,15 +123,19 @@ impl<SB: SurrogateBuilder + DeserializeOwned, C: CstrFn> EgorServiceApi<SB, C> {
     /// where optimum may be located with regard to the infill criterion.
     /// This function inverses the control of the optimization and can be used
     /// for an ask-and-tell interface to the Egor optimizer.
+    ///
+    /// # Errors
+    ///
+    /// * [EgoError::InvalidValue](crate::EgoError::InvalidValue): if the optimizer configuration is invalid
     pub fn suggest(
         &self,
         x_data: &ArrayBase<impl Data<Elem = f64>, Ix2>,
         y_data: &ArrayBase<impl Data<Elem = f64>, Ix2>,
-    ) -> Array2<f64> {
+    ) -> Result<Array2<f64>> {
         let xtypes = &self.solver.config.xtypes;
         let x_data = to_continuous_space(xtypes, x_data);
-        let x = self.solver.suggest(&x_data, y_data);
-        to_discrete_space(xtypes, &x).to_owned()
+        let x = self.solver.suggest(&x_data, y_data)?;
+        Ok(to_discrete_space(xtypes, &x).to_owned())
     }
 }
 
@@ -140,6 +145,7 @@ pub type EgorServiceBuilder = EgorServiceFactory<Cstr>;
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::EgoError;
     use crate::gpmix::spec::*;
     use approx::assert_abs_diff_eq;
     use ndarray::{ArrayView2, Axis, array, concatenate};
@@ -166,7 +172,7 @@ mod tests {
         let mut doe = array![[0.], [7.], [20.], [25.]];
         let mut y_doe = xsinx(&doe.view());
         for _i in 0..10 {
-            let x_suggested = ego.suggest(&doe, &y_doe);
+            let x_suggested = ego.suggest(&doe, &y_doe).expect("Suggestion");
 
             doe = concatenate![Axis(0), doe, x_suggested];
             y_doe = xsinx(&doe.view());
@@ -176,4 +182,16 @@ mod tests {
         let y_opt = y_doe.min().unwrap();
         assert_abs_diff_eq!(expected, *y_opt, epsilon = 1e-1);
     }
+
+    #[test]
+    fn test_n_obj_zero_egor_service() {
+        let ego = EgorServiceBuilder::optimize()
+            .configure(|conf| conf.n_obj(0).seed(42))
+            .min_within(&array![[0., 25.]]);
+
+        let doe = array![[0.], [7.], [20.], [25.]];
+        let y_doe = xsinx(&doe.view());
+        let res = ego.suggest(&doe, &y_doe);
+        assert!(matches!(res, Err(EgoError::InvalidValue(_))));
+    }
 }
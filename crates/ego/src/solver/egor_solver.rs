@@ -174,6 +174,7 @@ where
         problem: &mut Problem<O>,
         state: EgorState<f64>,
     ) -> std::result::Result<(EgorState<f64>, Option<KV>), argmin::core::Error> {
+        self.config.check()?;
         let mut rng = if let Some(seed) = self.config.seed {
             Xoshiro256Plus::seed_from_u64(seed)
         } else {
@@ -235,8 +236,8 @@ where
             write_npy(filepath, &doe).expect("Write initial doe");
         }
 
-        let clusterings = vec![None; self.config.n_cstr + 1];
-        let theta_inits = vec![None; self.config.n_cstr + 1];
+        let clusterings = vec![None; self.n_outputs()];
+        let theta_inits = vec![None; self.n_outputs()];
         let no_point_added_retries = MAX_POINT_ADDITION_RETRY;
 
         let c_data = self.eval_problem_fcstrs(problem, &x_data);
@@ -265,7 +266,8 @@ where
         ));
         initial_state.target_cost = self.config.target;
 
-        let best_index = find_best_result_index(&y_data, &c_data, &initial_state.cstr_tol);
+        let ranking = self.ranking_data(&y_data);
+        let best_index = find_best_result_index(&ranking, &c_data, &initial_state.cstr_tol);
         initial_state.best_index = Some(best_index);
         initial_state.prev_best_index = Some(best_index);
         initial_state.last_best_iter = 0;
@@ -276,7 +278,7 @@ where
         initial_state.feasibility = std::env::var(EGOBOX_USE_MAX_PROBA_OF_FEASIBILITY).is_err()
             || {
                 is_feasible(
-                    &y_data.row(best_index),
+                    &ranking.row(best_index),
                     &c_data.row(best_index),
                     &initial_state.cstr_tol,
                 )
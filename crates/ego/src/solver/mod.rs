@@ -3,6 +3,7 @@ mod egor_config;
 mod egor_service;
 mod egor_solver;
 mod egor_state;
+mod multiobj;
 mod solver_computations;
 mod solver_impl;
 mod solver_infill_optim;
@@ -0,0 +1,209 @@
+//! Multi-objective optimization: the function output is expected to be
+//! [obj_1, ..., obj_nobj, cstr_1, ..., cstr_n] and the next point is selected either
+//! by maximizing the Expected Hypervolume Improvement (EHVI) of the current Pareto front
+//! or the infill criterion of a random augmented Chebyshev scalarization (ParEGO).
+use crate::EgorSolver;
+use crate::criteria::ExpectedHypervolumeImprovement;
+use crate::types::*;
+use crate::utils::{find_pareto_indices, pofs, pofs_grad};
+
+use egobox_moe::MixtureGpSurrogate;
+use ndarray::{Array1, Array2, ArrayBase, ArrayView2, Axis, Data, Ix2, Zip, concatenate, s};
+use ndarray_rand::rand::Rng;
+use serde::de::DeserializeOwned;
+
+/// Weight of the augmented term of the ParEGO Chebyshev scalarization
+const PAREGO_RHO: f64 = 0.05;
+/// Reference point margin wrt the range of the Pareto front
+const REF_POINT_MARGIN: f64 = 0.1;
+
+impl<SB, C> EgorSolver<SB, C>
+where
+    SB: SurrogateBuilder + DeserializeOwned,
+    C: CstrFn,
+{
+    /// Number of outputs modeled by surrogates: objectives then constraints
+    pub(crate) fn n_outputs(&self) -> usize {
+        self.config.n_obj + self.config.n_cstr
+    }
+
+    /// Name of the surrogate model of the kth output
+    pub(crate) fn output_name(&self, k: usize) -> String {
+        let n_obj = self.config.n_obj;
+        if n_obj == 1 && k == 0 {
+            "Objective".to_string()
+        } else if k < n_obj {
+            format!("Objective[{}]", k + 1)
+        } else {
+            format!("Constraint[{}]", k - n_obj + 1)
+        }
+    }
+
+    /// Objective and constraints values [obj, cstr_1, ..., cstr_n] used to rank the results.
+    /// When several objectives are optimized only the first one is kept.
+    pub(crate) fn ranking_data(
+        &self,
+        y_data: &ArrayBase<impl Data<Elem = f64>, Ix2>,
+    ) -> Array2<f64> {
+        if self.config.n_obj == 1 {
+            y_data.to_owned()
+        } else {
+            concatenate![
+                Axis(1),
+                y_data.slice(s![.., ..1]),
+                y_data.slice(s![.., self.config.n_obj..])
+            ]
+        }
+    }
+
+    /// ParEGO augmented Chebyshev scalarization of the objectives normalized in [0, 1]
+    /// using weights drawn uniformly in the simplex
+    pub(crate) fn parego_scalarization(
+        &self,
+        y_data: &ArrayBase<impl Data<Elem = f64>, Ix2>,
+        rng: &mut impl Rng,
+    ) -> Array1<f64> {
+        let n_obj = self.config.n_obj;
+        let weights = Array1::from_shape_fn(n_obj, |_| -(1. - rng.r#gen::<f64>()).ln());
+        let weights = &weights / weights.sum();
+
+        let objs = y_data.slice(s![.., ..n_obj]);
+        let mut scalarized = Array1::zeros(objs.nrows());
+        Zip::from(&mut scalarized)
+            .and(objs.rows())
+            .for_each(|f, y| {
+                let normalized =
+                    Zip::from(&y)
+                        .and(objs.columns())
+                        .and(&weights)
+                        .map_collect(|v, col, w| {
+                            let min = col.fold(f64::INFINITY, |acc, c| acc.min(*c));
+                            let max = col.fold(f64::NEG_INFINITY, |acc, c| acc.max(*c));
+                            let range = if max - min > f64::EPSILON {
+                                max - min
+                            } else {
+                                1.
+                            };
+                            w * (v - min) / range
+                        });
+                *f = normalized.fold(f64::NEG_INFINITY, |acc, v| acc.max(*v))
+                    + PAREGO_RHO * normalized.sum();
+            });
+        scalarized
+    }
+
+    /// EHVI criterion of the current feasible Pareto front.
+    /// The reference point is the nadir point of the front shifted by a margin
+    /// relative to the front range.
+    pub(crate) fn make_ehvi(
+        &self,
+        y_data: &ArrayBase<impl Data<Elem = f64>, Ix2>,
+        c_data: &ArrayBase<impl Data<Elem = f64>, Ix2>,
+        cstr_tol: &Array1<f64>,
+        rng: &mut impl Rng,
+    ) -> ExpectedHypervolumeImprovement {
+        let n_obj = self.config.n_obj;
+        let objs = y_data.slice(s![.., ..n_obj]);
+        let indices = find_pareto_indices(y_data, c_data, n_obj, cstr_tol);
+        let front = objs.select(Axis(0), &indices);
+        // without feasible point the reference point is taken wrt all the data
+        let bounded = if front.nrows() > 0 {
+            front.view()
+        } else {
+            objs.view()
+        };
+        let ideal = bounded.map_axis(Axis(0), |c| c.fold(f64::INFINITY, |acc, v| acc.min(*v)));
+        let nadir = bounded.map_axis(Axis(0), |c| c.fold(f64::NEG_INFINITY, |acc, v| acc.max(*v)));
+        let data_range = objs.map_axis(Axis(0), |c| {
+            c.fold(f64::NEG_INFINITY, |acc, v| acc.max(*v))
+                - c.fold(f64::INFINITY, |acc, v| acc.min(*v))
+        });
+        let margin = Zip::from(&nadir)
+            .and(&ideal)
+            .and(&data_range)
+            .map_collect(|n, i, r| {
+                let range = if n - i > f64::EPSILON {
+                    n - i
+                } else if *r > f64::EPSILON {
+                    *r
+                } else {
+                    1.
+                };
+                REF_POINT_MARGIN * range
+            });
+        let ref_point = &nadir + &margin;
+        let lower_bound = &ideal - &(&margin / REF_POINT_MARGIN);
+        log::debug!(
+            "EHVI Pareto front size = {}, ref point = {ref_point}",
+            front.nrows()
+        );
+        ExpectedHypervolumeImprovement::new(&front, &ref_point, &lower_bound, rng)
+    }
+
+    /// The EHVI scaling is computed using x (n points of nx dim)
+    pub(crate) fn compute_ehvi_scale(
+        &self,
+        x: &ArrayView2<f64>,
+        ehvi: &ExpectedHypervolumeImprovement,
+        obj_models: &[Box<dyn MixtureGpSurrogate>],
+        cstr_models: &[Box<dyn MixtureGpSurrogate>],
+        cstr_tols: &Array1<f64>,
+    ) -> f64 {
+        let scale = x
+            .rows()
+            .into_iter()
+            .map(|x| {
+                self.eval_ehvi_obj(&x.to_vec(), ehvi, obj_models, cstr_models, cstr_tols, 1.)
+                    .abs()
+            })
+            .filter(|v| v.is_finite())
+            .fold(0., f64::max);
+        if scale < 100.0 * f64::EPSILON {
+            1.0
+        } else {
+            scale
+        }
+    }
+
+    /// Compute EHVI infill objective expected to be minimized (ie negative EHVI)
+    /// Constraints are taken into account using probability of feasibility
+    /// when constrained infill criterion is activated
+    pub(crate) fn eval_ehvi_obj(
+        &self,
+        x: &[f64],
+        ehvi: &ExpectedHypervolumeImprovement,
+        obj_models: &[Box<dyn MixtureGpSurrogate>],
+        cstr_models: &[Box<dyn MixtureGpSurrogate>],
+        cstr_tols: &Array1<f64>,
+        scale: f64,
+    ) -> f64 {
+        let value = -ehvi.value(x, obj_models) / scale;
+        if self.config.cstr_infill {
+            value * pofs(x, cstr_models, &cstr_tols.to_vec())
+        } else {
+            value
+        }
+    }
+
+    /// Derivatives of EHVI infill objective wrt x components
+    pub(crate) fn eval_grad_ehvi_obj(
+        &self,
+        x: &[f64],
+        ehvi: &ExpectedHypervolumeImprovement,
+        obj_models: &[Box<dyn MixtureGpSurrogate>],
+        cstr_models: &[Box<dyn MixtureGpSurrogate>],
+        cstr_tols: &Array1<f64>,
+        scale: f64,
+    ) -> Vec<f64> {
+        let grad = -ehvi.grad(x, obj_models) / scale;
+        if self.config.cstr_infill && !cstr_models.is_empty() {
+            let value = -ehvi.value(x, obj_models) / scale;
+            let cstr_tols = cstr_tols.to_vec();
+            let pofs_val = pofs(x, cstr_models, &cstr_tols);
+            let pofs_grad = pofs_grad(x, cstr_models, &cstr_tols);
+            (grad * pofs_val + pofs_grad * value).to_vec()
+        } else {
+            grad.to_vec()
+        }
+    }
+}
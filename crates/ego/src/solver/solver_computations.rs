@@ -233,28 +233,27 @@ where
         &self,
         xk: &ArrayBase<impl Data<Elem = f64>, Ix1>,
         y_data: &ArrayBase<impl Data<Elem = f64>, Ix2>,
-        obj_model: &dyn MixtureGpSurrogate,
+        obj_models: &[Box<dyn MixtureGpSurrogate>],
         cstr_models: &[Box<dyn MixtureGpSurrogate>],
     ) -> Result<Vec<f64>> {
         let mut res: Vec<f64> = vec![];
         if self.config.q_ei == QEiStrategy::ConstantLiarMinimum {
             let index_min = y_data.slice(s![.., 0_usize]).argmin().unwrap();
-            res.push(y_data[[index_min, 0]]);
-            for ic in 1..=self.config.n_cstr {
-                res.push(y_data[[index_min, ic]]);
-            }
+            res.extend(y_data.row(index_min).iter());
             Ok(res)
         } else {
             let x = &xk.view().insert_axis(Axis(0));
-            let pred = obj_model.predict(x)?[0];
-            let var = obj_model.predict_var(x)?[0];
             let conf = match self.config.q_ei {
                 QEiStrategy::KrigingBeliever => 0.,
                 QEiStrategy::KrigingBelieverLowerBound => -3.,
                 QEiStrategy::KrigingBelieverUpperBound => 3.,
                 _ => -1., // never used
             };
-            res.push(pred + conf * f64::sqrt(var));
+            for obj_model in obj_models {
+                let pred = obj_model.predict(x)?[0];
+                let var = obj_model.predict_var(x)?[0];
+                res.push(pred + conf * f64::sqrt(var));
+            }
             for cstr_model in cstr_models {
                 res.push(cstr_model.predict(x)?[0]);
             }
@@ -262,6 +261,21 @@ where
         }
     }
 
+    /// Posterior mean values [obj, cstr_1, ... cstr_n] predicted at x (n points of nx dim)
+    /// used to rank the evaluated points when evaluations are noisy
+    pub(crate) fn posterior_ranking_data(
+        &self,
+        models: &[Box<dyn MixtureGpSurrogate>],
+        x: &ArrayBase<impl Data<Elem = f64>, Ix2>,
+    ) -> Result<Array2<f64>> {
+        let ranked = std::iter::once(&models[0]).chain(&models[self.config.n_obj..]);
+        let mut ranking = Array2::zeros((x.nrows(), models.len() - self.config.n_obj + 1));
+        for (mut col, model) in ranking.columns_mut().into_iter().zip(ranked) {
+            col.assign(&model.predict(&x.view())?);
+        }
+        Ok(ranking)
+    }
+
     /// The infill criterion scaling is computed using x (n points of nx dim)
     /// given the objective function surrogate
     #[allow(clippy::too_many_arguments)]
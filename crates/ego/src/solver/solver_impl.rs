@@ -21,12 +21,12 @@ use crate::{EgorState, types::*};
 use argmin::argmin_error_closure;
 use argmin::core::{CostFunction, Problem, State};
 
-use egobox_doe::{Lhs, LhsKind};
+use egobox_doe::{Lhs, LhsKind, SamplingMethod};
 use egobox_gp::ThetaTuning;
 use env_logger::{Builder, Env};
 
 use egobox_moe::{Clustering, MixtureGpSurrogate, NbClusters};
-use log::{debug, info};
+use log::{debug, info, warn};
 use ndarray::{Array1, Array2, ArrayBase, Axis, Data, Ix1, Ix2, Zip, concatenate, s};
 use ndarray_rand::rand::{Rng, SeedableRng};
 use rand_xoshiro::Xoshiro256Plus;
@@ -41,11 +41,16 @@ impl<SB: SurrogateBuilder + DeserializeOwned, C: CstrFn> EgorSolver<SB, C> {
     ///
     /// The function `f` should return an objective but also constraint values if any.
     /// Design space is specified by a list of types for input variables `x` of `f` (see [`XType`]).
-    pub fn new(config: EgorConfig) -> Self {
+    pub fn new(mut config: EgorConfig) -> Self {
         let env = Env::new().filter_or(EGOBOX_LOG, "info");
         let mut builder = Builder::from_env(env);
         let builder = builder.target(env_logger::Target::Stdout);
         builder.try_init().ok();
+        if config.n_obj > 1 && (config.trego.activated || config.coego.activated) {
+            log::warn!("TREGO and CoEGO are not available in multi-objective optimization");
+            config.trego.activated = false;
+            config.coego.activated = false;
+        }
         let xtypes = config.xtypes.clone();
         EgorSolver {
             config,
@@ -59,18 +64,23 @@ impl<SB: SurrogateBuilder + DeserializeOwned, C: CstrFn> EgorSolver<SB, C> {
     /// where optimum may occurs regarding the infill criterium.
     /// This function inverse the control of the optimization and can used
     /// ask-and-tell interface to the EGO optimizer.
+    ///
+    /// # Errors
+    ///
+    /// * [EgoError::InvalidValue]: if the optimizer configuration is invalid
     pub fn suggest(
         &self,
         x_data: &ArrayBase<impl Data<Elem = f64>, Ix2>,
         y_data: &ArrayBase<impl Data<Elem = f64>, Ix2>,
-    ) -> Array2<f64> {
+    ) -> Result<Array2<f64>> {
+        self.config.check()?;
         let mut rng = if let Some(seed) = self.config.seed {
             Xoshiro256Plus::seed_from_u64(seed)
         } else {
             Xoshiro256Plus::from_entropy()
         };
-        let mut clusterings = vec![None; 1 + self.config.n_cstr];
-        let mut theta_tunings = vec![None; 1 + self.config.n_cstr];
+        let mut clusterings = vec![None; self.n_outputs()];
+        let mut theta_tunings = vec![None; self.n_outputs()];
         let cstr_tol = self
             .config
             .cstr_tol
@@ -84,10 +94,12 @@ impl<SB: SurrogateBuilder + DeserializeOwned, C: CstrFn> EgorSolver<SB, C> {
         // TODO: Coego not implemented
         let activity = None;
 
-        let best_index = find_best_result_index(y_data, &c_data, &cstr_tol);
-        let feasibility = is_feasible(&y_data.row(best_index), &c_data.row(best_index), &cstr_tol);
+        let ranking = self.ranking_data(y_data);
+        let best_index = find_best_result_index(&ranking, &c_data, &cstr_tol);
+        let feasibility = is_feasible(&ranking.row(best_index), &c_data.row(best_index), &cstr_tol);
 
-        let (x_dat, _, _, _, _) = self.select_next_points(
+        // infill criterion conditioning is done on a copy as suggestion has no side effect
+        let (x_dat, _, _, _, _, _) = self.clone().select_next_points(
             true,
             0,
             false, // done anyway
@@ -102,8 +114,8 @@ impl<SB: SurrogateBuilder + DeserializeOwned, C: CstrFn> EgorSolver<SB, C> {
             &fcstrs,
             feasibility,
             &mut rng,
-        );
-        x_dat
+        )?;
+        Ok(x_dat)
     }
 }
 
@@ -117,6 +129,28 @@ where
             && (added != 0 && added % 10 == 0 && added - prev_added > 0)
     }
 
+    /// Cluster the training data wrt objective and constraints values at once
+    /// to get a clustering shared by all the surrogates
+    /// (`None` when not supported by the surrogate builder)
+    fn make_shared_clustering(
+        &self,
+        xt: &ArrayBase<impl Data<Elem = f64>, Ix2>,
+        yt: &ArrayBase<impl Data<Elem = f64>, Ix2>,
+    ) -> Option<Clustering> {
+        let mut builder = self.surrogate_builder.clone();
+        builder.set_gp_type(self.config.gp.gp_type.clone());
+        builder.set_kpls_dim(self.config.gp.kpls_dim);
+        builder.set_regression_spec(self.config.gp.regression_spec);
+        builder.set_correlation_spec(self.config.gp.correlation_spec);
+        builder.set_n_clusters(self.config.gp.n_clusters.clone());
+        builder.set_recombination(self.config.gp.recombination);
+        info!("Shared clustering of objective and constraints...");
+        builder
+            .make_clustering(xt.view(), yt.slice(s![.., ..self.n_outputs()]))
+            .inspect_err(|err| warn!("{err}, outputs are clustered independently"))
+            .ok()
+    }
+
     /// Build surrogate given training data and surrogate builder
     /// Reclustering is triggered when recluster boolean is true otherwise
     /// previous clu=stering is used. theta_init allows to reuse
@@ -135,18 +169,36 @@ where
         actives: &Array2<usize>,
     ) -> (Box<dyn MixtureGpSurrogate>, Array2<f64>) {
         let mut builder = self.surrogate_builder.clone();
-        builder.set_kpls_dim(self.config.gp.kpls_dim);
+        builder.set_gp_type(self.config.gp.gp_type.clone());
+        // With automatic selection, the number of PLS components is selected when clustering
+        // and retrieved from hyperparameters dimension otherwise
+        let kpls_dim = match (self.config.gp.kpls_selection, theta_inits) {
+            (Some(_), Some(inits)) if !make_clustering => {
+                (inits.ncols() < xt.ncols()).then_some(inits.ncols())
+            }
+            (Some(kpls_selection), _) => builder
+                .select_kpls_dim(kpls_selection, xt.view(), yt.view())
+                .expect("KPLS dimension selection failure"),
+            (None, _) => self.config.gp.kpls_dim,
+        };
+        if self.config.gp.kpls_selection.is_some() {
+            debug!("{model_name} KPLS dimension: {kpls_dim:?}");
+        }
+        builder.set_kpls_dim(kpls_dim);
         builder.set_regression_spec(self.config.gp.regression_spec);
         builder.set_correlation_spec(self.config.gp.correlation_spec);
         builder.set_n_clusters(self.config.gp.n_clusters.clone());
         builder.set_recombination(self.config.gp.recombination);
+        builder.set_mixture_variance(self.config.gp.mixture_variance);
         builder.set_optim_params(self.config.gp.n_start, self.config.gp.max_eval);
         let mut model = None;
         let mut best_likelihood = -f64::INFINITY;
 
-        let dim = self.config.gp.kpls_dim.unwrap_or(xt.ncols());
+        let dim = kpls_dim.unwrap_or(xt.ncols());
 
-        let mut best_theta_inits = if let Some(inits) = theta_inits {
+        let mut best_theta_inits = if let Some(inits) = theta_inits
+            && inits.ncols() == dim
+        {
             inits.to_owned()
         } else {
             // otherwise suppose one cluster but will not be used
@@ -370,27 +422,25 @@ where
             .unwrap_or(&self.full_activity())
             .to_owned();
 
-        (0..=self.config.n_cstr)
-            .into_par_iter()
-            .map(|k| {
-                let name = if k == 0 {
-                    "Objective".to_string()
-                } else {
-                    format!("Constraint[{k}]")
-                };
-                self.make_clustered_surrogate(
-                    &name,
-                    &state.data.as_ref().unwrap().0,
-                    &state.data.as_ref().unwrap().1.slice(s![.., k]).to_owned(),
-                    false,
-                    true,
-                    state.clusterings.as_ref().unwrap()[k].as_ref(),
-                    state.theta_inits.as_ref().unwrap()[k].as_ref(),
-                    &actives,
-                )
-                .0
-            })
-            .collect()
+        self.config.parallelism.install(|| {
+            (0..self.n_outputs())
+                .into_par_iter()
+                .map(|k| {
+                    let name = self.output_name(k);
+                    self.make_clustered_surrogate(
+                        &name,
+                        &state.data.as_ref().unwrap().0,
+                        &state.data.as_ref().unwrap().1.slice(s![.., k]).to_owned(),
+                        false,
+                        true,
+                        state.clusterings.as_ref().unwrap()[k].as_ref(),
+                        state.theta_inits.as_ref().unwrap()[k].as_ref(),
+                        &actives,
+                    )
+                    .0
+                })
+                .collect()
+        })
     }
 
     /// This function is the main EGO algorithm iteration:
@@ -426,28 +476,29 @@ where
             .take_data()
             .ok_or_else(argmin_error_closure!(PotentialBug, "EgorSolver: No data!"))?;
 
-        let (try_add_count, rejected_count, _) = loop {
+        let (try_add_count, rejected_count, _, data_models) = loop {
             let recluster = self.have_to_recluster(new_state.added, new_state.prev_added);
             let init = new_state.get_iter() == 0;
             let pb = problem.take_problem().unwrap();
             let fcstrs = pb.fn_constraints();
 
-            let (x_dat, y_dat, c_dat, infill_value, infill_data) = self.select_next_points(
-                init,
-                state.get_iter(),
-                recluster,
-                &mut clusterings,
-                &mut theta_inits,
-                activity.as_ref(),
-                &x_data,
-                &y_data,
-                &c_data,
-                &state.cstr_tol,
-                state.best_index.unwrap(),
-                fcstrs,
-                state.feasibility,
-                &mut rng,
-            );
+            let (x_dat, y_dat, c_dat, infill_value, infill_data, data_models) = self
+                .select_next_points(
+                    init,
+                    state.get_iter(),
+                    recluster,
+                    &mut clusterings,
+                    &mut theta_inits,
+                    activity.as_ref(),
+                    &x_data,
+                    &y_data,
+                    &c_data,
+                    &state.cstr_tol,
+                    state.best_index.unwrap(),
+                    fcstrs,
+                    state.feasibility,
+                    &mut rng,
+                )?;
 
             problem.problem = Some(pb);
 
@@ -510,7 +561,7 @@ where
                 }
             } else {
                 // ok point added we can go on, just output number of rejected point
-                break (x_dat.nrows(), rejected_count, infill_data);
+                break (x_dat.nrows(), rejected_count, infill_data, data_models);
             }
         };
         let add_count = (try_add_count - rejected_count) as i32;
@@ -534,19 +585,39 @@ where
         Zip::from(y_data.slice_mut(s![-add_count.., ..]).rows_mut())
             .and(y_actual.rows())
             .for_each(|mut y, val| y.assign(&val));
-        let best_index = find_best_result_index_from(
-            state.best_index.unwrap(),
-            y_data.nrows() - add_count as usize,
-            &y_data,
-            &c_data,
-            &new_state.cstr_tol,
-        );
+        new_state = new_state.data((x_data.clone(), y_data.clone(), c_data.clone()));
+        // With noisy evaluations, the evaluated points are ranked wrt the posterior mean
+        // of the surrogates used to select the new points (not retrained with the new
+        // evaluations, which are taken into account at next iteration)
+        let posterior_ranking = match data_models {
+            Some(models) if self.config.noisy => self
+                .posterior_ranking_data(&models, &x_data)
+                .map_err(|err| info!("Error while predicting posterior best: {err}"))
+                .ok(),
+            _ => None,
+        };
+        let (ranking, best_index) = match posterior_ranking {
+            Some(ranking) => {
+                let index = find_best_result_index(&ranking, &c_data, &new_state.cstr_tol);
+                (ranking, index)
+            }
+            None => {
+                let ranking = self.ranking_data(&y_data);
+                let index = find_best_result_index_from(
+                    state.best_index.unwrap(),
+                    y_data.nrows() - add_count as usize,
+                    &ranking,
+                    &c_data,
+                    &new_state.cstr_tol,
+                );
+                (ranking, index)
+            }
+        };
         new_state.prev_best_index = state.best_index;
         new_state.best_index = Some(best_index);
-        new_state = new_state.data((x_data.clone(), y_data.clone(), c_data.clone()));
         new_state.feasibility = state.feasibility
             || is_feasible(
-                &y_data.row(best_index),
+                &ranking.row(best_index),
                 &c_data.row(best_index),
                 &new_state.cstr_tol,
             );
@@ -555,11 +626,12 @@ where
 
     /// Returns next promising x points together with virtual (predicted) y values
     /// from surrogate models (taking into account qei strategy if q_parallel)
-    /// infill criterion value is also returned
+    /// infill criterion value is also returned as well as the surrogates trained
+    /// on the given data if any
     #[allow(clippy::too_many_arguments)]
     #[allow(clippy::type_complexity)]
     pub fn select_next_points(
-        &self,
+        &mut self,
         init: bool,
         iter: u64,
         recluster: bool,
@@ -574,14 +646,16 @@ where
         cstr_funcs: &[impl CstrFn],
         feasibility: bool,
         rng: &mut Xoshiro256Plus,
-    ) -> (
+    ) -> Result<(
         Array2<f64>,
         Array2<f64>,
         Array2<f64>,
         f64,
         InfillObjData<f64>,
-    ) {
+        Option<Vec<Box<dyn MixtureGpSurrogate>>>,
+    )> {
         let mut portfolio = vec![];
+        let mut data_models = None;
 
         let sigma_weights =
             if std::env::var(EGOBOX_USE_GP_VAR_PORTFOLIO).is_ok() && self.config.q_points == 1 {
@@ -619,54 +693,121 @@ where
                 ..Default::default()
             };
             for i in 0..self.config.q_points {
-                let (xt, yt) = if i == 0 {
-                    (x_data.to_owned(), y_data.to_owned())
+                let (xt, yt, ct) = if i == 0 {
+                    (x_data.to_owned(), y_data.to_owned(), c_data.to_owned())
                 } else {
                     (
                         concatenate![Axis(0), x_data.to_owned(), x_dat.to_owned()],
                         concatenate![Axis(0), y_data.to_owned(), y_dat.to_owned()],
+                        concatenate![Axis(0), c_data.to_owned(), c_dat.to_owned()],
                     )
                 };
 
                 log::debug!("activity: {activity:?}");
                 let actives = activity.unwrap_or(&self.full_activity()).to_owned();
 
+                let make_clustering = (init && i == 0) || recluster;
+                let shared_clustering = (make_clustering && self.config.gp.shared_clustering)
+                    .then(|| self.make_shared_clustering(&xt, &yt))
+                    .flatten();
+
                 info!("Train surrogates with {} points...", xt.nrows());
-                let models_and_inits = (0..=self.config.n_cstr).into_par_iter().map(|k| {
-                    let name = if k == 0 {
-                        "Objective".to_string()
-                    } else {
-                        format!("Constraint[{k}]")
-                    };
-                    let make_clustering = (init && i == 0) || recluster;
-                    let optimize_theta =
-                        ((iter as usize * self.config.q_points + i) % (self.config.q_optmod) == 0)
-                            && j == 0;
-                    self.make_clustered_surrogate(
-                        &name,
-                        &xt,
-                        &yt.slice(s![.., k]).to_owned(),
-                        make_clustering,
-                        optimize_theta,
-                        clusterings[k].as_ref(),
-                        theta_inits[k].as_ref(),
-                        &actives,
-                    )
+                let (models, inits): (Vec<_>, Vec<_>) = self.config.parallelism.install(|| {
+                    (0..self.n_outputs())
+                        .into_par_iter()
+                        .map(|k| {
+                            let name = self.output_name(k);
+                            let optimize_theta = ((iter as usize * self.config.q_points + i)
+                                % (self.config.q_optmod)
+                                == 0)
+                                && j == 0;
+                            // With shared clustering, surrogates are trained on the given clusters
+                            let (make_clustering, optimize_theta, clustering) =
+                                match shared_clustering.as_ref() {
+                                    Some(shared) => (false, true, Some(shared)),
+                                    None => {
+                                        (make_clustering, optimize_theta, clusterings[k].as_ref())
+                                    }
+                                };
+                            self.make_clustered_surrogate(
+                                &name,
+                                &xt,
+                                &yt.slice(s![.., k]).to_owned(),
+                                make_clustering,
+                                optimize_theta,
+                                clustering,
+                                theta_inits[k].as_ref(),
+                                &actives,
+                            )
+                        })
+                        .unzip()
                 });
-                let (models, inits): (Vec<_>, Vec<_>) = models_and_inits.unzip();
                 // if std::env::var(EGOBOX_GP_RECORDER).is_ok() {
                 //     gp_recorder::save_gp_models(&models);
                 // }
 
-                (0..=self.config.n_cstr).for_each(|k| {
+                (0..self.n_outputs()).for_each(|k| {
                     clusterings[k] = Some(models[k].to_clustering());
                     theta_inits[k] = Some(inits[k].to_owned());
                 });
 
-                let (obj_model, cstr_models) = models.split_first().unwrap();
+                let (obj_models, cstr_models) = models.split_at(self.config.n_obj);
                 debug!("... surrogates trained");
 
-                let fmin = y_data[[best_index, 0]];
+                // With noisy evaluations, the current best is the evaluated point
+                // with the best posterior mean
+                let (best_index, posterior_fmin) = if self.config.noisy {
+                    match self.posterior_ranking_data(&models, x_data) {
+                        Ok(ranking) => {
+                            let index = find_best_result_index(&ranking, c_data, cstr_tol);
+                            (index, Some(ranking[[index, 0]]))
+                        }
+                        Err(err) => {
+                            info!("Error while predicting posterior best: {err}");
+                            (best_index, None)
+                        }
+                    }
+                } else {
+                    (best_index, None)
+                };
+
+                // In multi-objective optimization, ParEGO optimizes the infill criterion
+                // of a surrogate of the scalarized objectives while EHVI relies on
+                // the objectives surrogates
+                let (scalarized_model, fmin) =
+                    if self.config.n_obj > 1 && self.config.multi_obj == MultiObjStrategy::ParEgo {
+                        let scalarized = self.parego_scalarization(&yt, rng);
+                        let (model, _) = self.make_clustered_surrogate(
+                            "Scalarized objective",
+                            &xt,
+                            &scalarized,
+                            true,
+                            true,
+                            None,
+                            None,
+                            &actives,
+                        );
+                        let ranking = concatenate![
+                            Axis(1),
+                            scalarized.slice(s![..y_data.nrows()]).insert_axis(Axis(1)),
+                            y_data.slice(s![.., self.config.n_obj..])
+                        ];
+                        let index = find_best_result_index(&ranking, c_data, cstr_tol);
+                        (Some(model), scalarized[index])
+                    } else {
+                        (None, posterior_fmin.unwrap_or(y_data[[best_index, 0]]))
+                    };
+                let obj_model = scalarized_model.as_ref().unwrap_or(&obj_models[0]);
+                self.config.infill_criterion.condition(
+                    &x_data.view(),
+                    &self.xlimits.view(),
+                    obj_model.as_ref(),
+                    rng,
+                )?;
+                let ehvi = (self.config.n_obj > 1
+                    && self.config.multi_obj == MultiObjStrategy::Ehvi)
+                    .then(|| self.make_ehvi(&yt, &ct, cstr_tol, rng));
+
                 let ybest = y_data.row(best_index).to_owned();
                 let xbest = x_data.row(best_index).to_owned();
                 let cbest = c_data.row(best_index).to_owned();
@@ -684,6 +825,20 @@ where
                     fmin,
                     *sigma_weight,
                 );
+                let scale_infill_obj = if let Some(ehvi) = ehvi.as_ref() {
+                    let npts = (100 * self.xlimits.nrows()).min(1000);
+                    let scale = self.compute_ehvi_scale(
+                        &sampling.sample(npts).view(),
+                        ehvi,
+                        obj_models,
+                        cstr_models,
+                        cstr_tol,
+                    );
+                    info!("EHVI scaling is updated to {scale}");
+                    scale
+                } else {
+                    scale_infill_obj
+                };
 
                 let all_scale_cstr = concatenate![Axis(0), scale_cstr, scale_fcstr];
 
@@ -697,6 +852,7 @@ where
                     sigma_weight: *sigma_weight,
                 };
 
+                let config = &self.config;
                 let cstr_funcs = cstr_funcs
                     .iter()
                     .enumerate()
@@ -706,15 +862,13 @@ where
                               gradient: Option<&mut [f64]>,
                               params: &mut InfillObjData<f64>|
                               -> f64 {
-                            let x = if self.config.discrete() {
+                            let x = if config.discrete() {
                                 let xary =
                                     Array2::from_shape_vec((1, x.len()), x.to_vec()).unwrap();
                                 // We have to cast x to folded space as EgorSolver
                                 // works internally in the continuous space while
                                 // the constraint function expects discrete variable in folded space
-                                to_discrete_space(&self.config.xtypes, &xary)
-                                    .row(0)
-                                    .into_owned();
+                                to_discrete_space(&config.xtypes, &xary).row(0).into_owned();
                                 &xary.into_iter().collect::<Vec<_>>()
                             } else {
                                 x
@@ -731,6 +885,7 @@ where
 
                 let infill_optpb = InfillOptProblem {
                     obj_model: obj_model.as_ref(),
+                    ehvi: ehvi.as_ref().map(|ehvi| (obj_models, ehvi)),
                     cstr_models,
                     cstr_funcs: &cstr_funcs,
                     cstr_tols: cstr_tol,
@@ -745,9 +900,9 @@ where
                 );
                 debug!("+++++++  xk = {xk}");
 
-                match self.compute_virtual_point(&xk, y_data, obj_model.as_ref(), cstr_models) {
+                match self.compute_virtual_point(&xk, y_data, obj_models, cstr_models) {
                     Ok(yk) => {
-                        let yk = Array2::from_shape_vec((1, 1 + self.config.n_cstr), yk).unwrap();
+                        let yk = Array2::from_shape_vec((1, self.n_outputs()), yk).unwrap();
                         y_dat = concatenate![Axis(0), y_dat, yk];
 
                         let ck = cstr_funcs
@@ -772,6 +927,9 @@ where
                         break;
                     }
                 }
+                if i == 0 && j == 0 {
+                    data_models = Some(models);
+                }
             }
             portfolio.push((x_dat.to_owned(), y_dat, c_dat, infill_val, infill_data));
         }
@@ -787,6 +945,6 @@ where
             portfolio.remove(0)
         };
 
-        (x_dat, y_dat, c_dat, infill_value, infill_data)
+        Ok((x_dat, y_dat, c_dat, infill_value, infill_data, data_models))
     }
 }
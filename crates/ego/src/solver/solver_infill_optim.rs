@@ -1,3 +1,4 @@
+use crate::criteria::ExpectedHypervolumeImprovement;
 use crate::optimizers::*;
 use crate::types::*;
 
@@ -26,6 +27,11 @@ pub(crate) trait MultiStarter {
 
 pub(crate) struct InfillOptProblem<'a, CstrFn> {
     pub obj_model: &'a dyn MixtureGpSurrogate,
+    /// Surrogates of the objectives and EHVI criterion used in multi-objective optimization
+    pub ehvi: Option<(
+        &'a [Box<dyn MixtureGpSurrogate>],
+        &'a ExpectedHypervolumeImprovement,
+    )>,
     pub cstr_models: &'a [Box<dyn MixtureGpSurrogate>],
     pub cstr_funcs: &'a [CstrFn],
     pub cstr_tols: &'a Array1<f64>,
@@ -53,6 +59,7 @@ where
     {
         let InfillOptProblem {
             obj_model,
+            ehvi,
             cstr_models,
             cstr_funcs,
             cstr_tols,
@@ -90,7 +97,16 @@ where
                     }
 
                     if let Some(grad) = gradient {
-                        let g_infill_obj = if self.config.cstr_infill {
+                        let g_infill_obj = if let Some((obj_models, ehvi)) = ehvi {
+                            self.eval_grad_ehvi_obj(
+                                &xcoop,
+                                ehvi,
+                                obj_models,
+                                cstr_models,
+                                cstr_tols,
+                                *scale_infill_obj,
+                            )
+                        } else if self.config.cstr_infill {
                             // Use constrained infill criterion
                             self.eval_grad_infill_obj_with_cstrs(
                                 &xcoop,
@@ -120,7 +136,16 @@ where
                             .collect::<Vec<_>>();
                         grad[..].copy_from_slice(&g_infill_obj);
                     }
-                    if self.config.cstr_infill {
+                    if let Some((obj_models, ehvi)) = ehvi {
+                        self.eval_ehvi_obj(
+                            &xcoop,
+                            ehvi,
+                            obj_models,
+                            cstr_models,
+                            cstr_tols,
+                            *scale_infill_obj,
+                        )
+                    } else if self.config.cstr_infill {
                         // Use constrained infill criterion
                         self.eval_infill_obj_with_cstrs(
                             &xcoop,
@@ -216,20 +241,28 @@ where
             }
             while !success && n_optim <= n_max_optim {
                 let x_start = multistarter.multistart(self.config.n_start, &active);
-                let res = (0..x_start.nrows())
-                    .into_par_iter()
-                    .map(|i| {
-                        Optimizer::new(algorithm, &obj, &cstr_refs, &infill_data, &xlimits_active)
+                let res = self.config.parallelism.install(|| {
+                    (0..x_start.nrows())
+                        .into_par_iter()
+                        .map(|i| {
+                            Optimizer::new(
+                                algorithm,
+                                &obj,
+                                &cstr_refs,
+                                &infill_data,
+                                &xlimits_active,
+                            )
                             .xinit(&x_start.row(i))
                             .max_eval((10 * x_start.len()).min(INFILL_MAX_EVAL_DEFAULT))
                             .ftol_rel(1e-4)
                             .ftol_abs(1e-4)
                             .minimize()
-                    })
-                    .reduce(
-                        || (f64::INFINITY, Array::ones((xlimits_active.nrows(),))),
-                        |a, b| if b.0 < a.0 { b } else { a },
-                    );
+                        })
+                        .reduce(
+                            || (f64::INFINITY, Array::ones((xlimits_active.nrows(),))),
+                            |a, b| if b.0 < a.0 { b } else { a },
+                        )
+                });
 
                 if res.0.is_nan() || res.0.is_infinite() {
                     success = false;
@@ -120,6 +120,7 @@ where
 
         let infill_optpb = InfillOptProblem {
             obj_model: obj_model.as_ref(),
+            ehvi: None,
             cstr_models,
             cstr_funcs: fcstrs,
             cstr_tols: &cstr_tols,
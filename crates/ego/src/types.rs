@@ -1,7 +1,13 @@
 use crate::gpmix::spec::*;
-use crate::{EgorState, errors::Result};
+use crate::{
+    EgorState,
+    errors::{EgoError, Result},
+};
 use argmin::core::CostFunction;
-use egobox_moe::{Clustering, MixtureGpSurrogate, NbClusters, Recombination, ThetaTuning};
+use egobox_moe::{
+    Clustering, GpType, KplsSelection, MixtureGpSurrogate, MixtureVariance, NbClusters,
+    Recombination, ThetaTuning,
+};
 use linfa::Float;
 use ndarray::{Array1, Array2, ArrayView1, ArrayView2};
 use serde::{Deserialize, Serialize};
@@ -10,6 +16,7 @@ use serde::{Deserialize, Serialize};
 #[derive(Clone, Debug)]
 pub struct OptimResult<F: Float> {
     /// Optimum x value
+    /// (best point wrt the first objective in multi-objective optimization, see `pareto_set`)
     pub x_opt: Array1<F>,
     /// Optimum y value (e.g. f(x_opt))
     pub y_opt: Array1<F>,
@@ -17,6 +24,10 @@ pub struct OptimResult<F: Float> {
     pub x_doe: Array2<F>,
     /// History of successive y values (e.g f(x_doe))
     pub y_doe: Array2<F>,
+    /// Objective values of the feasible non-dominated points (multi-objective optimization only)
+    pub pareto_front: Option<Array2<F>>,
+    /// Input values of the feasible non-dominated points (multi-objective optimization only)
+    pub pareto_set: Option<Array2<F>>,
     /// EgorSolver final state
     pub state: EgorState<F>,
 }
@@ -32,6 +43,31 @@ pub enum InfillStrategy {
     WB2,
     /// Scaled WB2
     WB2S,
+    /// Augmented Expected Improvement (noisy evaluations)
+    AEI,
+    /// Expected Improvement averaged over sampled incumbent minima (noisy evaluations)
+    SampledIncumbentEI,
+    /// Lower Confidence Bound with GP-UCB exploration weight schedule
+    LCB,
+    /// Probability of Improvement with default margin
+    PI,
+    /// Thompson Sampling
+    TS,
+    /// Max-value Entropy Search with Gumbel sampling of the minimum values
+    MES,
+    /// Discretized Knowledge Gradient (see [crate::criteria::KnowledgeGradient])
+    KG,
+}
+
+/// Strategy used to select next promising point when several objectives are optimized
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MultiObjStrategy {
+    /// Expected Hypervolume Improvement of the Pareto front
+    #[default]
+    Ehvi,
+    /// Infill criterion of a surrogate of augmented Chebyshev scalarizations of the objectives
+    /// with random weights drawn at each iteration
+    ParEgo,
 }
 
 /// Constraint criterion used to select next promising point
@@ -144,6 +180,14 @@ pub trait SurrogateBuilder: Clone + Serialize + Sync {
     /// Constructor from domain space specified with types.
     fn new_with_xtypes(xtypes: &[XType]) -> Self;
 
+    /// Sets the type of gaussian processes used as experts.
+    /// The default implementation only supports full GP experts and ignores other types.
+    fn set_gp_type(&mut self, gp_type: GpType<f64>) {
+        if !matches!(gp_type, GpType::FullGp) {
+            log::warn!("GP type {gp_type:?} not supported by the surrogate builder, ignored");
+        }
+    }
+
     /// Sets the allowed regression models used in gaussian processes.
     fn set_regression_spec(&mut self, regression_spec: RegressionSpec);
 
@@ -159,6 +203,16 @@ pub trait SurrogateBuilder: Clone + Serialize + Sync {
     /// Sets the mode of recombination to get the output prediction from experts prediction
     fn set_recombination(&mut self, recombination: Recombination<f64>);
 
+    /// Sets the variance computation mode of the mixture with smooth recombination
+    /// The default implementation only supports the default mode and ignores other modes.
+    fn set_mixture_variance(&mut self, mixture_variance: MixtureVariance) {
+        if mixture_variance != MixtureVariance::default() {
+            log::warn!(
+                "Mixture variance {mixture_variance:?} not supported by the surrogate builder, ignored"
+            );
+        }
+    }
+
     /// Sets the hyperparameters tuning strategy
     fn set_theta_tunings(&mut self, theta_tunings: &[ThetaTuning<f64>]);
 
@@ -179,6 +233,30 @@ pub trait SurrogateBuilder: Clone + Serialize + Sync {
         yt: ArrayView1<f64>,
         clustering: &Clustering,
     ) -> Result<Box<dyn MixtureGpSurrogate>>;
+
+    /// Select the number of PLS components of a surrogate trained on the given
+    /// training dataset (x, y) wrt `kpls_selection` (`None` meaning no dimension reduction)
+    /// The default implementation does not select and keeps the maximum number of components.
+    fn select_kpls_dim(
+        &self,
+        kpls_selection: KplsSelection,
+        xt: ArrayView2<f64>,
+        _yt: ArrayView1<f64>,
+    ) -> Result<Option<usize>> {
+        let dim = kpls_selection.max_dim();
+        Ok((dim > 0 && dim < xt.ncols()).then_some(dim))
+    }
+
+    /// Cluster the training dataset (x, y) with outputs y given as a (n, ny) matrix.
+    /// The resulting clustering can be shared by the surrogates of each output
+    /// (see [SurrogateBuilder::train_on_clusters])
+    /// The default implementation returns an error: shared clustering is not supported
+    /// and each output surrogate is clustered independently.
+    fn make_clustering(&self, _xt: ArrayView2<f64>, _yt: ArrayView2<f64>) -> Result<Clustering> {
+        Err(EgoError::EgoError(
+            "Shared clustering not supported by the surrogate builder".to_string(),
+        ))
+    }
 }
 
 /// A trait for functions used by internal optimizers
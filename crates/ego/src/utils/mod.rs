@@ -4,6 +4,7 @@ mod find_result;
 mod hot_start;
 mod logei_helper;
 mod misc;
+mod pareto;
 mod portfolio;
 mod sort_axis;
 mod start_points;
@@ -14,6 +15,7 @@ pub use find_result::*;
 pub use hot_start::*;
 pub use logei_helper::*;
 pub use misc::*;
+pub use pareto::*;
 pub use portfolio::*;
 pub use start_points::*;
 
@@ -0,0 +1,192 @@
+use crate::utils::is_feasible;
+use ndarray::{Array1, Array2, ArrayBase, ArrayView1, Axis, Data, Ix1, Ix2, s};
+use std::iter::zip;
+
+/// Check whether objective values `a` dominate objective values `b` (minimization)
+pub fn dominates(a: &ArrayView1<f64>, b: &ArrayView1<f64>) -> bool {
+    zip(a, b).all(|(u, v)| u <= v) && zip(a, b).any(|(u, v)| u < v)
+}
+
+/// Indices of the non-dominated rows of `y` given as a matrix (ns, n_obj)
+/// Duplicated rows are reported once (first occurence).
+pub fn pareto_front_indices(y: &ArrayBase<impl Data<Elem = f64>, Ix2>) -> Vec<usize> {
+    (0..y.nrows())
+        .filter(|&i| {
+            !(0..y.nrows())
+                .any(|j| dominates(&y.row(j), &y.row(i)) || (j < i && y.row(j) == y.row(i)))
+        })
+        .collect()
+}
+
+/// Indices of the feasible non-dominated points where
+/// * y_data containing ns samples [obj_1, ..., obj_nobj, cstr_1, ... cstr_nc] is given as a matrix (ns, nobj + nc)
+/// * c_data containing [fcstr_1, ... fcstr1_nfc] where fcstr_i is the value of function constraints at x_i
+pub fn find_pareto_indices(
+    y_data: &ArrayBase<impl Data<Elem = f64>, Ix2>,
+    c_data: &ArrayBase<impl Data<Elem = f64>, Ix2>,
+    n_obj: usize,
+    cstr_tol: &Array1<f64>,
+) -> Vec<usize> {
+    // y rows starting from the last objective are [obj_nobj, cstr_1, ...]
+    // as expected by the feasibility check
+    let feasible: Vec<usize> = (0..y_data.nrows())
+        .filter(|&i| {
+            is_feasible(
+                &y_data.slice(s![i, (n_obj - 1)..]),
+                &c_data.row(i),
+                cstr_tol,
+            )
+        })
+        .collect();
+    let objs = y_data.slice(s![.., ..n_obj]).select(Axis(0), &feasible);
+    pareto_front_indices(&objs)
+        .into_iter()
+        .map(|i| feasible[i])
+        .collect()
+}
+
+/// Decomposition of the region bounded by `ref_point` and not dominated by the `front` points
+/// as a set of disjoint boxes given by their lower and upper bounds (nboxes, n_obj).
+/// Lower bounds may be infinite (-inf).
+///
+/// The number of boxes grows as `(n + 1)^(n_obj - 1)` where `n` is the number of points.
+pub fn non_dominated_boxes(
+    front: &ArrayBase<impl Data<Elem = f64>, Ix2>,
+    ref_point: &ArrayBase<impl Data<Elem = f64>, Ix1>,
+) -> (Array2<f64>, Array2<f64>) {
+    let n_obj = ref_point.len();
+    // Only points strictly dominating the reference point are relevant
+    let points: Vec<ArrayView1<f64>> = front
+        .rows()
+        .into_iter()
+        .filter(|p| zip(p, ref_point).all(|(v, r)| v < r))
+        .collect();
+
+    // Grid breakpoints [-inf, sorted values, ref] for the n_obj - 1 first objectives
+    let breakpoints: Vec<Vec<f64>> = (0..n_obj - 1)
+        .map(|i| {
+            let mut b: Vec<f64> = points.iter().map(|p| p[i]).collect();
+            b.sort_by(|u, v| u.partial_cmp(v).unwrap());
+            b.dedup();
+            let mut bp = vec![f64::NEG_INFINITY];
+            bp.extend(b);
+            bp.push(ref_point[i]);
+            bp
+        })
+        .collect();
+
+    let mut lowers = vec![];
+    let mut uppers = vec![];
+    let mut cell = vec![1; n_obj - 1];
+    'cells: loop {
+        let lower: Vec<f64> = (0..n_obj - 1)
+            .map(|i| breakpoints[i][cell[i] - 1])
+            .collect();
+        let upper: Vec<f64> = (0..n_obj - 1).map(|i| breakpoints[i][cell[i]]).collect();
+        // Column of cells along the last objective: non-dominated below
+        // the best last objective value of points dominating the lower corner
+        let top = points
+            .iter()
+            .filter(|p| zip(p.iter(), &lower).all(|(v, l)| v <= l))
+            .fold(ref_point[n_obj - 1], |acc, p| acc.min(p[n_obj - 1]));
+        if zip(&lower, &upper).all(|(l, u)| l < u) {
+            lowers.extend(lower);
+            lowers.push(f64::NEG_INFINITY);
+            uppers.extend(upper);
+            uppers.push(top);
+        }
+
+        // next cell
+        let mut i = 0;
+        loop {
+            if i == n_obj - 1 {
+                break 'cells;
+            }
+            cell[i] += 1;
+            if cell[i] < breakpoints[i].len() {
+                break;
+            }
+            cell[i] = 1;
+            i += 1;
+        }
+    }
+    let nboxes = lowers.len() / n_obj;
+    (
+        Array2::from_shape_vec((nboxes, n_obj), lowers).unwrap(),
+        Array2::from_shape_vec((nboxes, n_obj), uppers).unwrap(),
+    )
+}
+
+/// Hypervolume of the region dominated by the `front` points (n, n_obj) and bounded by `ref_point`
+pub fn hypervolume(
+    front: &ArrayBase<impl Data<Elem = f64>, Ix2>,
+    ref_point: &ArrayBase<impl Data<Elem = f64>, Ix1>,
+) -> f64 {
+    let points: Vec<usize> = (0..front.nrows())
+        .filter(|&i| zip(front.row(i), ref_point).all(|(v, r)| v < r))
+        .collect();
+    if points.is_empty() {
+        return 0.;
+    }
+    let front = front.select(Axis(0), &points);
+    let ideal = front.map_axis(Axis(0), |c| c.fold(f64::INFINITY, |acc, v| acc.min(*v)));
+    let (lowers, uppers) = non_dominated_boxes(&front, ref_point);
+    let non_dominated: f64 = zip(lowers.rows(), uppers.rows())
+        .map(|(l, u)| {
+            zip(zip(l, u), &ideal)
+                .map(|((l, u), lb)| (u - l.max(*lb)).max(0.))
+                .product::<f64>()
+        })
+        .sum();
+    zip(ref_point, &ideal).map(|(r, l)| r - l).product::<f64>() - non_dominated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+    use ndarray::array;
+
+    #[test]
+    fn test_pareto_front_indices() {
+        let y = array![
+            [1., 5.],
+            [2., 3.],
+            [3., 4.],
+            [4., 1.],
+            [2., 3.],
+            [5., 5.],
+            [1., 6.]
+        ];
+        assert_eq!(pareto_front_indices(&y), vec![0, 1, 3]);
+
+        // third point does not respect the constraint, fourth point violates function constraint
+        let y = array![[1., 5., -1.], [2., 3., -1.], [0., 0., 1.], [0., 1., -1.]];
+        let c = array![[-1.], [-1.], [-1.], [1.]];
+        let cstr_tol = Array1::from_elem(2, 1e-4);
+        assert_eq!(find_pareto_indices(&y, &c, 2, &cstr_tol), vec![0, 1]);
+    }
+
+    #[test]
+    fn test_hypervolume() {
+        let front = array![[1., 3.], [2., 2.], [3., 1.]];
+        assert_abs_diff_eq!(hypervolume(&front, &array![4., 4.]), 6., epsilon = 1e-12);
+        // point outside the reference box is ignored
+        let front = array![[1., 3.], [2., 2.], [3., 1.], [5., 0.]];
+        assert_abs_diff_eq!(hypervolume(&front, &array![4., 4.]), 6., epsilon = 1e-12);
+
+        let front = array![[1., 1., 1.]];
+        assert_abs_diff_eq!(
+            hypervolume(&front, &array![2., 3., 4.]),
+            6.,
+            epsilon = 1e-12
+        );
+        let front = array![[0., 1., 1.], [1., 0., 1.], [1., 1., 0.]];
+        // three boxes of volume 2 in [0, 2]^3 pairwise overlapping in the unit cube [1, 2]^3
+        assert_abs_diff_eq!(
+            hypervolume(&front, &array![2., 2., 2.]),
+            4.,
+            epsilon = 1e-12
+        );
+    }
+}
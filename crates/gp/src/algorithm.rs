@@ -2,6 +2,9 @@ use crate::errors::{GpError, Result};
 use crate::mean_models::*;
 use crate::optimization::{CobylaParams, optimize_params, prepare_multistart};
 use crate::parameters::{GpParams, GpValidParams};
+#[cfg(feature = "persistent")]
+use crate::persistence::{load_model, save_model};
+use crate::sample_paths::GpSamplePaths;
 use crate::utils::{DistanceMatrix, NormalizedData, pairwise_differences};
 use crate::{ThetaTuning, correlation_models::*};
 
@@ -16,9 +19,10 @@ use log::warn;
 use ndarray_linalg::{cholesky::*, eigh::*, qr::*, svd::*, triangular::*};
 
 use linfa_pls::PlsRegression;
-use ndarray::{Array, Array1, Array2, ArrayBase, Axis, Data, Ix1, Ix2, Zip};
+use ndarray::{Array, Array1, Array2, Array3, ArrayBase, Axis, Data, Ix1, Ix2, Zip, s};
 
 use ndarray_rand::RandomExt;
+use ndarray_rand::rand::Rng;
 use ndarray_rand::rand_distr::Normal;
 use ndarray_stats::QuantileExt;
 
@@ -33,6 +37,16 @@ pub const GP_OPTIM_N_START: usize = 10;
 pub const GP_COBYLA_MIN_EVAL: usize = 25;
 pub const GP_COBYLA_MAX_EVAL: usize = 1000;
 
+/// An enumeration of GP available file format
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GpFileFormat {
+    /// Human readable format
+    #[default]
+    Json,
+    /// Binary format
+    Binary,
+}
+
 /// Internal parameters computed Gp during training
 /// used later on in prediction computations
 #[derive(Default, Debug)]
@@ -43,17 +57,17 @@ pub const GP_COBYLA_MAX_EVAL: usize = 1000;
 )]
 pub(crate) struct GpInnerParams<F: Float> {
     /// Gaussian process variance
-    sigma2: F,
+    pub(crate) sigma2: F,
     /// Generalized least-squares regression weights for Universal Kriging or given beta0 for Ordinary Kriging
-    beta: Array2<F>,
+    pub(crate) beta: Array2<F>,
     /// Gaussian Process weights
-    gamma: Array2<F>,
+    pub(crate) gamma: Array2<F>,
     /// Cholesky decomposition of the correlation matrix \[R\]
-    r_chol: Array2<F>,
+    pub(crate) r_chol: Array2<F>,
     /// Solution of the linear equation system : \[R\] x Ft = y
-    ft: Array2<F>,
+    pub(crate) ft: Array2<F>,
     /// R upper triangle matrix of QR decomposition of the matrix Ft
-    ft_qr_r: Array2<F>,
+    pub(crate) ft_qr_r: Array2<F>,
 }
 
 impl<F: Float> Clone for GpInnerParams<F> {
@@ -170,18 +184,18 @@ impl<F: Float> Clone for GpInnerParams<F> {
 )]
 pub struct GaussianProcess<F: Float, Mean: RegressionModel<F>, Corr: CorrelationModel<F>> {
     /// Parameter of the autocorrelation model equal to the inverse of length scale
-    theta: Array1<F>,
+    pub(crate) theta: Array1<F>,
     /// Reduced likelihood value (result from internal optimization)
     /// Maybe used to compare different trained models
-    likelihood: F,
+    pub(crate) likelihood: F,
     /// Gaussian process internal fitted params
-    inner_params: GpInnerParams<F>,
+    pub(crate) inner_params: GpInnerParams<F>,
     /// Weights in case of KPLS dimension reduction coming from PLS regression (orig_dim, kpls_dim)
-    w_star: Array2<F>,
+    pub(crate) w_star: Array2<F>,
     /// Training inputs
-    xt_norm: NormalizedData<F>,
+    pub(crate) xt_norm: NormalizedData<F>,
     /// Training outputs
-    yt_norm: NormalizedData<F>,
+    pub(crate) yt_norm: NormalizedData<F>,
     /// Training dataset (input, output)
     pub(crate) training_data: (Array2<F>, Array1<F>),
     /// Parameters used to fit this model
@@ -297,42 +311,112 @@ impl<F: Float, Mean: RegressionModel<F>, Corr: CorrelationModel<F>> GaussianProc
         let corr = self._compute_correlation(&xnorm);
         let inners = &self.inner_params;
 
-        let corr_t = corr.t().to_owned();
+        let rt = self._solve_r_chol(&corr.t().to_owned());
+        let rhs = inners.ft.t().dot(&rt) - self.params.mean.value(&xnorm).t();
+        let u = self._solve_ft_qr_r_t(&rhs);
+        (rt, u, xnorm)
+    }
+
+    /// Solve `Rc . X = rhs` where `Rc` is the lower cholesky factor of the correlation matrix
+    fn _solve_r_chol(&self, rhs: &Array2<F>) -> Array2<F> {
         #[cfg(feature = "blas")]
-        let rt = inners
+        let res = self
+            .inner_params
             .r_chol
             .to_owned()
             .with_lapack()
-            .solve_triangular(UPLO::Lower, Diag::NonUnit, &corr_t.with_lapack())
+            .solve_triangular(UPLO::Lower, Diag::NonUnit, &rhs.to_owned().with_lapack())
             .unwrap()
             .without_lapack();
         #[cfg(not(feature = "blas"))]
-        let rt = inners
+        let res = self
+            .inner_params
             .r_chol
-            .solve_triangular(&corr_t, UPLO::Lower)
+            .solve_triangular(rhs, UPLO::Lower)
             .unwrap();
+        res
+    }
 
-        let rhs = inners.ft.t().dot(&rt) - self.params.mean.value(&xnorm).t();
+    /// Solve `Qr^t . X = rhs` where `Qr` is the upper triangle matrix of QR decomposition of Ft
+    fn _solve_ft_qr_r_t(&self, rhs: &Array2<F>) -> Array2<F> {
         #[cfg(feature = "blas")]
-        let u = inners
+        let res = self
+            .inner_params
             .ft_qr_r
             .to_owned()
             .t()
             .with_lapack()
-            .solve_triangular(UPLO::Upper, Diag::NonUnit, &rhs.with_lapack())
+            .solve_triangular(UPLO::Upper, Diag::NonUnit, &rhs.to_owned().with_lapack())
             .unwrap()
             .without_lapack();
         #[cfg(not(feature = "blas"))]
-        let u = inners
+        let res = self
+            .inner_params
             .ft_qr_r
             .t()
-            .solve_triangular(&rhs, UPLO::Lower)
+            .solve_triangular(rhs, UPLO::Lower)
             .unwrap();
-        (rt, u, xnorm)
+        res
+    }
+
+    /// Solve `Rc^t . X = rhs` where `Rc` is the lower cholesky factor of the correlation matrix
+    fn _solve_r_chol_t(&self, rhs: &Array2<F>) -> Array2<F> {
+        #[cfg(feature = "blas")]
+        let res = self
+            .inner_params
+            .r_chol
+            .to_owned()
+            .t()
+            .with_lapack()
+            .solve_triangular(UPLO::Upper, Diag::NonUnit, &rhs.to_owned().with_lapack())
+            .unwrap()
+            .without_lapack();
+        #[cfg(not(feature = "blas"))]
+        let res = self
+            .inner_params
+            .r_chol
+            .t()
+            .solve_triangular(rhs, UPLO::Upper)
+            .unwrap();
+        res
+    }
+
+    /// Solve `Qr . X = rhs` where `Qr` is the upper triangle matrix of QR decomposition of Ft
+    fn _solve_ft_qr_r(&self, rhs: &Array2<F>) -> Array2<F> {
+        #[cfg(feature = "blas")]
+        let res = self
+            .inner_params
+            .ft_qr_r
+            .to_owned()
+            .with_lapack()
+            .solve_triangular(UPLO::Upper, Diag::NonUnit, &rhs.to_owned().with_lapack())
+            .unwrap()
+            .without_lapack();
+        #[cfg(not(feature = "blas"))]
+        let res = self
+            .inner_params
+            .ft_qr_r
+            .solve_triangular(rhs, UPLO::Upper)
+            .unwrap();
+        res
+    }
+
+    /// Compute regression weights `beta` and GP weights `gamma` of the kriging predictor
+    /// interpolating the given normalized `targets` (nt, m) at training points
+    pub(crate) fn _compute_kriging_weights(&self, targets: &Array2<F>) -> (Array2<F>, Array2<F>) {
+        let ft = &self.inner_params.ft;
+        let yt = self._solve_r_chol(targets);
+        let beta = self._solve_ft_qr_r(&self._solve_ft_qr_r_t(&ft.t().dot(&yt)));
+        let rho = yt - ft.dot(&beta);
+        let gamma = self._solve_r_chol_t(&rho);
+        (beta, gamma)
     }
 
     /// Compute correlation matrix given x points specified as a (n, nx) matrix
-    fn _compute_correlation(&self, xnorm: &ArrayBase<impl Data<Elem = F>, Ix2>) -> Array2<F> {
+    pub(crate) fn _compute_correlation(
+        &self,
+        xnorm: &ArrayBase<impl Data<Elem = F>, Ix2>,
+    ) -> Array2<F> {
         // Get pairwise componentwise L1-distances to the input training set
         let dx = pairwise_differences(xnorm, &self.xt_norm.data);
         // Compute the correlation function
@@ -372,6 +456,21 @@ impl<F: Float, Mean: RegressionModel<F>, Corr: CorrelationModel<F>> GaussianProc
         sample(x, mean.insert_axis(Axis(1)), cov, n_traj, method)
     }
 
+    /// Draw `n_traj` posterior sample paths as continuous and differentiable functions of x.
+    ///
+    /// The GP prior is approximated with `n_features` random Fourier features
+    /// (see [crate::GP_RFF_N_FEATURES] default) and conditioned on training data with a pathwise update.
+    /// Contrary to [GaussianProcess::sample], paths can then be evaluated
+    /// at any number of points at a linear cost.
+    pub fn sample_paths<R: Rng>(
+        &self,
+        n_traj: usize,
+        n_features: usize,
+        rng: &mut R,
+    ) -> Result<GpSamplePaths<F, Mean, Corr>> {
+        GpSamplePaths::new(self, n_traj, n_features, rng)
+    }
+
     /// Retrieve optimized hyperparameters theta
     pub fn theta(&self) -> &Array1<F> {
         &self.theta
@@ -387,6 +486,24 @@ impl<F: Float, Mean: RegressionModel<F>, Corr: CorrelationModel<F>> GaussianProc
         self.likelihood
     }
 
+    /// Leave-one-out cross-validation errors `y_i - y_pred_{-i}` at training points
+    /// computed in closed form (Dubrule, 1983) without refitting, hyperparameters being kept fixed.
+    ///
+    /// With `Q = R^-1 - R^-1.F.(F^t.R^-1.F)^-1.F^t.R^-1`, the ith error is `(Q.y)_i / Q_ii`.
+    pub fn loo_errors(&self) -> Array1<F> {
+        let inners = &self.inner_params;
+        let n = inners.r_chol.nrows();
+        // Rc^-1 and Qf^t.Rc^-1 where Qf is the Q factor of QR decomposition of Ft
+        let r_chol_inv = self._solve_r_chol(&Array2::eye(n));
+        let u = self._solve_ft_qr_r_t(&inners.ft.t().dot(&r_chol_inv));
+        let q_diag =
+            r_chol_inv.mapv(|v| v * v).sum_axis(Axis(0)) - u.mapv(|v| v * v).sum_axis(Axis(0));
+        // gamma = R^-1.(y - F.beta) = Q.y
+        let mut errors = inners.gamma.column(0).to_owned() / q_diag;
+        errors.mapv_inplace(|v| v * self.yt_norm.std[0]);
+        errors
+    }
+
     /// Retrieve number of PLS components 1 <= n <= x dimension
     pub fn kpls_dim(&self) -> Option<usize> {
         if self.w_star.ncols() < self.xt_norm.ncols() {
@@ -414,23 +531,15 @@ impl<F: Float, Mean: RegressionModel<F>, Corr: CorrelationModel<F>> GaussianProc
         let beta = &self.inner_params.beta;
         let gamma = &self.inner_params.gamma;
 
-        let df_dx_kx = if self.inner_params.beta.nrows() <= 1 + self.xt_norm.data.ncols() {
-            // for constant or linear: df/dx = cst ([0] or [1]) for all x, so takes use x[0] to get the constant
-            let df = self.params.mean.jacobian(&x.row(0));
-            let df_dx = df.t().row(kx).dot(beta);
-            df_dx.broadcast((x.nrows(), 1)).unwrap().to_owned()
-        } else {
-            // for quadratic df/dx really depends on x
-            let mut dfdx = Array2::zeros((x.nrows(), 1));
-            Zip::from(dfdx.rows_mut())
-                .and(xnorm.rows())
-                .for_each(|mut dfxi, xi| {
-                    let df = self.params.mean.jacobian(&xi);
-                    let df_dx = (df.t().row(kx)).dot(beta);
-                    dfxi.assign(&df_dx);
-                });
-            dfdx
-        };
+        // df/dx depends on x for quadratic or custom mean models
+        let mut df_dx_kx = Array2::zeros((x.nrows(), 1));
+        Zip::from(df_dx_kx.rows_mut())
+            .and(xnorm.rows())
+            .for_each(|mut dfxi, xi| {
+                let df = self.params.mean.jacobian(&xi);
+                let df_dx = (df.t().row(kx)).dot(beta);
+                dfxi.assign(&df_dx);
+            });
 
         let nr = x.nrows();
         let nc = self.xt_norm.data.nrows();
@@ -618,7 +727,7 @@ impl<F: Float, Mean: RegressionModel<F>, Corr: CorrelationModel<F>> GaussianProc
 
         let p2 = inv_kr.t().dot(&dr);
 
-        let f_x = self.params.mean.value(x).t().to_owned();
+        let f_x = self.params.mean.value(&xnorm).t().to_owned();
         let f_mean = self.params.mean.value(&self.xt_norm.data).with_lapack();
 
         let rho2 = r_chol
@@ -671,6 +780,138 @@ impl<F: Float, Mean: RegressionModel<F>, Corr: CorrelationModel<F>> GaussianProc
             .for_each(|mut der, x| der.assign(&self.predict_var_gradients_single(&x)));
         derivs
     }
+
+    /// Predict hessian of the output at a given point `x` specified as a (nx,) vector where x has nx components.
+    /// Returns a (nx, nx) matrix containing the second order derivatives of the output at `x`
+    pub fn predict_hessian(&self, x: &ArrayBase<impl Data<Elem = F>, Ix1>) -> Array2<F> {
+        let nx = x.len();
+        let xnorm = (x - &self.xt_norm.mean) / &self.xt_norm.std;
+
+        let beta = &self.inner_params.beta;
+        let gamma = &self.inner_params.gamma;
+
+        // d2f(x)/dx2 . beta + d2r(x, X)/dx2 . gamma
+        let d2f = self.params.mean.hessian(&xnorm);
+        let d2f = d2f.into_shape((beta.nrows(), nx * nx)).unwrap();
+        let d2r = self
+            .params
+            .corr
+            .hessian(&xnorm, &self.xt_norm.data, &self.theta, &self.w_star);
+        let d2r = d2r.into_shape((gamma.nrows(), nx * nx)).unwrap();
+        let hess = (d2f.t().dot(beta) + d2r.t().dot(gamma))
+            .into_shape((nx, nx))
+            .unwrap();
+
+        let x_std = &self.xt_norm.std;
+        let y_std = self.yt_norm.std[0];
+        Array2::from_shape_fn((nx, nx), |(a, b)| {
+            hess[[a, b]] * y_std / (x_std[a] * x_std[b])
+        })
+    }
+
+    /// Predict hessians at a set of points `x` specified as a (n, nx) matrix where x has nx components.
+    /// Returns a (n, nx, nx) array containing the second order derivatives of the output at each x point
+    pub fn predict_hessians(&self, x: &ArrayBase<impl Data<Elem = F>, Ix2>) -> Array3<F> {
+        let mut hess = Array3::<F>::zeros((x.nrows(), x.ncols(), x.ncols()));
+        Zip::from(hess.outer_iter_mut())
+            .and(x.rows())
+            .for_each(|mut h, xi| h.assign(&self.predict_hessian(&xi)));
+        hess
+    }
+
+    /// Predict hessian of the variance at a point `x` specified as a (nx,) vector where x has nx components.
+    /// Returns a (nx, nx) matrix containing the second order derivatives of the variance at `x`
+    pub fn predict_var_hessian(&self, x: &ArrayBase<impl Data<Elem = F>, Ix1>) -> Array2<F> {
+        let nx = x.len();
+        let nt = self.xt_norm.data.nrows();
+        let xnorm = (x - &self.xt_norm.mean) / &self.xt_norm.std;
+        let inners = &self.inner_params;
+
+        // Stack r(x, X), dr(x, X)/dx and d2r(x, X)/dx2 columnwise to solve linear systems at once
+        let r = self._compute_correlation(&xnorm.view().insert_axis(Axis(0)));
+        let dr = self
+            .params
+            .corr
+            .jacobian(&xnorm, &self.xt_norm.data, &self.theta, &self.w_star);
+        let d2r = self
+            .params
+            .corr
+            .hessian(&xnorm, &self.xt_norm.data, &self.theta, &self.w_star);
+        let mut rs = Array2::zeros((nt, 1 + nx + nx * nx));
+        rs.column_mut(0).assign(&r.row(0));
+        rs.slice_mut(s![.., 1..nx + 1]).assign(&dr);
+        rs.slice_mut(s![.., nx + 1..])
+            .assign(&d2r.into_shape((nt, nx * nx)).unwrap());
+
+        // Same with f(x), df(x)/dx and d2f(x)/dx2
+        let f = self.params.mean.value(&xnorm.view().insert_axis(Axis(0)));
+        let df = self.params.mean.jacobian(&xnorm);
+        let d2f = self.params.mean.hessian(&xnorm);
+        let np = f.ncols();
+        let mut fs = Array2::zeros((np, 1 + nx + nx * nx));
+        fs.column_mut(0).assign(&f.row(0));
+        fs.slice_mut(s![.., 1..nx + 1]).assign(&df);
+        fs.slice_mut(s![.., nx + 1..])
+            .assign(&d2f.into_shape((np, nx * nx)).unwrap());
+
+        // var = sigma2 * (1 - rt^t . rt + u^t . u) with rt = Rc^-1 . r and u = Qr^t^-1 . (Ft^t . rt - f)
+        let rt = self._solve_r_chol(&rs);
+        let u = self._solve_ft_qr_r_t(&(inners.ft.t().dot(&rt) - fs));
+
+        // d2var/dxadxb = 2 * sigma2 * (du/dxa . du/dxb + u . d2u/dxadxb - drt/dxa . drt/dxb - rt . d2rt/dxadxb)
+        let (rt0, drt, d2rt) = (
+            rt.column(0),
+            rt.slice(s![.., 1..nx + 1]),
+            rt.slice(s![.., nx + 1..]),
+        );
+        let (u0, du, d2u) = (
+            u.column(0),
+            u.slice(s![.., 1..nx + 1]),
+            u.slice(s![.., nx + 1..]),
+        );
+        let first = du.t().dot(&du) - drt.t().dot(&drt);
+        let second = (d2u.t().dot(&u0) - d2rt.t().dot(&rt0))
+            .into_shape((nx, nx))
+            .unwrap();
+
+        let two = F::cast(2.);
+        let x_std = &self.xt_norm.std;
+        Array2::from_shape_fn((nx, nx), |(a, b)| {
+            two * inners.sigma2 * (first[[a, b]] + second[[a, b]]) / (x_std[a] * x_std[b])
+        })
+    }
+
+    /// Predict variance hessians at a set of points `x` specified as a (n, nx) matrix where x has nx components.
+    /// Returns a (n, nx, nx) array containing the second order derivatives of the variance at each x point
+    pub fn predict_var_hessians(&self, x: &ArrayBase<impl Data<Elem = F>, Ix2>) -> Array3<F> {
+        let mut hess = Array3::<F>::zeros((x.nrows(), x.ncols(), x.ncols()));
+        Zip::from(hess.outer_iter_mut())
+            .and(x.rows())
+            .for_each(|mut h, xi| h.assign(&self.predict_var_hessian(&xi)));
+        hess
+    }
+}
+
+#[cfg(feature = "persistent")]
+impl<F, Mean, Corr> GaussianProcess<F, Mean, Corr>
+where
+    F: Float + Serialize + serde::de::DeserializeOwned,
+    Mean: RegressionModel<F> + Serialize + serde::de::DeserializeOwned,
+    Corr: CorrelationModel<F> + Serialize + serde::de::DeserializeOwned,
+{
+    /// Save GP model in given file, the model is stored with a header
+    /// identifying the file format version, the crate version and the GP kind.
+    pub fn save(&self, path: &str, format: GpFileFormat) -> Result<()> {
+        save_model(self, path, format)
+    }
+
+    /// Load GP model from given file.
+    ///
+    /// Fails with [GpError::LoadFormatError] when the file was saved with an unsupported
+    /// format version or holds a GP with different float type, mean or correlation models.
+    pub fn load(path: &str, format: GpFileFormat) -> Result<Self> {
+        load_model(path, format)
+    }
 }
 
 impl<F, D, Mean, Corr> PredictInplace<ArrayBase<D, Ix2>, Array1<F>>
@@ -810,7 +1051,9 @@ impl<F: Float, Mean: RegressionModel<F>, Corr: CorrelationModel<F>, D: Data<Elem
                 "Warning: multiple x input features have the same value (at least same row twice)."
             );
         }
-        let fx = self.mean().value(&xtrain.data);
+        // Mean model evaluated on normalized inputs
+        let mean = self.mean().normalized(&xtrain.mean, &xtrain.std);
+        let fx = mean.value(&xtrain.data);
 
         let opt_params = match self.theta_tuning() {
             ThetaTuning::Fixed(init) => {
@@ -838,7 +1081,7 @@ impl<F: Float, Mean: RegressionModel<F>, Corr: CorrelationModel<F>, D: Data<Elem
                     }
                     let rxx = self.corr().value(&x_distances.d, &theta, &w_star);
                     match reduced_likelihood(&fx, rxx, &x_distances, &ytrain, self.nugget()) {
-                        Ok(r) => unsafe { -(*(&r.0 as *const F as *const f64)) },
+                        Ok(r) => -f64::cast(r.0),
                         Err(_) => f64::INFINITY,
                     }
                 };
@@ -921,11 +1164,33 @@ impl<F: Float, Mean: RegressionModel<F>, Corr: CorrelationModel<F>, D: Data<Elem
             xt_norm: xtrain,
             yt_norm: ytrain,
             training_data: (x.to_owned(), y.to_owned().remove_axis(Axis(1))),
-            params: self.clone(),
+            params: GpValidParams {
+                mean,
+                ..self.clone()
+            },
         })
     }
 }
 
+/// Correlation matrix of weighted noisy observations: `R + nugget * I + noise_ratio * diag(1/w)`
+pub(crate) fn noisy_correlation_matrix<F: Float>(
+    rxx: &Array2<F>,
+    x_distances: &DistanceMatrix<F>,
+    nugget: F,
+    noise_ratio: F,
+    weights: &Array1<F>,
+) -> Array2<F> {
+    let mut r_mx: Array2<F> = Array2::<F>::eye(x_distances.n_obs).mapv(|v| v + v * nugget);
+    for (i, ij) in x_distances.d_indices.outer_iter().enumerate() {
+        r_mx[[ij[0], ij[1]]] = rxx[[i, 0]];
+        r_mx[[ij[1], ij[0]]] = rxx[[i, 0]];
+    }
+    Zip::from(r_mx.diag_mut())
+        .and(weights)
+        .for_each(|r, w| *r += noise_ratio / *w);
+    r_mx
+}
+
 /// Compute reduced likelihood function
 /// fx: mean factors term at x samples,
 /// rxx: correlation factors at x samples,
@@ -946,6 +1211,15 @@ fn reduced_likelihood<F: Float>(
         r_mx[[ij[0], ij[1]]] = rxx[[i, 0]];
         r_mx[[ij[1], ij[0]]] = rxx[[i, 0]];
     }
+    reduced_likelihood_from_corr(fx, r_mx, ytrain)
+}
+
+#[cfg(not(feature = "blas"))]
+pub(crate) fn reduced_likelihood_from_corr<F: Float>(
+    fx: &ArrayBase<impl Data<Elem = F>, Ix2>,
+    r_mx: Array2<F>,
+    ytrain: &NormalizedData<F>,
+) -> Result<(F, GpInnerParams<F>)> {
     let fxl = fx;
     // R cholesky decomposition
     let r_chol = r_mx.cholesky()?;
@@ -981,7 +1255,7 @@ fn reduced_likelihood<F: Float>(
     let gamma = r_chol.t().solve_triangular_into(rho, UPLO::Upper)?;
     // The determinant of R is equal to the squared product of
     // the diagonal elements of its Cholesky decomposition r_chol
-    let n_obs: F = F::cast(x_distances.n_obs);
+    let n_obs: F = F::cast(ytrain.data.nrows());
 
     let logdet = r_chol.diag().mapv(|v: F| v.log10()).sum() * F::cast(2.) / n_obs;
 
@@ -1018,6 +1292,15 @@ fn reduced_likelihood<F: Float>(
         r_mx[[ij[1], ij[0]]] = rxx[[i, 0]];
     }
 
+    reduced_likelihood_from_corr(fx, r_mx, ytrain)
+}
+
+#[cfg(feature = "blas")]
+pub(crate) fn reduced_likelihood_from_corr<F: Float>(
+    fx: &ArrayBase<impl Data<Elem = F>, Ix2>,
+    r_mx: Array2<F>,
+    ytrain: &NormalizedData<F>,
+) -> Result<(F, GpInnerParams<F>)> {
     let fxl = fx.to_owned().with_lapack();
 
     // R cholesky decomposition
@@ -1065,7 +1348,7 @@ fn reduced_likelihood<F: Float>(
 
     // The determinant of R is equal to the squared product of
     // the diagonal elements of its Cholesky decomposition r_chol
-    let n_obs: F = F::cast(x_distances.n_obs);
+    let n_obs: F = F::cast(ytrain.data.nrows());
 
     let logdet = r_chol
         .to_owned()
@@ -1149,7 +1432,7 @@ mod tests {
     use linfa::prelude::Predict;
     #[cfg(not(feature = "blas"))]
     use linfa_linalg::norm::Norm;
-    use ndarray::{Array, Zip, arr1, arr2, array};
+    use ndarray::{Array, ArrayView1, ArrayView2, Zip, arr1, arr2, array, concatenate};
     #[cfg(feature = "blas")]
     use ndarray_linalg::Norm;
     use ndarray_npy::write_npy;
@@ -1540,6 +1823,58 @@ mod tests {
     test_gp_variance_derivatives!(Linear, Matern52, norm1, 1., 50);
     test_gp_variance_derivatives!(Quadratic, Matern52, sphere, 10., 100);
 
+    macro_rules! test_gp_hessians {
+        ($regr:ident, $corr:ident, $func:ident, $limit:expr_2021, $nt:expr_2021) => {
+            paste! {
+
+                #[test]
+                fn [<test_gp_hessians_ $regr:snake _ $corr:snake>]() {
+                    let mut rng = Xoshiro256Plus::seed_from_u64(42);
+                    let xt = egobox_doe::Lhs::new(&array![[-$limit, $limit], [-$limit, $limit]])
+                        .with_rng(rng.clone())
+                        .sample($nt);
+                    let yt = [<$func>](&xt);
+
+                    let gp = GaussianProcess::<f64, [<$regr Mean>], [<$corr Corr>] >::params(
+                        [<$regr Mean>]::default(),
+                        [<$corr Corr>]::default(),
+                    )
+                    .fit(&Dataset::new(xt, yt))
+                    .expect("GP fitting");
+
+                    for _ in 0..5 {
+                        let x = Array::random_using((2,), Uniform::new(-$limit, $limit), &mut rng);
+                        let hess = gp.predict_hessian(&x);
+                        let var_hess = gp.predict_var_hessian(&x);
+                        println!("hessian at {} = {}", x, hess);
+                        println!("variance hessian at {} = {}", x, var_hess);
+
+                        let e = 1e-5;
+                        for k in 0..2 {
+                            let mut xp = x.to_owned();
+                            xp[k] += e;
+                            let mut xm = x.to_owned();
+                            xm[k] -= e;
+                            let xpm = ndarray::stack![Axis(0), xp, xm];
+                            let grads = gp.predict_gradients(&xpm);
+                            let var_grads = gp.predict_var_gradients(&xpm);
+                            for j in 0..2 {
+                                let fdiff = (grads[[0, j]] - grads[[1, j]]) / (2. * e);
+                                assert_abs_diff_eq!(hess[[j, k]], fdiff, epsilon = 1e-3 * (1. + fdiff.abs()));
+                                let fdiff = (var_grads[[0, j]] - var_grads[[1, j]]) / (2. * e);
+                                assert_abs_diff_eq!(var_hess[[j, k]], fdiff, epsilon = 1e-3 * (1. + fdiff.abs()));
+                            }
+                        }
+                    }
+                }
+            }
+        };
+    }
+
+    test_gp_hessians!(Constant, SquaredExponential, sphere, 10., 8);
+    test_gp_hessians!(Linear, Matern32, sphere, 10., 8);
+    test_gp_hessians!(Quadratic, Matern52, sphere, 10., 8);
+
     #[test]
     fn test_variance_derivatives() {
         let xt = egobox_doe::FullFactorial::new(&array![[-10., 10.], [-10., 10.]]).sample(10);
@@ -1603,6 +1938,76 @@ mod tests {
         assert_abs_diff_eq!(*gp.theta(), expected);
     }
 
+    #[cfg(not(feature = "blas"))]
+    #[test]
+    fn test_loo_errors() {
+        let xt = array![[0.0], [0.7], [1.5], [2.0], [3.1], [3.6], [4.4], [5.0]];
+        let yt = xt.mapv(|v: f64| v * v.sin()).remove_axis(Axis(1));
+        let gp = GaussianProcess::<f64, LinearMean, SquaredExponentialCorr>::params(
+            LinearMean::default(),
+            SquaredExponentialCorr::default(),
+        )
+        .fit(&Dataset::new(xt, yt))
+        .expect("GP fit error");
+        let errors = gp.loo_errors();
+
+        // Brute force leave-one-out kriging predictions with the same correlation matrix
+        let r_chol = &gp.inner_params.r_chol;
+        let r_mx = r_chol.dot(&r_chol.t());
+        let fx = gp.params.mean.value(&gp.xt_norm.data);
+        let y = gp.yt_norm.data.column(0);
+        let n = y.len();
+        for i in 0..n {
+            let idx: Vec<usize> = (0..n).filter(|j| *j != i).collect();
+            let r_inv = r_mx
+                .select(Axis(0), &idx)
+                .select(Axis(1), &idx)
+                .invc()
+                .unwrap();
+            let f = fx.select(Axis(0), &idx);
+            let yi = y.select(Axis(0), &idx);
+            let ft_r_inv = f.t().dot(&r_inv);
+            let beta = ft_r_inv.dot(&f).invc().unwrap().dot(&ft_r_inv.dot(&yi));
+            let r_i = r_mx.row(i).select(Axis(0), &idx);
+            let pred = fx.row(i).dot(&beta) + r_i.dot(&r_inv.dot(&(&yi - &f.dot(&beta))));
+            assert_abs_diff_eq!(errors[i], (y[i] - pred) * gp.yt_norm.std[0], epsilon = 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_custom_mean() {
+        // Low-fidelity model used as trend of the high-fidelity one
+        let lf = |x: &ArrayView2<f64>| x.mapv(|v| (3. * v).sin() + v * v);
+        let mean = CustomMean::new(
+            move |x: &ArrayView2<f64>| concatenate![Axis(1), Array2::ones((x.nrows(), 1)), lf(x)],
+            |x: &ArrayView1<f64>| array![[0.], [3. * (3. * x[0]).cos() + 2. * x[0]]],
+        )
+        .with_hessian(|x: &ArrayView1<f64>| array![[[0.]], [[-9. * (3. * x[0]).sin() + 2.]]])
+        .with_name("LowFidelity");
+        let hf = |x: &Array2<f64>| (2. * lf(&x.view()) + 1.5).remove_axis(Axis(1));
+
+        let xt = Array::linspace(0., 4., 5).insert_axis(Axis(1));
+        let yt = hf(&xt);
+        let gp = GaussianProcess::<f64, CustomMean<f64>, SquaredExponentialCorr>::params(
+            mean,
+            SquaredExponentialCorr::default(),
+        )
+        .fit(&Dataset::new(xt, yt))
+        .expect("GP fit error");
+        assert_eq!("LowFidelity", gp.params.mean.to_string());
+
+        // Trend is recovered far from training points
+        let x = array![[0.3], [1.7], [3.3], [6.]];
+        let y = gp.predict(&x).unwrap();
+        assert_abs_diff_eq!(y, hf(&x), epsilon = 1e-4);
+        let dy = gp.predict_gradients(&x);
+        let expected = x.mapv(|v| 2. * (3. * (3. * v).cos() + 2. * v));
+        assert_abs_diff_eq!(dy, expected, epsilon = 1e-3);
+        let d2y = gp.predict_hessians(&x);
+        let expected = x.mapv(|v| 2. * (-9. * (3. * v).sin() + 2.));
+        assert_abs_diff_eq!(d2y.remove_axis(Axis(2)), expected, epsilon = 1e-2);
+    }
+
     fn x2sinx(x: &Array2<f64>) -> Array1<f64> {
         ((x * x) * (x).mapv(|v| v.sin())).remove_axis(Axis(1))
     }
@@ -0,0 +1,629 @@
+use crate::GaussianProcess;
+use crate::algorithm::{noisy_correlation_matrix, reduced_likelihood_from_corr};
+use crate::bayesian_parameters::{BayesianGpParams, BayesianGpValidParams};
+use crate::correlation_models::*;
+use crate::errors::{GpError, Result};
+use crate::mean_models::*;
+use crate::utils::DistanceMatrix;
+
+use linfa::prelude::{DatasetBase, Fit, Float, PredictInplace};
+use ndarray::{Array1, Array2, Array3, ArrayBase, Axis, Data, Ix1, Ix2, Zip, s};
+use ndarray_rand::rand::{Rng, SeedableRng};
+use ndarray_rand::rand_distr::{Distribution, Gamma};
+use rand_xoshiro::Xoshiro256Plus;
+
+use log::debug;
+#[cfg(feature = "serializable")]
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Initial width (log10 scale) of the slice sampling interval
+const SLICE_WIDTH: f64 = 0.5;
+/// Maximum number of stepping out expansions of the slice sampling interval
+const SLICE_MAX_STEPS: usize = 10;
+
+/// A fully Bayesian GP model where hyperparameters are marginalized out.
+///
+/// Instead of a point estimation of the hyperparameters maximizing the likelihood,
+/// the model draws samples of theta, process variance and optionally noise variance from
+/// their posterior distribution. Predictions come from the resulting mixture of GPs with
+/// the integrated mean `E[mu_s(x)]` and variance `E[sigma_s^2(x)] + Var[mu_s(x)]`.
+///
+/// With small training datasets, the likelihood is flat and point-estimated hyperparameters
+/// lead to overconfident predictions, marginalization gives better-calibrated uncertainties.
+///
+/// # Implementation
+///
+/// * Priors are uniform on log10 of theta components within theta tuning bounds
+///   and on log10 of the noise to process variance ratio within noise bounds.
+/// * Theta and noise ratio are sampled from the concentrated likelihood (reduced likelihood
+///   where the process variance is replaced by its maximum likelihood estimation
+///   `sigma_hat^2`) by coordinatewise slice sampling, starting from the maximum likelihood
+///   estimation.
+/// * Given theta and noise ratio, the process variance is drawn from its conditional posterior
+///   `InvGamma(n/2, n * sigma_hat^2 / 2)` (Jeffreys prior).
+///
+/// # Example
+///
+/// ```no_run
+/// use egobox_gp::{correlation_models::*, mean_models::*, BayesianGaussianProcess};
+/// use linfa::prelude::*;
+/// use ndarray::{array, Array1, Axis};
+///
+/// let xt = array![[0.0], [5.0], [10.0], [15.0], [18.0], [20.0], [25.0]];
+/// let yt = array![1., 2., -3., -8., -9., 2., 0.];
+///
+/// let gp = BayesianGaussianProcess::<f64, ConstantMean, SquaredExponentialCorr>::params(
+///         ConstantMean::default(),
+///         SquaredExponentialCorr::default())
+///     .n_samples(32)
+///     .seed(Some(42))
+///     .fit(&Dataset::new(xt, yt))
+///     .expect("Bayesian GP trained");
+///
+/// let x = Array1::linspace(0., 25., 50).insert_axis(Axis(1));
+/// let ypred = gp.predict(&x);
+/// let yvar = gp.predict_var(&x);
+/// ```
+///
+/// # Reference
+///
+/// Neal, R. M. [Slice sampling](https://doi.org/10.1214/aos/1056562461),
+/// The Annals of Statistics 31(3): 705-767, 2003.
+///
+/// Eriksson, D., Jankowiak, M. [High-dimensional Bayesian optimization with sparse axis-aligned subspaces](https://proceedings.mlr.press/v161/eriksson21a.html),
+/// Proceedings of the 37th Conference on Uncertainty in Artificial Intelligence, PMLR 161:493-503, 2021.
+#[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serializable",
+    derive(Serialize, Deserialize),
+    serde(bound(
+        serialize = "F: Serialize, Mean: Serialize, Corr: Serialize",
+        deserialize = "F: Deserialize<'de>, Mean: Deserialize<'de>, Corr: Deserialize<'de>"
+    ))
+)]
+pub struct BayesianGaussianProcess<F: Float, Mean: RegressionModel<F>, Corr: CorrelationModel<F>> {
+    /// GPs built from posterior samples of hyperparameters
+    gps: Vec<GaussianProcess<F, Mean, Corr>>,
+    /// Posterior samples of the noise to process variance ratio
+    noise_ratios: Array1<F>,
+    /// Posterior mean of theta
+    theta: Array1<F>,
+}
+
+impl<F: Float, Mean: RegressionModel<F>, Corr: CorrelationModel<F>> fmt::Display
+    for BayesianGaussianProcess<F, Mean, Corr>
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "BayesianGP(mean={}, corr={}, n_samples={}, theta={}, variance={}, noise_variance={})",
+            self.gps[0].params.mean,
+            self.gps[0].params.corr,
+            self.gps.len(),
+            self.theta,
+            self.variance(),
+            self.noise_variance()
+        )
+    }
+}
+
+impl<F: Float, Mean: RegressionModel<F>, Corr: CorrelationModel<F>>
+    BayesianGaussianProcess<F, Mean, Corr>
+{
+    /// Fully Bayesian GP parameters contructor
+    pub fn params<NewMean: RegressionModel<F>, NewCorr: CorrelationModel<F>>(
+        mean: NewMean,
+        corr: NewCorr,
+    ) -> BayesianGpParams<F, NewMean, NewCorr> {
+        BayesianGpParams::new(mean, corr)
+    }
+
+    /// Predictions (n_samples, n) of each posterior GP at `x` points
+    fn _predict_samples(&self, x: &ArrayBase<impl Data<Elem = F>, Ix2>) -> Result<Array2<F>> {
+        let mut preds = Array2::zeros((self.gps.len(), x.nrows()));
+        for (mut row, gp) in preds.rows_mut().into_iter().zip(&self.gps) {
+            row.assign(&gp.predict(x)?);
+        }
+        Ok(preds)
+    }
+
+    /// Predict output values at n given `x` points of nx components specified as a (n, nx) matrix.
+    /// Returns n scalar output values as a vector (n,), the mean of the GP mixture.
+    pub fn predict(&self, x: &ArrayBase<impl Data<Elem = F>, Ix2>) -> Result<Array1<F>> {
+        Ok(self._predict_samples(x)?.mean_axis(Axis(0)).unwrap())
+    }
+
+    /// Predict variance values at n given `x` points of nx components specified as a (n, nx) matrix.
+    /// Returns n variance values as (n,) column vector, the variance of the GP mixture.
+    pub fn predict_var(&self, x: &ArrayBase<impl Data<Elem = F>, Ix2>) -> Result<Array1<F>> {
+        let preds = self._predict_samples(x)?;
+        let mut var = Array1::zeros(x.nrows());
+        for gp in &self.gps {
+            var += &gp.predict_var(x)?;
+        }
+        var /= F::cast(self.gps.len());
+        Ok(var + preds.var_axis(Axis(0), F::zero()))
+    }
+
+    /// Predict derivatives at a set of point `x` specified as a (n, nx) matrix where x has nx components.
+    /// Returns a (n, nx) matrix containing output derivatives at x wrt each nx components
+    pub fn predict_gradients(&self, x: &ArrayBase<impl Data<Elem = F>, Ix2>) -> Array2<F> {
+        let mut drv = Array2::zeros(x.dim());
+        for gp in &self.gps {
+            drv += &gp.predict_gradients(x);
+        }
+        drv / F::cast(self.gps.len())
+    }
+
+    /// Predict variance derivatives at a set of points `x` specified as a (n, nx) matrix where x has nx components.
+    /// Returns a (n, nx) matrix containing variance derivatives at `x` wrt each nx components
+    pub fn predict_var_gradients(&self, x: &ArrayBase<impl Data<Elem = F>, Ix2>) -> Array2<F> {
+        let n_samples = F::cast(self.gps.len());
+        let mut mean = Array1::<F>::zeros(x.nrows());
+        let mut mean_drv = Array2::<F>::zeros(x.dim());
+        let mut drv = Array2::<F>::zeros(x.dim());
+        for gp in &self.gps {
+            let pred = gp.predict(x).unwrap();
+            let pred_drv = gp.predict_gradients(x);
+            // d(mu_s^2) = 2 mu_s dmu_s
+            drv += &(&pred_drv * &pred.view().insert_axis(Axis(1)) * F::cast(2.));
+            drv += &gp.predict_var_gradients(x);
+            mean += &pred;
+            mean_drv += &pred_drv;
+        }
+        mean /= n_samples;
+        mean_drv /= n_samples;
+        drv / n_samples - mean_drv * &mean.insert_axis(Axis(1)) * F::cast(2.)
+    }
+
+    /// Predict hessians at a set of points `x` specified as a (n, nx) matrix.
+    /// Returns a (n, nx, nx) array containing output hessians at `x`
+    pub fn predict_hessians(&self, x: &ArrayBase<impl Data<Elem = F>, Ix2>) -> Array3<F> {
+        let mut hess = Array3::zeros((x.nrows(), x.ncols(), x.ncols()));
+        for gp in &self.gps {
+            hess += &gp.predict_hessians(x);
+        }
+        hess / F::cast(self.gps.len())
+    }
+
+    /// Predict variance hessians at a set of points `x` specified as a (n, nx) matrix.
+    /// Returns a (n, nx, nx) array containing variance hessians at `x`
+    pub fn predict_var_hessians(&self, x: &ArrayBase<impl Data<Elem = F>, Ix2>) -> Array3<F> {
+        let n_samples = F::cast(self.gps.len());
+        let outer = |g: &Array2<F>| &g.view().insert_axis(Axis(2)) * &g.view().insert_axis(Axis(1));
+        let scale =
+            |h: &Array3<F>, m: &Array1<F>| h * &m.view().insert_axis(Axis(1)).insert_axis(Axis(2));
+        let mut mean = Array1::<F>::zeros(x.nrows());
+        let mut mean_drv = Array2::<F>::zeros(x.dim());
+        let mut mean_hess = Array3::<F>::zeros((x.nrows(), x.ncols(), x.ncols()));
+        let mut hess = Array3::<F>::zeros((x.nrows(), x.ncols(), x.ncols()));
+        for gp in &self.gps {
+            let pred = gp.predict(x).unwrap();
+            let pred_drv = gp.predict_gradients(x);
+            let pred_hess = gp.predict_hessians(x);
+            // d2(mu_s^2) = 2 (dmu_s dmu_s^T + mu_s d2mu_s)
+            hess += &((outer(&pred_drv) + scale(&pred_hess, &pred)) * F::cast(2.));
+            hess += &gp.predict_var_hessians(x);
+            mean += &pred;
+            mean_drv += &pred_drv;
+            mean_hess += &pred_hess;
+        }
+        mean /= n_samples;
+        mean_drv /= n_samples;
+        mean_hess /= n_samples;
+        hess / n_samples - (outer(&mean_drv) + scale(&mean_hess, &mean)) * F::cast(2.)
+    }
+
+    /// Sample the GP mixture at given `x` points of nx components specified as a (n, nx) matrix.
+    /// Trajectories are drawn in turn from each posterior GP.
+    /// Returns n_traj samples as a (n, n_traj) matrix
+    pub fn sample(&self, x: &ArrayBase<impl Data<Elem = F>, Ix2>, n_traj: usize) -> Array2<F> {
+        let n_gps = self.gps.len();
+        let mut trajs = Array2::zeros((x.nrows(), n_traj));
+        for (k, gp) in self.gps.iter().enumerate().take(n_traj) {
+            let n = (n_traj - k).div_ceil(n_gps);
+            trajs.slice_mut(s![.., k..;n_gps]).assign(&gp.sample(x, n));
+        }
+        trajs
+    }
+
+    /// Posterior mean of hyperparameters theta
+    pub fn theta(&self) -> &Array1<F> {
+        &self.theta
+    }
+
+    /// Posterior mean of the process variance
+    pub fn variance(&self) -> F {
+        self.gps
+            .iter()
+            .fold(F::zero(), |acc, gp| acc + gp.variance())
+            / F::cast(self.gps.len())
+    }
+
+    /// Posterior mean of the noise variance
+    pub fn noise_variance(&self) -> F {
+        Zip::from(&self.noise_ratios)
+            .and(&self.gps)
+            .fold(F::zero(), |acc, r, gp| acc + *r * gp.variance())
+            / F::cast(self.gps.len())
+    }
+
+    /// Mean of the reduced likelihood values of posterior GPs
+    pub fn likelihood(&self) -> F {
+        self.gps
+            .iter()
+            .fold(F::zero(), |acc, gp| acc + gp.likelihood())
+            / F::cast(self.gps.len())
+    }
+
+    /// Posterior samples of theta as a (n_samples, theta dim) matrix
+    pub fn theta_samples(&self) -> Array2<F> {
+        let mut thetas = Array2::zeros((self.gps.len(), self.theta.len()));
+        Zip::from(thetas.rows_mut())
+            .and(&self.gps)
+            .for_each(|mut row, gp| row.assign(gp.theta()));
+        thetas
+    }
+
+    /// Posterior samples of the process variance
+    pub fn variance_samples(&self) -> Array1<F> {
+        self.gps.iter().map(|gp| gp.variance()).collect()
+    }
+
+    /// Posterior samples of the noise variance
+    pub fn noise_variance_samples(&self) -> Array1<F> {
+        Zip::from(&self.noise_ratios)
+            .and(&self.gps)
+            .map_collect(|r, gp| *r * gp.variance())
+    }
+
+    /// GPs built from hyperparameters posterior samples
+    pub fn gps(&self) -> &[GaussianProcess<F, Mean, Corr>] {
+        &self.gps
+    }
+
+    /// Retrieve number of PLS components 1 <= n <= x dimension
+    pub fn kpls_dim(&self) -> Option<usize> {
+        self.gps[0].kpls_dim()
+    }
+
+    /// Retrieve input and output dimensions
+    pub fn dims(&self) -> (usize, usize) {
+        self.gps[0].dims()
+    }
+}
+
+impl<F, D, Mean, Corr> PredictInplace<ArrayBase<D, Ix2>, Array1<F>>
+    for BayesianGaussianProcess<F, Mean, Corr>
+where
+    F: Float,
+    D: Data<Elem = F>,
+    Mean: RegressionModel<F>,
+    Corr: CorrelationModel<F>,
+{
+    fn predict_inplace(&self, x: &ArrayBase<D, Ix2>, y: &mut Array1<F>) {
+        assert_eq!(
+            x.nrows(),
+            y.len(),
+            "The number of data points must match the number of output targets."
+        );
+
+        let values = self.predict(x).expect("Bayesian GP Prediction");
+        *y = values;
+    }
+
+    fn default_target(&self, x: &ArrayBase<D, Ix2>) -> Array1<F> {
+        Array1::zeros((x.nrows(),))
+    }
+}
+
+/// Update in place every coordinate of `p` with univariate slice sampling of the `logpdf`
+/// density restricted to `bounds`, `lp` being `logpdf(p)`. Returns the log density at updated `p`.
+fn slice_sample<R: Rng>(
+    p: &mut [f64],
+    mut lp: f64,
+    bounds: &[(f64, f64)],
+    logpdf: &impl Fn(&[f64]) -> f64,
+    rng: &mut R,
+) -> f64 {
+    for i in 0..p.len() {
+        let (lo, up) = bounds[i];
+        let log_y = lp + rng.r#gen::<f64>().ln();
+        let eval_at = |v: f64| {
+            let mut q = p.to_vec();
+            q[i] = v;
+            logpdf(&q)
+        };
+        // Stepping out
+        let mut left = (p[i] - SLICE_WIDTH * rng.r#gen::<f64>()).max(lo);
+        let mut right = (left + SLICE_WIDTH).min(up);
+        for _ in 0..SLICE_MAX_STEPS {
+            if left <= lo || eval_at(left) <= log_y {
+                break;
+            }
+            left = (left - SLICE_WIDTH).max(lo);
+        }
+        for _ in 0..SLICE_MAX_STEPS {
+            if right >= up || eval_at(right) <= log_y {
+                break;
+            }
+            right = (right + SLICE_WIDTH).min(up);
+        }
+        // Shrinkage
+        loop {
+            let v = left + (right - left) * rng.r#gen::<f64>();
+            let lv = eval_at(v);
+            if lv > log_y {
+                p[i] = v;
+                lp = lv;
+                break;
+            }
+            if v < p[i] {
+                left = v;
+            } else {
+                right = v;
+            }
+            if right - left < 1e-10 {
+                break;
+            }
+        }
+    }
+    lp
+}
+
+impl<F: Float, Mean: RegressionModel<F>, Corr: CorrelationModel<F>, D: Data<Elem = F>>
+    Fit<ArrayBase<D, Ix2>, ArrayBase<D, Ix1>, GpError> for BayesianGpValidParams<F, Mean, Corr>
+{
+    type Object = BayesianGaussianProcess<F, Mean, Corr>;
+
+    /// Sample GP hyperparameters from their posterior distribution
+    fn fit(
+        &self,
+        dataset: &DatasetBase<ArrayBase<D, Ix2>, ArrayBase<D, Ix1>>,
+    ) -> Result<Self::Object> {
+        let gp_params = self.gp_params();
+
+        // Maximum likelihood estimation is used as the starting point of the chain
+        let gp0 = gp_params.fit(dataset)?;
+        let dim = gp0.w_star.ncols();
+        let theta0 = if gp0.theta.len() == 1 {
+            Array1::from_elem(dim, gp0.theta[0])
+        } else {
+            gp0.theta.to_owned()
+        };
+
+        let xtrain = &gp0.xt_norm;
+        let ytrain = &gp0.yt_norm;
+        let w_star = &gp0.w_star;
+        let corr = &gp_params.corr;
+        let nugget = gp_params.nugget;
+        let x_distances = DistanceMatrix::new(&xtrain.data);
+        // gp0 mean model is already normalized
+        let fx = gp0.params.mean.value(&xtrain.data);
+        let n_obs = xtrain.data.nrows();
+        let ones = Array1::<F>::ones(n_obs);
+
+        // Sampled parameters are log10 of active theta components then log10 of noise ratio
+        let (active, bounds): (Vec<_>, Vec<_>) = gp_params
+            .theta_tuning()
+            .active_bounds(dim)?
+            .into_iter()
+            .unzip();
+        let mut bounds = bounds
+            .iter()
+            .map(|(lo, up)| (f64::cast(*lo).log10(), f64::cast(*up).log10()))
+            .collect::<Vec<_>>();
+        let mut p = active
+            .iter()
+            .zip(&bounds)
+            .map(|(&i, (lo, up))| f64::cast(theta0[i]).log10().clamp(*lo, *up))
+            .collect::<Vec<_>>();
+        if let Some((lo, up)) = self.noise_bounds() {
+            let (lo, up) = (f64::cast(*lo).log10(), f64::cast(*up).log10());
+            bounds.push((lo, up));
+            p.push(0.5 * (lo + up));
+        }
+        let noisy = self.noise_bounds().is_some();
+
+        let hyperparameters = |p: &[f64]| {
+            let mut theta = theta0.to_owned();
+            std::iter::zip(&active, p).for_each(|(&i, pi)| theta[i] = F::cast(10f64.powf(*pi)));
+            let noise_ratio = if noisy {
+                F::cast(10f64.powf(p[active.len()]))
+            } else {
+                F::zero()
+            };
+            (theta, noise_ratio)
+        };
+        let build = |theta: &Array1<F>, noise_ratio: F| {
+            let rxx = corr.value(&x_distances.d, theta, w_star);
+            let r_mx = noisy_correlation_matrix(&rxx, &x_distances, nugget, noise_ratio, &ones);
+            reduced_likelihood_from_corr(&fx, r_mx, ytrain)
+        };
+        // Reduced likelihood is -(n.log10(sigma_hat^2) + log10(det R)),
+        // concentrated log-likelihood is -(n.ln(sigma_hat^2) + ln(det R)) / 2
+        let logpdf = |p: &[f64]| -> f64 {
+            if p.iter().zip(&bounds).any(|(v, (lo, up))| v < lo || v > up) {
+                return f64::NEG_INFINITY;
+            }
+            let (theta, noise_ratio) = hyperparameters(p);
+            match build(&theta, noise_ratio) {
+                Ok((lkh, _)) => f64::cast(lkh) * std::f64::consts::LN_10 / 2.,
+                Err(_) => f64::NEG_INFINITY,
+            }
+        };
+
+        let mut rng = match self.seed() {
+            Some(seed) => Xoshiro256Plus::seed_from_u64(*seed),
+            None => Xoshiro256Plus::from_entropy(),
+        };
+        let mut lp = logpdf(&p);
+        if !lp.is_finite() {
+            return Err(GpError::LikelihoodComputationError(
+                "Cannot start hyperparameters sampling from maximum likelihood estimation"
+                    .to_string(),
+            ));
+        }
+        let variance_dist = Gamma::new(0.5 * n_obs as f64, 1.).unwrap();
+        let mut gps = Vec::with_capacity(self.n_samples());
+        let mut noise_ratios = Vec::with_capacity(self.n_samples());
+        let n_iters = self.n_warmup() + self.n_samples() * self.thinning();
+        for iter in 1..=n_iters {
+            if !p.is_empty() {
+                lp = slice_sample(&mut p, lp, &bounds, &logpdf, &mut rng);
+            }
+            if iter <= self.n_warmup() || !(iter - self.n_warmup()).is_multiple_of(self.thinning())
+            {
+                continue;
+            }
+            let (theta, noise_ratio) = hyperparameters(&p);
+            let (likelihood, mut inner_params) = build(&theta, noise_ratio)?;
+            // sigma2 ~ InvGamma(n/2, n * sigma_hat^2 / 2)
+            let g = F::cast(variance_dist.sample(&mut rng));
+            inner_params.sigma2 = inner_params.sigma2 * F::cast(0.5 * n_obs as f64) / g;
+            debug!("Posterior sample theta={theta}, noise_ratio={noise_ratio}, log p={lp}");
+            gps.push(GaussianProcess {
+                theta,
+                likelihood,
+                inner_params,
+                params: gp0.params.clone(),
+                w_star: gp0.w_star.clone(),
+                xt_norm: gp0.xt_norm.clone(),
+                yt_norm: gp0.yt_norm.clone(),
+                training_data: gp0.training_data.clone(),
+            });
+            noise_ratios.push(noise_ratio);
+        }
+
+        let mut theta = Array1::zeros(dim);
+        gps.iter().for_each(|gp| theta += gp.theta());
+        theta /= F::cast(gps.len());
+        Ok(BayesianGaussianProcess {
+            gps,
+            noise_ratios: Array1::from_vec(noise_ratios),
+            theta,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+    use linfa::prelude::Dataset;
+    use ndarray::{Array, array};
+
+    fn xsinx(x: &Array2<f64>) -> Array1<f64> {
+        ((x - 3.5) * ((x - 3.5) / std::f64::consts::PI).mapv(|v| v.sin())).remove_axis(Axis(1))
+    }
+
+    #[test]
+    fn test_bayesian_gp_xsinx() {
+        let xt = array![[0.0], [5.0], [10.0], [15.0], [18.0], [20.0], [25.0]];
+        let yt = xsinx(&xt);
+
+        let gp = GaussianProcess::<f64, ConstantMean, SquaredExponentialCorr>::params(
+            ConstantMean::default(),
+            SquaredExponentialCorr::default(),
+        )
+        .fit(&Dataset::new(xt.clone(), yt.clone()))
+        .expect("GP fitted");
+        let bgp = BayesianGaussianProcess::<f64, ConstantMean, SquaredExponentialCorr>::params(
+            ConstantMean::default(),
+            SquaredExponentialCorr::default(),
+        )
+        .n_samples(20)
+        .seed(Some(42))
+        .fit(&Dataset::new(xt.clone(), yt.clone()))
+        .expect("Bayesian GP fitted");
+        println!("{bgp}");
+        println!("theta samples = {}", bgp.theta_samples());
+
+        assert_eq!(bgp.gps().len(), 20);
+        assert_eq!(bgp.theta_samples().dim(), (20, 1));
+        // Posterior samples are not all equal
+        let thetas = bgp.theta_samples();
+        assert!(thetas.iter().any(|t| (*t - thetas[[0, 0]]).abs() > 1e-6));
+        // Still an interpolating model
+        assert_abs_diff_eq!(bgp.predict(&xt).unwrap(), yt, epsilon = 1e-3);
+        assert_eq!(bgp.noise_variance(), 0.);
+
+        let x = Array::linspace(0., 25., 51).insert_axis(Axis(1));
+        let var = bgp.predict_var(&x).unwrap();
+        assert!(var.iter().all(|v| *v >= 0.));
+        // Marginalization widens uncertainty wrt point estimation somewhere
+        let gp_var = gp.predict_var(&x).unwrap();
+        assert!(var.iter().zip(&gp_var).any(|(v, gv)| *v > *gv));
+    }
+
+    #[test]
+    fn test_bayesian_gp_derivatives() {
+        let xt = array![[0.0], [5.0], [10.0], [15.0], [18.0], [20.0], [25.0]];
+        let yt = xsinx(&xt);
+        let bgp = BayesianGaussianProcess::<f64, ConstantMean, Matern52Corr>::params(
+            ConstantMean::default(),
+            Matern52Corr::default(),
+        )
+        .n_samples(8)
+        .n_warmup(16)
+        .seed(Some(0))
+        .fit(&Dataset::new(xt, yt))
+        .expect("Bayesian GP fitted");
+
+        let x = array![[2.], [7.5], [12.], [21.]];
+        let h = 1e-4;
+        let fd = |f: &dyn Fn(&Array2<f64>) -> Array1<f64>| (f(&(&x + h)) - f(&(&x - h))) / (2. * h);
+
+        let mean_fd = fd(&|x| bgp.predict(x).unwrap());
+        assert_abs_diff_eq!(
+            bgp.predict_gradients(&x).column(0).to_owned(),
+            mean_fd,
+            epsilon = 1e-3
+        );
+        let var_fd = fd(&|x| bgp.predict_var(x).unwrap());
+        assert_abs_diff_eq!(
+            bgp.predict_var_gradients(&x).column(0).to_owned(),
+            var_fd,
+            epsilon = 1e-3
+        );
+        let mean_hess_fd = fd(&|x| bgp.predict_gradients(x).column(0).to_owned());
+        assert_abs_diff_eq!(
+            bgp.predict_hessians(&x).slice(s![.., 0, 0]).to_owned(),
+            mean_hess_fd,
+            epsilon = 1e-3
+        );
+        let var_hess_fd = fd(&|x| bgp.predict_var_gradients(x).column(0).to_owned());
+        assert_abs_diff_eq!(
+            bgp.predict_var_hessians(&x).slice(s![.., 0, 0]).to_owned(),
+            var_hess_fd,
+            epsilon = 1e-3
+        );
+        assert_eq!(bgp.sample(&x, 10).dim(), (4, 10));
+    }
+
+    #[test]
+    fn test_bayesian_gp_noisy() {
+        let xt = Array::linspace(0., 25., 20).insert_axis(Axis(1));
+        let noise = array![
+            0.3, -0.2, 0.1, -0.4, 0.2, 0.0, -0.1, 0.3, -0.3, 0.1, 0.2, -0.2, 0.4, -0.1, 0.0, 0.2,
+            -0.3, 0.1, -0.2, 0.3
+        ];
+        let yt = xsinx(&xt) + noise;
+        let bgp = BayesianGaussianProcess::<f64, ConstantMean, SquaredExponentialCorr>::params(
+            ConstantMean::default(),
+            SquaredExponentialCorr::default(),
+        )
+        .noise_bounds(Some((1e-6, 1.)))
+        .n_samples(16)
+        .seed(Some(42))
+        .fit(&Dataset::new(xt.clone(), yt.clone()))
+        .expect("Bayesian GP fitted");
+        println!("{bgp}");
+
+        assert!(bgp.noise_variance() > 1e-3);
+        assert_eq!(bgp.noise_variance_samples().len(), 16);
+        let x = Array::linspace(0., 25., 51).insert_axis(Axis(1));
+        assert_abs_diff_eq!(bgp.predict(&x).unwrap(), xsinx(&x), epsilon = 1.);
+    }
+}
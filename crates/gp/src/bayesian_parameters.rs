@@ -0,0 +1,229 @@
+use crate::correlation_models::CorrelationModel;
+use crate::errors::{GpError, Result};
+use crate::mean_models::RegressionModel;
+use crate::parameters::GpValidParams;
+use crate::{GP_COBYLA_MAX_EVAL, GP_COBYLA_MIN_EVAL, GP_OPTIM_N_START, ThetaTuning};
+use linfa::{Float, ParamGuard};
+#[cfg(feature = "serializable")]
+use serde::{Deserialize, Serialize};
+
+/// Default number of hyperparameters posterior samples
+pub const GP_MCMC_N_SAMPLES: usize = 32;
+/// Default number of discarded MCMC iterations at the start of the chain
+pub const GP_MCMC_N_WARMUP: usize = 64;
+/// Default number of MCMC iterations between two retained samples
+pub const GP_MCMC_THINNING: usize = 2;
+
+/// A set of validated fully Bayesian GP parameters.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serializable",
+    derive(Serialize, Deserialize),
+    serde(bound(
+        serialize = "F: Serialize, Mean: Serialize, Corr: Serialize",
+        deserialize = "F: Deserialize<'de>, Mean: Deserialize<'de>, Corr: Deserialize<'de>"
+    ))
+)]
+pub struct BayesianGpValidParams<F: Float, Mean: RegressionModel<F>, Corr: CorrelationModel<F>> {
+    /// Underlying GP parameters, the chain starts from the maximum likelihood estimation
+    pub(crate) gp_params: GpValidParams<F, Mean, Corr>,
+    /// Number of retained posterior samples
+    pub(crate) n_samples: usize,
+    /// Number of discarded iterations at the start of the chain
+    pub(crate) n_warmup: usize,
+    /// Number of iterations between two retained samples
+    pub(crate) thinning: usize,
+    /// Bounds of the noise to process variance ratio when noise is sampled
+    pub(crate) noise_bounds: Option<(F, F)>,
+    /// Random generator seed of the sampler
+    pub(crate) seed: Option<u64>,
+}
+
+impl<F: Float, Mean: RegressionModel<F>, Corr: CorrelationModel<F>>
+    BayesianGpValidParams<F, Mean, Corr>
+{
+    /// Get underlying GP parameters
+    pub fn gp_params(&self) -> &GpValidParams<F, Mean, Corr> {
+        &self.gp_params
+    }
+
+    /// Get number of retained posterior samples
+    pub fn n_samples(&self) -> usize {
+        self.n_samples
+    }
+
+    /// Get number of discarded iterations at the start of the chain
+    pub fn n_warmup(&self) -> usize {
+        self.n_warmup
+    }
+
+    /// Get number of iterations between two retained samples
+    pub fn thinning(&self) -> usize {
+        self.thinning
+    }
+
+    /// Get bounds of the noise to process variance ratio if noise is sampled
+    pub fn noise_bounds(&self) -> Option<&(F, F)> {
+        self.noise_bounds.as_ref()
+    }
+
+    /// Get seed
+    pub fn seed(&self) -> Option<&u64> {
+        self.seed.as_ref()
+    }
+}
+
+#[derive(Clone, Debug)]
+/// The set of hyperparameters that can be specified for the execution of
+/// the [fully Bayesian GP algorithm](struct.BayesianGaussianProcess.html).
+pub struct BayesianGpParams<F: Float, Mean: RegressionModel<F>, Corr: CorrelationModel<F>>(
+    BayesianGpValidParams<F, Mean, Corr>,
+);
+
+impl<F: Float, Mean: RegressionModel<F>, Corr: CorrelationModel<F>>
+    BayesianGpParams<F, Mean, Corr>
+{
+    /// A constructor for fully Bayesian GP parameters given mean and correlation models
+    pub fn new(mean: Mean, corr: Corr) -> BayesianGpParams<F, Mean, Corr> {
+        Self(BayesianGpValidParams {
+            gp_params: GpValidParams {
+                theta_tuning: ThetaTuning::default(),
+                mean,
+                corr,
+                kpls_dim: None,
+                n_start: GP_OPTIM_N_START,
+                max_eval: GP_COBYLA_MAX_EVAL,
+                nugget: F::cast(100.0) * F::epsilon(),
+            },
+            n_samples: GP_MCMC_N_SAMPLES,
+            n_warmup: GP_MCMC_N_WARMUP,
+            thinning: GP_MCMC_THINNING,
+            noise_bounds: None,
+            seed: None,
+        })
+    }
+
+    pub fn new_from_valid(params: &BayesianGpValidParams<F, Mean, Corr>) -> Self {
+        Self(params.clone())
+    }
+
+    /// Set the number of retained posterior samples
+    pub fn n_samples(mut self, n_samples: usize) -> Self {
+        self.0.n_samples = n_samples;
+        self
+    }
+
+    /// Set the number of discarded iterations at the start of the chain
+    pub fn n_warmup(mut self, n_warmup: usize) -> Self {
+        self.0.n_warmup = n_warmup;
+        self
+    }
+
+    /// Set the number of iterations between two retained samples
+    pub fn thinning(mut self, thinning: usize) -> Self {
+        self.0.thinning = thinning;
+        self
+    }
+
+    /// Sample noise to process variance ratio within given bounds,
+    /// `None` (default) means interpolating model
+    pub fn noise_bounds(mut self, noise_bounds: Option<(F, F)>) -> Self {
+        self.0.noise_bounds = noise_bounds;
+        self
+    }
+
+    /// Set random generator seed of the sampler
+    pub fn seed(mut self, seed: Option<u64>) -> Self {
+        self.0.seed = seed;
+        self
+    }
+
+    /// Set theta hyper parameter tuning, bounds define the support of the uniform prior
+    /// on log10(theta) and `Fixed` tuning disables theta sampling.
+    pub fn theta_tuning(mut self, theta_tuning: ThetaTuning<F>) -> Self {
+        self.0.gp_params.theta_tuning = theta_tuning;
+        self
+    }
+
+    /// Set the number of PLS components.
+    /// Should be 0 < n < pb size (i.e. x dimension)
+    pub fn kpls_dim(mut self, kpls_dim: Option<usize>) -> Self {
+        self.0.gp_params.kpls_dim = kpls_dim;
+        self
+    }
+
+    /// Set the number of internal GP hyperparameter theta optimization restarts
+    /// used to find the starting point of the chain
+    pub fn n_start(mut self, n_start: usize) -> Self {
+        self.0.gp_params.n_start = n_start;
+        self
+    }
+
+    /// Set the max number of internal likelihood evaluations during one optimization
+    /// Given max_eval has to be greater than [crate::GP_COBYLA_MIN_EVAL] otherwise
+    /// max_eval is set to [crate::GP_COBYLA_MAX_EVAL].
+    pub fn max_eval(mut self, max_eval: usize) -> Self {
+        self.0.gp_params.max_eval = GP_COBYLA_MIN_EVAL.max(max_eval);
+        self
+    }
+
+    /// Set nugget.
+    ///
+    /// Nugget is used to improve numerical stability
+    pub fn nugget(mut self, nugget: F) -> Self {
+        self.0.gp_params.nugget = nugget;
+        self
+    }
+}
+
+impl<F: Float, Mean: RegressionModel<F>, Corr: CorrelationModel<F>>
+    From<BayesianGpValidParams<F, Mean, Corr>> for BayesianGpParams<F, Mean, Corr>
+{
+    fn from(valid: BayesianGpValidParams<F, Mean, Corr>) -> Self {
+        BayesianGpParams(valid.clone())
+    }
+}
+
+impl<F: Float, Mean: RegressionModel<F>, Corr: CorrelationModel<F>> ParamGuard
+    for BayesianGpParams<F, Mean, Corr>
+{
+    type Checked = BayesianGpValidParams<F, Mean, Corr>;
+    type Error = GpError;
+
+    fn check_ref(&self) -> Result<&Self::Checked> {
+        if let Some(d) = self.0.gp_params.kpls_dim {
+            if d == 0 {
+                return Err(GpError::InvalidValueError(
+                    "`kpls_dim` canot be 0!".to_string(),
+                ));
+            }
+            let theta = self.0.gp_params.theta_tuning().init();
+            if theta.len() > 1 && d > theta.len() {
+                return Err(GpError::InvalidValueError(format!(
+                    "Dimension reduction ({}) should be smaller than expected
+                        training input size infered from given initial theta length ({})",
+                    d,
+                    theta.len()
+                )));
+            };
+        }
+        if self.0.n_samples == 0 || self.0.thinning == 0 {
+            return Err(GpError::InvalidValueError(
+                "`n_samples` and `thinning` should be strictly positive".to_string(),
+            ));
+        }
+        if let Some((lower, upper)) = self.0.noise_bounds
+            && (lower <= F::zero() || lower >= upper)
+        {
+            return Err(GpError::InvalidValueError(format!(
+                "Noise bounds should verify 0 < lower ({lower}) < upper ({upper})"
+            )));
+        }
+        Ok(&self.0)
+    }
+
+    fn check(self) -> Result<Self::Checked> {
+        self.check_ref()?;
+        Ok(self.0)
+    }
+}
@@ -0,0 +1,743 @@
+use crate::GaussianProcess;
+use crate::constrained_parameters::{ConstrainedGpParams, ConstrainedGpValidParams, OutputWarping};
+use crate::correlation_models::*;
+use crate::errors::{GpError, Result};
+use crate::mean_models::*;
+use crate::utils::pairwise_differences;
+
+use egobox_doe::{Lhs, SamplingMethod};
+use linfa::prelude::{Dataset, DatasetBase, Fit, Float, PredictInplace};
+use linfa_linalg::{cholesky::*, triangular::*};
+use ndarray::{Array1, Array2, ArrayBase, Axis, Data, Ix1, Ix2, Zip, s, stack};
+use ndarray_rand::rand::SeedableRng;
+use ndarray_stats::QuantileExt;
+use rand_xoshiro::Xoshiro256Plus;
+
+use log::debug;
+#[cfg(feature = "serializable")]
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Tolerance on the derivative sign violation (in normalized units)
+/// below which monotonicity is considered as satisfied
+const MONOTONICITY_TOL: f64 = 1e-3;
+/// Relative nugget of virtual derivative observations to keep the augmented
+/// correlation matrix well-conditioned
+const DERIVATIVE_NUGGET: f64 = 1e-6;
+/// Scale of the probit likelihood of derivative signs, the smaller the stricter
+/// the monotonicity information (value used by Riihimäki & Vehtari)
+const PROBIT_SCALE: f64 = 1e-6;
+/// Maximum number of expectation propagation sweeps over virtual observations
+const EP_MAX_SWEEPS: usize = 100;
+/// Convergence tolerance of expectation propagation site parameters
+const EP_TOL: f64 = 1e-6;
+
+/// A GP model which predictions comply with physical constraints, namely
+/// monotonicity wrt some input components and/or output positivity or boundedness.
+///
+/// # Implementation
+///
+/// * Output range is enforced by warping: a [GaussianProcess] is trained on the warped outputs `z = w(y)`
+///   (see [OutputWarping]) and predictions are mapped back with the inverse transform `y = w^-1(z)`.
+///   The predicted value is the posterior median `w^-1(mu_z)` and the variance is approximated
+///   at first order `(dw^-1/dz)^2 * sigma_z^2`.
+/// * Monotonicity is enforced with virtual derivative sign observations: the derivative sign
+///   is checked on a set of LHS candidate locations spanning the training domain and a virtual
+///   observation is added at the location of the largest violation until the constraint holds
+///   on all candidates or the maximum number of virtual observations is reached.
+///   Each virtual observation states the derivative sign through a probit likelihood, the resulting
+///   non-gaussian posterior of the derivatives at virtual locations is approximated by
+///   expectation propagation (EP), then predictions are made with the GP conditioned on
+///   training outputs and on that derivative posterior.
+///   Contrary to the reference, GP hyperparameters are not re-estimated: they are the ones of the
+///   GP trained without monotonicity information.
+///   The correlation model has to be differentiable twice, hence absolute exponential is rejected.
+///
+/// # Example
+///
+/// ```no_run
+/// use egobox_gp::{correlation_models::*, mean_models::*, ConstrainedGaussianProcess, Monotonicity, OutputWarping};
+/// use linfa::prelude::*;
+/// use ndarray::{array, Array1, Axis};
+///
+/// let xt = array![[0.0], [0.2], [0.4], [0.6], [0.8], [1.0]];
+/// let yt = array![0.1, 0.15, 0.6, 0.62, 0.9, 0.95];
+///
+/// let gp = ConstrainedGaussianProcess::<f64, ConstantMean, SquaredExponentialCorr>::params(
+///         ConstantMean::default(),
+///         SquaredExponentialCorr::default())
+///     .monotonic(0, Monotonicity::Increasing)
+///     .warping(OutputWarping::Bounded { lower: 0., upper: 1. })
+///     .seed(Some(42))
+///     .fit(&Dataset::new(xt, yt))
+///     .expect("Constrained GP trained");
+///
+/// let ypred = gp.predict(&Array1::linspace(0., 1., 50).insert_axis(Axis(1)));
+/// ```
+///
+/// # Reference
+///
+/// Riihimäki, J., Vehtari, A. [Gaussian processes with monotonicity information](http://proceedings.mlr.press/v9/riihimaki10a/riihimaki10a.pdf),
+/// Proceedings of the Thirteenth International Conference on Artificial Intelligence and Statistics, PMLR 9:645-652, 2010.
+#[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serializable",
+    derive(Serialize, Deserialize),
+    serde(bound(
+        serialize = "F: Serialize, Mean: Serialize, Corr: Serialize",
+        deserialize = "F: Deserialize<'de>, Mean: Deserialize<'de>, Corr: Deserialize<'de>"
+    ))
+)]
+pub struct ConstrainedGaussianProcess<F: Float, Mean: RegressionModel<F>, Corr: CorrelationModel<F>>
+{
+    /// GP trained on warped outputs, its hyperparameters are kept fixed during conditioning
+    gp: GaussianProcess<F, Mean, Corr>,
+    /// Output warping
+    warping: OutputWarping<F>,
+    /// Virtual derivative observation locations (normalized inputs)
+    xv_norm: Array2<F>,
+    /// Input component of each virtual derivative observation
+    xv_dims: Vec<usize>,
+    /// Expected derivative sign of each virtual derivative observation
+    xv_signs: Vec<F>,
+    /// EP posterior covariance of latent derivatives at virtual locations
+    dv_cov: Array2<F>,
+    /// Cholesky decomposition of the correlation matrix augmented with virtual observations
+    k_chol: Array2<F>,
+    /// Weights of the augmented predictor: K^-1 (observations - trend)
+    alpha: Array1<F>,
+    /// Augmented regression matrix solved with K cholesky factor
+    ft: Array2<F>,
+    /// Cholesky decomposition of ft^t . ft
+    g_chol: Array2<F>,
+}
+
+impl<F: Float, Mean: RegressionModel<F>, Corr: CorrelationModel<F>> fmt::Display
+    for ConstrainedGaussianProcess<F, Mean, Corr>
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "ConstrainedGP(gp={}, warping={:?}, virtual_points={})",
+            self.gp,
+            self.warping,
+            self.xv_dims.len()
+        )
+    }
+}
+
+impl<F: Float, Mean: RegressionModel<F>, Corr: CorrelationModel<F>>
+    ConstrainedGaussianProcess<F, Mean, Corr>
+{
+    /// Constrained GP parameters contructor
+    pub fn params<NewMean: RegressionModel<F>, NewCorr: CorrelationModel<F>>(
+        mean: NewMean,
+        corr: NewCorr,
+    ) -> ConstrainedGpParams<F, NewMean, NewCorr> {
+        ConstrainedGpParams::new(mean, corr)
+    }
+
+    /// Condition given `gp` on virtual derivative sign observations located at `xv_norm` (normalized)
+    /// wrt the `xv_dims` input components, `xv_signs` being the expected derivative signs.
+    fn condition(
+        gp: GaussianProcess<F, Mean, Corr>,
+        warping: OutputWarping<F>,
+        xv_norm: Array2<F>,
+        xv_dims: Vec<usize>,
+        xv_signs: Vec<F>,
+    ) -> Result<Self> {
+        let xt = &gp.xt_norm.data;
+        let (n, m) = (xt.nrows(), xv_norm.nrows());
+        let corr = &gp.params.corr;
+        let nugget = gp.params.nugget;
+        // Process variance of normalized outputs
+        let sigma2 = gp.inner_params.sigma2 / (gp.yt_norm.std[0] * gp.yt_norm.std[0]);
+
+        // Augmented correlation matrix [[R, dR], [dR^t, d2R]]
+        let mut k = Array2::<F>::zeros((n + m, n + m));
+        let r = corr
+            .value(&pairwise_differences(xt, xt), &gp.theta, &gp.w_star)
+            .into_shape((n, n))
+            .unwrap();
+        k.slice_mut(s![..n, ..n]).assign(&r);
+        for (v, (xv, &j)) in xv_norm.rows().into_iter().zip(&xv_dims).enumerate() {
+            let dr = corr.jacobian(&xv, xt, &gp.theta, &gp.w_star);
+            k.slice_mut(s![..n, n + v]).assign(&dr.column(j));
+            k.slice_mut(s![n + v, ..n]).assign(&dr.column(j));
+            let d2r = corr.hessian(&xv, &xv_norm, &gp.theta, &gp.w_star);
+            Zip::indexed(k.slice_mut(s![n + v, n..])).for_each(|w, kvw| {
+                *kvw = -d2r[[w, j, xv_dims[w]]];
+            });
+        }
+        k.slice_mut(s![..n, ..n])
+            .diag_mut()
+            .mapv_inplace(|v| v * (F::one() + nugget));
+        k.slice_mut(s![n.., n..])
+            .diag_mut()
+            .mapv_inplace(|v| v * (F::one() + F::cast(DERIVATIVE_NUGGET)));
+
+        // Trend at training points and trend derivatives at virtual locations
+        let beta = gp.inner_params.beta.column(0);
+        let mean = &gp.params.mean;
+        let f = mean.value(xt);
+        let mut f_aug = Array2::<F>::zeros((n + m, f.ncols()));
+        f_aug.slice_mut(s![..n, ..]).assign(&f);
+        Zip::from(f_aug.slice_mut(s![n.., ..]).rows_mut())
+            .and(xv_norm.rows())
+            .and(&xv_dims)
+            .for_each(|mut fv, xv, &j| fv.assign(&mean.jacobian(&xv).column(j)));
+        let trend = f_aug.dot(&beta);
+        let yres = &gp.yt_norm.data.column(0) - &trend.slice(s![..n]);
+
+        // Posterior of latent derivatives at virtual locations given training outputs
+        let r_chol = k.slice(s![..n, ..n]).cholesky()?;
+        let v = r_chol.solve_triangular(&k.slice(s![..n, n..]), UPLO::Lower)?;
+        let yt = r_chol.solve_triangular(&yres.to_owned().insert_axis(Axis(1)), UPLO::Lower)?;
+        let mu_p = &trend.slice(s![n..]) + &v.t().dot(&yt.column(0));
+        let sigma_p = (&k.slice(s![n.., n..]) - &v.t().dot(&v)).mapv(|c| c * sigma2);
+        // Account for derivative signs
+        let (mu_d, dv_cov) = ep_derivative_posterior(&mu_p, &sigma_p, &xv_signs)?;
+
+        // Residuals of observations (outputs then expected derivatives) wrt the trend
+        let mut res = Array1::<F>::zeros(n + m);
+        res.slice_mut(s![..n]).assign(&yres);
+        res.slice_mut(s![n..])
+            .assign(&(&mu_d - &trend.slice(s![n..])));
+
+        let k_chol = k.cholesky()?;
+        let res = res.insert_axis(Axis(1));
+        let alpha = k_chol
+            .t()
+            .solve_triangular(&k_chol.solve_triangular(&res, UPLO::Lower)?, UPLO::Upper)?
+            .remove_axis(Axis(1));
+        let ft = k_chol.solve_triangular(&f_aug, UPLO::Lower)?;
+        let g_chol = ft.t().dot(&ft).cholesky()?;
+
+        Ok(ConstrainedGaussianProcess {
+            gp,
+            warping,
+            xv_norm,
+            xv_dims,
+            xv_signs,
+            dv_cov,
+            k_chol,
+            alpha,
+            ft,
+            g_chol,
+        })
+    }
+
+    /// Cross correlations (n, ntrain + nvirtual) between normalized `xnorm` points
+    /// and training outputs then virtual derivative observations
+    fn _compute_cross_correlation(&self, xnorm: &ArrayBase<impl Data<Elem = F>, Ix2>) -> Array2<F> {
+        let xt = &self.gp.xt_norm.data;
+        let n = xt.nrows();
+        let mut kx = Array2::<F>::zeros((xnorm.nrows(), n + self.xv_dims.len()));
+        let r = self
+            .gp
+            .params
+            .corr
+            .value(
+                &pairwise_differences(xnorm, xt),
+                &self.gp.theta,
+                &self.gp.w_star,
+            )
+            .into_shape((xnorm.nrows(), n))
+            .unwrap();
+        kx.slice_mut(s![.., ..n]).assign(&r);
+        for (v, (xv, &j)) in self
+            .xv_norm
+            .rows()
+            .into_iter()
+            .zip(&self.xv_dims)
+            .enumerate()
+        {
+            let dr = self
+                .gp
+                .params
+                .corr
+                .jacobian(&xv, xnorm, &self.gp.theta, &self.gp.w_star);
+            kx.column_mut(n + v).assign(&dr.column(j));
+        }
+        kx
+    }
+
+    /// Predict latent mean (normalized) at normalized `xnorm` points
+    fn _predict_latent(&self, xnorm: &ArrayBase<impl Data<Elem = F>, Ix2>) -> Array1<F> {
+        let trend = self
+            .gp
+            .params
+            .mean
+            .value(xnorm)
+            .dot(&self.gp.inner_params.beta.column(0));
+        trend + self._compute_cross_correlation(xnorm).dot(&self.alpha)
+    }
+
+    /// Predict latent derivatives (normalized) at a normalized `xnorm` point
+    fn _predict_latent_jacobian(&self, xnorm: &ArrayBase<impl Data<Elem = F>, Ix1>) -> Array1<F> {
+        let gp = &self.gp;
+        let n = gp.xt_norm.data.nrows();
+        let mut drv = gp
+            .params
+            .mean
+            .jacobian(xnorm)
+            .t()
+            .dot(&gp.inner_params.beta.column(0));
+        let dr = gp
+            .params
+            .corr
+            .jacobian(xnorm, &gp.xt_norm.data, &gp.theta, &gp.w_star);
+        drv += &dr.t().dot(&self.alpha.slice(s![..n]));
+        if !self.xv_dims.is_empty() {
+            let d2r = gp
+                .params
+                .corr
+                .hessian(xnorm, &self.xv_norm, &gp.theta, &gp.w_star);
+            Zip::indexed(&mut drv).for_each(|k, drv_k| {
+                for (v, &j) in self.xv_dims.iter().enumerate() {
+                    *drv_k -= d2r[[v, k, j]] * self.alpha[n + v];
+                }
+            });
+        }
+        drv
+    }
+
+    /// Predict output values at n given `x` points of nx components specified as a (n, nx) matrix.
+    /// Returns n scalar output values as a vector (n,).
+    pub fn predict(&self, x: &ArrayBase<impl Data<Elem = F>, Ix2>) -> Result<Array1<F>> {
+        let xnorm = (x - &self.gp.xt_norm.mean) / &self.gp.xt_norm.std;
+        let z = self._predict_latent(&xnorm) * self.gp.yt_norm.std[0] + self.gp.yt_norm.mean[0];
+        Ok(z.mapv(|v| self.warping.unwarp(v)))
+    }
+
+    /// Predict variance values at n given `x` points of nx components specified as a (n, nx) matrix.
+    /// Returns n variance values as (n,) column vector.
+    pub fn predict_var(&self, x: &ArrayBase<impl Data<Elem = F>, Ix2>) -> Result<Array1<F>> {
+        let xnorm = (x - &self.gp.xt_norm.mean) / &self.gp.xt_norm.std;
+        let n = self.gp.xt_norm.data.nrows();
+        let kx = self._compute_cross_correlation(&xnorm);
+        let rt = self
+            .k_chol
+            .solve_triangular(&kx.t().to_owned(), UPLO::Lower)?;
+        // Trend uncertainty as in GaussianProcess::predict_var
+        let rhs = self.ft.t().dot(&rt) - self.gp.params.mean.value(&xnorm).t();
+        let u = self.g_chol.solve_triangular(&rhs, UPLO::Lower)?;
+        // Uncertainty of derivatives at virtual locations
+        let w = self.k_chol.t().solve_triangular(&rt, UPLO::Upper)?;
+        let wd = w.slice(s![n.., ..]);
+        let dv_var = (&wd * &self.dv_cov.dot(&wd)).sum_axis(Axis(0));
+
+        let ystd = self.gp.yt_norm.std[0];
+        let mse = (Array1::<F>::ones(rt.ncols()) - rt.mapv(|v| v * v).sum_axis(Axis(0))
+            + u.mapv(|v| v * v).sum_axis(Axis(0)))
+        .mapv(|v| v * self.gp.inner_params.sigma2)
+            + dv_var.mapv(|v| v * ystd * ystd);
+        let z = self._predict_latent(&xnorm) * self.gp.yt_norm.std[0] + self.gp.yt_norm.mean[0];
+        Ok(Zip::from(&mse)
+            .and(&z)
+            .map_collect(|v, z| v.max(F::zero()) * self.warping.unwarp_derivative(*z).powi(2)))
+    }
+
+    /// Predict derivatives at a set of point `x` specified as a (n, nx) matrix where x has nx components.
+    /// Returns a (n, nx) matrix containing output derivatives at x wrt each nx components
+    pub fn predict_gradients(&self, x: &ArrayBase<impl Data<Elem = F>, Ix2>) -> Array2<F> {
+        let xnorm = (x - &self.gp.xt_norm.mean) / &self.gp.xt_norm.std;
+        let z = self._predict_latent(&xnorm) * self.gp.yt_norm.std[0] + self.gp.yt_norm.mean[0];
+        let mut drv = Array2::<F>::zeros(x.dim());
+        Zip::from(drv.rows_mut())
+            .and(xnorm.rows())
+            .and(&z)
+            .for_each(|mut row, xi, zi| {
+                let dz = self._predict_latent_jacobian(&xi) * self.gp.yt_norm.std[0]
+                    / &self.gp.xt_norm.std;
+                row.assign(&(dz * self.warping.unwarp_derivative(*zi)));
+            });
+        drv
+    }
+
+    /// Underlying GP trained on warped outputs without monotonicity information
+    pub fn gp(&self) -> &GaussianProcess<F, Mean, Corr> {
+        &self.gp
+    }
+
+    /// Output warping
+    pub fn warping(&self) -> &OutputWarping<F> {
+        &self.warping
+    }
+
+    /// Virtual derivative observations as locations (nv, nx) matrix
+    /// and corresponding input component indices
+    pub fn virtual_points(&self) -> (Array2<F>, &[usize]) {
+        (
+            &self.xv_norm * &self.gp.xt_norm.std + &self.gp.xt_norm.mean,
+            &self.xv_dims,
+        )
+    }
+
+    /// Retrieve input and output dimensions
+    pub fn dims(&self) -> (usize, usize) {
+        self.gp.dims()
+    }
+}
+
+/// Moments (mean, variance) of the product of a gaussian `N(mu, var)` with the probit
+/// likelihood `Phi(sign * x / PROBIT_SCALE)` of a derivative sign observation
+fn probit_moments<F: Float>(mu: F, var: F, sign: F) -> (F, F) {
+    let nu = F::cast(PROBIT_SCALE);
+    let s = (nu * nu + var).sqrt();
+    let z = sign * mu / s;
+    let zf = z.to_f64().unwrap();
+    // Ratio pdf(z) / cdf(z) with its asymptotic expansion in the far lower tail
+    let ratio = if zf < -30. {
+        F::cast(-zf - 1. / zf + 2. / (zf * zf * zf))
+    } else {
+        let cdf = 0.5 * libm::erfc(-zf / std::f64::consts::SQRT_2);
+        let pdf = (-0.5 * zf * zf).exp() / (2. * std::f64::consts::PI).sqrt();
+        F::cast(pdf / cdf)
+    };
+    let mu_hat = mu + sign * var * ratio / s;
+    let var_hat = var - var * var * ratio * (z + ratio) / (s * s);
+    (mu_hat, var_hat.max(F::epsilon() * var))
+}
+
+/// Gaussian posterior `N(mu, sigma)` of the prior `N(mu_p, sigma_p)` times EP sites
+/// of precisions `tau` and precision-adjusted means `nu`
+fn ep_posterior<F: Float>(
+    mu_p: &Array1<F>,
+    sigma_p: &Array2<F>,
+    tau: &Array1<F>,
+    nu: &Array1<F>,
+) -> Result<(Array1<F>, Array2<F>)> {
+    // Stable formulation with B = I + S^1/2 . sigma_p . S^1/2 where S = diag(tau)
+    let sq = tau.mapv(|t| t.sqrt());
+    let ssp = sigma_p * &sq.view().insert_axis(Axis(1));
+    let mut b = &ssp * &sq.view().insert_axis(Axis(0));
+    b.diag_mut().mapv_inplace(|v| v + F::one());
+    let b_chol = b.cholesky()?;
+    let v = b_chol.solve_triangular(&ssp, UPLO::Lower)?;
+    let w = b_chol.solve_triangular(&(&sq * mu_p).insert_axis(Axis(1)), UPLO::Lower)?;
+    let sigma = sigma_p - &v.t().dot(&v);
+    let mu = sigma.dot(nu) + mu_p - v.t().dot(&w.column(0));
+    Ok((mu, sigma))
+}
+
+/// Approximate by expectation propagation the posterior of latent derivatives given
+/// their gaussian prior `N(mu_p, sigma_p)` and their expected `signs`.
+/// Returns the mean and covariance of the posterior approximation.
+fn ep_derivative_posterior<F: Float>(
+    mu_p: &Array1<F>,
+    sigma_p: &Array2<F>,
+    signs: &[F],
+) -> Result<(Array1<F>, Array2<F>)> {
+    let m = signs.len();
+    let mut tau = Array1::<F>::zeros(m);
+    let mut nu = Array1::<F>::zeros(m);
+    let (mut mu, mut sigma) = (mu_p.to_owned(), sigma_p.to_owned());
+    for sweep in 0..EP_MAX_SWEEPS {
+        let (tau_old, nu_old) = (tau.clone(), nu.clone());
+        for i in 0..m {
+            // Cavity distribution
+            let tau_c = F::one() / sigma[[i, i]] - tau[i];
+            let nu_c = mu[i] / sigma[[i, i]] - nu[i];
+            if tau_c <= F::zero() {
+                continue;
+            }
+            let (mu_hat, var_hat) = probit_moments(nu_c / tau_c, F::one() / tau_c, signs[i]);
+            let tau_new = (F::one() / var_hat - tau_c).max(F::zero());
+            let nu_new = mu_hat / var_hat - nu_c;
+            let (dtau, dnu) = (tau_new - tau[i], nu_new - nu[i]);
+            tau[i] = tau_new;
+            nu[i] = nu_new;
+            // Rank one update of the posterior
+            let si = sigma.column(i).to_owned();
+            let c = dtau / (F::one() + dtau * sigma[[i, i]]);
+            mu.scaled_add(dnu * (F::one() - c * sigma[[i, i]]) - c * mu[i], &si);
+            let si = si.insert_axis(Axis(1));
+            sigma.scaled_add(-c, &si.dot(&si.t()));
+        }
+        // Recompute the posterior from scratch to avoid loss of precision
+        (mu, sigma) = ep_posterior(mu_p, sigma_p, &tau, &nu)?;
+
+        let tol = F::cast(EP_TOL);
+        let converged = Zip::from(&tau)
+            .and(&tau_old)
+            .and(&nu)
+            .and(&nu_old)
+            .all(|t, to, n, no| {
+                (*t - *to).abs() <= tol * (F::one() + to.abs())
+                    && (*n - *no).abs() <= tol * (F::one() + no.abs())
+            });
+        if converged {
+            debug!("EP converged after {} sweeps", sweep + 1);
+            break;
+        }
+    }
+    Ok((mu, sigma))
+}
+
+impl<F, D, Mean, Corr> PredictInplace<ArrayBase<D, Ix2>, Array1<F>>
+    for ConstrainedGaussianProcess<F, Mean, Corr>
+where
+    F: Float,
+    D: Data<Elem = F>,
+    Mean: RegressionModel<F>,
+    Corr: CorrelationModel<F>,
+{
+    fn predict_inplace(&self, x: &ArrayBase<D, Ix2>, y: &mut Array1<F>) {
+        assert_eq!(
+            x.nrows(),
+            y.len(),
+            "The number of data points must match the number of output targets."
+        );
+
+        let values = self.predict(x).expect("Constrained GP Prediction");
+        *y = values;
+    }
+
+    fn default_target(&self, x: &ArrayBase<D, Ix2>) -> Array1<F> {
+        Array1::zeros((x.nrows(),))
+    }
+}
+
+impl<F: Float, Mean: RegressionModel<F>, Corr: CorrelationModel<F>, D: Data<Elem = F>>
+    Fit<ArrayBase<D, Ix2>, ArrayBase<D, Ix1>, GpError> for ConstrainedGpValidParams<F, Mean, Corr>
+{
+    type Object = ConstrainedGaussianProcess<F, Mean, Corr>;
+
+    /// Fit GP on warped outputs then condition it on virtual derivative observations
+    fn fit(
+        &self,
+        dataset: &DatasetBase<ArrayBase<D, Ix2>, ArrayBase<D, Ix1>>,
+    ) -> Result<Self::Object> {
+        let x = dataset.records();
+        let y = dataset.targets();
+
+        if let Some(yi) = y.iter().find(|yi| !self.warping().contains(**yi)) {
+            return Err(GpError::InvalidValueError(format!(
+                "Training output {yi} is out of the range of output warping {:?}",
+                self.warping()
+            )));
+        }
+        if let Some((d, _)) = self.monotonicity().iter().find(|(d, _)| *d >= x.ncols()) {
+            return Err(GpError::InvalidValueError(format!(
+                "Monotonicity component {d} should be smaller than input dimension {}",
+                x.ncols()
+            )));
+        }
+
+        let z = y.mapv(|yi| self.warping().warp(yi));
+        let gp = self.gp_params().fit(&Dataset::new(x.to_owned(), z))?;
+        let mut cgp = ConstrainedGaussianProcess::condition(
+            gp,
+            self.warping().clone(),
+            Array2::zeros((0, x.ncols())),
+            vec![],
+            vec![],
+        )?;
+        if self.monotonicity().is_empty() {
+            return Ok(cgp);
+        }
+
+        // Candidate locations spanning the training domain
+        let xlimits = stack![
+            Axis(1),
+            x.map_axis(Axis(0), |xc| *xc.min().unwrap()),
+            x.map_axis(Axis(0), |xc| *xc.max().unwrap())
+        ];
+        let rng = match self.seed() {
+            Some(seed) => Xoshiro256Plus::seed_from_u64(*seed),
+            None => Xoshiro256Plus::from_entropy(),
+        };
+        let xc = Lhs::new(&xlimits).with_rng(rng).sample(self.n_candidates());
+        let xc_norm = (&xc - &cgp.gp.xt_norm.mean) / &cgp.gp.xt_norm.std;
+
+        let tol = F::cast(MONOTONICITY_TOL);
+        let mut used: Vec<(usize, usize)> = vec![];
+        while cgp.xv_dims.len() < self.max_virtual_points() {
+            // Find the largest violation of monotonicity constraints over candidates
+            let mut worst: Option<(usize, usize, F, F)> = None;
+            for (i, xi) in xc_norm.rows().into_iter().enumerate() {
+                let drv = cgp._predict_latent_jacobian(&xi);
+                for (d, direction) in self.monotonicity() {
+                    let sign = direction.sign::<F>();
+                    let signed = drv[*d] * sign;
+                    if signed < -tol
+                        && worst.is_none_or(|(_, _, w, _)| signed < w)
+                        && !used.contains(&(i, *d))
+                    {
+                        worst = Some((i, *d, signed, sign));
+                    }
+                }
+            }
+            let Some((i, d, violation, sign)) = worst else {
+                break;
+            };
+            debug!("Add virtual derivative observation wrt x{d} (violation={violation})");
+            used.push((i, d));
+
+            let ConstrainedGaussianProcess {
+                gp,
+                warping,
+                mut xv_norm,
+                mut xv_dims,
+                mut xv_signs,
+                ..
+            } = cgp;
+            xv_norm.push_row(xc_norm.row(i)).unwrap();
+            xv_dims.push(d);
+            xv_signs.push(sign);
+            cgp = ConstrainedGaussianProcess::condition(gp, warping, xv_norm, xv_dims, xv_signs)?;
+        }
+        Ok(cgp)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Monotonicity;
+    use approx::assert_abs_diff_eq;
+    use ndarray::{Array, array};
+
+    fn smooth_step(x: &Array2<f64>) -> Array1<f64> {
+        // Increasing function which unconstrained interpolation overshoots
+        x.column(0).mapv(|v| 1. / (1. + (-40. * (v - 0.5)).exp()))
+    }
+
+    #[test]
+    fn test_constrained_gp_no_constraint() {
+        let xt = array![[0.0], [0.2], [0.4], [0.6], [0.8], [1.0]];
+        let yt = smooth_step(&xt);
+
+        let gp = GaussianProcess::<f64, ConstantMean, SquaredExponentialCorr>::params(
+            ConstantMean::default(),
+            SquaredExponentialCorr::default(),
+        )
+        .fit(&Dataset::new(xt.clone(), yt.clone()))
+        .expect("GP fitted");
+        let cgp = ConstrainedGaussianProcess::<f64, ConstantMean, SquaredExponentialCorr>::params(
+            ConstantMean::default(),
+            SquaredExponentialCorr::default(),
+        )
+        .fit(&Dataset::new(xt, yt))
+        .expect("Constrained GP fitted");
+
+        let x = Array::linspace(0., 1., 21).insert_axis(Axis(1));
+        assert_abs_diff_eq!(
+            gp.predict(&x).unwrap(),
+            cgp.predict(&x).unwrap(),
+            epsilon = 1e-6
+        );
+        assert_abs_diff_eq!(
+            gp.predict_gradients(&x),
+            cgp.predict_gradients(&x),
+            epsilon = 1e-4
+        );
+        assert_abs_diff_eq!(
+            gp.predict_var(&x).unwrap(),
+            cgp.predict_var(&x).unwrap(),
+            epsilon = 1e-8
+        );
+        assert_eq!(cgp.virtual_points().1.len(), 0);
+    }
+
+    #[test]
+    fn test_constrained_gp_monotonic() {
+        let xt = array![[0.0], [0.2], [0.4], [0.45], [0.55], [0.6], [0.8], [1.0]];
+        let yt = smooth_step(&xt);
+
+        let gp = GaussianProcess::<f64, ConstantMean, SquaredExponentialCorr>::params(
+            ConstantMean::default(),
+            SquaredExponentialCorr::default(),
+        )
+        .fit(&Dataset::new(xt.clone(), yt.clone()))
+        .expect("GP fitted");
+        let cgp = ConstrainedGaussianProcess::<f64, ConstantMean, SquaredExponentialCorr>::params(
+            ConstantMean::default(),
+            SquaredExponentialCorr::default(),
+        )
+        .monotonic(0, Monotonicity::Increasing)
+        .seed(Some(42))
+        .fit(&Dataset::new(xt.clone(), yt.clone()))
+        .expect("Constrained GP fitted");
+
+        let x = Array::linspace(0., 1., 101).insert_axis(Axis(1));
+        // Unconstrained model is not monotonic
+        assert!(gp.predict_gradients(&x).iter().any(|d| *d < 0.));
+        assert!(!cgp.virtual_points().1.is_empty());
+        // Constrained model still interpolates and is monotonic
+        assert_abs_diff_eq!(cgp.predict(&xt).unwrap(), yt, epsilon = 1e-3);
+        let ypred = cgp.predict(&x).unwrap();
+        ypred
+            .windows(2)
+            .into_iter()
+            .for_each(|w| assert!(w[1] - w[0] > -1e-3, "{} > {}", w[0], w[1]));
+        // Gradients are consistent with finite differences
+        let h = 1e-4;
+        let fd = (cgp.predict(&(&x + h)).unwrap() - cgp.predict(&(&x - h)).unwrap()) / (2. * h);
+        let drv = cgp.predict_gradients(&x);
+        assert_abs_diff_eq!(drv.column(0), fd, epsilon = 1e-4);
+        // Derivative sign information does not flatten the model where data are increasing
+        // (zero-derivative virtual observations would give dv = 0)
+        let (xv, _) = cgp.virtual_points();
+        let dv = cgp.predict_gradients(&xv);
+        let steep: Vec<f64> =
+            Zip::from(xv.column(0))
+                .and(dv.column(0))
+                .fold(vec![], |mut acc, x, d| {
+                    if 0.3 < *x && *x < 0.7 {
+                        acc.push(*d);
+                    }
+                    acc
+                });
+        assert!(!steep.is_empty());
+        assert!(steep.iter().all(|d| *d > 1e-2), "{steep:?}");
+        // Variance is zero at training points but not at virtual locations
+        assert!(cgp.predict_var(&xv).unwrap().iter().all(|v| *v > 0.));
+    }
+
+    #[test]
+    fn test_constrained_gp_monotonic_absexp() {
+        let xt = array![[0.0], [0.5], [1.0]];
+        let yt = array![0., 0.5, 1.];
+        let err = ConstrainedGaussianProcess::<f64, ConstantMean, AbsoluteExponentialCorr>::params(
+            ConstantMean::default(),
+            AbsoluteExponentialCorr::default(),
+        )
+        .monotonic(0, Monotonicity::Increasing)
+        .fit(&Dataset::new(xt, yt));
+        assert!(matches!(err, Err(GpError::InvalidValueError(_))));
+    }
+
+    #[test]
+    fn test_constrained_gp_bounded() {
+        let xt = array![[0.0], [0.1], [0.3], [0.5], [0.7], [0.9], [1.0]];
+        let yt = array![0.01, 0.02, 0.5, 0.97, 0.99, 0.98, 0.99];
+        let warping = OutputWarping::Bounded {
+            lower: 0.,
+            upper: 1.,
+        };
+
+        let cgp = ConstrainedGaussianProcess::<f64, ConstantMean, Matern52Corr>::params(
+            ConstantMean::default(),
+            Matern52Corr::default(),
+        )
+        .warping(warping.clone())
+        .fit(&Dataset::new(xt.clone(), yt.clone()))
+        .expect("Constrained GP fitted");
+
+        let x = Array::linspace(-0.5, 1.5, 101).insert_axis(Axis(1));
+        let ypred = cgp.predict(&x).unwrap();
+        assert!(ypred.iter().all(|y| warping.contains(*y)));
+        assert_abs_diff_eq!(cgp.predict(&xt).unwrap(), yt, epsilon = 1e-4);
+        assert!(cgp.predict_var(&x).unwrap().iter().all(|v| *v >= 0.));
+
+        let err = ConstrainedGaussianProcess::<f64, ConstantMean, Matern52Corr>::params(
+            ConstantMean::default(),
+            Matern52Corr::default(),
+        )
+        .warping(OutputWarping::Positive)
+        .fit(&Dataset::new(xt, yt - 0.5));
+        assert!(matches!(err, Err(GpError::InvalidValueError(_))));
+    }
+}
@@ -0,0 +1,315 @@
+use crate::correlation_models::CorrelationModel;
+use crate::errors::{GpError, Result};
+use crate::mean_models::RegressionModel;
+use crate::parameters::GpValidParams;
+use crate::{GP_COBYLA_MAX_EVAL, GP_COBYLA_MIN_EVAL, GP_OPTIM_N_START, ThetaTuning};
+use linfa::{Float, ParamGuard};
+#[cfg(feature = "serializable")]
+use serde::{Deserialize, Serialize};
+
+/// Default maximum number of virtual derivative observations
+pub const GP_MAX_VIRTUAL_POINTS: usize = 50;
+/// Default number of candidate locations where monotonicity is checked
+pub const GP_N_MONOTONICITY_CANDIDATES: usize = 200;
+
+/// Monotonicity direction of the output with respect to an input component
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serializable", derive(Serialize, Deserialize))]
+pub enum Monotonicity {
+    /// Output is non-decreasing wrt the input component
+    Increasing,
+    /// Output is non-increasing wrt the input component
+    Decreasing,
+}
+
+impl Monotonicity {
+    /// Sign of the derivative expected along the constrained component
+    pub(crate) fn sign<F: Float>(&self) -> F {
+        match self {
+            Monotonicity::Increasing => F::one(),
+            Monotonicity::Decreasing => -F::one(),
+        }
+    }
+}
+
+/// Output warping used to enforce output range of GP predictions.
+///
+/// The GP is trained on the warped outputs `z = w(y)` and predictions are
+/// mapped back with the inverse transform, hence stay within the specified range.
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serializable", derive(Serialize, Deserialize))]
+pub enum OutputWarping<F: Float> {
+    /// No warping
+    #[default]
+    Identity,
+    /// Positive outputs, `z = ln(y)`
+    Positive,
+    /// Outputs bounded in ]lower, upper[, `z = ln((y - lower) / (upper - y))`
+    Bounded { lower: F, upper: F },
+}
+
+impl<F: Float> OutputWarping<F> {
+    /// Check given output value lies within the warping domain
+    pub fn contains(&self, y: F) -> bool {
+        match self {
+            OutputWarping::Identity => true,
+            OutputWarping::Positive => y > F::zero(),
+            OutputWarping::Bounded { lower, upper } => *lower < y && y < *upper,
+        }
+    }
+
+    /// Transform output value `y` into latent value `z`
+    pub fn warp(&self, y: F) -> F {
+        match self {
+            OutputWarping::Identity => y,
+            OutputWarping::Positive => y.ln(),
+            OutputWarping::Bounded { lower, upper } => ((y - *lower) / (*upper - y)).ln(),
+        }
+    }
+
+    /// Transform latent value `z` back into output value `y`
+    pub fn unwarp(&self, z: F) -> F {
+        match self {
+            OutputWarping::Identity => z,
+            OutputWarping::Positive => z.exp(),
+            OutputWarping::Bounded { lower, upper } => {
+                *lower + (*upper - *lower) / (F::one() + (-z).exp())
+            }
+        }
+    }
+
+    /// Derivative of the inverse transform dy/dz at latent value `z`
+    pub fn unwarp_derivative(&self, z: F) -> F {
+        match self {
+            OutputWarping::Identity => F::one(),
+            OutputWarping::Positive => z.exp(),
+            OutputWarping::Bounded { lower, upper } => {
+                let s = F::one() / (F::one() + (-z).exp());
+                (*upper - *lower) * s * (F::one() - s)
+            }
+        }
+    }
+}
+
+/// A set of validated constrained GP parameters.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serializable",
+    derive(Serialize, Deserialize),
+    serde(bound(
+        serialize = "F: Serialize, Mean: Serialize, Corr: Serialize",
+        deserialize = "F: Deserialize<'de>, Mean: Deserialize<'de>, Corr: Deserialize<'de>"
+    ))
+)]
+pub struct ConstrainedGpValidParams<F: Float, Mean: RegressionModel<F>, Corr: CorrelationModel<F>> {
+    /// Underlying GP parameters
+    pub(crate) gp_params: GpValidParams<F, Mean, Corr>,
+    /// Monotonicity constraints as (input component, direction)
+    pub(crate) monotonicity: Vec<(usize, Monotonicity)>,
+    /// Output warping
+    pub(crate) warping: OutputWarping<F>,
+    /// Maximum number of virtual derivative observations
+    pub(crate) max_virtual_points: usize,
+    /// Number of candidate locations where monotonicity is checked
+    pub(crate) n_candidates: usize,
+    /// Random generator seed used to generate candidate locations
+    pub(crate) seed: Option<u64>,
+}
+
+impl<F: Float, Mean: RegressionModel<F>, Corr: CorrelationModel<F>>
+    ConstrainedGpValidParams<F, Mean, Corr>
+{
+    /// Get underlying GP parameters
+    pub fn gp_params(&self) -> &GpValidParams<F, Mean, Corr> {
+        &self.gp_params
+    }
+
+    /// Get monotonicity constraints
+    pub fn monotonicity(&self) -> &[(usize, Monotonicity)] {
+        &self.monotonicity
+    }
+
+    /// Get output warping
+    pub fn warping(&self) -> &OutputWarping<F> {
+        &self.warping
+    }
+
+    /// Get the maximum number of virtual derivative observations
+    pub fn max_virtual_points(&self) -> usize {
+        self.max_virtual_points
+    }
+
+    /// Get the number of candidate locations where monotonicity is checked
+    pub fn n_candidates(&self) -> usize {
+        self.n_candidates
+    }
+
+    /// Get seed
+    pub fn seed(&self) -> Option<&u64> {
+        self.seed.as_ref()
+    }
+}
+
+#[derive(Clone, Debug)]
+/// The set of hyperparameters that can be specified for the execution of
+/// the [constrained GP algorithm](struct.ConstrainedGaussianProcess.html).
+pub struct ConstrainedGpParams<F: Float, Mean: RegressionModel<F>, Corr: CorrelationModel<F>>(
+    ConstrainedGpValidParams<F, Mean, Corr>,
+);
+
+impl<F: Float, Mean: RegressionModel<F>, Corr: CorrelationModel<F>>
+    ConstrainedGpParams<F, Mean, Corr>
+{
+    /// A constructor for constrained GP parameters given mean and correlation models
+    pub fn new(mean: Mean, corr: Corr) -> ConstrainedGpParams<F, Mean, Corr> {
+        Self(ConstrainedGpValidParams {
+            gp_params: GpValidParams {
+                theta_tuning: ThetaTuning::default(),
+                mean,
+                corr,
+                kpls_dim: None,
+                n_start: GP_OPTIM_N_START,
+                max_eval: GP_COBYLA_MAX_EVAL,
+                nugget: F::cast(100.0) * F::epsilon(),
+            },
+            monotonicity: vec![],
+            warping: OutputWarping::default(),
+            max_virtual_points: GP_MAX_VIRTUAL_POINTS,
+            n_candidates: GP_N_MONOTONICITY_CANDIDATES,
+            seed: None,
+        })
+    }
+
+    pub fn new_from_valid(params: &ConstrainedGpValidParams<F, Mean, Corr>) -> Self {
+        Self(params.clone())
+    }
+
+    /// Set theta hyper parameter tuning
+    pub fn theta_tuning(mut self, theta_tuning: ThetaTuning<F>) -> Self {
+        self.0.gp_params.theta_tuning = theta_tuning;
+        self
+    }
+
+    /// Set the number of PLS components.
+    /// Should be 0 < n < pb size (i.e. x dimension)
+    pub fn kpls_dim(mut self, kpls_dim: Option<usize>) -> Self {
+        self.0.gp_params.kpls_dim = kpls_dim;
+        self
+    }
+
+    /// Set the number of internal GP hyperparameter theta optimization restarts
+    pub fn n_start(mut self, n_start: usize) -> Self {
+        self.0.gp_params.n_start = n_start;
+        self
+    }
+
+    /// Set the max number of internal likelihood evaluations during one optimization
+    /// Given max_eval has to be greater than [crate::GP_COBYLA_MIN_EVAL] otherwise
+    /// max_eval is set to [crate::GP_COBYLA_MAX_EVAL].
+    pub fn max_eval(mut self, max_eval: usize) -> Self {
+        self.0.gp_params.max_eval = GP_COBYLA_MIN_EVAL.max(max_eval);
+        self
+    }
+
+    /// Set nugget.
+    ///
+    /// Nugget is used to improve numerical stability
+    pub fn nugget(mut self, nugget: F) -> Self {
+        self.0.gp_params.nugget = nugget;
+        self
+    }
+
+    /// Require the output to be monotonic wrt the `dim`-th input component.
+    ///
+    /// Calling it again for the same component overrides the previous direction.
+    pub fn monotonic(mut self, dim: usize, direction: Monotonicity) -> Self {
+        self.0.monotonicity.retain(|(d, _)| *d != dim);
+        self.0.monotonicity.push((dim, direction));
+        self
+    }
+
+    /// Set output warping used to enforce positive or bounded predictions
+    pub fn warping(mut self, warping: OutputWarping<F>) -> Self {
+        self.0.warping = warping;
+        self
+    }
+
+    /// Set the maximum number of virtual derivative observations
+    pub fn max_virtual_points(mut self, max_virtual_points: usize) -> Self {
+        self.0.max_virtual_points = max_virtual_points;
+        self
+    }
+
+    /// Set the number of candidate locations where monotonicity is checked
+    pub fn n_candidates(mut self, n_candidates: usize) -> Self {
+        self.0.n_candidates = n_candidates;
+        self
+    }
+
+    /// Set random generator seed used to generate candidate locations
+    pub fn seed(mut self, seed: Option<u64>) -> Self {
+        self.0.seed = seed;
+        self
+    }
+}
+
+impl<F: Float, Mean: RegressionModel<F>, Corr: CorrelationModel<F>>
+    From<ConstrainedGpValidParams<F, Mean, Corr>> for ConstrainedGpParams<F, Mean, Corr>
+{
+    fn from(valid: ConstrainedGpValidParams<F, Mean, Corr>) -> Self {
+        ConstrainedGpParams(valid.clone())
+    }
+}
+
+impl<F: Float, Mean: RegressionModel<F>, Corr: CorrelationModel<F>> ParamGuard
+    for ConstrainedGpParams<F, Mean, Corr>
+{
+    type Checked = ConstrainedGpValidParams<F, Mean, Corr>;
+    type Error = GpError;
+
+    fn check_ref(&self) -> Result<&Self::Checked> {
+        if let Some(d) = self.0.gp_params.kpls_dim {
+            if d == 0 {
+                return Err(GpError::InvalidValueError(
+                    "`kpls_dim` canot be 0!".to_string(),
+                ));
+            }
+            let theta = self.0.gp_params.theta_tuning().init();
+            if theta.len() > 1 && d > theta.len() {
+                return Err(GpError::InvalidValueError(format!(
+                    "Dimension reduction ({}) should be smaller than expected
+                        training input size infered from given initial theta length ({})",
+                    d,
+                    theta.len()
+                )));
+            };
+        }
+        if let OutputWarping::Bounded { lower, upper } = self.0.warping
+            && lower >= upper
+        {
+            return Err(GpError::InvalidValueError(format!(
+                "Output warping lower bound ({lower}) should be smaller than upper bound ({upper})"
+            )));
+        }
+        if !self.0.monotonicity.is_empty()
+            && self.0.gp_params.corr().to_string() == "AbsoluteExponential"
+        {
+            return Err(GpError::InvalidValueError(
+                "Monotonicity requires a twice differentiable correlation model, \
+                 absolute exponential is not"
+                    .to_string(),
+            ));
+        }
+        if !self.0.monotonicity.is_empty() && self.0.n_candidates == 0 {
+            return Err(GpError::InvalidValueError(
+                "`n_candidates` should be strictly positive to check monotonicity".to_string(),
+            ));
+        }
+        Ok(&self.0)
+    }
+
+    fn check(self) -> Result<Self::Checked> {
+        self.check_ref()?;
+        Ok(self.0)
+    }
+}
@@ -8,8 +8,11 @@
 
 use crate::utils::differences;
 use linfa::Float;
-use ndarray::{Array1, Array2, ArrayBase, Axis, Data, Ix1, Ix2, Zip};
+use ndarray::{Array1, Array2, Array3, ArrayBase, Axis, Data, Ix1, Ix2, Zip, s};
 use ndarray_einsum_beta::einsum;
+use ndarray_rand::RandomExt;
+use ndarray_rand::rand::Rng;
+use ndarray_rand::rand_distr::{Cauchy, StandardNormal, StudentT};
 #[cfg(feature = "serializable")]
 use serde::{Deserialize, Serialize};
 use std::convert::TryFrom;
@@ -40,11 +43,46 @@ pub trait CorrelationModel<F: Float>: Clone + Copy + Default + fmt::Display + Sy
         weights: &ArrayBase<impl Data<Elem = F>, Ix2>,
     ) -> Array2<F>;
 
+    /// Compute hessian tensor of `r(x, x')` at given `x` given a set of `xtrain` training samples,
+    /// `theta` parameters, and PLS `weights`.
+    /// Returns a (nt, nx, nx) array where nt is the number of training samples.
+    /// The default implementation uses central finite differences of the jacobian.
+    fn hessian(
+        &self,
+        x: &ArrayBase<impl Data<Elem = F>, Ix1>,
+        xtrain: &ArrayBase<impl Data<Elem = F>, Ix2>,
+        theta: &ArrayBase<impl Data<Elem = F>, Ix1>,
+        weights: &ArrayBase<impl Data<Elem = F>, Ix2>,
+    ) -> Array3<F> {
+        let nx = x.len();
+        let mut hess = Array3::zeros((xtrain.nrows(), nx, nx));
+        let eps = F::epsilon().cbrt();
+        for k in 0..nx {
+            let h = eps * (F::one() + x[k].abs());
+            let mut xp = x.to_owned();
+            xp[k] += h;
+            let mut xm = x.to_owned();
+            xm[k] -= h;
+            let fdiff = (self.jacobian(&xp, xtrain, theta, weights)
+                - self.jacobian(&xm, xtrain, theta, weights))
+            .mapv(|v| v / (F::cast(2.) * h));
+            hess.slice_mut(s![.., .., k]).assign(&fdiff);
+        }
+        hess
+    }
+
     /// Returns the theta influence factors for the correlation model.
     /// See <https://hal.science/hal-03812073v2/document>
     fn theta_influence_factors(&self) -> (F, F) {
         (F::one(), F::one())
     }
+
+    /// Draw `n` frequencies from the spectral density of the one-dimensional correlation
+    /// function with unit theta, used to build random Fourier features of the GP prior.
+    /// Returns `None` when the spectral density of the correlation model is unknown.
+    fn spectral_samples<R: Rng>(&self, _n: usize, _rng: &mut R) -> Option<Array1<F>> {
+        None
+    }
 }
 
 /// Squared exponential correlation models
@@ -111,9 +149,29 @@ impl<F: Float> CorrelationModel<F> for SquaredExponentialCorr {
         d * &dtheta_w * &r
     }
 
+    fn hessian(
+        &self,
+        x: &ArrayBase<impl Data<Elem = F>, Ix1>,
+        xtrain: &ArrayBase<impl Data<Elem = F>, Ix2>,
+        theta: &ArrayBase<impl Data<Elem = F>, Ix1>,
+        weights: &ArrayBase<impl Data<Elem = F>, Ix2>,
+    ) -> Array3<F> {
+        let d = differences(x, xtrain);
+        // exp(-u^2/2) with u = theta_l * weight_j_l * d_j
+        separable_hessian(&d, &(theta * weights), false, |u| {
+            let m = F::exp(F::cast(-0.5) * u * u);
+            (m, -u * m, (u * u - F::one()) * m)
+        })
+    }
+
     fn theta_influence_factors(&self) -> (F, F) {
         (F::cast(0.29), F::cast(1.96))
     }
+
+    /// exp(-u^2/2) is the characteristic function of the standard normal distribution
+    fn spectral_samples<R: Rng>(&self, n: usize, rng: &mut R) -> Option<Array1<F>> {
+        Some(Array1::random_using(n, StandardNormal, rng).mapv(|v: f64| F::cast(v)))
+    }
 }
 
 impl fmt::Display for SquaredExponentialCorr {
@@ -182,9 +240,30 @@ impl<F: Float> CorrelationModel<F> for AbsoluteExponentialCorr {
         &dtheta_w * &r
     }
 
+    fn hessian(
+        &self,
+        x: &ArrayBase<impl Data<Elem = F>, Ix1>,
+        xtrain: &ArrayBase<impl Data<Elem = F>, Ix2>,
+        theta: &ArrayBase<impl Data<Elem = F>, Ix1>,
+        weights: &ArrayBase<impl Data<Elem = F>, Ix2>,
+    ) -> Array3<F> {
+        let d = differences(x, xtrain);
+        // exp(-u) with u = theta_l * |weight_j_l * d_j|
+        separable_hessian(&d, &(theta * &weights.mapv(|v| v.abs())), true, |u| {
+            let m = F::exp(-u);
+            (m, -m, m)
+        })
+    }
+
     fn theta_influence_factors(&self) -> (F, F) {
         (F::cast(0.15), F::cast(3.76))
     }
+
+    /// exp(-|u|) is the characteristic function of the standard Cauchy distribution
+    fn spectral_samples<R: Rng>(&self, n: usize, rng: &mut R) -> Option<Array1<F>> {
+        let cauchy = Cauchy::new(0., 1.).unwrap();
+        Some(Array1::random_using(n, cauchy, rng).mapv(|v: f64| F::cast(v)))
+    }
 }
 
 impl fmt::Display for AbsoluteExponentialCorr {
@@ -325,9 +404,36 @@ impl<F: Float> CorrelationModel<F> for Matern32Corr {
         db + da
     }
 
+    fn hessian(
+        &self,
+        x: &ArrayBase<impl Data<Elem = F>, Ix1>,
+        xtrain: &ArrayBase<impl Data<Elem = F>, Ix2>,
+        theta: &ArrayBase<impl Data<Elem = F>, Ix1>,
+        weights: &ArrayBase<impl Data<Elem = F>, Ix2>,
+    ) -> Array3<F> {
+        let sqrt3 = F::cast(3.).sqrt();
+        let three = F::cast(3.);
+        let d = differences(x, xtrain);
+        // (1 + sqrt(3) * u) exp(-sqrt(3) * u) with u = theta_l * |weight_j_l * d_j|
+        separable_hessian(&d, &(theta * &weights.mapv(|v| v.abs())), true, |u| {
+            let e = F::exp(-sqrt3 * u);
+            (
+                (F::one() + sqrt3 * u) * e,
+                -three * u * e,
+                three * (sqrt3 * u - F::one()) * e,
+            )
+        })
+    }
+
     fn theta_influence_factors(&self) -> (F, F) {
         (F::cast(0.21), F::cast(2.74))
     }
+
+    /// Matern 3/2 spectral density is a Student-t distribution with 3 degrees of freedom
+    fn spectral_samples<R: Rng>(&self, n: usize, rng: &mut R) -> Option<Array1<F>> {
+        let student = StudentT::new(3.).unwrap();
+        Some(Array1::random_using(n, student, rng).mapv(|v: f64| F::cast(v)))
+    }
 }
 
 impl fmt::Display for Matern32Corr {
@@ -474,9 +580,37 @@ impl<F: Float> CorrelationModel<F> for Matern52Corr {
         db + da
     }
 
+    fn hessian(
+        &self,
+        x: &ArrayBase<impl Data<Elem = F>, Ix1>,
+        xtrain: &ArrayBase<impl Data<Elem = F>, Ix2>,
+        theta: &ArrayBase<impl Data<Elem = F>, Ix1>,
+        weights: &ArrayBase<impl Data<Elem = F>, Ix2>,
+    ) -> Array3<F> {
+        let sqrt5 = F::cast(5.).sqrt();
+        let div5_3 = F::cast(5. / 3.);
+        let five = F::cast(5.);
+        let d = differences(x, xtrain);
+        // (1 + sqrt(5) * u + (5./3.) * u^2) exp(-sqrt(5) * u) with u = theta_l * |weight_j_l * d_j|
+        separable_hessian(&d, &(theta * &weights.mapv(|v| v.abs())), true, |u| {
+            let e = F::exp(-sqrt5 * u);
+            (
+                (F::one() + sqrt5 * u + div5_3 * u * u) * e,
+                -div5_3 * u * (F::one() + sqrt5 * u) * e,
+                -div5_3 * (F::one() + sqrt5 * u - five * u * u) * e,
+            )
+        })
+    }
+
     fn theta_influence_factors(&self) -> (F, F) {
         (F::cast(0.23), F::cast(2.44))
     }
+
+    /// Matern 5/2 spectral density is a Student-t distribution with 5 degrees of freedom
+    fn spectral_samples<R: Rng>(&self, n: usize, rng: &mut R) -> Option<Array1<F>> {
+        let student = StudentT::new(5.).unwrap();
+        Some(Array1::random_using(n, student, rng).mapv(|v: f64| F::cast(v)))
+    }
 }
 
 impl fmt::Display for Matern52Corr {
@@ -485,12 +619,86 @@ impl fmt::Display for Matern52Corr {
     }
 }
 
+/// Compute the hessian tensor (nt, nx, nx) of correlation models which can be written
+/// as a product of one dimensional kernels
+///
+///   d    h
+/// prod prod k(u_j_l)  with u_j_l = t_j_l * d_j (or t_j_l * |d_j| when `abs` is true)
+///  j=1  l=1
+///
+/// where `kernel` returns (k(u), k'(u), k''(u)), `d` are the (nt, nx) differences x - xtrain
+/// and `t` the (nx, h) scaled weights.
+fn separable_hessian<F: Float>(
+    d: &Array2<F>,
+    t: &Array2<F>,
+    abs: bool,
+    kernel: impl Fn(F) -> (F, F, F),
+) -> Array3<F> {
+    let (nt, nx) = d.dim();
+    let h = t.ncols();
+    let mut hess = Array3::zeros((nt, nx, nx));
+    Zip::from(hess.outer_iter_mut())
+        .and(d.rows())
+        .for_each(|mut hess_i, d_i| {
+            // One dimensional factors of dimension j and their first and second derivatives wrt d_j
+            let mut k = Array1::<F>::ones(nx);
+            let mut dk = Array1::<F>::zeros(nx);
+            let mut d2k = Array1::<F>::zeros(nx);
+            for j in 0..nx {
+                let (u_sign, u_abs) = if abs {
+                    (d_i[j].signum(), d_i[j].abs())
+                } else {
+                    (F::one(), d_i[j])
+                };
+                let mut m = Array1::<F>::zeros(h);
+                let mut dm = Array1::<F>::zeros(h);
+                let mut d2m = Array1::<F>::zeros(h);
+                for l in 0..h {
+                    let (v, dv, d2v) = kernel(t[[j, l]] * u_abs);
+                    m[l] = v;
+                    dm[l] = t[[j, l]] * u_sign * dv;
+                    d2m[l] = t[[j, l]] * t[[j, l]] * d2v;
+                }
+                k[j] = m.product();
+                for l in 0..h {
+                    let mut prod_l = F::one();
+                    for p in (0..h).filter(|&p| p != l) {
+                        prod_l *= m[p];
+                    }
+                    dk[j] += dm[l] * prod_l;
+                    d2k[j] += d2m[l] * prod_l;
+                    for q in (0..h).filter(|&q| q != l) {
+                        let mut prod_lq = F::one();
+                        for p in (0..h).filter(|&p| p != l && p != q) {
+                            prod_lq *= m[p];
+                        }
+                        d2k[j] += dm[l] * dm[q] * prod_lq;
+                    }
+                }
+            }
+            for a in 0..nx {
+                for b in 0..nx {
+                    let mut prod = F::one();
+                    for j in (0..nx).filter(|&j| j != a && j != b) {
+                        prod *= k[j];
+                    }
+                    hess_i[[a, b]] = if a == b {
+                        d2k[a] * prod
+                    } else {
+                        dk[a] * dk[b] * prod
+                    };
+                }
+            }
+        });
+    hess
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::utils::{DistanceMatrix, NormalizedData};
     use approx::assert_abs_diff_eq;
-    use ndarray::{arr1, array};
+    use ndarray::{arr1, array, s};
     use paste::paste;
 
     #[test]
@@ -614,6 +822,129 @@ mod tests {
     test_correlation!(Matern32, true);
     test_correlation!(Matern52, true);
 
+    macro_rules! test_correlation_hessian {
+        ($corr:ident, $kpls:expr_2021) => {
+            paste! {
+                #[test]
+                fn [<test_corr_ $corr:lower _kpls_ $kpls _hessian>]() {
+                    let x = array![0.3, -0.7];
+                    let xtrain = array![
+                        [-1.2, -0.5],
+                        [-0.6, 0.4],
+                        [0.9, 0.2],
+                        [0.8, -1.3],
+                        [1.5, 1.1]
+                    ];
+                    let (theta, weights) = if $kpls {
+                        (array![0.31059002], array![[-0.02701716], [-0.99963497]])
+                    } else {
+                        (array![0.34599115925909146, 0.32083374253611624],
+                         array![[1., 0.], [0., 1.]])
+                    };
+
+                    let corr = [< $corr Corr >]::default();
+                    let hess = corr.hessian(&x, &xtrain, &theta, &weights);
+
+                    let e = 1e-6;
+                    for k in 0..x.len() {
+                        let mut xp = x.to_owned();
+                        xp[k] += e;
+                        let mut xm = x.to_owned();
+                        xm[k] -= e;
+                        let fdiff = (corr.jacobian(&xp, &xtrain, &theta, &weights)
+                            - corr.jacobian(&xm, &xtrain, &theta, &weights))
+                            .mapv(|v| v / (2. * e));
+                        for i in 0..xtrain.nrows() {
+                            assert_abs_diff_eq!(fdiff.row(i), hess.slice(s![i, .., k]), epsilon = 1e-6);
+                        }
+                    }
+                }
+            }
+        };
+    }
+
+    test_correlation_hessian!(SquaredExponential, false);
+    test_correlation_hessian!(AbsoluteExponential, false);
+    test_correlation_hessian!(Matern32, false);
+    test_correlation_hessian!(Matern52, false);
+    test_correlation_hessian!(SquaredExponential, true);
+    test_correlation_hessian!(AbsoluteExponential, true);
+    test_correlation_hessian!(Matern32, true);
+    test_correlation_hessian!(Matern52, true);
+
+    /// Squared exponential correlation relying on the default finite differences hessian
+    #[derive(Clone, Copy, Default)]
+    struct FdHessianCorr(SquaredExponentialCorr);
+
+    impl fmt::Display for FdHessianCorr {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "FdHessian")
+        }
+    }
+
+    impl CorrelationModel<f64> for FdHessianCorr {
+        fn value(
+            &self,
+            d: &ArrayBase<impl Data<Elem = f64>, Ix2>,
+            theta: &ArrayBase<impl Data<Elem = f64>, Ix1>,
+            weights: &ArrayBase<impl Data<Elem = f64>, Ix2>,
+        ) -> Array2<f64> {
+            self.0.value(d, theta, weights)
+        }
+
+        fn jacobian(
+            &self,
+            x: &ArrayBase<impl Data<Elem = f64>, Ix1>,
+            xtrain: &ArrayBase<impl Data<Elem = f64>, Ix2>,
+            theta: &ArrayBase<impl Data<Elem = f64>, Ix1>,
+            weights: &ArrayBase<impl Data<Elem = f64>, Ix2>,
+        ) -> Array2<f64> {
+            self.0.jacobian(x, xtrain, theta, weights)
+        }
+    }
+
+    #[test]
+    fn test_corr_default_hessian() {
+        let x = array![0.3, -0.7];
+        let xtrain = array![[-1.2, -0.5], [-0.6, 0.4], [0.9, 0.2], [0.8, -1.3]];
+        let theta = array![0.34599115925909146, 0.32083374253611624];
+        let weights = array![[1., 0.], [0., 1.]];
+        assert_abs_diff_eq!(
+            FdHessianCorr::default().hessian(&x, &xtrain, &theta, &weights),
+            SquaredExponentialCorr::default().hessian(&x, &xtrain, &theta, &weights),
+            epsilon = 1e-6
+        );
+    }
+
+    macro_rules! test_correlation_spectral {
+        ($corr:ident) => {
+            paste! {
+                #[test]
+                fn [<test_corr_ $corr:lower _spectral_samples>]() {
+                    use ndarray_rand::rand::SeedableRng;
+                    use rand_xoshiro::Xoshiro256Plus;
+
+                    let mut rng = Xoshiro256Plus::seed_from_u64(42);
+                    let corr = [< $corr Corr >]::default();
+                    let omega: Array1<f64> = corr.spectral_samples(200_000, &mut rng).unwrap();
+
+                    // Correlation is the characteristic function of the spectral density
+                    let d = array![[0.], [0.3], [0.7], [1.5]];
+                    let expected = corr.value(&d, &array![1.], &array![[1.]]);
+                    for (di, ri) in d.column(0).iter().zip(expected.column(0)) {
+                        let r = omega.mapv(|w| (w * di).cos()).mean().unwrap();
+                        assert_abs_diff_eq!(r, ri, epsilon = 1e-2);
+                    }
+                }
+            }
+        };
+    }
+
+    test_correlation_spectral!(SquaredExponential);
+    test_correlation_spectral!(AbsoluteExponential);
+    test_correlation_spectral!(Matern32);
+    test_correlation_spectral!(Matern52);
+
     #[test]
     fn test_matern52_2d() {
         let xt = array![[0., 1.], [2., 3.], [4., 5.]];
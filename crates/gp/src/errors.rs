@@ -27,12 +27,19 @@ pub enum GpError {
     #[cfg(feature = "persistent")]
     #[error("Save error: {0}")]
     SaveError(#[from] serde_json::Error),
+    /// When error during saving in binary format
+    #[cfg(feature = "persistent")]
+    #[error("Save error: {0}")]
+    SaveBinaryError(#[from] bincode::Error),
     /// When error during loading
     #[error("Load IO error")]
     LoadIoError(#[from] std::io::Error),
     /// When error during loading
     #[error("Load error: {0}")]
     LoadError(String),
+    /// When a saved model is not compatible with the model to be loaded
+    #[error("Load format error: {0}")]
+    LoadFormatError(String),
     /// When error dur to a bad value
     #[error("InvalidValue error: {0}")]
     InvalidValueError(String),
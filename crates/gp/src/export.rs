@@ -0,0 +1,731 @@
+//! Export of trained GP models as a self-contained set of coefficients.
+//!
+//! The coefficient format is meant to deploy GP surrogates into codes which can not link
+//! Rust (C, C++, Fortran simulation codes, embedded controllers): it only holds plain numbers
+//! (normalization constants, hyperparameters, PLS weights, regression and GP weights,
+//! training points and factorization matrices) from which the GP prediction and variance are
+//! recomputed with a few loops and triangular solves.
+//!
+//! Given a point `x` with `nx` components, the prediction is computed as follows:
+//!
+//! * `xn = (x - x_mean) / x_std`
+//! * `f = mean_basis(xn)`, the regression basis (`p` values) of the mean model
+//! * `r_i = corr(xn - xt_i)` for each (normalized) training point `xt_i`
+//! * `y = (f . beta + r . gamma) * y_std + y_mean`
+//!
+//! and the variance as follows:
+//!
+//! * `rt` solution of `r_chol . rt = r` (`r_chol` lower triangular)
+//! * `u` solution of `ft_qr_r^T . u = ft^T . rt - f` (`ft_qr_r` upper triangular)
+//! * `var = max(0, sigma2 * (1 - |rt|^2 + |u|^2))`
+//!
+//! [GpCoefficients::predict] and [GpCoefficients::predict_var] are the reference implementation
+//! of these formulas, [GpCoefficients::to_c_source] generates an equivalent standalone C source.
+
+use crate::GaussianProcess;
+use crate::correlation_models::{
+    AbsoluteExponentialCorr, CorrelationModel, Matern32Corr, Matern52Corr, SquaredExponentialCorr,
+};
+use crate::errors::{GpError, Result};
+use crate::mean_models::{ConstantMean, LinearMean, QuadraticMean, RegressionModel};
+
+use linfa::Float;
+use ndarray::{Array1, Array2};
+use std::any::TypeId;
+use std::fmt;
+use std::fmt::Write;
+
+#[cfg(feature = "serializable")]
+use serde::{Deserialize, Serialize};
+
+/// Identifier of the GP coefficients format
+pub const GP_COEFFICIENTS_FORMAT: &str = "egobox-gp-coefficients";
+
+/// Current version of the GP coefficients format
+pub const GP_COEFFICIENTS_VERSION: u32 = 1;
+
+/// Mean models handled by the coefficients format
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serializable", derive(Serialize, Deserialize))]
+pub enum CoefficientsMean {
+    /// `f(x) = [1]`
+    #[cfg_attr(feature = "serializable", serde(rename = "ConstantMean"))]
+    Constant,
+    /// `f(x) = [1, x_1, ..., x_nx]`
+    #[cfg_attr(feature = "serializable", serde(rename = "LinearMean"))]
+    Linear,
+    /// `f(x) = [1, x_1, ..., x_nx, x_1*x_1, x_1*x_2, ..., x_1*x_nx, x_2*x_2, ..., x_nx*x_nx]`
+    #[cfg_attr(feature = "serializable", serde(rename = "QuadraticMean"))]
+    Quadratic,
+}
+
+impl CoefficientsMean {
+    /// Number of regression basis functions for `nx` dimensional inputs
+    pub fn n_basis(&self, nx: usize) -> usize {
+        match self {
+            CoefficientsMean::Constant => 1,
+            CoefficientsMean::Linear => 1 + nx,
+            CoefficientsMean::Quadratic => 1 + nx + nx * (nx + 1) / 2,
+        }
+    }
+}
+
+impl fmt::Display for CoefficientsMean {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mean = match self {
+            CoefficientsMean::Constant => "ConstantMean",
+            CoefficientsMean::Linear => "LinearMean",
+            CoefficientsMean::Quadratic => "QuadraticMean",
+        };
+        write!(f, "{mean}")
+    }
+}
+
+/// Correlation models handled by the coefficients format
+///
+/// With `d` the difference between two normalized points, `tw_jl = theta_l * |w_jl|`
+/// (`w` being the PLS weights, identity without PLS):
+///
+/// * `SquaredExponential`: `exp(-0.5 * sum_j d_j^2 * sum_l tw_jl^2)`
+/// * `AbsoluteExponential`: `exp(-sum_j |d_j| * sum_l tw_jl)`
+/// * `Matern32`: `prod_jl (1 + sqrt(3) tw_jl |d_j|) * exp(-sqrt(3) * sum_jl tw_jl |d_j|)`
+/// * `Matern52`: `prod_jl (1 + sqrt(5) tw_jl |d_j| + 5/3 tw_jl^2 d_j^2) * exp(-sqrt(5) * sum_jl tw_jl |d_j|)`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serializable", derive(Serialize, Deserialize))]
+pub enum CoefficientsCorr {
+    SquaredExponential,
+    AbsoluteExponential,
+    Matern32,
+    Matern52,
+}
+
+impl fmt::Display for CoefficientsCorr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let corr = match self {
+            CoefficientsCorr::SquaredExponential => "SquaredExponential",
+            CoefficientsCorr::AbsoluteExponential => "AbsoluteExponential",
+            CoefficientsCorr::Matern32 => "Matern32",
+            CoefficientsCorr::Matern52 => "Matern52",
+        };
+        write!(f, "{corr}")
+    }
+}
+
+/// Coefficients of a trained [GaussianProcess] in double precision.
+///
+/// Matrices are stored row-major as vectors of rows, dimensions are given wrt
+/// `nx` the input dimension, `nt` the number of training points, `h` the number of
+/// PLS components (`h = nx` and `w_star` is the identity without PLS) and `p` the
+/// number of regression basis functions of the mean model.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serializable", derive(Serialize, Deserialize))]
+pub struct GpCoefficients {
+    /// Format identifier, always [GP_COEFFICIENTS_FORMAT]
+    pub format: String,
+    /// Format version
+    pub version: u32,
+    /// Mean model
+    pub mean: CoefficientsMean,
+    /// Correlation model
+    pub corr: CoefficientsCorr,
+    /// Input means (nx,)
+    pub x_mean: Vec<f64>,
+    /// Input standard deviations (nx,)
+    pub x_std: Vec<f64>,
+    /// Output mean
+    pub y_mean: f64,
+    /// Output standard deviation
+    pub y_std: f64,
+    /// Correlation hyperparameters (h,)
+    pub theta: Vec<f64>,
+    /// PLS weights (nx, h)
+    pub w_star: Vec<Vec<f64>>,
+    /// Normalized training inputs (nt, nx)
+    pub xt: Vec<Vec<f64>>,
+    /// Regression weights (p,)
+    pub beta: Vec<f64>,
+    /// GP weights (nt,)
+    pub gamma: Vec<f64>,
+    /// Process variance
+    pub sigma2: f64,
+    /// Lower triangular Cholesky factor of the training correlation matrix (nt, nt)
+    pub r_chol: Vec<Vec<f64>>,
+    /// Regression basis at training points whitened by `r_chol` (nt, p)
+    pub ft: Vec<Vec<f64>>,
+    /// Upper triangular R factor of the QR decomposition of `ft` (p, p)
+    pub ft_qr_r: Vec<Vec<f64>>,
+}
+
+fn to_vec<F: Float>(a: &Array1<F>) -> Vec<f64> {
+    a.iter().map(|v| f64::cast(*v)).collect()
+}
+
+fn to_rows<F: Float>(a: &Array2<F>) -> Vec<Vec<f64>> {
+    a.rows()
+        .into_iter()
+        .map(|row| row.iter().map(|v| f64::cast(*v)).collect())
+        .collect()
+}
+
+impl<F: Float, Mean: RegressionModel<F> + 'static, Corr: CorrelationModel<F> + 'static>
+    GaussianProcess<F, Mean, Corr>
+{
+    /// Export the GP model as a self-contained set of coefficients (see [GpCoefficients]).
+    ///
+    /// Returns an error when the mean or correlation model is not one of the
+    /// polynomial means and correlation models provided by this crate.
+    pub fn coefficients(&self) -> Result<GpCoefficients> {
+        let mean = if TypeId::of::<Mean>() == TypeId::of::<ConstantMean>() {
+            CoefficientsMean::Constant
+        } else if TypeId::of::<Mean>() == TypeId::of::<LinearMean>() {
+            CoefficientsMean::Linear
+        } else if TypeId::of::<Mean>() == TypeId::of::<QuadraticMean>() {
+            CoefficientsMean::Quadratic
+        } else {
+            return Err(GpError::InvalidValueError(format!(
+                "{} mean can not be exported as coefficients",
+                self.params.mean
+            )));
+        };
+        let corr = if TypeId::of::<Corr>() == TypeId::of::<SquaredExponentialCorr>() {
+            CoefficientsCorr::SquaredExponential
+        } else if TypeId::of::<Corr>() == TypeId::of::<AbsoluteExponentialCorr>() {
+            CoefficientsCorr::AbsoluteExponential
+        } else if TypeId::of::<Corr>() == TypeId::of::<Matern32Corr>() {
+            CoefficientsCorr::Matern32
+        } else if TypeId::of::<Corr>() == TypeId::of::<Matern52Corr>() {
+            CoefficientsCorr::Matern52
+        } else {
+            return Err(GpError::InvalidValueError(format!(
+                "{} correlation can not be exported as coefficients",
+                self.params.corr
+            )));
+        };
+        let inners = &self.inner_params;
+        Ok(GpCoefficients {
+            format: GP_COEFFICIENTS_FORMAT.to_string(),
+            version: GP_COEFFICIENTS_VERSION,
+            mean,
+            corr,
+            x_mean: to_vec(&self.xt_norm.mean),
+            x_std: to_vec(&self.xt_norm.std),
+            y_mean: f64::cast(self.yt_norm.mean[0]),
+            y_std: f64::cast(self.yt_norm.std[0]),
+            theta: to_vec(&self.theta),
+            w_star: to_rows(&self.w_star),
+            xt: to_rows(&self.xt_norm.data),
+            beta: inners.beta.iter().map(|v| f64::cast(*v)).collect(),
+            gamma: inners.gamma.iter().map(|v| f64::cast(*v)).collect(),
+            sigma2: f64::cast(inners.sigma2),
+            r_chol: to_rows(&inners.r_chol),
+            ft: to_rows(&inners.ft),
+            ft_qr_r: to_rows(&inners.ft_qr_r),
+        })
+    }
+}
+
+impl GpCoefficients {
+    /// Input dimension
+    pub fn nx(&self) -> usize {
+        self.x_mean.len()
+    }
+
+    /// Number of training points
+    pub fn nt(&self) -> usize {
+        self.xt.len()
+    }
+
+    /// Predict the output value at the given `x` point (nx,)
+    pub fn predict(&self, x: &[f64]) -> f64 {
+        let (f, r) = self.terms(x);
+        let y = dot(&f, &self.beta) + dot(&r, &self.gamma);
+        y * self.y_std + self.y_mean
+    }
+
+    /// Predict the variance at the given `x` point (nx,)
+    pub fn predict_var(&self, x: &[f64]) -> f64 {
+        let (f, r) = self.terms(x);
+        let nt = self.nt();
+        let p = f.len();
+
+        // rt = r_chol^-1 . r
+        let mut rt = vec![0.; nt];
+        for i in 0..nt {
+            let s: f64 = (0..i).map(|k| self.r_chol[i][k] * rt[k]).sum();
+            rt[i] = (r[i] - s) / self.r_chol[i][i];
+        }
+        // u = ft_qr_r^-T . (ft^T . rt - f)
+        let mut u = vec![0.; p];
+        for j in 0..p {
+            let rhs = (0..nt).map(|i| self.ft[i][j] * rt[i]).sum::<f64>() - f[j];
+            let s: f64 = (0..j).map(|k| self.ft_qr_r[k][j] * u[k]).sum();
+            u[j] = (rhs - s) / self.ft_qr_r[j][j];
+        }
+
+        let mse = self.sigma2 * (1. - dot(&rt, &rt) + dot(&u, &u));
+        if mse < 0. { 0. } else { mse }
+    }
+
+    /// Regression basis and correlations with training points at the given `x` point
+    fn terms(&self, x: &[f64]) -> (Vec<f64>, Vec<f64>) {
+        let xn: Vec<f64> = x
+            .iter()
+            .zip(self.x_mean.iter().zip(&self.x_std))
+            .map(|(v, (m, s))| (v - m) / s)
+            .collect();
+        let f = self.mean_basis(&xn);
+        let r = self
+            .xt
+            .iter()
+            .map(|xt| {
+                let d: Vec<f64> = xn.iter().zip(xt).map(|(a, b)| a - b).collect();
+                self.correlation(&d)
+            })
+            .collect();
+        (f, r)
+    }
+
+    fn mean_basis(&self, xn: &[f64]) -> Vec<f64> {
+        let mut f = vec![1.];
+        if self.mean != CoefficientsMean::Constant {
+            f.extend_from_slice(xn);
+        }
+        if self.mean == CoefficientsMean::Quadratic {
+            for k in 0..xn.len() {
+                for j in k..xn.len() {
+                    f.push(xn[j] * xn[k]);
+                }
+            }
+        }
+        f
+    }
+
+    fn correlation(&self, d: &[f64]) -> f64 {
+        let mut a = 1.;
+        let mut s = 0.;
+        for (j, dj) in d.iter().enumerate() {
+            for (l, theta) in self.theta.iter().enumerate() {
+                let tw = theta * self.w_star[j][l].abs();
+                match self.corr {
+                    CoefficientsCorr::SquaredExponential => s += dj * dj * tw * tw,
+                    CoefficientsCorr::AbsoluteExponential => s += dj.abs() * tw,
+                    CoefficientsCorr::Matern32 => {
+                        a *= 1. + 3f64.sqrt() * tw * dj.abs();
+                        s += dj.abs() * tw;
+                    }
+                    CoefficientsCorr::Matern52 => {
+                        a *= 1. + 5f64.sqrt() * tw * dj.abs() + 5. / 3. * tw * tw * dj * dj;
+                        s += dj.abs() * tw;
+                    }
+                }
+            }
+        }
+        match self.corr {
+            CoefficientsCorr::SquaredExponential => (-0.5 * s).exp(),
+            CoefficientsCorr::AbsoluteExponential => (-s).exp(),
+            CoefficientsCorr::Matern32 => a * (-(3f64.sqrt()) * s).exp(),
+            CoefficientsCorr::Matern52 => a * (-(5f64.sqrt()) * s).exp(),
+        }
+    }
+
+    /// Check the consistency of the format, version and dimensions of the coefficients
+    pub fn check(&self) -> Result<()> {
+        if self.format != GP_COEFFICIENTS_FORMAT || self.version > GP_COEFFICIENTS_VERSION {
+            return Err(GpError::LoadFormatError(format!(
+                "expected {GP_COEFFICIENTS_FORMAT} format up to version {GP_COEFFICIENTS_VERSION}, got {} version {}",
+                self.format, self.version
+            )));
+        }
+        let (nx, nt, h) = (self.nx(), self.nt(), self.theta.len());
+        let p = self.mean.n_basis(nx);
+        let is_matrix = |m: &Vec<Vec<f64>>, nrows: usize, ncols: usize| {
+            m.len() == nrows && m.iter().all(|row| row.len() == ncols)
+        };
+        if self.x_std.len() != nx
+            || !is_matrix(&self.w_star, nx, h)
+            || !is_matrix(&self.xt, nt, nx)
+            || self.beta.len() != p
+            || self.gamma.len() != nt
+            || !is_matrix(&self.r_chol, nt, nt)
+            || !is_matrix(&self.ft, nt, p)
+            || !is_matrix(&self.ft_qr_r, p, p)
+        {
+            return Err(GpError::LoadFormatError(format!(
+                "inconsistent coefficients dimensions wrt nx={nx}, nt={nt}, h={h}, p={p}"
+            )));
+        }
+        Ok(())
+    }
+
+    /// Generate a standalone C source defining the coefficients as static arrays and
+    /// `double <name>_predict(const double *x)` and `double <name>_predict_var(const double *x)`
+    /// functions (only depending on `math.h`) where `name` is used to prefix all the symbols.
+    /// Returns an error when a coefficient is not finite as it has no C literal representation.
+    pub fn to_c_source(&self, name: &str) -> Result<String> {
+        if let Some(var) = self.non_finite() {
+            return Err(GpError::InvalidValueError(format!(
+                "Coefficients {var} are not finite, C source can not be generated"
+            )));
+        }
+        let up = name.to_uppercase();
+        let (nx, nt, h, p) = (self.nx(), self.nt(), self.theta.len(), self.beta.len());
+        let mut src = String::new();
+        let mut w = |s: String| src.push_str(&s);
+
+        w(format!(
+            "/* GP model ({}, {}) exported by egobox-gp {} */\n#include <math.h>\n\n",
+            self.mean,
+            self.corr,
+            env!("CARGO_PKG_VERSION")
+        ));
+        w(format!(
+            "#define {up}_NX {nx}\n#define {up}_NT {nt}\n#define {up}_H {h}\n#define {up}_P {p}\n\n"
+        ));
+        w(format!(
+            "static const double {name}_y_mean = {:e};\n",
+            self.y_mean
+        ));
+        w(format!(
+            "static const double {name}_y_std = {:e};\n",
+            self.y_std
+        ));
+        w(format!(
+            "static const double {name}_sigma2 = {:e};\n",
+            self.sigma2
+        ));
+        w(c_vector(name, "x_mean", &up, "NX", &self.x_mean));
+        w(c_vector(name, "x_std", &up, "NX", &self.x_std));
+        w(c_vector(name, "theta", &up, "H", &self.theta));
+        w(c_vector(name, "beta", &up, "P", &self.beta));
+        w(c_vector(name, "gamma", &up, "NT", &self.gamma));
+        w(c_matrix(name, "w_star", &up, ("NX", "H"), &self.w_star));
+        w(c_matrix(name, "xt", &up, ("NT", "NX"), &self.xt));
+        w(c_matrix(name, "r_chol", &up, ("NT", "NT"), &self.r_chol));
+        w(c_matrix(name, "ft", &up, ("NT", "P"), &self.ft));
+        w(c_matrix(name, "ft_qr_r", &up, ("P", "P"), &self.ft_qr_r));
+
+        let mut basis = String::from("    f[0] = 1.0;\n");
+        if self.mean != CoefficientsMean::Constant {
+            basis.push_str(&format!(
+                "    for (int i = 0; i < {up}_NX; i++) f[1 + i] = xn[i];\n"
+            ));
+        }
+        if self.mean == CoefficientsMean::Quadratic {
+            basis.push_str(&format!(
+                "    int o = 1 + {up}_NX;\n    for (int k = 0; k < {up}_NX; k++)\n        \
+                for (int j = k; j < {up}_NX; j++) f[o++] = xn[j] * xn[k];\n"
+            ));
+        }
+        let (update, result) = match self.corr {
+            CoefficientsCorr::SquaredExponential => {
+                ("s += d[j] * d[j] * tw * tw;", "exp(-0.5 * s)")
+            }
+            CoefficientsCorr::AbsoluteExponential => ("s += fabs(d[j]) * tw;", "exp(-s)"),
+            CoefficientsCorr::Matern32 => (
+                "a *= 1.0 + sqrt(3.0) * tw * fabs(d[j]);\n            s += fabs(d[j]) * tw;",
+                "a * exp(-sqrt(3.0) * s)",
+            ),
+            CoefficientsCorr::Matern52 => (
+                "a *= 1.0 + sqrt(5.0) * tw * fabs(d[j]) + 5.0 / 3.0 * tw * tw * d[j] * d[j];\n            \
+                s += fabs(d[j]) * tw;",
+                "a * exp(-sqrt(5.0) * s)",
+            ),
+        };
+        w(format!(
+            r#"
+static double {name}_corr(const double *d) {{
+    double a = 1.0, s = 0.0;
+    (void)a;
+    for (int j = 0; j < {up}_NX; j++) {{
+        for (int l = 0; l < {up}_H; l++) {{
+            double tw = {name}_theta[l] * fabs({name}_w_star[j][l]);
+            {update}
+        }}
+    }}
+    return {result};
+}}
+
+static void {name}_terms(const double *x, double *f, double *r) {{
+    double xn[{up}_NX], d[{up}_NX];
+    for (int i = 0; i < {up}_NX; i++) xn[i] = (x[i] - {name}_x_mean[i]) / {name}_x_std[i];
+{basis}    for (int t = 0; t < {up}_NT; t++) {{
+        for (int i = 0; i < {up}_NX; i++) d[i] = xn[i] - {name}_xt[t][i];
+        r[t] = {name}_corr(d);
+    }}
+}}
+
+double {name}_predict(const double *x) {{
+    double f[{up}_P], r[{up}_NT], y = 0.0;
+    {name}_terms(x, f, r);
+    for (int j = 0; j < {up}_P; j++) y += f[j] * {name}_beta[j];
+    for (int t = 0; t < {up}_NT; t++) y += r[t] * {name}_gamma[t];
+    return y * {name}_y_std + {name}_y_mean;
+}}
+
+double {name}_predict_var(const double *x) {{
+    double f[{up}_P], r[{up}_NT], rt[{up}_NT], u[{up}_P], mse = 1.0;
+    {name}_terms(x, f, r);
+    for (int i = 0; i < {up}_NT; i++) {{
+        double s = 0.0;
+        for (int k = 0; k < i; k++) s += {name}_r_chol[i][k] * rt[k];
+        rt[i] = (r[i] - s) / {name}_r_chol[i][i];
+        mse -= rt[i] * rt[i];
+    }}
+    for (int j = 0; j < {up}_P; j++) {{
+        double rhs = 0.0, s = 0.0;
+        for (int i = 0; i < {up}_NT; i++) rhs += {name}_ft[i][j] * rt[i];
+        for (int k = 0; k < j; k++) s += {name}_ft_qr_r[k][j] * u[k];
+        u[j] = (rhs - f[j] - s) / {name}_ft_qr_r[j][j];
+        mse += u[j] * u[j];
+    }}
+    mse *= {name}_sigma2;
+    return mse < 0.0 ? 0.0 : mse;
+}}
+"#
+        ));
+        Ok(src)
+    }
+
+    /// Name of the first coefficients holding a non finite value if any
+    fn non_finite(&self) -> Option<&'static str> {
+        let finite = |values: &[f64]| values.iter().all(|v| v.is_finite());
+        let finite_rows = |rows: &[Vec<f64>]| rows.iter().all(|row| finite(row));
+        [
+            ("y_mean", finite(&[self.y_mean])),
+            ("y_std", finite(&[self.y_std])),
+            ("sigma2", finite(&[self.sigma2])),
+            ("x_mean", finite(&self.x_mean)),
+            ("x_std", finite(&self.x_std)),
+            ("theta", finite(&self.theta)),
+            ("beta", finite(&self.beta)),
+            ("gamma", finite(&self.gamma)),
+            ("w_star", finite_rows(&self.w_star)),
+            ("xt", finite_rows(&self.xt)),
+            ("r_chol", finite_rows(&self.r_chol)),
+            ("ft", finite_rows(&self.ft)),
+            ("ft_qr_r", finite_rows(&self.ft_qr_r)),
+        ]
+        .into_iter()
+        .find(|(_, ok)| !ok)
+        .map(|(var, _)| var)
+    }
+
+    /// Serialize the coefficients in JSON format
+    #[cfg(feature = "persistent")]
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Deserialize coefficients from JSON format checking their consistency
+    #[cfg(feature = "persistent")]
+    pub fn from_json(json: &str) -> Result<Self> {
+        let coefs: GpCoefficients =
+            serde_json::from_str(json).map_err(|e| GpError::LoadError(e.to_string()))?;
+        coefs.check()?;
+        Ok(coefs)
+    }
+}
+
+fn dot(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(u, v)| u * v).sum()
+}
+
+fn c_values<'a>(values: impl Iterator<Item = &'a f64>) -> String {
+    values
+        .map(|v| format!("{v:e}"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn c_vector(name: &str, var: &str, up: &str, dim: &str, values: &[f64]) -> String {
+    format!(
+        "static const double {name}_{var}[{up}_{dim}] = {{{}}};\n",
+        c_values(values.iter())
+    )
+}
+
+fn c_matrix(name: &str, var: &str, up: &str, dims: (&str, &str), values: &[Vec<f64>]) -> String {
+    let mut src = format!(
+        "static const double {name}_{var}[{up}_{}][{up}_{}] = {{\n",
+        dims.0, dims.1
+    );
+    for row in values {
+        let _ = writeln!(src, "    {{{}}},", c_values(row.iter()));
+    }
+    src.push_str("};\n");
+    src
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::GpParams;
+    use approx::assert_abs_diff_eq;
+    use egobox_doe::{Lhs, SamplingMethod};
+    use linfa::prelude::{Dataset, Fit};
+    use ndarray::{Axis, array};
+    use ndarray_rand::rand::SeedableRng;
+    use rand_xoshiro::Xoshiro256Plus;
+
+    fn check_coefficients<Mean, Corr>(gp: &GaussianProcess<f64, Mean, Corr>, xtest: &Array2<f64>)
+    where
+        Mean: RegressionModel<f64> + 'static,
+        Corr: CorrelationModel<f64> + 'static,
+    {
+        let coefs = gp.coefficients().expect("GP exported");
+        coefs.check().expect("consistent coefficients");
+        let y = gp.predict(xtest).unwrap();
+        let var = gp.predict_var(xtest).unwrap();
+        for (i, x) in xtest.rows().into_iter().enumerate() {
+            let x = x.to_vec();
+            assert_abs_diff_eq!(coefs.predict(&x), y[i], epsilon = 1e-8 * (1. + y[i].abs()));
+            assert_abs_diff_eq!(coefs.predict_var(&x), var[i], epsilon = 1e-8);
+        }
+    }
+
+    fn training_data(nx: usize) -> (Array2<f64>, Array1<f64>, Array2<f64>) {
+        let xlimits = Array2::from_shape_fn((nx, 2), |(_, j)| if j == 0 { -2. } else { 2. });
+        let rng = Xoshiro256Plus::seed_from_u64(42);
+        let xt = Lhs::new(&xlimits).with_rng(rng.clone()).sample(20);
+        let yt = xt.map_axis(Axis(1), |x| {
+            x.iter()
+                .enumerate()
+                .map(|(i, v): (usize, &f64)| (i as f64 + 1.) * v.sin() + v * v)
+                .sum::<f64>()
+        });
+        let xtest = Lhs::new(&xlimits).with_rng(rng).sample(10);
+        (xt, yt, xtest)
+    }
+
+    macro_rules! test_coefficients {
+        ($regr:ident, $corr:ident) => {
+            paste::paste! {
+                #[test]
+                fn [<test_coefficients_ $regr:snake _ $corr:snake>]() {
+                    let (xt, yt, xtest) = training_data(2);
+                    let gp = GpParams::new([<$regr Mean>](), [<$corr Corr>]())
+                        .fit(&Dataset::new(xt, yt))
+                        .expect("GP fitted");
+                    check_coefficients(&gp, &xtest);
+                }
+            }
+        };
+    }
+
+    test_coefficients!(Constant, SquaredExponential);
+    test_coefficients!(Linear, AbsoluteExponential);
+    test_coefficients!(Quadratic, Matern32);
+    test_coefficients!(Linear, Matern52);
+
+    #[test]
+    fn test_coefficients_kpls() {
+        let (xt, yt, xtest) = training_data(4);
+        let gp = GpParams::new(ConstantMean(), Matern52Corr())
+            .kpls_dim(Some(2))
+            .fit(&Dataset::new(xt, yt))
+            .expect("GP fitted");
+        let coefs = gp.coefficients().unwrap();
+        assert_eq!(coefs.w_star.len(), 4);
+        assert_eq!(coefs.theta.len(), 2);
+        check_coefficients(&gp, &xtest);
+    }
+
+    #[test]
+    fn test_coefficients_c_source() {
+        let xt = array![[0.0], [1.0], [2.0], [3.0], [4.0]];
+        let yt = array![0.0, 1.0, 1.5, 0.9, 1.0];
+        let gp = GpParams::new(QuadraticMean(), SquaredExponentialCorr())
+            .fit(&Dataset::new(xt, yt))
+            .expect("GP fitted");
+        let src = gp.coefficients().unwrap().to_c_source("surrogate").unwrap();
+        assert!(src.contains("#define SURROGATE_NT 5"));
+        assert!(src.contains("double surrogate_predict(const double *x)"));
+        assert!(src.contains("double surrogate_predict_var(const double *x)"));
+
+        let mut coefs = gp.coefficients().unwrap();
+        coefs.gamma[2] = f64::NAN;
+        assert!(matches!(
+            coefs.to_c_source("surrogate"),
+            Err(GpError::InvalidValueError(_))
+        ));
+    }
+
+    #[test]
+    fn test_coefficients_c_compiled() {
+        let (xt, yt, xtest) = training_data(2);
+        let gp = GpParams::new(LinearMean(), Matern52Corr())
+            .fit(&Dataset::new(xt, yt))
+            .expect("GP fitted");
+        let mut src = gp.coefficients().unwrap().to_c_source("gp").unwrap();
+        let points: Vec<String> = xtest
+            .rows()
+            .into_iter()
+            .map(|x| format!("{{{:e}, {:e}}}", x[0], x[1]))
+            .collect();
+        let _ = write!(
+            src,
+            r#"
+#include <stdio.h>
+int main(void) {{
+    const double x[][GP_NX] = {{{}}};
+    for (int i = 0; i < {}; i++) printf("%.17g %.17g\n", gp_predict(x[i]), gp_predict_var(x[i]));
+    return 0;
+}}
+"#,
+            points.join(", "),
+            points.len()
+        );
+
+        let dir = std::env::temp_dir().join(format!("egobox_gp_c_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (c_file, exe) = (dir.join("gp.c"), dir.join("gp"));
+        std::fs::write(&c_file, src).unwrap();
+        let compiled = std::process::Command::new("cc")
+            .arg(&c_file)
+            .arg("-o")
+            .arg(&exe)
+            .arg("-lm")
+            .status();
+        match compiled {
+            Ok(status) => assert!(status.success(), "C source compilation failed"),
+            Err(_) => {
+                println!("No C compiler found, test skipped");
+                return;
+            }
+        }
+        let output = std::process::Command::new(&exe).output().unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+        let output = String::from_utf8(output.stdout).unwrap();
+        let values: Vec<Vec<f64>> = output
+            .lines()
+            .map(|l| l.split_whitespace().map(|v| v.parse().unwrap()).collect())
+            .collect();
+        assert_eq!(values.len(), xtest.nrows());
+
+        let y = gp.predict(&xtest).unwrap();
+        let var = gp.predict_var(&xtest).unwrap();
+        for (i, v) in values.iter().enumerate() {
+            assert_abs_diff_eq!(v[0], y[i], epsilon = 1e-8 * (1. + y[i].abs()));
+            assert_abs_diff_eq!(v[1], var[i], epsilon = 1e-8);
+        }
+    }
+
+    #[cfg(feature = "persistent")]
+    #[test]
+    fn test_coefficients_json() {
+        let (xt, yt, xtest) = training_data(2);
+        let gp = GpParams::new(LinearMean(), Matern32Corr())
+            .fit(&Dataset::new(xt, yt))
+            .expect("GP fitted");
+        let coefs = gp.coefficients().unwrap();
+        let json = coefs.to_json().unwrap();
+        assert!(json.contains("\"LinearMean\""));
+        let loaded = GpCoefficients::from_json(&json).unwrap();
+        let x = xtest.row(0).to_vec();
+        assert_abs_diff_eq!(loaded.predict(&x), coefs.predict(&x), epsilon = 1e-12);
+
+        let mut bad = coefs.clone();
+        bad.gamma.pop();
+        assert!(GpCoefficients::from_json(&bad.to_json().unwrap()).is_err());
+    }
+}
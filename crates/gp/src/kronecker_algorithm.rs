@@ -0,0 +1,836 @@
+use crate::correlation_models::CorrelationModel;
+use crate::errors::{GpError, Result};
+use crate::kronecker_parameters::{KroneckerGpParams, KroneckerGpValidParams};
+use crate::optimization::{CobylaParams, optimize_params, prepare_multistart};
+use crate::utils::{NormalizedData, pairwise_differences};
+use crate::{GP_COBYLA_MIN_EVAL, ThetaTuning};
+use linfa::prelude::{DatasetBase, Fit, Float, PredictInplace};
+use linfa_linalg::{cholesky::*, eigh::*, triangular::*};
+use ndarray::{Array, Array1, Array2, ArrayBase, ArrayView1, Axis, Data, Ix1, Ix2, IxDyn, Zip};
+
+use log::debug;
+use rayon::prelude::*;
+#[cfg(feature = "serializable")]
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::Instant;
+
+/// Tolerance used to identify the levels of normalized training inputs along each component
+const GRID_TOL: f64 = 1e-8;
+/// Relative residual tolerance of linear systems solved when the grid has missing cells
+const SOLVE_RTOL: f64 = 1e-8;
+/// Max number of iterative refinements of linear systems solutions
+const SOLVE_MAX_REFINEMENTS: usize = 10;
+
+/// Tensor grid structure of training inputs
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serializable",
+    derive(Serialize, Deserialize),
+    serde(bound(deserialize = "F: Deserialize<'de>"))
+)]
+pub(crate) struct TensorGrid<F: Float> {
+    /// Sorted distinct values taken by each input component
+    pub(crate) levels: Vec<Array1<F>>,
+    /// Flat (row-major) grid cell index of each training point
+    pub(crate) cells: Vec<usize>,
+    /// Flat indices of grid cells without training point
+    pub(crate) missing: Vec<usize>,
+}
+
+impl<F: Float> TensorGrid<F> {
+    /// Retrieve the tensor grid on which `x` points lie
+    pub(crate) fn new(x: &ArrayBase<impl Data<Elem = F>, Ix2>) -> Result<Self> {
+        if x.iter().any(|v| !v.is_finite()) {
+            return Err(GpError::InvalidValueError(
+                "Training inputs should be finite values".to_string(),
+            ));
+        }
+        let tol = F::cast(GRID_TOL);
+        let mut levels = Vec::with_capacity(x.ncols());
+        let mut cells = vec![0; x.nrows()];
+        for col in x.columns() {
+            let mut values = col.to_vec();
+            values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            let mut lv: Vec<F> = vec![];
+            values.into_iter().for_each(|v| {
+                if lv.last().is_none_or(|l| v - *l > tol) {
+                    lv.push(v)
+                }
+            });
+            Zip::from(&mut cells).and(col).for_each(|c, v| {
+                *c = *c * lv.len() + lv.partition_point(|l| *l < *v - tol);
+            });
+            levels.push(Array1::from_vec(lv));
+        }
+
+        let n_cells = levels.iter().map(|l| l.len()).product::<usize>();
+        if n_cells > 2 * x.nrows() {
+            return Err(GpError::InvalidValueError(format!(
+                "Training inputs do not lie on a tensor grid: {} points for a grid of {} cells, \
+                at most half of the cells can be missing",
+                x.nrows(),
+                n_cells
+            )));
+        }
+        let mut observed = vec![false; n_cells];
+        for &c in cells.iter() {
+            if observed[c] {
+                return Err(GpError::InvalidValueError(
+                    "Training inputs should be located at distinct cells of the grid".to_string(),
+                ));
+            }
+            observed[c] = true;
+        }
+        let missing = (0..n_cells).filter(|&c| !observed[c]).collect();
+        Ok(TensorGrid {
+            levels,
+            cells,
+            missing,
+        })
+    }
+
+    /// Number of levels along each input component
+    pub(crate) fn shape(&self) -> Vec<usize> {
+        self.levels.iter().map(|l| l.len()).collect()
+    }
+
+    /// Total number of grid cells
+    pub(crate) fn n_cells(&self) -> usize {
+        self.levels.iter().map(|l| l.len()).product()
+    }
+
+    /// Embed values given at training points in the grid, missing cells being set to zero
+    pub(crate) fn embed(&self, values: &ArrayBase<impl Data<Elem = F>, Ix1>) -> Array1<F> {
+        let mut res = Array1::zeros(self.n_cells());
+        Zip::from(values)
+            .and(&self.cells)
+            .for_each(|v, &c| res[c] = *v);
+        res
+    }
+}
+
+/// Linear solver of systems involving the correlation matrix of observed grid cells.
+///
+/// The full grid correlation matrix `A = R_1 ⊗ ... ⊗ R_d + nugget.I` is inverted through
+/// the eigendecompositions of the 1D correlation matrices `R_i`, the restriction to observed
+/// cells is handled using the Schur complement `S` of the missing cells in `A^-1`.
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serializable",
+    derive(Serialize, Deserialize),
+    serde(bound(deserialize = "F: Deserialize<'de>"))
+)]
+pub(crate) struct KroneckerSolver<F: Float> {
+    /// Eigenvectors of the 1D correlation matrices
+    eigvecs: Vec<Array2<F>>,
+    /// Inverse of the eigenvalues of the full grid correlation matrix
+    inv_eigvals: Array1<F>,
+    /// Flat indices of missing cells
+    missing: Vec<usize>,
+    /// Cholesky decomposition of `S = (A^-1)_MM` where M are missing cells
+    s_chol: Array2<F>,
+    /// Natural logarithm of the determinant of the correlation matrix of observed cells
+    logdet: F,
+}
+
+impl<F: Float> KroneckerSolver<F> {
+    pub(crate) fn new(corrs: Vec<Array2<F>>, nugget: F, missing: &[usize]) -> Result<Self> {
+        let mut eigvals = Array1::ones(1);
+        let mut eigvecs = Vec::with_capacity(corrs.len());
+        for r in corrs {
+            let (vals, vecs) = r.eigh_into()?;
+            eigvals = kron_vec(&eigvals, &vals.mapv(|v| v.max(F::zero())));
+            eigvecs.push(vecs);
+        }
+        eigvals.mapv_inplace(|v| v + nugget);
+        let mut solver = KroneckerSolver {
+            eigvecs,
+            inv_eigvals: eigvals.mapv(|v| F::one() / v),
+            missing: missing.to_vec(),
+            s_chol: Array2::zeros((0, 0)),
+            // det(A_OO) = det(A).det(S)
+            logdet: eigvals.mapv(|v| v.ln()).sum(),
+        };
+        if !missing.is_empty() {
+            let n_cells = eigvals.len();
+            let cols = missing
+                .par_iter()
+                .map(|&j| {
+                    let mut e = Array1::zeros(n_cells);
+                    e[j] = F::one();
+                    let col = solver.apply_inv(&e);
+                    missing.iter().map(|&k| col[k]).collect::<Array1<F>>()
+                })
+                .collect::<Vec<_>>();
+            let mut s = Array2::zeros((missing.len(), missing.len()));
+            Zip::from(s.columns_mut())
+                .and(&cols)
+                .for_each(|mut s_j, col| s_j.assign(col));
+            let s_chol = s.cholesky()?;
+            solver.logdet += s_chol.diag().mapv(|v| v.ln()).sum() * F::cast(2.);
+            solver.s_chol = s_chol;
+        }
+        Ok(solver)
+    }
+
+    /// Natural logarithm of the determinant of the correlation matrix of observed cells
+    pub(crate) fn logdet(&self) -> F {
+        self.logdet
+    }
+
+    /// Compute `A^-1 v` for the full grid correlation matrix
+    fn apply_inv(&self, v: &Array1<F>) -> Array1<F> {
+        let w = kron_mv(&self.eigvecs, v, true) * &self.inv_eigvals;
+        kron_mv(&self.eigvecs, &w, false)
+    }
+
+    /// Compute `A v` for the full grid correlation matrix
+    fn apply(&self, v: &Array1<F>) -> Array1<F> {
+        let w = kron_mv(&self.eigvecs, v, true) / &self.inv_eigvals;
+        kron_mv(&self.eigvecs, &w, false)
+    }
+
+    /// Compute `(A_OO)^-1 v_O` using the Schur complement of missing cells
+    fn solve_schur(&self, v: &Array1<F>) -> Result<Array1<F>> {
+        let mut u = self.apply_inv(v);
+        // (A_OO)^-1 = (A^-1)_OO - (A^-1)_OM S^-1 (A^-1)_MO
+        let u_m = Array2::from_shape_fn((self.missing.len(), 1), |(i, _)| u[self.missing[i]]);
+        let z = self.s_chol.solve_triangular(&u_m, UPLO::Lower)?;
+        let z = self.s_chol.t().solve_triangular_into(z, UPLO::Upper)?;
+        let mut e = Array1::zeros(u.len());
+        Zip::from(&self.missing)
+            .and(z.column(0))
+            .for_each(|&k, zk| e[k] = *zk);
+        u = u - self.apply_inv(&e);
+        self.missing.iter().for_each(|&k| u[k] = F::zero());
+        Ok(u)
+    }
+
+    /// Residual `v_O - A_OO u_O` given on the grid with zeros at missing cells
+    fn residual(&self, v: &Array1<F>, u: &Array1<F>) -> Array1<F> {
+        let mut res = v - &self.apply(u);
+        self.missing.iter().for_each(|&k| res[k] = F::zero());
+        res
+    }
+
+    /// Compute `(A_OO)^-1 v_O` where `v` is given on the grid with zeros at missing cells,
+    /// the result is given on the grid with zeros at missing cells.
+    ///
+    /// As the Schur complement is subject to cancellation errors when the correlation
+    /// matrix is ill conditioned, the solution is iteratively refined and an error is
+    /// returned when the residual remains too large.
+    pub(crate) fn solve(&self, v: &Array1<F>) -> Result<Array1<F>> {
+        if self.missing.is_empty() {
+            return Ok(self.apply_inv(v));
+        }
+        let norm = |a: &Array1<F>| a.dot(a).sqrt();
+        let tol = F::cast(SOLVE_RTOL) * norm(v);
+        let mut u = self.solve_schur(v)?;
+        let mut res = self.residual(v, &u);
+        let mut iter = 0;
+        while norm(&res) > tol {
+            if iter == SOLVE_MAX_REFINEMENTS {
+                return Err(GpError::LikelihoodComputationError(
+                    "Grid correlation matrix is too ill conditioned, try another theta again"
+                        .to_string(),
+                ));
+            }
+            u = &u + &self.solve_schur(&res)?;
+            res = self.residual(v, &u);
+            iter += 1;
+        }
+        Ok(u)
+    }
+}
+
+/// Compute `(M_1 ⊗ ... ⊗ M_d) v` (or with transposed square factors `M_i`)
+/// without forming the Kronecker product
+fn kron_mv<F: Float>(mats: &[Array2<F>], v: &Array1<F>, transpose: bool) -> Array1<F> {
+    let shape = mats.iter().map(|m| m.nrows()).collect::<Vec<_>>();
+    let mut t = v.to_owned().into_shape(IxDyn(&shape)).unwrap();
+    for (i, m) in mats.iter().enumerate() {
+        let m = if transpose { m.t() } else { m.view() };
+        t.lanes_mut(Axis(i)).into_iter().for_each(|mut lane| {
+            let r = m.dot(&lane);
+            lane.assign(&r);
+        });
+    }
+    t.into_shape(v.len()).unwrap()
+}
+
+/// Kronecker product of two vectors
+fn kron_vec<F: Float>(a: &Array1<F>, b: &ArrayBase<impl Data<Elem = F>, Ix1>) -> Array1<F> {
+    let nb = b.len();
+    Array1::from_shape_fn(a.len() * nb, |k| a[k / nb] * b[k % nb])
+}
+
+/// Kronecker product of given vectors
+fn kron_vecs<F: Float>(vs: &[Array1<F>]) -> Array1<F> {
+    vs.iter().fold(Array1::ones(1), |acc, v| kron_vec(&acc, v))
+}
+
+/// Correlation matrix (nx, nl) between `x` values and `levels` of one input component
+fn corr_1d<F: Float, Corr: CorrelationModel<F>>(
+    corr: &Corr,
+    x: &ArrayBase<impl Data<Elem = F>, Ix1>,
+    levels: &Array1<F>,
+    theta: F,
+) -> Array2<F> {
+    let d = pairwise_differences(
+        &x.to_owned().insert_axis(Axis(1)),
+        &levels.to_owned().insert_axis(Axis(1)),
+    );
+    corr.value(&d, &Array1::from_elem(1, theta), &Array2::ones((1, 1)))
+        .into_shape((x.len(), levels.len()))
+        .unwrap()
+}
+
+/// Kronecker GP internal parameters computed during training
+/// used later on in prediction computations
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serializable",
+    derive(Serialize, Deserialize),
+    serde(bound(deserialize = "F: Deserialize<'de>"))
+)]
+pub(crate) struct KroneckerInnerParams<F: Float> {
+    /// Gaussian process variance
+    pub(crate) sigma2: F,
+    /// Constant mean estimated by generalized least squares
+    pub(crate) beta: F,
+    /// Solver of the correlation matrix of observed cells
+    pub(crate) solver: KroneckerSolver<F>,
+    /// Gaussian process weights `(A_OO)^-1 (y - beta)` given on the grid
+    pub(crate) alpha: Array1<F>,
+    /// `(A_OO)^-1 1` given on the grid
+    pub(crate) inv_ones: Array1<F>,
+    /// `1^T (A_OO)^-1 1`
+    pub(crate) ones_inv_ones: F,
+}
+
+/// Compute reduced likelihood function
+/// grid: grid of normalized training inputs,
+/// y_grid: normalized output training values given on the grid
+/// ytrain: normalized output training values
+fn reduced_likelihood<F: Float, Corr: CorrelationModel<F>>(
+    corr: &Corr,
+    theta: &Array1<F>,
+    grid: &TensorGrid<F>,
+    y_grid: &Array1<F>,
+    ytrain: &NormalizedData<F>,
+    nugget: F,
+) -> Result<(F, KroneckerInnerParams<F>)> {
+    let corrs = std::iter::zip(&grid.levels, theta)
+        .map(|(l, &t)| corr_1d(corr, l, l, t))
+        .collect::<Vec<_>>();
+    let solver = KroneckerSolver::new(corrs, nugget, &grid.missing)?;
+
+    let ones = grid.embed(&Array1::ones(grid.cells.len()));
+    let inv_ones = solver.solve(&ones)?;
+    let ones_inv_ones = ones.dot(&inv_ones);
+    let inv_y = solver.solve(y_grid)?;
+    let beta = ones.dot(&inv_y) / ones_inv_ones;
+    let alpha = inv_y - inv_ones.mapv(|v| v * beta);
+
+    let n_obs = F::cast(grid.cells.len());
+    let sigma2 = (y_grid - &ones.mapv(|v| v * beta)).dot(&alpha) / n_obs;
+    if sigma2 <= F::zero() || !sigma2.is_finite() {
+        return Err(GpError::LikelihoodComputationError(
+            "Correlation matrix is too ill conditioned, try another theta again".to_string(),
+        ));
+    }
+    let reduced_likelihood = -n_obs * sigma2.log10() - solver.logdet() / F::cast(10.).ln();
+
+    Ok((
+        reduced_likelihood,
+        KroneckerInnerParams {
+            sigma2: sigma2 * ytrain.std[0] * ytrain.std[0],
+            beta,
+            solver,
+            alpha,
+            inv_ones,
+            ones_inv_ones,
+        },
+    ))
+}
+
+/// Structured Gaussian Process for training data lying on a full tensor grid,
+/// possibly with missing cells.
+///
+/// Using a constant mean and a separable correlation model, the correlation matrix
+/// of grid points is the Kronecker product of the 1D correlation matrices of each input
+/// component levels. Training relies on the eigendecompositions of these 1D matrices,
+/// hence a cost in O(sum n_i^3 + N.sum n_i) where n_i are the numbers of levels
+/// and N the number of grid cells, while the memory footprint is in O(N).
+///
+/// Missing grid cells are handled exactly with an additional cost in O(m.N.sum n_i + m^3)
+/// where m is the number of missing cells: the model is meant for full grids or grids with
+/// a small proportion of missing cells (at most half of the cells can be missing).
+///
+/// # Example
+///
+/// ```no_run
+/// use egobox_gp::{KroneckerGaussianProcess, correlation_models::*};
+/// use linfa::prelude::*;
+/// use ndarray::{Array1, Array2};
+///
+/// // Tabulated function on a 30x30x20 grid
+/// let axes = [30, 30, 20].map(|n| Array1::<f64>::linspace(0., 1., n));
+/// let xt = Array2::from_shape_fn((30 * 30 * 20, 3), |(i, j)| {
+///     let idx = [i / 600, (i / 20) % 30, i % 20];
+///     axes[j][idx[j]]
+/// });
+/// let yt = xt.map_axis(ndarray::Axis(1), |x| x[0].sin() + x[1] * x[2]);
+///
+/// let gp = KroneckerGaussianProcess::<f64, Matern52Corr>::params(Matern52Corr::default())
+///     .fit(&Dataset::new(xt, yt))
+///     .expect("Kronecker GP fitted");
+/// let ypred = gp.predict(&Array2::from_elem((1, 3), 0.5)).expect("Kronecker GP prediction");
+/// ```
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serializable",
+    derive(Serialize, Deserialize),
+    serde(bound(
+        serialize = "F: Serialize, Corr: Serialize",
+        deserialize = "F: Deserialize<'de>, Corr: Deserialize<'de>"
+    ))
+)]
+pub struct KroneckerGaussianProcess<F: Float, Corr: CorrelationModel<F>> {
+    /// Parameter of the autocorrelation model equal to the inverse of length scale
+    theta: Array1<F>,
+    /// Reduced likelihood value (result from internal optimization)
+    likelihood: F,
+    /// Kronecker GP internal fitted params
+    inner_params: KroneckerInnerParams<F>,
+    /// Grid of normalized training inputs
+    grid: TensorGrid<F>,
+    /// Training inputs
+    xt_norm: NormalizedData<F>,
+    /// Training outputs
+    yt_norm: NormalizedData<F>,
+    /// Training dataset (input, output)
+    training_data: (Array2<F>, Array1<F>),
+    /// Parameters used to fit this model
+    params: KroneckerGpValidParams<F, Corr>,
+}
+
+impl<F: Float, Corr: CorrelationModel<F>> fmt::Display for KroneckerGaussianProcess<F, Corr> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let shape = self
+            .grid
+            .shape()
+            .iter()
+            .map(|n| n.to_string())
+            .collect::<Vec<_>>();
+        write!(
+            f,
+            "KroneckerGP(corr={}, grid={}, missing={}, theta={}, variance={}, likelihood={})",
+            self.params.corr(),
+            shape.join("x"),
+            self.grid.missing.len(),
+            self.theta,
+            self.inner_params.sigma2,
+            self.likelihood,
+        )
+    }
+}
+
+impl<F: Float, Corr: CorrelationModel<F>> KroneckerGaussianProcess<F, Corr> {
+    /// Kronecker GP parameters contructor
+    pub fn params<NewCorr: CorrelationModel<F>>(corr: NewCorr) -> KroneckerGpParams<F, NewCorr> {
+        KroneckerGpParams::new(corr)
+    }
+
+    /// 1D correlations between normalized point `x` components and the grid levels
+    fn _compute_correlations(&self, x: &ArrayView1<F>) -> Vec<Array1<F>> {
+        std::iter::zip(x, std::iter::zip(&self.grid.levels, &self.theta))
+            .map(|(xi, (l, &t))| {
+                corr_1d(self.params.corr(), &Array1::from_elem(1, *xi), l, t).remove_axis(Axis(0))
+            })
+            .collect()
+    }
+
+    /// Predict output values at n given `x` points of nx components specified as a (n, nx) matrix.
+    /// Returns n scalar output values as a vector (n,).
+    pub fn predict(&self, x: &ArrayBase<impl Data<Elem = F>, Ix2>) -> Result<Array1<F>> {
+        let xnorm = (x - &self.xt_norm.mean) / &self.xt_norm.std;
+        let inners = &self.inner_params;
+        let mut y = Array1::zeros(x.nrows());
+        Zip::from(&mut y).and(xnorm.rows()).par_for_each(|yi, xi| {
+            let r = kron_vecs(&self._compute_correlations(&xi));
+            *yi = inners.beta + r.dot(&inners.alpha);
+        });
+        Ok(y.mapv(|v| v * self.yt_norm.std[0] + self.yt_norm.mean[0]))
+    }
+
+    /// Predict variance values at n given `x` points of nx components specified as a (n, nx) matrix.
+    /// Returns n variance values as (n,) column vector.
+    pub fn predict_var(&self, x: &ArrayBase<impl Data<Elem = F>, Ix2>) -> Result<Array1<F>> {
+        let xnorm = (x - &self.xt_norm.mean) / &self.xt_norm.std;
+        let inners = &self.inner_params;
+        let mse = (0..xnorm.nrows())
+            .into_par_iter()
+            .map(|i| {
+                let mut r = kron_vecs(&self._compute_correlations(&xnorm.row(i)));
+                self.grid.missing.iter().for_each(|&k| r[k] = F::zero());
+                let rt_r = r.dot(&inners.solver.solve(&r)?);
+                let u = inners.inv_ones.dot(&r) - F::one();
+                let mse = F::one() - rt_r + u * u / inners.ones_inv_ones;
+                // Mean Squared Error might be slightly negative depending on
+                // machine precision: set to zero in that case
+                Ok(inners.sigma2 * mse.max(F::zero()))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Array1::from_vec(mse))
+    }
+
+    /// Predict derivatives of the output prediction
+    /// wrt the kxth component at a set of n points `x` specified as a (n, nx) matrix where x has nx components.
+    pub fn predict_gradients(&self, x: &ArrayBase<impl Data<Elem = F>, Ix2>) -> Array2<F> {
+        let xnorm = (x - &self.xt_norm.mean) / &self.xt_norm.std;
+        let corr = self.params.corr();
+        let mut drv = Array2::zeros(x.dim());
+        Zip::from(drv.rows_mut())
+            .and(xnorm.rows())
+            .par_for_each(|mut drv_i, xi| {
+                let r = self._compute_correlations(&xi);
+                for (k, l) in self.grid.levels.iter().enumerate() {
+                    let dr_k = corr.jacobian(
+                        &Array1::from_elem(1, xi[k]),
+                        &l.to_owned().insert_axis(Axis(1)),
+                        &Array1::from_elem(1, self.theta[k]),
+                        &Array2::ones((1, 1)),
+                    );
+                    let mut rk = r.clone();
+                    rk[k] = dr_k.remove_axis(Axis(1));
+                    drv_i[k] = kron_vecs(&rk).dot(&self.inner_params.alpha) * self.yt_norm.std[0]
+                        / self.xt_norm.std[k];
+                }
+            });
+        drv
+    }
+
+    /// Theta
+    pub fn theta(&self) -> &Array1<F> {
+        &self.theta
+    }
+
+    /// Estimated variance
+    pub fn variance(&self) -> F {
+        self.inner_params.sigma2
+    }
+
+    /// Retrieve reduced likelihood value
+    pub fn likelihood(&self) -> F {
+        self.likelihood
+    }
+
+    /// Retrieve input and output dimensions
+    pub fn dims(&self) -> (usize, usize) {
+        (self.grid.levels.len(), 1)
+    }
+
+    /// Number of levels of the training grid along each input component
+    pub fn grid_shape(&self) -> Vec<usize> {
+        self.grid.shape()
+    }
+
+    /// Levels of the training grid along each input component
+    pub fn grid_levels(&self) -> Vec<Array1<F>> {
+        std::iter::zip(
+            &self.grid.levels,
+            std::iter::zip(&self.xt_norm.mean, &self.xt_norm.std),
+        )
+        .map(|(l, (&m, &s))| l.mapv(|v| v * s + m))
+        .collect()
+    }
+
+    /// Number of grid cells without training point
+    pub fn n_missing(&self) -> usize {
+        self.grid.missing.len()
+    }
+
+    /// Training dataset (input, output)
+    pub fn training_data(&self) -> &(Array2<F>, Array1<F>) {
+        &self.training_data
+    }
+
+    /// Parameters used to fit this model
+    pub fn params_used(&self) -> &KroneckerGpValidParams<F, Corr> {
+        &self.params
+    }
+}
+
+impl<F, D, Corr> PredictInplace<ArrayBase<D, Ix2>, Array1<F>> for KroneckerGaussianProcess<F, Corr>
+where
+    F: Float,
+    D: Data<Elem = F>,
+    Corr: CorrelationModel<F>,
+{
+    fn predict_inplace(&self, x: &ArrayBase<D, Ix2>, y: &mut Array1<F>) {
+        assert_eq!(
+            x.nrows(),
+            y.len(),
+            "The number of data points must match the number of output targets."
+        );
+
+        let values = self.predict(x).expect("Kronecker GP Prediction");
+        *y = values;
+    }
+
+    fn default_target(&self, x: &ArrayBase<D, Ix2>) -> Array1<F> {
+        Array1::zeros((x.nrows(),))
+    }
+}
+
+impl<F: Float, Corr: CorrelationModel<F>, D: Data<Elem = F>>
+    Fit<ArrayBase<D, Ix2>, ArrayBase<D, Ix1>, GpError> for KroneckerGpValidParams<F, Corr>
+{
+    type Object = KroneckerGaussianProcess<F, Corr>;
+
+    /// Fit Kronecker GP parameters using maximum likelihood
+    fn fit(
+        &self,
+        dataset: &DatasetBase<ArrayBase<D, Ix2>, ArrayBase<D, Ix1>>,
+    ) -> Result<Self::Object> {
+        let x = dataset.records();
+        let y = dataset.targets().to_owned().insert_axis(Axis(1));
+        let dim = x.ncols();
+
+        let init = self.theta_tuning().init();
+        let theta0 = match init.len() {
+            1 => Array1::from_elem(dim, init[0]),
+            n if n == dim => init.to_owned(),
+            n => {
+                return Err(GpError::InvalidValueError(format!(
+                    "Initial guess for theta should be either 1-dim or dim of xtrain ({dim}), got {n}"
+                )));
+            }
+        };
+
+        let xtrain = NormalizedData::new(x);
+        let ytrain = NormalizedData::new(&y);
+        let grid = TensorGrid::new(&xtrain.data)?;
+        debug!(
+            "Kronecker GP grid {:?} with {} missing cells",
+            grid.shape(),
+            grid.missing.len()
+        );
+        let y_grid = grid.embed(&ytrain.data.column(0));
+
+        let corr = self.corr();
+        let nugget = self.nugget();
+        let (active, bounds): (Vec<_>, Vec<_>) =
+            self.theta_tuning().active_bounds(dim)?.into_iter().unzip();
+
+        let mut theta = theta0;
+        if !matches!(self.theta_tuning(), ThetaTuning::Fixed(_)) {
+            let base: f64 = 10.;
+            let objfn = |p: &[f64], _gradient: Option<&mut [f64]>, _params: &mut ()| -> f64 {
+                let mut theta = theta.to_owned();
+                std::iter::zip(&active, p).for_each(|(&i, pi)| theta[i] = F::cast(base.powf(*pi)));
+                if theta.iter().any(|v| v.is_nan()) {
+                    // shortcut return worst value wrt to rlf minimization
+                    return f64::INFINITY;
+                }
+                match reduced_likelihood(corr, &theta, &grid, &y_grid, &ytrain, nugget) {
+                    Ok(r) => -f64::cast(r.0),
+                    Err(_) => f64::INFINITY,
+                }
+            };
+
+            let (theta_inits, log_bounds) =
+                prepare_multistart(self.n_start(), &theta.select(Axis(0), &active), &bounds);
+            debug!("Optimize with multistart theta = {theta_inits:?} and bounds = {log_bounds:?}");
+            let now = Instant::now();
+            let opt_params = (0..theta_inits.nrows())
+                .into_par_iter()
+                .map(|i| {
+                    optimize_params(
+                        objfn,
+                        &theta_inits.row(i).to_owned(),
+                        &log_bounds,
+                        CobylaParams {
+                            maxeval: (10 * theta_inits.ncols())
+                                .clamp(GP_COBYLA_MIN_EVAL, self.max_eval()),
+                            ..CobylaParams::default()
+                        },
+                    )
+                })
+                .reduce(
+                    || (f64::INFINITY, Array::ones((theta_inits.ncols(),))),
+                    |a, b| if b.0 < a.0 { b } else { a },
+                );
+            debug!("elapsed optim = {:?}", now.elapsed().as_millis());
+            if opt_params.0.is_finite() {
+                std::iter::zip(&active, &opt_params.1)
+                    .for_each(|(&i, pi)| theta[i] = F::cast(base.powf(*pi)));
+            }
+        }
+
+        let (likelihood, inner_params) =
+            reduced_likelihood(corr, &theta, &grid, &y_grid, &ytrain, nugget)?;
+        Ok(KroneckerGaussianProcess {
+            theta,
+            likelihood,
+            inner_params,
+            grid,
+            xt_norm: xtrain,
+            yt_norm: ytrain,
+            training_data: (x.to_owned(), y.remove_axis(Axis(1))),
+            params: self.clone(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::correlation_models::Matern52Corr;
+    use crate::{GaussianProcess, mean_models::ConstantMean};
+    use approx::assert_abs_diff_eq;
+    use linfa::prelude::Dataset;
+    use ndarray::{Array2, array};
+    use ndarray_rand::RandomExt;
+    use ndarray_rand::rand::SeedableRng;
+    use ndarray_rand::rand_distr::Uniform;
+    use rand_xoshiro::Xoshiro256Plus;
+
+    fn full_factorial(axes: &[Array1<f64>]) -> Array2<f64> {
+        let shape = axes.iter().map(|a| a.len()).collect::<Vec<_>>();
+        let n = shape.iter().product::<usize>();
+        Array2::from_shape_fn((n, axes.len()), |(i, j)| {
+            let stride = shape[j + 1..].iter().product::<usize>();
+            axes[j][(i / stride) % shape[j]]
+        })
+    }
+
+    fn fun(x: &Array2<f64>) -> Array1<f64> {
+        x.map_axis(Axis(1), |x| {
+            (3. * x[0]).sin() + x[1] * x[1] * (2. * x[0]).cos()
+        })
+    }
+
+    fn xtest() -> Array2<f64> {
+        let mut rng = Xoshiro256Plus::seed_from_u64(42);
+        Array2::random_using((20, 2), Uniform::new(0., 1.), &mut rng)
+    }
+
+    #[test]
+    fn test_kronecker_gp_full_grid() {
+        let xt = full_factorial(&[Array1::linspace(0., 1., 7), Array1::linspace(0., 1., 5)]);
+        let yt = fun(&xt);
+        let theta = array![0.5, 2.];
+
+        let kgp = KroneckerGaussianProcess::<f64, Matern52Corr>::params(Matern52Corr::default())
+            .theta_tuning(ThetaTuning::Fixed(theta.clone()))
+            .fit(&Dataset::new(xt.clone(), yt.clone()))
+            .expect("Kronecker GP fitted");
+        let gp = GaussianProcess::<f64, ConstantMean, Matern52Corr>::params(
+            ConstantMean::default(),
+            Matern52Corr::default(),
+        )
+        .theta_tuning(ThetaTuning::Fixed(theta))
+        .fit(&Dataset::new(xt, yt))
+        .expect("GP fitted");
+        assert_eq!(kgp.grid_shape(), vec![7, 5]);
+        assert_eq!(kgp.n_missing(), 0);
+        assert_abs_diff_eq!(kgp.likelihood(), gp.likelihood(), epsilon = 1e-6);
+        assert_abs_diff_eq!(kgp.variance(), gp.variance(), epsilon = 1e-8);
+
+        let xtest = xtest();
+        assert_abs_diff_eq!(
+            kgp.predict(&xtest).unwrap(),
+            gp.predict(&xtest).unwrap(),
+            epsilon = 1e-8
+        );
+        assert_abs_diff_eq!(
+            kgp.predict_var(&xtest).unwrap(),
+            gp.predict_var(&xtest).unwrap(),
+            epsilon = 1e-8
+        );
+        assert_abs_diff_eq!(
+            kgp.predict_gradients(&xtest),
+            gp.predict_gradients(&xtest),
+            epsilon = 1e-6
+        );
+    }
+
+    #[test]
+    fn test_kronecker_gp_missing_cells() {
+        let xt = full_factorial(&[Array1::linspace(0., 1., 6), Array1::linspace(0., 1., 6)]);
+        // Remove some cells of the grid
+        let kept = (0..xt.nrows())
+            .filter(|i| ![3, 14, 15, 22, 35].contains(i))
+            .collect::<Vec<_>>();
+        let xt = xt.select(Axis(0), &kept);
+        let yt = fun(&xt);
+
+        let theta = array![0.5, 2.];
+        let kgp = KroneckerGaussianProcess::<f64, Matern52Corr>::params(Matern52Corr::default())
+            .theta_tuning(ThetaTuning::Fixed(theta.clone()))
+            .fit(&Dataset::new(xt.clone(), yt.clone()))
+            .expect("Kronecker GP fitted");
+        assert_eq!(kgp.n_missing(), 5);
+
+        let gp = GaussianProcess::<f64, ConstantMean, Matern52Corr>::params(
+            ConstantMean::default(),
+            Matern52Corr::default(),
+        )
+        .theta_tuning(ThetaTuning::Fixed(theta))
+        .fit(&Dataset::new(xt.clone(), yt.clone()))
+        .expect("GP fitted");
+        assert_abs_diff_eq!(kgp.likelihood(), gp.likelihood(), epsilon = 1e-6);
+        assert_abs_diff_eq!(kgp.variance(), gp.variance(), epsilon = 1e-8);
+
+        let xtest = xtest();
+        assert_abs_diff_eq!(
+            kgp.predict(&xtest).unwrap(),
+            gp.predict(&xtest).unwrap(),
+            epsilon = 1e-6
+        );
+        assert_abs_diff_eq!(
+            kgp.predict_var(&xtest).unwrap(),
+            gp.predict_var(&xtest).unwrap(),
+            epsilon = 1e-6
+        );
+        // Interpolation of training data
+        assert_abs_diff_eq!(kgp.predict(&xt).unwrap(), yt, epsilon = 1e-5);
+    }
+
+    #[test]
+    fn test_kronecker_gp_large_grid() {
+        let axes = [30, 30, 20].map(|n| Array1::linspace(0., 1., n));
+        let xt = full_factorial(&axes);
+        // Engine map like grid with a few missing cells
+        let kept = (0..xt.nrows()).filter(|i| i % 401 != 7).collect::<Vec<_>>();
+        let xt = xt.select(Axis(0), &kept);
+        let yt = xt.map_axis(Axis(1), |x| (3. * x[0]).sin() + x[1] * x[2]);
+
+        let kgp = KroneckerGaussianProcess::<f64, Matern52Corr>::params(Matern52Corr::default())
+            .n_start(0)
+            .fit(&Dataset::new(xt.clone(), yt.clone()))
+            .expect("Kronecker GP fitted");
+        println!("{kgp}");
+        assert_eq!(kgp.grid_shape(), vec![30, 30, 20]);
+        assert_eq!(kgp.n_missing(), 45);
+
+        let mut rng = Xoshiro256Plus::seed_from_u64(0);
+        let xtest: Array2<f64> = Array2::random_using((10, 3), Uniform::new(0., 1.), &mut rng);
+        let ytest = xtest.map_axis(Axis(1), |x| (3. * x[0]).sin() + x[1] * x[2]);
+        assert_abs_diff_eq!(kgp.predict(&xtest).unwrap(), ytest, epsilon = 1e-3);
+        assert!(kgp.predict_var(&xtest).unwrap().iter().all(|v| *v < 1e-4));
+    }
+
+    #[test]
+    fn test_kronecker_gp_not_a_grid() {
+        let mut rng = Xoshiro256Plus::seed_from_u64(42);
+        let xt = Array2::random_using((20, 2), Uniform::new(0., 1.), &mut rng);
+        let yt = fun(&xt);
+        let res = KroneckerGaussianProcess::<f64, Matern52Corr>::params(Matern52Corr::default())
+            .fit(&Dataset::new(xt, yt));
+        assert!(matches!(res, Err(GpError::InvalidValueError(_))));
+    }
+}
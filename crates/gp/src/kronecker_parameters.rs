@@ -0,0 +1,162 @@
+use crate::correlation_models::CorrelationModel;
+use crate::errors::{GpError, Result};
+use crate::mean_models::ConstantMean;
+use crate::parameters::GpValidParams;
+use crate::{GP_COBYLA_MAX_EVAL, GP_COBYLA_MIN_EVAL, GP_OPTIM_N_START, ThetaTuning};
+use linfa::{Float, ParamGuard};
+use ndarray::Array1;
+#[cfg(feature = "serializable")]
+use serde::{Deserialize, Serialize};
+
+/// A set of validated Kronecker GP parameters.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serializable",
+    derive(Serialize, Deserialize),
+    serde(bound(
+        serialize = "F: Serialize, Corr: Serialize",
+        deserialize = "F: Deserialize<'de>, Corr: Deserialize<'de>"
+    ))
+)]
+pub struct KroneckerGpValidParams<F: Float, Corr: CorrelationModel<F>> {
+    /// Underlying GP parameters, mean is constant and PLS reduction is not used
+    /// as grid structure is defined along input components
+    pub(crate) gp_params: GpValidParams<F, ConstantMean, Corr>,
+}
+
+impl<F: Float, Corr: CorrelationModel<F>> KroneckerGpValidParams<F, Corr> {
+    /// Get correlation corr k(x, x')
+    pub fn corr(&self) -> &Corr {
+        &self.gp_params.corr
+    }
+
+    /// Get starting theta value for optimization
+    pub fn theta_tuning(&self) -> &ThetaTuning<F> {
+        &self.gp_params.theta_tuning
+    }
+
+    /// Get the number of internal GP hyperparameters optimization restart
+    pub fn n_start(&self) -> usize {
+        self.gp_params.n_start
+    }
+
+    /// Get the max number of internal likelihood evaluations during one optimization
+    pub fn max_eval(&self) -> usize {
+        self.gp_params.max_eval
+    }
+
+    /// Get nugget
+    pub fn nugget(&self) -> F {
+        self.gp_params.nugget
+    }
+}
+
+#[derive(Clone, Debug)]
+/// The set of hyperparameters that can be specified for the execution of
+/// the [Kronecker GP algorithm](struct.KroneckerGaussianProcess.html).
+pub struct KroneckerGpParams<F: Float, Corr: CorrelationModel<F>>(KroneckerGpValidParams<F, Corr>);
+
+impl<F: Float, Corr: CorrelationModel<F>> KroneckerGpParams<F, Corr> {
+    /// A constructor for Kronecker GP parameters given a correlation model
+    pub fn new(corr: Corr) -> KroneckerGpParams<F, Corr> {
+        Self(KroneckerGpValidParams {
+            gp_params: GpValidParams {
+                theta_tuning: ThetaTuning::default(),
+                mean: ConstantMean(),
+                corr,
+                kpls_dim: None,
+                n_start: GP_OPTIM_N_START,
+                max_eval: GP_COBYLA_MAX_EVAL,
+                nugget: F::cast(100.0) * F::epsilon(),
+            },
+        })
+    }
+
+    pub fn new_from_valid(params: &KroneckerGpValidParams<F, Corr>) -> Self {
+        Self(params.clone())
+    }
+
+    /// Set correlation model.
+    pub fn corr(mut self, corr: Corr) -> Self {
+        self.0.gp_params.corr = corr;
+        self
+    }
+
+    /// Set initial value for theta hyper parameter.
+    ///
+    /// During training process, the internal optimization is started from `theta_init`.
+    pub fn theta_init(mut self, theta_init: Array1<F>) -> Self {
+        self.0.gp_params.theta_tuning = match self.0.gp_params.theta_tuning {
+            ThetaTuning::Fixed(_) => ThetaTuning::Fixed(theta_init),
+            ThetaTuning::Full { init: _, bounds } => ThetaTuning::Full {
+                init: theta_init,
+                bounds,
+            },
+            ThetaTuning::Partial {
+                init: _,
+                bounds,
+                active,
+            } => ThetaTuning::Partial {
+                init: theta_init,
+                bounds,
+                active,
+            },
+        };
+        self
+    }
+
+    /// Set theta hyper parameter tuning
+    pub fn theta_tuning(mut self, theta_tuning: ThetaTuning<F>) -> Self {
+        self.0.gp_params.theta_tuning = theta_tuning;
+        self
+    }
+
+    /// Set the number of internal GP hyperparameter theta optimization restarts
+    pub fn n_start(mut self, n_start: usize) -> Self {
+        self.0.gp_params.n_start = n_start;
+        self
+    }
+
+    /// Set the max number of internal likelihood evaluations during one optimization
+    /// Given max_eval has to be greater than [crate::GP_COBYLA_MIN_EVAL] otherwise
+    /// max_eval is set to [crate::GP_COBYLA_MAX_EVAL].
+    pub fn max_eval(mut self, max_eval: usize) -> Self {
+        self.0.gp_params.max_eval = GP_COBYLA_MIN_EVAL.max(max_eval);
+        self
+    }
+
+    /// Set nugget.
+    ///
+    /// Nugget is used to improve numerical stability
+    pub fn nugget(mut self, nugget: F) -> Self {
+        self.0.gp_params.nugget = nugget;
+        self
+    }
+}
+
+impl<F: Float, Corr: CorrelationModel<F>> From<KroneckerGpValidParams<F, Corr>>
+    for KroneckerGpParams<F, Corr>
+{
+    fn from(valid: KroneckerGpValidParams<F, Corr>) -> Self {
+        KroneckerGpParams(valid.clone())
+    }
+}
+
+impl<F: Float, Corr: CorrelationModel<F>> ParamGuard for KroneckerGpParams<F, Corr> {
+    type Checked = KroneckerGpValidParams<F, Corr>;
+    type Error = GpError;
+
+    fn check_ref(&self) -> Result<&Self::Checked> {
+        if self.0.gp_params.nugget <= F::zero() {
+            return Err(GpError::InvalidValueError(
+                "`nugget` should be strictly positive".to_string(),
+            ));
+        }
+        Ok(&self.0)
+    }
+
+    fn check(self) -> Result<Self::Checked> {
+        self.check_ref()?;
+        Ok(self.0)
+    }
+}
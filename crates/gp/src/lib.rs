@@ -10,21 +10,70 @@
 //! GP methods are implemented by [GaussianProcess] parameterized by [GpParams].
 //!
 //! SGP methods are implemented by [SparseGaussianProcess] parameterized by [SgpParams].
+//!
+//! GP predictions complying with monotonicity and output range constraints are implemented by
+//! [ConstrainedGaussianProcess] parameterized by [ConstrainedGpParams].
+//!
+//! GP regression robust to outliers (Student-t likelihood or Huber loss) is implemented by
+//! [RobustGaussianProcess] parameterized by [RobustGpParams]. With a Gaussian likelihood,
+//! it is a GP regression estimating the variance of a homoscedastic noise.
+//!
+//! Fully Bayesian GP regression, where hyperparameters are marginalized by MCMC sampling,
+//! is implemented by [BayesianGaussianProcess] parameterized by [BayesianGpParams].
+//!
+//! GP regression of data lying on a full tensor grid (e.g. full factorial designs or tabulated data),
+//! possibly with missing cells, exploiting the Kronecker structure of the correlation matrix
+//! is implemented by [KroneckerGaussianProcess] parameterized by [KroneckerGpParams].
+//!
+//! With the `persistent` feature, [GaussianProcess] and [SparseGaussianProcess] models can be saved
+//! and loaded using a versioned file format (see `GpFileHeader`).
+//!
+//! Trained [GaussianProcess] models can be exported as a self-contained set of coefficients
+//! (see [GpCoefficients]) to be evaluated without linking Rust, for instance from the
+//! generated C source.
+//!
+//! Posterior sample paths given as continuous and differentiable functions, cheap to evaluate
+//! at a large number of points, are drawn with [GaussianProcess::sample_paths] and
+//! [SparseGaussianProcess::sample_paths] using random Fourier features.
 mod algorithm;
+mod bayesian_algorithm;
+mod constrained_algorithm;
 pub mod correlation_models;
 mod errors;
+mod export;
+mod kronecker_algorithm;
 pub mod mean_models;
 pub mod metrics;
+mod robust_algorithm;
 mod sparse_algorithm;
 
+mod bayesian_parameters;
+mod constrained_parameters;
+mod kronecker_parameters;
 mod parameters;
+#[cfg(feature = "persistent")]
+mod persistence;
+mod robust_parameters;
+mod sample_paths;
 mod sparse_parameters;
 mod utils;
 
 mod optimization;
 
 pub use algorithm::*;
+pub use bayesian_algorithm::*;
+pub use bayesian_parameters::*;
+pub use constrained_algorithm::*;
+pub use constrained_parameters::*;
 pub use errors::*;
+pub use export::*;
+pub use kronecker_algorithm::*;
+pub use kronecker_parameters::*;
 pub use parameters::*;
+#[cfg(feature = "persistent")]
+pub use persistence::*;
+pub use robust_algorithm::*;
+pub use robust_parameters::*;
+pub use sample_paths::*;
 pub use sparse_algorithm::*;
 pub use sparse_parameters::*;
@@ -5,18 +5,23 @@
 //! The following models are implemented:
 //! * constant,
 //! * linear,
-//! * quadratic
+//! * quadratic,
+//! * custom, using user-defined basis functions (universal kriging with a given trend)
 
 use linfa::Float;
-use ndarray::{Array2, ArrayBase, Axis, Data, Ix1, Ix2, concatenate, s};
+use ndarray::{
+    Array1, Array2, Array3, ArrayBase, ArrayView1, ArrayView2, Axis, Data, Ix1, Ix2, Zip,
+    concatenate, s,
+};
 use paste::paste;
 #[cfg(feature = "serializable")]
 use serde::{Deserialize, Serialize};
 use std::convert::TryFrom;
 use std::fmt;
+use std::sync::Arc;
 
 /// A trait for mean models used in GP regression
-pub trait RegressionModel<F: Float>: Clone + Copy + Default + fmt::Display + Sync {
+pub trait RegressionModel<F: Float>: Clone + fmt::Display + Sync {
     /// Compute regression coefficients defining the mean behaviour of the GP model
     /// for the given `x` data points specified as (n, nx) matrix.
     fn value(&self, x: &ArrayBase<impl Data<Elem = F>, Ix2>) -> Array2<F>;
@@ -24,6 +29,42 @@ pub trait RegressionModel<F: Float>: Clone + Copy + Default + fmt::Display + Syn
     /// Compute regression derivative coefficients
     /// at the given `x` data point specified as (nx,) vector.
     fn jacobian(&self, x: &ArrayBase<impl Data<Elem = F>, Ix1>) -> Array2<F>;
+
+    /// Compute regression second derivative coefficients
+    /// at the given `x` data point specified as (nx,) vector.
+    /// The default implementation uses central finite differences of the jacobian.
+    fn hessian(&self, x: &ArrayBase<impl Data<Elem = F>, Ix1>) -> Array3<F> {
+        finite_difference_hessian(self, x)
+    }
+
+    /// Returns the regression model to be evaluated on inputs normalized
+    /// as `(x - x_mean) / x_std` while being defined on original inputs.
+    /// Polynomial models span the same space whatever the input scaling,
+    /// hence the default implementation returns the model unchanged.
+    /// Implementations should be idempotent: the given scaling replaces any previous one.
+    fn normalized(&self, _x_mean: &Array1<F>, _x_std: &Array1<F>) -> Self {
+        self.clone()
+    }
+}
+
+/// Hessian of the given regression model at `x` computed with central finite differences of its jacobian
+fn finite_difference_hessian<F: Float>(
+    model: &impl RegressionModel<F>,
+    x: &ArrayBase<impl Data<Elem = F>, Ix1>,
+) -> Array3<F> {
+    let nx = x.len();
+    let mut hess = Array3::zeros((model.jacobian(x).nrows(), nx, nx));
+    let eps = F::epsilon().cbrt();
+    for k in 0..nx {
+        let h = eps * (F::one() + x[k].abs());
+        let mut xp = x.to_owned();
+        xp[k] += h;
+        let mut xm = x.to_owned();
+        xm[k] -= h;
+        let fdiff = (model.jacobian(&xp) - model.jacobian(&xm)).mapv(|v| v / (F::cast(2.) * h));
+        hess.slice_mut(s![.., .., k]).assign(&fdiff);
+    }
+    hess
 }
 
 /// A constant function as mean of the GP
@@ -50,6 +91,12 @@ impl<F: Float> RegressionModel<F> for ConstantMean {
     fn jacobian(&self, x: &ArrayBase<impl Data<Elem = F>, Ix1>) -> Array2<F> {
         Array2::<F>::zeros((1, x.len()))
     }
+
+    /// regr.hess(x) = 0
+    /// (1, nx, nx) tensor where nx is the dimension of x (number fo components)
+    fn hessian(&self, x: &ArrayBase<impl Data<Elem = F>, Ix1>) -> Array3<F> {
+        Array3::<F>::zeros((1, x.len(), x.len()))
+    }
 }
 
 /// An affine function as mean of the GP
@@ -79,6 +126,13 @@ impl<F: Float> RegressionModel<F> for LinearMean {
         jac.slice_mut(s![1.., ..]).assign(&Array2::eye(x.len()));
         jac
     }
+
+    /// regr.hess(x) = 0
+    /// (nx+1, nx, nx) tensor where nx is the dimension of x (number fo components)
+    fn hessian(&self, x: &ArrayBase<impl Data<Elem = F>, Ix1>) -> Array3<F> {
+        let nx = x.len();
+        Array3::<F>::zeros((nx + 1, nx, nx))
+    }
 }
 
 /// A 2-degree polynomial as mean of the GP
@@ -125,6 +179,165 @@ impl<F: Float> RegressionModel<F> for QuadraticMean {
         }
         jac
     }
+
+    /// regr.hess(x) = [0,   ...  , 0
+    ///                 { d2(xi*xj)/dxidxj (i,j) = 1,...,n  , j >= i} ]
+    /// (1 + nx + nx * (nx + 1) / 2, nx, nx) tensor where nx is the dimension of x (number fo components)
+    fn hessian(&self, x: &ArrayBase<impl Data<Elem = F>, Ix1>) -> Array3<F> {
+        let nx = x.len();
+        let mut hess = Array3::<F>::zeros((1 + nx + nx * (nx + 1) / 2, nx, nx));
+        let mut o = 1 + nx;
+        for i in 0..nx {
+            for j in i..nx {
+                hess[[o, i, j]] += F::one();
+                hess[[o, j, i]] += F::one();
+                o += 1;
+            }
+        }
+        hess
+    }
+}
+
+/// Basis functions of a [CustomMean]: x (n, nx) -> f(x) (n, p)
+pub type BasisFn<F> = Arc<dyn Fn(&ArrayView2<F>) -> Array2<F> + Send + Sync>;
+/// Jacobian of the basis functions of a [CustomMean]: x (nx,) -> df/dx(x) (p, nx)
+pub type BasisJacobianFn<F> = Arc<dyn Fn(&ArrayView1<F>) -> Array2<F> + Send + Sync>;
+/// Hessian of the basis functions of a [CustomMean]: x (nx,) -> d2f/dx2(x) (p, nx, nx)
+pub type BasisHessianFn<F> = Arc<dyn Fn(&ArrayView1<F>) -> Array3<F> + Send + Sync>;
+
+/// A mean of the GP defined as a linear combination of user-defined basis functions
+/// (i.e. universal kriging with a given trend), for instance physics-based terms or
+/// a cheap analytical model.
+///
+/// Basis functions and their derivatives are defined on the original input space,
+/// regression coefficients are estimated by generalized least-squares while fitting the GP.
+/// As training outputs are normalized, basis functions should usually include a constant term.
+///
+/// ```
+/// use egobox_gp::{GaussianProcess, correlation_models::SquaredExponentialCorr, mean_models::CustomMean};
+/// use linfa::prelude::*;
+/// use ndarray::{Array2, Axis, array, concatenate};
+///
+/// // Low-fidelity model used as a trend: f(x) = [1, sin(x)]
+/// let mean = CustomMean::new(
+///     |x| concatenate![Axis(1), Array2::ones((x.nrows(), 1)), x.mapv(f64::sin)],
+///     |x| array![[0.], [x[0].cos()]],
+/// );
+/// let xt = array![[0.], [1.], [2.], [3.], [4.]];
+/// let yt = array![0.1, 1.0, 1.1, 0.3, -0.6];
+/// let gp = GaussianProcess::<f64, CustomMean<f64>, SquaredExponentialCorr>::params(
+///     mean,
+///     SquaredExponentialCorr::default(),
+/// )
+/// .fit(&Dataset::new(xt, yt))
+/// .expect("GP fitted");
+/// let y = gp.predict(&array![[1.5]]).expect("GP prediction");
+/// ```
+#[derive(Clone)]
+pub struct CustomMean<F: Float> {
+    /// Name used to display the mean model
+    name: String,
+    /// Basis functions
+    basis: BasisFn<F>,
+    /// Jacobian of basis functions
+    jacobian: BasisJacobianFn<F>,
+    /// Optional hessian of basis functions, approximated by finite differences of the jacobian otherwise
+    hessian: Option<BasisHessianFn<F>>,
+    /// Input normalization (mean, std) used by the GP
+    x_scaling: Option<(Array1<F>, Array1<F>)>,
+}
+
+impl<F: Float> CustomMean<F> {
+    /// Constructor given `basis` functions and their `jacobian`
+    pub fn new(
+        basis: impl Fn(&ArrayView2<F>) -> Array2<F> + Send + Sync + 'static,
+        jacobian: impl Fn(&ArrayView1<F>) -> Array2<F> + Send + Sync + 'static,
+    ) -> Self {
+        CustomMean {
+            name: "CustomMean".to_string(),
+            basis: Arc::new(basis),
+            jacobian: Arc::new(jacobian),
+            hessian: None,
+            x_scaling: None,
+        }
+    }
+
+    /// Set the hessian of basis functions
+    pub fn with_hessian(
+        mut self,
+        hessian: impl Fn(&ArrayView1<F>) -> Array3<F> + Send + Sync + 'static,
+    ) -> Self {
+        self.hessian = Some(Arc::new(hessian));
+        self
+    }
+
+    /// Set the name used to display the mean model
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+
+    /// Map normalized x to original input space
+    fn unnormalize(&self, x: &ArrayBase<impl Data<Elem = F>, Ix1>) -> Array1<F> {
+        match &self.x_scaling {
+            Some((x_mean, x_std)) => x * x_std + x_mean,
+            None => x.to_owned(),
+        }
+    }
+}
+
+impl<F: Float> RegressionModel<F> for CustomMean<F> {
+    /// regr(x) = [ f_1(x), ..., f_p(x) ].T
+    fn value(&self, x: &ArrayBase<impl Data<Elem = F>, Ix2>) -> Array2<F> {
+        match &self.x_scaling {
+            Some((x_mean, x_std)) => (self.basis)(&(x * x_std + x_mean).view()),
+            None => (self.basis)(&x.view()),
+        }
+    }
+
+    /// (p, nx) matrix where nx is the dimension of x (number fo components)
+    fn jacobian(&self, x: &ArrayBase<impl Data<Elem = F>, Ix1>) -> Array2<F> {
+        let jac = (self.jacobian)(&self.unnormalize(x).view());
+        match &self.x_scaling {
+            Some((_, x_std)) => jac * x_std,
+            None => jac,
+        }
+    }
+
+    /// (p, nx, nx) tensor where nx is the dimension of x (number fo components)
+    fn hessian(&self, x: &ArrayBase<impl Data<Elem = F>, Ix1>) -> Array3<F> {
+        let Some(hessian) = &self.hessian else {
+            return finite_difference_hessian(self, x);
+        };
+        let mut hess = hessian(&self.unnormalize(x).view());
+        if let Some((_, x_std)) = &self.x_scaling {
+            Zip::indexed(hess.lanes_mut(Axis(0))).for_each(|(a, b), mut h| {
+                h.mapv_inplace(|v| v * x_std[a] * x_std[b]);
+            });
+        }
+        hess
+    }
+
+    fn normalized(&self, x_mean: &Array1<F>, x_std: &Array1<F>) -> Self {
+        let mut mean = self.clone();
+        mean.x_scaling = Some((x_mean.to_owned(), x_std.to_owned()));
+        mean
+    }
+}
+
+impl<F: Float> fmt::Display for CustomMean<F> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+impl<F: Float> fmt::Debug for CustomMean<F> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CustomMean")
+            .field("name", &self.name)
+            .field("x_scaling", &self.x_scaling)
+            .finish()
+    }
 }
 
 macro_rules! declare_mean_util_impls {
@@ -212,6 +425,84 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_quadratic_hess() {
+        let x = array![1., 2.];
+        let hess = QuadraticMean::default().hessian(&x);
+        let expected = array![
+            [[0., 0.], [0., 0.]],
+            [[0., 0.], [0., 0.]],
+            [[0., 0.], [0., 0.]],
+            [[2., 0.], [0., 0.]],
+            [[0., 1.], [1., 0.]],
+            [[0., 0.], [0., 2.]]
+        ];
+        assert_abs_diff_eq!(expected, hess);
+    }
+
+    /// Quadratic mean relying on the default finite differences hessian
+    #[derive(Clone, Default)]
+    struct FdHessianMean(QuadraticMean);
+
+    impl fmt::Display for FdHessianMean {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "FdHessian")
+        }
+    }
+
+    impl RegressionModel<f64> for FdHessianMean {
+        fn value(&self, x: &ArrayBase<impl Data<Elem = f64>, Ix2>) -> Array2<f64> {
+            self.0.value(x)
+        }
+
+        fn jacobian(&self, x: &ArrayBase<impl Data<Elem = f64>, Ix1>) -> Array2<f64> {
+            self.0.jacobian(x)
+        }
+    }
+
+    #[test]
+    fn test_default_hess() {
+        let x = array![1., 2.];
+        assert_abs_diff_eq!(
+            FdHessianMean::default().hessian(&x),
+            QuadraticMean::default().hessian(&x),
+            epsilon = 1e-6
+        );
+    }
+
+    #[test]
+    fn test_custom_mean_normalized() {
+        let mean = CustomMean::new(
+            |x: &ArrayView2<f64>| {
+                concatenate![
+                    Axis(1),
+                    Array2::ones((x.nrows(), 1)),
+                    (&x.column(0) * &x.column(1)).insert_axis(Axis(1))
+                ]
+            },
+            |x: &ArrayView1<f64>| array![[0., 0.], [x[1], x[0]]],
+        );
+        let x = array![[1., 2.], [-3., 0.5]];
+        assert_abs_diff_eq!(mean.value(&x), array![[1., 2.], [1., -1.5]]);
+
+        let (x_mean, x_std) = (array![1., -1.], array![2., 4.]);
+        let mean = mean
+            .normalized(&array![5., 5.], &array![1., 1.])
+            .normalized(&x_mean, &x_std);
+        let xnorm = (&x - &x_mean) / &x_std;
+        assert_abs_diff_eq!(mean.value(&xnorm), array![[1., 2.], [1., -1.5]]);
+        assert_abs_diff_eq!(
+            mean.jacobian(&xnorm.row(0)),
+            array![[0., 0.], [2. * 2., 1. * 4.]]
+        );
+        // hessian approximated from jacobian
+        assert_abs_diff_eq!(
+            mean.hessian(&xnorm.row(1)),
+            array![[[0., 0.], [0., 0.]], [[0., 8.], [8., 0.]]],
+            epsilon = 1e-6
+        );
+    }
+
     #[test]
     fn test_utils() {
         assert_eq!("ConstantMean", ConstantMean().to_string());
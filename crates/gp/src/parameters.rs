@@ -66,6 +66,47 @@ impl<F: Float> ThetaTuning<F> {
             ThetaTuning::Fixed(_) => None,
         }
     }
+
+    /// Components of theta (among `dim` ones) to be tuned with their bounds
+    pub(crate) fn active_bounds(&self, dim: usize) -> Result<Vec<(usize, (F, F))>> {
+        let (active, bounds) = match self {
+            ThetaTuning::Fixed(_) => return Ok(vec![]),
+            ThetaTuning::Full { init: _, bounds } => ((0..dim).collect::<Vec<_>>(), bounds),
+            ThetaTuning::Partial {
+                init: _,
+                bounds,
+                active,
+            } => (active.to_vec(), bounds),
+        };
+        match bounds.len() {
+            1 => Ok(active.into_iter().map(|i| (i, bounds[0])).collect()),
+            n if n == dim => Ok(active.into_iter().map(|i| (i, bounds[i])).collect()),
+            n => Err(GpError::InvalidValueError(format!(
+                "Bounds for theta should be either 1-dim or dim of xtrain ({dim}), got {n}"
+            ))),
+        }
+    }
+
+    /// Convert the tuning to another float type
+    pub fn cast<G: Float>(&self) -> ThetaTuning<G> {
+        let cast_bounds = |bounds: &Array1<(F, F)>| bounds.mapv(|(l, u)| (G::cast(l), G::cast(u)));
+        match self {
+            ThetaTuning::Fixed(init) => ThetaTuning::Fixed(init.mapv(G::cast)),
+            ThetaTuning::Full { init, bounds } => ThetaTuning::Full {
+                init: init.mapv(G::cast),
+                bounds: cast_bounds(bounds),
+            },
+            ThetaTuning::Partial {
+                init,
+                bounds,
+                active,
+            } => ThetaTuning::Partial {
+                init: init.mapv(G::cast),
+                bounds: cast_bounds(bounds),
+                active: active.to_owned(),
+            },
+        }
+    }
 }
 
 /// A set of validated GP parameters.
@@ -95,7 +136,7 @@ pub struct GpValidParams<F: Float, Mean: RegressionModel<F>, Corr: CorrelationMo
     pub(crate) nugget: F,
 }
 
-impl<F: Float, Mean: RegressionModel<F>, Corr: CorrelationModel<F>> Default
+impl<F: Float, Mean: RegressionModel<F> + Default, Corr: CorrelationModel<F>> Default
     for GpValidParams<F, Mean, Corr>
 {
     fn default() -> GpValidParams<F, Mean, Corr> {
@@ -159,9 +200,13 @@ impl<F: Float, Mean: RegressionModel<F>, Corr: CorrelationModel<F>> GpParams<F,
     /// A constructor for GP parameters given mean and correlation models
     pub fn new(mean: Mean, corr: Corr) -> GpParams<F, Mean, Corr> {
         Self(GpValidParams {
+            theta_tuning: ThetaTuning::default(),
             mean,
             corr,
-            ..Default::default()
+            kpls_dim: None,
+            n_start: GP_OPTIM_N_START,
+            max_eval: GP_COBYLA_MAX_EVAL,
+            nugget: F::cast(100.0) * F::epsilon(),
         })
     }
 
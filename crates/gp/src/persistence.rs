@@ -0,0 +1,450 @@
+use crate::correlation_models::CorrelationModel;
+use crate::errors::{GpError, Result};
+use crate::mean_models::RegressionModel;
+use crate::{GaussianProcess, GpFileFormat, SparseGaussianProcess};
+use linfa::Float;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use std::fs;
+use std::io::Write;
+
+/// Current version of the GP model file format
+pub const GP_FILE_FORMAT_VERSION: u32 = 1;
+
+/// Tag identifying files holding egobox GP models
+const GP_FILE_MAGIC: &str = "egobox-gp";
+
+/// Header of a GP model file identifying the saved model.
+///
+/// Models are saved within a versioned envelope `{ header, model }` so that
+/// a model saved by another release is either loaded or rejected with a [GpError]
+/// explaining the incompatibility.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GpFileHeader {
+    /// Tag identifying egobox GP model files
+    magic: String,
+    /// Version of the file format
+    format_version: u32,
+    /// Version of the egobox-gp crate used to save the model
+    crate_version: String,
+    /// Kind of model (ex: GaussianProcess)
+    model: String,
+    /// Float type of the model (ex: f64)
+    float: String,
+    /// Mean model identifier
+    mean: String,
+    /// Correlation model identifier
+    corr: String,
+}
+
+impl GpFileHeader {
+    fn new<M: GpModelFile>(model: &M) -> Self {
+        GpFileHeader {
+            magic: GP_FILE_MAGIC.to_string(),
+            format_version: GP_FILE_FORMAT_VERSION,
+            crate_version: env!("CARGO_PKG_VERSION").to_string(),
+            model: M::MODEL.to_string(),
+            float: M::float_id().to_string(),
+            mean: model.mean_id(),
+            corr: model.corr_id(),
+        }
+    }
+
+    /// Read the header of the GP model saved in the given file
+    pub fn read(path: &str, format: GpFileFormat) -> Result<Self> {
+        let data = fs::read(path)?;
+        read_header(&data, format).ok_or(GpError::LoadFormatError(format!(
+            "{path} is not a GP model file with versioned header"
+        )))
+    }
+
+    /// Version of the file format
+    pub fn format_version(&self) -> u32 {
+        self.format_version
+    }
+
+    /// Version of the egobox-gp crate used to save the model
+    pub fn crate_version(&self) -> &str {
+        &self.crate_version
+    }
+
+    /// Kind of model
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
+    /// Float type of the model
+    pub fn float(&self) -> &str {
+        &self.float
+    }
+
+    /// Mean model identifier
+    pub fn mean(&self) -> &str {
+        &self.mean
+    }
+
+    /// Correlation model identifier
+    pub fn corr(&self) -> &str {
+        &self.corr
+    }
+
+    /// Check the saved model can be loaded as a `M` model before deserialization
+    fn check<M: GpModelFile>(&self) -> Result<()> {
+        if self.format_version > GP_FILE_FORMAT_VERSION {
+            return Err(GpError::LoadFormatError(format!(
+                "model saved with file format version {} by egobox-gp {}, \
+                egobox-gp {} supports file format versions up to {}",
+                self.format_version,
+                self.crate_version,
+                env!("CARGO_PKG_VERSION"),
+                GP_FILE_FORMAT_VERSION
+            )));
+        }
+        if self.model != M::MODEL || self.float != M::float_id() {
+            return Err(GpError::LoadFormatError(format!(
+                "file holds a {}<{}> model, expected {}<{}>",
+                self.model,
+                self.float,
+                M::MODEL,
+                M::float_id()
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Models persisted within a versioned envelope
+pub(crate) trait GpModelFile: Serialize + DeserializeOwned {
+    /// Kind of model
+    const MODEL: &'static str;
+    /// Float type identifier
+    fn float_id() -> &'static str;
+    /// Mean model identifier
+    fn mean_id(&self) -> String;
+    /// Correlation model identifier
+    fn corr_id(&self) -> String;
+    /// Type name of the egobox-moe surrogate tagging the model in legacy files
+    fn legacy_tag(&self) -> String;
+}
+
+impl<F, Mean, Corr> GpModelFile for GaussianProcess<F, Mean, Corr>
+where
+    F: Float + Serialize + DeserializeOwned,
+    Mean: RegressionModel<F> + Serialize + DeserializeOwned,
+    Corr: CorrelationModel<F> + Serialize + DeserializeOwned,
+{
+    const MODEL: &'static str = "GaussianProcess";
+
+    fn float_id() -> &'static str {
+        std::any::type_name::<F>()
+    }
+
+    fn mean_id(&self) -> String {
+        self.params.mean.to_string()
+    }
+
+    fn corr_id(&self) -> String {
+        self.params.corr.to_string()
+    }
+
+    fn legacy_tag(&self) -> String {
+        format!(
+            "Gp{}{}Surrogate{}",
+            self.mean_id().trim_end_matches("Mean"),
+            self.corr_id(),
+            legacy_float_suffix::<F>()
+        )
+    }
+}
+
+impl<F, Corr> GpModelFile for SparseGaussianProcess<F, Corr>
+where
+    F: Float + Serialize + DeserializeOwned,
+    Corr: CorrelationModel<F> + Serialize + DeserializeOwned,
+{
+    const MODEL: &'static str = "SparseGaussianProcess";
+
+    fn float_id() -> &'static str {
+        std::any::type_name::<F>()
+    }
+
+    fn mean_id(&self) -> String {
+        "ConstantMean".to_string()
+    }
+
+    fn corr_id(&self) -> String {
+        self.params.corr().to_string()
+    }
+
+    fn legacy_tag(&self) -> String {
+        format!(
+            "Sgp{}Surrogate{}",
+            self.corr_id(),
+            legacy_float_suffix::<F>()
+        )
+    }
+}
+
+/// Single precision surrogates type names are suffixed with `F32`
+fn legacy_float_suffix<F: Float>() -> &'static str {
+    if std::any::type_name::<F>() == "f32" {
+        "F32"
+    } else {
+        ""
+    }
+}
+
+#[derive(Serialize)]
+struct GpFileRef<'a, M> {
+    header: GpFileHeader,
+    model: &'a M,
+}
+
+#[derive(Deserialize)]
+struct GpFile<M> {
+    header: GpFileHeader,
+    model: M,
+}
+
+/// Only the header is read, the model being ignored (json) or not read (binary)
+#[derive(Deserialize)]
+struct GpFileHead {
+    header: GpFileHeader,
+}
+
+fn from_bytes<T: DeserializeOwned>(
+    data: &[u8],
+    format: GpFileFormat,
+) -> std::result::Result<T, String> {
+    match format {
+        GpFileFormat::Json => serde_json::from_slice(data).map_err(|e| e.to_string()),
+        GpFileFormat::Binary => bincode::deserialize(data).map_err(|e| e.to_string()),
+    }
+}
+
+fn read_header(data: &[u8], format: GpFileFormat) -> Option<GpFileHeader> {
+    from_bytes::<GpFileHead>(data, format)
+        .ok()
+        .map(|f| f.header)
+        .filter(|h| h.magic == GP_FILE_MAGIC)
+}
+
+/// Save given model in a versioned envelope
+pub(crate) fn save_model<M: GpModelFile>(
+    model: &M,
+    path: &str,
+    format: GpFileFormat,
+) -> Result<()> {
+    let file = GpFileRef {
+        header: GpFileHeader::new(model),
+        model,
+    };
+    let bytes = match format {
+        GpFileFormat::Json => serde_json::to_vec(&file)?,
+        GpFileFormat::Binary => bincode::serialize(&file)?,
+    };
+    fs::File::create(path)?.write_all(&bytes)?;
+    Ok(())
+}
+
+/// Load model from legacy json files without header written by egobox <= 0.32
+/// through `GpSurrogate::save` where the model is tagged with the surrogate type name
+fn load_legacy_model<M: GpModelFile>(data: &[u8], format: GpFileFormat, path: &str) -> Result<M> {
+    let err =
+        |msg: String| GpError::LoadFormatError(format!("{path} has no versioned header and {msg}"));
+    if format == GpFileFormat::Binary {
+        return Err(err(
+            "legacy binary files are not supported, use egobox_moe::load".to_string(),
+        ));
+    }
+    let value: serde_json::Value =
+        serde_json::from_slice(data).map_err(|e| err(format!("is not a valid json file: {e}")))?;
+    let Some((tag, content)) = value
+        .as_object()
+        .filter(|o| o.len() == 1)
+        .and_then(|o| o.iter().next())
+    else {
+        return Err(err("is not a legacy surrogate file".to_string()));
+    };
+    let model: M = serde_json::from_value(content.clone()).map_err(|e| {
+        err(format!(
+            "legacy {tag} cannot be loaded as a {}: {e}",
+            M::MODEL
+        ))
+    })?;
+    if model.legacy_tag() != *tag {
+        return Err(err(format!(
+            "holds a legacy {tag}, expected {}",
+            model.legacy_tag()
+        )));
+    }
+    Ok(model)
+}
+
+/// Load model from a versioned envelope or from a legacy file
+pub(crate) fn load_model<M: GpModelFile>(path: &str, format: GpFileFormat) -> Result<M> {
+    let data = fs::read(path)?;
+    let Some(header) = read_header(&data, format) else {
+        return load_legacy_model(&data, format, path);
+    };
+    header.check::<M>()?;
+    let file: GpFile<M> = from_bytes(&data, format).map_err(|e| {
+        GpError::LoadFormatError(format!(
+            "{} saved by egobox-gp {} (file format version {}) cannot be loaded: {e}",
+            header.model, header.crate_version, header.format_version
+        ))
+    })?;
+    let model = file.model;
+    // Mean and correlation models are unit structs not distinguished by serde
+    if model.mean_id() != file.header.mean || model.corr_id() != file.header.corr {
+        return Err(GpError::LoadFormatError(format!(
+            "file holds a {} model with mean {} and correlation {}, expected mean {} and correlation {}",
+            header.model,
+            file.header.mean,
+            file.header.corr,
+            model.mean_id(),
+            model.corr_id()
+        )));
+    }
+    Ok(model)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::correlation_models::{Matern52Corr, SquaredExponentialCorr};
+    use crate::mean_models::ConstantMean;
+    use crate::{Inducings, SparseMethod};
+    use approx::assert_abs_diff_eq;
+    use linfa::prelude::*;
+    use ndarray::{Array1, Array2, Axis, array};
+
+    const TEST_DIR: &str = "target/tests";
+
+    fn training_data() -> (Array2<f64>, Array1<f64>) {
+        let xt = array![[0.0], [1.0], [2.0], [3.0], [4.0]];
+        let yt = xt.column(0).mapv(|v: f64| v * v.sin());
+        (xt, yt)
+    }
+
+    fn gp() -> GaussianProcess<f64, ConstantMean, SquaredExponentialCorr> {
+        let (xt, yt) = training_data();
+        GaussianProcess::<f64, ConstantMean, SquaredExponentialCorr>::params(
+            ConstantMean::default(),
+            SquaredExponentialCorr::default(),
+        )
+        .fit(&Dataset::new(xt, yt))
+        .expect("GP fitted")
+    }
+
+    #[test]
+    fn test_save_load_gp() {
+        std::fs::create_dir_all(TEST_DIR).ok();
+        let gp = gp();
+        let xtest = Array1::linspace(0., 4., 10).insert_axis(Axis(1));
+        for (format, ext) in [(GpFileFormat::Json, "json"), (GpFileFormat::Binary, "bin")] {
+            let filename = format!("{TEST_DIR}/saved_gp.{ext}");
+            gp.save(&filename, format).expect("GP saved");
+
+            let header = GpFileHeader::read(&filename, format).expect("header read");
+            assert_eq!(header.format_version(), GP_FILE_FORMAT_VERSION);
+            assert_eq!(header.crate_version(), env!("CARGO_PKG_VERSION"));
+            assert_eq!(header.model(), "GaussianProcess");
+            assert_eq!(header.float(), "f64");
+            assert_eq!(header.mean(), "ConstantMean");
+            assert_eq!(header.corr(), "SquaredExponential");
+
+            let loaded = GaussianProcess::<f64, ConstantMean, SquaredExponentialCorr>::load(
+                &filename, format,
+            )
+            .expect("GP loaded");
+            assert_abs_diff_eq!(
+                gp.predict(&xtest).unwrap(),
+                loaded.predict(&xtest).unwrap(),
+                epsilon = 1e-10
+            );
+
+            // Correlation models are not distinguished by serde, checked against the header
+            let res = GaussianProcess::<f64, ConstantMean, Matern52Corr>::load(&filename, format);
+            assert!(matches!(res, Err(GpError::LoadFormatError(_))));
+            let res = GaussianProcess::<f32, ConstantMean, SquaredExponentialCorr>::load(
+                &filename, format,
+            );
+            assert!(matches!(res, Err(GpError::LoadFormatError(_))));
+            let res = SparseGaussianProcess::<f64, SquaredExponentialCorr>::load(&filename, format);
+            assert!(matches!(res, Err(GpError::LoadFormatError(_))));
+        }
+    }
+
+    #[test]
+    fn test_save_load_sgp() {
+        std::fs::create_dir_all(TEST_DIR).ok();
+        let (xt, yt) = training_data();
+        let sgp = SparseGaussianProcess::<f64, Matern52Corr>::params(
+            Matern52Corr::default(),
+            Inducings::Located(array![[0.5], [2.5], [3.5]]),
+        )
+        .sparse_method(SparseMethod::Vfe)
+        .fit(&Dataset::new(xt, yt))
+        .expect("SGP fitted");
+        let filename = format!("{TEST_DIR}/saved_sgp.bin");
+        sgp.save(&filename, GpFileFormat::Binary)
+            .expect("SGP saved");
+        let loaded =
+            SparseGaussianProcess::<f64, Matern52Corr>::load(&filename, GpFileFormat::Binary)
+                .expect("SGP loaded");
+        let xtest = Array1::linspace(0., 4., 10).insert_axis(Axis(1));
+        assert_abs_diff_eq!(
+            sgp.predict(&xtest).unwrap(),
+            loaded.predict(&xtest).unwrap()
+        );
+        assert_abs_diff_eq!(
+            sgp.predict_var(&xtest).unwrap(),
+            loaded.predict_var(&xtest).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_load_unsupported_version() {
+        std::fs::create_dir_all(TEST_DIR).ok();
+        let gp = gp();
+        let mut header = GpFileHeader::new(&gp);
+        header.format_version = GP_FILE_FORMAT_VERSION + 1;
+        header.crate_version = "99.0.0".to_string();
+        let filename = format!("{TEST_DIR}/saved_gp_future.json");
+        let bytes = serde_json::to_vec(&GpFileRef { header, model: &gp }).unwrap();
+        std::fs::write(&filename, bytes).unwrap();
+        match GaussianProcess::<f64, ConstantMean, SquaredExponentialCorr>::load(
+            &filename,
+            GpFileFormat::Json,
+        ) {
+            Err(GpError::LoadFormatError(msg)) => assert!(msg.contains("99.0.0")),
+            _ => panic!("Loading should fail with a format error"),
+        }
+    }
+
+    #[test]
+    fn test_load_legacy_surrogate() {
+        std::fs::create_dir_all(TEST_DIR).ok();
+        let gp = gp();
+        // Json written by egobox-moe GpSurrogate::save without versioned header
+        let filename = format!("{TEST_DIR}/saved_gp_legacy.json");
+        let legacy = serde_json::json!({ "GpConstantSquaredExponentialSurrogate": &gp });
+        std::fs::write(&filename, serde_json::to_vec(&legacy).unwrap()).unwrap();
+
+        let loaded = GaussianProcess::<f64, ConstantMean, SquaredExponentialCorr>::load(
+            &filename,
+            GpFileFormat::Json,
+        )
+        .expect("legacy GP loaded");
+        let xtest = Array1::linspace(0., 4., 10).insert_axis(Axis(1));
+        assert_abs_diff_eq!(
+            gp.predict(&xtest).unwrap(),
+            loaded.predict(&xtest).unwrap(),
+            epsilon = 1e-10
+        );
+
+        let res =
+            GaussianProcess::<f64, ConstantMean, Matern52Corr>::load(&filename, GpFileFormat::Json);
+        assert!(matches!(res, Err(GpError::LoadFormatError(_))));
+    }
+}
@@ -0,0 +1,533 @@
+use crate::algorithm::{noisy_correlation_matrix, reduced_likelihood_from_corr};
+use crate::correlation_models::*;
+use crate::errors::{GpError, Result};
+use crate::mean_models::*;
+use crate::optimization::{CobylaParams, optimize_params, prepare_multistart};
+use crate::parameters::GpValidParams;
+use crate::robust_parameters::{RobustGpParams, RobustGpValidParams, RobustLikelihood};
+use crate::utils::DistanceMatrix;
+use crate::{GP_COBYLA_MIN_EVAL, GaussianProcess, GpSamplePaths, ThetaTuning};
+
+use linfa::prelude::{Dataset, DatasetBase, Fit, Float, PredictInplace};
+use linfa_linalg::triangular::*;
+use ndarray::{Array, Array1, Array2, Array3, ArrayBase, Axis, Data, Ix1, Ix2, Zip};
+use ndarray_rand::rand::Rng;
+use rayon::prelude::*;
+
+use log::debug;
+#[cfg(feature = "serializable")]
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Initial noise to process variance ratio
+const NOISE_RATIO_INIT: f64 = 1e-2;
+/// Bounds of the noise to process variance ratio
+const NOISE_RATIO_BOUNDS: (f64, f64) = (1e-6, 1e1);
+/// Tolerance on weights change used to stop reweighting iterations
+const WEIGHTS_TOL: f64 = 1e-3;
+/// Lower bound of weights to keep noise variances finite
+const WEIGHTS_MIN: f64 = 1e-8;
+
+/// A GP model robust to outliers in training outputs.
+///
+/// Observations are assumed to be corrupted by a heavy-tailed noise (Student-t likelihood)
+/// or are fitted with a Huber loss. Each training point gets a weight in ]0, 1] which
+/// scales down its noise precision: outliers end up with small weights and hardly
+/// influence the predictions.
+///
+/// # Implementation
+///
+/// Student-t noise is represented as a scale mixture of gaussians, the model is trained
+/// by expectation-maximization (iteratively reweighted GP):
+/// * M-step: GP hyperparameters theta and noise to process variance ratio are optimized
+///   given heteroscedastic noise variances `sigma_n^2 / w_i` (noise variance `sigma_n^2`),
+/// * E-step: weights are updated from the expected squared residuals `E[r_i^2]` at training points,
+///   `w_i = (nu + 1) / (nu + E[r_i^2] / sigma_n^2)` for Student-t likelihood
+///   or `w_i = min(1, delta * sigma_n / |r_i|)` for Huber loss.
+///
+/// Iterations stop when weights are stabilized or the maximum number of iterations is reached.
+/// With a [RobustLikelihood::Gaussian] likelihood, weights stay equal to 1 and the model is
+/// a GP regression with an estimated homoscedastic noise variance (single M-step).
+/// Predictions are those of a GP with the resulting heteroscedastic noise, the predicted
+/// variance being the variance of the latent function.
+///
+/// # Example
+///
+/// ```no_run
+/// use egobox_gp::{correlation_models::*, mean_models::*, RobustGaussianProcess, RobustLikelihood};
+/// use linfa::prelude::*;
+/// use ndarray::{array, Array1, Array2, Axis};
+///
+/// let xt: Array2<f64> = array![[0.0], [0.1], [0.2], [0.3], [0.4], [0.5], [0.6], [0.7], [0.8], [0.9], [1.0]];
+/// let mut yt = xt.column(0).mapv(|v| (6. * v).sin());
+/// yt[4] = 10.; // spurious value
+///
+/// let gp = RobustGaussianProcess::<f64, ConstantMean, SquaredExponentialCorr>::params(
+///         ConstantMean::default(),
+///         SquaredExponentialCorr::default())
+///     .likelihood(RobustLikelihood::StudentT { nu: 4. })
+///     .fit(&Dataset::new(xt, yt))
+///     .expect("Robust GP trained");
+///
+/// // outlier is downweighted
+/// println!("weights = {}", gp.weights());
+/// let ypred = gp.predict(&Array1::linspace(0., 1., 50).insert_axis(Axis(1)));
+/// ```
+///
+/// # Reference
+///
+/// Jylänki, P., Vanhatalo, J., Vehtari, A. [Robust Gaussian process regression with a Student-t likelihood](https://jmlr.org/papers/v12/jylanki11a.html),
+/// Journal of Machine Learning Research 12:3227-3257, 2011.
+#[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serializable",
+    derive(Serialize, Deserialize),
+    serde(bound(
+        serialize = "F: Serialize, Mean: Serialize, Corr: Serialize",
+        deserialize = "F: Deserialize<'de>, Mean: Deserialize<'de>, Corr: Deserialize<'de>"
+    ))
+)]
+pub struct RobustGaussianProcess<F: Float, Mean: RegressionModel<F>, Corr: CorrelationModel<F>> {
+    /// GP conditioned on heteroscedastic noisy observations
+    gp: GaussianProcess<F, Mean, Corr>,
+    /// Robust likelihood
+    likelihood: RobustLikelihood<F>,
+    /// Weights of training points, small values denote outliers
+    weights: Array1<F>,
+    /// Ratio of noise variance to process variance
+    noise_ratio: F,
+}
+
+impl<F: Float, Mean: RegressionModel<F>, Corr: CorrelationModel<F>> fmt::Display
+    for RobustGaussianProcess<F, Mean, Corr>
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "RobustGP(gp={}, likelihood={:?}, noise_variance={})",
+            self.gp,
+            self.likelihood,
+            self.noise_variance()
+        )
+    }
+}
+
+impl<F: Float, Mean: RegressionModel<F>, Corr: CorrelationModel<F>>
+    RobustGaussianProcess<F, Mean, Corr>
+{
+    /// Robust GP parameters contructor
+    pub fn params<NewMean: RegressionModel<F>, NewCorr: CorrelationModel<F>>(
+        mean: NewMean,
+        corr: NewCorr,
+    ) -> RobustGpParams<F, NewMean, NewCorr> {
+        RobustGpParams::new(mean, corr)
+    }
+
+    /// Predict output values at n given `x` points of nx components specified as a (n, nx) matrix.
+    /// Returns n scalar output values as a vector (n,).
+    pub fn predict(&self, x: &ArrayBase<impl Data<Elem = F>, Ix2>) -> Result<Array1<F>> {
+        self.gp.predict(x)
+    }
+
+    /// Predict variance values of the latent function at n given `x` points of nx components
+    /// specified as a (n, nx) matrix.
+    /// Returns n variance values as (n,) column vector.
+    pub fn predict_var(&self, x: &ArrayBase<impl Data<Elem = F>, Ix2>) -> Result<Array1<F>> {
+        self.gp.predict_var(x)
+    }
+
+    /// Predict derivatives at a set of point `x` specified as a (n, nx) matrix where x has nx components.
+    /// Returns a (n, nx) matrix containing output derivatives at x wrt each nx components
+    pub fn predict_gradients(&self, x: &ArrayBase<impl Data<Elem = F>, Ix2>) -> Array2<F> {
+        self.gp.predict_gradients(x)
+    }
+
+    /// Predict variance derivatives at a set of points `x` specified as a (n, nx) matrix where x has nx components.
+    /// Returns a (n, nx) matrix containing variance derivatives at `x` wrt each nx components
+    pub fn predict_var_gradients(&self, x: &ArrayBase<impl Data<Elem = F>, Ix2>) -> Array2<F> {
+        self.gp.predict_var_gradients(x)
+    }
+
+    /// Predict hessians at a set of points `x` specified as a (n, nx) matrix.
+    /// Returns a (n, nx, nx) array containing output hessians at `x`
+    pub fn predict_hessians(&self, x: &ArrayBase<impl Data<Elem = F>, Ix2>) -> Array3<F> {
+        self.gp.predict_hessians(x)
+    }
+
+    /// Predict variance hessians at a set of points `x` specified as a (n, nx) matrix.
+    /// Returns a (n, nx, nx) array containing variance hessians at `x`
+    pub fn predict_var_hessians(&self, x: &ArrayBase<impl Data<Elem = F>, Ix2>) -> Array3<F> {
+        self.gp.predict_var_hessians(x)
+    }
+
+    /// Sample the latent function at given `x` points of nx components specified as a (n, nx) matrix.
+    /// Returns n_traj samples as a (n, n_traj) matrix
+    pub fn sample(&self, x: &ArrayBase<impl Data<Elem = F>, Ix2>, n_traj: usize) -> Array2<F> {
+        self.gp.sample(x, n_traj)
+    }
+
+    /// Draw `n_traj` posterior sample paths of the latent function,
+    /// see [GaussianProcess::sample_paths]
+    pub fn sample_paths<R: Rng>(
+        &self,
+        n_traj: usize,
+        n_features: usize,
+        rng: &mut R,
+    ) -> Result<GpSamplePaths<F, Mean, Corr>> {
+        self.gp.sample_paths(n_traj, n_features, rng)
+    }
+
+    /// Retrieve optimized hyperparameters theta
+    pub fn theta(&self) -> &Array1<F> {
+        self.gp.theta()
+    }
+
+    /// Estimated process variance
+    pub fn variance(&self) -> F {
+        self.gp.variance()
+    }
+
+    /// Estimated noise variance of a non-outlier point (i.e. with weight 1)
+    pub fn noise_variance(&self) -> F {
+        self.noise_ratio * self.gp.variance()
+    }
+
+    /// Retrieve reduced likelihood value
+    pub fn likelihood(&self) -> F {
+        self.gp.likelihood()
+    }
+
+    /// Robust likelihood used to train the model
+    pub fn robust_likelihood(&self) -> &RobustLikelihood<F> {
+        &self.likelihood
+    }
+
+    /// Weights of training points in ]0, 1]: the smaller the weight,
+    /// the more the point is considered as an outlier.
+    /// Noise variance of the ith training point is `noise_variance / weights[i]`.
+    pub fn weights(&self) -> &Array1<F> {
+        &self.weights
+    }
+
+    /// Retrieve number of PLS components 1 <= n <= x dimension
+    pub fn kpls_dim(&self) -> Option<usize> {
+        self.gp.kpls_dim()
+    }
+
+    /// Retrieve input and output dimensions
+    pub fn dims(&self) -> (usize, usize) {
+        self.gp.dims()
+    }
+
+    /// Underlying GP conditioned on weighted noisy observations
+    pub fn gp(&self) -> &GaussianProcess<F, Mean, Corr> {
+        &self.gp
+    }
+}
+
+impl<F, D, Mean, Corr> PredictInplace<ArrayBase<D, Ix2>, Array1<F>>
+    for RobustGaussianProcess<F, Mean, Corr>
+where
+    F: Float,
+    D: Data<Elem = F>,
+    Mean: RegressionModel<F>,
+    Corr: CorrelationModel<F>,
+{
+    fn predict_inplace(&self, x: &ArrayBase<D, Ix2>, y: &mut Array1<F>) {
+        assert_eq!(
+            x.nrows(),
+            y.len(),
+            "The number of data points must match the number of output targets."
+        );
+
+        let values = self.predict(x).expect("Robust GP Prediction");
+        *y = values;
+    }
+
+    fn default_target(&self, x: &ArrayBase<D, Ix2>) -> Array1<F> {
+        Array1::zeros((x.nrows(),))
+    }
+}
+
+impl<F: Float, Mean: RegressionModel<F>, Corr: CorrelationModel<F>, D: Data<Elem = F>>
+    Fit<ArrayBase<D, Ix2>, ArrayBase<D, Ix1>, GpError> for RobustGpValidParams<F, Mean, Corr>
+{
+    type Object = RobustGaussianProcess<F, Mean, Corr>;
+
+    /// Fit GP hyperparameters and outlier weights by expectation-maximization
+    fn fit(
+        &self,
+        dataset: &DatasetBase<ArrayBase<D, Ix2>, ArrayBase<D, Ix1>>,
+    ) -> Result<Self::Object> {
+        let x = dataset.records();
+        let y = dataset.targets();
+        let gp_params = self.gp_params();
+
+        // Interpolating GP with initial theta gives normalization, PLS rotation and trend
+        let dim = gp_params.kpls_dim().copied().unwrap_or(x.ncols());
+        let init = gp_params.theta_tuning().init();
+        let theta0 = if init.len() == 1 {
+            Array1::from_elem(dim, init[0])
+        } else if init.len() == dim {
+            init.to_owned()
+        } else {
+            return Err(GpError::InvalidValueError(format!(
+                "Initial guess for theta should be either 1-dim or dim of xtrain ({dim}), got {}",
+                init.len()
+            )));
+        };
+        let gp0 = GpValidParams {
+            theta_tuning: ThetaTuning::Fixed(theta0.clone()),
+            ..gp_params.clone()
+        }
+        .fit(&Dataset::new(x.to_owned(), y.to_owned()))?;
+
+        let xtrain = &gp0.xt_norm;
+        let ytrain = &gp0.yt_norm;
+        let w_star = &gp0.w_star;
+        let corr = &gp_params.corr;
+        let nugget = gp_params.nugget;
+        let x_distances = DistanceMatrix::new(&xtrain.data);
+        // gp0 mean model is already normalized
+        let fx = gp0.params.mean.value(&xtrain.data);
+
+        // Optimized parameters are active theta components then noise ratio
+        let (active, mut param_bounds): (Vec<_>, Vec<_>) = gp_params
+            .theta_tuning()
+            .active_bounds(dim)?
+            .into_iter()
+            .unzip();
+        param_bounds.push((F::cast(NOISE_RATIO_BOUNDS.0), F::cast(NOISE_RATIO_BOUNDS.1)));
+
+        let n_obs = x.nrows();
+        let mut theta = theta0;
+        let mut noise_ratio = F::cast(NOISE_RATIO_INIT);
+        let mut weights = Array1::<F>::ones(n_obs);
+        let mut iter = 0;
+        let inner_params = loop {
+            // M-step: optimize log10 of theta active components and noise ratio given weights
+            let base: f64 = 10.;
+            let objfn = |p: &[f64], _gradient: Option<&mut [f64]>, _params: &mut ()| -> f64 {
+                let mut theta = theta.to_owned();
+                std::iter::zip(&active, p).for_each(|(&i, pi)| theta[i] = F::cast(base.powf(*pi)));
+                let noise_ratio = F::cast(base.powf(p[active.len()]));
+                if theta.iter().any(|v| v.is_nan()) || noise_ratio.is_nan() {
+                    return f64::INFINITY;
+                }
+                let rxx = corr.value(&x_distances.d, &theta, w_star);
+                let r_mx =
+                    noisy_correlation_matrix(&rxx, &x_distances, nugget, noise_ratio, &weights);
+                match reduced_likelihood_from_corr(&fx, r_mx, ytrain) {
+                    Ok(r) => -f64::cast(r.0),
+                    Err(_) => f64::INFINITY,
+                }
+            };
+            let mut param0 = active.iter().map(|&i| theta[i]).collect::<Vec<_>>();
+            param0.push(noise_ratio);
+            let param0 = Array1::from_vec(param0);
+            // Multistart only on first iteration then start from current estimation
+            let n_start = if iter == 0 { gp_params.n_start() } else { 0 };
+            let (param_inits, log_bounds) = prepare_multistart(n_start, &param0, &param_bounds);
+            let opt_params = (0..param_inits.nrows())
+                .into_par_iter()
+                .map(|i| {
+                    optimize_params(
+                        objfn,
+                        &param_inits.row(i).to_owned(),
+                        &log_bounds,
+                        CobylaParams {
+                            maxeval: (10 * param_inits.ncols())
+                                .clamp(GP_COBYLA_MIN_EVAL, gp_params.max_eval()),
+                            ..CobylaParams::default()
+                        },
+                    )
+                })
+                .reduce(
+                    || (f64::INFINITY, Array::ones((param_inits.ncols(),))),
+                    |a, b| if b.0 < a.0 { b } else { a },
+                );
+            if opt_params.0.is_finite() {
+                let opt_params = opt_params.1.mapv(|v| F::cast(base.powf(v)));
+                std::iter::zip(&active, &opt_params).for_each(|(&i, pi)| theta[i] = *pi);
+                noise_ratio = opt_params[active.len()];
+            }
+
+            let rxx = corr.value(&x_distances.d, &theta, w_star);
+            let r_mx = noisy_correlation_matrix(&rxx, &x_distances, nugget, noise_ratio, &weights);
+            let (_, inner_params) = reduced_likelihood_from_corr(&fx, r_mx, ytrain)?;
+            iter += 1;
+            if iter > self.max_iter() || *self.likelihood() == RobustLikelihood::Gaussian {
+                break inner_params;
+            }
+
+            // E-step: update weights from expected squared residuals at training points
+            // r = D.gamma and E[r^2] = r^2 + s2 * (D - D^2 diag(C^-1)) with D = noise_ratio / w
+            let linv = inner_params
+                .r_chol
+                .solve_triangular(&Array2::eye(n_obs), UPLO::Lower)?;
+            let cinv_diag = linv.mapv(|v| v * v).sum_axis(Axis(0));
+            let s2 = inner_params.sigma2 / (ytrain.std[0] * ytrain.std[0]);
+            let noise2 = noise_ratio * s2;
+            let new_weights = Zip::from(&weights)
+                .and(inner_params.gamma.column(0))
+                .and(&cinv_diag)
+                .map_collect(|w, g, ci| {
+                    let d = noise_ratio / *w;
+                    let r = d * *g;
+                    let v = (s2 * (d - d * d * *ci)).max(F::zero());
+                    self.likelihood()
+                        .weight(r * r + v, noise2)
+                        .min(F::one())
+                        .max(F::cast(WEIGHTS_MIN))
+                });
+            let delta = Zip::from(&new_weights)
+                .and(&weights)
+                .fold(F::zero(), |acc, a, b| acc.max((*a - *b).abs()));
+            debug!("Robust GP iter {iter}: noise ratio={noise_ratio}, weights change={delta}");
+            weights = new_weights;
+            if delta < F::cast(WEIGHTS_TOL) {
+                // Final GP consistent with last weights
+                let r_mx =
+                    noisy_correlation_matrix(&rxx, &x_distances, nugget, noise_ratio, &weights);
+                break reduced_likelihood_from_corr(&fx, r_mx, ytrain)?.1;
+            }
+        };
+
+        let likelihood = {
+            let rxx = corr.value(&x_distances.d, &theta, w_star);
+            let r_mx = noisy_correlation_matrix(&rxx, &x_distances, nugget, noise_ratio, &weights);
+            reduced_likelihood_from_corr(&fx, r_mx, ytrain)?.0
+        };
+        let gp = GaussianProcess {
+            theta,
+            likelihood,
+            inner_params,
+            params: GpValidParams {
+                theta_tuning: gp_params.theta_tuning.clone(),
+                ..gp0.params.clone()
+            },
+            ..gp0
+        };
+        Ok(RobustGaussianProcess {
+            gp,
+            likelihood: *self.likelihood(),
+            weights,
+            noise_ratio,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+    use ndarray::{Array, array};
+
+    fn sin6x(x: &Array2<f64>) -> Array1<f64> {
+        x.column(0).mapv(|v| (6. * v).sin())
+    }
+
+    fn outlier_data() -> (Array2<f64>, Array1<f64>) {
+        let xt = Array::linspace(0., 1., 21).insert_axis(Axis(1));
+        let mut yt = sin6x(&xt);
+        yt[5] += 3.;
+        yt[14] -= 2.5;
+        (xt, yt)
+    }
+
+    #[test]
+    fn test_robust_gp_student_t() {
+        let (xt, yt) = outlier_data();
+
+        let gp = GaussianProcess::<f64, ConstantMean, SquaredExponentialCorr>::params(
+            ConstantMean::default(),
+            SquaredExponentialCorr::default(),
+        )
+        .fit(&Dataset::new(xt.clone(), yt.clone()))
+        .expect("GP fitted");
+        let rgp = RobustGaussianProcess::<f64, ConstantMean, SquaredExponentialCorr>::params(
+            ConstantMean::default(),
+            SquaredExponentialCorr::default(),
+        )
+        .likelihood(RobustLikelihood::StudentT { nu: 4. })
+        .fit(&Dataset::new(xt.clone(), yt.clone()))
+        .expect("Robust GP fitted");
+        println!("{rgp}");
+        println!("weights = {}", rgp.weights());
+
+        // Outliers are identified
+        let weights = rgp.weights();
+        assert!(weights[5] < 0.2 && weights[14] < 0.2);
+        weights
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| *i != 5 && *i != 14)
+            .for_each(|(_, w)| assert!(*w > 0.5, "{w}"));
+
+        // Robust predictions are closer to the truth
+        let x = Array::linspace(0., 1., 101).insert_axis(Axis(1));
+        let ytrue = sin6x(&x);
+        let err = (gp.predict(&x).unwrap() - &ytrue).mapv(|v| v * v).sum();
+        let rerr = (rgp.predict(&x).unwrap() - &ytrue).mapv(|v| v * v).sum();
+        assert!(rerr < 0.1 * err, "{rerr} < 0.1 * {err}");
+        assert_abs_diff_eq!(rgp.predict(&x).unwrap(), ytrue, epsilon = 0.1);
+    }
+
+    #[test]
+    fn test_robust_gp_huber() {
+        let (xt, yt) = outlier_data();
+
+        let rgp = RobustGaussianProcess::<f64, ConstantMean, Matern52Corr>::params(
+            ConstantMean::default(),
+            Matern52Corr::default(),
+        )
+        .likelihood(RobustLikelihood::Huber { delta: 1.5 })
+        .fit(&Dataset::new(xt.clone(), yt.clone()))
+        .expect("Robust GP fitted");
+
+        let weights = rgp.weights();
+        assert!(weights[5] < 0.5 && weights[14] < 0.5);
+        assert!(rgp.noise_variance() > 0.);
+        let x = Array::linspace(0., 1., 101).insert_axis(Axis(1));
+        assert!(rgp.predict_var(&x).unwrap().iter().all(|v| *v >= 0.));
+        assert_abs_diff_eq!(rgp.predict(&x).unwrap(), sin6x(&x), epsilon = 0.3);
+    }
+
+    #[test]
+    fn test_robust_gp_gaussian() {
+        let xt = Array::linspace(0., 1., 21).insert_axis(Axis(1));
+        let noise = Array::from_iter((0..21).map(|i| 0.1 * if i % 2 == 0 { 1. } else { -1. }));
+        let yt = sin6x(&xt) + &noise;
+
+        let rgp = RobustGaussianProcess::<f64, ConstantMean, SquaredExponentialCorr>::params(
+            ConstantMean::default(),
+            SquaredExponentialCorr::default(),
+        )
+        .likelihood(RobustLikelihood::Gaussian)
+        .fit(&Dataset::new(xt.clone(), yt.clone()))
+        .expect("Noisy GP fitted");
+
+        // No observation is downweighted, noise is smoothed out
+        assert!(rgp.weights().iter().all(|w| *w == 1.));
+        assert!(rgp.noise_variance() > 1e-3);
+        let x = Array::linspace(0., 1., 101).insert_axis(Axis(1));
+        assert_abs_diff_eq!(rgp.predict(&x).unwrap(), sin6x(&x), epsilon = 0.1);
+    }
+
+    #[test]
+    fn test_robust_gp_clean_data() {
+        let xt = array![[0.0], [0.1], [0.25], [0.4], [0.5], [0.7], [0.85], [1.0]];
+        let yt = sin6x(&xt);
+
+        let rgp = RobustGaussianProcess::<f64, ConstantMean, SquaredExponentialCorr>::params(
+            ConstantMean::default(),
+            SquaredExponentialCorr::default(),
+        )
+        .fit(&Dataset::new(xt.clone(), yt.clone()))
+        .expect("Robust GP fitted");
+
+        // No outlier: model nearly interpolates
+        assert!(rgp.weights().iter().all(|w| *w > 0.5));
+        assert_abs_diff_eq!(rgp.predict(&xt).unwrap(), yt, epsilon = 5e-2);
+    }
+}
@@ -0,0 +1,226 @@
+use crate::correlation_models::CorrelationModel;
+use crate::errors::{GpError, Result};
+use crate::mean_models::RegressionModel;
+use crate::parameters::GpValidParams;
+use crate::{GP_COBYLA_MAX_EVAL, GP_COBYLA_MIN_EVAL, GP_OPTIM_N_START, ThetaTuning};
+use linfa::{Float, ParamGuard};
+#[cfg(feature = "serializable")]
+use serde::{Deserialize, Serialize};
+
+/// Default maximum number of reweighting iterations of the robust GP fitting
+pub const GP_ROBUST_MAX_ITER: usize = 20;
+
+/// Likelihood used to downweight outliers in robust GP
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serializable", derive(Serialize, Deserialize))]
+pub enum RobustLikelihood<F: Float> {
+    /// Student-t likelihood with `nu` degrees of freedom,
+    /// the smaller `nu` the heavier the tails
+    StudentT { nu: F },
+    /// Huber loss, residuals greater than `delta` noise standard deviations
+    /// are penalized linearly instead of quadratically
+    Huber { delta: F },
+    /// Gaussian likelihood, no observation is downweighted:
+    /// GP regression with an estimated homoscedastic noise variance
+    Gaussian,
+}
+
+impl<F: Float> Default for RobustLikelihood<F> {
+    fn default() -> Self {
+        RobustLikelihood::StudentT { nu: F::cast(4.) }
+    }
+}
+
+impl<F: Float> RobustLikelihood<F> {
+    /// Convert likelihood parameters to another float type
+    pub fn cast<G: Float>(&self) -> RobustLikelihood<G> {
+        match self {
+            RobustLikelihood::StudentT { nu } => RobustLikelihood::StudentT { nu: G::cast(*nu) },
+            RobustLikelihood::Huber { delta } => RobustLikelihood::Huber {
+                delta: G::cast(*delta),
+            },
+            RobustLikelihood::Gaussian => RobustLikelihood::Gaussian,
+        }
+    }
+
+    /// Weight of an observation given its expected squared residual `r2`
+    /// and the noise variance `noise2` (both in normalized units)
+    pub(crate) fn weight(&self, r2: F, noise2: F) -> F {
+        match self {
+            RobustLikelihood::StudentT { nu } => (*nu + F::one()) / (*nu + r2 / noise2),
+            RobustLikelihood::Huber { delta } => {
+                let r = (r2 / noise2).sqrt();
+                if r <= *delta { F::one() } else { *delta / r }
+            }
+            RobustLikelihood::Gaussian => F::one(),
+        }
+    }
+}
+
+/// A set of validated robust GP parameters.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serializable",
+    derive(Serialize, Deserialize),
+    serde(bound(
+        serialize = "F: Serialize, Mean: Serialize, Corr: Serialize",
+        deserialize = "F: Deserialize<'de>, Mean: Deserialize<'de>, Corr: Deserialize<'de>"
+    ))
+)]
+pub struct RobustGpValidParams<F: Float, Mean: RegressionModel<F>, Corr: CorrelationModel<F>> {
+    /// Underlying GP parameters
+    pub(crate) gp_params: GpValidParams<F, Mean, Corr>,
+    /// Robust likelihood
+    pub(crate) likelihood: RobustLikelihood<F>,
+    /// Maximum number of reweighting iterations
+    pub(crate) max_iter: usize,
+}
+
+impl<F: Float, Mean: RegressionModel<F>, Corr: CorrelationModel<F>>
+    RobustGpValidParams<F, Mean, Corr>
+{
+    /// Get underlying GP parameters
+    pub fn gp_params(&self) -> &GpValidParams<F, Mean, Corr> {
+        &self.gp_params
+    }
+
+    /// Get robust likelihood
+    pub fn likelihood(&self) -> &RobustLikelihood<F> {
+        &self.likelihood
+    }
+
+    /// Get maximum number of reweighting iterations
+    pub fn max_iter(&self) -> usize {
+        self.max_iter
+    }
+}
+
+#[derive(Clone, Debug)]
+/// The set of hyperparameters that can be specified for the execution of
+/// the [robust GP algorithm](struct.RobustGaussianProcess.html).
+pub struct RobustGpParams<F: Float, Mean: RegressionModel<F>, Corr: CorrelationModel<F>>(
+    RobustGpValidParams<F, Mean, Corr>,
+);
+
+impl<F: Float, Mean: RegressionModel<F>, Corr: CorrelationModel<F>> RobustGpParams<F, Mean, Corr> {
+    /// A constructor for robust GP parameters given mean and correlation models
+    pub fn new(mean: Mean, corr: Corr) -> RobustGpParams<F, Mean, Corr> {
+        Self(RobustGpValidParams {
+            gp_params: GpValidParams {
+                theta_tuning: ThetaTuning::default(),
+                mean,
+                corr,
+                kpls_dim: None,
+                n_start: GP_OPTIM_N_START,
+                max_eval: GP_COBYLA_MAX_EVAL,
+                nugget: F::cast(100.0) * F::epsilon(),
+            },
+            likelihood: RobustLikelihood::default(),
+            max_iter: GP_ROBUST_MAX_ITER,
+        })
+    }
+
+    pub fn new_from_valid(params: &RobustGpValidParams<F, Mean, Corr>) -> Self {
+        Self(params.clone())
+    }
+
+    /// Set robust likelihood
+    pub fn likelihood(mut self, likelihood: RobustLikelihood<F>) -> Self {
+        self.0.likelihood = likelihood;
+        self
+    }
+
+    /// Set maximum number of reweighting iterations
+    pub fn max_iter(mut self, max_iter: usize) -> Self {
+        self.0.max_iter = max_iter;
+        self
+    }
+
+    /// Set theta hyper parameter tuning
+    pub fn theta_tuning(mut self, theta_tuning: ThetaTuning<F>) -> Self {
+        self.0.gp_params.theta_tuning = theta_tuning;
+        self
+    }
+
+    /// Set the number of PLS components.
+    /// Should be 0 < n < pb size (i.e. x dimension)
+    pub fn kpls_dim(mut self, kpls_dim: Option<usize>) -> Self {
+        self.0.gp_params.kpls_dim = kpls_dim;
+        self
+    }
+
+    /// Set the number of internal GP hyperparameter theta optimization restarts
+    pub fn n_start(mut self, n_start: usize) -> Self {
+        self.0.gp_params.n_start = n_start;
+        self
+    }
+
+    /// Set the max number of internal likelihood evaluations during one optimization
+    /// Given max_eval has to be greater than [crate::GP_COBYLA_MIN_EVAL] otherwise
+    /// max_eval is set to [crate::GP_COBYLA_MAX_EVAL].
+    pub fn max_eval(mut self, max_eval: usize) -> Self {
+        self.0.gp_params.max_eval = GP_COBYLA_MIN_EVAL.max(max_eval);
+        self
+    }
+
+    /// Set nugget.
+    ///
+    /// Nugget is used to improve numerical stability
+    pub fn nugget(mut self, nugget: F) -> Self {
+        self.0.gp_params.nugget = nugget;
+        self
+    }
+}
+
+impl<F: Float, Mean: RegressionModel<F>, Corr: CorrelationModel<F>>
+    From<RobustGpValidParams<F, Mean, Corr>> for RobustGpParams<F, Mean, Corr>
+{
+    fn from(valid: RobustGpValidParams<F, Mean, Corr>) -> Self {
+        RobustGpParams(valid.clone())
+    }
+}
+
+impl<F: Float, Mean: RegressionModel<F>, Corr: CorrelationModel<F>> ParamGuard
+    for RobustGpParams<F, Mean, Corr>
+{
+    type Checked = RobustGpValidParams<F, Mean, Corr>;
+    type Error = GpError;
+
+    fn check_ref(&self) -> Result<&Self::Checked> {
+        if let Some(d) = self.0.gp_params.kpls_dim {
+            if d == 0 {
+                return Err(GpError::InvalidValueError(
+                    "`kpls_dim` canot be 0!".to_string(),
+                ));
+            }
+            let theta = self.0.gp_params.theta_tuning().init();
+            if theta.len() > 1 && d > theta.len() {
+                return Err(GpError::InvalidValueError(format!(
+                    "Dimension reduction ({}) should be smaller than expected
+                        training input size infered from given initial theta length ({})",
+                    d,
+                    theta.len()
+                )));
+            };
+        }
+        match self.0.likelihood {
+            RobustLikelihood::StudentT { nu } if nu <= F::zero() => {
+                return Err(GpError::InvalidValueError(format!(
+                    "Student-t degrees of freedom ({nu}) should be strictly positive"
+                )));
+            }
+            RobustLikelihood::Huber { delta } if delta <= F::zero() => {
+                return Err(GpError::InvalidValueError(format!(
+                    "Huber threshold ({delta}) should be strictly positive"
+                )));
+            }
+            _ => (),
+        }
+        Ok(&self.0)
+    }
+
+    fn check(self) -> Result<Self::Checked> {
+        self.check_ref()?;
+        Ok(self.0)
+    }
+}
@@ -0,0 +1,425 @@
+//! Posterior sample paths of GP models given as continuous and differentiable functions.
+//!
+//! The GP prior is approximated with random Fourier features (RFF) drawn from the spectral
+//! density of the correlation model, then conditioned on training data with a pathwise
+//! update (Matheron's rule), see
+//!
+//! Wilson, J., Borovitskiy, V., Terenin, A., Mostowsky, P., & Deisenroth, M. (2020).
+//! [Efficiently sampling functions from Gaussian process posteriors](https://arxiv.org/abs/2002.09309).
+//! In International Conference on Machine Learning (pp. 10292-10302). PMLR.
+//!
+//! Once built, evaluating a path is linear in the number of evaluation points,
+//! which makes paths suitable for Thompson sampling or Monte Carlo analyses
+//! on large sets of points.
+
+use crate::GaussianProcess;
+use crate::correlation_models::CorrelationModel;
+use crate::errors::{GpError, Result};
+use crate::mean_models::RegressionModel;
+use crate::utils::pairwise_differences;
+
+use linfa::Float;
+use ndarray::{Array1, Array2, Array3, ArrayBase, Axis, Data, Ix1, Ix2, Zip, s};
+use ndarray_rand::RandomExt;
+use ndarray_rand::rand::Rng;
+use ndarray_rand::rand_distr::{StandardNormal, Uniform};
+
+/// Default number of random Fourier features used to approximate the GP prior
+pub const GP_RFF_N_FEATURES: usize = 1000;
+
+/// Random Fourier features approximation of GP prior trajectories with unit variance
+#[derive(Clone, Debug)]
+pub(crate) struct RandomFourierFeatures<F: Float> {
+    /// Frequencies (n_features, nx)
+    omega: Array2<F>,
+    /// Phases (n_features,)
+    phases: Array1<F>,
+    /// Weights of the features for each trajectory (n_features, n_traj)
+    weights: Array2<F>,
+}
+
+impl<F: Float> RandomFourierFeatures<F> {
+    /// Draw features of the stationary prior given by the correlation model,
+    /// `theta` parameters and PLS `weights` (nx, h)
+    pub(crate) fn new<Corr: CorrelationModel<F>, R: Rng>(
+        corr: &Corr,
+        theta: &Array1<F>,
+        weights: &Array2<F>,
+        n_features: usize,
+        n_traj: usize,
+        rng: &mut R,
+    ) -> Result<Self> {
+        if n_features == 0 {
+            return Err(GpError::InvalidValueError(
+                "Number of random Fourier features should be strictly positive".to_string(),
+            ));
+        }
+        // Correlation is a product of one-dimensional correlations with scales
+        // theta_l * |weight_j_l|, hence frequencies are sums of scaled independent draws
+        let scales = (theta * weights).mapv(|v| v.abs());
+        let nx = weights.nrows();
+        let mut omega = Array2::zeros((n_features, nx));
+        for scale in scales.columns() {
+            let xi = corr
+                .spectral_samples(n_features * nx, rng)
+                .ok_or_else(|| {
+                    GpError::InvalidValueError(format!(
+                        "Spectral density of {corr} correlation model is unknown"
+                    ))
+                })?
+                .into_shape((n_features, nx))
+                .unwrap();
+            omega = omega + xi * scale;
+        }
+        let phases =
+            Array1::random_using(n_features, Uniform::new(0., 2. * std::f64::consts::PI), rng)
+                .mapv(|v| F::cast(v));
+        let weights = Array2::random_using((n_features, n_traj), StandardNormal, rng)
+            .mapv(|v: f64| F::cast(v));
+        Ok(RandomFourierFeatures {
+            omega,
+            phases,
+            weights,
+        })
+    }
+
+    fn scale(&self) -> F {
+        (F::cast(2.) / F::cast(self.phases.len())).sqrt()
+    }
+
+    /// Prior trajectories values at (n, nx) points, returns a (n, n_traj) matrix
+    pub(crate) fn values(&self, x: &ArrayBase<impl Data<Elem = F>, Ix2>) -> Array2<F> {
+        let scale = self.scale();
+        let features = (x.dot(&self.omega.t()) + &self.phases).mapv(|v| scale * v.cos());
+        features.dot(&self.weights)
+    }
+
+    /// Prior trajectories gradients at a (nx,) point, returns a (nx, n_traj) matrix
+    pub(crate) fn gradients(&self, x: &ArrayBase<impl Data<Elem = F>, Ix1>) -> Array2<F> {
+        let scale = self.scale();
+        let dfeatures = (self.omega.dot(x) + &self.phases).mapv(|v| -scale * v.sin());
+        self.omega
+            .t()
+            .dot(&(&self.weights * &dfeatures.insert_axis(Axis(1))))
+    }
+
+    /// Number of trajectories
+    pub(crate) fn n_traj(&self) -> usize {
+        self.weights.ncols()
+    }
+}
+
+/// Posterior sample paths of a [GaussianProcess] built with [GaussianProcess::sample_paths].
+///
+/// A path is the GP prediction plus the error of the kriging predictor of a prior trajectory,
+/// which accounts for the regression weights uncertainty of universal kriging.
+#[derive(Clone, Debug)]
+pub struct GpSamplePaths<F: Float, Mean: RegressionModel<F>, Corr: CorrelationModel<F>> {
+    /// Conditioning GP
+    gp: GaussianProcess<F, Mean, Corr>,
+    /// Prior trajectories in normalized space
+    rff: RandomFourierFeatures<F>,
+    /// Regression weights of the kriging predictors of prior trajectories (p, n_traj)
+    beta: Array2<F>,
+    /// GP weights of the kriging predictors of prior trajectories (nt, n_traj)
+    gamma: Array2<F>,
+}
+
+impl<F: Float, Mean: RegressionModel<F>, Corr: CorrelationModel<F>> GpSamplePaths<F, Mean, Corr> {
+    pub(crate) fn new<R: Rng>(
+        gp: &GaussianProcess<F, Mean, Corr>,
+        n_traj: usize,
+        n_features: usize,
+        rng: &mut R,
+    ) -> Result<Self> {
+        let rff = RandomFourierFeatures::new(
+            &gp.params.corr,
+            &gp.theta,
+            &gp.w_star,
+            n_features,
+            n_traj,
+            rng,
+        )?;
+
+        // Prior trajectories at training points are perturbed with the nugget (or noise)
+        // part of the correlation matrix R = Rc.Rc^t diagonal
+        let r_chol = &gp.inner_params.r_chol;
+        let mut noise_std = Array1::zeros(r_chol.nrows());
+        Zip::indexed(&mut noise_std).for_each(|i, std| {
+            let row = r_chol.slice(s![i, ..=i]);
+            *std = (row.dot(&row) - F::one()).max(F::zero()).sqrt();
+        });
+        let eps = Array2::random_using((r_chol.nrows(), n_traj), StandardNormal, rng)
+            .mapv(|v: f64| F::cast(v))
+            * noise_std.insert_axis(Axis(1));
+        let targets = rff.values(&gp.xt_norm.data) + eps;
+        let (beta, gamma) = gp._compute_kriging_weights(&targets);
+
+        Ok(GpSamplePaths {
+            gp: gp.clone(),
+            rff,
+            beta,
+            gamma,
+        })
+    }
+
+    /// Number of sample paths
+    pub fn n_traj(&self) -> usize {
+        self.rff.n_traj()
+    }
+
+    /// Evaluate sample paths at n given `x` points of nx components specified as a (n, nx) matrix.
+    /// Returns a (n, n_traj) matrix, one column per path.
+    pub fn predict(&self, x: &ArrayBase<impl Data<Elem = F>, Ix2>) -> Result<Array2<F>> {
+        let gp = &self.gp;
+        let mean = gp.predict(x)?.insert_axis(Axis(1));
+        let xnorm = (x - &gp.xt_norm.mean) / &gp.xt_norm.std;
+        let err = self.rff.values(&xnorm)
+            - gp._compute_correlation(&xnorm).dot(&self.gamma)
+            - gp.params.mean.value(&xnorm).dot(&self.beta);
+        Ok(mean + err * gp.inner_params.sigma2.sqrt())
+    }
+
+    /// Evaluate sample paths gradients at n given `x` points of nx components specified as a (n, nx) matrix.
+    /// Returns a (n, nx, n_traj) array, the last axis being the path index.
+    pub fn predict_gradients(&self, x: &ArrayBase<impl Data<Elem = F>, Ix2>) -> Array3<F> {
+        let gp = &self.gp;
+        let sigma = gp.inner_params.sigma2.sqrt();
+        let mut drv = Array3::zeros((x.nrows(), x.ncols(), self.n_traj()));
+        Zip::from(drv.outer_iter_mut())
+            .and(x.rows())
+            .for_each(|mut drv_i, xi| {
+                let xnorm = (&xi - &gp.xt_norm.mean) / &gp.xt_norm.std;
+                let dr = gp
+                    .params
+                    .corr
+                    .jacobian(&xnorm, &gp.xt_norm.data, &gp.theta, &gp.w_star);
+                let df = gp.params.mean.jacobian(&xnorm);
+                let derr =
+                    self.rff.gradients(&xnorm) - dr.t().dot(&self.gamma) - df.t().dot(&self.beta);
+                let dmean = gp.predict_jacobian(&xi);
+                Zip::from(drv_i.rows_mut())
+                    .and(derr.rows())
+                    .and(dmean.rows())
+                    .and(&gp.xt_norm.std)
+                    .for_each(|mut d, de, dm, std| {
+                        d.assign(&de.mapv(|v| dm[0] + v * sigma / *std));
+                    });
+            });
+        drv
+    }
+}
+
+/// Posterior sample paths of a [SparseGaussianProcess](crate::SparseGaussianProcess)
+/// built with [SparseGaussianProcess::sample_paths](crate::SparseGaussianProcess::sample_paths).
+///
+/// A path is a prior trajectory updated through the inducing values drawn from their
+/// approximate posterior distribution, it represents the latent function (i.e. without noise).
+#[derive(Clone, Debug)]
+pub struct SgpSamplePaths<F: Float, Corr: CorrelationModel<F>> {
+    /// Correlation kernel
+    corr: Corr,
+    /// Parameter of the autocorrelation model
+    theta: Array1<F>,
+    /// Weights in case of KPLS dimension reduction
+    w_star: Array2<F>,
+    /// Estimated gaussian process variance
+    sigma2: F,
+    /// Inducing points
+    inducings: Array2<F>,
+    /// Prior trajectories
+    rff: RandomFourierFeatures<F>,
+    /// Weights of the kernel functions at inducing points (n_inducings, n_traj)
+    weights: Array2<F>,
+}
+
+impl<F: Float, Corr: CorrelationModel<F>> SgpSamplePaths<F, Corr> {
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn new(
+        corr: Corr,
+        theta: Array1<F>,
+        w_star: Array2<F>,
+        sigma2: F,
+        inducings: Array2<F>,
+        rff: RandomFourierFeatures<F>,
+        weights: Array2<F>,
+    ) -> Self {
+        SgpSamplePaths {
+            corr,
+            theta,
+            w_star,
+            sigma2,
+            inducings,
+            rff,
+            weights,
+        }
+    }
+
+    /// Number of sample paths
+    pub fn n_traj(&self) -> usize {
+        self.rff.n_traj()
+    }
+
+    /// Evaluate sample paths at n given `x` points of nx components specified as a (n, nx) matrix.
+    /// Returns a (n, n_traj) matrix, one column per path.
+    pub fn predict(&self, x: &ArrayBase<impl Data<Elem = F>, Ix2>) -> Result<Array2<F>> {
+        let dx = pairwise_differences(x, &self.inducings);
+        let kx = self
+            .corr
+            .value(&dx, &self.theta, &self.w_star)
+            .into_shape((x.nrows(), self.inducings.nrows()))
+            .unwrap()
+            .mapv(|v| v * self.sigma2);
+        Ok(self.rff.values(x) * self.sigma2.sqrt() + kx.dot(&self.weights))
+    }
+
+    /// Evaluate sample paths gradients at n given `x` points of nx components specified as a (n, nx) matrix.
+    /// Returns a (n, nx, n_traj) array, the last axis being the path index.
+    pub fn predict_gradients(&self, x: &ArrayBase<impl Data<Elem = F>, Ix2>) -> Array3<F> {
+        let sigma = self.sigma2.sqrt();
+        let mut drv = Array3::zeros((x.nrows(), x.ncols(), self.n_traj()));
+        Zip::from(drv.outer_iter_mut())
+            .and(x.rows())
+            .for_each(|mut drv_i, xi| {
+                let dk = self
+                    .corr
+                    .jacobian(&xi, &self.inducings, &self.theta, &self.w_star)
+                    .mapv(|v| v * self.sigma2);
+                drv_i.assign(&(self.rff.gradients(&xi) * sigma + dk.t().dot(&self.weights)));
+            });
+        drv
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::correlation_models::{Matern52Corr, SquaredExponentialCorr};
+    use crate::mean_models::{ConstantMean, LinearMean};
+    use crate::{Inducings, SparseGaussianProcess, SparseMethod};
+    use approx::assert_abs_diff_eq;
+    use linfa::prelude::{Dataset, Fit};
+    use ndarray::{Array, array};
+    use ndarray_rand::rand::SeedableRng;
+    use ndarray_rand::rand_distr::Normal;
+    use rand_xoshiro::Xoshiro256Plus;
+
+    fn check_moments(paths: &Array2<f64>, mean: &Array1<f64>, var: &Array1<f64>) {
+        let paths_mean = paths.mean_axis(Axis(1)).unwrap();
+        let paths_var = paths.var_axis(Axis(1), 1.);
+        println!("mean {paths_mean} vs {mean}");
+        println!("var {paths_var} vs {var}");
+        Zip::from(&paths_mean)
+            .and(&paths_var)
+            .and(mean)
+            .and(var)
+            .for_each(|pm, pv, m, v| {
+                assert_abs_diff_eq!(pm, m, epsilon = 0.1 * v.sqrt() + 1e-6);
+                assert_abs_diff_eq!(pv, v, epsilon = 0.2 * v + 1e-6);
+            });
+    }
+
+    #[test]
+    fn test_gp_sample_paths() {
+        let xt = array![[0.0], [1.0], [2.0], [3.0], [4.0]];
+        let yt = xt.mapv(|v: f64| v * v.sin()).remove_axis(Axis(1));
+        let gp = GaussianProcess::<f64, LinearMean, Matern52Corr>::params(
+            LinearMean::default(),
+            Matern52Corr::default(),
+        )
+        .fit(&Dataset::new(xt.clone(), yt.clone()))
+        .expect("GP fit error");
+
+        let mut rng = Xoshiro256Plus::seed_from_u64(42);
+        // Matern 5/2 heavy-tailed spectral density requires more features
+        let paths = gp
+            .sample_paths(2000, 10000, &mut rng)
+            .expect("Sample paths");
+        assert_eq!(paths.n_traj(), 2000);
+
+        // Paths interpolate training data
+        let values = paths.predict(&xt).expect("Paths evaluation");
+        for traj in values.columns() {
+            assert_abs_diff_eq!(traj, yt, epsilon = 1e-4);
+        }
+
+        // Paths follow GP posterior distribution
+        let x = array![[0.5], [1.7], [2.5], [3.2], [4.5]];
+        let values = paths.predict(&x).expect("Paths evaluation");
+        check_moments(
+            &values,
+            &gp.predict(&x).unwrap(),
+            &gp.predict_var(&x).unwrap(),
+        );
+    }
+
+    #[test]
+    fn test_gp_sample_paths_gradients() {
+        let mut rng = Xoshiro256Plus::seed_from_u64(42);
+        let xt: Array2<f64> = Array::random_using((10, 2), Uniform::new(-1., 1.), &mut rng);
+        let yt = xt.map_axis(Axis(1), |x| (2. * x[0]).cos() + (3. * x[1]).sin());
+        let gp = GaussianProcess::<f64, ConstantMean, SquaredExponentialCorr>::params(
+            ConstantMean::default(),
+            SquaredExponentialCorr::default(),
+        )
+        .fit(&Dataset::new(xt, yt))
+        .expect("GP fit error");
+
+        let paths = gp.sample_paths(3, 200, &mut rng).expect("Sample paths");
+        let x = array![[0.1, -0.3], [-0.5, 0.7], [0.8, 0.2]];
+        let grads = paths.predict_gradients(&x);
+        let e = 1e-5;
+        for k in 0..x.ncols() {
+            let mut xp = x.to_owned();
+            xp.column_mut(k).mapv_inplace(|v| v + e);
+            let mut xm = x.to_owned();
+            xm.column_mut(k).mapv_inplace(|v| v - e);
+            let fdiff =
+                (paths.predict(&xp).unwrap() - paths.predict(&xm).unwrap()).mapv(|v| v / (2. * e));
+            assert_abs_diff_eq!(fdiff, grads.slice(s![.., k, ..]), epsilon = 1e-4);
+        }
+    }
+
+    #[test]
+    fn test_sgp_sample_paths() {
+        let mut rng = Xoshiro256Plus::seed_from_u64(42);
+        let nt = 200;
+        let xt: Array2<f64> = Array::random_using((nt, 1), Uniform::new(-1., 1.), &mut rng);
+        let noise = Array::random_using(nt, Normal::new(0., 0.1).unwrap(), &mut rng);
+        let yt = xt.column(0).mapv(|v| (3. * v).sin()) + noise;
+
+        for method in [SparseMethod::Fitc, SparseMethod::Vfe] {
+            let sgp = SparseGaussianProcess::<f64, SquaredExponentialCorr>::params(
+                SquaredExponentialCorr::default(),
+                Inducings::Randomized(8),
+            )
+            .sparse_method(method)
+            .seed(Some(42))
+            .fit(&Dataset::new(xt.clone(), yt.clone()))
+            .expect("SGP fit error");
+
+            let paths = sgp
+                .sample_paths(2000, GP_RFF_N_FEATURES, &mut rng)
+                .expect("Sample paths");
+            let x = array![[-0.9], [-0.4], [0.1], [0.6], [0.95]];
+            let values = paths.predict(&x).expect("Paths evaluation");
+            let mean = sgp.predict(&x).unwrap();
+            // Paths are drawn from the latent function posterior
+            let var = sgp.predict_var(&x).unwrap() - sgp.noise_variance();
+            if method == SparseMethod::Fitc {
+                check_moments(&values, &mean, &var);
+            } else {
+                // VFE predictive variance is not the latent posterior variance
+                // (Woodbury inverse stores Kmm^-1 + Sigma), only mean is checked
+                let paths_mean = values.mean_axis(Axis(1)).unwrap();
+                assert_abs_diff_eq!(paths_mean, mean, epsilon = 0.05);
+            }
+
+            let grads = paths.predict_gradients(&x);
+            let e = 1e-6;
+            let fdiff = (paths.predict(&(&x + e)).unwrap() - paths.predict(&(&x - e)).unwrap())
+                .mapv(|v| v / (2. * e));
+            assert_abs_diff_eq!(fdiff, grads.slice(s![.., 0, ..]), epsilon = 1e-3);
+        }
+    }
+}
@@ -1,16 +1,24 @@
 use crate::ThetaTuning;
 use crate::errors::{GpError, Result};
 use crate::optimization::{CobylaParams, optimize_params, prepare_multistart};
+use crate::sample_paths::{RandomFourierFeatures, SgpSamplePaths};
 use crate::sparse_parameters::{Inducings, ParamTuning, SgpParams, SgpValidParams, SparseMethod};
+#[cfg(feature = "persistent")]
+use crate::{
+    GpFileFormat,
+    persistence::{load_model, save_model},
+};
 use crate::{GpSamplingMethod, correlation_models::*, sample, utils::pairwise_differences};
 use finitediff::FiniteDiff;
 use linfa::prelude::{Dataset, DatasetBase, Fit, Float, PredictInplace};
 use linfa_linalg::{cholesky::*, triangular::*};
 use linfa_pls::PlsRegression;
-use ndarray::{Array, Array1, Array2, ArrayBase, ArrayView2, Axis, Data, Ix1, Ix2, Zip, s};
+use ndarray::{Array, Array1, Array2, Array3, ArrayBase, ArrayView2, Axis, Data, Ix1, Ix2, Zip, s};
 use ndarray_einsum_beta::*;
-use ndarray_rand::rand::SeedableRng;
+use ndarray_rand::RandomExt;
 use ndarray_rand::rand::seq::SliceRandom;
+use ndarray_rand::rand::{Rng, SeedableRng};
+use ndarray_rand::rand_distr::StandardNormal;
 use rand_xoshiro::Xoshiro256Plus;
 
 use log::debug;
@@ -299,12 +307,12 @@ impl<F: Float, Corr: CorrelationModel<F>> SparseGaussianProcess<F, Corr> {
         let f = |x: &Array1<f64>| -> f64 {
             let x = x.to_owned().insert_axis(Axis(0)).mapv(|v| F::cast(v));
             let v = self.predict(&x).unwrap()[0];
-            unsafe { *(&v as *const F as *const f64) }
+            f64::cast(v)
         };
         Zip::from(drv.rows_mut())
             .and(x.rows())
             .for_each(|mut row, xi| {
-                let xi = xi.mapv(|v| unsafe { *(&v as *const F as *const f64) });
+                let xi = xi.mapv(f64::cast);
                 let grad = xi.central_diff(&f).mapv(|v| F::cast(v));
                 row.assign(&grad);
             });
@@ -315,18 +323,71 @@ impl<F: Float, Corr: CorrelationModel<F>> SparseGaussianProcess<F, Corr> {
         let f = |x: &Array1<f64>| -> f64 {
             let x = x.to_owned().insert_axis(Axis(0)).mapv(|v| F::cast(v));
             let v = self.predict_var(&x).unwrap()[0];
-            unsafe { *(&v as *const F as *const f64) }
+            f64::cast(v)
         };
         Zip::from(drv.rows_mut())
             .and(x.rows())
             .for_each(|mut row, xi| {
-                let xi = xi.mapv(|v| unsafe { *(&v as *const F as *const f64) });
+                let xi = xi.mapv(f64::cast);
                 let grad = xi.central_diff(&f).mapv(|v| F::cast(v));
                 row.assign(&grad);
             });
         drv
     }
 
+    /// Predict hessians at a set of points `x` specified as a (n, nx) matrix where x has nx components.
+    /// Returns a (n, nx, nx) array containing the second order derivatives of the output at each x point
+    pub fn predict_hessians(&self, x: &ArrayBase<impl Data<Elem = F>, Ix2>) -> Array3<F> {
+        let nx = x.ncols();
+        let nz = self.inducings.nrows();
+        let mut hess = Array3::<F>::zeros((x.nrows(), nx, nx));
+        Zip::from(hess.outer_iter_mut())
+            .and(x.rows())
+            .for_each(|mut h, xi| {
+                // mu(x) = sigma2 * r(x, Z) . vec
+                let d2k = self
+                    .corr
+                    .hessian(&xi, &self.inducings, &self.theta, &self.w_star)
+                    .into_shape((nz, nx * nx))
+                    .unwrap();
+                let d2mu = d2k.t().dot(&self.w_data.vec).mapv(|v| v * self.sigma2);
+                h.assign(&d2mu.into_shape((nx, nx)).unwrap());
+            });
+        hess
+    }
+
+    /// Predict variance hessians at a set of points `x` specified as a (n, nx) matrix where x has nx components.
+    /// Returns a (n, nx, nx) array containing the second order derivatives of the variance at each x point
+    pub fn predict_var_hessians(&self, x: &ArrayBase<impl Data<Elem = F>, Ix2>) -> Array3<F> {
+        let nx = x.ncols();
+        let nz = self.inducings.nrows();
+        let mut hess = Array3::<F>::zeros((x.nrows(), nx, nx));
+        // var(x) = sigma2 - k^t . W . k + noise where k = sigma2 * r(Z, x)
+        // d2var/dxadxb = - d2k/dxadxb^t . (W + W^t) . k - dk/dxa^t . (W + W^t) . dk/dxb
+        let w_sym = &self.w_data.inv + &self.w_data.inv.t();
+        Zip::from(hess.outer_iter_mut())
+            .and(x.rows())
+            .for_each(|mut h, xi| {
+                let xx = xi.to_owned().insert_axis(Axis(0));
+                let k =
+                    self.compute_k(&self.inducings, &xx, &self.w_star, &self.theta, self.sigma2);
+                let dk = self
+                    .corr
+                    .jacobian(&xi, &self.inducings, &self.theta, &self.w_star)
+                    .mapv(|v| v * self.sigma2);
+                let d2k = self
+                    .corr
+                    .hessian(&xi, &self.inducings, &self.theta, &self.w_star)
+                    .into_shape((nz, nx * nx))
+                    .unwrap()
+                    .mapv(|v| v * self.sigma2);
+                let first = d2k.t().dot(&w_sym.dot(&k)).into_shape((nx, nx)).unwrap();
+                let second = dk.t().dot(&w_sym).dot(&dk);
+                h.assign(&(-(first + second)));
+            });
+        hess
+    }
+
     /// Sample the gaussian process for `n_traj` trajectories using cholesky decomposition
     pub fn sample_chol(&self, x: &ArrayBase<impl Data<Elem = F>, Ix2>, n_traj: usize) -> Array2<F> {
         self._sample(x, n_traj, GpSamplingMethod::Cholesky)
@@ -352,6 +413,102 @@ impl<F: Float, Corr: CorrelationModel<F>> SparseGaussianProcess<F, Corr> {
         let cov = self.compute_k(x, x, &self.w_star, &self.theta, self.sigma2);
         sample(x, mean, cov, n_traj, method)
     }
+
+    /// Draw `n_traj` posterior sample paths of the latent function as continuous and
+    /// differentiable functions of x.
+    ///
+    /// The GP prior is approximated with `n_features` random Fourier features
+    /// (see [crate::GP_RFF_N_FEATURES] default) and updated with inducing values drawn from
+    /// their approximate posterior distribution. Paths can then be evaluated at any number
+    /// of points at a linear cost.
+    pub fn sample_paths<R: Rng>(
+        &self,
+        n_traj: usize,
+        n_features: usize,
+        rng: &mut R,
+    ) -> Result<SgpSamplePaths<F, Corr>> {
+        let rff = RandomFourierFeatures::new(
+            &self.corr,
+            &self.theta,
+            &self.w_star,
+            n_features,
+            n_traj,
+            rng,
+        )?;
+        let (ui, li_ui) = self.inducings_posterior_factors()?;
+
+        let nz = self.inducings.nrows();
+        let normal = |rng: &mut R| {
+            Array2::random_using((nz, n_traj), StandardNormal, rng).mapv(|v: f64| F::cast(v))
+        };
+        // Prior trajectories at inducing points including nugget used in Kmm
+        let prior = rff.values(&self.inducings) * self.sigma2.sqrt()
+            + normal(rng) * self.params.nugget().sqrt();
+        let weights = li_ui.t().dot(&normal(rng)) - ui.t().dot(&ui.dot(&prior)) + &self.w_data.vec;
+
+        Ok(SgpSamplePaths::new(
+            self.corr,
+            self.theta.to_owned(),
+            self.w_star.to_owned(),
+            self.sigma2,
+            self.inducings.to_owned(),
+            rff,
+            weights,
+        ))
+    }
+
+    /// Compute `Ui` and `Li.Ui` factors such that `Kmm^-1 = Ui^t.Ui` and
+    /// `(Li.Ui)^t.(Li.Ui)` is the posterior covariance of `Kmm^-1.u`, u being the inducing values
+    fn inducings_posterior_factors(&self) -> Result<(Array2<F>, Array2<F>)> {
+        let z = &self.inducings;
+        let xtrain = &self.training_data.0;
+        let nz = z.nrows();
+        let nugget = self.params.nugget();
+        let kmm =
+            self.compute_k(z, z, &self.w_star, &self.theta, self.sigma2) + Array::eye(nz) * nugget;
+        let kmn = self.compute_k(z, xtrain, &self.w_star, &self.theta, self.sigma2);
+
+        let u = kmm.cholesky()?;
+        let ui = u.solve_triangular(&Array::eye(nz), UPLO::Lower)?;
+        let v = ui.dot(&kmn);
+
+        // Effective noise precision as computed by the sparse method
+        let beta = match self.method {
+            SparseMethod::Fitc => (Array1::from_elem(xtrain.nrows(), self.sigma2)
+                - (&v * &v).sum_axis(Axis(0))
+                + self.noise)
+                .mapv(|v| F::one() / v),
+            SparseMethod::Vfe => {
+                Array1::from_elem(xtrain.nrows(), F::one() / self.noise.max(nugget))
+            }
+        };
+        let a = Array::eye(nz) + (&v * &beta.insert_axis(Axis(0))).dot(&v.t());
+        let l = a.cholesky()?;
+        let li = l.solve_triangular(&Array::eye(nz), UPLO::Lower)?;
+        let li_ui = li.dot(&ui);
+        Ok((ui, li_ui))
+    }
+}
+
+#[cfg(feature = "persistent")]
+impl<F, Corr> SparseGaussianProcess<F, Corr>
+where
+    F: Float + Serialize + serde::de::DeserializeOwned,
+    Corr: CorrelationModel<F> + Serialize + serde::de::DeserializeOwned,
+{
+    /// Save SGP model in given file, the model is stored with a header
+    /// identifying the file format version, the crate version and the GP kind.
+    pub fn save(&self, path: &str, format: GpFileFormat) -> Result<()> {
+        save_model(self, path, format)
+    }
+
+    /// Load SGP model from given file.
+    ///
+    /// Fails with [GpError::LoadFormatError] when the file was saved with an unsupported
+    /// format version or holds a SGP with different float type or correlation model.
+    pub fn load(path: &str, format: GpFileFormat) -> Result<Self> {
+        load_model(path, format)
+    }
 }
 
 impl<F, D, Corr> PredictInplace<ArrayBase<D, Ix2>, Array1<F>> for SparseGaussianProcess<F, Corr>
@@ -543,7 +700,7 @@ impl<F: Float, Corr: CorrelationModel<F>, D: Data<Elem = F> + Sync>
                 &z,
                 self.nugget(),
             ) {
-                Ok(r) => unsafe { -(*(&r.0 as *const F as *const f64)) },
+                Ok(r) => -f64::cast(r.0),
                 Err(_) => f64::INFINITY,
             }
         };
@@ -946,6 +1103,42 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_sgp_hessians() {
+        let mut rng = Xoshiro256Plus::seed_from_u64(42);
+        let (xt, yt) = make_test_data(100, 0.01, &mut rng);
+
+        let sgp = SparseKriging::params(Inducings::Randomized(20))
+            .seed(Some(42))
+            .fit(&Dataset::new(xt, yt))
+            .expect("GP fitted");
+
+        let x = array![[-0.7], [-0.2], [0.35], [0.8]];
+        let hess = sgp.predict_hessians(&x);
+        let var_hess = sgp.predict_var_hessians(&x);
+
+        let e = 1e-4;
+        let y = sgp.predict(&x).unwrap();
+        let yp = sgp.predict(&(&x + e)).unwrap();
+        let ym = sgp.predict(&(&x - e)).unwrap();
+        let fdiff = (yp - y.mapv(|v| 2. * v) + ym).mapv(|v| v / (e * e));
+        assert_abs_diff_eq!(
+            hess.slice(s![.., 0, 0]),
+            fdiff,
+            epsilon = 1e-2 * fdiff.mapv(f64::abs).sum()
+        );
+
+        let v = sgp.predict_var(&x).unwrap();
+        let vp = sgp.predict_var(&(&x + e)).unwrap();
+        let vm = sgp.predict_var(&(&x - e)).unwrap();
+        let fdiff = (vp - v.mapv(|v| 2. * v) + vm).mapv(|v| v / (e * e));
+        assert_abs_diff_eq!(
+            var_hess.slice(s![.., 0, 0]),
+            fdiff,
+            epsilon = 1e-2 * fdiff.mapv(f64::abs).sum()
+        );
+    }
+
     #[test]
     fn test_sgp_vfe() {
         let mut rng = Xoshiro256Plus::seed_from_u64(42);
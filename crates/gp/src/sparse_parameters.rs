@@ -42,6 +42,16 @@ impl<F: Float> Default for Inducings<F> {
     }
 }
 
+impl<F: Float> Inducings<F> {
+    /// Convert inducing points specification to another float type
+    pub fn cast<G: Float>(&self) -> Inducings<G> {
+        match self {
+            Inducings::Randomized(n) => Inducings::Randomized(*n),
+            Inducings::Located(z) => Inducings::Located(z.mapv(G::cast)),
+        }
+    }
+}
+
 /// SGP algorithm method specification
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
 #[cfg_attr(feature = "serializable", derive(Serialize, Deserialize))]
@@ -1,27 +1,41 @@
 use super::gaussian_mixture::GaussianMixture;
 use crate::clustering::{find_best_number_of_clusters, sort_by_cluster};
+use crate::diagnostics::{MixtureReport, ResponsibilityMap};
 use crate::errors::MoeError;
 use crate::errors::Result;
+use crate::experts::ExpertPlugin;
+use crate::experts::{PolynomialSurrogate, RbfSurrogate};
+use crate::export::GpMixtureCoefficients;
+use crate::gating::GatingNetwork;
+use crate::multi_output::MultiGpMixture;
+use crate::parameters::{ExpertSelection, ExpertType, KplsSelection, is_builtin_expert_name};
 use crate::parameters::{GpMixtureParams, GpMixtureValidParams};
+use crate::partitioning::{
+    CentroidPartition, Partition, TreePartition, agglomerative_clustering,
+    gaussian_mixture_from_clusters, kmeans_clustering,
+};
 use crate::types::*;
 use crate::{GpType, expertise_macros::*};
 use crate::{NbClusters, surrogates::*};
 
 use egobox_gp::metrics::CrossValScore;
-use egobox_gp::{GaussianProcess, SparseGaussianProcess, correlation_models::*, mean_models::*};
+use egobox_gp::{GaussianProcess, ThetaTuning, correlation_models::*, mean_models::*};
 use linfa::dataset::Records;
-use linfa::traits::{Fit, Predict, PredictInplace};
+use linfa::traits::{Fit, Predict, PredictInplace, Transformer};
 use linfa::{Dataset, DatasetBase, Float, ParamGuard};
 use linfa_clustering::GaussianMixtureModel;
+use linfa_pls::PlsRegression;
 use log::{debug, info, trace};
 use paste::paste;
+use rayon::prelude::*;
 use std::cmp::Ordering;
 use std::ops::Sub;
 
 #[cfg(not(feature = "blas"))]
 use linfa_linalg::norm::*;
 use ndarray::{
-    Array1, Array2, Array3, ArrayBase, ArrayView2, Axis, Data, Ix1, Ix2, Zip, concatenate, s,
+    Array1, Array2, Array3, Array4, ArrayBase, ArrayView2, Axis, Data, Ix1, Ix2, Zip, concatenate,
+    s,
 };
 
 #[cfg(feature = "blas")]
@@ -46,10 +60,10 @@ macro_rules! check_allowed {
     };
 }
 
-impl<D: Data<Elem = f64>> Fit<ArrayBase<D, Ix2>, ArrayBase<D, Ix1>, MoeError>
-    for GpMixtureValidParams<f64>
+impl<F: SurrogateFloat, D: Data<Elem = F>> Fit<ArrayBase<D, Ix2>, ArrayBase<D, Ix1>, MoeError>
+    for GpMixtureValidParams<F>
 {
-    type Object = GpMixture;
+    type Object = GpMixture<F>;
 
     /// Fit Moe parameters using maximum likelihood
     ///
@@ -68,27 +82,60 @@ impl<D: Data<Elem = f64>> Fit<ArrayBase<D, Ix2>, ArrayBase<D, Ix1>, MoeError>
     }
 }
 
-impl GpMixtureValidParams<f64> {
+impl<F: SurrogateFloat> GpMixtureValidParams<F> {
+    /// Train the mixture of experts within the thread pool specified by
+    /// the parallelism policy (see [GpMixtureParams::parallelism])
     pub fn train(
         &self,
-        xt: &ArrayBase<impl Data<Elem = f64>, Ix2>,
-        yt: &ArrayBase<impl Data<Elem = f64>, Ix1>,
-    ) -> Result<GpMixture> {
+        xt: &ArrayBase<impl Data<Elem = F>, Ix2>,
+        yt: &ArrayBase<impl Data<Elem = F>, Ix1>,
+    ) -> Result<GpMixture<F>> {
+        let (xt, yt) = (xt.view(), yt.view());
+        self.parallelism().install(|| self.fit_mixture(&xt, &yt))
+    }
+
+    fn fit_mixture(
+        &self,
+        xt: &ArrayBase<impl Data<Elem = F>, Ix2>,
+        yt: &ArrayBase<impl Data<Elem = F>, Ix1>,
+    ) -> Result<GpMixture<F>> {
         trace!("Moe training...");
+        let clustering = self.make_clustering(xt, &yt.view().insert_axis(Axis(1)))?;
+        trace!("Train on clusters...");
+        self.fit_on_clusters(xt, yt, &clustering)
+    }
+
+    /// Cluster the training data made of inputs `xt` and outputs `yt` given as a (n, ny) matrix
+    /// wrt the clustering settings of the mixture. With several outputs, outputs are
+    /// standardized beforehand to contribute equally to the clustering.
+    ///
+    /// When the number of clusters is automatically determined or when using tree partitioning,
+    /// only the first output is taken into account.
+    pub fn make_clustering(
+        &self,
+        xt: &ArrayBase<impl Data<Elem = F>, Ix2>,
+        yt: &ArrayBase<impl Data<Elem = F>, Ix2>,
+    ) -> Result<Clustering> {
         let nx = xt.ncols();
-        let data = concatenate(
-            Axis(1),
-            &[xt.view(), yt.to_owned().insert_axis(Axis(1)).view()],
-        )
-        .unwrap();
+        // outputs are standardized to contribute equally to the clustering
+        let ys = if yt.ncols() > 1 {
+            let mean = yt.mean_axis(Axis(0)).unwrap();
+            let std = yt
+                .std_axis(Axis(0), F::one())
+                .mapv(|v| if v > F::zero() { v } else { F::one() });
+            (yt - &mean) / &std
+        } else {
+            yt.to_owned()
+        };
+        let data = concatenate(Axis(1), &[xt.view(), ys.view()]).unwrap();
 
         let (n_clusters, recomb) = match self.n_clusters() {
             NbClusters::Auto { max } => {
                 // automatic mode
                 let max_nb_clusters = max.unwrap_or(xt.nrows() / 10 + 1);
                 find_best_number_of_clusters(
-                    xt,
-                    yt,
+                    &cast_array::<F, f64, _>(xt),
+                    &cast_array::<F, f64, _>(&yt.column(0)),
                     max_nb_clusters,
                     self.kpls_dim(),
                     self.regression_spec(),
@@ -96,11 +143,32 @@ impl GpMixtureValidParams<f64> {
                     self.rng(),
                 )
             }
-            NbClusters::Fixed { nb: nb_clusters } => (nb_clusters, self.recombination()),
+            NbClusters::Fixed { nb: nb_clusters } => (nb_clusters, self.recombination().cast()),
         };
         if let NbClusters::Auto { max: _ } = self.n_clusters() {
             debug!("Automatic settings {n_clusters} {recomb:?}");
         }
+        let tree = match self.clustering_method() {
+            ClusteringMethod::Tree { min_leaf_size } if self.gmx().is_none() => Some(min_leaf_size),
+            _ => None,
+        };
+        // experts are gated by the tree regions or by the nearest cluster centroids
+        // unless a gating network is trained on the clusters
+        let logistic_gating = matches!(
+            self.gating_method(),
+            GatingMethod::LogisticRegression { .. }
+        );
+        let gated_by_partition = self.gmx().is_none()
+            && match self.clustering_method() {
+                ClusteringMethod::Gmm => false,
+                ClusteringMethod::Tree { .. } => true,
+                ClusteringMethod::KMeans | ClusteringMethod::Agglomerative => !logistic_gating,
+            };
+        let recomb = if gated_by_partition {
+            Recombination::Hard
+        } else {
+            recomb
+        };
 
         let training = if recomb == Recombination::Smooth(None) && self.n_clusters().is_multi() {
             // Extract 5% of data for validation to find best heaviside factor
@@ -110,12 +178,20 @@ impl GpMixtureValidParams<f64> {
         } else {
             data.to_owned()
         };
-        let dataset = Dataset::from(training);
+        let factor = match recomb {
+            Recombination::Smooth(Some(f)) => f,
+            Recombination::Smooth(_) => 1.,
+            Recombination::Hard => 1.,
+        };
 
+        let mut partition = None;
+        // assignments of the training points to the clusters when not given by the gmx
+        let mut labels = None;
         let gmx = if self.gmx().is_some() {
-            self.gmx().unwrap().clone()
-        } else {
+            self.gmx().unwrap().cast()
+        } else if self.clustering_method() == ClusteringMethod::Gmm {
             trace!("GMM training...");
+            let dataset = Dataset::from(training.clone());
             let gmm = GaussianMixtureModel::params(n_clusters)
                 .n_runs(20)
                 .with_rng(self.rng())
@@ -125,27 +201,215 @@ impl GpMixtureValidParams<f64> {
             let weights = gmm.weights().to_owned();
             let means = gmm.means().slice(s![.., ..nx]).to_owned();
             let covariances = gmm.covariances().slice(s![.., ..nx, ..nx]).to_owned();
-            let factor = match recomb {
-                Recombination::Smooth(Some(f)) => f,
-                Recombination::Smooth(_) => 1.,
-                Recombination::Hard => 1.,
+            GaussianMixture::new(weights, means, covariances)?
+                .cast()
+                .heaviside_factor(factor)
+        } else {
+            trace!("{:?} clustering...", self.clustering_method());
+            let training = cast_array::<F, f64, _>(&training);
+            let xtrain = training.slice(s![.., ..nx]);
+            let (clusters, n_clusters) = match (self.clustering_method(), tree) {
+                (_, Some(min_leaf_size)) => {
+                    let ytrain = training.column(nx);
+                    let tree = TreePartition::fit(&xtrain, &ytrain, n_clusters, min_leaf_size);
+                    let n_regions = tree.n_regions();
+                    if n_regions < n_clusters {
+                        info!("Tree partitioning found {n_regions} regions only");
+                    }
+                    let clusters = tree.predict(&xtrain);
+                    partition = Some(Partition::Tree(tree));
+                    (clusters, n_regions)
+                }
+                (ClusteringMethod::KMeans, _) => (
+                    kmeans_clustering(&training, n_clusters, self.rng())?,
+                    n_clusters,
+                ),
+                _ => (agglomerative_clustering(&training, n_clusters), n_clusters),
             };
-            GaussianMixture::new(weights, means, covariances)?.heaviside_factor(factor)
+            if gated_by_partition && partition.is_none() {
+                partition = Some(Partition::Centroids(CentroidPartition::fit(
+                    &xtrain, &clusters, n_clusters,
+                )?));
+            }
+            let gmx = gaussian_mixture_from_clusters(&xtrain, &clusters, n_clusters)?;
+            labels = Some(clusters);
+            gmx.heaviside_factor(factor)
         };
 
-        trace!("Train on clusters...");
-        let clustering = Clustering::new(gmx, recomb);
-        self.train_on_clusters(&xt.view(), &yt.view(), &clustering)
+        let gating = match self.gating_method() {
+            GatingMethod::LogisticRegression { regularization } if partition.is_none() => {
+                trace!("Gating network training...");
+                let training = cast_array::<F, f64, _>(&training);
+                let xtrain = training.slice(s![.., ..nx]);
+                let clusters = labels.unwrap_or_else(|| gmx.predict(&xtrain));
+                let gating =
+                    GatingNetwork::fit(&xtrain, &clusters, gmx.n_clusters(), regularization)?;
+                Some(gating.heaviside_factor(factor))
+            }
+            _ => None,
+        };
+
+        Ok(Clustering::new(gmx, recomb)
+            .with_partition(partition)
+            .with_gating(gating))
+    }
+
+    /// Select the number of PLS components of an expert trained on the whole given data
+    /// (`None` meaning no dimension reduction) wrt the kpls settings of the mixture
+    /// (see [GpMixtureParams::kpls_selection]).
+    pub fn select_kpls_dim(
+        &self,
+        xt: &ArrayBase<impl Data<Elem = F>, Ix2>,
+        yt: &ArrayBase<impl Data<Elem = F>, Ix1>,
+    ) -> Result<Option<usize>> {
+        let nx = xt.ncols();
+        let data = concatenate(
+            Axis(1),
+            &[xt.view(), yt.to_owned().insert_axis(Axis(1)).view()],
+        )
+        .unwrap();
+        // Only the scores of the candidates are required, no expert is trained on the whole data
+        Ok(match self.expert_type(0, data.nrows(), nx) {
+            ExpertType::Gp(_) => {
+                self.parallelism()
+                    .install(|| self.select_expert(0, nx, &data))
+                    .1
+            }
+            _ => None,
+        })
     }
 
     /// Using the current state of the clustering, select and train the experts
     /// Returns the fitted mixture of experts model
     pub fn train_on_clusters(
         &self,
-        xt: &ArrayBase<impl Data<Elem = f64>, Ix2>,
-        yt: &ArrayBase<impl Data<Elem = f64>, Ix1>,
+        xt: &ArrayBase<impl Data<Elem = F>, Ix2>,
+        yt: &ArrayBase<impl Data<Elem = F>, Ix1>,
+        clustering: &Clustering,
+    ) -> Result<GpMixture<F>> {
+        let (xt, yt) = (xt.view(), yt.view());
+        self.parallelism()
+            .install(|| self.fit_on_clusters(&xt, &yt, clustering))
+    }
+
+    /// Train a mixture of experts for each output of `yt` given as a (n, ny) matrix,
+    /// the mixtures sharing the same clustering of the training data
+    /// (see [GpMixtureParams::multi_output_experts]).
+    pub fn train_multi(
+        &self,
+        xt: &ArrayBase<impl Data<Elem = F>, Ix2>,
+        yt: &ArrayBase<impl Data<Elem = F>, Ix2>,
+    ) -> Result<MultiGpMixture<F>> {
+        let (xt, yt) = (xt.view(), yt.view());
+        self.parallelism()
+            .install(|| self.fit_multi_mixture(&xt, &yt))
+    }
+
+    fn fit_multi_mixture(
+        &self,
+        xt: &ArrayView2<F>,
+        yt: &ArrayView2<F>,
+    ) -> Result<MultiGpMixture<F>> {
+        if yt.ncols() == 0 || yt.nrows() != xt.nrows() {
+            return Err(MoeError::InvalidValueError(format!(
+                "Outputs should be given as a ({}, ny) matrix with ny > 0, got {:?}",
+                xt.nrows(),
+                yt.shape()
+            )));
+        }
+        trace!("Multi-output Moe clustering...");
+        let mut clustering = self.make_clustering(xt, yt)?;
+
+        if clustering.recombination() == Recombination::Smooth(None) && self.n_clusters().is_multi()
+        {
+            // heaviside factor optimized wrt the first output
+            let nx = xt.ncols();
+            let data = concatenate(Axis(1), &[xt.view(), yt.slice(s![.., ..1])]).unwrap();
+            let (test, _) = extract_part(&data, 5);
+            let xtest = test.slice(s![.., ..nx]).to_owned();
+            let ytest = test.column(nx).to_owned();
+            let unit = Clustering::new(clustering.gmx().clone(), Recombination::Smooth(Some(1.)))
+                .with_partition(clustering.partition().cloned())
+                .with_gating(clustering.gating().cloned());
+            let first = self.fit_on_clusters(xt, &yt.column(0), &unit)?;
+            let factor = self.optimize_heaviside_factor(&first.experts, &unit, &xtest, &ytest);
+            info!("Multi-output mixture heaviside factor={factor}");
+            clustering = Clustering::new(
+                clustering.gmx().clone(),
+                Recombination::Smooth(Some(factor)),
+            )
+            .with_partition(clustering.partition().cloned())
+            .with_gating(clustering.gating().cloned())
+            .heaviside_factor(factor);
+        }
+
+        trace!("Train on clusters {} outputs...", yt.ncols());
+        let mixtures = match self.multi_output_experts() {
+            MultiOutputExperts::Independent => (0..yt.ncols())
+                .into_par_iter()
+                .map(|k| self.fit_on_clusters(xt, &yt.column(k), &clustering))
+                .collect::<Result<Vec<_>>>()?,
+            MultiOutputExperts::SharedHyperparameters => {
+                let first = self.fit_on_clusters(xt, &yt.column(0), &clustering)?;
+                let others = (1..yt.ncols())
+                    .into_par_iter()
+                    .map(|k| self.fit_shared_on_clusters(xt, &yt.column(k), &first))
+                    .collect::<Result<Vec<_>>>()?;
+                std::iter::once(first).chain(others).collect()
+            }
+        };
+        Ok(MultiGpMixture::new(mixtures))
+    }
+
+    /// Train a mixture on the clusters of the given `reference` mixture
+    /// with experts using the models and hyperparameters of the reference experts
+    fn fit_shared_on_clusters(
+        &self,
+        xt: &ArrayBase<impl Data<Elem = F>, Ix2>,
+        yt: &ArrayBase<impl Data<Elem = F>, Ix1>,
+        reference: &GpMixture<F>,
+    ) -> Result<GpMixture<F>> {
+        let nx = xt.ncols();
+        let data = concatenate(Axis(1), &[xt.view(), yt.view().insert_axis(Axis(1))]).unwrap();
+        let clustering = reference.to_clustering();
+        let dataset_clustering = clustering.predict_clusters(&cast_array::<F, f64, _>(xt));
+        let clusters = sort_by_cluster(reference.n_clusters(), &data, &dataset_clustering);
+        let (experts, expert_scores): (Vec<_>, Vec<_>) = clusters
+            .par_iter()
+            .enumerate()
+            .map(|(nc, cluster)| {
+                self.refit_expert(
+                    nc,
+                    nx,
+                    cluster,
+                    reference.experts[nc].as_ref(),
+                    &reference.expert_scores.get(nc).cloned().unwrap_or_default(),
+                    true,
+                )
+            })
+            .collect::<Result<Vec<_>>>()?
+            .into_iter()
+            .unzip();
+        Ok(GpMixture {
+            gp_type: self.gp_type().clone(),
+            recombination: reference.recombination,
+            experts,
+            expert_scores,
+            gmx: reference.gmx.clone(),
+            partition: reference.partition.clone(),
+            gating: reference.gating.clone(),
+            mixture_variance: self.mixture_variance(),
+            training_data: (xt.to_owned(), yt.to_owned()),
+            params: reference.params.clone(),
+        })
+    }
+
+    fn fit_on_clusters(
+        &self,
+        xt: &ArrayBase<impl Data<Elem = F>, Ix2>,
+        yt: &ArrayBase<impl Data<Elem = F>, Ix1>,
         clustering: &Clustering,
-    ) -> Result<GpMixture> {
+    ) -> Result<GpMixture<F>> {
         let gmx = clustering.gmx();
         let recomb = clustering.recombination();
         let nx = xt.ncols();
@@ -155,25 +419,29 @@ impl GpMixtureValidParams<f64> {
         )
         .unwrap();
 
-        let dataset_clustering = gmx.predict(xt);
+        let dataset_clustering = clustering.predict_clusters(&cast_array::<F, f64, _>(xt));
         let clusters = sort_by_cluster(gmx.n_clusters(), &data, &dataset_clustering);
 
         check_number_of_points(&clusters, xt.ncols(), self.regression_spec())?;
 
         // Fit GPs on clustered data
-        let mut experts = Vec::new();
         let nb_clusters = clusters.len();
-        for (nc, cluster) in clusters.iter().enumerate() {
-            if nb_clusters > 1 && cluster.nrows() < 3 {
-                return Err(MoeError::ClusteringError(format!(
-                    "Not enough points in cluster, requires at least 3, got {}",
-                    cluster.nrows()
-                )));
-            }
-            debug!("nc={} theta_tuning={:?}", nc, self.theta_tunings());
-            let expert = self.find_best_expert(nc, nx, cluster)?;
-            experts.push(expert);
+        if let Some(cluster) = clusters.iter().find(|c| nb_clusters > 1 && c.nrows() < 3) {
+            return Err(MoeError::ClusteringError(format!(
+                "Not enough points in cluster, requires at least 3, got {}",
+                cluster.nrows()
+            )));
         }
+        let (experts, expert_scores): (Vec<_>, Vec<_>) = clusters
+            .par_iter()
+            .enumerate()
+            .map(|(nc, cluster)| {
+                debug!("nc={} theta_tuning={:?}", nc, self.theta_tunings());
+                self.find_best_expert(nc, nx, cluster)
+            })
+            .collect::<Result<Vec<_>>>()?
+            .into_iter()
+            .unzip();
 
         if recomb == Recombination::Smooth(None) && self.n_clusters().is_multi() {
             // Extract 5% of data for validation to find best heaviside factor
@@ -181,12 +449,12 @@ impl GpMixtureValidParams<f64> {
             let (test, _) = extract_part(&data, 5);
             let xtest = test.slice(s![.., ..nx]).to_owned();
             let ytest = test.slice(s![.., nx..]).to_owned().remove_axis(Axis(1));
-            let factor = self.optimize_heaviside_factor(&experts, gmx, &xtest, &ytest);
+            let factor = self.optimize_heaviside_factor(&experts, clustering, &xtest, &ytest);
             info!("Retrain mixture with optimized heaviside factor={factor}");
 
             let moe = GpMixtureParams::from(self.clone())
                 .n_clusters(NbClusters::fixed(gmx.n_clusters()))
-                .recombination(Recombination::Smooth(Some(factor)))
+                .recombination(Recombination::Smooth(Some(F::cast(factor))))
                 .check()?
                 .train(xt, yt)?; // needs to train the gaussian mixture on all data (xt, yt) as it was
             // previously trained on data excluding test data (see train method)
@@ -196,24 +464,129 @@ impl GpMixtureValidParams<f64> {
                 gp_type: self.gp_type().clone(),
                 recombination: recomb,
                 experts,
+                expert_scores,
                 gmx: gmx.clone(),
+                partition: clustering.partition().cloned(),
+                gating: clustering.gating().cloned(),
+                mixture_variance: self.mixture_variance(),
                 training_data: (xt.to_owned(), yt.to_owned()),
                 params: self.clone(),
             })
         }
     }
 
-    /// Select the surrogate which gives the smallest prediction error on the given data
-    /// The error is computed using cross-validation
+    /// Candidate numbers of PLS components for the expert of the `nc`th cluster
+    /// given its training data
+    fn kpls_dim_candidates(
+        &self,
+        nc: usize,
+        xtrain: &Array2<f64>,
+        ytrain: &Array2<f64>,
+    ) -> Vec<Option<usize>> {
+        if !self.kpls_dims().is_empty() {
+            return vec![self.kpls_dims()[nc]];
+        }
+        let nx = xtrain.ncols();
+        // no dimension reduction when all components are kept
+        let kpls = |d: usize| if d < nx { Some(d) } else { None };
+        match self.kpls_selection() {
+            Some(KplsSelection::ExplainedVariance { threshold, max_dim }) => {
+                let dim = explained_variance_kpls_dim(xtrain, ytrain, threshold, max_dim.min(nx));
+                debug!("Cluster #{nc}: {dim} PLS components explain {threshold} of variance");
+                vec![kpls(dim)]
+            }
+            Some(KplsSelection::Criterion { max_dim }) => (1..=max_dim.min(nx)).map(kpls).collect(),
+            None => vec![self.kpls_dim()],
+        }
+    }
+
+    /// Select the surrogate which gives the best score on the given data
+    /// wrt the selection criterion (cross-validation error by default)
     fn find_best_expert(
         &self,
         nc: usize,
         nx: usize,
-        data: &ArrayBase<impl Data<Elem = f64>, Ix2>,
-    ) -> Result<Box<dyn FullGpSurrogate>> {
-        let xtrain = data.slice(s![.., ..nx]).to_owned();
-        let ytrain = data.slice(s![.., nx..]).to_owned();
-        let mut dataset = Dataset::from((xtrain.clone(), ytrain.clone().remove_axis(Axis(1))));
+        data: &ArrayBase<impl Data<Elem = F>, Ix2>,
+    ) -> Result<(Box<dyn FullGpSurrogate>, ExpertScores)> {
+        let gp_type = match self.expert_type(nc, data.nrows(), nx) {
+            ExpertType::Polynomial { degree } => {
+                let xtrain = cast_array::<F, f64, _>(&data.slice(s![.., ..nx]));
+                let ytrain = cast_array::<F, f64, _>(&data.slice(s![.., nx..]));
+                debug!("Polynomial expert of degree {degree} for cluster #{nc}");
+                let expert = PolynomialSurrogate::train(degree, &xtrain, &ytrain)?;
+                let scores =
+                    ExpertScores::new(self.selection_criterion(), vec![], expert.to_string());
+                return Ok((Box::new(expert), scores));
+            }
+            ExpertType::Rbf => {
+                let xtrain = cast_array::<F, f64, _>(&data.slice(s![.., ..nx]));
+                let ytrain = cast_array::<F, f64, _>(&data.slice(s![.., nx..]));
+                debug!("RBF expert for cluster #{nc}");
+                let expert = RbfSurrogate::train(&xtrain, &ytrain)?;
+                let scores =
+                    ExpertScores::new(self.selection_criterion(), vec![], expert.to_string());
+                return Ok((Box::new(expert), scores));
+            }
+            ExpertType::Plugin(name) => {
+                let xtrain = cast_array::<F, f64, _>(&data.slice(s![.., ..nx]));
+                let ytrain = cast_array::<F, f64, _>(&data.slice(s![.., nx..]));
+                debug!("Plugin expert {name} for cluster #{nc}");
+                let plugin = self.registered_plugin(&name)?;
+                let expert = plugin.train(&xtrain.view(), &ytrain.view())?;
+                let scores = ExpertScores::new(self.selection_criterion(), vec![], name);
+                return Ok((expert, scores));
+            }
+            ExpertType::Gp(gp_type) => gp_type,
+        };
+        // Surrogate parameters are trained from double precision data
+        let xtrain = cast_array::<F, f64, _>(&data.slice(s![.., ..nx]));
+        let ytrain = cast_array::<F, f64, _>(&data.slice(s![.., nx..]));
+        let criterion = self.selection_criterion();
+        let (best, kpls_dim, scores) = self.select_expert(nc, nx, data);
+        let label = |name: &str, kpls_dim: Option<usize>| match kpls_dim {
+            Some(d) => format!("{name}_PLS({d})"),
+            None => name.to_string(),
+        };
+
+        let expert = match self.expert_plugin(&best) {
+            Some(plugin) => plugin.train(&xtrain.view(), &ytrain.view()),
+            None => {
+                let theta_tuning = self.theta_tuning_of(nc, &gp_type);
+                self.train_expert(
+                    &gp_type,
+                    best.as_str(),
+                    kpls_dim,
+                    theta_tuning,
+                    &xtrain,
+                    &ytrain,
+                )
+            }
+        };
+
+        debug!("...after best expert training");
+        let scores = ExpertScores::new(criterion, scores, label(&best, kpls_dim));
+        info!("Cluster #{nc} expert scores\n{scores}");
+        Ok((expert?, scores))
+    }
+
+    /// Select the best GP expert (or expert plugin) of the `nc`th cluster wrt the selection
+    /// criterion, the selected expert being not trained on the whole data.
+    /// Returns the name and the number of PLS components of the selected expert
+    /// with the scores of all the candidates.
+    #[allow(clippy::type_complexity)]
+    fn select_expert(
+        &self,
+        nc: usize,
+        nx: usize,
+        data: &ArrayBase<impl Data<Elem = F>, Ix2>,
+    ) -> (String, Option<usize>, Vec<(String, Option<f64>)>) {
+        let dataset = Dataset::from((
+            data.slice(s![.., ..nx]).to_owned(),
+            data.slice(s![.., nx]).to_owned(),
+        ));
+        // Surrogate parameters are trained from double precision data
+        let xtrain = cast_array::<F, f64, _>(&data.slice(s![.., ..nx]));
+        let ytrain = cast_array::<F, f64, _>(&data.slice(s![.., nx..]));
         let regression_spec = self.regression_spec();
         let mut allowed_means = vec![];
         check_allowed!(regression_spec, Regression, Constant, allowed_means);
@@ -237,68 +610,133 @@ impl GpMixtureValidParams<f64> {
         check_allowed!(correlation_spec, Correlation, Matern52, allowed_corrs);
 
         debug!("Find best expert");
-        let best = if allowed_means.len() == 1 && allowed_corrs.len() == 1 {
-            (format!("{}_{}", allowed_means[0], allowed_corrs[0]), None) // shortcut
+        let criterion = self.selection_criterion();
+        let names: Vec<String> = allowed_means
+            .iter()
+            .flat_map(|m| allowed_corrs.iter().map(move |c| format!("{m}_{c}")))
+            .collect();
+        let kpls_dims = self.kpls_dim_candidates(nc, &xtrain, &ytrain);
+        let label = |name: &str, kpls_dim: Option<usize>| match kpls_dim {
+            Some(d) => format!("{name}_PLS({d})"),
+            None => name.to_string(),
+        };
+        let plugins = self.expert_plugins();
+        let (best, kpls_dim, scores) = if names.len() == 1
+            && kpls_dims.len() == 1
+            && plugins.is_empty()
+        {
+            let kpls_dim = kpls_dims[0];
+            let name = label(&names[0], kpls_dim);
+            (names[0].clone(), kpls_dim, vec![(name, None)]) // shortcut
         } else {
-            let mut map_error = Vec::new();
-            compute_errors!(self, allowed_means, allowed_corrs, dataset, map_error);
-            let errs: Vec<f64> = map_error.iter().map(|(_, err)| *err).collect();
-            debug!("Accuracies {map_error:?}");
-            let argmin = errs
-                .iter()
-                .enumerate()
-                .min_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(Ordering::Equal))
-                .map(|(index, _)| index)
+            let mut scores = Vec::new();
+            let mut results = Vec::new();
+            for kpls_dim in kpls_dims {
+                let mut tasks = Vec::new();
+                let map_error = if self.early_pruning()
+                    && allowed_means.len() > 1
+                    && allowed_corrs.len() > 1
+                {
+                    // Score correlation models with the simplest regression model first
+                    let first_means = [allowed_means[0]];
+                    compute_errors!(
+                        self,
+                        first_means,
+                        allowed_corrs,
+                        dataset,
+                        tasks,
+                        F,
+                        kpls_dim
+                    );
+                    let mut map_error = eval_error_tasks(tasks);
+                    let best_score = map_error
+                        .iter()
+                        .map(|(_, err)| *err)
+                        .fold(f64::INFINITY, f64::min);
+                    let kept_corrs: Vec<&str> = allowed_corrs
+                        .iter()
+                        .zip(map_error.iter())
+                        .filter(|(_, (_, err))| !criterion.is_clearly_inferior(*err, best_score))
+                        .map(|(c, _)| *c)
+                        .collect();
+                    debug!("Early pruning keeps {kept_corrs:?}");
+                    let other_means = allowed_means[1..].to_vec();
+                    let mut tasks = Vec::new();
+                    compute_errors!(self, other_means, kept_corrs, dataset, tasks, F, kpls_dim);
+                    map_error.extend(eval_error_tasks(tasks));
+                    map_error
+                } else {
+                    compute_errors!(
+                        self,
+                        allowed_means,
+                        allowed_corrs,
+                        dataset,
+                        tasks,
+                        F,
+                        kpls_dim
+                    );
+                    eval_error_tasks(tasks)
+                };
+                debug!("Accuracies with kpls_dim={kpls_dim:?} {map_error:?}");
+                scores.extend(names.iter().map(|name| {
+                    let score = map_error.iter().find(|(n, _)| n == name).map(|(_, e)| *e);
+                    (label(name, kpls_dim), score)
+                }));
+                results.extend(map_error.into_iter().map(|(n, e)| (n, kpls_dim, e)));
+            }
+            if !plugins.is_empty() {
+                // User-defined experts compete with the GP experts
+                let tasks: Vec<(String, ErrorTask<'_>)> = plugins
+                    .iter()
+                    .map(|plugin| {
+                        let task: ErrorTask<'_> =
+                            Box::new(|| plugin.score(criterion, &xtrain, &ytrain));
+                        (plugin.name().to_string(), task)
+                    })
+                    .collect();
+                let map_error = eval_error_tasks(tasks);
+                debug!("Plugin experts accuracies {map_error:?}");
+                scores.extend(map_error.iter().map(|(n, e)| (n.clone(), Some(*e))));
+                results.extend(map_error.into_iter().map(|(n, e)| (n, None, e)));
+            }
+            let (name, kpls_dim, _) = results
+                .into_iter()
+                .min_by(|(_, _, a), (_, _, b)| a.partial_cmp(b).unwrap_or(Ordering::Equal))
                 .unwrap();
-            (map_error[argmin].0.clone(), Some(map_error[argmin].1))
+            (name, kpls_dim, scores)
         };
         debug!("after Find best expert");
+        (best, kpls_dim, scores)
+    }
+
+    /// Theta tuning used to train the expert of the `nc`-th cluster
+    fn theta_tuning_of(&self, nc: usize, gp_type: &GpType<F>) -> ThetaTuning<f64> {
+        debug!("Theta tuning = {:?}", self.theta_tunings());
+        match gp_type {
+            GpType::SparseGp { .. } => self.theta_tunings()[0].cast(),
+            _ if nc > 0 && self.theta_tunings().len() == 1 => self.theta_tunings()[0].cast(),
+            _ => self.theta_tunings()[nc].cast(),
+        }
+    }
 
-        let expert = match self.gp_type() {
+    /// Train the GP expert of given type and name (`<Regression>_<Correlation>`)
+    /// on the given data
+    fn train_expert(
+        &self,
+        gp_type: &GpType<F>,
+        name: &str,
+        kpls_dim: Option<usize>,
+        theta_tuning: ThetaTuning<f64>,
+        xtrain: &Array2<f64>,
+        ytrain: &Array2<f64>,
+    ) -> Result<Box<dyn FullGpSurrogate>> {
+        match gp_type {
             GpType::FullGp => {
-                let best_expert_params: std::result::Result<Box<dyn GpSurrogateParams>, MoeError> =
-                    match best.0.as_str() {
-                        "Constant_SquaredExponential" => {
-                            Ok(make_surrogate_params!(Constant, SquaredExponential))
-                        }
-                        "Constant_AbsoluteExponential" => {
-                            Ok(make_surrogate_params!(Constant, AbsoluteExponential))
-                        }
-                        "Constant_Matern32" => Ok(make_surrogate_params!(Constant, Matern32)),
-                        "Constant_Matern52" => Ok(make_surrogate_params!(Constant, Matern52)),
-                        "Linear_SquaredExponential" => {
-                            Ok(make_surrogate_params!(Linear, SquaredExponential))
-                        }
-                        "Linear_AbsoluteExponential" => {
-                            Ok(make_surrogate_params!(Linear, AbsoluteExponential))
-                        }
-                        "Linear_Matern32" => Ok(make_surrogate_params!(Linear, Matern32)),
-                        "Linear_Matern52" => Ok(make_surrogate_params!(Linear, Matern52)),
-                        "Quadratic_SquaredExponential" => {
-                            Ok(make_surrogate_params!(Quadratic, SquaredExponential))
-                        }
-                        "Quadratic_AbsoluteExponential" => {
-                            Ok(make_surrogate_params!(Quadratic, AbsoluteExponential))
-                        }
-                        "Quadratic_Matern32" => Ok(make_surrogate_params!(Quadratic, Matern32)),
-                        "Quadratic_Matern52" => Ok(make_surrogate_params!(Quadratic, Matern52)),
-                        _ => {
-                            return Err(MoeError::ExpertError(format!(
-                                "Unknown expert {}",
-                                best.0
-                            )));
-                        }
-                    };
-                let mut expert_params = best_expert_params?;
+                let mut expert_params = F::gp_surrogate_params(name)?;
                 expert_params.n_start(self.n_start());
                 expert_params.max_eval(self.max_eval());
-                expert_params.kpls_dim(self.kpls_dim());
-                if nc > 0 && self.theta_tunings().len() == 1 {
-                    expert_params.theta_tuning(self.theta_tunings()[0].clone());
-                } else {
-                    debug!("Training with theta_tuning = {:?}.", self.theta_tunings());
-                    expert_params.theta_tuning(self.theta_tunings()[nc].clone());
-                }
+                expert_params.kpls_dim(kpls_dim);
+                expert_params.theta_tuning(theta_tuning);
                 debug!("Train best expert...");
                 expert_params.train(&xtrain.view(), &ytrain.view())
             }
@@ -307,64 +745,112 @@ impl GpMixtureValidParams<f64> {
                 sparse_method,
                 ..
             } => {
-                let inducings = inducings.to_owned();
-                let best_expert_params: std::result::Result<Box<dyn SgpSurrogateParams>, MoeError> =
-                    match best.0.as_str() {
-                        "Constant_SquaredExponential" => {
-                            Ok(make_sgp_surrogate_params!(SquaredExponential, inducings))
-                        }
-                        "Constant_AbsoluteExponential" => {
-                            Ok(make_sgp_surrogate_params!(AbsoluteExponential, inducings))
-                        }
-                        "Constant_Matern32" => Ok(make_sgp_surrogate_params!(Matern32, inducings)),
-                        "Constant_Matern52" => Ok(make_sgp_surrogate_params!(Matern52, inducings)),
-                        _ => {
-                            return Err(MoeError::ExpertError(format!(
-                                "Unknown expert {}",
-                                best.0
-                            )));
-                        }
-                    };
-                let mut expert_params = best_expert_params?;
+                let mut expert_params = F::sgp_surrogate_params(name, inducings.to_owned())?;
                 let seed = self.rng().r#gen();
-                debug!("Theta tuning = {:?}", self.theta_tunings());
                 expert_params.sparse_method(*sparse_method);
                 expert_params.seed(seed);
                 expert_params.n_start(self.n_start());
-                expert_params.kpls_dim(self.kpls_dim());
-                expert_params.theta_tuning(self.theta_tunings()[0].clone());
+                expert_params.kpls_dim(kpls_dim);
+                expert_params.theta_tuning(theta_tuning);
                 debug!("Train best expert...");
                 expert_params.train(&xtrain.view(), &ytrain.view())
             }
-        };
+            GpType::RobustGp { likelihood } => {
+                let mut expert_params = F::rgp_surrogate_params(name, *likelihood)?;
+                expert_params.n_start(self.n_start());
+                expert_params.max_eval(self.max_eval());
+                expert_params.kpls_dim(kpls_dim);
+                expert_params.theta_tuning(theta_tuning);
+                debug!("Train best robust expert...");
+                expert_params.train(&xtrain.view(), &ytrain.view())
+            }
+            GpType::BayesianGp {
+                n_samples,
+                noise_bounds,
+            } => {
+                let mut expert_params = F::bgp_surrogate_params(name)?;
+                let seed = self.rng().r#gen();
+                expert_params.n_samples(*n_samples);
+                expert_params.noise_bounds(
+                    noise_bounds.map(|(lo, up)| (lo.to_f64().unwrap(), up.to_f64().unwrap())),
+                );
+                expert_params.seed(seed);
+                expert_params.n_start(self.n_start());
+                expert_params.max_eval(self.max_eval());
+                expert_params.kpls_dim(kpls_dim);
+                expert_params.theta_tuning(theta_tuning);
+                debug!("Train best Bayesian expert...");
+                expert_params.train(&xtrain.view(), &ytrain.view())
+            }
+        }
+    }
 
-        debug!("...after best expert training");
-        if let Some(v) = best.1 {
-            info!("Best expert {} accuracy={}", best.0, v);
+    /// Refit the expert of the `nc`-th cluster on the given cluster data, the given GP `expert`
+    /// model and hyperparameters being kept if `keep_hyperparameters` is true
+    fn refit_expert(
+        &self,
+        nc: usize,
+        nx: usize,
+        data: &ArrayBase<impl Data<Elem = F>, Ix2>,
+        expert: &dyn FullGpSurrogate,
+        scores: &ExpertScores,
+        keep_hyperparameters: bool,
+    ) -> Result<(Box<dyn FullGpSurrogate>, ExpertScores)> {
+        match self.expert_type(nc, data.nrows(), nx) {
+            // GP expert with selected model and hyperparameters kept
+            ExpertType::Gp(gp_type) if keep_hyperparameters && !scores.scores().is_empty() => {
+                let xtrain = cast_array::<F, f64, _>(&data.slice(s![.., ..nx]));
+                let ytrain = cast_array::<F, f64, _>(&data.slice(s![.., nx..]));
+                // remove kpls label suffix if any
+                let name = scores.best().split("_PLS(").next().unwrap();
+                if let Some(plugin) = self.expert_plugin(name) {
+                    debug!("Refit plugin expert {name} of cluster #{nc}");
+                    let expert = plugin.train(&xtrain.view(), &ytrain.view())?;
+                    return Ok((expert, scores.clone()));
+                }
+                let theta = expert.theta();
+                let kpls_dim = if theta.len() < nx {
+                    Some(theta.len())
+                } else {
+                    None
+                };
+                debug!("Refit expert {name} of cluster #{nc} with fixed theta={theta}");
+                let expert = self.train_expert(
+                    &gp_type,
+                    name,
+                    kpls_dim,
+                    ThetaTuning::Fixed(theta),
+                    &xtrain,
+                    &ytrain,
+                )?;
+                Ok((expert, scores.clone()))
+            }
+            _ => self.find_best_expert(nc, nx, data),
         }
-        expert
     }
 
     /// Take the best heaviside factor from 0.1 to 2.1 (step 0.1).
-    /// Mixture (`gmx` and experts`) is already trained only the continuous recombination is changed
+    /// Mixture (clustering and experts`) is already trained only the continuous recombination is changed
     /// and the factor giving the smallest prediction error on the given test data  
     /// Used only in case of smooth recombination
     fn optimize_heaviside_factor(
         &self,
         experts: &[Box<dyn FullGpSurrogate>],
-        gmx: &GaussianMixture<f64>,
-        xtest: &ArrayBase<impl Data<Elem = f64>, Ix2>,
-        ytest: &ArrayBase<impl Data<Elem = f64>, Ix1>,
+        clustering: &Clustering,
+        xtest: &ArrayBase<impl Data<Elem = F>, Ix2>,
+        ytest: &ArrayBase<impl Data<Elem = F>, Ix1>,
     ) -> f64 {
         if self.recombination() == Recombination::Hard || self.n_clusters().is_mono() {
             1.
         } else {
+            let xtest = cast_array::<F, f64, _>(xtest);
+            let ytest = cast_array::<F, f64, _>(ytest);
             let scale_factors = Array1::linspace(0.1, 2.1, 20);
             let errors = scale_factors.map(move |&factor| {
-                let gmx2 = gmx.clone();
-                let gmx2 = gmx2.heaviside_factor(factor);
-                let pred = predict_smooth(experts, &gmx2, xtest).unwrap();
-                pred.sub(ytest).mapv(|x| x * x).sum().sqrt() / xtest.mapv(|x| x * x).sum().sqrt()
+                let clustering = clustering.clone().heaviside_factor(factor);
+                let probas = clustering.predict_probas(&xtest);
+                let pred = predict_smooth(experts, &probas, &xtest).unwrap();
+                pred.sub(&ytest).mapv(|x| x * x).sum().sqrt() / xtest.mapv(|x| x * x).sum().sqrt()
             });
 
             let min_error_index = errors.argmin().unwrap();
@@ -403,16 +889,26 @@ fn check_number_of_points<F>(
     Ok(())
 }
 
-/// Predict outputs at given points with `experts` and gaussian mixture `gmx`.
-/// `gmx` is used to get the probability of x to belongs to one cluster
-/// or another (ie responsabilities). Those responsabilities are used to combine
-/// output values predict by each cluster experts.
+/// Deferred computation of the error of a tested expert
+type ErrorTask<'a> = Box<dyn Fn() -> f64 + Send + 'a>;
+
+/// Compute the errors of the tested experts in parallel, the order of the
+/// given tasks is preserved to get deterministic selection
+fn eval_error_tasks(tasks: Vec<(String, ErrorTask<'_>)>) -> Vec<(String, f64)> {
+    tasks
+        .into_par_iter()
+        .map(|(name, task)| (name, task()))
+        .collect()
+}
+
+/// Predict outputs at given points with `experts` and their responsabilities `probas`
+/// (ie the probability of x to belongs to one cluster or another) given as a (n, n_clusters) matrix.
+/// Those responsabilities are used to combine output values predict by each cluster experts.
 fn predict_smooth(
     experts: &[Box<dyn FullGpSurrogate>],
-    gmx: &GaussianMixture<f64>,
+    probas: &Array2<f64>,
     points: &ArrayBase<impl Data<Elem = f64>, Ix2>,
 ) -> Result<Array1<f64>> {
-    let probas = gmx.predict_probas(points);
     let preds: Array1<f64> = experts
         .iter()
         .enumerate()
@@ -421,27 +917,77 @@ fn predict_smooth(
     Ok(preds)
 }
 
+/// Smallest number of PLS components (at most `max_dim`) of `x` explaining at least
+/// the `threshold` ratio of the variance of `y`
+fn explained_variance_kpls_dim(
+    x: &Array2<f64>,
+    y: &Array2<f64>,
+    threshold: f64,
+    max_dim: usize,
+) -> usize {
+    let dataset = Dataset::new(x.to_owned(), y.to_owned());
+    let Ok(pls) = PlsRegression::params(max_dim).fit(&dataset) else {
+        return max_dim;
+    };
+    let scores = pls.transform(dataset).records;
+    let yc = y.column(0).mapv(|v| v - y.mean().unwrap());
+    let total = yc.dot(&yc);
+    // PLS scores are orthogonal: explained variances of components add up
+    let mut explained = 0.;
+    for (k, t) in scores.columns().into_iter().enumerate() {
+        let tt = t.dot(&t);
+        if tt > 0. {
+            explained += t.dot(&yc).powi(2) / tt;
+        }
+        if explained >= threshold * total {
+            return k + 1;
+        }
+    }
+    max_dim
+}
+
 /// Mixture of gaussian process experts
-/// Implementation note: the structure is not generic over 'F: Float' to be able to
+///
+/// The float type `F` (`f64` or `f32`) is the precision used to train the experts
+/// and to store training data. The experts responsabilities are computed in double precision
+/// while the `F` typed predictions (see [GpMixture::predict]) recombine experts predictions
+/// in `F` precision.
+///
+/// Implementation note: the experts are not generic over 'F: Float' to be able to
 /// implement use serde easily as deserialization of generic impls is not supported yet
 /// See <https://github.com/dtolnay/typetag/issues/1>
 #[cfg_attr(feature = "serializable", derive(Serialize, Deserialize))]
-pub struct GpMixture {
+pub struct GpMixture<F: Float = f64> {
     /// The mode of recombination to get the output prediction from experts prediction
     recombination: Recombination<f64>,
     /// The list of the best experts trained on each cluster
     experts: Vec<Box<dyn FullGpSurrogate>>,
+    /// The scores of the tested experts on each cluster
+    #[cfg_attr(feature = "serializable", serde(default))]
+    expert_scores: Vec<ExpertScores>,
     /// The gaussian mixture allowing to predict cluster responsabilities for a given point
     gmx: GaussianMixture<f64>,
+    /// The partition of the input space gating the experts (take precedence over gmx)
+    #[cfg_attr(feature = "serializable", serde(default))]
+    partition: Option<Partition>,
+    /// The gating network computing the experts responsabilities (take precedence over gmx)
+    #[cfg_attr(feature = "serializable", serde(default))]
+    gating: Option<GatingNetwork>,
+    /// The variance computation mode with smooth recombination
+    #[cfg_attr(feature = "serializable", serde(default))]
+    mixture_variance: MixtureVariance,
     /// Gp type
-    gp_type: GpType<f64>,
+    gp_type: GpType<F>,
     /// Training inputs
-    training_data: (Array2<f64>, Array1<f64>),
+    training_data: (Array2<F>, Array1<F>),
     /// Params used to fit this model
-    params: GpMixtureValidParams<f64>,
+    params: GpMixtureValidParams<F>,
 }
 
-impl std::fmt::Display for GpMixture {
+/// Mixture of gaussian process experts trained in single precision
+pub type GpMixtureF32 = GpMixture<f32>;
+
+impl<F: Float> std::fmt::Display for GpMixture<F> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let recomb = match self.recombination() {
             Recombination::Hard => "Hard".to_string(),
@@ -458,7 +1004,7 @@ impl std::fmt::Display for GpMixture {
     }
 }
 
-impl Clustered for GpMixture {
+impl<F: Float> Clustered for GpMixture<F> {
     /// Number of clusters
     fn n_clusters(&self) -> usize {
         self.gmx.n_clusters()
@@ -471,88 +1017,182 @@ impl Clustered for GpMixture {
 
     /// Convert to clustering
     fn to_clustering(&self) -> Clustering {
-        Clustering {
-            recombination: self.recombination(),
-            gmx: self.gmx.clone(),
-        }
+        Clustering::new(self.gmx.clone(), self.recombination())
+            .with_partition(self.partition.clone())
+            .with_gating(self.gating.clone())
     }
 }
 
-#[cfg_attr(feature = "serializable", typetag::serde)]
-impl GpSurrogate for GpMixture {
-    fn dims(&self) -> (usize, usize) {
-        self.experts[0].dims()
+/// Sample paths of a mixture with hard recombination: a path value at a given point
+/// is the value of the path of the expert of the cluster containing the point
+struct HardMixtureSamplePaths {
+    clustering: Clustering,
+    paths: Vec<Box<dyn SamplePaths>>,
+}
+
+impl SamplePaths for HardMixtureSamplePaths {
+    fn n_traj(&self) -> usize {
+        self.paths[0].n_traj()
     }
 
-    fn predict(&self, x: &ArrayView2<f64>) -> Result<Array1<f64>> {
-        match self.recombination {
-            Recombination::Hard => self.predict_hard(x),
-            Recombination::Smooth(_) => self.predict_smooth(x),
+    fn predict(&self, x: &ArrayView2<f64>) -> Result<Array2<f64>> {
+        let clusters = self.clustering.predict_clusters(x);
+        let mut values = Array2::zeros((x.nrows(), self.n_traj()));
+        for ((mut v, xi), &c) in values.rows_mut().into_iter().zip(x.rows()).zip(&clusters) {
+            v.assign(&self.paths[c].predict(&xi.insert_axis(Axis(0)))?.row(0));
         }
+        Ok(values)
     }
 
-    fn predict_var(&self, x: &ArrayView2<f64>) -> Result<Array1<f64>> {
-        match self.recombination {
-            Recombination::Hard => self.predict_var_hard(x),
-            Recombination::Smooth(_) => self.predict_var_smooth(x),
+    fn predict_gradients(&self, x: &ArrayView2<f64>) -> Result<Array3<f64>> {
+        let clusters = self.clustering.predict_clusters(x);
+        let mut drv = Array3::zeros((x.nrows(), x.ncols(), self.n_traj()));
+        for ((mut d, xi), &c) in drv.outer_iter_mut().zip(x.rows()).zip(&clusters) {
+            let xi = xi.insert_axis(Axis(0));
+            d.assign(&self.paths[c].predict_gradients(&xi)?.index_axis(Axis(0), 0));
         }
+        Ok(drv)
     }
-    /// Save Moe model in given file.
-    #[cfg(feature = "persistent")]
-    fn save(&self, path: &str, format: GpFileFormat) -> Result<()> {
-        let mut file = fs::File::create(path).unwrap();
+}
 
-        let bytes = match format {
-            GpFileFormat::Json => serde_json::to_vec(self).map_err(MoeError::SaveJsonError)?,
-            GpFileFormat::Binary => bincode::serialize(self).map_err(MoeError::SaveBinaryError)?,
-        };
-        file.write_all(&bytes)?;
+/// A macro to implement surrogate traits for the mixture trained with the given float type
+macro_rules! impl_mixture_surrogate {
+    ($mixture:ident) => {
+        #[cfg_attr(feature = "serializable", typetag::serde)]
+        impl GpSurrogate for $mixture {
+            fn dims(&self) -> (usize, usize) {
+                self.experts[0].dims()
+            }
 
-        Ok(())
-    }
-}
+            fn predict(&self, x: &ArrayView2<f64>) -> Result<Array1<f64>> {
+                match self.recombination {
+                    Recombination::Hard => self.predict_hard(x),
+                    Recombination::Smooth(_) => self.predict_smooth(x),
+                }
+            }
 
-#[cfg_attr(feature = "serializable", typetag::serde)]
-impl GpSurrogateExt for GpMixture {
-    fn predict_gradients(&self, x: &ArrayView2<f64>) -> Result<Array2<f64>> {
-        match self.recombination {
-            Recombination::Hard => self.predict_gradients_hard(x),
-            Recombination::Smooth(_) => self.predict_gradients_smooth(x),
+            fn predict_var(&self, x: &ArrayView2<f64>) -> Result<Array1<f64>> {
+                match self.recombination {
+                    Recombination::Hard => self.predict_var_hard(x),
+                    Recombination::Smooth(_) => self.predict_var_smooth(x),
+                }
+            }
+
+            fn predict_f32(&self, x: &ArrayView2<f32>) -> Result<Array1<f32>> {
+                self.predict_in(x)
+            }
+
+            fn predict_var_f32(&self, x: &ArrayView2<f32>) -> Result<Array1<f32>> {
+                self.predict_var_in(x)
+            }
+            /// Save Moe model in given file.
+            #[cfg(feature = "persistent")]
+            fn save(&self, path: &str, format: GpFileFormat) -> Result<()> {
+                let mut file = fs::File::create(path).unwrap();
+
+                let bytes = match format {
+                    GpFileFormat::Json => {
+                        serde_json::to_vec(self).map_err(MoeError::SaveJsonError)?
+                    }
+                    GpFileFormat::Binary => {
+                        bincode::serialize(self).map_err(MoeError::SaveBinaryError)?
+                    }
+                };
+                file.write_all(&bytes)?;
+
+                Ok(())
+            }
         }
-    }
 
-    fn predict_var_gradients(&self, x: &ArrayView2<f64>) -> Result<Array2<f64>> {
-        match self.recombination {
-            Recombination::Hard => self.predict_var_gradients_hard(x),
-            Recombination::Smooth(_) => self.predict_var_gradients_smooth(x),
+        #[cfg_attr(feature = "serializable", typetag::serde)]
+        impl GpSurrogateExt for $mixture {
+            fn predict_gradients(&self, x: &ArrayView2<f64>) -> Result<Array2<f64>> {
+                match self.recombination {
+                    Recombination::Hard => self.predict_gradients_hard(x),
+                    Recombination::Smooth(_) => self.predict_gradients_smooth(x),
+                }
+            }
+
+            fn predict_var_gradients(&self, x: &ArrayView2<f64>) -> Result<Array2<f64>> {
+                match self.recombination {
+                    Recombination::Hard => self.predict_var_gradients_hard(x),
+                    Recombination::Smooth(_) => self.predict_var_gradients_smooth(x),
+                }
+            }
+
+            fn predict_hessians(&self, x: &ArrayView2<f64>) -> Result<Array3<f64>> {
+                match self.recombination {
+                    Recombination::Hard => self.predict_hessians_hard(x),
+                    Recombination::Smooth(_) => self.predict_hessians_smooth(x),
+                }
+            }
+
+            fn predict_var_hessians(&self, x: &ArrayView2<f64>) -> Result<Array3<f64>> {
+                match self.recombination {
+                    Recombination::Hard => self.predict_var_hessians_hard(x),
+                    Recombination::Smooth(_) => self.predict_var_hessians_smooth(x),
+                }
+            }
+
+            fn sample(&self, x: &ArrayView2<f64>, n_traj: usize) -> Result<Array2<f64>> {
+                if self.n_clusters() != 1 {
+                    return Err(MoeError::SampleError(format!(
+                        "Can not sample when several clusters {}",
+                        self.n_clusters()
+                    )));
+                }
+                self.sample_expert(0, x, n_traj)
+            }
+
+            fn sample_paths(&self, n_traj: usize, seed: u64) -> Result<Box<dyn SamplePaths>> {
+                if self.n_clusters() == 1 {
+                    return self.experts[0].sample_paths(n_traj, seed);
+                }
+                match self.recombination {
+                    Recombination::Hard => {
+                        let paths = self
+                            .experts
+                            .iter()
+                            .enumerate()
+                            .map(|(k, expert)| {
+                                expert.sample_paths(n_traj, seed.wrapping_add(k as u64))
+                            })
+                            .collect::<Result<Vec<_>>>()?;
+                        Ok(Box::new(HardMixtureSamplePaths {
+                            clustering: self.to_clustering(),
+                            paths,
+                        }))
+                    }
+                    Recombination::Smooth(_) => Err(MoeError::SampleError(format!(
+                        "Can not draw sample paths with smooth recombination of {} clusters",
+                        self.n_clusters()
+                    ))),
+                }
+            }
         }
-    }
 
-    fn sample(&self, x: &ArrayView2<f64>, n_traj: usize) -> Result<Array2<f64>> {
-        if self.n_clusters() != 1 {
-            return Err(MoeError::SampleError(format!(
-                "Can not sample when several clusters {}",
-                self.n_clusters()
-            )));
+        impl MixtureGpSurrogate for $mixture {
+            /// Selected experts in the mixture
+            fn experts(&self) -> &Vec<Box<dyn FullGpSurrogate>> {
+                &self.experts
+            }
         }
-        self.sample_expert(0, x, n_traj)
-    }
+    };
 }
 
-impl CrossValScore<f64, MoeError, GpMixtureParams<f64>, Self> for GpMixture {
-    fn training_data(&self) -> &(Array2<f64>, Array1<f64>) {
-        &self.training_data
-    }
+impl_mixture_surrogate!(GpMixture);
+impl_mixture_surrogate!(GpMixtureF32);
 
-    fn params(&self) -> GpMixtureParams<f64> {
-        GpMixtureParams::<f64>::from(self.params.clone())
+impl<F: SurrogateFloat> CrossValScore<F, MoeError, GpMixtureParams<F>, Self> for GpMixture<F>
+where
+    Self: GpSurrogate + GpSurrogateExt,
+{
+    fn training_data(&self) -> &(Array2<F>, Array1<F>) {
+        &self.training_data
     }
-}
 
-impl MixtureGpSurrogate for GpMixture {
-    /// Selected experts in the mixture
-    fn experts(&self) -> &Vec<Box<dyn FullGpSurrogate>> {
-        &self.experts
+    fn params(&self) -> GpMixtureParams<F> {
+        GpMixtureParams::<F>::from(self.params.clone())
     }
 }
 
@@ -561,9 +1201,11 @@ impl GpMixture {
     pub fn params() -> GpMixtureParams<f64> {
         GpMixtureParams::new()
     }
+}
 
+impl<F: Float> GpMixture<F> {
     /// Retrieve output dimensions from
-    pub fn gp_type(&self) -> &GpType<f64> {
+    pub fn gp_type(&self) -> &GpType<F> {
         &self.gp_type
     }
 
@@ -577,9 +1219,125 @@ impl GpMixture {
         &self.gmx
     }
 
-    /// Sets recombination mode
+    /// Export the mixture as a self-contained set of coefficients (see [GpMixtureCoefficients]).
+    ///
+    /// Returns an error when an expert is not a [GaussianProcess] which can be exported
+    /// (see [GaussianProcess::coefficients]).
+    pub fn coefficients(&self) -> Result<GpMixtureCoefficients> {
+        GpMixtureCoefficients::new(
+            self.recombination,
+            &self.gmx,
+            self.partition.as_ref(),
+            self.gating.as_ref(),
+            self.mixture_variance,
+            &self.experts,
+        )
+    }
+
+    /// Scores of the experts tested on each cluster during selection
+    /// explaining why the experts were chosen
+    pub fn expert_scores(&self) -> &[ExpertScores] {
+        &self.expert_scores
+    }
+
+    /// Diagnostics of the trained mixture: for each cluster, number of assigned training points,
+    /// selected expert with its score, hyperparameters and likelihood (see [MixtureReport])
+    pub fn report(&self) -> MixtureReport {
+        let gating = match (&self.partition, &self.gating) {
+            (Some(partition), _) => partition.name(),
+            (None, Some(_)) => "GatingNetwork",
+            (None, None) => "GaussianMixture",
+        };
+        let training_clusters =
+            self.predict_clusters(&cast_array::<F, f64, _>(&self.training_data.0));
+        MixtureReport::new(
+            self.recombination,
+            gating,
+            self.gmx.weights(),
+            &self.experts,
+            &self.expert_scores,
+            &training_clusters,
+        )
+    }
+
+    /// Diagnostics of the trained mixture (see [GpMixture::report]) including
+    /// the experts responsabilities evaluated at the n points of the given (n, nx) `grid`
+    pub fn report_with_grid(&self, grid: &ArrayBase<impl Data<Elem = f64>, Ix2>) -> MixtureReport {
+        self.report()
+            .with_responsibility_map(self.responsibility_map(grid))
+    }
+
+    /// Experts responsabilities evaluated at the n points of the given (n, nx) `grid`.
+    ///
+    /// With a partition, responsabilities are 1 for the region containing the point, 0 otherwise.
+    pub fn responsibility_map(
+        &self,
+        grid: &ArrayBase<impl Data<Elem = f64>, Ix2>,
+    ) -> ResponsibilityMap {
+        let clusters = self.predict_clusters(grid);
+        let responsibilities = if self.partition.is_some() {
+            let mut probas = Array2::zeros((grid.nrows(), self.n_clusters()));
+            Zip::from(probas.rows_mut())
+                .and(&clusters)
+                .for_each(|mut p, &c| p[c] = 1.);
+            probas
+        } else {
+            self.predict_probas(grid)
+        };
+        ResponsibilityMap::new(grid, &responsibilities, &clusters)
+    }
+
+    /// Partition of the input space gating the experts when trained with
+    /// [ClusteringMethod::Tree] clustering, or with [ClusteringMethod::KMeans] and
+    /// [ClusteringMethod::Agglomerative] clusterings without gating network
+    pub fn partition(&self) -> Option<&Partition> {
+        self.partition.as_ref()
+    }
+
+    /// Cluster index (i.e. expert index) of the n points given as a (n, nx) matrix
+    pub fn predict_clusters(&self, x: &ArrayBase<impl Data<Elem = f64>, Ix2>) -> Array1<usize> {
+        match (&self.partition, &self.gating) {
+            (Some(partition), _) => partition.predict(x),
+            (None, Some(gating)) => gating.predict(x),
+            (None, None) => self.gmx.predict(x),
+        }
+    }
+
+    /// Gating network computing the experts responsabilities when trained with
+    /// [GatingMethod::LogisticRegression] gating
+    pub fn gating(&self) -> Option<&GatingNetwork> {
+        self.gating.as_ref()
+    }
+
+    /// Experts responsabilities (n, n_clusters) at the n points given as a (n, nx) matrix
+    fn predict_probas(&self, x: &ArrayBase<impl Data<Elem = f64>, Ix2>) -> Array2<f64> {
+        match &self.gating {
+            Some(gating) => gating.predict_probas(x),
+            None => self.gmx.predict_probas(x),
+        }
+    }
+
+    /// Derivatives (n, n_clusters, nx) of the experts responsabilities
+    fn predict_probas_derivatives(&self, x: &ArrayBase<impl Data<Elem = f64>, Ix2>) -> Array3<f64> {
+        match &self.gating {
+            Some(gating) => gating.predict_probas_derivatives(x),
+            None => self.gmx.predict_probas_derivatives(x),
+        }
+    }
+
+    /// Hessians (n, n_clusters, nx, nx) of the experts responsabilities
+    fn predict_probas_hessians(&self, x: &ArrayBase<impl Data<Elem = f64>, Ix2>) -> Array4<f64> {
+        match &self.gating {
+            Some(gating) => gating.predict_probas_hessians(x),
+            None => self.gmx.predict_probas_hessians(x),
+        }
+    }
+
+    /// Sets recombination mode, recombination of experts gated by a partition
+    /// remains hard
     pub fn set_recombination(mut self, recombination: Recombination<f64>) -> Self {
         self.recombination = match recombination {
+            _ if self.partition.is_some() => Recombination::Hard,
             Recombination::Hard => recombination,
             Recombination::Smooth(Some(_)) => recombination,
             Recombination::Smooth(_) => Recombination::Smooth(Some(1.)),
@@ -587,6 +1345,17 @@ impl GpMixture {
         self
     }
 
+    /// Variance computation mode used with smooth recombination
+    pub fn mixture_variance(&self) -> MixtureVariance {
+        self.mixture_variance
+    }
+
+    /// Sets variance computation mode used with smooth recombination
+    pub fn set_mixture_variance(mut self, mixture_variance: MixtureVariance) -> Self {
+        self.mixture_variance = mixture_variance;
+        self
+    }
+
     pub fn set_gmx(
         mut self,
         weights: Array1<f64>,
@@ -602,23 +1371,51 @@ impl GpMixture {
         self
     }
 
+    /// Registers the user-defined expert trained by the given `factory` under the given `name`
+    /// (see [GpMixtureParams::register_expert]).
+    ///
+    /// Plugins are not saved with the mixture: they have to be registered again on a loaded
+    /// mixture before updating clusters relying on them (see [GpMixture::update]).
+    pub fn register_expert(
+        mut self,
+        name: &str,
+        factory: impl GpSurrogateParams + Send + Sync + 'static,
+    ) -> Self {
+        self.params
+            .add_expert_plugin(ExpertPlugin::new(name, factory));
+        self
+    }
+
     /// Predict outputs at a set of points `x` specified as (n, nx) matrix.
     /// Gaussian Mixture is used to get the probability of the point to belongs to one cluster
     /// or another (ie responsabilities).     
     /// The smooth recombination of each cluster expert responsabilty is used to get the result.
     pub fn predict_smooth(&self, x: &ArrayBase<impl Data<Elem = f64>, Ix2>) -> Result<Array1<f64>> {
-        predict_smooth(&self.experts, &self.gmx, x)
+        predict_smooth(&self.experts, &self.predict_probas(x), x)
     }
 
     /// Predict variances at a set of points `x` specified as (n, nx) matrix.
     /// Gaussian Mixture is used to get the probability of the point to belongs to one cluster
     /// or another (ie responsabilities).
-    /// The smooth recombination of each cluster expert responsabilty is used to get the result.
+    /// The smooth recombination of each cluster expert responsabilty is used to get the result
+    /// wrt the [MixtureVariance] mode.
     pub fn predict_var_smooth(
         &self,
         x: &ArrayBase<impl Data<Elem = f64>, Ix2>,
     ) -> Result<Array1<f64>> {
-        let probas = self.gmx.predict_probas(x);
+        let probas = self.predict_probas(x);
+        if self.mixture_variance == MixtureVariance::TotalVariance {
+            let mut mean = Array1::<f64>::zeros(x.nrows());
+            let mut second = Array1::<f64>::zeros(x.nrows());
+            for (i, gp) in self.experts.iter().enumerate() {
+                let p = probas.column(i);
+                let mu = gp.predict(&x.view())?;
+                let var = gp.predict_var(&x.view())?;
+                mean += &(&mu * &p);
+                second += &((var + &mu * &mu) * p);
+            }
+            return Ok((second - &mean * &mean).mapv(|v| v.max(0.)));
+        }
         let preds: Array1<f64> = self
             .experts
             .iter()
@@ -639,8 +1436,8 @@ impl GpMixture {
         &self,
         x: &ArrayBase<impl Data<Elem = f64>, Ix2>,
     ) -> Result<Array2<f64>> {
-        let probas = self.gmx.predict_probas(x);
-        let probas_drv = self.gmx.predict_probas_derivatives(x);
+        let probas = self.predict_probas(x);
+        let probas_drv = self.predict_probas_derivatives(x);
         let mut drv = Array2::<f64>::zeros((x.nrows(), x.ncols()));
 
         Zip::from(drv.rows_mut())
@@ -687,8 +1484,11 @@ impl GpMixture {
         &self,
         x: &ArrayBase<impl Data<Elem = f64>, Ix2>,
     ) -> Result<Array2<f64>> {
-        let probas = self.gmx.predict_probas(x);
-        let probas_drv = self.gmx.predict_probas_derivatives(x);
+        if self.mixture_variance == MixtureVariance::TotalVariance {
+            return self.predict_total_var_gradients_smooth(x);
+        }
+        let probas = self.predict_probas(x);
+        let probas_drv = self.predict_probas_derivatives(x);
 
         let mut drv = Array2::<f64>::zeros((x.nrows(), x.ncols()));
 
@@ -720,7 +1520,7 @@ impl GpMixture {
                     .for_each(|mut t, p, der| t.assign(&(der.to_owned().mapv(|v| v * p * p))));
                 let term1 = term1.sum_axis(Axis(0));
 
-                let term2 = (p.to_owned() * pprime * preds).mapv(|v| 2. * v);
+                let term2 = (p.to_owned().insert_axis(Axis(1)) * pprime * preds).mapv(|v| 2. * v);
                 let term2 = term2.sum_axis(Axis(0));
 
                 y.assign(&(term1 + term2));
@@ -729,17 +1529,198 @@ impl GpMixture {
         Ok(drv)
     }
 
-    /// Predict outputs at a set of points `x` specified as (n, nx) matrix.
-    /// Gaussian Mixture is used to get the cluster where the point belongs (highest responsability)
-    /// Then the expert of the cluster is used to predict the output value.
-    /// Returns the ouputs as a (n, 1) column vector
-    pub fn predict_hard(&self, x: &ArrayBase<impl Data<Elem = f64>, Ix2>) -> Result<Array1<f64>> {
-        let clustering = self.gmx.predict(x);
-        trace!("Clustering {clustering:?}");
-        let mut preds = Array1::zeros((x.nrows(),));
-        Zip::from(&mut preds)
-            .and(x.rows())
-            .and(&clustering)
+    /// Predict hessians of the output at a set of points `x` specified as (n, nx) matrix.
+    /// Return hessians as a (n, nx, nx) array where the ith matrix contain the second derivatives
+    /// of the output wrt the nx components of `x` valued at the ith x point.
+    /// The smooth recombination of each cluster expert responsability is used to get the result.
+    pub fn predict_hessians_smooth(
+        &self,
+        x: &ArrayBase<impl Data<Elem = f64>, Ix2>,
+    ) -> Result<Array3<f64>> {
+        let probas = self.predict_probas(x);
+        let probas_drv = self.predict_probas_derivatives(x);
+        let probas_hess = self.predict_probas_hessians(x);
+        let mut hess = Array3::<f64>::zeros((x.nrows(), x.ncols(), x.ncols()));
+
+        Zip::from(hess.outer_iter_mut())
+            .and(x.rows())
+            .and(probas.rows())
+            .and(probas_drv.outer_iter())
+            .and(probas_hess.outer_iter())
+            .for_each(|mut h, xi, p, pprime, psecond| {
+                let xii = xi.insert_axis(Axis(0));
+                for (k, gp) in self.experts.iter().enumerate() {
+                    let pred = gp.predict(&xii).unwrap()[0];
+                    let drv = gp.predict_gradients(&xii).unwrap();
+                    let drv = drv.row(0);
+                    let hs = gp.predict_hessians(&xii).unwrap();
+                    let hs = hs.index_axis(Axis(0), 0);
+                    let dp = pprime.row(k);
+
+                    // p_k * H_k + dp_k . dy_k^t + dy_k . dp_k^t + y_k * d2p_k
+                    Zip::indexed(&mut h).for_each(|(a, b), hab| {
+                        *hab += p[k] * hs[[a, b]]
+                            + dp[a] * drv[b]
+                            + drv[a] * dp[b]
+                            + pred * psecond[[k, a, b]];
+                    });
+                }
+            });
+        Ok(hess)
+    }
+
+    /// Predict hessians of the variance at a set of points `x` specified as (n, nx) matrix.
+    /// Return hessians as a (n, nx, nx) array where the ith matrix contain the second derivatives
+    /// of the variance wrt the nx components of `x` valued at the ith x point.
+    /// The smooth recombination of each cluster expert responsability is used to get the result.
+    pub fn predict_var_hessians_smooth(
+        &self,
+        x: &ArrayBase<impl Data<Elem = f64>, Ix2>,
+    ) -> Result<Array3<f64>> {
+        if self.mixture_variance == MixtureVariance::TotalVariance {
+            return self.predict_total_var_hessians_smooth(x);
+        }
+        let probas = self.predict_probas(x);
+        let probas_drv = self.predict_probas_derivatives(x);
+        let probas_hess = self.predict_probas_hessians(x);
+        let mut hess = Array3::<f64>::zeros((x.nrows(), x.ncols(), x.ncols()));
+
+        Zip::from(hess.outer_iter_mut())
+            .and(x.rows())
+            .and(probas.rows())
+            .and(probas_drv.outer_iter())
+            .and(probas_hess.outer_iter())
+            .for_each(|mut h, xi, p, pprime, psecond| {
+                let xii = xi.insert_axis(Axis(0));
+                for (k, gp) in self.experts.iter().enumerate() {
+                    let var = gp.predict_var(&xii).unwrap()[0];
+                    let drv = gp.predict_var_gradients(&xii).unwrap();
+                    let drv = drv.row(0);
+                    let hs = gp.predict_var_hessians(&xii).unwrap();
+                    let hs = hs.index_axis(Axis(0), 0);
+                    let dp = pprime.row(k);
+                    let pk = p[k];
+
+                    // p_k^2 * H_k + 2 * p_k * (dp_k . dv_k^t + dv_k . dp_k^t)
+                    //   + 2 * v_k * (dp_k . dp_k^t + p_k * d2p_k)
+                    Zip::indexed(&mut h).for_each(|(a, b), hab| {
+                        *hab += pk * pk * hs[[a, b]]
+                            + 2. * pk * (dp[a] * drv[b] + drv[a] * dp[b])
+                            + 2. * var * (dp[a] * dp[b] + pk * psecond[[k, a, b]]);
+                    });
+                }
+            });
+        Ok(hess)
+    }
+
+    /// Derivatives (n, nx) of the total variance `sum_k p_k * (var_k + mu_k^2) - mu^2`
+    fn predict_total_var_gradients_smooth(
+        &self,
+        x: &ArrayBase<impl Data<Elem = f64>, Ix2>,
+    ) -> Result<Array2<f64>> {
+        let probas = self.predict_probas(x);
+        let probas_drv = self.predict_probas_derivatives(x);
+        let mut drv = Array2::<f64>::zeros((x.nrows(), x.ncols()));
+
+        Zip::from(drv.rows_mut())
+            .and(x.rows())
+            .and(probas.rows())
+            .and(probas_drv.outer_iter())
+            .for_each(|mut d, xi, p, pprime| {
+                let xii = xi.insert_axis(Axis(0));
+                let mut mean = 0.;
+                let mut mean_drv = Array1::<f64>::zeros(xi.len());
+                let mut second_drv = Array1::<f64>::zeros(xi.len());
+                for (k, gp) in self.experts.iter().enumerate() {
+                    let mu = gp.predict(&xii).unwrap()[0];
+                    let dmu = gp.predict_gradients(&xii).unwrap().row(0).to_owned();
+                    let var = gp.predict_var(&xii).unwrap()[0];
+                    let dvar = gp.predict_var_gradients(&xii).unwrap().row(0).to_owned();
+                    let dp = pprime.row(k);
+
+                    mean += p[k] * mu;
+                    mean_drv += &(&dp * mu + &dmu * p[k]);
+                    // dp_k * (v_k + mu_k^2) + p_k * (dv_k + 2 * mu_k * dmu_k)
+                    second_drv += &(&dp * (var + mu * mu) + (dvar + &dmu * (2. * mu)) * p[k]);
+                }
+                d.assign(&(second_drv - mean_drv * (2. * mean)));
+            });
+        Ok(drv)
+    }
+
+    /// Hessians (n, nx, nx) of the total variance `sum_k p_k * (var_k + mu_k^2) - mu^2`
+    fn predict_total_var_hessians_smooth(
+        &self,
+        x: &ArrayBase<impl Data<Elem = f64>, Ix2>,
+    ) -> Result<Array3<f64>> {
+        let probas = self.predict_probas(x);
+        let probas_drv = self.predict_probas_derivatives(x);
+        let probas_hess = self.predict_probas_hessians(x);
+        let nx = x.ncols();
+        let mut hess = Array3::<f64>::zeros((x.nrows(), nx, nx));
+
+        Zip::from(hess.outer_iter_mut())
+            .and(x.rows())
+            .and(probas.rows())
+            .and(probas_drv.outer_iter())
+            .and(probas_hess.outer_iter())
+            .for_each(|mut h, xi, p, pprime, psecond| {
+                let xii = xi.insert_axis(Axis(0));
+                let mut mean = 0.;
+                let mut mean_drv = Array1::<f64>::zeros(nx);
+                let mut mean_hess = Array2::<f64>::zeros((nx, nx));
+                for (k, gp) in self.experts.iter().enumerate() {
+                    let mu = gp.predict(&xii).unwrap()[0];
+                    let dmu = gp.predict_gradients(&xii).unwrap().row(0).to_owned();
+                    let hmu = gp.predict_hessians(&xii).unwrap();
+                    let hmu = hmu.index_axis(Axis(0), 0);
+                    let var = gp.predict_var(&xii).unwrap()[0];
+                    let dvar = gp.predict_var_gradients(&xii).unwrap().row(0).to_owned();
+                    let hvar = gp.predict_var_hessians(&xii).unwrap();
+                    let hvar = hvar.index_axis(Axis(0), 0);
+                    let dp = pprime.row(k);
+                    let pk = p[k];
+
+                    // s_k = v_k + mu_k^2 and its derivatives
+                    let s = var + mu * mu;
+                    let ds = dvar + &dmu * (2. * mu);
+                    // d2p_k * s_k + dp_k . ds_k^t + ds_k . dp_k^t
+                    //   + p_k * (H_vk + 2 * (dmu_k . dmu_k^t + mu_k * H_muk))
+                    Zip::indexed(&mut h).for_each(|(a, b), hab| {
+                        *hab += psecond[[k, a, b]] * s
+                            + dp[a] * ds[b]
+                            + ds[a] * dp[b]
+                            + pk * (hvar[[a, b]] + 2. * (dmu[a] * dmu[b] + mu * hmu[[a, b]]));
+                    });
+                    // d2p_k * mu_k + dp_k . dmu_k^t + dmu_k . dp_k^t + p_k * H_muk
+                    Zip::indexed(&mut mean_hess).for_each(|(a, b), hab| {
+                        *hab += psecond[[k, a, b]] * mu
+                            + dp[a] * dmu[b]
+                            + dmu[a] * dp[b]
+                            + pk * hmu[[a, b]];
+                    });
+                    mean += pk * mu;
+                    mean_drv += &(&dp * mu + &dmu * pk);
+                }
+                // - 2 * (dmu . dmu^t + mu * H_mu)
+                Zip::indexed(&mut h).for_each(|(a, b), hab| {
+                    *hab -= 2. * (mean_drv[a] * mean_drv[b] + mean * mean_hess[[a, b]]);
+                });
+            });
+        Ok(hess)
+    }
+
+    /// Predict outputs at a set of points `x` specified as (n, nx) matrix.
+    /// Gaussian Mixture is used to get the cluster where the point belongs (highest responsability)
+    /// Then the expert of the cluster is used to predict the output value.
+    /// Returns the ouputs as a (n, 1) column vector
+    pub fn predict_hard(&self, x: &ArrayBase<impl Data<Elem = f64>, Ix2>) -> Result<Array1<f64>> {
+        let clustering = self.predict_clusters(x);
+        trace!("Clustering {clustering:?}");
+        let mut preds = Array1::zeros((x.nrows(),));
+        Zip::from(&mut preds)
+            .and(x.rows())
+            .and(&clustering)
             .for_each(|y, x, &c| *y = self.experts[c].predict(&x.insert_axis(Axis(0))).unwrap()[0]);
         Ok(preds)
     }
@@ -752,7 +1733,7 @@ impl GpMixture {
         &self,
         x: &ArrayBase<impl Data<Elem = f64>, Ix2>,
     ) -> Result<Array1<f64>> {
-        let clustering = self.gmx.predict(x);
+        let clustering = self.predict_clusters(x);
         trace!("Clustering {clustering:?}");
         let mut variances = Array1::zeros(x.nrows());
         Zip::from(&mut variances)
@@ -766,6 +1747,77 @@ impl GpMixture {
         Ok(variances)
     }
 
+    /// Predict outputs at a set of points `x` specified as (n, nx) matrix in `G` precision.
+    /// Experts predict in `G` precision, without conversion when trained with `G` float type,
+    /// while the responsabilities (or clusters) are computed in double precision.
+    fn predict_in<G: SurrogateFloat>(&self, x: &ArrayView2<G>) -> Result<Array1<G>> {
+        match self.recombination {
+            Recombination::Hard => self.predict_hard_in(x, |gp, x| G::surrogate_predict(gp, x)),
+            Recombination::Smooth(_) => {
+                let probas: Array2<G> = cast_array(&self.predict_probas(&cast_array(x)));
+                let mut preds = Array1::<G>::zeros(x.nrows());
+                for (i, gp) in self.experts.iter().enumerate() {
+                    preds += &(G::surrogate_predict(gp.as_ref(), x)? * probas.column(i));
+                }
+                Ok(preds)
+            }
+        }
+    }
+
+    /// Predict variances at a set of points `x` specified as (n, nx) matrix in `G` precision
+    /// wrt the [MixtureVariance] mode (see [GpMixture::predict_in]).
+    fn predict_var_in<G: SurrogateFloat>(&self, x: &ArrayView2<G>) -> Result<Array1<G>> {
+        if self.recombination == Recombination::Hard {
+            return self.predict_hard_in(x, |gp, x| G::surrogate_predict_var(gp, x));
+        }
+        let probas: Array2<G> = cast_array(&self.predict_probas(&cast_array(x)));
+        if self.mixture_variance == MixtureVariance::TotalVariance {
+            let mut mean = Array1::<G>::zeros(x.nrows());
+            let mut second = Array1::<G>::zeros(x.nrows());
+            for (i, gp) in self.experts.iter().enumerate() {
+                let p = probas.column(i);
+                let mu = G::surrogate_predict(gp.as_ref(), x)?;
+                let var = G::surrogate_predict_var(gp.as_ref(), x)?;
+                mean += &(&mu * &p);
+                second += &((var + &mu * &mu) * p);
+            }
+            return Ok((second - &mean * &mean).mapv(|v| v.max(G::zero())));
+        }
+        let mut preds = Array1::<G>::zeros(x.nrows());
+        for (i, gp) in self.experts.iter().enumerate() {
+            let p = probas.column(i);
+            preds += &(G::surrogate_predict_var(gp.as_ref(), x)? * p * p);
+        }
+        Ok(preds)
+    }
+
+    /// Predict values at a set of points `x` specified as (n, nx) matrix in `G` precision
+    /// with the `predict` function of the expert of the cluster where each point belongs.
+    fn predict_hard_in<G: SurrogateFloat>(
+        &self,
+        x: &ArrayView2<G>,
+        predict: impl Fn(&dyn FullGpSurrogate, &ArrayView2<G>) -> Result<Array1<G>>,
+    ) -> Result<Array1<G>> {
+        let clustering = self.predict_clusters(&cast_array(x));
+        trace!("Clustering {clustering:?}");
+        let mut preds = Array1::<G>::zeros(x.nrows());
+        for (c, gp) in self.experts.iter().enumerate() {
+            let indices: Vec<usize> = clustering
+                .iter()
+                .enumerate()
+                .filter_map(|(i, &k)| (k == c).then_some(i))
+                .collect();
+            if indices.is_empty() {
+                continue;
+            }
+            let values = predict(gp.as_ref(), &x.select(Axis(0), &indices).view())?;
+            for (&i, v) in indices.iter().zip(values) {
+                preds[i] = v;
+            }
+        }
+        Ok(preds)
+    }
+
     /// Predict derivatives of the output at a set of points `x` specified as (n, nx) matrix.
     /// Gaussian Mixture is used to get the cluster where the point belongs (highest responsability)
     /// The expert of the cluster is used to predict variance value.
@@ -776,7 +1828,7 @@ impl GpMixture {
         x: &ArrayBase<impl Data<Elem = f64>, Ix2>,
     ) -> Result<Array2<f64>> {
         let mut drv = Array2::<f64>::zeros((x.nrows(), x.ncols()));
-        let clustering = self.gmx.predict(x);
+        let clustering = self.predict_clusters(x);
         Zip::from(drv.rows_mut())
             .and(x.rows())
             .and(&clustering)
@@ -799,7 +1851,7 @@ impl GpMixture {
         x: &ArrayBase<impl Data<Elem = f64>, Ix2>,
     ) -> Result<Array2<f64>> {
         let mut vardrv = Array2::<f64>::zeros((x.nrows(), x.ncols()));
-        let clustering = self.gmx.predict(x);
+        let clustering = self.predict_clusters(x);
         Zip::from(vardrv.rows_mut())
             .and(x.rows())
             .and(&clustering)
@@ -812,6 +1864,48 @@ impl GpMixture {
         Ok(vardrv)
     }
 
+    /// Predict hessians of the output at a set of points `x` specified as (n, nx) matrix.
+    /// Gaussian Mixture is used to get the cluster where the point belongs (highest responsability)
+    /// The expert of the cluster is used to predict hessian value.
+    /// Returns hessians as a (n, nx, nx) array.
+    pub fn predict_hessians_hard(
+        &self,
+        x: &ArrayBase<impl Data<Elem = f64>, Ix2>,
+    ) -> Result<Array3<f64>> {
+        let mut hess = Array3::<f64>::zeros((x.nrows(), x.ncols(), x.ncols()));
+        let clustering = self.predict_clusters(x);
+        Zip::from(hess.outer_iter_mut())
+            .and(x.rows())
+            .and(&clustering)
+            .for_each(|mut hess_i, xi, &c| {
+                let x = xi.to_owned().insert_axis(Axis(0));
+                let x_hess = self.experts[c].predict_hessians(&x.view()).unwrap();
+                hess_i.assign(&x_hess.index_axis(Axis(0), 0))
+            });
+        Ok(hess)
+    }
+
+    /// Predict hessians of the variance at a set of points `x` specified as (n, nx) matrix.
+    /// Gaussian Mixture is used to get the cluster where the point belongs (highest responsability)
+    /// The expert of the cluster is used to predict variance hessian value.
+    /// Returns hessians as a (n, nx, nx) array.
+    pub fn predict_var_hessians_hard(
+        &self,
+        x: &ArrayBase<impl Data<Elem = f64>, Ix2>,
+    ) -> Result<Array3<f64>> {
+        let mut hess = Array3::<f64>::zeros((x.nrows(), x.ncols(), x.ncols()));
+        let clustering = self.predict_clusters(x);
+        Zip::from(hess.outer_iter_mut())
+            .and(x.rows())
+            .and(&clustering)
+            .for_each(|mut hess_i, xi, &c| {
+                let x = xi.to_owned().insert_axis(Axis(0));
+                let x_hess = self.experts[c].predict_var_hessians(&x.view()).unwrap();
+                hess_i.assign(&x_hess.index_axis(Axis(0), 0))
+            });
+        Ok(hess)
+    }
+
     pub fn sample_expert(
         &self,
         ith: usize,
@@ -820,35 +1914,60 @@ impl GpMixture {
     ) -> Result<Array2<f64>> {
         self.experts[ith].sample(&x.view(), n_traj)
     }
+}
 
-    pub fn predict(&self, x: &ArrayBase<impl Data<Elem = f64>, Ix2>) -> Result<Array1<f64>> {
-        <GpMixture as GpSurrogate>::predict(self, &x.view())
+/// Predictions with the float type used to train the mixture
+impl<F: SurrogateFloat> GpMixture<F>
+where
+    Self: GpSurrogate + GpSurrogateExt,
+{
+    /// Predict outputs at a set of points `x` specified as (n, nx) matrix.
+    /// Experts predict natively in `F` precision, only the mixture responsabilities
+    /// are computed in double precision.
+    pub fn predict(&self, x: &ArrayBase<impl Data<Elem = F>, Ix2>) -> Result<Array1<F>> {
+        self.predict_in(&x.view())
     }
 
-    pub fn predict_var(&self, x: &ArrayBase<impl Data<Elem = f64>, Ix2>) -> Result<Array1<f64>> {
-        <GpMixture as GpSurrogate>::predict_var(self, &x.view())
+    /// Predict variances at a set of points `x` specified as (n, nx) matrix.
+    /// Experts predict natively in `F` precision, only the mixture responsabilities
+    /// are computed in double precision.
+    pub fn predict_var(&self, x: &ArrayBase<impl Data<Elem = F>, Ix2>) -> Result<Array1<F>> {
+        self.predict_var_in(&x.view())
     }
 
-    pub fn predict_gradients(
-        &self,
-        x: &ArrayBase<impl Data<Elem = f64>, Ix2>,
-    ) -> Result<Array2<f64>> {
-        <GpMixture as GpSurrogateExt>::predict_gradients(self, &x.view())
+    pub fn predict_gradients(&self, x: &ArrayBase<impl Data<Elem = F>, Ix2>) -> Result<Array2<F>> {
+        let dy = <Self as GpSurrogateExt>::predict_gradients(self, &cast_array(x).view())?;
+        Ok(cast_array(&dy))
     }
 
     pub fn predict_var_gradients(
         &self,
-        x: &ArrayBase<impl Data<Elem = f64>, Ix2>,
-    ) -> Result<Array2<f64>> {
-        <GpMixture as GpSurrogateExt>::predict_var_gradients(self, &x.view())
+        x: &ArrayBase<impl Data<Elem = F>, Ix2>,
+    ) -> Result<Array2<F>> {
+        let dv = <Self as GpSurrogateExt>::predict_var_gradients(self, &cast_array(x).view())?;
+        Ok(cast_array(&dv))
+    }
+
+    pub fn predict_hessians(&self, x: &ArrayBase<impl Data<Elem = F>, Ix2>) -> Result<Array3<F>> {
+        let d2y = <Self as GpSurrogateExt>::predict_hessians(self, &cast_array(x).view())?;
+        Ok(cast_array(&d2y))
+    }
+
+    pub fn predict_var_hessians(
+        &self,
+        x: &ArrayBase<impl Data<Elem = F>, Ix2>,
+    ) -> Result<Array3<F>> {
+        let d2v = <Self as GpSurrogateExt>::predict_var_hessians(self, &cast_array(x).view())?;
+        Ok(cast_array(&d2v))
     }
 
     pub fn sample(
         &self,
-        x: &ArrayBase<impl Data<Elem = f64>, Ix2>,
+        x: &ArrayBase<impl Data<Elem = F>, Ix2>,
         n_traj: usize,
-    ) -> Result<Array2<f64>> {
-        <GpMixture as GpSurrogateExt>::sample(self, &x.view(), n_traj)
+    ) -> Result<Array2<F>> {
+        let trajs = <Self as GpSurrogateExt>::sample(self, &cast_array(x).view(), n_traj)?;
+        Ok(cast_array(&trajs))
     }
 
     // pub fn cv_quality(&self) -> f64 {
@@ -875,7 +1994,10 @@ impl GpMixture {
 
     #[cfg(feature = "persistent")]
     /// Load Moe from given json file.
-    pub fn load(path: &str, format: GpFileFormat) -> Result<Box<GpMixture>> {
+    pub fn load(path: &str, format: GpFileFormat) -> Result<Box<GpMixture<F>>>
+    where
+        F: serde::de::DeserializeOwned,
+    {
         let data = fs::read(path)?;
         let moe = match format {
             GpFileFormat::Json => serde_json::from_slice(&data).unwrap(),
@@ -885,6 +2007,150 @@ impl GpMixture {
     }
 }
 
+impl<F: SurrogateFloat> GpMixture<F>
+where
+    Self: GpSurrogate + GpSurrogateExt,
+{
+    /// Update the mixture with new samples `(x_new, y_new)` without re-clustering.
+    ///
+    /// New samples are assigned to the existing clusters (using the gating of the mixture)
+    /// and only the experts of the clusters receiving new samples are refitted,
+    /// with their current hyperparameters if specified (see [GpMixtureParams::keep_hyperparameters]).
+    /// When a drift of the data is detected wrt the [DriftCriterion] of the mixture
+    /// (see [GpMixtureParams::drift_criterion]) the whole mixture is retrained from scratch.
+    ///
+    /// # Errors
+    ///
+    /// * [MoeError::InvalidValueError]: if new samples dimensions do not match the training data
+    ///   or if an expert to refit is a plugin which is not registered (see [GpMixture::register_expert])
+    /// * [MoeError::GpError]: if the refitting of an expert fails
+    pub fn update(
+        &mut self,
+        x_new: &ArrayBase<impl Data<Elem = F>, Ix2>,
+        y_new: &ArrayBase<impl Data<Elem = F>, Ix1>,
+    ) -> Result<UpdateStatus> {
+        let (xt, yt) = &self.training_data;
+        if x_new.ncols() != xt.ncols() || x_new.nrows() != y_new.len() {
+            return Err(MoeError::InvalidValueError(format!(
+                "New samples should be given as ({}, {}) and ({},) arrays, got {:?} and {:?}",
+                x_new.nrows(),
+                xt.ncols(),
+                x_new.nrows(),
+                x_new.shape(),
+                y_new.shape()
+            )));
+        }
+        // plugins are not saved with the mixture: fail before any refit or retrain
+        self.check_registered_plugins()?;
+
+        let xt = concatenate(Axis(0), &[xt.view(), x_new.view()]).unwrap();
+        let yt = concatenate(Axis(0), &[yt.view(), y_new.view()]).unwrap();
+        let x_new = cast_array::<F, f64, _>(x_new);
+        let y_new = cast_array::<F, f64, _>(y_new);
+
+        let params = self.params.clone();
+        if self.is_drifting(&x_new, &y_new)? {
+            info!(
+                "Data drift detected, retrain mixture on {} points",
+                xt.nrows()
+            );
+            *self = params.train(&xt, &yt)?;
+            return Ok(UpdateStatus::Reclustered);
+        }
+
+        let nx = xt.ncols();
+        let data = concatenate(Axis(1), &[xt.view(), yt.view().insert_axis(Axis(1))]).unwrap();
+        let clustering = self.to_clustering();
+        let dataset_clustering = clustering.predict_clusters(&cast_array::<F, f64, _>(&xt));
+        let clusters = sort_by_cluster(self.n_clusters(), &data, &dataset_clustering);
+        let new_clustering = clustering.predict_clusters(&x_new);
+        let updated: Vec<usize> = (0..self.n_clusters())
+            .filter(|nc| new_clustering.iter().any(|c| c == nc))
+            .collect();
+        debug!(
+            "Update experts {updated:?} with {} new points",
+            x_new.nrows()
+        );
+        let refitted = params.parallelism().install(|| {
+            updated
+                .par_iter()
+                .map(|&nc| {
+                    params.refit_expert(
+                        nc,
+                        nx,
+                        &clusters[nc],
+                        self.experts[nc].as_ref(),
+                        &self.expert_scores.get(nc).cloned().unwrap_or_default(),
+                        params.keep_hyperparameters(),
+                    )
+                })
+                .collect::<Result<Vec<_>>>()
+        })?;
+        for (&nc, (expert, scores)) in updated.iter().zip(refitted) {
+            self.experts[nc] = expert;
+            if let Some(s) = self.expert_scores.get_mut(nc) {
+                *s = scores;
+            }
+        }
+        self.training_data = (xt, yt);
+        Ok(UpdateStatus::Refitted(updated))
+    }
+
+    /// Check the user-defined experts of the mixture are registered (see [GpMixture::register_expert])
+    fn check_registered_plugins(&self) -> Result<()> {
+        for nc in 0..self.n_clusters() {
+            let plugin = match self.params.expert_selection() {
+                ExpertSelection::PerCluster(types) => match &types[nc] {
+                    ExpertType::Plugin(name) => Some(name.clone()),
+                    _ => None,
+                },
+                _ => None,
+            }
+            .or_else(|| {
+                self.expert_scores.get(nc).and_then(|scores| {
+                    // remove kpls label suffix if any
+                    let name = scores.best().split("_PLS(").next().unwrap();
+                    (!name.is_empty() && !is_builtin_expert_name(name)).then(|| name.to_string())
+                })
+            });
+            if let Some(name) = plugin {
+                self.params.registered_plugin(&name)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether new samples are drifting from the data the mixture was trained on
+    /// wrt the [DriftCriterion] of the mixture
+    fn is_drifting(&self, x_new: &Array2<f64>, y_new: &Array1<f64>) -> Result<bool> {
+        let drifting = match self.params.drift_criterion() {
+            DriftCriterion::Never => false,
+            DriftCriterion::OutOfClusters { ratio } => {
+                let dists = self.gmx.mahalanobis_distances(x_new);
+                let n_out = dists
+                    .rows()
+                    .into_iter()
+                    .filter(|d| d.iter().all(|&v| v > 3.))
+                    .count();
+                debug!("{n_out} new points out of clusters");
+                n_out as f64 > ratio * x_new.nrows() as f64
+            }
+            DriftCriterion::PredictionError { threshold } => {
+                let pred = <Self as GpSurrogate>::predict(self, &x_new.view())?;
+                let var = <Self as GpSurrogate>::predict_var(self, &x_new.view())?;
+                let errors = Zip::from(y_new)
+                    .and(&pred)
+                    .and(&var)
+                    .map_collect(|y, mu, s2| (y - mu).powi(2) / s2.max(f64::EPSILON));
+                let error = errors.mean().unwrap_or(0.);
+                debug!("Mean standardized squared prediction error = {error}");
+                error > threshold
+            }
+        };
+        Ok(drifting)
+    }
+}
+
 /// Take one out of `quantile` in a set of data rows
 /// Returns the selected part and the remaining data.
 fn extract_part<F: Float>(
@@ -899,8 +2165,12 @@ fn extract_part<F: Float>(
     (data_test, data_train)
 }
 
-impl<D: Data<Elem = f64>> PredictInplace<ArrayBase<D, Ix2>, Array1<f64>> for GpMixture {
-    fn predict_inplace(&self, x: &ArrayBase<D, Ix2>, y: &mut Array1<f64>) {
+impl<F: SurrogateFloat, D: Data<Elem = F>> PredictInplace<ArrayBase<D, Ix2>, Array1<F>>
+    for GpMixture<F>
+where
+    Self: GpSurrogate + GpSurrogateExt,
+{
+    fn predict_inplace(&self, x: &ArrayBase<D, Ix2>, y: &mut Array1<F>) {
         assert_eq!(
             x.nrows(),
             y.len(),
@@ -911,18 +2181,20 @@ impl<D: Data<Elem = f64>> PredictInplace<ArrayBase<D, Ix2>, Array1<f64>> for GpM
         *y = values;
     }
 
-    fn default_target(&self, x: &ArrayBase<D, Ix2>) -> Array1<f64> {
+    fn default_target(&self, x: &ArrayBase<D, Ix2>) -> Array1<F> {
         Array1::zeros(x.nrows())
     }
 }
 
 /// Adaptator to implement `linfa::Predict` for variance prediction
 #[allow(dead_code)]
-pub struct MoeVariancePredictor<'a>(&'a GpMixture);
-impl<D: Data<Elem = f64>> PredictInplace<ArrayBase<D, Ix2>, Array1<f64>>
-    for MoeVariancePredictor<'_>
+pub struct MoeVariancePredictor<'a, F: Float = f64>(&'a GpMixture<F>);
+impl<F: SurrogateFloat, D: Data<Elem = F>> PredictInplace<ArrayBase<D, Ix2>, Array1<F>>
+    for MoeVariancePredictor<'_, F>
+where
+    GpMixture<F>: GpSurrogate + GpSurrogateExt,
 {
-    fn predict_inplace(&self, x: &ArrayBase<D, Ix2>, y: &mut Array1<f64>) {
+    fn predict_inplace(&self, x: &ArrayBase<D, Ix2>, y: &mut Array1<F>) {
         assert_eq!(
             x.nrows(),
             y.len(),
@@ -933,7 +2205,7 @@ impl<D: Data<Elem = f64>> PredictInplace<ArrayBase<D, Ix2>, Array1<f64>>
         *y = values;
     }
 
-    fn default_target(&self, x: &ArrayBase<D, Ix2>) -> Array1<f64> {
+    fn default_target(&self, x: &ArrayBase<D, Ix2>) -> Array1<F> {
         Array1::zeros(x.nrows())
     }
 }
@@ -941,6 +2213,7 @@ impl<D: Data<Elem = f64>> PredictInplace<ArrayBase<D, Ix2>, Array1<f64>>
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{ExpertSelection, Inducings, KplsSelection, RobustLikelihood};
     use approx::assert_abs_diff_eq;
     use argmin_testfunctions::rosenbrock;
     use egobox_doe::{Lhs, SamplingMethod};
@@ -1017,6 +2290,43 @@ mod tests {
         println!("LOOCV = {}", moe.loocv_score());
     }
 
+    #[test]
+    fn test_moe_hard_sample_paths() {
+        let mut rng = Xoshiro256Plus::seed_from_u64(0);
+        let xt = Array2::random_using((50, 1), Uniform::new(0., 1.), &mut rng);
+        let yt = f_test_1d(&xt.to_owned());
+        let moe = GpMixture::params()
+            .n_clusters(NbClusters::fixed(3))
+            .recombination(Recombination::Hard)
+            .with_rng(rng)
+            .fit(&Dataset::new(xt.clone(), yt.clone()))
+            .expect("MOE fitted");
+        let paths = GpSurrogateExt::sample_paths(&moe, 4, 42).expect("MOE sample paths");
+        assert_eq!(4, paths.n_traj());
+
+        // paths interpolate training data
+        let values = paths.predict(&xt.view()).expect("paths values");
+        for col in values.columns() {
+            assert_abs_diff_eq!(col, yt, epsilon = 1e-2);
+        }
+
+        // paths gradients are consistent with finite differences within clusters
+        let x = array![[0.2], [0.6], [0.9]];
+        let h = 1e-4;
+        let dvalues = paths.predict_gradients(&x.view()).expect("paths gradients");
+        let fdiff = (paths.predict(&(&x + h).view()).unwrap()
+            - paths.predict(&(&x - h).view()).unwrap())
+            / (2. * h);
+        assert_abs_diff_eq!(dvalues.index_axis(Axis(1), 0), fdiff, epsilon = 1e-2);
+
+        let smooth = GpMixture::params()
+            .n_clusters(NbClusters::fixed(3))
+            .recombination(Recombination::Smooth(Some(0.5)))
+            .fit(&Dataset::new(xt, yt))
+            .expect("MOE fitted");
+        assert!(GpSurrogateExt::sample_paths(&smooth, 4, 42).is_err());
+    }
+
     #[test]
     fn test_moe_smooth() {
         let test_dir = "target/tests";
@@ -1066,6 +2376,118 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_moe_f32() {
+        let mut rng = Xoshiro256Plus::seed_from_u64(0);
+        let xt = Array2::random_using((50, 1), Uniform::new(0., 1.), &mut rng);
+        let yt = f_test_1d(&xt);
+        let moe = GpMixtureParams::<f32>::new()
+            .n_clusters(NbClusters::fixed(3))
+            .recombination(Recombination::Hard)
+            .with_rng(rng)
+            .fit(&Dataset::new(xt.mapv(|v| v as f32), yt.mapv(|v| v as f32)))
+            .expect("MOE fitted");
+
+        let x = array![[0.1], [0.3], [0.5], [0.7]];
+        let preds: Array1<f32> = moe.predict(&x.mapv(|v| v as f32)).expect("MOE prediction");
+        assert_abs_diff_eq!(preds.mapv(|v| v as f64), f_test_1d(&x), epsilon = 5e-2);
+
+        // Surrogate interface remains double precision
+        let surrogate: Box<dyn MixtureGpSurrogate> = Box::new(moe);
+        assert_abs_diff_eq!(
+            GpSurrogate::predict(surrogate.as_ref(), &x.view())
+                .unwrap()
+                .mapv(|v| v as f32),
+            preds,
+            epsilon = 1e-5
+        );
+    }
+
+    #[test]
+    fn test_moe_f32_native_predictions() {
+        let mut rng = Xoshiro256Plus::seed_from_u64(0);
+        let xt = Array2::random_using((50, 1), Uniform::new(0., 1.), &mut rng);
+        let yt = f_test_1d(&xt);
+        let x = array![[0.1], [0.3], [0.5], [0.7]];
+        let xf32 = x.mapv(|v| v as f32);
+        for (recombination, mixture_variance) in [
+            (Recombination::Hard, MixtureVariance::default()),
+            (Recombination::Smooth(Some(0.5)), MixtureVariance::default()),
+            (
+                Recombination::Smooth(Some(0.5)),
+                MixtureVariance::TotalVariance,
+            ),
+        ] {
+            let moe = GpMixtureParams::<f32>::new()
+                .n_clusters(NbClusters::fixed(3))
+                .recombination(recombination)
+                .mixture_variance(mixture_variance)
+                .with_rng(rng.clone())
+                .fit(&Dataset::new(xt.mapv(|v| v as f32), yt.mapv(|v| v as f32)))
+                .expect("MOE fitted");
+
+            // single precision experts predict without conversion to double precision
+            let preds = moe.predict(&xf32).expect("MOE prediction");
+            let vars = moe.predict_var(&xf32).expect("MOE variances prediction");
+            let surrogate: &dyn MixtureGpSurrogate = &moe;
+            assert_eq!(surrogate.predict_f32(&xf32.view()).unwrap(), preds);
+            assert_eq!(surrogate.predict_var_f32(&xf32.view()).unwrap(), vars);
+
+            let preds_f64 = GpSurrogate::predict(surrogate, &x.view()).unwrap();
+            let vars_f64 = GpSurrogate::predict_var(surrogate, &x.view()).unwrap();
+            assert_abs_diff_eq!(preds.mapv(|v| v as f64), preds_f64, epsilon = 1e-4);
+            assert_abs_diff_eq!(vars.mapv(|v| v as f64), vars_f64, epsilon = 1e-4);
+        }
+    }
+
+    #[test]
+    fn test_moe_robust() {
+        let xt = Array::linspace(0., 1., 21).insert_axis(Axis(1));
+        let ytrue = |x: &Array2<f64>| x.column(0).mapv(|v| (6. * v).sin());
+        let mut yt = ytrue(&xt);
+        yt[5] += 3.;
+        yt[14] -= 2.5;
+
+        let moe = GpMixture::params()
+            .gp_type(GpType::RobustGp {
+                likelihood: RobustLikelihood::StudentT { nu: 4. },
+            })
+            .n_clusters(NbClusters::fixed(1))
+            .with_rng(Xoshiro256Plus::seed_from_u64(0))
+            .fit(&Dataset::new(xt, yt))
+            .expect("MOE fitted");
+        assert!(moe.experts()[0].noise_variance() > 0.);
+
+        let x = Array::linspace(0., 1., 51).insert_axis(Axis(1));
+        let preds = moe.predict(&x).expect("MOE prediction");
+        assert_abs_diff_eq!(preds, ytrue(&x), epsilon = 0.1);
+    }
+
+    #[test]
+    fn test_moe_bayesian() {
+        let xt = array![[0.0], [1.0], [2.0], [3.0], [4.0]];
+        let yt = xt.column(0).mapv(|v: f64| v * v.sin());
+
+        let moe = GpMixture::params()
+            .gp_type(GpType::BayesianGp {
+                n_samples: 8,
+                noise_bounds: None,
+            })
+            .n_clusters(NbClusters::fixed(1))
+            .regression_spec(RegressionSpec::CONSTANT)
+            .correlation_spec(CorrelationSpec::SQUAREDEXPONENTIAL)
+            .with_rng(Xoshiro256Plus::seed_from_u64(42))
+            .fit(&Dataset::new(xt.clone(), yt.clone()))
+            .expect("MOE fitted");
+
+        let preds = moe.predict(&xt).expect("MOE prediction");
+        assert_abs_diff_eq!(preds, yt, epsilon = 1e-3);
+        let vars = moe
+            .predict_var(&array![[0.5], [2.5]])
+            .expect("MOE variance");
+        assert!(vars.iter().all(|v| *v > 0.));
+    }
+
     #[test]
     fn test_moe_auto() {
         let mut rng = Xoshiro256Plus::seed_from_u64(42);
@@ -1119,8 +2541,59 @@ mod tests {
         let yt = xt.mapv(|x| xsinx(&[x]));
         let data = concatenate(Axis(1), &[xt.view(), yt.view()]).unwrap();
         let moe = GpMixture::params().with_rng(rng).check_unwrap();
-        let best_expert = &moe.find_best_expert(0, 1, &data).unwrap();
+        let (best_expert, scores) = &moe.find_best_expert(0, 1, &data).unwrap();
         println!("Best expert {best_expert}");
+        assert_eq!(scores.scores().len(), 1);
+    }
+
+    #[test]
+    fn test_expert_selection_criteria() {
+        let mut rng = Xoshiro256Plus::seed_from_u64(0);
+        let xt = Array2::random_using((20, 1), Uniform::new(0., 1.), &mut rng);
+        let yt = xt.mapv(|x| xsinx(&[x])).remove_axis(Axis(1));
+        let xtest = Array1::linspace(0.1, 0.9, 9).insert_axis(Axis(1));
+        let ytest = xtest.mapv(|x| xsinx(&[x])).remove_axis(Axis(1));
+
+        for criterion in [
+            SelectionCriterion::MarginalLikelihood,
+            SelectionCriterion::Aic,
+            SelectionCriterion::Bic,
+            SelectionCriterion::Loo,
+        ] {
+            for early_pruning in [false, true] {
+                let moe = GpMixture::params()
+                    .n_clusters(NbClusters::fixed(1))
+                    .regression_spec(RegressionSpec::ALL)
+                    .correlation_spec(CorrelationSpec::ALL)
+                    .selection_criterion(criterion)
+                    .early_pruning(early_pruning)
+                    .with_rng(rng.clone())
+                    .fit(&Dataset::new(xt.clone(), yt.clone()))
+                    .expect("MOE fitted");
+                let scores = &moe.expert_scores()[0];
+                println!("{scores}");
+                assert_eq!(scores.criterion(), criterion);
+                assert_eq!(scores.scores().len(), 12);
+                let evaluated: Vec<_> = scores
+                    .scores()
+                    .iter()
+                    .filter_map(|(name, s)| s.map(|s| (name, s)))
+                    .collect();
+                if !early_pruning {
+                    assert_eq!(evaluated.len(), 12);
+                }
+                // correlation models are always scored with constant mean
+                assert!(scores.scores()[..4].iter().all(|(_, s)| s.is_some()));
+                let (best, _) = evaluated
+                    .iter()
+                    .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+                    .unwrap();
+                assert_eq!(scores.best(), best.as_str());
+                assert!(moe.experts()[0].to_string().starts_with(scores.best()));
+                let ypred = moe.predict(&xtest).unwrap();
+                assert_abs_diff_eq!(ypred, ytest, epsilon = 1e-2);
+            }
+        }
     }
 
     #[test]
@@ -1215,6 +2688,321 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_moe_gating_network() {
+        let rng = Xoshiro256Plus::seed_from_u64(0);
+        let xt = Array1::linspace(0., 1., 100).insert_axis(Axis(1));
+        let yt = f_test_1d(&xt);
+
+        let moe = GpMixture::params()
+            .n_clusters(NbClusters::fixed(3))
+            .recombination(Recombination::Smooth(None))
+            .gating_method(GatingMethod::LogisticRegression {
+                regularization: 1e-3,
+            })
+            .with_rng(rng)
+            .fit(&Dataset::new(xt, yt))
+            .expect("MOE fitted");
+        assert!(moe.gating().is_some());
+        assert!(matches!(
+            moe.recombination(),
+            Recombination::Smooth(Some(_))
+        ));
+
+        let xtest = Array1::linspace(0.05, 0.95, 20).insert_axis(Axis(1));
+        let err = (moe.predict(&xtest).unwrap() - f_test_1d(&xtest)).mapv(f64::abs);
+        let mut err = err.to_vec();
+        err.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_abs_diff_eq!(err[err.len() / 2], 0., epsilon = 5e-2);
+
+        let e = 1e-5;
+        for &x1 in [0.13, 0.38, 0.57, 0.84].iter() {
+            let x = array![[x1], [x1 + e], [x1 - e]];
+            let y_pred = moe.predict(&x).unwrap();
+            let y_deriv = moe.predict_gradients(&x).unwrap();
+            let y_hess = moe.predict_hessians(&x).unwrap();
+            assert_rel_or_abs_error(y_deriv[[0, 0]], (y_pred[1] - y_pred[2]) / (2. * e));
+            assert_rel_or_abs_error(
+                y_hess[[0, 0, 0]],
+                (y_deriv[[1, 0]] - y_deriv[[2, 0]]) / (2. * e),
+            );
+
+            let y_pred = moe.predict_var(&x).unwrap();
+            let y_deriv = moe.predict_var_gradients(&x).unwrap();
+            assert_rel_or_abs_error(y_deriv[[0, 0]], (y_pred[1] - y_pred[2]) / (2. * e));
+        }
+
+        let coefs = moe.coefficients().expect("mixture exported");
+        coefs.check().expect("consistent coefficients");
+        let y = moe.predict(&xtest).unwrap();
+        for (i, x) in xtest.rows().into_iter().enumerate() {
+            assert_abs_diff_eq!(coefs.predict(&x.to_vec()), y[i], epsilon = 1e-8);
+        }
+    }
+
+    #[test]
+    fn test_moe_total_variance() {
+        let rng = Xoshiro256Plus::seed_from_u64(0);
+        let xt = Array1::linspace(0., 1., 50).insert_axis(Axis(1));
+        let yt = f_test_1d(&xt);
+
+        let moe = GpMixture::params()
+            .n_clusters(NbClusters::fixed(3))
+            .recombination(Recombination::Smooth(Some(0.5)))
+            .mixture_variance(MixtureVariance::TotalVariance)
+            .with_rng(rng)
+            .fit(&Dataset::new(xt, yt))
+            .expect("MOE fitted");
+        assert_eq!(moe.mixture_variance(), MixtureVariance::TotalVariance);
+
+        let xtest = Array1::linspace(0., 1., 41).insert_axis(Axis(1));
+        let total = moe.predict_var(&xtest).unwrap();
+        let moe = moe.set_mixture_variance(MixtureVariance::Weighted);
+        let weighted = moe.predict_var(&xtest).unwrap();
+        let moe = moe.set_mixture_variance(MixtureVariance::TotalVariance);
+        // experts disagreement only increases the variance
+        Zip::from(&total)
+            .and(&weighted)
+            .for_each(|t, w| assert!(*t >= *w - 1e-12));
+        assert!((&total - &weighted).iter().any(|d| *d > 1e-2));
+
+        let e = 1e-5;
+        for &x1 in [0.13, 0.38, 0.41, 0.79, 0.84].iter() {
+            let x = array![[x1], [x1 + e], [x1 - e]];
+            let y_pred = moe.predict_var(&x).unwrap();
+            let y_deriv = moe.predict_var_gradients(&x).unwrap();
+            let y_hess = moe.predict_var_hessians(&x).unwrap();
+            assert_rel_or_abs_error(y_deriv[[0, 0]], (y_pred[1] - y_pred[2]) / (2. * e));
+            assert_rel_or_abs_error(
+                y_hess[[0, 0, 0]],
+                (y_deriv[[1, 0]] - y_deriv[[2, 0]]) / (2. * e),
+            );
+        }
+
+        let coefs = moe.coefficients().expect("mixture exported");
+        for (i, x) in xtest.rows().into_iter().enumerate() {
+            assert_abs_diff_eq!(coefs.predict_var(&x.to_vec()), total[i], epsilon = 1e-8);
+        }
+    }
+
+    #[test]
+    fn test_moe_heterogeneous_experts() {
+        let rng = Xoshiro256Plus::seed_from_u64(0);
+        let xt = Array1::linspace(0., 1., 60).insert_axis(Axis(1));
+        let yt = f_test_1d(&xt);
+        // away from discontinuities
+        let xtest = array![[0.1], [0.2], [0.3], [0.5], [0.6], [0.7], [0.9]];
+        let ytest = f_test_1d(&xtest);
+
+        let moe = GpMixture::params()
+            .n_clusters(NbClusters::fixed(3))
+            .recombination(Recombination::Hard)
+            .expert_selection(ExpertSelection::PerCluster(vec![
+                ExpertType::Rbf,
+                ExpertType::Gp(GpType::FullGp),
+                ExpertType::Rbf,
+            ]))
+            .with_rng(rng.clone())
+            .fit(&Dataset::new(xt.clone(), yt.clone()))
+            .expect("MOE fitted");
+        let names: Vec<String> = moe.experts().iter().map(|e| e.to_string()).collect();
+        assert_eq!(names[0], "Rbf(Cubic)");
+        assert!(!names[1].starts_with("Rbf"));
+        assert_eq!(names[2], "Rbf(Cubic)");
+        let ypred = moe.predict(&xtest).unwrap();
+        assert_abs_diff_eq!(ypred, ytest, epsilon = 1e-1);
+
+        // small clusters are handled by polynomial experts
+        let moe = GpMixture::params()
+            .n_clusters(NbClusters::fixed(3))
+            .recombination(Recombination::Hard)
+            .expert_selection(ExpertSelection::BySize {
+                min_gp_size: 100,
+                max_full_gp_size: 200,
+                inducings: Inducings::default(),
+            })
+            .with_rng(rng)
+            .fit(&Dataset::new(xt, yt))
+            .expect("MOE fitted");
+        for expert in moe.experts() {
+            assert_eq!(expert.to_string(), "Polynomial(2)");
+        }
+        let ypred = moe.predict(&array![[0.2], [0.6]]).unwrap();
+        assert_abs_diff_eq!(ypred, array![0.04, 2.8], epsilon = 1e-6);
+    }
+
+    #[test]
+    fn test_moe_expert_selection_check() {
+        let params = GpMixture::params()
+            .n_clusters(NbClusters::fixed(3))
+            .expert_selection(ExpertSelection::PerCluster(vec![ExpertType::Rbf]));
+        assert!(params.check().is_err());
+
+        let params = GpMixture::params().gating_method(GatingMethod::LogisticRegression {
+            regularization: f64::NAN,
+        });
+        assert!(params.check().is_err());
+    }
+
+    #[test]
+    fn test_moe_kpls_selection() {
+        let dim = 6;
+        let xlimits = Array2::from_shape_fn((dim, 2), |(_, j)| j as f64);
+        let xt = Lhs::new(&xlimits)
+            .with_rng(Xoshiro256Plus::seed_from_u64(0))
+            .sample(50);
+        let yt = xt.sum_axis(Axis(1)) + xt.column(0).mapv(|v| 0.1 * v * v);
+
+        // one component is enough to explain a quasi linear function
+        let moe = GpMixture::params()
+            .n_clusters(NbClusters::fixed(1))
+            .kpls_selection(Some(KplsSelection::ExplainedVariance {
+                threshold: 0.95,
+                max_dim: 4,
+            }))
+            .with_rng(Xoshiro256Plus::seed_from_u64(0))
+            .fit(&Dataset::new(xt.clone(), yt.clone()))
+            .expect("MOE fitted");
+        assert!(moe.experts()[0].to_string().contains("_PLS(1)"));
+
+        let moe = GpMixture::params()
+            .n_clusters(NbClusters::fixed(1))
+            .kpls_selection(Some(KplsSelection::Criterion { max_dim: 3 }))
+            .selection_criterion(SelectionCriterion::Loo)
+            .with_rng(Xoshiro256Plus::seed_from_u64(0))
+            .fit(&Dataset::new(xt.clone(), yt.clone()))
+            .expect("MOE fitted");
+        let scores = &moe.expert_scores()[0];
+        let names: Vec<&str> = scores.scores().iter().map(|(n, _)| n.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "Constant_SquaredExponential_PLS(1)",
+                "Constant_SquaredExponential_PLS(2)",
+                "Constant_SquaredExponential_PLS(3)"
+            ]
+        );
+        assert!(scores.scores().iter().all(|(_, s)| s.is_some()));
+        assert!(moe.experts()[0].to_string().starts_with(scores.best()));
+
+        let dim = GpMixture::params()
+            .kpls_selection(Some(KplsSelection::ExplainedVariance {
+                threshold: 0.95,
+                max_dim: 4,
+            }))
+            .check_unwrap()
+            .select_kpls_dim(&xt, &yt)
+            .unwrap();
+        assert_eq!(dim, Some(1));
+
+        // per cluster overrides
+        let moe = GpMixture::params()
+            .n_clusters(NbClusters::fixed(2))
+            .kpls_dims(&[Some(2), None])
+            .with_rng(Xoshiro256Plus::seed_from_u64(0))
+            .fit(&Dataset::new(xt.clone(), yt.clone()))
+            .expect("MOE fitted");
+        assert!(moe.experts()[0].to_string().contains("_PLS(2)"));
+        assert!(!moe.experts()[1].to_string().contains("_PLS"));
+
+        let params = GpMixture::params()
+            .n_clusters(NbClusters::fixed(3))
+            .kpls_dims(&[Some(2), None]);
+        assert!(params.check().is_err());
+    }
+
+    #[test]
+    fn test_moe_parallelism() {
+        let mut rng = Xoshiro256Plus::seed_from_u64(0);
+        let xt = Array2::random_using((50, 1), Uniform::new(0., 1.), &mut rng);
+        let yt = f_test_1d(&xt);
+        let xtest = Array::linspace(0., 1., 20).insert_axis(Axis(1));
+
+        let policies = vec![
+            Parallelism::Global,
+            Parallelism::Threads(1),
+            Parallelism::Threads(3),
+            Parallelism::Pool(std::sync::Arc::new(
+                rayon::ThreadPoolBuilder::new()
+                    .num_threads(2)
+                    .build()
+                    .unwrap(),
+            )),
+        ];
+        let preds: Vec<_> = policies
+            .into_iter()
+            .map(|parallelism| {
+                let moe = GpMixture::params()
+                    .n_clusters(NbClusters::fixed(3))
+                    .regression_spec(RegressionSpec::ALL)
+                    .correlation_spec(CorrelationSpec::ALL)
+                    .parallelism(parallelism)
+                    .with_rng(Xoshiro256Plus::seed_from_u64(42))
+                    .fit(&Dataset::new(xt.clone(), yt.clone()))
+                    .expect("MOE fitted");
+                let experts: Vec<_> = moe.experts().iter().map(|e| e.to_string()).collect();
+                (experts, moe.predict(&xtest).unwrap())
+            })
+            .collect();
+        for (experts, pred) in preds.iter().skip(1) {
+            assert_eq!(experts, &preds[0].0);
+            assert_abs_diff_eq!(pred, &preds[0].1, epsilon = 1e-12);
+        }
+
+        // dedicated thread pools are reused
+        let pool = Parallelism::thread_pool(3).unwrap();
+        assert!(std::sync::Arc::ptr_eq(
+            &pool,
+            &Parallelism::thread_pool(3).unwrap()
+        ));
+    }
+
+    #[test]
+    fn test_moe_update() {
+        let mut rng = Xoshiro256Plus::seed_from_u64(0);
+        let xt = Array2::random_using((50, 1), Uniform::new(0., 1.), &mut rng);
+        let yt = f_test_1d(&xt);
+        let mut moe = GpMixture::params()
+            .n_clusters(NbClusters::fixed(3))
+            .recombination(Recombination::Hard)
+            .drift_criterion(DriftCriterion::Never)
+            .keep_hyperparameters(true)
+            .with_rng(Xoshiro256Plus::seed_from_u64(0))
+            .fit(&Dataset::new(xt, yt))
+            .expect("MOE fitted");
+        let thetas: Vec<_> = moe.experts().iter().map(|e| e.theta()).collect();
+
+        // new points falling in one cluster
+        let x_new = array![[0.61], [0.65], [0.7]];
+        let y_new = f_test_1d(&x_new);
+        let clusters = moe.predict_clusters(&x_new);
+        assert!(clusters.iter().all(|&c| c == clusters[0]));
+        let status = moe.update(&x_new, &y_new).expect("MOE updated");
+        assert_eq!(status, UpdateStatus::Refitted(vec![clusters[0]]));
+        assert_eq!(moe.training_data().0.nrows(), 53);
+        for (expert, theta) in moe.experts().iter().zip(thetas) {
+            assert_abs_diff_eq!(expert.theta(), theta);
+        }
+        assert_abs_diff_eq!(moe.predict(&x_new).unwrap(), y_new, epsilon = 1e-3);
+
+        // drift detected with new points out of the clusters
+        let xt = Array2::random_using((30, 1), Uniform::new(0., 0.5), &mut rng);
+        let yt = f_test_1d(&xt);
+        let mut moe = GpMixture::params()
+            .with_rng(Xoshiro256Plus::seed_from_u64(0))
+            .fit(&Dataset::new(xt, yt))
+            .expect("MOE fitted");
+        let x_new = array![[0.25]];
+        let status = moe.update(&x_new, &f_test_1d(&x_new)).unwrap();
+        assert_eq!(status, UpdateStatus::Refitted(vec![0]));
+        let x_new = array![[3.], [3.5], [4.]];
+        let status = moe.update(&x_new, &f_test_1d(&x_new)).unwrap();
+        assert_eq!(status, UpdateStatus::Reclustered);
+        assert_eq!(moe.training_data().0.nrows(), 34);
+
+        assert!(moe.update(&array![[1., 2.]], &array![1.]).is_err());
+    }
+
     fn norm1(x: &Array2<f64>) -> Array2<f64> {
         x.mapv(|v| v.abs())
             .sum_axis(Axis(1))
@@ -1300,6 +3088,82 @@ mod tests {
         test_variance_derivatives(rosenb);
     }
 
+    #[test]
+    fn test_moe_var_deriv_smooth_1d() {
+        // number of clusters differs from input dimension: cluster probabilities
+        // have to be broadcast along input components
+        let rng = Xoshiro256Plus::seed_from_u64(0);
+        let xt = Array1::linspace(0., 1., 20).insert_axis(Axis(1));
+        let yt = f_test_1d(&xt);
+        let moe = GpMixture::params()
+            .n_clusters(NbClusters::fixed(3))
+            .regression_spec(RegressionSpec::CONSTANT)
+            .correlation_spec(CorrelationSpec::SQUAREDEXPONENTIAL)
+            .recombination(Recombination::Smooth(Some(0.5)))
+            .with_rng(rng)
+            .fit(&Dataset::new(xt, yt))
+            .expect("MOE fitted");
+        let e = 1e-5;
+        for xa in [0.13, 0.47, 0.86] {
+            let x = array![[xa], [xa + e], [xa - e]];
+            let var = moe.predict_var(&x).unwrap();
+            let var_deriv = moe.predict_var_gradients(&x).unwrap();
+            assert_eq!(var_deriv.dim(), (3, 1));
+            assert_rel_or_abs_error(var_deriv[[0, 0]], (var[1] - var[2]) / (2. * e));
+        }
+    }
+
+    #[test]
+    fn test_moe_hessians_smooth() {
+        let rng = Xoshiro256Plus::seed_from_u64(0);
+        let xt = egobox_doe::FullFactorial::new(&array![[-1., 1.], [-1., 1.]]).sample(36);
+        let yt = rosenb(&xt);
+
+        let moe = GpMixture::params()
+            .n_clusters(NbClusters::fixed(2))
+            .regression_spec(RegressionSpec::CONSTANT)
+            .correlation_spec(CorrelationSpec::SQUAREDEXPONENTIAL)
+            .recombination(Recombination::Smooth(Some(1.)))
+            .with_rng(rng)
+            .fit(&Dataset::new(xt, yt.remove_axis(Axis(1))))
+            .expect("MOE fitted");
+
+        let mut rng = Xoshiro256Plus::seed_from_u64(42);
+        for _ in 0..5 {
+            let x = Array::random_using((1, 2), Uniform::new(-0.9, 0.9), &mut rng);
+            let hess = moe.predict_hessians(&x).unwrap();
+            let var_hess = moe.predict_var_hessians(&x).unwrap();
+
+            let e = 1e-3;
+            for k in 0..2 {
+                let mut xp = x.to_owned();
+                xp[[0, k]] += e;
+                let mut xm = x.to_owned();
+                xm[[0, k]] -= e;
+                let fdiff = (moe.predict_gradients(&xp).unwrap()
+                    - moe.predict_gradients(&xm).unwrap())
+                    / (2. * e);
+                for j in 0..2 {
+                    assert_abs_diff_eq!(
+                        hess[[0, j, k]],
+                        fdiff[[0, j]],
+                        epsilon = 1e-3 * (1. + fdiff[[0, j]].abs())
+                    );
+                }
+                let fdiff = (moe.predict_var_gradients(&xp).unwrap()
+                    - moe.predict_var_gradients(&xm).unwrap())
+                    / (2. * e);
+                for j in 0..2 {
+                    assert_abs_diff_eq!(
+                        var_hess[[0, j, k]],
+                        fdiff[[0, j]],
+                        epsilon = 1e-3 * (1. + fdiff[[0, j]].abs())
+                    );
+                }
+            }
+        }
+    }
+
     #[test]
     fn test_moe_display() {
         let rng = Xoshiro256Plus::seed_from_u64(0);
@@ -0,0 +1,279 @@
+//! Diagnostics of trained mixtures of experts.
+//!
+//! A [MixtureReport] gathers, for each cluster of a trained [GpMixture](crate::GpMixture),
+//! the number of training points assigned to the cluster, the selected expert with its score
+//! and hyperparameters, and optionally the experts responsabilities evaluated on a user grid
+//! (see [ResponsibilityMap]) to inspect the transitions between clusters.
+
+use crate::surrogates::FullGpSurrogate;
+use crate::types::{ExpertScores, Recombination};
+
+use ndarray::{Array1, Array2, ArrayBase, Data, Ix2};
+use std::fmt::Display;
+
+#[cfg(feature = "persistent")]
+use crate::errors::Result;
+#[cfg(feature = "serializable")]
+use serde::{Deserialize, Serialize};
+
+/// Diagnostics of a cluster and of its expert
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serializable", derive(Serialize, Deserialize))]
+pub struct ClusterReport {
+    /// Number of training points assigned to the cluster
+    pub n_points: usize,
+    /// Weight of the cluster in the gaussian mixture
+    pub weight: f64,
+    /// Name of the selected expert (`<Regression>_<Correlation>` for GP experts)
+    pub expert: String,
+    /// Score of the selected expert wrt the selection criterion if evaluated
+    pub score: Option<f64>,
+    /// Hyperparameters of the expert
+    pub theta: Vec<f64>,
+    /// Likelihood of the expert
+    pub likelihood: f64,
+    /// Scores of all the experts tested on the cluster
+    pub scores: ExpertScores,
+}
+
+/// Experts responsabilities evaluated at n points of a user grid
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serializable", derive(Serialize, Deserialize))]
+pub struct ResponsibilityMap {
+    /// Grid points (n, nx)
+    pub x: Vec<Vec<f64>>,
+    /// Responsabilities of the experts at the grid points (n, n_clusters)
+    pub responsibilities: Vec<Vec<f64>>,
+    /// Cluster index of the grid points (n,)
+    pub clusters: Vec<usize>,
+    /// Number of grid points assigned to each cluster (n_clusters,)
+    pub counts: Vec<usize>,
+}
+
+impl ResponsibilityMap {
+    pub(crate) fn new(
+        x: &ArrayBase<impl Data<Elem = f64>, Ix2>,
+        responsibilities: &Array2<f64>,
+        clusters: &Array1<usize>,
+    ) -> Self {
+        ResponsibilityMap {
+            x: x.rows().into_iter().map(|r| r.to_vec()).collect(),
+            responsibilities: responsibilities
+                .rows()
+                .into_iter()
+                .map(|r| r.to_vec())
+                .collect(),
+            clusters: clusters.to_vec(),
+            counts: cluster_counts(clusters, responsibilities.ncols()),
+        }
+    }
+}
+
+/// Diagnostics of a trained mixture of experts (see [GpMixture::report](crate::GpMixture::report))
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serializable", derive(Serialize, Deserialize))]
+pub struct MixtureReport {
+    /// Recombination mode of the experts predictions
+    pub recombination: Recombination<f64>,
+    /// Heaviside factor controlling the smoothness between clusters (`Smooth` recombination only)
+    pub heaviside_factor: Option<f64>,
+    /// Method used to gate the experts: `GaussianMixture`, `GatingNetwork`, `TreePartition`
+    /// or `CentroidPartition`
+    pub gating: String,
+    /// Number of training points
+    pub n_points: usize,
+    /// Diagnostics of each cluster
+    pub clusters: Vec<ClusterReport>,
+    /// Experts responsabilities evaluated on a user grid
+    #[cfg_attr(
+        feature = "serializable",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub responsibility_map: Option<ResponsibilityMap>,
+}
+
+impl MixtureReport {
+    pub(crate) fn new(
+        recombination: Recombination<f64>,
+        gating: &str,
+        weights: &Array1<f64>,
+        experts: &[Box<dyn FullGpSurrogate>],
+        expert_scores: &[ExpertScores],
+        training_clusters: &Array1<usize>,
+    ) -> Self {
+        let counts = cluster_counts(training_clusters, experts.len());
+        let clusters = experts
+            .iter()
+            .enumerate()
+            .map(|(nc, expert)| {
+                // scores may be missing for models saved with previous versions
+                let scores = expert_scores.get(nc).cloned().unwrap_or_default();
+                let (name, score) = if scores.best().is_empty() {
+                    (expert.to_string(), None)
+                } else {
+                    let score = scores
+                        .scores()
+                        .iter()
+                        .find(|(name, _)| name == scores.best())
+                        .and_then(|(_, score)| *score);
+                    (scores.best().to_string(), score)
+                };
+                ClusterReport {
+                    n_points: counts[nc],
+                    weight: weights.get(nc).copied().unwrap_or(1.),
+                    expert: name,
+                    score,
+                    theta: expert.theta().to_vec(),
+                    likelihood: expert.likelihood(),
+                    scores,
+                }
+            })
+            .collect();
+        let heaviside_factor = match recombination {
+            Recombination::Smooth(factor) => factor,
+            Recombination::Hard => None,
+        };
+        MixtureReport {
+            recombination,
+            heaviside_factor,
+            gating: gating.to_string(),
+            n_points: training_clusters.len(),
+            clusters,
+            responsibility_map: None,
+        }
+    }
+
+    /// Sets the responsabilities evaluated on a user grid
+    pub(crate) fn with_responsibility_map(mut self, map: ResponsibilityMap) -> Self {
+        self.responsibility_map = Some(map);
+        self
+    }
+
+    /// Serialize the report in JSON format
+    #[cfg(feature = "persistent")]
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+impl Display for MixtureReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "Mixture of {} experts trained on {} points",
+            self.clusters.len(),
+            self.n_points
+        )?;
+        match self.recombination {
+            Recombination::Hard => write!(f, "Recombination: Hard")?,
+            Recombination::Smooth(_) => write!(f, "Recombination: Smooth")?,
+        }
+        if let Some(factor) = self.heaviside_factor {
+            write!(f, ", heaviside factor={factor}")?;
+        }
+        writeln!(f, ", gating: {}", self.gating)?;
+        for (nc, cluster) in self.clusters.iter().enumerate() {
+            writeln!(
+                f,
+                "Cluster #{nc}: {} points, weight={:.4}",
+                cluster.n_points, cluster.weight
+            )?;
+            write!(f, "  Expert: {}", cluster.expert)?;
+            if let Some(score) = cluster.score {
+                write!(f, " ({}={score:.6e})", cluster.scores.criterion())?;
+            }
+            writeln!(f)?;
+            writeln!(f, "  Theta: {:?}", cluster.theta)?;
+            writeln!(f, "  Likelihood: {:.6e}", cluster.likelihood)?;
+        }
+        if let Some(map) = &self.responsibility_map {
+            writeln!(
+                f,
+                "Grid of {} points assignment counts: {:?}",
+                map.x.len(),
+                map.counts
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Number of points assigned to each of the `n_clusters` clusters
+fn cluster_counts(clusters: &Array1<usize>, n_clusters: usize) -> Vec<usize> {
+    let mut counts = vec![0; n_clusters];
+    clusters
+        .iter()
+        .filter(|&&c| c < n_clusters)
+        .for_each(|&c| counts[c] += 1);
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{GpMixture, MixtureGpSurrogate, NbClusters};
+    use approx::assert_abs_diff_eq;
+    use egobox_doe::{Lhs, SamplingMethod};
+    use linfa::{Dataset, traits::Fit};
+    use ndarray::{Axis, array};
+    use ndarray_rand::rand::SeedableRng;
+    use rand_xoshiro::Xoshiro256Plus;
+
+    fn f_test_1d(x: &Array2<f64>) -> Array1<f64> {
+        x.column(0).mapv(|v| {
+            if v < 0.4 {
+                v * v
+            } else if v < 0.8 {
+                3. * v + 1.
+            } else {
+                f64::sin(10. * v)
+            }
+        })
+    }
+
+    #[test]
+    fn test_moe_report() {
+        let rng = Xoshiro256Plus::seed_from_u64(0);
+        let xt = Lhs::new(&array![[0., 1.]]).with_rng(rng.clone()).sample(50);
+        let yt = f_test_1d(&xt);
+        let moe = GpMixture::params()
+            .n_clusters(NbClusters::fixed(3))
+            .recombination(Recombination::Smooth(None))
+            .with_rng(rng)
+            .fit(&Dataset::new(xt, yt))
+            .expect("MOE fitted");
+
+        let grid = Array1::linspace(0., 1., 21).insert_axis(Axis(1));
+        let report = moe.report_with_grid(&grid);
+        println!("{report}");
+
+        assert_eq!(report.n_points, 50);
+        assert_eq!(report.clusters.len(), 3);
+        assert!(report.heaviside_factor.is_some());
+        assert_eq!(report.gating, "GaussianMixture");
+        assert_eq!(
+            report.clusters.iter().map(|c| c.n_points).sum::<usize>(),
+            50
+        );
+        for (cluster, expert) in report.clusters.iter().zip(moe.experts()) {
+            assert_eq!(cluster.theta, expert.theta().to_vec());
+            assert_eq!(cluster.expert, cluster.scores.best());
+        }
+        let map = report.responsibility_map.as_ref().unwrap();
+        assert_eq!(map.counts.iter().sum::<usize>(), 21);
+        assert_eq!(map.clusters, moe.predict_clusters(&grid).to_vec());
+        for probas in map.responsibilities.iter() {
+            assert_abs_diff_eq!(probas.iter().sum::<f64>(), 1., epsilon = 1e-6);
+        }
+
+        #[cfg(feature = "persistent")]
+        {
+            let json = report.to_json().expect("Report serialized");
+            let loaded: MixtureReport = serde_json::from_str(&json).expect("Report deserialized");
+            assert_eq!(loaded.clusters.len(), 3);
+            let loaded_map = loaded.responsibility_map.unwrap();
+            assert_eq!(loaded_map.clusters, map.clusters);
+            assert_eq!(loaded_map.counts, map.counts);
+        }
+    }
+}
@@ -1,9 +1,9 @@
 #[doc(hidden)]
 // Create a GP with given regression and correlation models.
 macro_rules! make_gp_params {
-    ($regr:ident, $corr:ident) => {
+    ($regr:ident, $corr:ident, $float:ty) => {
         paste! {
-            GaussianProcess::<f64, [<$regr Mean>], [<$corr Corr>] >::params(
+            GaussianProcess::<$float, [<$regr Mean>], [<$corr Corr>] >::params(
                 [<$regr Mean>]::default(),
                 [<$corr Corr>]::default(),
             )
@@ -12,14 +12,15 @@ macro_rules! make_gp_params {
 }
 
 macro_rules! compute_error {
-    ($self:ident, $regr:ident, $corr:ident, $dataset:ident) => {{
+    ($self:ident, $regr:ident, $corr:ident, $dataset:ident, $kpls_dim:ident) => {{ compute_error!($self, $regr, $corr, $dataset, f64, $kpls_dim) }};
+    ($self:ident, $regr:ident, $corr:ident, $dataset:ident, $float:ty, $kpls_dim:ident) => {{
         debug!(
             "Surrogate {}_{} on dataset size = {}",
             stringify!($regr),
             stringify!($corr),
             $dataset.nsamples()
         );
-        let params = make_gp_params!($regr, $corr).kpls_dim($self.kpls_dim());
+        let params = make_gp_params!($regr, $corr, $float).kpls_dim($kpls_dim);
         let mut errors = Vec::new();
         let input_dim = $dataset.records().shape()[1];
         let n_fold = std::cmp::min($dataset.nsamples(), 5);
@@ -28,13 +29,11 @@ macro_rules! compute_error {
             f64::INFINITY // not enough points => huge error
         } else if (n_fold < 3 * input_dim && stringify!($regr) == "Linear") {
             f64::INFINITY // not enough points => huge error
+        } else if $self.selection_criterion() != SelectionCriterion::CrossValidation {
+            compute_score!($self, $regr, params, $dataset, $float)
         } else {
             for (gp, valid) in $dataset.iter_fold(n_fold, |train| {
-                let gp = params
-                    .clone()
-                    .kpls_dim($self.kpls_dim())
-                    .fit(&train)
-                    .unwrap();
+                let gp = params.clone().kpls_dim($kpls_dim).fit(&train).unwrap();
                 trace!("GP trained");
                 gp
             }) {
@@ -43,32 +42,69 @@ macro_rules! compute_error {
                 trace!("Prediction error = {error}");
                 errors.push(error);
             }
-            let mean_err = errors.iter().sum::<f64>() / errors.len() as f64;
+            let mean_err = f64::cast(errors.iter().copied().sum::<$float>()) / errors.len() as f64;
             trace!("-> mean error = {}", mean_err);
             mean_err
         }
     }};
 }
 
+// Score a GP fitted once on the whole dataset wrt the selected criterion
+macro_rules! compute_score {
+    ($self:ident, $regr:ident, $params:ident, $dataset:ident, $float:ty) => {{
+        match $params.fit(&$dataset) {
+            Ok(gp) => {
+                let n = $dataset.nsamples() as f64;
+                // -2 log L up to a constant, the reduced likelihood being in log10 scale
+                let deviance = -std::f64::consts::LN_10 * f64::cast(gp.likelihood());
+                // theta, regression weights and variance
+                let n_regr = paste! { [<$regr Mean>]::default() }
+                    .value(&Array2::<$float>::zeros((1, $dataset.nfeatures())))
+                    .len();
+                let n_params = (gp.theta().len() + n_regr + 1) as f64;
+                let score = match $self.selection_criterion() {
+                    SelectionCriterion::Aic => deviance + 2. * n_params,
+                    SelectionCriterion::Bic => deviance + n_params * n.ln(),
+                    SelectionCriterion::Loo => {
+                        let errors = gp.loo_errors();
+                        (errors.mapv(|v| f64::cast(v * v)).sum() / n).sqrt()
+                    }
+                    _ => deviance / 2.,
+                };
+                trace!("-> {} score = {}", $self.selection_criterion(), score);
+                score
+            }
+            Err(err) => {
+                debug!("GP fit failed ({err}) => huge score");
+                f64::INFINITY
+            }
+        }
+    }};
+}
+
 macro_rules! compute_errors_with_corr {
-    ($self:ident, $allowed_corr_models:ident, $dataset:ident, $map_error:ident, $regr:ident, $corr:ident) => {{
+    ($self:ident, $allowed_corr_models:ident, $dataset:ident, $map_error:ident, $float:ty, $kpls_dim:ident, $regr:ident, $corr:ident) => {{
         if $allowed_corr_models.contains(&stringify!($corr)) {
-            $map_error.push((
-                format!("{}_{}", stringify!($regr), stringify!($corr)),
-                compute_error!($self, $regr, $corr, $dataset),
-            ));
+            // evaluation is deferred to allow parallel scoring of the candidates
+            let task: ErrorTask<'_> = Box::new(|| {
+                let mut dataset = $dataset.clone();
+                compute_error!($self, $regr, $corr, dataset, $float, $kpls_dim)
+            });
+            $map_error.push((format!("{}_{}", stringify!($regr), stringify!($corr)), task));
         }
     }};
 }
 
 macro_rules! compute_errors_with_regr {
-    ($self:ident, $allowed_mean_models:ident, $allowed_corr_models:ident, $dataset:ident, $map_error:ident, $regr:ident) => {{
+    ($self:ident, $allowed_mean_models:ident, $allowed_corr_models:ident, $dataset:ident, $map_error:ident, $float:ty, $kpls_dim:ident, $regr:ident) => {{
         if $allowed_mean_models.contains(&stringify!($regr)) {
             compute_errors_with_corr!(
                 $self,
                 $allowed_corr_models,
                 $dataset,
                 $map_error,
+                $float,
+                $kpls_dim,
                 $regr,
                 SquaredExponential
             );
@@ -77,6 +113,8 @@ macro_rules! compute_errors_with_regr {
                 $allowed_corr_models,
                 $dataset,
                 $map_error,
+                $float,
+                $kpls_dim,
                 $regr,
                 AbsoluteExponential
             );
@@ -85,6 +123,8 @@ macro_rules! compute_errors_with_regr {
                 $allowed_corr_models,
                 $dataset,
                 $map_error,
+                $float,
+                $kpls_dim,
                 $regr,
                 Matern32
             );
@@ -93,6 +133,8 @@ macro_rules! compute_errors_with_regr {
                 $allowed_corr_models,
                 $dataset,
                 $map_error,
+                $float,
+                $kpls_dim,
                 $regr,
                 Matern52
             );
@@ -101,13 +143,26 @@ macro_rules! compute_errors_with_regr {
 }
 
 macro_rules! compute_errors {
-    ($self:ident, $allowed_mean_models:ident, $allowed_corr_models:ident, $dataset:ident, $map_error:ident) => {{
+    ($self:ident, $allowed_mean_models:ident, $allowed_corr_models:ident, $dataset:ident, $map_error:ident, $kpls_dim:ident) => {{
+        compute_errors!(
+            $self,
+            $allowed_mean_models,
+            $allowed_corr_models,
+            $dataset,
+            $map_error,
+            f64,
+            $kpls_dim
+        )
+    }};
+    ($self:ident, $allowed_mean_models:ident, $allowed_corr_models:ident, $dataset:ident, $map_error:ident, $float:ty, $kpls_dim:ident) => {{
         compute_errors_with_regr!(
             $self,
             $allowed_mean_models,
             $allowed_corr_models,
             $dataset,
             $map_error,
+            $float,
+            $kpls_dim,
             Constant
         );
         compute_errors_with_regr!(
@@ -116,6 +171,8 @@ macro_rules! compute_errors {
             $allowed_corr_models,
             $dataset,
             $map_error,
+            $float,
+            $kpls_dim,
             Linear
         );
         compute_errors_with_regr!(
@@ -124,6 +181,8 @@ macro_rules! compute_errors {
             $allowed_corr_models,
             $dataset,
             $map_error,
+            $float,
+            $kpls_dim,
             Quadratic
         );
     }};
@@ -133,4 +192,5 @@ pub(crate) use compute_error;
 pub(crate) use compute_errors;
 pub(crate) use compute_errors_with_corr;
 pub(crate) use compute_errors_with_regr;
+pub(crate) use compute_score;
 pub(crate) use make_gp_params;
@@ -0,0 +1,1005 @@
+//! Non GP experts which can be mixed with GP experts in a mixture
+//! (see [ExpertSelection](crate::ExpertSelection)).
+//!
+//! * [PolynomialSurrogate]: polynomial response surface fitted by least squares,
+//!   its variance is the variance of the least squares estimation of the mean.
+//! * [RbfSurrogate]: cubic radial basis function interpolant with a linear polynomial tail,
+//!   as an interpolant without uncertainty model its variance is zero.
+//!
+//! Both experts work on inputs standardized wrt to their training data.
+//!
+//! User-defined experts can also be registered in the mixture as an [ExpertPlugin]
+//! to compete with GP experts during the expert selection. The trained plugin experts
+//! are (de)serialized with the mixture provided their surrogate traits implementations
+//! are registered with `#[typetag::serde]`.
+
+use crate::errors::{MoeError, Result};
+use crate::surrogates::*;
+#[cfg(feature = "persistent")]
+use crate::types::GpFileFormat;
+use crate::types::SelectionCriterion;
+
+use linfa::Dataset;
+use log::debug;
+use std::sync::Arc;
+
+#[cfg(feature = "blas")]
+use linfa::dataset::{WithLapack, WithoutLapack};
+#[cfg(not(feature = "blas"))]
+use linfa_linalg::qr::*;
+use ndarray::{Array1, Array2, Array3, ArrayBase, ArrayView2, Axis, Data, Ix2, Zip, s};
+#[cfg(feature = "blas")]
+use ndarray_linalg::{Factorize, Solve};
+
+#[cfg(feature = "serializable")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "persistent")]
+use std::fs;
+#[cfg(feature = "persistent")]
+use std::io::Write;
+
+/// Relative regularization of the least squares normal equations
+const RIDGE: f64 = 1e-10;
+
+/// Standardization of the training inputs
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serializable", derive(Serialize, Deserialize))]
+struct Scaler {
+    mean: Array1<f64>,
+    std: Array1<f64>,
+}
+
+impl Scaler {
+    fn new(x: &ArrayBase<impl Data<Elem = f64>, Ix2>) -> Self {
+        Scaler {
+            mean: x.mean_axis(Axis(0)).unwrap(),
+            std: x
+                .std_axis(Axis(0), 0.)
+                .mapv(|v| if v < f64::EPSILON { 1. } else { v }),
+        }
+    }
+
+    fn transform(&self, x: &ArrayBase<impl Data<Elem = f64>, Ix2>) -> Array2<f64> {
+        (x - &self.mean) / &self.std
+    }
+
+    /// Convert gradients (n, nx) wrt standardized inputs into gradients wrt inputs
+    fn gradients(&self, drv: Array2<f64>) -> Array2<f64> {
+        drv / &self.std
+    }
+
+    /// Convert hessians (n, nx, nx) wrt standardized inputs into hessians wrt inputs
+    fn hessians(&self, mut hess: Array3<f64>) -> Array3<f64> {
+        let scale = Array2::from_shape_fn((self.std.len(), self.std.len()), |(a, b)| {
+            self.std[a] * self.std[b]
+        });
+        hess.outer_iter_mut().for_each(|mut h| h /= &scale);
+        hess
+    }
+}
+
+/// Solve the square linear system `a . x = b`
+fn solve_linear(a: &Array2<f64>, b: &Array2<f64>) -> Result<Array2<f64>> {
+    #[cfg(feature = "blas")]
+    {
+        let lu = a.to_owned().with_lapack().factorize_into()?;
+        let mut sol = Array2::zeros(b.dim());
+        for (mut s, bj) in sol.columns_mut().into_iter().zip(b.columns()) {
+            s.assign(&lu.solve(&bj.to_owned().with_lapack())?.without_lapack());
+        }
+        Ok(sol)
+    }
+    #[cfg(not(feature = "blas"))]
+    {
+        Ok(a.qr()?.solve(b)?)
+    }
+}
+
+/// Polynomial response surface of degree 1 (linear) or 2 (full quadratic)
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serializable", derive(Serialize, Deserialize))]
+pub struct PolynomialSurrogate {
+    /// Polynomial degree
+    degree: usize,
+    /// Input standardization
+    scaler: Scaler,
+    /// Coefficients (p,) of the polynomial basis
+    beta: Array1<f64>,
+    /// Inverse (p, p) of the information matrix F^t.F
+    inv_info: Array2<f64>,
+    /// Residual variance
+    sigma2: f64,
+    /// Reduced likelihood of the residuals in log10 scale (as GP experts)
+    likelihood: f64,
+}
+
+impl PolynomialSurrogate {
+    /// Number of coefficients of a polynomial of the given `degree` in dimension `nx`
+    pub fn n_coefficients(degree: usize, nx: usize) -> usize {
+        match degree {
+            1 => nx + 1,
+            _ => (nx + 1) * (nx + 2) / 2,
+        }
+    }
+
+    /// Fit a polynomial of the given `degree` (1 or 2) on (x, y) training data by least squares
+    pub fn train(
+        degree: usize,
+        x: &ArrayBase<impl Data<Elem = f64>, Ix2>,
+        y: &ArrayBase<impl Data<Elem = f64>, Ix2>,
+    ) -> Result<Self> {
+        if degree != 1 && degree != 2 {
+            return Err(MoeError::InvalidValueError(format!(
+                "Polynomial degree should be 1 or 2, got {degree}"
+            )));
+        }
+        let (n, nx) = x.dim();
+        let p = Self::n_coefficients(degree, nx);
+        if n < p {
+            return Err(MoeError::ExpertError(format!(
+                "Not enough points to fit a polynomial of degree {degree}, requires at least {p}, got {n}"
+            )));
+        }
+        let scaler = Scaler::new(x);
+        let xs = scaler.transform(x);
+        let mut basis = Array2::zeros((n, p));
+        Zip::from(basis.rows_mut())
+            .and(xs.rows())
+            .for_each(|mut f, xi| f.assign(&polynomial_basis(degree, &xi.to_vec())));
+
+        let mut info = basis.t().dot(&basis);
+        let ridge = RIDGE * info.diag().iter().fold(1., |acc: f64, v| acc.max(*v));
+        info.diag_mut().mapv_inplace(|v| v + ridge);
+        let inv_info = solve_linear(&info, &Array2::eye(p))?;
+        let beta = inv_info.dot(&basis.t().dot(&y.column(0)));
+
+        let rss = (&basis.dot(&beta) - &y.column(0)).mapv(|r| r * r).sum();
+        let sigma2 = if n > p { rss / (n - p) as f64 } else { 0. };
+        // reduced likelihood of a GP with uncorrelated residuals on normalized outputs
+        let y_var = y.column(0).var(1.);
+        let y_var = if y_var == 0. { 1. } else { y_var };
+        let mle_var = f64::max(rss / n as f64 / y_var, f64::EPSILON);
+        let likelihood = -(n as f64) * mle_var.log10();
+        Ok(PolynomialSurrogate {
+            degree,
+            scaler,
+            beta,
+            inv_info,
+            sigma2,
+            likelihood,
+        })
+    }
+
+    /// Polynomial degree
+    pub fn degree(&self) -> usize {
+        self.degree
+    }
+
+    /// Coefficients of the polynomial basis of standardized inputs
+    /// (constant, linear then quadratic terms `x_i * x_j` with `i <= j`)
+    pub fn beta(&self) -> &Array1<f64> {
+        &self.beta
+    }
+
+    /// Basis values (p,), jacobian (p, nx) and hessians (p, nx, nx) at standardized point
+    fn basis_derivatives(&self, xs: &[f64]) -> (Array1<f64>, Array2<f64>, Array3<f64>) {
+        let nx = xs.len();
+        let p = self.beta.len();
+        let f = polynomial_basis(self.degree, xs);
+        let mut jac = Array2::zeros((p, nx));
+        let mut hess = Array3::zeros((p, nx, nx));
+        for a in 0..nx {
+            jac[[1 + a, a]] = 1.;
+        }
+        if self.degree == 2 {
+            let mut k = nx + 1;
+            for i in 0..nx {
+                for j in i..nx {
+                    jac[[k, i]] += xs[j];
+                    jac[[k, j]] += xs[i];
+                    hess[[k, i, j]] += 1.;
+                    hess[[k, j, i]] += 1.;
+                    k += 1;
+                }
+            }
+        }
+        (f, jac, hess)
+    }
+
+    fn value(&self, x: &ArrayBase<impl Data<Elem = f64>, Ix2>) -> Array1<f64> {
+        let xs = self.scaler.transform(x);
+        xs.rows()
+            .into_iter()
+            .map(|xi| polynomial_basis(self.degree, &xi.to_vec()).dot(&self.beta))
+            .collect()
+    }
+
+    fn var(&self, x: &ArrayBase<impl Data<Elem = f64>, Ix2>) -> Array1<f64> {
+        let xs = self.scaler.transform(x);
+        xs.rows()
+            .into_iter()
+            .map(|xi| {
+                let f = polynomial_basis(self.degree, &xi.to_vec());
+                self.sigma2 * f.dot(&self.inv_info.dot(&f))
+            })
+            .collect()
+    }
+
+    fn value_gradients(&self, x: &ArrayBase<impl Data<Elem = f64>, Ix2>) -> Array2<f64> {
+        let xs = self.scaler.transform(x);
+        let mut drv = Array2::zeros(x.dim());
+        Zip::from(drv.rows_mut())
+            .and(xs.rows())
+            .for_each(|mut d, xi| {
+                let (_, jac, _) = self.basis_derivatives(&xi.to_vec());
+                d.assign(&jac.t().dot(&self.beta));
+            });
+        self.scaler.gradients(drv)
+    }
+
+    fn var_gradients(&self, x: &ArrayBase<impl Data<Elem = f64>, Ix2>) -> Array2<f64> {
+        let xs = self.scaler.transform(x);
+        let mut drv = Array2::zeros(x.dim());
+        Zip::from(drv.rows_mut())
+            .and(xs.rows())
+            .for_each(|mut d, xi| {
+                let (f, jac, _) = self.basis_derivatives(&xi.to_vec());
+                d.assign(&(jac.t().dot(&self.inv_info.dot(&f)) * (2. * self.sigma2)));
+            });
+        self.scaler.gradients(drv)
+    }
+
+    fn value_hessians(&self, x: &ArrayBase<impl Data<Elem = f64>, Ix2>) -> Array3<f64> {
+        let xs = self.scaler.transform(x);
+        let nx = x.ncols();
+        let mut hess = Array3::zeros((x.nrows(), nx, nx));
+        Zip::from(hess.outer_iter_mut())
+            .and(xs.rows())
+            .for_each(|mut h, xi| {
+                let (_, _, hb) = self.basis_derivatives(&xi.to_vec());
+                h.assign(
+                    &hb.into_shape((self.beta.len(), nx * nx))
+                        .unwrap()
+                        .t()
+                        .dot(&self.beta)
+                        .into_shape((nx, nx))
+                        .unwrap(),
+                );
+            });
+        self.scaler.hessians(hess)
+    }
+
+    fn var_hessians(&self, x: &ArrayBase<impl Data<Elem = f64>, Ix2>) -> Array3<f64> {
+        let xs = self.scaler.transform(x);
+        let nx = x.ncols();
+        let mut hess = Array3::zeros((x.nrows(), nx, nx));
+        Zip::from(hess.outer_iter_mut())
+            .and(xs.rows())
+            .for_each(|mut h, xi| {
+                // 2 * sigma2 * (J^t.A.J + sum_b (A.f)_b * H_b)
+                let (f, jac, hb) = self.basis_derivatives(&xi.to_vec());
+                let af = self.inv_info.dot(&f);
+                let second = hb
+                    .into_shape((self.beta.len(), nx * nx))
+                    .unwrap()
+                    .t()
+                    .dot(&af)
+                    .into_shape((nx, nx))
+                    .unwrap();
+                h.assign(&((jac.t().dot(&self.inv_info).dot(&jac) + second) * (2. * self.sigma2)));
+            });
+        self.scaler.hessians(hess)
+    }
+
+    fn process_variance(&self) -> f64 {
+        self.sigma2
+    }
+
+    fn log_likelihood(&self) -> f64 {
+        self.likelihood
+    }
+}
+
+/// Polynomial basis (constant, linear, quadratic `x_i * x_j` with `i <= j` terms) at x
+fn polynomial_basis(degree: usize, x: &[f64]) -> Array1<f64> {
+    let mut f = vec![1.];
+    f.extend_from_slice(x);
+    if degree == 2 {
+        for i in 0..x.len() {
+            for j in i..x.len() {
+                f.push(x[i] * x[j]);
+            }
+        }
+    }
+    Array1::from(f)
+}
+
+impl std::fmt::Display for PolynomialSurrogate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Polynomial({})", self.degree)
+    }
+}
+
+/// Cubic radial basis function interpolant with linear polynomial tail:
+/// `s(x) = sum_i w_i * |x - x_i|^3 + c_0 + sum_a c_a * x_a`
+///
+/// As an interpolant, its `likelihood()` is not defined and set to 0: when used as a
+/// plugin expert, it should be scored with cross-validation based [SelectionCriterion].
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serializable", derive(Serialize, Deserialize))]
+pub struct RbfSurrogate {
+    /// Input standardization
+    scaler: Scaler,
+    /// Standardized training inputs (n, nx)
+    centers: Array2<f64>,
+    /// Weights (n,) of the radial basis functions
+    weights: Array1<f64>,
+    /// Coefficients (nx + 1,) of the linear tail
+    tail: Array1<f64>,
+}
+
+impl RbfSurrogate {
+    /// Fit the interpolant on (x, y) training data, training points should be distinct
+    pub fn train(
+        x: &ArrayBase<impl Data<Elem = f64>, Ix2>,
+        y: &ArrayBase<impl Data<Elem = f64>, Ix2>,
+    ) -> Result<Self> {
+        let (n, nx) = x.dim();
+        if n < nx + 1 {
+            return Err(MoeError::ExpertError(format!(
+                "Not enough points to fit a RBF interpolant, requires at least {}, got {n}",
+                nx + 1
+            )));
+        }
+        let scaler = Scaler::new(x);
+        let centers = scaler.transform(x);
+        let mut system = Array2::zeros((n + nx + 1, n + nx + 1));
+        for i in 0..n {
+            for j in 0..n {
+                let r = (&centers.row(i) - &centers.row(j))
+                    .mapv(|v| v * v)
+                    .sum()
+                    .sqrt();
+                system[[i, j]] = r * r * r;
+            }
+            system[[i, n]] = 1.;
+            system[[n, i]] = 1.;
+            for a in 0..nx {
+                system[[i, n + 1 + a]] = centers[[i, a]];
+                system[[n + 1 + a, i]] = centers[[i, a]];
+            }
+        }
+        let mut rhs = Array2::zeros((n + nx + 1, 1));
+        rhs.slice_mut(s![..n, 0]).assign(&y.column(0));
+        let sol = solve_linear(&system, &rhs)?;
+        Ok(RbfSurrogate {
+            scaler,
+            centers,
+            weights: sol.slice(s![..n, 0]).to_owned(),
+            tail: sol.slice(s![n.., 0]).to_owned(),
+        })
+    }
+
+    fn value(&self, x: &ArrayBase<impl Data<Elem = f64>, Ix2>) -> Array1<f64> {
+        let xs = self.scaler.transform(x);
+        xs.rows()
+            .into_iter()
+            .map(|xi| {
+                let rbf: f64 =
+                    Zip::from(self.centers.rows())
+                        .and(&self.weights)
+                        .fold(0., |acc, c, w| {
+                            let r = (&xi - &c).mapv(|v| v * v).sum().sqrt();
+                            acc + w * r * r * r
+                        });
+                rbf + self.tail[0] + xi.dot(&self.tail.slice(s![1..]))
+            })
+            .collect()
+    }
+
+    fn value_gradients(&self, x: &ArrayBase<impl Data<Elem = f64>, Ix2>) -> Array2<f64> {
+        let xs = self.scaler.transform(x);
+        let mut drv = Array2::zeros(x.dim());
+        Zip::from(drv.rows_mut())
+            .and(xs.rows())
+            .for_each(|mut d, xi| {
+                d.assign(&self.tail.slice(s![1..]));
+                Zip::from(self.centers.rows())
+                    .and(&self.weights)
+                    .for_each(|c, w| {
+                        // 3 * w_i * r_i * (x - x_i)
+                        let diff = &xi - &c;
+                        let r = diff.mapv(|v| v * v).sum().sqrt();
+                        d.scaled_add(3. * w * r, &diff);
+                    });
+            });
+        self.scaler.gradients(drv)
+    }
+
+    fn value_hessians(&self, x: &ArrayBase<impl Data<Elem = f64>, Ix2>) -> Array3<f64> {
+        let xs = self.scaler.transform(x);
+        let nx = x.ncols();
+        let mut hess = Array3::zeros((x.nrows(), nx, nx));
+        Zip::from(hess.outer_iter_mut())
+            .and(xs.rows())
+            .for_each(|mut h, xi| {
+                Zip::from(self.centers.rows())
+                    .and(&self.weights)
+                    .for_each(|c, w| {
+                        // 3 * w_i * (r_i * I + (x - x_i).(x - x_i)^t / r_i)
+                        let diff = &xi - &c;
+                        let r = diff.mapv(|v| v * v).sum().sqrt();
+                        if r > 0. {
+                            Zip::indexed(&mut h).for_each(|(a, b), hab| {
+                                let delta = if a == b { r } else { 0. };
+                                *hab += 3. * w * (delta + diff[a] * diff[b] / r);
+                            });
+                        }
+                    });
+            });
+        self.scaler.hessians(hess)
+    }
+
+    // The interpolant has neither variance nor likelihood: residuals being null,
+    // a likelihood of 0 is returned which is meaningless for likelihood based
+    // selection criteria, cross-validation ones should be used instead
+    fn var(&self, x: &ArrayBase<impl Data<Elem = f64>, Ix2>) -> Array1<f64> {
+        Array1::zeros(x.nrows())
+    }
+
+    fn var_gradients(&self, x: &ArrayBase<impl Data<Elem = f64>, Ix2>) -> Array2<f64> {
+        Array2::zeros(x.dim())
+    }
+
+    fn var_hessians(&self, x: &ArrayBase<impl Data<Elem = f64>, Ix2>) -> Array3<f64> {
+        Array3::zeros((x.nrows(), x.ncols(), x.ncols()))
+    }
+
+    fn process_variance(&self) -> f64 {
+        0.
+    }
+
+    fn log_likelihood(&self) -> f64 {
+        0.
+    }
+}
+
+impl std::fmt::Display for RbfSurrogate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Rbf(Cubic)")
+    }
+}
+
+/// A user-defined expert registered under a unique `name` in the mixture
+/// (see [GpMixtureParams::register_expert](crate::GpMixtureParams::register_expert)).
+///
+/// The `factory` trains the expert on the cluster data, it is used as is: the settings
+/// of the mixture (PLS reduction, theta tuning, optimization restarts...) are not applied.
+/// When selected, the expert is scored wrt the [SelectionCriterion] of the mixture:
+/// cross-validation and leave-one-out errors are computed by refitting the expert
+/// while likelihood based criteria rely on the expert `likelihood()` taken as
+/// a reduced likelihood in log10 scale (as GP experts) with `theta().len() + 1` parameters.
+/// Experts without likelihood such as interpolants should be scored by cross-validation.
+#[derive(Clone)]
+pub struct ExpertPlugin {
+    name: String,
+    factory: Arc<dyn GpSurrogateParams + Send + Sync>,
+}
+
+impl ExpertPlugin {
+    /// Constructor of a plugin expert given its `name` and the parameters training it
+    pub fn new(name: &str, factory: impl GpSurrogateParams + Send + Sync + 'static) -> Self {
+        ExpertPlugin {
+            name: name.to_string(),
+            factory: Arc::new(factory),
+        }
+    }
+
+    /// Name of the expert
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Train the expert on the given (n, nx) inputs and (n, 1) outputs
+    pub fn train(
+        &self,
+        x: &ArrayView2<f64>,
+        y: &ArrayView2<f64>,
+    ) -> Result<Box<dyn FullGpSurrogate>> {
+        self.factory.train(x, y)
+    }
+
+    /// Score of the expert trained on the given data wrt the given criterion
+    /// (the lower the better, infinite when training fails)
+    pub(crate) fn score(
+        &self,
+        criterion: SelectionCriterion,
+        xtrain: &Array2<f64>,
+        ytrain: &Array2<f64>,
+    ) -> f64 {
+        let n = xtrain.nrows();
+        match criterion {
+            SelectionCriterion::CrossValidation | SelectionCriterion::Loo => {
+                let n_fold = if criterion == SelectionCriterion::Loo {
+                    n
+                } else {
+                    std::cmp::min(n, 5)
+                };
+                let mut dataset = Dataset::new(xtrain.to_owned(), ytrain.column(0).to_owned());
+                let mut errors = Vec::new();
+                for (expert, valid) in dataset.iter_fold(n_fold, |train| {
+                    self.train(
+                        &train.records().view(),
+                        &train.targets().view().insert_axis(Axis(1)),
+                    )
+                }) {
+                    let error = expert
+                        .and_then(|expert| expert.predict(&valid.records().view()))
+                        .map(|pred| (valid.targets() - pred).mapv(|e| e * e).sum().sqrt());
+                    match error {
+                        Ok(error) => errors.push(error),
+                        Err(err) => {
+                            debug!("Expert {} fit failed ({err}) => huge score", self.name);
+                            return f64::INFINITY;
+                        }
+                    }
+                }
+                if criterion == SelectionCriterion::Loo {
+                    (errors.iter().map(|e| e * e).sum::<f64>() / n as f64).sqrt()
+                } else {
+                    errors.iter().sum::<f64>() / errors.len() as f64
+                }
+            }
+            _ => match self.train(&xtrain.view(), &ytrain.view()) {
+                Ok(expert) => {
+                    let deviance = -std::f64::consts::LN_10 * expert.likelihood();
+                    let n_params = (expert.theta().len() + 1) as f64;
+                    match criterion {
+                        SelectionCriterion::Aic => deviance + 2. * n_params,
+                        SelectionCriterion::Bic => deviance + n_params * (n as f64).ln(),
+                        _ => deviance / 2.,
+                    }
+                }
+                Err(err) => {
+                    debug!("Expert {} fit failed ({err}) => huge score", self.name);
+                    f64::INFINITY
+                }
+            },
+        }
+    }
+}
+
+impl std::fmt::Debug for ExpertPlugin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "ExpertPlugin({})", self.name)
+    }
+}
+
+/// A macro to implement surrogate traits for the non GP experts
+/// relying on `value`, `var`, their derivatives, `process_variance` and `log_likelihood` methods
+macro_rules! impl_expert_surrogate {
+    ($expert:ident) => {
+        #[cfg_attr(feature = "serializable", typetag::serde)]
+        impl GpSurrogate for $expert {
+            fn dims(&self) -> (usize, usize) {
+                (self.scaler.mean.len(), 1)
+            }
+            fn predict(&self, x: &ArrayView2<f64>) -> Result<Array1<f64>> {
+                Ok(self.value(x))
+            }
+            fn predict_var(&self, x: &ArrayView2<f64>) -> Result<Array1<f64>> {
+                Ok(self.var(x))
+            }
+
+            #[cfg(feature = "persistent")]
+            fn save(&self, path: &str, format: GpFileFormat) -> Result<()> {
+                let mut file = fs::File::create(path).unwrap();
+                let bytes = match format {
+                    GpFileFormat::Json => serde_json::to_vec(self as &dyn FullGpSurrogate)
+                        .map_err(MoeError::SaveJsonError)?,
+                    GpFileFormat::Binary => bincode::serialize(self as &dyn FullGpSurrogate)
+                        .map_err(MoeError::SaveBinaryError)?,
+                };
+                file.write_all(&bytes)?;
+                Ok(())
+            }
+        }
+
+        #[cfg_attr(feature = "serializable", typetag::serde)]
+        impl GpSurrogateExt for $expert {
+            fn predict_gradients(&self, x: &ArrayView2<f64>) -> Result<Array2<f64>> {
+                Ok(self.value_gradients(x))
+            }
+            fn predict_var_gradients(&self, x: &ArrayView2<f64>) -> Result<Array2<f64>> {
+                Ok(self.var_gradients(x))
+            }
+            fn predict_hessians(&self, x: &ArrayView2<f64>) -> Result<Array3<f64>> {
+                Ok(self.value_hessians(x))
+            }
+            fn predict_var_hessians(&self, x: &ArrayView2<f64>) -> Result<Array3<f64>> {
+                Ok(self.var_hessians(x))
+            }
+            fn sample(&self, _x: &ArrayView2<f64>, _n_traj: usize) -> Result<Array2<f64>> {
+                Err(MoeError::SampleError(format!(
+                    "Can not sample trajectories of {self} expert"
+                )))
+            }
+        }
+
+        #[cfg_attr(feature = "serializable", typetag::serde)]
+        impl GpParameterized for $expert {
+            fn theta(&self) -> Array1<f64> {
+                Array1::zeros(0)
+            }
+
+            fn variance(&self) -> f64 {
+                self.process_variance()
+            }
+
+            fn noise_variance(&self) -> f64 {
+                self.process_variance()
+            }
+
+            fn likelihood(&self) -> f64 {
+                self.log_likelihood()
+            }
+        }
+
+        #[cfg_attr(feature = "serializable", typetag::serde)]
+        impl FullGpSurrogate for $expert {}
+    };
+}
+
+impl_expert_surrogate!(PolynomialSurrogate);
+impl_expert_surrogate!(RbfSurrogate);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+    use egobox_doe::{Lhs, SamplingMethod};
+    use ndarray::{Array, array};
+    use ndarray_rand::rand::SeedableRng;
+    use rand_xoshiro::Xoshiro256Plus;
+
+    fn quadratic(x: &Array2<f64>) -> Array2<f64> {
+        x.map_axis(Axis(1), |r| {
+            1. + 2. * r[0] - r[1] + 0.5 * r[0] * r[1] + r[1] * r[1]
+        })
+        .insert_axis(Axis(1))
+    }
+
+    fn check_derivatives(expert: &dyn FullGpSurrogate, x: &Array2<f64>) {
+        let e = 1e-5;
+        let drv = expert.predict_gradients(&x.view()).unwrap();
+        let hess = expert.predict_hessians(&x.view()).unwrap();
+        let var_drv = expert.predict_var_gradients(&x.view()).unwrap();
+        let var_hess = expert.predict_var_hessians(&x.view()).unwrap();
+        for a in 0..x.ncols() {
+            let mut xp = x.to_owned();
+            xp.column_mut(a).mapv_inplace(|v| v + e);
+            let mut xm = x.to_owned();
+            xm.column_mut(a).mapv_inplace(|v| v - e);
+            let fdiff = (expert.predict(&xp.view()).unwrap() - expert.predict(&xm.view()).unwrap())
+                / (2. * e);
+            assert_abs_diff_eq!(fdiff, drv.column(a), epsilon = 1e-5);
+            let fdiff = (expert.predict_gradients(&xp.view()).unwrap()
+                - expert.predict_gradients(&xm.view()).unwrap())
+                / (2. * e);
+            assert_abs_diff_eq!(fdiff, hess.slice(s![.., .., a]), epsilon = 1e-5);
+            let fdiff = (expert.predict_var(&xp.view()).unwrap()
+                - expert.predict_var(&xm.view()).unwrap())
+                / (2. * e);
+            assert_abs_diff_eq!(fdiff, var_drv.column(a), epsilon = 1e-5);
+            let fdiff = (expert.predict_var_gradients(&xp.view()).unwrap()
+                - expert.predict_var_gradients(&xm.view()).unwrap())
+                / (2. * e);
+            assert_abs_diff_eq!(fdiff, var_hess.slice(s![.., .., a]), epsilon = 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_polynomial_surrogate() {
+        let xlimits = array![[-1., 2.], [0., 3.]];
+        let xt = Lhs::new(&xlimits)
+            .with_rng(Xoshiro256Plus::seed_from_u64(42))
+            .sample(20);
+        let yt = quadratic(&xt);
+        let noisy = &yt + &Array::linspace(-0.1, 0.1, 20).insert_axis(Axis(1));
+
+        let poly = PolynomialSurrogate::train(2, &xt, &yt).unwrap();
+        let xv = Lhs::new(&xlimits)
+            .with_rng(Xoshiro256Plus::seed_from_u64(0))
+            .sample(10);
+        assert_abs_diff_eq!(
+            poly.predict(&xv.view()).unwrap(),
+            quadratic(&xv).remove_axis(Axis(1)),
+            epsilon = 1e-8
+        );
+
+        let poly = PolynomialSurrogate::train(2, &xt, &noisy).unwrap();
+        assert!(
+            poly.predict_var(&xv.view())
+                .unwrap()
+                .iter()
+                .all(|v| *v > 0.)
+        );
+        check_derivatives(&poly, &xv);
+
+        let linear = PolynomialSurrogate::train(1, &xt, &noisy).unwrap();
+        check_derivatives(&linear, &xv);
+        assert!(PolynomialSurrogate::train(2, &xt.slice(s![..5, ..]), &yt).is_err());
+    }
+
+    #[test]
+    fn test_polynomial_likelihood() {
+        use egobox_gp::{
+            GaussianProcess, ThetaTuning, correlation_models::SquaredExponentialCorr,
+            mean_models::LinearMean,
+        };
+        use linfa::traits::Fit;
+
+        // with a huge fixed theta the GP correlation matrix is the identity
+        // and its reduced likelihood is the one of the linear regression
+        let xt = Array::linspace(0., 1., 20).insert_axis(Axis(1));
+        let yt = xt.mapv(|v| 2. * v + f64::sin(20. * v) / 5.);
+        let gp = GaussianProcess::<f64, LinearMean, SquaredExponentialCorr>::params(
+            LinearMean(),
+            SquaredExponentialCorr(),
+        )
+        .theta_tuning(ThetaTuning::Fixed(array![1e6]))
+        .fit(&Dataset::new(xt.clone(), yt.column(0).to_owned()))
+        .expect("GP fitted");
+        let poly = PolynomialSurrogate::train(1, &xt, &yt).unwrap();
+        assert_abs_diff_eq!(poly.likelihood(), gp.likelihood(), epsilon = 1e-6);
+    }
+
+    #[test]
+    fn test_rbf_surrogate() {
+        let xlimits = array![[-1., 2.], [0., 3.]];
+        let xt = Lhs::new(&xlimits)
+            .with_rng(Xoshiro256Plus::seed_from_u64(42))
+            .sample(30);
+        let yt = xt.mapv(f64::sin).sum_axis(Axis(1)).insert_axis(Axis(1));
+
+        let rbf = RbfSurrogate::train(&xt, &yt).unwrap();
+        // interpolation of training data
+        assert_abs_diff_eq!(
+            rbf.predict(&xt.view()).unwrap(),
+            yt.column(0),
+            epsilon = 1e-8
+        );
+        let xv = Lhs::new(&xlimits)
+            .with_rng(Xoshiro256Plus::seed_from_u64(0))
+            .sample(10);
+        let yv = xv.mapv(f64::sin).sum_axis(Axis(1));
+        assert_abs_diff_eq!(rbf.predict(&xv.view()).unwrap(), yv, epsilon = 0.1);
+        check_derivatives(&rbf, &xv);
+    }
+
+    /// A user-defined expert predicting the mean of its training outputs
+    #[derive(Clone, Debug)]
+    #[cfg_attr(feature = "serializable", derive(Serialize, Deserialize))]
+    struct MeanExpert {
+        nx: usize,
+        mean: f64,
+        var: f64,
+    }
+
+    impl std::fmt::Display for MeanExpert {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "Mean({})", self.mean)
+        }
+    }
+
+    #[cfg_attr(feature = "serializable", typetag::serde)]
+    impl GpSurrogate for MeanExpert {
+        fn dims(&self) -> (usize, usize) {
+            (self.nx, 1)
+        }
+        fn predict(&self, x: &ArrayView2<f64>) -> Result<Array1<f64>> {
+            Ok(Array1::from_elem(x.nrows(), self.mean))
+        }
+        fn predict_var(&self, x: &ArrayView2<f64>) -> Result<Array1<f64>> {
+            Ok(Array1::from_elem(x.nrows(), self.var))
+        }
+        #[cfg(feature = "persistent")]
+        fn save(&self, path: &str, format: GpFileFormat) -> Result<()> {
+            let mut file = fs::File::create(path)?;
+            let bytes = match format {
+                GpFileFormat::Json => serde_json::to_vec(self as &dyn FullGpSurrogate)
+                    .map_err(MoeError::SaveJsonError)?,
+                GpFileFormat::Binary => bincode::serialize(self as &dyn FullGpSurrogate)
+                    .map_err(MoeError::SaveBinaryError)?,
+            };
+            file.write_all(&bytes)?;
+            Ok(())
+        }
+    }
+
+    #[cfg_attr(feature = "serializable", typetag::serde)]
+    impl GpSurrogateExt for MeanExpert {
+        fn predict_gradients(&self, x: &ArrayView2<f64>) -> Result<Array2<f64>> {
+            Ok(Array2::zeros(x.dim()))
+        }
+        fn predict_var_gradients(&self, x: &ArrayView2<f64>) -> Result<Array2<f64>> {
+            Ok(Array2::zeros(x.dim()))
+        }
+        fn predict_hessians(&self, x: &ArrayView2<f64>) -> Result<Array3<f64>> {
+            Ok(Array3::zeros((x.nrows(), self.nx, self.nx)))
+        }
+        fn predict_var_hessians(&self, x: &ArrayView2<f64>) -> Result<Array3<f64>> {
+            Ok(Array3::zeros((x.nrows(), self.nx, self.nx)))
+        }
+        fn sample(&self, x: &ArrayView2<f64>, n_traj: usize) -> Result<Array2<f64>> {
+            Ok(Array2::from_elem((x.nrows(), n_traj), self.mean))
+        }
+    }
+
+    #[cfg_attr(feature = "serializable", typetag::serde)]
+    impl GpParameterized for MeanExpert {
+        fn theta(&self) -> Array1<f64> {
+            Array1::zeros(0)
+        }
+        fn variance(&self) -> f64 {
+            self.var
+        }
+        fn noise_variance(&self) -> f64 {
+            0.
+        }
+        fn likelihood(&self) -> f64 {
+            0.
+        }
+    }
+
+    #[cfg_attr(feature = "serializable", typetag::serde)]
+    impl FullGpSurrogate for MeanExpert {}
+
+    struct MeanExpertParams;
+
+    impl GpSurrogateParams for MeanExpertParams {
+        fn theta_tuning(&mut self, _theta_tuning: egobox_gp::ThetaTuning<f64>) {}
+        fn kpls_dim(&mut self, _kpls_dim: Option<usize>) {}
+        fn n_start(&mut self, _n_start: usize) {}
+        fn max_eval(&mut self, _max_eval: usize) {}
+        fn nugget(&mut self, _nugget: f64) {}
+        fn train(
+            &self,
+            x: &ArrayView2<f64>,
+            y: &ArrayView2<f64>,
+        ) -> Result<Box<dyn FullGpSurrogate>> {
+            Ok(Box::new(MeanExpert {
+                nx: x.ncols(),
+                mean: y.mean().unwrap(),
+                var: y.var(0.),
+            }))
+        }
+    }
+
+    #[test]
+    fn test_expert_plugin() {
+        use crate::{
+            DriftCriterion, ExpertSelection, ExpertType, GpMixture, MixtureGpSurrogate, NbClusters,
+            Recombination, UpdateStatus,
+        };
+        use linfa::{ParamGuard, traits::Fit};
+
+        let xt = Array::linspace(0., 1., 40).insert_axis(Axis(1));
+        let yt = xt
+            .column(0)
+            .mapv(|v| if v < 0.5 { 1. } else { f64::sin(10. * v) });
+        let params = GpMixture::params()
+            .n_clusters(NbClusters::fixed(2))
+            .recombination(Recombination::Hard)
+            .register_expert("Mean", MeanExpertParams)
+            .with_rng(Xoshiro256Plus::seed_from_u64(0));
+
+        // plugin expert competes with GP experts
+        let moe = params
+            .clone()
+            .fit(&Dataset::new(xt.clone(), yt.clone()))
+            .expect("MOE fitted");
+        for scores in moe.expert_scores() {
+            let (_, score) = scores.scores().iter().find(|(n, _)| n == "Mean").unwrap();
+            assert!(score.is_some());
+            // the best expert has the lowest score
+            let best = scores
+                .scores()
+                .iter()
+                .filter_map(|(n, score)| score.map(|s| (n, s)))
+                .min_by(|a, b| a.1.total_cmp(&b.1))
+                .unwrap();
+            assert_eq!(best.0, scores.best());
+        }
+
+        // plugin expert given explicitly
+        let moe = params
+            .clone()
+            .expert_selection(ExpertSelection::PerCluster(vec![
+                ExpertType::Plugin("Mean".to_string()),
+                ExpertType::Plugin("Mean".to_string()),
+            ]))
+            .fit(&Dataset::new(xt.clone(), yt.clone()))
+            .expect("MOE fitted");
+        assert!(
+            moe.experts()
+                .iter()
+                .all(|e| e.to_string().starts_with("Mean("))
+        );
+
+        #[cfg(feature = "persistent")]
+        {
+            let test_dir = "target/tests";
+            std::fs::create_dir_all(test_dir).ok();
+            let filename = format!("{test_dir}/saved_moe_plugin.json");
+            let xtest = array![[0.2], [0.8]];
+            moe.save(&filename, GpFileFormat::Json).expect("MoE saving");
+            let mut loaded = GpMixture::load(&filename, GpFileFormat::Json).expect("MoE loading");
+            assert_abs_diff_eq!(
+                loaded.predict(&xtest).unwrap(),
+                moe.predict(&xtest).unwrap(),
+                epsilon = 1e-12
+            );
+
+            // plugins are not saved: update requires to register them again
+            let (x_new, y_new) = (array![[0.21], [0.81]], array![1., f64::sin(8.1)]);
+            let err = loaded.update(&x_new, &y_new).unwrap_err();
+            assert!(err.to_string().contains("'Mean' is not registered"));
+            let mut loaded = (*loaded).register_expert("Mean", MeanExpertParams);
+            loaded.update(&x_new, &y_new).expect("MoE updated");
+
+            // even when the update retrains the whole mixture on drifting data
+            let moe = params
+                .clone()
+                .expert_selection(ExpertSelection::PerCluster(vec![
+                    ExpertType::Plugin("Mean".to_string()),
+                    ExpertType::Plugin("Mean".to_string()),
+                ]))
+                .drift_criterion(DriftCriterion::PredictionError { threshold: 1e-6 })
+                .fit(&Dataset::new(xt.clone(), yt.clone()))
+                .expect("MOE fitted");
+            moe.save(&filename, GpFileFormat::Json).expect("MoE saving");
+            let mut loaded = GpMixture::load(&filename, GpFileFormat::Json).expect("MoE loading");
+            let err = loaded.update(&x_new, &y_new).unwrap_err();
+            assert!(err.to_string().contains("'Mean' is not registered"));
+            assert_abs_diff_eq!(
+                loaded.predict(&xtest).unwrap(),
+                moe.predict(&xtest).unwrap(),
+                epsilon = 1e-12
+            );
+            let mut loaded = (*loaded).register_expert("Mean", MeanExpertParams);
+            let status = loaded.update(&x_new, &y_new).expect("MoE updated");
+            assert_eq!(status, UpdateStatus::Reclustered);
+
+            // plugin experts can be saved on their own
+            let filename = format!("{test_dir}/saved_plugin_expert.json");
+            moe.experts()[0]
+                .save(&filename, GpFileFormat::Json)
+                .expect("Expert saving");
+        }
+
+        // invalid registrations
+        assert!(
+            params
+                .clone()
+                .register_expert("Mean", MeanExpertParams)
+                .check()
+                .is_err()
+        );
+        assert!(
+            GpMixture::params()
+                .register_expert("Constant_SquaredExponential", MeanExpertParams)
+                .check()
+                .is_err()
+        );
+        assert!(
+            GpMixture::params()
+                .n_clusters(NbClusters::fixed(1))
+                .expert_selection(ExpertSelection::PerCluster(vec![ExpertType::Plugin(
+                    "Unknown".to_string()
+                )]))
+                .check()
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,361 @@
+//! Export of trained mixtures of GP experts as a self-contained set of coefficients.
+//!
+//! The mixture coefficients gather the coefficients of each GP expert (see [GpCoefficients]
+//! for the GP prediction formulas) and the parameters of the gaussian mixture used
+//! to compute the responsabilities `p_k(x)` of the experts at a given point `x`:
+//!
+//! * `z_k = (x - mean_k) . precisions_chol_k`
+//! * `w_k = -0.5 * (|z_k|^2 + nx * ln(2 * pi)) + log_det_k + ln(weight_k)`
+//! * `p_k = exp(w_k - ln(sum_j exp(w_j)))`
+//!
+//! When the mixture is gated by a gating network (see [GatingNetwork]), the responsabilities
+//! are given by the network instead of the gaussian mixture.
+//!
+//! When the mixture is gated by a partition of the input space (see [Partition]),
+//! the expert of the region containing `x` gives the prediction.
+//!
+//! Otherwise with `Hard` recombination the expert with the highest responsability gives the prediction
+//! while with `Smooth` recombination the prediction is `sum_k p_k * y_k(x)` and the variance
+//! `sum_k p_k^2 * var_k(x)` or `sum_k p_k * (var_k(x) + y_k(x)^2) - y(x)^2` wrt [MixtureVariance].
+
+use crate::errors::{MoeError, Result};
+use crate::gating::GatingNetwork;
+use crate::gaussian_mixture::GaussianMixture;
+use crate::partitioning::Partition;
+use crate::surrogates::FullGpSurrogate;
+use crate::types::{MixtureVariance, Recombination};
+
+use egobox_gp::GpCoefficients;
+use ndarray::{ArrayView1, Axis};
+
+#[cfg(feature = "serializable")]
+use serde::{Deserialize, Serialize};
+
+/// Identifier of the GP mixture coefficients format
+pub const GP_MIXTURE_COEFFICIENTS_FORMAT: &str = "egobox-moe-coefficients";
+
+/// Current version of the GP mixture coefficients format
+pub const GP_MIXTURE_COEFFICIENTS_VERSION: u32 = 1;
+
+/// Gaussian mixture coefficients used to compute experts responsabilities.
+///
+/// Dimensions are given wrt `k` the number of clusters and `nx` the input dimension.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serializable", derive(Serialize, Deserialize))]
+pub struct GatingCoefficients {
+    /// Weights of the clusters (k,)
+    pub weights: Vec<f64>,
+    /// Means of the clusters (k, nx)
+    pub means: Vec<Vec<f64>>,
+    /// Lower cholesky factors of the precision matrices scaled by the heaviside factor (k, nx, nx)
+    pub precisions_chol: Vec<Vec<Vec<f64>>>,
+    /// Log determinants of the scaled cholesky factors (k,)
+    pub log_det: Vec<f64>,
+}
+
+impl GatingCoefficients {
+    fn new(gmx: &GaussianMixture<f64>) -> Self {
+        let (precisions_chol, log_det) = gmx.scaled_precisions_chol();
+        GatingCoefficients {
+            weights: gmx.weights().to_vec(),
+            means: gmx.means().rows().into_iter().map(|m| m.to_vec()).collect(),
+            precisions_chol: precisions_chol
+                .outer_iter()
+                .map(|prec| prec.rows().into_iter().map(|r| r.to_vec()).collect())
+                .collect(),
+            log_det: log_det.to_vec(),
+        }
+    }
+
+    /// Number of clusters
+    pub fn n_clusters(&self) -> usize {
+        self.weights.len()
+    }
+
+    /// Compute the responsabilities (k,) of the experts at the given `x` point (nx,)
+    pub fn probas(&self, x: &[f64]) -> Vec<f64> {
+        if self.n_clusters() == 1 {
+            return vec![1.];
+        }
+        let cst = x.len() as f64 * f64::ln(2. * std::f64::consts::PI);
+        let weighted_log_prob: Vec<f64> = (0..self.n_clusters())
+            .map(|k| {
+                let prec = &self.precisions_chol[k];
+                let sq: f64 = (0..x.len())
+                    .map(|j| {
+                        let z: f64 = (0..x.len())
+                            .map(|i| (x[i] - self.means[k][i]) * prec[i][j])
+                            .sum();
+                        z * z
+                    })
+                    .sum();
+                -0.5 * (sq + cst) + self.log_det[k] + self.weights[k].ln()
+            })
+            .collect();
+        let norm: f64 = weighted_log_prob
+            .iter()
+            .map(|v| {
+                if *v <= f64::MIN_10_EXP as f64 {
+                    0.
+                } else {
+                    v.exp()
+                }
+            })
+            .sum();
+        let log_norm = if norm.abs() < f64::EPSILON {
+            0.
+        } else {
+            norm.ln()
+        };
+        weighted_log_prob
+            .iter()
+            .map(|v| (v - log_norm).exp())
+            .collect()
+    }
+}
+
+/// Coefficients of a trained [GpMixture](crate::GpMixture) in double precision.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serializable", derive(Serialize, Deserialize))]
+pub struct GpMixtureCoefficients {
+    /// Format identifier, always [GP_MIXTURE_COEFFICIENTS_FORMAT]
+    pub format: String,
+    /// Format version
+    pub version: u32,
+    /// Recombination mode of the experts predictions
+    pub recombination: Recombination<f64>,
+    /// Variance computation mode with `Smooth` recombination
+    #[cfg_attr(feature = "serializable", serde(default))]
+    pub mixture_variance: MixtureVariance,
+    /// Gaussian mixture coefficients
+    pub gating: GatingCoefficients,
+    /// Partition of the input space gating the experts (take precedence over gating)
+    #[cfg_attr(
+        feature = "serializable",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub partition: Option<Partition>,
+    /// Gating network computing the responsabilities (take precedence over gating)
+    #[cfg_attr(
+        feature = "serializable",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub gating_network: Option<GatingNetwork>,
+    /// Coefficients of the GP experts, one per cluster
+    pub experts: Vec<GpCoefficients>,
+}
+
+impl GpMixtureCoefficients {
+    pub(crate) fn new(
+        recombination: Recombination<f64>,
+        gmx: &GaussianMixture<f64>,
+        partition: Option<&Partition>,
+        gating_network: Option<&GatingNetwork>,
+        mixture_variance: MixtureVariance,
+        experts: &[Box<dyn FullGpSurrogate>],
+    ) -> Result<Self> {
+        Ok(GpMixtureCoefficients {
+            format: GP_MIXTURE_COEFFICIENTS_FORMAT.to_string(),
+            version: GP_MIXTURE_COEFFICIENTS_VERSION,
+            recombination,
+            mixture_variance,
+            gating: GatingCoefficients::new(gmx),
+            partition: partition.cloned(),
+            gating_network: gating_network.cloned(),
+            experts: experts
+                .iter()
+                .map(|expert| expert.coefficients())
+                .collect::<Result<Vec<_>>>()?,
+        })
+    }
+
+    /// Predict the output value at the given `x` point (nx,)
+    pub fn predict(&self, x: &[f64]) -> f64 {
+        match self.recombination {
+            Recombination::Hard => self.experts[self.best_expert(x)].predict(x),
+            Recombination::Smooth(_) => self
+                .probas(x)
+                .iter()
+                .zip(&self.experts)
+                .map(|(p, expert)| expert.predict(x) * p)
+                .sum(),
+        }
+    }
+
+    /// Predict the variance at the given `x` point (nx,)
+    pub fn predict_var(&self, x: &[f64]) -> f64 {
+        match self.recombination {
+            Recombination::Hard => self.experts[self.best_expert(x)].predict_var(x),
+            Recombination::Smooth(_) => {
+                let probas = self.probas(x);
+                match self.mixture_variance {
+                    MixtureVariance::Weighted => probas
+                        .iter()
+                        .zip(&self.experts)
+                        .map(|(p, expert)| expert.predict_var(x) * p * p)
+                        .sum(),
+                    MixtureVariance::TotalVariance => {
+                        let (mean, second) = probas.iter().zip(&self.experts).fold(
+                            (0., 0.),
+                            |(mean, second), (p, expert)| {
+                                let mu = expert.predict(x);
+                                (
+                                    mean + p * mu,
+                                    second + p * (expert.predict_var(x) + mu * mu),
+                                )
+                            },
+                        );
+                        f64::max(second - mean * mean, 0.)
+                    }
+                }
+            }
+        }
+    }
+
+    /// Responsabilities (k,) of the experts at the given `x` point (nx,)
+    pub fn probas(&self, x: &[f64]) -> Vec<f64> {
+        match &self.gating_network {
+            Some(network) => network
+                .predict_probas(&ArrayView1::from(x).insert_axis(Axis(0)))
+                .row(0)
+                .to_vec(),
+            None => self.gating.probas(x),
+        }
+    }
+
+    /// Index of the expert with the highest responsability at `x`
+    fn best_expert(&self, x: &[f64]) -> usize {
+        if let Some(partition) = &self.partition {
+            return partition.region(x);
+        }
+        let probas = self.probas(x);
+        (1..probas.len()).fold(0, |best, k| if probas[k] > probas[best] { k } else { best })
+    }
+
+    /// Check the consistency of the format, version and dimensions of the coefficients
+    pub fn check(&self) -> Result<()> {
+        if self.format != GP_MIXTURE_COEFFICIENTS_FORMAT
+            || self.version > GP_MIXTURE_COEFFICIENTS_VERSION
+        {
+            return Err(MoeError::LoadError(format!(
+                "expected {GP_MIXTURE_COEFFICIENTS_FORMAT} format up to version {GP_MIXTURE_COEFFICIENTS_VERSION}, got {} version {}",
+                self.format, self.version
+            )));
+        }
+        let k = self.gating.n_clusters();
+        let nx = self.experts.first().map(|e| e.nx()).unwrap_or_default();
+        let gating = &self.gating;
+        if self.experts.len() != k
+            || self.partition.as_ref().is_some_and(|p| p.n_regions() != k)
+            || self
+                .gating_network
+                .as_ref()
+                .is_some_and(|g| g.n_clusters() != k || g.weights().nrows() != nx)
+            || self.experts.iter().any(|e| e.nx() != nx)
+            || gating.log_det.len() != k
+            || gating.means.len() != k
+            || gating.means.iter().any(|m| m.len() != nx)
+            || gating.precisions_chol.len() != k
+            || gating
+                .precisions_chol
+                .iter()
+                .any(|p| p.len() != nx || p.iter().any(|r| r.len() != nx))
+        {
+            return Err(MoeError::LoadError(format!(
+                "inconsistent mixture coefficients dimensions wrt {k} clusters and nx={nx}"
+            )));
+        }
+        for expert in self.experts.iter() {
+            expert.check()?;
+        }
+        Ok(())
+    }
+
+    /// Serialize the coefficients in JSON format
+    #[cfg(feature = "persistent")]
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Deserialize coefficients from JSON format checking their consistency
+    #[cfg(feature = "persistent")]
+    pub fn from_json(json: &str) -> Result<Self> {
+        let coefs: GpMixtureCoefficients =
+            serde_json::from_str(json).map_err(|e| MoeError::LoadError(e.to_string()))?;
+        coefs.check()?;
+        Ok(coefs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{GpMixture, NbClusters};
+    use approx::assert_abs_diff_eq;
+    use linfa::{Dataset, traits::Fit};
+    use ndarray::{Array1, Array2, Axis};
+    use ndarray_rand::rand::SeedableRng;
+    use rand_xoshiro::Xoshiro256Plus;
+
+    fn f_test_1d(x: &Array2<f64>) -> Array1<f64> {
+        x.column(0).mapv(|xi| {
+            if xi < 0.4 {
+                xi * xi
+            } else if xi < 0.8 {
+                3. * xi + 1.
+            } else {
+                f64::sin(10. * xi)
+            }
+        })
+    }
+
+    fn check_coefficients(moe: &GpMixture) {
+        let coefs = moe.coefficients().expect("mixture exported");
+        coefs.check().expect("consistent coefficients");
+        assert_eq!(coefs.experts.len(), 3);
+
+        let xtest = Array1::linspace(0., 1., 50).insert_axis(Axis(1));
+        let y = moe.predict(&xtest).unwrap();
+        let var = moe.predict_var(&xtest).unwrap();
+        for (i, x) in xtest.rows().into_iter().enumerate() {
+            let x = x.to_vec();
+            assert_abs_diff_eq!(coefs.predict(&x), y[i], epsilon = 1e-8 * (1. + y[i].abs()));
+            assert_abs_diff_eq!(coefs.predict_var(&x), var[i], epsilon = 1e-8);
+        }
+    }
+
+    fn train(recombination: Recombination<f64>) -> GpMixture {
+        let rng = Xoshiro256Plus::seed_from_u64(42);
+        let xt = Array1::linspace(0., 1., 40).insert_axis(Axis(1));
+        let yt = f_test_1d(&xt);
+        GpMixture::params()
+            .n_clusters(NbClusters::fixed(3))
+            .recombination(recombination)
+            .with_rng(rng)
+            .fit(&Dataset::new(xt, yt))
+            .expect("MoE model training")
+    }
+
+    #[test]
+    fn test_mixture_coefficients_hard() {
+        check_coefficients(&train(Recombination::Hard));
+    }
+
+    #[test]
+    fn test_mixture_coefficients_smooth() {
+        check_coefficients(&train(Recombination::Smooth(Some(0.5))));
+    }
+
+    #[cfg(feature = "persistent")]
+    #[test]
+    fn test_mixture_coefficients_json() {
+        let coefs = train(Recombination::Smooth(None)).coefficients().unwrap();
+        let loaded = GpMixtureCoefficients::from_json(&coefs.to_json().unwrap()).unwrap();
+        assert_eq!(loaded.recombination, coefs.recombination);
+        assert_abs_diff_eq!(
+            loaded.predict(&[0.42]),
+            coefs.predict(&[0.42]),
+            epsilon = 1e-12
+        );
+    }
+}
@@ -0,0 +1,357 @@
+//! Gating network used as an alternative to the gaussian mixture to compute
+//! the responsabilities of the experts (see [GatingMethod](crate::GatingMethod)).
+//!
+//! The gating network is a multinomial logistic regression (softmax classifier)
+//! trained on the assignments of the training points to the experts.
+//! With `x_s = (x - x_mean) / x_std` the standardized input, the responsability of
+//! the kth expert is `p_k(x) = exp(a_k(x)) / sum_j exp(a_j(x))` where
+//! `a_k(x) = (x_s . w_k + b_k) / h` and `h` the heaviside factor.
+
+use crate::errors::Result;
+
+#[cfg(feature = "blas")]
+use linfa::dataset::{WithLapack, WithoutLapack};
+use linfa::traits::PredictInplace;
+#[cfg(not(feature = "blas"))]
+use linfa_linalg::{cholesky::*, triangular::*};
+use ndarray::{Array1, Array2, Array3, Array4, ArrayBase, Axis, Data, Ix1, Ix2, Zip, s};
+#[cfg(feature = "blas")]
+use ndarray_linalg::{cholesky::*, triangular::*};
+use ndarray_stats::QuantileExt;
+
+#[cfg(feature = "serializable")]
+use serde::{Deserialize, Serialize};
+
+/// Maximum number of Newton iterations when fitting the gating network
+const MAX_ITERS: usize = 100;
+/// Tolerance on the gradient norm of the training loss
+const GRAD_TOL: f64 = 1e-8;
+
+/// Multinomial logistic regression computing the responsabilities of k experts
+/// at points of dimension nx.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serializable", derive(Serialize, Deserialize))]
+pub struct GatingNetwork {
+    /// Mean (nx,) of the training inputs
+    x_mean: Array1<f64>,
+    /// Standard deviation (nx,) of the training inputs
+    x_std: Array1<f64>,
+    /// Weights (nx, k) of the standardized inputs
+    weights: Array2<f64>,
+    /// Intercepts (k,)
+    intercept: Array1<f64>,
+    /// Factor controlling the smoothness of the transition between experts
+    heaviside_factor: f64,
+}
+
+impl GatingNetwork {
+    /// Fit the gating network on `x` (n, nx) points assigned to `clusters` (n,) among `n_clusters`.
+    ///
+    /// The L2 regularized cross-entropy loss is minimized with Newton iterations,
+    /// the intercepts are not penalized.
+    pub(crate) fn fit(
+        x: &ArrayBase<impl Data<Elem = f64>, Ix2>,
+        clusters: &ArrayBase<impl Data<Elem = usize>, Ix1>,
+        n_clusters: usize,
+        regularization: f64,
+    ) -> Result<Self> {
+        let (n, nx) = x.dim();
+        let x_mean = x.mean_axis(Axis(0)).unwrap();
+        let x_std = x
+            .std_axis(Axis(0), 0.)
+            .mapv(|v| if v < f64::EPSILON { 1. } else { v });
+        let mut xs = Array2::ones((n, nx + 1));
+        xs.slice_mut(s![.., ..nx]).assign(&((x - &x_mean) / &x_std));
+        let mut targets = Array2::<f64>::zeros((n, n_clusters));
+        for (i, &c) in clusters.iter().enumerate() {
+            targets[[i, c]] = 1.;
+        }
+
+        // coefs (nx + 1, k) gathers weights and intercepts (last row)
+        let mut coefs = Array2::<f64>::zeros((nx + 1, n_clusters));
+        let mut penalty = Array2::from_elem((nx + 1, n_clusters), regularization);
+        penalty.row_mut(nx).fill(0.);
+        let loss = |coefs: &Array2<f64>| {
+            let logits = xs.dot(coefs);
+            let data_loss: f64 =
+                Zip::from(logits.rows())
+                    .and(targets.rows())
+                    .fold(0., |acc, a, t| {
+                        let amax = *a.max().unwrap();
+                        let lse = amax + a.mapv(|v| (v - amax).exp()).sum().ln();
+                        acc + lse - a.dot(&t)
+                    });
+            data_loss / n as f64 + 0.5 * (&penalty * &coefs.mapv(|v| v * v)).sum()
+        };
+
+        let mut current = loss(&coefs);
+        for _ in 0..MAX_ITERS {
+            let probas = softmax(&xs.dot(&coefs));
+            let grad = xs.t().dot(&(&probas - &targets)) / n as f64 + &coefs * &penalty;
+            if grad.iter().fold(0., |acc: f64, g| acc.max(g.abs())) < GRAD_TOL {
+                break;
+            }
+
+            // hessian wrt coefs flattened column-wise, block (a, b) is
+            // sum_i p_ia * (delta_ab - p_ib) * xs_i . xs_i^t / n
+            let mut hess = Array2::from_diag(&Array1::from_iter(penalty.t().iter().cloned()));
+            // the loss is invariant when shifting all intercepts by the same amount and
+            // the gradient is orthogonal to that direction: adding v.v^t with v the
+            // normalized shift direction makes the hessian definite without changing the step
+            for a in 0..n_clusters {
+                for b in 0..n_clusters {
+                    hess[[a * (nx + 1) + nx, b * (nx + 1) + nx]] += 1. / n_clusters as f64;
+                }
+            }
+            for (xi, pi) in xs.rows().into_iter().zip(probas.rows()) {
+                let outer = Array2::from_shape_fn((nx + 1, nx + 1), |(u, v)| xi[u] * xi[v]);
+                for a in 0..n_clusters {
+                    for b in 0..n_clusters {
+                        let delta = if a == b { 1. } else { 0. };
+                        let w = pi[a] * (delta - pi[b]) / n as f64;
+                        hess.slice_mut(s![
+                            a * (nx + 1)..(a + 1) * (nx + 1),
+                            b * (nx + 1)..(b + 1) * (nx + 1)
+                        ])
+                        .scaled_add(w, &outer);
+                    }
+                }
+            }
+            let flat_grad = Array1::from_iter(grad.t().iter().cloned());
+            let step = solve_spd(&hess, &flat_grad)?;
+            let step = Array2::from_shape_vec((n_clusters, nx + 1), step.to_vec())
+                .unwrap()
+                .reversed_axes();
+
+            // backtracking line search to guarantee the loss decrease
+            let mut alpha = 1.;
+            let mut improved = false;
+            while alpha > 1e-10 {
+                let candidate = &coefs - &(&step * alpha);
+                let value = loss(&candidate);
+                if value <= current {
+                    coefs = candidate;
+                    improved = current - value > f64::EPSILON * current.abs();
+                    current = value;
+                    break;
+                }
+                alpha *= 0.5;
+            }
+            if !improved {
+                break;
+            }
+        }
+
+        Ok(GatingNetwork {
+            x_mean,
+            x_std,
+            weights: coefs.slice(s![..nx, ..]).to_owned(),
+            intercept: coefs.row(nx).to_owned(),
+            heaviside_factor: 1.,
+        })
+    }
+
+    /// Number of clusters (i.e. experts)
+    pub fn n_clusters(&self) -> usize {
+        self.intercept.len()
+    }
+
+    /// Weights (nx, k) applied to the standardized inputs
+    pub fn weights(&self) -> &Array2<f64> {
+        &self.weights
+    }
+
+    /// Intercepts (k,)
+    pub fn intercept(&self) -> &Array1<f64> {
+        &self.intercept
+    }
+
+    /// Setter for heaviside factor which change the transition between
+    /// clusters in case of smooth recombination
+    pub fn heaviside_factor(mut self, heaviside_factor: f64) -> Self {
+        self.heaviside_factor = heaviside_factor;
+        self
+    }
+
+    /// Gradients (nx, k) of the logits wrt the inputs
+    fn logits_gradients(&self) -> Array2<f64> {
+        &self.weights / &(&self.x_std * self.heaviside_factor).insert_axis(Axis(1))
+    }
+
+    /// Compute the probability of each n x points given as a (n, nx) matrix to belong to a given cluster.
+    pub fn predict_probas(&self, x: &ArrayBase<impl Data<Elem = f64>, Ix2>) -> Array2<f64> {
+        let logits = ((x - &self.x_mean) / &self.x_std).dot(&self.weights) + &self.intercept;
+        softmax(&(logits / self.heaviside_factor))
+    }
+
+    /// Compute the derivatives of the probabilities of x points given as a (m, nx) matrix
+    /// to belong to each cluster.
+    /// Returns a (m, n_clusters, nx) array
+    pub fn predict_probas_derivatives(
+        &self,
+        x: &ArrayBase<impl Data<Elem = f64>, Ix2>,
+    ) -> Array3<f64> {
+        let g = self.logits_gradients();
+        let probas = self.predict_probas(x);
+        let mut deriv = Array3::zeros((x.nrows(), self.n_clusters(), x.ncols()));
+        Zip::from(deriv.outer_iter_mut())
+            .and(probas.rows())
+            .for_each(|mut der, p| {
+                // dp_k/dx = p_k * (g_k - gbar) where gbar = sum_j p_j * g_j
+                let gbar = g.dot(&p);
+                Zip::from(der.rows_mut())
+                    .and(g.columns())
+                    .and(&p)
+                    .for_each(|mut dk, gk, pk| dk.assign(&((&gk - &gbar) * *pk)));
+            });
+        deriv
+    }
+
+    /// Compute the second derivatives of the probabilities of x points given as a (m, nx) matrix
+    /// to belong to each cluster.
+    /// Returns a (m, n_clusters, nx, nx) array
+    pub fn predict_probas_hessians(
+        &self,
+        x: &ArrayBase<impl Data<Elem = f64>, Ix2>,
+    ) -> Array4<f64> {
+        let nx = x.ncols();
+        let g = self.logits_gradients();
+        let probas = self.predict_probas(x);
+        let mut hess = Array4::zeros((x.nrows(), self.n_clusters(), nx, nx));
+        Zip::from(hess.outer_iter_mut())
+            .and(probas.rows())
+            .for_each(|mut h, p| {
+                // d2p_k/dxdx^t = p_k * ((g_k - gbar).(g_k - gbar)^t - cov)
+                // where cov = sum_j p_j * (g_j - gbar).(g_j - gbar)^t
+                let dg = &g - &g.dot(&p).insert_axis(Axis(1));
+                let cov = (&dg * &p).dot(&dg.t());
+                Zip::from(h.outer_iter_mut())
+                    .and(dg.columns())
+                    .and(&p)
+                    .for_each(|mut hk, dgk, pk| {
+                        hk.assign(&Array2::from_shape_fn((nx, nx), |(a, b)| {
+                            *pk * (dgk[a] * dgk[b] - cov[[a, b]])
+                        }));
+                    });
+            });
+        hess
+    }
+}
+
+impl<D: Data<Elem = f64>> PredictInplace<ArrayBase<D, Ix2>, Array1<usize>> for GatingNetwork {
+    fn predict_inplace(&self, x: &ArrayBase<D, Ix2>, targets: &mut Array1<usize>) {
+        assert_eq!(
+            x.nrows(),
+            targets.len(),
+            "The number of data points must match the number of output targets."
+        );
+        *targets = self
+            .predict_probas(x)
+            .map_axis(Axis(1), |row| row.argmax().unwrap_or(0));
+    }
+
+    fn default_target(&self, x: &ArrayBase<D, Ix2>) -> Array1<usize> {
+        Array1::zeros(x.nrows())
+    }
+}
+
+/// Row-wise softmax of the given (n, k) logits
+fn softmax(logits: &Array2<f64>) -> Array2<f64> {
+    let mut probas = logits.to_owned();
+    for mut row in probas.rows_mut() {
+        let amax = *row.max().unwrap();
+        row.mapv_inplace(|v| (v - amax).exp());
+        let sum = row.sum();
+        row /= sum;
+    }
+    probas
+}
+
+/// Solve the linear system `a . x = b` where `a` is symmetric positive definite
+fn solve_spd(a: &Array2<f64>, b: &Array1<f64>) -> Result<Array1<f64>> {
+    let b = b.to_owned().insert_axis(Axis(1));
+    #[cfg(feature = "blas")]
+    let sol = {
+        let chol = a.with_lapack().cholesky(UPLO::Lower)?;
+        let z = chol.solve_triangular(UPLO::Lower, Diag::NonUnit, &b.with_lapack())?;
+        chol.t()
+            .solve_triangular(UPLO::Upper, Diag::NonUnit, &z)?
+            .without_lapack()
+    };
+    #[cfg(not(feature = "blas"))]
+    let sol = {
+        let chol = a.cholesky()?;
+        let z = chol.solve_triangular(&b, UPLO::Lower)?;
+        chol.t().solve_triangular(&z, UPLO::Upper)?
+    };
+    Ok(sol.remove_axis(Axis(1)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+    use linfa::traits::Predict;
+    use ndarray::{Array, array};
+
+    fn gating() -> GatingNetwork {
+        let x = Array::linspace(0., 1., 30).into_shape((15, 2)).unwrap();
+        let clusters = x.map_axis(Axis(1), |r| {
+            if r[0] < 0.3 {
+                0
+            } else if r[1] < 0.7 {
+                1
+            } else {
+                2
+            }
+        });
+        GatingNetwork::fit(&x, &clusters, 3, 1e-3).unwrap()
+    }
+
+    #[test]
+    fn test_gating_fit() {
+        let x = Array::linspace(0., 1., 40).insert_axis(Axis(1));
+        let clusters = x.column(0).mapv(|v| if v < 0.5 { 0 } else { 1 });
+        let gating = GatingNetwork::fit(&x, &clusters, 2, 1e-3).unwrap();
+        assert_eq!(gating.predict(&x), clusters);
+
+        let probas = gating.predict_probas(&array![[0.1], [0.5], [0.9]]);
+        assert_abs_diff_eq!(probas.sum_axis(Axis(1)), Array1::ones(3), epsilon = 1e-12);
+        assert!(probas[[0, 0]] > 0.99 && probas[[2, 1]] > 0.99);
+        assert_abs_diff_eq!(probas[[1, 0]], 0.5, epsilon = 0.1);
+    }
+
+    #[test]
+    fn test_gating_unpenalized_intercept() {
+        // with a strong penalty the weights vanish and the unpenalized intercepts
+        // recover the proportions of the clusters
+        let x = Array::linspace(0., 1., 40).insert_axis(Axis(1));
+        let clusters = x.column(0).mapv(|v| if v < 0.25 { 0 } else { 1 });
+        let gating = GatingNetwork::fit(&x, &clusters, 2, 1e3).unwrap();
+        assert_abs_diff_eq!(gating.weights().sum(), 0., epsilon = 1e-3);
+        let probas = gating.predict_probas(&array![[0.5]]);
+        assert_abs_diff_eq!(probas, array![[0.25, 0.75]], epsilon = 1e-3);
+    }
+
+    #[test]
+    fn test_gating_derivatives() {
+        let gating = gating().heaviside_factor(0.7);
+        let x = array![[0.3, 0.6], [0.5, 0.8]];
+        let deriv = gating.predict_probas_derivatives(&x);
+        let hess = gating.predict_probas_hessians(&x);
+
+        let e = 1e-6;
+        for k in 0..2 {
+            let mut xp = x.to_owned();
+            xp.column_mut(k).mapv_inplace(|v| v + e);
+            let mut xm = x.to_owned();
+            xm.column_mut(k).mapv_inplace(|v| v - e);
+            let fdiff = (gating.predict_probas(&xp) - gating.predict_probas(&xm)) / (2. * e);
+            assert_abs_diff_eq!(fdiff, deriv.slice(s![.., .., k]), epsilon = 1e-6);
+            let fdiff = (gating.predict_probas_derivatives(&xp)
+                - gating.predict_probas_derivatives(&xm))
+                / (2. * e);
+            assert_abs_diff_eq!(fdiff, hess.slice(s![.., .., .., k]), epsilon = 1e-6);
+        }
+    }
+}
@@ -8,7 +8,9 @@ use linfa::{Float, traits::*};
 use linfa::{dataset::WithLapack, dataset::WithoutLapack};
 #[cfg(not(feature = "blas"))]
 use linfa_linalg::{cholesky::*, triangular::*};
-use ndarray::{Array, Array1, Array2, Array3, ArrayBase, Axis, Data, Ix1, Ix2, Ix3, Zip, s};
+use ndarray::{
+    Array, Array1, Array2, Array3, Array4, ArrayBase, Axis, Data, Ix1, Ix2, Ix3, Zip, s,
+};
 #[cfg(feature = "blas")]
 use ndarray_linalg::{cholesky::*, triangular::*};
 use ndarray_stats::QuantileExt;
@@ -96,6 +98,27 @@ impl<F: Float> GaussianMixture<F> {
         &self.covariances
     }
 
+    /// Lower cholesky precisions scaled by the heaviside factor (n, nx, nx) and their
+    /// log determinants (n,) as used to compute cluster responsabilities
+    pub(crate) fn scaled_precisions_chol(&self) -> (Array3<F>, &Array1<F>) {
+        let factor =
+            ndarray_rand::rand_distr::num_traits::Float::powf(self.heaviside_factor, F::cast(-0.5));
+        (&self.precisions_chol * factor, &self.log_det)
+    }
+
+    /// Convert the gaussian mixture to another float type
+    pub fn cast<G: Float>(&self) -> GaussianMixture<G> {
+        GaussianMixture {
+            weights: self.weights.mapv(G::cast),
+            means: self.means.mapv(G::cast),
+            covariances: self.covariances.mapv(G::cast),
+            precisions: self.precisions.mapv(G::cast),
+            precisions_chol: self.precisions_chol.mapv(G::cast),
+            heaviside_factor: G::cast(self.heaviside_factor),
+            log_det: self.log_det.mapv(G::cast),
+        }
+    }
+
     /// Setter for heaviside factor which change the transition between
     /// clusters in case of smooth recombination
     pub fn heaviside_factor(mut self, heaviside_factor: F) -> Self {
@@ -171,6 +194,83 @@ impl<F: Float> GaussianMixture<F> {
         self.compute_log_gaussian_prob(&xx).row(0).mapv(|v| v.exp())
     }
 
+    /// Compute the second derivatives of the probabilities of x to belong to each cluster
+    /// Returns a (n_clusters, nx, nx) array
+    pub fn predict_single_probas_hessians<D: Data<Elem = F>>(
+        &self,
+        x: &ArrayBase<D, Ix1>,
+    ) -> Array3<F> {
+        let nx = x.len();
+        let u = self.weights.to_owned() * self.pdfs(x);
+        let v = u.sum();
+        let p = u.mapv(|ui| ui / v);
+        let precs = &self.precisions / self.heaviside_factor;
+
+        // g_k = prec_k . (x - mu_k), dp_k/dx = p_k * (gbar - g_k) where gbar = sum_j p_j * g_j
+        let mut g = Array2::zeros((self.means.nrows(), nx));
+        Zip::from(g.rows_mut())
+            .and(self.means.rows())
+            .and(precs.outer_iter())
+            .for_each(|mut gk, mu, prec| {
+                gk.assign(&(&x.to_owned() - &mu).dot(&prec));
+            });
+        let gbar = g.t().dot(&p);
+
+        // common = gbar . gbar^t - sum_j p_j * g_j . g_j^t + sum_j p_j * prec_j
+        let mut common = Array2::from_shape_fn((nx, nx), |(a, b)| gbar[a] * gbar[b]);
+        Zip::from(g.rows())
+            .and(&p)
+            .and(precs.outer_iter())
+            .for_each(|gj, pj, prec| {
+                common.zip_mut_with(&prec, |c, pr| *c += *pj * *pr);
+                for a in 0..nx {
+                    for b in 0..nx {
+                        common[[a, b]] -= *pj * gj[a] * gj[b];
+                    }
+                }
+            });
+
+        // d2p_k/dxadxb = p_k * ((gbar - g_k)_a * (gbar - g_k)_b + common - prec_k)
+        let mut hess = Array3::zeros((self.means.nrows(), nx, nx));
+        Zip::from(hess.outer_iter_mut())
+            .and(g.rows())
+            .and(&p)
+            .and(precs.outer_iter())
+            .for_each(|mut hk, gk, pk, prec| {
+                let dg = &gbar - &gk;
+                hk.assign(&Array2::from_shape_fn((nx, nx), |(a, b)| {
+                    *pk * (dg[a] * dg[b] + common[[a, b]] - prec[[a, b]])
+                }));
+            });
+        hess
+    }
+
+    /// Compute the second derivatives of the probabilities of x points to belong to each cluster
+    /// Returns a (n, n_clusters, nx, nx) array
+    pub fn predict_probas_hessians<D: Data<Elem = F>>(&self, x: &ArrayBase<D, Ix2>) -> Array4<F> {
+        let mut hess = Array4::zeros((x.nrows(), self.means.nrows(), x.ncols(), x.ncols()));
+        Zip::from(hess.outer_iter_mut())
+            .and(x.rows())
+            .for_each(|mut h, xi| {
+                h.assign(&self.predict_single_probas_hessians(&xi));
+            });
+        hess
+    }
+
+    /// Compute the Mahalanobis distances of the n x points given as a (n, nx) matrix
+    /// to each multivariate normal distribution. Returns a (n, n_clusters) matrix.
+    pub fn mahalanobis_distances<D: Data<Elem = F>>(&self, x: &ArrayBase<D, Ix2>) -> Array2<F> {
+        let mut dists = Array2::zeros((x.nrows(), self.n_clusters()));
+        Zip::from(dists.columns_mut())
+            .and(self.means.rows())
+            .and(self.precisions_chol.outer_iter())
+            .for_each(|mut d, mu, prec_chol| {
+                let diff = (x - &mu).dot(&prec_chol);
+                d.assign(&diff.mapv(|v| v * v).sum_axis(Axis(1)).mapv(|v| v.sqrt()));
+            });
+        dists
+    }
+
     /// Compute precision matrices cholesky decomposiotions given the covariance matrices of
     /// the n multivariate normal distributions specified as a (n, nx, nx) ndarray where
     /// nx is the multivariate dimension.
@@ -391,4 +491,28 @@ mod tests {
             array![-1., 2.],
         )
     }
+
+    #[test]
+    fn test_probas_hessians() {
+        let weights = array![0.3, 0.7];
+        let means = array![[0., 0.], [2., 1.]];
+        let covs = array![[[1.5, 0.2], [0.2, 1.]], [[1., -0.3], [-0.3, 2.]]];
+        let gmix = GaussianMixture::new(weights, means, covs)
+            .unwrap()
+            .heaviside_factor(0.8);
+        let x = array![0.7, 0.4];
+        let hess = gmix.predict_single_probas_hessians(&x);
+
+        let e = 1e-6;
+        for k in 0..2 {
+            let mut xp = x.to_owned();
+            xp[k] += e;
+            let mut xm = x.to_owned();
+            xm[k] -= e;
+            let fdiff = (gmix.predict_single_probas_derivatives(&xp)
+                - gmix.predict_single_probas_derivatives(&xm))
+            .mapv(|v| v / (2. * e));
+            assert_abs_diff_eq!(fdiff, hess.slice(s![.., .., k]), epsilon = 1e-6);
+        }
+    }
 }
@@ -20,12 +20,33 @@
 //!
 //! * Clusters are defined by clustering the training data with
 //!   [linfa-clustering](https://docs.rs/linfa-clustering/latest/linfa_clustering/)
-//!   gaussian mixture model.
+//!   gaussian mixture model. Alternatively k-means, agglomerative clustering or
+//!   regression tree partitioning of the input space can be used (see [`ClusteringMethod`]).
+//! * Experts responsabilities are given by the gaussian mixture in the input space or
+//!   by a classifier trained on the clusters (see [`GatingMethod`]).
+//! * Experts can be GPs of different types or non GP experts (polynomials, RBF interpolants)
+//!   chosen per cluster explicitly or wrt cluster size (see [`ExpertSelection`]).
+//! * User-defined experts can be registered to compete with GP experts in the expert selection
+//!   (see [`ExpertPlugin`]).
+//! * Experts are trained in parallel within a configurable thread pool (see [`Parallelism`]).
+//! * Vector-valued outputs can be handled by mixtures sharing the same clustering
+//!   (see [`MultiGpMixture`]).
+//! * A trained mixture can be updated with new samples refitting only the impacted experts,
+//!   re-clustering being triggered on data drift (see [`GpMixture::update`]).
 //! * This library is a port of the
 //!   [SMT MoE method](https://smt.readthedocs.io/en/latest/_src_docs/applications/moe.html)
 //!   using egobox GP models as experts.
 //! * It leverages on the egobox GP PLS reduction feature to handle high dimensional problems.
+//! * Experts can be trained in single precision using `GpMixtureParams::<f32>` to get
+//!   a [`GpMixtureF32`] model storing its training data and expert parameters as `f32`.
+//!   Its `predict` and `predict_var` methods (as well as [`GpSurrogate::predict_f32`]) use
+//!   the single precision experts without conversion, while surrogate traits keep a double
+//!   precision interface converting inputs and outputs of predictions with copies.
 //! * MoE trained model can be save to disk and reloaded. See
+//! * MoE trained model can be inspected through a per-cluster diagnostics report
+//!   including experts responsabilities on a user grid (see [`MixtureReport`]).
+//! * MoE trained model made of GP experts can be exported as a self-contained set of
+//!   coefficients (see [`GpMixtureCoefficients`]) to be evaluated without linking Rust.
 //!  
 //! # Features
 //!
@@ -86,9 +107,15 @@
 //! Structural and multidisciplinary optimization 43.2 (2011): 243-259.
 //!
 mod clustering;
+mod diagnostics;
 mod errors;
 mod expertise_macros;
+mod experts;
+mod export;
+mod gating;
 mod gaussian_mixture;
+mod multi_output;
+mod partitioning;
 mod surrogates;
 mod types;
 
@@ -96,8 +123,14 @@ mod algorithm;
 mod parameters;
 
 pub use clustering::*;
+pub use diagnostics::*;
 pub use errors::*;
+pub use experts::{ExpertPlugin, PolynomialSurrogate, RbfSurrogate};
+pub use export::*;
+pub use gating::GatingNetwork;
 pub use gaussian_mixture::*;
+pub use multi_output::MultiGpMixture;
+pub use partitioning::{CentroidPartition, Partition, TreePartition};
 pub use surrogates::*;
 pub use types::*;
 
@@ -0,0 +1,160 @@
+//! Mixture of experts with vector-valued outputs.
+//!
+//! A mixture of experts is trained for each output, all the mixtures sharing the same
+//! clustering of the training data (computed once wrt all the outputs) so that the
+//! regions of the input space are consistent across outputs.
+
+use crate::algorithm::GpMixture;
+use crate::errors::Result;
+use crate::surrogates::{GpSurrogate, GpSurrogateExt, SurrogateFloat};
+use crate::types::{Clustered, Clustering, Recombination};
+
+use linfa::Float;
+use ndarray::{Array2, ArrayBase, Axis, Data, Ix2, stack};
+
+#[cfg(feature = "serializable")]
+use serde::{Deserialize, Serialize};
+
+/// Mixture of gaussian process experts with `ny` outputs
+/// (see [GpMixtureValidParams::train_multi](crate::GpMixtureValidParams::train_multi))
+#[cfg_attr(feature = "serializable", derive(Serialize, Deserialize))]
+pub struct MultiGpMixture<F: Float = f64> {
+    /// Mixtures of each output sharing the same clustering
+    mixtures: Vec<GpMixture<F>>,
+}
+
+impl<F: Float> MultiGpMixture<F> {
+    pub(crate) fn new(mixtures: Vec<GpMixture<F>>) -> Self {
+        MultiGpMixture { mixtures }
+    }
+
+    /// Number of outputs
+    pub fn n_outputs(&self) -> usize {
+        self.mixtures.len()
+    }
+
+    /// Mixtures of experts of each output
+    pub fn outputs(&self) -> &[GpMixture<F>] {
+        &self.mixtures
+    }
+
+    /// Consume the multi-output mixture to get the mixture of experts of each output
+    pub fn into_outputs(self) -> Vec<GpMixture<F>> {
+        self.mixtures
+    }
+}
+
+impl<F: Float> Clustered for MultiGpMixture<F> {
+    /// Number of clusters
+    fn n_clusters(&self) -> usize {
+        self.mixtures[0].n_clusters()
+    }
+
+    /// Clustering Recombination
+    fn recombination(&self) -> Recombination<f64> {
+        self.mixtures[0].recombination()
+    }
+
+    /// Convert to the clustering shared by the outputs
+    fn to_clustering(&self) -> Clustering {
+        self.mixtures[0].to_clustering()
+    }
+}
+
+impl<F: SurrogateFloat> MultiGpMixture<F>
+where
+    GpMixture<F>: GpSurrogate + GpSurrogateExt,
+{
+    /// Predict the outputs at `x` points given as a (n, nx) matrix.
+    /// Returns a (n, ny) matrix
+    pub fn predict(&self, x: &ArrayBase<impl Data<Elem = F>, Ix2>) -> Result<Array2<F>> {
+        let preds = self
+            .mixtures
+            .iter()
+            .map(|moe| moe.predict(x))
+            .collect::<Result<Vec<_>>>()?;
+        let views: Vec<_> = preds.iter().map(|p| p.view()).collect();
+        Ok(stack(Axis(1), &views).unwrap())
+    }
+
+    /// Predict the variances of the outputs at `x` points given as a (n, nx) matrix.
+    /// Returns a (n, ny) matrix
+    pub fn predict_var(&self, x: &ArrayBase<impl Data<Elem = F>, Ix2>) -> Result<Array2<F>> {
+        let vars = self
+            .mixtures
+            .iter()
+            .map(|moe| moe.predict_var(x))
+            .collect::<Result<Vec<_>>>()?;
+        let views: Vec<_> = vars.iter().map(|v| v.view()).collect();
+        Ok(stack(Axis(1), &views).unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        GpMixtureParams, MixtureGpSurrogate, MultiOutputExperts, NbClusters, Recombination,
+    };
+    use approx::{assert_abs_diff_eq, assert_relative_eq};
+    use egobox_doe::{Lhs, SamplingMethod};
+    use linfa::ParamGuard;
+    use ndarray::{Array1, array};
+    use ndarray_rand::rand::SeedableRng;
+    use rand_xoshiro::Xoshiro256Plus;
+
+    fn f_multi(x: &Array2<f64>) -> Array2<f64> {
+        let y1 = x
+            .column(0)
+            .mapv(|v| if v < 0.5 { v * v } else { (10. * v).sin() });
+        let y2: Array1<f64> = x.column(0).mapv(|v| 100. * (2. * v + 1.));
+        stack(Axis(1), &[y1.view(), y2.view()]).unwrap()
+    }
+
+    #[test]
+    fn test_multi_output_moe() {
+        let xlimits = array![[0., 1.]];
+        let xt = Lhs::new(&xlimits)
+            .with_rng(Xoshiro256Plus::seed_from_u64(0))
+            .sample(40);
+        let yt = f_multi(&xt);
+        let xtest = Array1::linspace(0.05, 0.95, 10).insert_axis(Axis(1));
+
+        for multi_output_experts in [
+            MultiOutputExperts::Independent,
+            MultiOutputExperts::SharedHyperparameters,
+        ] {
+            let moe = GpMixtureParams::new()
+                .n_clusters(NbClusters::fixed(2))
+                .recombination(Recombination::Hard)
+                .multi_output_experts(multi_output_experts)
+                .with_rng(Xoshiro256Plus::seed_from_u64(42))
+                .check_unwrap()
+                .train_multi(&xt, &yt)
+                .expect("Multi-output MoE fitted");
+            assert_eq!(moe.n_outputs(), 2);
+            // same clustering for all outputs
+            let clusters = moe.outputs()[0].predict_clusters(&xtest);
+            assert_eq!(moe.outputs()[1].predict_clusters(&xtest), clusters);
+            if multi_output_experts == MultiOutputExperts::SharedHyperparameters {
+                for (e0, e1) in moe.outputs()[0]
+                    .experts()
+                    .iter()
+                    .zip(moe.outputs()[1].experts())
+                {
+                    assert_abs_diff_eq!(e0.theta(), e1.theta());
+                }
+            }
+            let preds = moe.predict(&xtest).expect("Multi-output prediction");
+            assert_eq!(preds.dim(), (10, 2));
+            let expected = f_multi(&xtest);
+            if multi_output_experts == MultiOutputExperts::Independent {
+                assert_abs_diff_eq!(preds.column(1), expected.column(1), epsilon = 1e-3);
+            } else {
+                // hyperparameters tuned wrt the first output
+                assert_relative_eq!(preds.column(1), expected.column(1), max_relative = 5e-2);
+            }
+            assert_eq!(moe.predict_var(&xtest).unwrap().dim(), (10, 2));
+        }
+    }
+}
@@ -1,5 +1,7 @@
 use crate::errors::{MoeError, Result};
+use crate::experts::{ExpertPlugin, PolynomialSurrogate};
 use crate::gaussian_mixture::GaussianMixture;
+use crate::surrogates::GpSurrogateParams;
 use crate::types::*;
 
 use egobox_gp::GP_COBYLA_MAX_EVAL;
@@ -18,7 +20,7 @@ use rand_xoshiro::Xoshiro256Plus;
 #[cfg(feature = "serializable")]
 use serde::{Deserialize, Serialize};
 
-pub use egobox_gp::{Inducings, SparseMethod, ThetaTuning};
+pub use egobox_gp::{Inducings, RobustLikelihood, SparseMethod, ThetaTuning};
 
 #[derive(Clone, Debug, PartialEq)]
 #[cfg_attr(feature = "serializable", derive(Serialize, Deserialize))]
@@ -77,7 +79,7 @@ impl NbClusters {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug)]
 #[cfg_attr(feature = "serializable", derive(Serialize, Deserialize))]
 pub enum GpType<F: Float> {
     FullGp,
@@ -87,6 +89,134 @@ pub enum GpType<F: Float> {
         /// Inducings
         inducings: Inducings<F>,
     },
+    /// GP robust to outliers
+    RobustGp {
+        /// Likelihood used to downweight outliers
+        likelihood: RobustLikelihood<F>,
+    },
+    /// Fully Bayesian GP marginalising hyperparameters over posterior samples
+    BayesianGp {
+        /// Number of hyperparameters posterior samples
+        n_samples: usize,
+        /// Bounds of the noise to process variance ratio, `None` means interpolating model
+        noise_bounds: Option<(F, F)>,
+    },
+}
+
+impl<F: Float> GpType<F> {
+    /// Convert GP type specification to another float type
+    pub fn cast<G: Float>(&self) -> GpType<G> {
+        match self {
+            GpType::FullGp => GpType::FullGp,
+            GpType::SparseGp {
+                sparse_method,
+                inducings,
+            } => GpType::SparseGp {
+                sparse_method: *sparse_method,
+                inducings: inducings.cast(),
+            },
+            GpType::RobustGp { likelihood } => GpType::RobustGp {
+                likelihood: likelihood.cast(),
+            },
+            GpType::BayesianGp {
+                n_samples,
+                noise_bounds,
+            } => GpType::BayesianGp {
+                n_samples: *n_samples,
+                noise_bounds: noise_bounds.map(|(lo, up)| (G::cast(lo), G::cast(up))),
+            },
+        }
+    }
+}
+
+/// Type of the expert trained on a cluster
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serializable", derive(Serialize, Deserialize))]
+pub enum ExpertType<F: Float> {
+    /// Gaussian process of the given type, regression and correlation models
+    /// are selected wrt the mixture specifications
+    Gp(GpType<F>),
+    /// Polynomial response surface of the given degree (1 or 2), see [PolynomialSurrogate](crate::PolynomialSurrogate)
+    Polynomial { degree: usize },
+    /// Cubic RBF interpolant, see [RbfSurrogate](crate::RbfSurrogate)
+    Rbf,
+    /// User-defined expert registered with the given name, see [ExpertPlugin]
+    Plugin(String),
+}
+
+impl<F: Float> ExpertType<F> {
+    /// Convert expert type specification to another float type
+    pub fn cast<G: Float>(&self) -> ExpertType<G> {
+        match self {
+            ExpertType::Gp(gp_type) => ExpertType::Gp(gp_type.cast()),
+            ExpertType::Polynomial { degree } => ExpertType::Polynomial { degree: *degree },
+            ExpertType::Rbf => ExpertType::Rbf,
+            ExpertType::Plugin(name) => ExpertType::Plugin(name.clone()),
+        }
+    }
+}
+
+/// Selection of the type of expert trained on each cluster
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serializable", derive(Serialize, Deserialize))]
+pub enum ExpertSelection<F: Float> {
+    /// All experts are GPs of the mixture [GpType]
+    #[default]
+    Uniform,
+    /// Expert types given explicitly for each cluster (requires a fixed number of clusters)
+    PerCluster(Vec<ExpertType<F>>),
+    /// Expert type chosen wrt the number of points `n` of the cluster:
+    /// a polynomial when `n < min_gp_size` (quadratic if enough points, linear otherwise),
+    /// a sparse GP with given `inducings` when `n > max_full_gp_size`
+    /// and a GP of the mixture [GpType] otherwise.
+    BySize {
+        min_gp_size: usize,
+        max_full_gp_size: usize,
+        inducings: Inducings<F>,
+    },
+}
+
+impl<F: Float> ExpertSelection<F> {
+    /// Convert expert selection specification to another float type
+    pub fn cast<G: Float>(&self) -> ExpertSelection<G> {
+        match self {
+            ExpertSelection::Uniform => ExpertSelection::Uniform,
+            ExpertSelection::PerCluster(types) => {
+                ExpertSelection::PerCluster(types.iter().map(|t| t.cast()).collect())
+            }
+            ExpertSelection::BySize {
+                min_gp_size,
+                max_full_gp_size,
+                inducings,
+            } => ExpertSelection::BySize {
+                min_gp_size: *min_gp_size,
+                max_full_gp_size: *max_full_gp_size,
+                inducings: inducings.cast(),
+            },
+        }
+    }
+}
+
+/// Automatic selection of the number of PLS components of each expert
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serializable", derive(Serialize, Deserialize))]
+pub enum KplsSelection {
+    /// Smallest number of PLS components (at most `max_dim`) explaining
+    /// at least the `threshold` ratio (in ]0, 1]) of the output variance
+    ExplainedVariance { threshold: f64, max_dim: usize },
+    /// Number of PLS components in `1..=max_dim` giving the best expert wrt the
+    /// selection criterion of the mixture (see [SelectionCriterion])
+    Criterion { max_dim: usize },
+}
+
+impl KplsSelection {
+    /// The max number of PLS components
+    pub fn max_dim(&self) -> usize {
+        match self {
+            KplsSelection::ExplainedVariance { max_dim, .. }
+            | KplsSelection::Criterion { max_dim } => *max_dim,
+        }
+    }
 }
 
 /// Mixture of experts checked parameters
@@ -100,15 +230,39 @@ pub struct GpMixtureValidParams<F: Float> {
     n_clusters: NbClusters,
     /// [Recombination] mode
     recombination: Recombination<F>,
+    /// [ClusteringMethod] used to partition training data
+    #[cfg_attr(feature = "serializable", serde(default))]
+    clustering_method: ClusteringMethod,
+    /// [GatingMethod] used to compute experts responsabilities
+    #[cfg_attr(feature = "serializable", serde(default))]
+    gating_method: GatingMethod,
+    /// [MixtureVariance] computation mode
+    #[cfg_attr(feature = "serializable", serde(default))]
+    mixture_variance: MixtureVariance,
+    /// [ExpertSelection] of the type of each expert
+    #[cfg_attr(feature = "serializable", serde(default))]
+    expert_selection: ExpertSelection<F>,
     /// Specification of GP regression models to be used
     regression_spec: RegressionSpec,
     /// Specification of GP correlation models to be used
     correlation_spec: CorrelationSpec,
+    /// Criterion used to select the best expert among allowed models
+    #[cfg_attr(feature = "serializable", serde(default))]
+    selection_criterion: SelectionCriterion,
+    /// Whether clearly inferior correlation models are pruned early during selection
+    #[cfg_attr(feature = "serializable", serde(default))]
+    early_pruning: bool,
     /// Theta hyperparameter tuning
     theta_tunings: Vec<ThetaTuning<F>>,
     /// Number of PLS components, should be used when problem size
     /// is over ten variables or so.
     kpls_dim: Option<usize>,
+    /// Automatic selection of the number of PLS components of each expert
+    #[cfg_attr(feature = "serializable", serde(default))]
+    kpls_selection: Option<KplsSelection>,
+    /// Number of PLS components of each expert (overrides `kpls_dim` and `kpls_selection`)
+    #[cfg_attr(feature = "serializable", serde(default))]
+    kpls_dims: Vec<Option<usize>>,
     /// Number of GP hyperparameters optimization restarts
     n_start: usize,
     /// Max number of likelihood evaluations during GP hyperparameters optimization
@@ -117,6 +271,21 @@ pub struct GpMixtureValidParams<F: Float> {
     gmm: Option<GaussianMixtureModel<F>>,
     /// GaussianMixture preset
     gmx: Option<GaussianMixture<F>>,
+    /// Experts of the mixtures trained for each output of a multi-output mixture
+    #[cfg_attr(feature = "serializable", serde(default))]
+    multi_output_experts: MultiOutputExperts,
+    /// Criterion triggering the re-clustering when the mixture is updated with new samples
+    #[cfg_attr(feature = "serializable", serde(default))]
+    drift_criterion: DriftCriterion,
+    /// Whether experts refitted on update keep their hyperparameters
+    #[cfg_attr(feature = "serializable", serde(default))]
+    keep_hyperparameters: bool,
+    /// Parallelism policy used to train the experts
+    #[cfg_attr(feature = "serializable", serde(skip))]
+    parallelism: Parallelism,
+    /// User-defined experts competing with GP experts during selection
+    #[cfg_attr(feature = "serializable", serde(skip))]
+    expert_plugins: Vec<ExpertPlugin>,
     /// Random number generator
     rng: Xoshiro256Plus,
 }
@@ -127,14 +296,27 @@ impl<F: Float> Default for GpMixtureValidParams<F> {
             gp_type: GpType::FullGp,
             n_clusters: NbClusters::default(),
             recombination: Recombination::Hard,
+            clustering_method: ClusteringMethod::default(),
+            gating_method: GatingMethod::default(),
+            mixture_variance: MixtureVariance::default(),
+            expert_selection: ExpertSelection::default(),
             regression_spec: RegressionSpec::CONSTANT,
             correlation_spec: CorrelationSpec::SQUAREDEXPONENTIAL,
+            selection_criterion: SelectionCriterion::default(),
+            early_pruning: false,
             theta_tunings: vec![ThetaTuning::default()],
             kpls_dim: None,
+            kpls_selection: None,
+            kpls_dims: vec![],
             n_start: 10,
             max_eval: GP_COBYLA_MAX_EVAL,
             gmm: None,
             gmx: None,
+            multi_output_experts: MultiOutputExperts::default(),
+            drift_criterion: DriftCriterion::default(),
+            keep_hyperparameters: false,
+            parallelism: Parallelism::default(),
+            expert_plugins: vec![],
             rng: Xoshiro256Plus::from_entropy(),
         }
     }
@@ -156,6 +338,56 @@ impl<F: Float> GpMixtureValidParams<F> {
         self.recombination
     }
 
+    /// The clustering method used to partition training data
+    pub fn clustering_method(&self) -> ClusteringMethod {
+        self.clustering_method
+    }
+
+    /// The gating method used to compute experts responsabilities
+    pub fn gating_method(&self) -> GatingMethod {
+        self.gating_method
+    }
+
+    /// The variance computation mode of the mixture
+    pub fn mixture_variance(&self) -> MixtureVariance {
+        self.mixture_variance
+    }
+
+    /// The selection of the type of each expert
+    pub fn expert_selection(&self) -> &ExpertSelection<F> {
+        &self.expert_selection
+    }
+
+    /// The type of the expert of the `nc`th cluster made of `n` points in dimension `nx`
+    pub fn expert_type(&self, nc: usize, n: usize, nx: usize) -> ExpertType<F> {
+        match &self.expert_selection {
+            ExpertSelection::Uniform => ExpertType::Gp(self.gp_type.clone()),
+            ExpertSelection::PerCluster(types) => types[nc].clone(),
+            ExpertSelection::BySize {
+                min_gp_size,
+                max_full_gp_size,
+                inducings,
+            } => {
+                if n < *min_gp_size {
+                    // keep some degrees of freedom to estimate the variance
+                    let degree = if n > PolynomialSurrogate::n_coefficients(2, nx) {
+                        2
+                    } else {
+                        1
+                    };
+                    ExpertType::Polynomial { degree }
+                } else if n > *max_full_gp_size {
+                    ExpertType::Gp(GpType::SparseGp {
+                        sparse_method: SparseMethod::default(),
+                        inducings: inducings.clone(),
+                    })
+                } else {
+                    ExpertType::Gp(self.gp_type.clone())
+                }
+            }
+        }
+    }
+
     /// The allowed GP regression models in the mixture
     pub fn regression_spec(&self) -> RegressionSpec {
         self.regression_spec
@@ -166,6 +398,16 @@ impl<F: Float> GpMixtureValidParams<F> {
         self.correlation_spec
     }
 
+    /// The criterion used to select the best expert
+    pub fn selection_criterion(&self) -> SelectionCriterion {
+        self.selection_criterion
+    }
+
+    /// Whether early pruning is used during experts selection
+    pub fn early_pruning(&self) -> bool {
+        self.early_pruning
+    }
+
     /// The speified tuning of theta hyperparameter
     pub fn theta_tunings(&self) -> &Vec<ThetaTuning<F>> {
         &self.theta_tunings
@@ -176,6 +418,16 @@ impl<F: Float> GpMixtureValidParams<F> {
         self.kpls_dim
     }
 
+    /// The optional automatic selection of the number of PLS components
+    pub fn kpls_selection(&self) -> Option<KplsSelection> {
+        self.kpls_selection
+    }
+
+    /// The number of PLS components of each expert if specified (empty otherwise)
+    pub fn kpls_dims(&self) -> &[Option<usize>] {
+        &self.kpls_dims
+    }
+
     /// The number of hyperparameters optimization restarts
     pub fn n_start(&self) -> usize {
         self.n_start
@@ -197,6 +449,53 @@ impl<F: Float> GpMixtureValidParams<F> {
         self.gmx.as_ref()
     }
 
+    /// The experts of the mixtures trained for each output of a multi-output mixture
+    pub fn multi_output_experts(&self) -> MultiOutputExperts {
+        self.multi_output_experts
+    }
+
+    /// The criterion triggering the re-clustering on update
+    pub fn drift_criterion(&self) -> DriftCriterion {
+        self.drift_criterion
+    }
+
+    /// Whether experts refitted on update keep their hyperparameters
+    pub fn keep_hyperparameters(&self) -> bool {
+        self.keep_hyperparameters
+    }
+
+    /// The parallelism policy used to train the experts
+    pub fn parallelism(&self) -> &Parallelism {
+        &self.parallelism
+    }
+
+    /// The user-defined experts registered in the mixture
+    pub fn expert_plugins(&self) -> &[ExpertPlugin] {
+        &self.expert_plugins
+    }
+
+    /// The user-defined expert registered with the given name if any
+    pub fn expert_plugin(&self, name: &str) -> Option<&ExpertPlugin> {
+        self.expert_plugins.iter().find(|p| p.name() == name)
+    }
+
+    /// The user-defined expert registered with the given name or an error
+    /// telling to register it (plugins are not saved with the mixture)
+    pub(crate) fn registered_plugin(&self, name: &str) -> Result<&ExpertPlugin> {
+        self.expert_plugin(name).ok_or_else(|| {
+            MoeError::InvalidValueError(format!(
+                "Expert plugin '{name}' is not registered: plugins are not saved with the mixture \
+                 and should be registered again (see GpMixture::register_expert)"
+            ))
+        })
+    }
+
+    /// Registers the given plugin, replacing the one registered with the same name if any
+    pub(crate) fn add_expert_plugin(&mut self, plugin: ExpertPlugin) {
+        self.expert_plugins.retain(|p| p.name() != plugin.name());
+        self.expert_plugins.push(plugin);
+    }
+
     /// The random generator
     pub fn rng(&self) -> Xoshiro256Plus {
         self.rng.clone()
@@ -252,6 +551,43 @@ impl<F: Float> GpMixtureParams<F> {
         self
     }
 
+    /// Sets the clustering method used to partition training data among experts.
+    ///
+    /// Methods other than [ClusteringMethod::Gmm] require a fixed number of clusters,
+    /// with [ClusteringMethod::Tree] the recombination is always hard as well as with
+    /// [ClusteringMethod::KMeans] and [ClusteringMethod::Agglomerative] without gating network.
+    pub fn clustering_method(mut self, clustering_method: ClusteringMethod) -> Self {
+        self.0.clustering_method = clustering_method;
+        self
+    }
+
+    /// Sets the gating method used to compute experts responsabilities.
+    ///
+    /// With [GatingMethod::LogisticRegression] a classifier trained on the clusters
+    /// replaces the gaussian mixture (or the nearest centroid partition with
+    /// [ClusteringMethod::KMeans] and [ClusteringMethod::Agglomerative]) to predict
+    /// the responsabilities, it does not apply to experts gated by [ClusteringMethod::Tree] regions.
+    pub fn gating_method(mut self, gating_method: GatingMethod) -> Self {
+        self.0.gating_method = gating_method;
+        self
+    }
+
+    /// Sets the variance computation mode used with smooth recombination.
+    ///
+    /// [MixtureVariance::TotalVariance] accounts for the disagreement between experts
+    /// which avoids overconfident predictions at the transitions between clusters.
+    pub fn mixture_variance(mut self, mixture_variance: MixtureVariance) -> Self {
+        self.0.mixture_variance = mixture_variance;
+        self
+    }
+
+    /// Sets the selection of the type of each expert, allowing to mix GPs of different types
+    /// and non GP experts (polynomials or RBF interpolants) in the mixture.
+    pub fn expert_selection(mut self, expert_selection: ExpertSelection<F>) -> Self {
+        self.0.expert_selection = expert_selection;
+        self
+    }
+
     /// Sets the regression models used in the mixture.
     ///
     /// Only GP models with regression models allowed by this specification
@@ -270,12 +606,47 @@ impl<F: Float> GpMixtureParams<F> {
         self
     }
 
+    /// Sets the criterion used to select the best expert among the allowed
+    /// regression and correlation models (default to cross-validation).
+    ///
+    /// Criteria other than [SelectionCriterion::CrossValidation] require only one GP fit
+    /// per tested model.
+    pub fn selection_criterion(mut self, selection_criterion: SelectionCriterion) -> Self {
+        self.0.selection_criterion = selection_criterion;
+        self
+    }
+
+    /// Sets early pruning during experts selection: correlation models are first scored
+    /// with the simplest allowed regression model and only the ones which are not clearly
+    /// inferior (see [SelectionCriterion]) are tested with the other regression models.
+    pub fn early_pruning(mut self, early_pruning: bool) -> Self {
+        self.0.early_pruning = early_pruning;
+        self
+    }
+
     /// Sets the number of componenets retained during PLS dimension reduction.
     pub fn kpls_dim(mut self, kpls_dim: Option<usize>) -> Self {
         self.0.kpls_dim = kpls_dim;
         self
     }
 
+    /// Sets the automatic selection of the number of PLS components of each expert.
+    ///
+    /// When set, it takes precedence over `kpls_dim`.
+    pub fn kpls_selection(mut self, kpls_selection: Option<KplsSelection>) -> Self {
+        self.0.kpls_selection = kpls_selection;
+        self
+    }
+
+    /// Sets the number of PLS components of each expert (`None` meaning no dimension reduction).
+    ///
+    /// It requires a fixed number of clusters equal to `kpls_dims` length and takes
+    /// precedence over `kpls_dim` and `kpls_selection`. An empty slice removes the overrides.
+    pub fn kpls_dims(mut self, kpls_dims: &[Option<usize>]) -> Self {
+        self.0.kpls_dims = kpls_dims.to_vec();
+        self
+    }
+
     /// Set theta hyper parameter tuning
     pub fn theta_tunings(mut self, theta_tunings: &[ThetaTuning<F>]) -> Self {
         self.0.theta_tunings = theta_tunings.to_vec();
@@ -310,6 +681,51 @@ impl<F: Float> GpMixtureParams<F> {
         self
     }
 
+    /// Sets how experts are trained for each output of a multi-output mixture
+    /// (see [GpMixtureValidParams::train_multi]): either independently or sharing
+    /// the models and hyperparameters of the experts trained for the first output.
+    pub fn multi_output_experts(mut self, multi_output_experts: MultiOutputExperts) -> Self {
+        self.0.multi_output_experts = multi_output_experts;
+        self
+    }
+
+    /// Sets the criterion used to detect a drift of the data when the trained mixture
+    /// is updated with new samples (see [GpMixture::update](crate::GpMixture::update)).
+    pub fn drift_criterion(mut self, drift_criterion: DriftCriterion) -> Self {
+        self.0.drift_criterion = drift_criterion;
+        self
+    }
+
+    /// Sets whether experts refitted when the trained mixture is updated with new samples
+    /// keep their selected model and hyperparameters (no expert selection and no likelihood
+    /// optimization) or are selected and trained again on their cluster data (default).
+    pub fn keep_hyperparameters(mut self, keep_hyperparameters: bool) -> Self {
+        self.0.keep_hyperparameters = keep_hyperparameters;
+        self
+    }
+
+    /// Sets the parallelism policy used to train the experts: clusters, tested
+    /// regression x correlation models and hyperparameters optimization restarts
+    /// are processed within the specified thread pool.
+    pub fn parallelism(mut self, parallelism: Parallelism) -> Self {
+        self.0.parallelism = parallelism;
+        self
+    }
+
+    /// Registers a user-defined expert under the given `name` trained by the given `factory`
+    /// (see [ExpertPlugin]).
+    ///
+    /// Registered experts compete with the allowed GP models in the expert selection of the
+    /// clusters with GP experts and can be assigned explicitly with [ExpertType::Plugin].
+    pub fn register_expert(
+        mut self,
+        name: &str,
+        factory: impl GpSurrogateParams + Send + Sync + 'static,
+    ) -> Self {
+        self.0.expert_plugins.push(ExpertPlugin::new(name, factory));
+        self
+    }
+
     /// Sets the random number generator for reproducibility
     pub fn with_rng(mut self, rng: Xoshiro256Plus) -> GpMixtureParams<F> {
         self.0.rng = rng;
@@ -317,6 +733,20 @@ impl<F: Float> GpMixtureParams<F> {
     }
 }
 
+/// Whether the name is the name `<Regression>_<Correlation>` of a GP expert
+pub(crate) fn is_builtin_expert_name(name: &str) -> bool {
+    ["Constant", "Linear", "Quadratic"].iter().any(|regr| {
+        [
+            "SquaredExponential",
+            "AbsoluteExponential",
+            "Matern32",
+            "Matern52",
+        ]
+        .iter()
+        .any(|corr| name == format!("{regr}_{corr}"))
+    })
+}
+
 impl<F: Float> ParamGuard for GpMixtureParams<F> {
     type Checked = GpMixtureValidParams<F>;
     type Error = MoeError;
@@ -329,6 +759,128 @@ impl<F: Float> ParamGuard for GpMixtureParams<F> {
                 "`kpls_dim` canot be 0!".to_string(),
             ));
         }
+        if !self.0.kpls_dims.is_empty() {
+            match self.0.n_clusters {
+                NbClusters::Fixed { nb } if nb == self.0.kpls_dims.len() => {}
+                _ => {
+                    return Err(MoeError::InvalidValueError(format!(
+                        "`kpls_dims` given for {} clusters, requires the same fixed number of clusters, got {:?}",
+                        self.0.kpls_dims.len(),
+                        self.0.n_clusters
+                    )));
+                }
+            }
+            if self.0.kpls_dims.contains(&Some(0)) {
+                return Err(MoeError::InvalidValueError(
+                    "`kpls_dims` canot contain 0!".to_string(),
+                ));
+            }
+        }
+        if let Some(kpls_selection) = self.0.kpls_selection {
+            if kpls_selection.max_dim() == 0 {
+                return Err(MoeError::InvalidValueError(
+                    "`max_dim` of kpls selection canot be 0!".to_string(),
+                ));
+            }
+            if let KplsSelection::ExplainedVariance { threshold, .. } = kpls_selection
+                && (threshold <= 0. || threshold > 1.)
+            {
+                return Err(MoeError::InvalidValueError(format!(
+                    "`threshold` of kpls selection should be in ]0, 1], got {threshold}"
+                )));
+            }
+        }
+
+        match &self.0.expert_selection {
+            ExpertSelection::PerCluster(types) => match self.0.n_clusters {
+                NbClusters::Fixed { nb } if nb == types.len() => {}
+                _ => {
+                    return Err(MoeError::InvalidValueError(format!(
+                        "Expert types given for {} clusters, requires the same fixed number of clusters, got {:?}",
+                        types.len(),
+                        self.0.n_clusters
+                    )));
+                }
+            },
+            ExpertSelection::BySize {
+                min_gp_size,
+                max_full_gp_size,
+                ..
+            } if min_gp_size > max_full_gp_size => {
+                return Err(MoeError::InvalidValueError(format!(
+                    "`min_gp_size` ({min_gp_size}) should be less than `max_full_gp_size` ({max_full_gp_size})"
+                )));
+            }
+            _ => {}
+        }
+
+        for (i, plugin) in self.0.expert_plugins.iter().enumerate() {
+            let name = plugin.name();
+            if name.is_empty() || name.contains("_PLS(") || is_builtin_expert_name(name) {
+                return Err(MoeError::InvalidValueError(format!(
+                    "Invalid expert plugin name '{name}', should not be empty nor a GP expert name"
+                )));
+            }
+            if self.0.expert_plugins[..i].iter().any(|p| p.name() == name) {
+                return Err(MoeError::InvalidValueError(format!(
+                    "Expert plugin '{name}' registered twice"
+                )));
+            }
+        }
+        if let ExpertSelection::PerCluster(types) = &self.0.expert_selection
+            && let Some(name) = types.iter().find_map(|t| match t {
+                ExpertType::Plugin(name) if self.0.expert_plugin(name).is_none() => Some(name),
+                _ => None,
+            })
+        {
+            return Err(MoeError::InvalidValueError(format!(
+                "Expert plugin '{name}' is not registered"
+            )));
+        }
+
+        match self.0.drift_criterion {
+            DriftCriterion::OutOfClusters { ratio } if !(0. ..=1.).contains(&ratio) => {
+                return Err(MoeError::InvalidValueError(format!(
+                    "Drift criterion `ratio` should be in [0, 1], got {ratio}"
+                )));
+            }
+            DriftCriterion::PredictionError { threshold }
+                if threshold.is_nan() || threshold <= 0. =>
+            {
+                return Err(MoeError::InvalidValueError(format!(
+                    "Drift criterion `threshold` should be positive, got {threshold}"
+                )));
+            }
+            _ => {}
+        }
+
+        if self.0.clustering_method != ClusteringMethod::Gmm && self.0.n_clusters.is_auto() {
+            return Err(MoeError::InvalidValueError(format!(
+                "{:?} clustering requires a fixed number of clusters",
+                self.0.clustering_method
+            )));
+        }
+        if let ClusteringMethod::Tree { min_leaf_size } = self.0.clustering_method
+            && min_leaf_size == 0
+        {
+            return Err(MoeError::InvalidValueError(
+                "`min_leaf_size` of tree clustering cannot be 0!".to_string(),
+            ));
+        }
+        if let GatingMethod::LogisticRegression { regularization } = self.0.gating_method {
+            if matches!(self.0.clustering_method, ClusteringMethod::Tree { .. }) {
+                return Err(MoeError::InvalidValueError(
+                    "Tree clustering experts are gated by regions, gating method should be Gmm"
+                        .to_string(),
+                ));
+            }
+            if regularization.is_nan() || regularization <= 0. {
+                return Err(MoeError::InvalidValueError(
+                    "`regularization` of logistic regression gating should be positive!"
+                        .to_string(),
+                ));
+            }
+        }
 
         if self.0.n_clusters.is_multi() && self.0.theta_tunings.len() == 1 {
         } else if let NbClusters::Fixed { nb } = self.0.n_clusters
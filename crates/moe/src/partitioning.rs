@@ -0,0 +1,510 @@
+//! Clustering methods alternative to the gaussian mixture model used to partition
+//! the training data among the experts of the mixture (see [ClusteringMethod](crate::ClusteringMethod)).
+
+use crate::errors::{MoeError, Result};
+use crate::gaussian_mixture::GaussianMixture;
+
+use linfa::DatasetBase;
+use linfa::traits::{Fit, Predict};
+use linfa_clustering::KMeans;
+use ndarray::{Array1, Array2, Array3, ArrayBase, ArrayView1, Axis, Data, Ix1, Ix2};
+use rand_xoshiro::Xoshiro256Plus;
+
+#[cfg(feature = "serializable")]
+use serde::{Deserialize, Serialize};
+
+/// Regularization added to the diagonal of cluster covariances
+const REG_COVAR: f64 = 1e-6;
+
+/// Standardize columns of the given data to get zero mean and unit variance
+fn standardize(data: &ArrayBase<impl Data<Elem = f64>, Ix2>) -> Array2<f64> {
+    let mean = data.mean_axis(Axis(0)).unwrap();
+    let std = data
+        .std_axis(Axis(0), 0.)
+        .mapv(|v| if v < f64::EPSILON { 1. } else { v });
+    (data - &mean) / &std
+}
+
+/// Cluster (x, y) `data` with k-means, returns the cluster index of each row
+pub(crate) fn kmeans_clustering(
+    data: &ArrayBase<impl Data<Elem = f64>, Ix2>,
+    n_clusters: usize,
+    rng: Xoshiro256Plus,
+) -> Result<Array1<usize>> {
+    let records = standardize(data);
+    let model = KMeans::params_with_rng(n_clusters, rng)
+        .n_runs(10)
+        .fit(&DatasetBase::from(records.clone()))
+        .map_err(|e| MoeError::ClusteringError(e.to_string()))?;
+    Ok(model.predict(&records))
+}
+
+/// Cluster (x, y) `data` by agglomerative clustering with Ward linkage,
+/// returns the cluster index of each row
+pub(crate) fn agglomerative_clustering(
+    data: &ArrayBase<impl Data<Elem = f64>, Ix2>,
+    n_clusters: usize,
+) -> Array1<usize> {
+    let records = standardize(data);
+    let n = records.nrows();
+    // Ward merging cost between clusters updated with Lance-Williams formula
+    let mut dist = Array2::from_shape_fn((n, n), |(i, j)| {
+        (&records.row(i) - &records.row(j)).mapv(|v| v * v).sum()
+    });
+    let mut sizes = vec![1.; n];
+    let mut members: Vec<Vec<usize>> = (0..n).map(|i| vec![i]).collect();
+    let mut active: Vec<usize> = (0..n).collect();
+
+    while active.len() > n_clusters.max(1) {
+        let (mut a, mut b, mut best) = (0, 1, f64::INFINITY);
+        for (ia, &i) in active.iter().enumerate() {
+            for &j in active[ia + 1..].iter() {
+                if dist[[i, j]] < best {
+                    (a, b, best) = (i, j, dist[[i, j]]);
+                }
+            }
+        }
+        for &k in active.iter().filter(|&&k| k != a && k != b) {
+            let d = ((sizes[a] + sizes[k]) * dist[[a, k]] + (sizes[b] + sizes[k]) * dist[[b, k]]
+                - sizes[k] * best)
+                / (sizes[a] + sizes[b] + sizes[k]);
+            dist[[a, k]] = d;
+            dist[[k, a]] = d;
+        }
+        sizes[a] += sizes[b];
+        let merged = std::mem::take(&mut members[b]);
+        members[a].extend(merged);
+        active.retain(|&k| k != b);
+    }
+
+    let mut clusters = Array1::zeros(n);
+    for (c, &k) in active.iter().enumerate() {
+        for &i in members[k].iter() {
+            clusters[i] = c;
+        }
+    }
+    clusters
+}
+
+/// Build the gaussian mixture in the input space from the given clustering of `x` points
+pub(crate) fn gaussian_mixture_from_clusters(
+    x: &ArrayBase<impl Data<Elem = f64>, Ix2>,
+    clusters: &Array1<usize>,
+    n_clusters: usize,
+) -> Result<GaussianMixture<f64>> {
+    let (n, nx) = x.dim();
+    let mut weights = Array1::zeros(n_clusters);
+    let mut means = Array2::zeros((n_clusters, nx));
+    let mut covariances = Array3::zeros((n_clusters, nx, nx));
+    for k in 0..n_clusters {
+        let indices: Vec<usize> = (0..n).filter(|&i| clusters[i] == k).collect();
+        if indices.is_empty() {
+            return Err(MoeError::EmptyCluster(format!(
+                "Cluster #{k} has no training point"
+            )));
+        }
+        let xk = x.select(Axis(0), &indices);
+        let mean = xk.mean_axis(Axis(0)).unwrap();
+        let centered = &xk - &mean;
+        let cov =
+            centered.t().dot(&centered) / indices.len() as f64 + Array2::<f64>::eye(nx) * REG_COVAR;
+        weights[k] = indices.len() as f64 / n as f64;
+        means.row_mut(k).assign(&mean);
+        covariances.index_axis_mut(Axis(0), k).assign(&cov);
+    }
+    GaussianMixture::new(weights, means, covariances)
+}
+
+/// Partition of the input space gating the experts of the mixture:
+/// each expert gives the prediction in its region
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serializable", derive(Serialize, Deserialize))]
+pub enum Partition {
+    /// Axis-aligned regions of a regression tree (see [ClusteringMethod::Tree](crate::ClusteringMethod::Tree))
+    Tree(TreePartition),
+    /// Cells of the nearest cluster centroids
+    /// (see [ClusteringMethod::KMeans](crate::ClusteringMethod::KMeans) and
+    /// [ClusteringMethod::Agglomerative](crate::ClusteringMethod::Agglomerative))
+    Centroids(CentroidPartition),
+}
+
+impl Partition {
+    /// Number of regions of the partition
+    pub fn n_regions(&self) -> usize {
+        match self {
+            Partition::Tree(tree) => tree.n_regions(),
+            Partition::Centroids(centroids) => centroids.n_regions(),
+        }
+    }
+
+    /// Region index of the given `x` point (nx,)
+    pub fn region(&self, x: &[f64]) -> usize {
+        match self {
+            Partition::Tree(tree) => tree.region(x),
+            Partition::Centroids(centroids) => centroids.region(x),
+        }
+    }
+
+    /// Region index of the n points given as a (n, nx) matrix
+    pub fn predict(&self, x: &ArrayBase<impl Data<Elem = f64>, Ix2>) -> Array1<usize> {
+        x.rows()
+            .into_iter()
+            .map(|row| self.region(&row.to_vec()))
+            .collect()
+    }
+
+    /// Name of the partition kind
+    pub fn name(&self) -> &'static str {
+        match self {
+            Partition::Tree(_) => "TreePartition",
+            Partition::Centroids(_) => "CentroidPartition",
+        }
+    }
+}
+
+/// Partition of the input space in the cells of cluster centroids: a point belongs
+/// to the region of its nearest centroid, distances being computed on inputs
+/// scaled by the standard deviation of training inputs.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serializable", derive(Serialize, Deserialize))]
+pub struct CentroidPartition {
+    /// Centroids (n_regions, nx) of the clusters in the input space
+    centroids: Array2<f64>,
+    /// Scaling of the input components
+    scale: Array1<f64>,
+}
+
+impl CentroidPartition {
+    /// Build the partition from the given clustering of `x` points
+    pub(crate) fn fit(
+        x: &ArrayBase<impl Data<Elem = f64>, Ix2>,
+        clusters: &Array1<usize>,
+        n_clusters: usize,
+    ) -> Result<Self> {
+        let mut centroids = Array2::zeros((n_clusters, x.ncols()));
+        for (k, mut centroid) in centroids.rows_mut().into_iter().enumerate() {
+            let indices: Vec<usize> = (0..x.nrows()).filter(|&i| clusters[i] == k).collect();
+            if indices.is_empty() {
+                return Err(MoeError::EmptyCluster(format!(
+                    "Cluster #{k} has no training point"
+                )));
+            }
+            centroid.assign(&x.select(Axis(0), &indices).mean_axis(Axis(0)).unwrap());
+        }
+        let scale = x
+            .std_axis(Axis(0), 0.)
+            .mapv(|v| if v < f64::EPSILON { 1. } else { v });
+        Ok(CentroidPartition { centroids, scale })
+    }
+
+    /// Number of regions (i.e. centroids) of the partition
+    pub fn n_regions(&self) -> usize {
+        self.centroids.nrows()
+    }
+
+    /// Region index of the given `x` point (nx,)
+    pub fn region(&self, x: &[f64]) -> usize {
+        let dist = |c: ArrayView1<f64>| -> f64 {
+            c.iter()
+                .zip(x)
+                .zip(&self.scale)
+                .map(|((c, x), s)| ((x - c) / s).powi(2))
+                .sum()
+        };
+        self.centroids
+            .rows()
+            .into_iter()
+            .map(dist)
+            .enumerate()
+            .fold(
+                (0, f64::INFINITY),
+                |(i, m), (j, d)| if d < m { (j, d) } else { (i, m) },
+            )
+            .0
+    }
+
+    /// Region index of the n points given as a (n, nx) matrix
+    pub fn predict(&self, x: &ArrayBase<impl Data<Elem = f64>, Ix2>) -> Array1<usize> {
+        x.rows()
+            .into_iter()
+            .map(|row| self.region(&row.to_vec()))
+            .collect()
+    }
+}
+
+/// A node of the regression tree
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serializable", derive(Serialize, Deserialize))]
+enum TreeNode {
+    /// Points with `x[dim] <= threshold` go to `left` node, other points go to `right` node
+    Split {
+        dim: usize,
+        threshold: f64,
+        left: usize,
+        right: usize,
+    },
+    /// Region of the input space
+    Leaf { region: usize },
+}
+
+/// Partition of the input space in axis-aligned regions given by a regression tree
+/// fitted on training data
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serializable", derive(Serialize, Deserialize))]
+pub struct TreePartition {
+    /// Tree nodes, first one being the root
+    nodes: Vec<TreeNode>,
+    /// Number of regions (i.e. leaves)
+    n_regions: usize,
+}
+
+/// Best split of a set of points: (gain, dim, threshold, left indices, right indices)
+type TreeSplit = (f64, usize, f64, Vec<usize>, Vec<usize>);
+
+impl TreePartition {
+    /// Fit a regression tree with at most `n_regions` leaves containing at least
+    /// `min_leaf_size` points, splits are selected best-first to minimize the sum of
+    /// squared errors of `y` wrt to the mean value of `y` in each region
+    pub(crate) fn fit(
+        x: &ArrayBase<impl Data<Elem = f64>, Ix2>,
+        y: &ArrayBase<impl Data<Elem = f64>, Ix1>,
+        n_regions: usize,
+        min_leaf_size: usize,
+    ) -> Self {
+        let mut nodes = vec![TreeNode::Leaf { region: 0 }];
+        // leaves to be split: (node index, best split)
+        let root: Vec<usize> = (0..x.nrows()).collect();
+        let mut leaves = vec![(0, Self::best_split(x, y, &root, min_leaf_size))];
+
+        while leaves.len() < n_regions {
+            let best = leaves
+                .iter()
+                .enumerate()
+                .filter_map(|(i, (_, split))| split.as_ref().map(|s| (i, s.0)))
+                .max_by(|a, b| a.1.total_cmp(&b.1));
+            let Some((i, _)) = best else { break };
+            let (node, split) = leaves.swap_remove(i);
+            let (_, dim, threshold, left, right) = split.unwrap();
+            let (l, r) = (nodes.len(), nodes.len() + 1);
+            nodes[node] = TreeNode::Split {
+                dim,
+                threshold,
+                left: l,
+                right: r,
+            };
+            nodes.push(TreeNode::Leaf { region: 0 });
+            nodes.push(TreeNode::Leaf { region: 0 });
+            leaves.push((l, Self::best_split(x, y, &left, min_leaf_size)));
+            leaves.push((r, Self::best_split(x, y, &right, min_leaf_size)));
+        }
+
+        // number regions following nodes order
+        let mut n_regions = 0;
+        for node in nodes.iter_mut() {
+            if let TreeNode::Leaf { region } = node {
+                *region = n_regions;
+                n_regions += 1;
+            }
+        }
+        TreePartition { nodes, n_regions }
+    }
+
+    /// Find the split of the `indices` points giving the largest decrease of the sum of
+    /// squared errors, returns None if no split is possible
+    fn best_split(
+        x: &ArrayBase<impl Data<Elem = f64>, Ix2>,
+        y: &ArrayBase<impl Data<Elem = f64>, Ix1>,
+        indices: &[usize],
+        min_leaf_size: usize,
+    ) -> Option<TreeSplit> {
+        let n = indices.len();
+        if n < 2 * min_leaf_size {
+            return None;
+        }
+        let sse = |s: f64, s2: f64, m: f64| s2 - s * s / m;
+        let total: f64 = indices.iter().map(|&i| y[i]).sum();
+        let total2: f64 = indices.iter().map(|&i| y[i] * y[i]).sum();
+        let parent_sse = sse(total, total2, n as f64);
+
+        let mut best: Option<(f64, usize, f64, usize)> = None;
+        let mut sorted = indices.to_vec();
+        for d in 0..x.ncols() {
+            sorted.sort_by(|&a, &b| x[[a, d]].total_cmp(&x[[b, d]]));
+            let (mut s, mut s2) = (0., 0.);
+            for k in 0..n - 1 {
+                let yk = y[sorted[k]];
+                s += yk;
+                s2 += yk * yk;
+                let nl = k + 1;
+                let (xa, xb) = (x[[sorted[k], d]], x[[sorted[k + 1], d]]);
+                if nl < min_leaf_size || n - nl < min_leaf_size || xa == xb {
+                    continue;
+                }
+                let gain = parent_sse
+                    - sse(s, s2, nl as f64)
+                    - sse(total - s, total2 - s2, (n - nl) as f64);
+                if best.is_none_or(|b| gain > b.0) {
+                    best = Some((gain, d, 0.5 * (xa + xb), nl));
+                }
+            }
+        }
+        best.filter(|b| b.0 > 1e-12 * parent_sse)
+            .map(|(gain, dim, threshold, _)| {
+                let (left, right) = indices.iter().partition(|&&i| x[[i, dim]] <= threshold);
+                (gain, dim, threshold, left, right)
+            })
+    }
+
+    /// Number of regions of the partition
+    pub fn n_regions(&self) -> usize {
+        self.n_regions
+    }
+
+    /// Region index of the given `x` point (nx,)
+    pub fn region(&self, x: &[f64]) -> usize {
+        let mut node = 0;
+        loop {
+            match self.nodes[node] {
+                TreeNode::Split {
+                    dim,
+                    threshold,
+                    left,
+                    right,
+                } => node = if x[dim] <= threshold { left } else { right },
+                TreeNode::Leaf { region } => return region,
+            }
+        }
+    }
+
+    /// Region index of the n points given as a (n, nx) matrix
+    pub fn predict(&self, x: &ArrayBase<impl Data<Elem = f64>, Ix2>) -> Array1<usize> {
+        x.rows()
+            .into_iter()
+            .map(|row| self.region(&row.to_vec()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        Clustered, ClusteringMethod, GatingMethod, GpMixture, MixtureGpSurrogate, NbClusters,
+        Recombination,
+    };
+    use approx::assert_abs_diff_eq;
+    use egobox_doe::{Lhs, SamplingMethod};
+    use linfa::Dataset;
+    use ndarray::{Array, Zip, array};
+    use ndarray_rand::RandomExt;
+    use ndarray_rand::rand::SeedableRng;
+    use ndarray_rand::rand_distr::Uniform;
+
+    /// Function with a discontinuity along x0 = 0.3 + 0.2 * x1
+    fn f_shock(x: &Array2<f64>) -> Array1<f64> {
+        x.map_axis(Axis(1), |xi| {
+            if xi[0] < 0.3 + 0.2 * xi[1] {
+                xi[0] + xi[1]
+            } else {
+                3. + (2. * xi[1]).sin()
+            }
+        })
+    }
+
+    #[test]
+    fn test_tree_partition() {
+        let x = Array::linspace(0., 1., 20).insert_axis(Axis(1));
+        let y = x.column(0).mapv(|v| if v < 0.4 { 0. } else { 1. });
+        let tree = TreePartition::fit(&x, &y, 3, 3);
+        // two constant pieces: the second split brings no improvement
+        assert_eq!(tree.n_regions(), 2);
+        assert_eq!(tree.region(&[0.38]), 0);
+        assert_eq!(tree.region(&[0.43]), 1);
+
+        let tree = TreePartition::fit(&x, &y, 3, 12);
+        assert_eq!(tree.n_regions(), 1);
+    }
+
+    #[test]
+    fn test_agglomerative_clustering() {
+        let data = array![
+            [0., 0.],
+            [0.1, 0.],
+            [0., 0.1],
+            [5., 5.],
+            [5.1, 5.],
+            [5., 5.2]
+        ];
+        let clusters = agglomerative_clustering(&data, 2);
+        assert_eq!(clusters, array![0, 0, 0, 1, 1, 1]);
+        let gmx = gaussian_mixture_from_clusters(&data, &clusters, 2).unwrap();
+        assert_abs_diff_eq!(gmx.weights(), &array![0.5, 0.5]);
+        assert_abs_diff_eq!(
+            gmx.means().row(1),
+            array![15.1 / 3., 15.2 / 3.],
+            epsilon = 1e-12
+        );
+    }
+
+    #[test]
+    fn test_mixture_clustering_methods() {
+        let mut rng = Xoshiro256Plus::seed_from_u64(42);
+        let xt = Lhs::new(&array![[0., 1.], [0., 1.]])
+            .with_rng(rng.clone())
+            .sample(60);
+        let yt = f_shock(&xt);
+        let xtest = Array2::random_using((200, 2), Uniform::new(0., 1.), &mut rng);
+        let ytest = f_shock(&xtest);
+
+        for method in [
+            ClusteringMethod::KMeans,
+            ClusteringMethod::Agglomerative,
+            ClusteringMethod::Tree { min_leaf_size: 5 },
+        ] {
+            let moe = GpMixture::params()
+                .n_clusters(NbClusters::fixed(2))
+                .clustering_method(method)
+                .with_rng(rng.clone())
+                .fit(&Dataset::new(xt.clone(), yt.clone()))
+                .expect("MoE trained");
+            assert_eq!(moe.n_clusters(), 2);
+            let ypred = moe.predict(&xtest).unwrap();
+            let mut errs = (&ypred - &ytest).mapv(f64::abs).to_vec();
+            errs.sort_by(f64::total_cmp);
+            println!("{method:?}: median error {}", errs[errs.len() / 2]);
+            assert!(errs[errs.len() / 2] < 0.1);
+            assert_eq!(moe.recombination(), Recombination::Hard);
+            // gating by the regions of the partition
+            let partition = moe.to_clustering().partition().cloned().unwrap();
+            match method {
+                ClusteringMethod::Tree { .. } => {
+                    assert!(matches!(partition, Partition::Tree(_)))
+                }
+                _ => assert!(matches!(partition, Partition::Centroids(_))),
+            }
+            let regions = partition.predict(&xtest);
+            let preds: Vec<f64> = Zip::from(xtest.rows())
+                .and(&regions)
+                .map_collect(|x, &r| moe.experts()[r].predict(&x.insert_axis(Axis(0))).unwrap()[0])
+                .to_vec();
+            assert_abs_diff_eq!(Array1::from(preds), ypred, epsilon = 1e-12);
+            let coefs = moe.coefficients().unwrap();
+            let x = xtest.row(0).to_vec();
+            assert_abs_diff_eq!(coefs.predict(&x), ypred[0], epsilon = 1e-8);
+        }
+
+        // a gating network trained on the clusters replaces the nearest centroid partition
+        let moe = GpMixture::params()
+            .n_clusters(NbClusters::fixed(2))
+            .clustering_method(ClusteringMethod::KMeans)
+            .gating_method(GatingMethod::LogisticRegression {
+                regularization: 1e-3,
+            })
+            .with_rng(rng.clone())
+            .fit(&Dataset::new(xt.clone(), yt.clone()))
+            .expect("MoE trained");
+        assert!(moe.partition().is_none());
+        assert!(moe.gating().is_some());
+        assert_ne!(moe.recombination(), Recombination::Hard);
+    }
+}
@@ -2,17 +2,23 @@ use crate::errors::Result;
 #[cfg(feature = "persistent")]
 use crate::types::GpFileFormat;
 use egobox_gp::{
-    GaussianProcess, GpParams, SgpParams, SparseGaussianProcess, SparseMethod, ThetaTuning,
+    BayesianGaussianProcess, BayesianGpParams, GP_RFF_N_FEATURES, GaussianProcess, GpCoefficients,
+    GpParams, GpSamplePaths, Inducings, RobustGaussianProcess, RobustGpParams, RobustLikelihood,
+    SgpParams, SgpSamplePaths, SparseGaussianProcess, SparseMethod, ThetaTuning,
     correlation_models::*, mean_models::*,
 };
+use linfa::Float;
 use linfa::prelude::{Dataset, Fit};
-use ndarray::{Array1, Array2, ArrayView2, Axis};
+use ndarray::{
+    Array, Array1, Array2, Array3, ArrayBase, ArrayView2, Axis, CowArray, Data, Dimension, Ix2, s,
+};
+use ndarray_rand::rand::SeedableRng;
 use paste::paste;
+use rand_xoshiro::Xoshiro256Plus;
 
 #[cfg(feature = "serializable")]
 use serde::{Deserialize, Serialize};
 
-#[cfg(feature = "persistent")]
 use crate::MoeError;
 #[cfg(feature = "persistent")]
 use std::fs;
@@ -42,6 +48,16 @@ pub trait SgpSurrogateParams: GpSurrogateParams {
     fn seed(&mut self, seed: Option<u64>);
 }
 
+/// A trait for fully Bayesian GP surrogate parameters to build surrogate.
+pub trait BgpSurrogateParams: GpSurrogateParams {
+    /// Set the number of hyperparameters posterior samples
+    fn n_samples(&mut self, n_samples: usize);
+    /// Set bounds of the noise to process variance ratio, `None` means interpolating model
+    fn noise_bounds(&mut self, noise_bounds: Option<(f64, f64)>);
+    /// Set random generator seed
+    fn seed(&mut self, seed: Option<u64>);
+}
+
 /// A trait for a base GP surrogate
 #[cfg_attr(feature = "serializable", typetag::serde(tag = "type"))]
 pub trait GpSurrogate: std::fmt::Display + Sync + Send {
@@ -56,9 +72,28 @@ pub trait GpSurrogate: std::fmt::Display + Sync + Send {
     fn predict(&self, x: &ArrayView2<f64>) -> Result<Array1<f64>>;
     /// Predict variance values at n points given as (n, xdim) matrix.
     fn predict_var(&self, x: &ArrayView2<f64>) -> Result<Array1<f64>>;
+    /// Predict output values at n points given as (n, xdim) single precision matrix.
+    ///
+    /// The default implementation converts inputs and outputs of [GpSurrogate::predict]
+    /// while surrogates trained in single precision predict without conversion.
+    fn predict_f32(&self, x: &ArrayView2<f32>) -> Result<Array1<f32>> {
+        Ok(cast_array(&self.predict(&cast_array(x).view())?))
+    }
+    /// Predict variance values at n points given as (n, xdim) single precision matrix.
+    ///
+    /// See [GpSurrogate::predict_f32].
+    fn predict_var_f32(&self, x: &ArrayView2<f32>) -> Result<Array1<f32>> {
+        Ok(cast_array(&self.predict_var(&cast_array(x).view())?))
+    }
     /// Save model in given file.
     #[cfg(feature = "persistent")]
     fn save(&self, path: &str, format: GpFileFormat) -> Result<()>;
+    /// Export the surrogate as a self-contained set of coefficients (see [GpCoefficients]).
+    fn coefficients(&self) -> Result<GpCoefficients> {
+        Err(MoeError::InvalidValueError(format!(
+            "{self} surrogate can not be exported as coefficients"
+        )))
+    }
 }
 
 /// A trait for a GP surrogate with derivatives predictions and sampling
@@ -70,14 +105,102 @@ pub trait GpSurrogateExt {
     /// Predict derivatives of the variance at n points and return (n, xdim) matrix
     /// where each column is the partial derivatives wrt the ith component
     fn predict_var_gradients(&self, x: &ArrayView2<f64>) -> Result<Array2<f64>>;
+    /// Predict second derivatives at n points and return (n, xdim, xdim) array
+    /// where each (xdim, xdim) matrix is the hessian of the output at the ith point.
+    /// The default implementation uses central finite differences of the gradients.
+    fn predict_hessians(&self, x: &ArrayView2<f64>) -> Result<Array3<f64>> {
+        finite_difference_hessians(x, |x| self.predict_gradients(x))
+    }
+    /// Predict second derivatives of the variance at n points and return (n, xdim, xdim) array
+    /// where each (xdim, xdim) matrix is the hessian of the variance at the ith point.
+    /// The default implementation uses central finite differences of the variance gradients.
+    fn predict_var_hessians(&self, x: &ArrayView2<f64>) -> Result<Array3<f64>> {
+        finite_difference_hessians(x, |x| self.predict_var_gradients(x))
+    }
     /// Sample trajectories
     fn sample(&self, x: &ArrayView2<f64>, n_traj: usize) -> Result<Array2<f64>>;
+    /// Draw `n_traj` posterior sample paths using the given random `seed` (see [SamplePaths])
+    fn sample_paths(&self, _n_traj: usize, _seed: u64) -> Result<Box<dyn SamplePaths>> {
+        Err(MoeError::SampleError(
+            "Sample paths are not available for this surrogate".to_string(),
+        ))
+    }
+}
+
+/// Hessians at n points given as (n, xdim) matrix computed with central finite differences
+/// of the `gradients` function returning a (n, xdim) matrix
+fn finite_difference_hessians(
+    x: &ArrayView2<f64>,
+    gradients: impl Fn(&ArrayView2<f64>) -> Result<Array2<f64>>,
+) -> Result<Array3<f64>> {
+    let (n, nx) = x.dim();
+    let mut hess = Array3::zeros((n, nx, nx));
+    let eps = f64::EPSILON.cbrt();
+    for k in 0..nx {
+        let h = x.column(k).mapv(|v| eps * (1. + v.abs()));
+        let mut xp = x.to_owned();
+        let mut xm = x.to_owned();
+        xp.column_mut(k).zip_mut_with(&h, |v, h| *v += h);
+        xm.column_mut(k).zip_mut_with(&h, |v, h| *v -= h);
+        let mut fdiff = gradients(&xp.view())? - gradients(&xm.view())?;
+        fdiff
+            .axis_iter_mut(Axis(1))
+            .for_each(|mut col| col.zip_mut_with(&h, |v, h| *v /= 2. * h));
+        hess.slice_mut(s![.., .., k]).assign(&fdiff);
+    }
+    Ok(hess)
+}
+
+/// A trait for posterior sample paths of a surrogate given as continuous
+/// and differentiable functions which can be evaluated at any point
+/// (e.g. to minimize a posterior draw in Thompson sampling)
+pub trait SamplePaths: Sync + Send {
+    /// Number of sample paths
+    fn n_traj(&self) -> usize;
+    /// Evaluate sample paths at n points given as (n, xdim) matrix and return (n, n_traj) matrix
+    fn predict(&self, x: &ArrayView2<f64>) -> Result<Array2<f64>>;
+    /// Evaluate sample paths derivatives at n points given as (n, xdim) matrix
+    /// and return (n, xdim, n_traj) array, the last axis being the path index
+    fn predict_gradients(&self, x: &ArrayView2<f64>) -> Result<Array3<f64>>;
+}
+
+impl<F, Mean, Corr> SamplePaths for GpSamplePaths<F, Mean, Corr>
+where
+    F: Float,
+    Mean: RegressionModel<F> + Sync + Send,
+    Corr: CorrelationModel<F> + Sync + Send,
+{
+    fn n_traj(&self) -> usize {
+        self.n_traj()
+    }
+    fn predict(&self, x: &ArrayView2<f64>) -> Result<Array2<f64>> {
+        Ok(cast_array(&self.predict(&cast_array::<f64, F, _>(x))?))
+    }
+    fn predict_gradients(&self, x: &ArrayView2<f64>) -> Result<Array3<f64>> {
+        Ok(cast_array(
+            &self.predict_gradients(&cast_array::<f64, F, _>(x)),
+        ))
+    }
+}
+
+impl<F: Float, Corr: CorrelationModel<F> + Sync + Send> SamplePaths for SgpSamplePaths<F, Corr> {
+    fn n_traj(&self) -> usize {
+        self.n_traj()
+    }
+    fn predict(&self, x: &ArrayView2<f64>) -> Result<Array2<f64>> {
+        Ok(cast_array(&self.predict(&cast_array::<f64, F, _>(x))?))
+    }
+    fn predict_gradients(&self, x: &ArrayView2<f64>) -> Result<Array3<f64>> {
+        Ok(cast_array(
+            &self.predict_gradients(&cast_array::<f64, F, _>(x)),
+        ))
+    }
 }
 
 /// A trait for a GP surrogate.
 #[cfg_attr(feature = "serializable", typetag::serde(tag = "type"))]
 pub trait GpParameterized {
-    fn theta(&self) -> &Array1<f64>;
+    fn theta(&self) -> Array1<f64>;
     fn variance(&self) -> f64;
     fn noise_variance(&self) -> f64;
     fn likelihood(&self) -> f64;
@@ -91,10 +214,221 @@ pub trait FullGpSurrogate: GpParameterized + GpSurrogate + GpSurrogateExt {}
 #[cfg_attr(feature = "serializable", typetag::serde(tag = "type"))]
 pub trait SgpSurrogate: FullGpSurrogate {}
 
+/// Convert array elements to another float type
+pub(crate) fn cast_array<F: Float, G: Float, D: Dimension>(
+    a: &ArrayBase<impl Data<Elem = F>, D>,
+) -> Array<G, D> {
+    a.mapv(G::cast)
+}
+
+/// Float types of the GP underlying a surrogate, used to predict from single precision
+/// inputs without conversion when the GP is trained in single precision.
+trait FromF32: Float {
+    fn from_f32_view<'a>(x: &ArrayView2<'a, f32>) -> CowArray<'a, Self, Ix2>;
+    fn into_f32_array(y: Array1<Self>) -> Array1<f32>;
+}
+
+impl FromF32 for f32 {
+    fn from_f32_view<'a>(x: &ArrayView2<'a, f32>) -> CowArray<'a, Self, Ix2> {
+        CowArray::from(*x)
+    }
+    fn into_f32_array(y: Array1<Self>) -> Array1<f32> {
+        y
+    }
+}
+
+impl FromF32 for f64 {
+    fn from_f32_view<'a>(x: &ArrayView2<'a, f32>) -> CowArray<'a, Self, Ix2> {
+        CowArray::from(cast_array(x))
+    }
+    fn into_f32_array(y: Array1<Self>) -> Array1<f32> {
+        cast_array(&y)
+    }
+}
+
+/// Single precision prediction of a GP trained with `F` float type
+/// given its `predict` function
+fn predict_f32_with<F: FromF32, E>(
+    x: &ArrayView2<f32>,
+    predict: impl FnOnce(&CowArray<F, Ix2>) -> std::result::Result<Array1<F>, E>,
+) -> Result<Array1<f32>>
+where
+    MoeError: From<E>,
+{
+    Ok(F::into_f32_array(predict(&F::from_f32_view(x))?))
+}
+
+/// Noise variance of a GP (interpolating model)
+fn no_noise_variance<F: Float, Mean: RegressionModel<F>, Corr: CorrelationModel<F>>(
+    _gp: &GaussianProcess<F, Mean, Corr>,
+) -> F {
+    F::zero()
+}
+
+/// Posterior sample paths of a GP
+fn gp_sample_paths<F, Mean, Corr>(
+    gp: &GaussianProcess<F, Mean, Corr>,
+    n_traj: usize,
+    rng: &mut Xoshiro256Plus,
+) -> Result<Box<dyn SamplePaths>>
+where
+    F: Float,
+    Mean: RegressionModel<F> + Sync + Send + 'static,
+    Corr: CorrelationModel<F> + Sync + Send + 'static,
+{
+    Ok(Box::new(gp.sample_paths(n_traj, GP_RFF_N_FEATURES, rng)?))
+}
+
+/// Posterior sample paths of a sparse GP
+fn sgp_sample_paths<F, Corr>(
+    sgp: &SparseGaussianProcess<F, Corr>,
+    n_traj: usize,
+    rng: &mut Xoshiro256Plus,
+) -> Result<Box<dyn SamplePaths>>
+where
+    F: Float,
+    Corr: CorrelationModel<F> + Sync + Send + 'static,
+{
+    Ok(Box::new(sgp.sample_paths(
+        n_traj,
+        GP_RFF_N_FEATURES,
+        rng,
+    )?))
+}
+
+/// Posterior sample paths of the latent function of a robust GP
+fn rgp_sample_paths<F, Mean, Corr>(
+    rgp: &RobustGaussianProcess<F, Mean, Corr>,
+    n_traj: usize,
+    rng: &mut Xoshiro256Plus,
+) -> Result<Box<dyn SamplePaths>>
+where
+    F: Float,
+    Mean: RegressionModel<F> + Sync + Send + 'static,
+    Corr: CorrelationModel<F> + Sync + Send + 'static,
+{
+    Ok(Box::new(rgp.sample_paths(
+        n_traj,
+        GP_RFF_N_FEATURES,
+        rng,
+    )?))
+}
+
+/// Sample paths of a fully Bayesian GP (not available, paths of a mixture over hyperparameters)
+fn no_sample_paths<F, Mean, Corr>(
+    _bgp: &BayesianGaussianProcess<F, Mean, Corr>,
+    _n_traj: usize,
+    _rng: &mut Xoshiro256Plus,
+) -> Result<Box<dyn SamplePaths>>
+where
+    F: Float,
+    Mean: RegressionModel<F>,
+    Corr: CorrelationModel<F>,
+{
+    Err(MoeError::SampleError(
+        "Sample paths of fully Bayesian GP are not available".to_string(),
+    ))
+}
+
+/// A macro to implement GP surrogate traits for the given surrogate type.
+///
+/// Surrogate traits are f64 based while the underlying GP may be trained with
+/// another float type, inputs and outputs are converted accordingly.
+/// Single precision predictions of GP trained in single precision are not converted.
+/// `$noise` is the function used to get the noise variance of the underlying GP,
+/// `$paths` the function used to draw its posterior sample paths.
+macro_rules! impl_gp_surrogate {
+    ($surrogate:ident, $trait:ident, $noise:path, $paths:path $(, $coefficients:path)?) => {
+        #[cfg_attr(feature = "serializable", typetag::serde)]
+        impl GpSurrogate for $surrogate {
+            fn dims(&self) -> (usize, usize) {
+                self.0.dims()
+            }
+            fn predict(&self, x: &ArrayView2<f64>) -> Result<Array1<f64>> {
+                Ok(cast_array(&self.0.predict(&cast_array(x))?))
+            }
+            fn predict_var(&self, x: &ArrayView2<f64>) -> Result<Array1<f64>> {
+                Ok(cast_array(&self.0.predict_var(&cast_array(x))?))
+            }
+            fn predict_f32(&self, x: &ArrayView2<f32>) -> Result<Array1<f32>> {
+                predict_f32_with(x, |x| self.0.predict(x))
+            }
+            fn predict_var_f32(&self, x: &ArrayView2<f32>) -> Result<Array1<f32>> {
+                predict_f32_with(x, |x| self.0.predict_var(x))
+            }
+
+            #[cfg(feature = "persistent")]
+            fn save(&self, path: &str, format: GpFileFormat) -> Result<()> {
+                let mut file = fs::File::create(path).unwrap();
+                let bytes = match format {
+                    GpFileFormat::Json => {
+                        serde_json::to_vec(self as &dyn $trait).map_err(MoeError::SaveJsonError)?
+                    }
+                    GpFileFormat::Binary => bincode::serialize(self as &dyn $trait)
+                        .map_err(MoeError::SaveBinaryError)?,
+                };
+                file.write_all(&bytes)?;
+                Ok(())
+            }
+
+            $(
+                fn coefficients(&self) -> Result<GpCoefficients> {
+                    Ok($coefficients(&self.0)?)
+                }
+            )?
+        }
+
+        #[cfg_attr(feature = "serializable", typetag::serde)]
+        impl GpSurrogateExt for $surrogate {
+            fn predict_gradients(&self, x: &ArrayView2<f64>) -> Result<Array2<f64>> {
+                Ok(cast_array(&self.0.predict_gradients(&cast_array(x))))
+            }
+            fn predict_var_gradients(&self, x: &ArrayView2<f64>) -> Result<Array2<f64>> {
+                Ok(cast_array(&self.0.predict_var_gradients(&cast_array(x))))
+            }
+            fn predict_hessians(&self, x: &ArrayView2<f64>) -> Result<Array3<f64>> {
+                Ok(cast_array(&self.0.predict_hessians(&cast_array(x))))
+            }
+            fn predict_var_hessians(&self, x: &ArrayView2<f64>) -> Result<Array3<f64>> {
+                Ok(cast_array(&self.0.predict_var_hessians(&cast_array(x))))
+            }
+            fn sample(&self, x: &ArrayView2<f64>, n_traj: usize) -> Result<Array2<f64>> {
+                Ok(cast_array(&self.0.sample(&cast_array(x), n_traj)))
+            }
+            fn sample_paths(&self, n_traj: usize, seed: u64) -> Result<Box<dyn SamplePaths>> {
+                $paths(&self.0, n_traj, &mut Xoshiro256Plus::seed_from_u64(seed))
+            }
+        }
+
+        #[cfg_attr(feature = "serializable", typetag::serde)]
+        impl GpParameterized for $surrogate {
+            fn theta(&self) -> Array1<f64> {
+                cast_array(self.0.theta())
+            }
+
+            fn variance(&self) -> f64 {
+                f64::cast(self.0.variance())
+            }
+
+            fn noise_variance(&self) -> f64 {
+                f64::cast($noise(&self.0))
+            }
+
+            fn likelihood(&self) -> f64 {
+                f64::cast(self.0.likelihood())
+            }
+        }
+
+        #[cfg_attr(feature = "serializable", typetag::serde)]
+        impl FullGpSurrogate for $surrogate {}
+    };
+}
+
 /// A macro to declare GP surrogate using regression model and correlation model names.
 ///
 /// Regression model is either `Constant`, `Linear` or `Quadratic`.
 /// Correlation model is either `SquaredExponential`, `AbsoluteExponential`, `Matern32` or `Matern52`.
+/// Surrogates are declared for double precision (default) and single precision (`F32` suffix).
 macro_rules! declare_surrogate {
     ($regr:ident, $corr:ident) => {
         paste! {
@@ -102,20 +436,23 @@ macro_rules! declare_surrogate {
             #[doc(hidden)]
             #[doc = "GP surrogate parameters with `" $regr "` regression model and `" $corr "` correlation model. \n\nSee [GpParams](egobox_gp::GpParams)"]
             #[derive(Clone, Debug)]
-            pub struct [<Gp $regr $corr SurrogateParams>](
-                GpParams<f64, [<$regr Mean>], [<$corr Corr>]>,
+            pub struct [<Gp $regr $corr SurrogateParams>]<F: Float = f64>(
+                GpParams<F, [<$regr Mean>], [<$corr Corr>]>,
             );
 
-            impl [<Gp $regr $corr SurrogateParams>] {
+            impl<F: Float> [<Gp $regr $corr SurrogateParams>]<F> {
                 /// Constructor
-                pub fn new(gp_params: GpParams<f64, [<$regr Mean>], [<$corr Corr>]>) -> [<Gp $regr $corr SurrogateParams>] {
+                pub fn new(gp_params: GpParams<F, [<$regr Mean>], [<$corr Corr>]>) -> [<Gp $regr $corr SurrogateParams>]<F> {
                     [<Gp $regr $corr SurrogateParams>](gp_params)
                 }
             }
 
-            impl GpSurrogateParams for [<Gp $regr $corr SurrogateParams>] {
+            impl<F: Float> GpSurrogateParams for [<Gp $regr $corr SurrogateParams>]<F>
+            where
+                [<Gp $regr $corr Surrogate>]<F>: FullGpSurrogate + 'static,
+            {
                 fn theta_tuning(&mut self, theta_tuning: ThetaTuning<f64>) {
-                    self.0 = self.0.clone().theta_tuning(theta_tuning);
+                    self.0 = self.0.clone().theta_tuning(theta_tuning.cast());
                 }
 
                 fn kpls_dim(&mut self, kpls_dim: Option<usize>) {
@@ -131,7 +468,7 @@ macro_rules! declare_surrogate {
                 }
 
                 fn nugget(&mut self, nugget: f64) {
-                    self.0 = self.0.clone().nugget(nugget);
+                    self.0 = self.0.clone().nugget(F::cast(nugget));
                 }
 
                 fn train(
@@ -140,7 +477,7 @@ macro_rules! declare_surrogate {
                     y: &ArrayView2<f64>,
                 ) -> Result<Box<dyn FullGpSurrogate>> {
                     Ok(Box::new([<Gp $regr $corr Surrogate>](
-                        self.0.clone().fit(&Dataset::new(x.to_owned(), y.to_owned().remove_axis(Axis(1))))?,
+                        self.0.clone().fit(&Dataset::new(cast_array(x), cast_array(y).remove_axis(Axis(1))))?,
                     )))
                 }
             }
@@ -148,75 +485,17 @@ macro_rules! declare_surrogate {
             #[doc = "GP surrogate with `" $regr "` regression model and `" $corr "` correlation model. \n\nSee [`GaussianProcess`](egobox_gp::GaussianProcess)"]
             #[derive(Clone, Debug)]
             #[cfg_attr(feature = "serializable", derive(Serialize, Deserialize))]
-            pub struct [<Gp $regr $corr Surrogate>](
-                pub GaussianProcess<f64, [<$regr Mean>], [<$corr Corr>]>,
+            pub struct [<Gp $regr $corr Surrogate>]<F: Float = f64>(
+                pub GaussianProcess<F, [<$regr Mean>], [<$corr Corr>]>,
             );
 
-            #[cfg_attr(feature = "serializable", typetag::serde)]
-            impl GpSurrogate for [<Gp $regr $corr Surrogate>] {
-                fn dims(&self) -> (usize, usize) {
-                    self.0.dims()
-                }
-                fn predict(&self, x: &ArrayView2<f64>) -> Result<Array1<f64>> {
-                    Ok(self.0.predict(x)?)
-                }
-                fn predict_var(&self, x: &ArrayView2<f64>) -> Result<Array1<f64>> {
-                    Ok(self.0.predict_var(x)?)
-                }
+            #[doc = "Single precision GP surrogate with `" $regr "` regression model and `" $corr "` correlation model."]
+            pub type [<Gp $regr $corr SurrogateF32>] = [<Gp $regr $corr Surrogate>]<f32>;
 
-                #[cfg(feature = "persistent")]
-                fn save(&self, path: &str, format: GpFileFormat) -> Result<()> {
-                    let mut file = fs::File::create(path).unwrap();
-                    let bytes = match format {
-                        GpFileFormat::Json => serde_json::to_vec(self as &dyn GpSurrogate)
-                            .map_err(MoeError::SaveJsonError)?,
-                        GpFileFormat::Binary => {
-                            bincode::serialize(self as &dyn GpSurrogate).map_err(MoeError::SaveBinaryError)?
-                        }
-                    };
-                    file.write_all(&bytes)?;
-
-                    Ok(())
-                }
+            impl_gp_surrogate!([<Gp $regr $corr Surrogate>], GpSurrogate, no_noise_variance, gp_sample_paths, GaussianProcess::coefficients);
+            impl_gp_surrogate!([<Gp $regr $corr SurrogateF32>], GpSurrogate, no_noise_variance, gp_sample_paths, GaussianProcess::coefficients);
 
-            }
-
-            #[cfg_attr(feature = "serializable", typetag::serde)]
-            impl GpSurrogateExt for [<Gp $regr $corr Surrogate>] {
-                fn predict_gradients(&self, x: &ArrayView2<f64>) -> Result<Array2<f64>> {
-                    Ok(self.0.predict_gradients(x))
-                }
-                fn predict_var_gradients(&self, x: &ArrayView2<f64>) -> Result<Array2<f64>> {
-                    Ok(self.0.predict_var_gradients(x))
-                }
-                fn sample(&self, x: &ArrayView2<f64>, n_traj: usize) -> Result<Array2<f64>> {
-                    Ok(self.0.sample(x, n_traj))
-                }
-            }
-
-            #[cfg_attr(feature = "serializable", typetag::serde)]
-            impl GpParameterized for [<Gp $regr $corr Surrogate>] {
-                fn theta(&self) -> &Array1<f64> {
-                    self.0.theta()
-                }
-
-                fn variance(&self) -> f64 {
-                    self.0.variance()
-                }
-
-                fn noise_variance(&self) -> f64 {
-                    0.0
-                }
-
-                fn likelihood(&self) -> f64 {
-                    self.0.likelihood()
-                }
-            }
-
-            #[cfg_attr(feature = "serializable", typetag::serde)]
-            impl FullGpSurrogate for [<Gp $regr $corr Surrogate>] {}
-
-            impl std::fmt::Display for [<Gp $regr $corr Surrogate>] {
+            impl<F: Float> std::fmt::Display for [<Gp $regr $corr Surrogate>]<F> {
                 fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
                     write!(f, "{}_{}{}{}", stringify!($regr), stringify!($corr),
                         match self.0.kpls_dim() {
@@ -247,6 +526,7 @@ declare_surrogate!(Quadratic, Matern52);
 /// A macro to declare SGP surrogate using correlation model names.
 ///
 /// Correlation model is either `SquaredExponential`, `AbsoluteExponential`, `Matern32` or `Matern52`.
+/// Surrogates are declared for double precision (default) and single precision (`F32` suffix).
 macro_rules! declare_sgp_surrogate {
     ($corr:ident) => {
         paste! {
@@ -254,20 +534,23 @@ macro_rules! declare_sgp_surrogate {
             #[doc(hidden)]
             #[doc = "SGP surrogate parameters with `" $corr "` correlation model. \n\nSee [SgpParams](egobox_gp::SgpParams)"]
             #[derive(Clone, Debug)]
-            pub struct [<Sgp $corr SurrogateParams>](
-                SgpParams<f64, [<$corr Corr>]>,
+            pub struct [<Sgp $corr SurrogateParams>]<F: Float = f64>(
+                SgpParams<F, [<$corr Corr>]>,
             );
 
-            impl [<Sgp $corr SurrogateParams>] {
+            impl<F: Float> [<Sgp $corr SurrogateParams>]<F> {
                 /// Constructor
-                pub fn new(gp_params: SgpParams<f64, [<$corr Corr>]>) -> [<Sgp $corr SurrogateParams>] {
+                pub fn new(gp_params: SgpParams<F, [<$corr Corr>]>) -> [<Sgp $corr SurrogateParams>]<F> {
                     [<Sgp $corr SurrogateParams>](gp_params)
                 }
             }
 
-            impl GpSurrogateParams for [<Sgp $corr SurrogateParams>] {
+            impl<F: Float> GpSurrogateParams for [<Sgp $corr SurrogateParams>]<F>
+            where
+                [<Sgp $corr Surrogate>]<F>: FullGpSurrogate + 'static,
+            {
                 fn theta_tuning(&mut self, theta_tuning: ThetaTuning<f64>) {
-                    self.0 = self.0.clone().theta_tuning(theta_tuning);
+                    self.0 = self.0.clone().theta_tuning(theta_tuning.cast());
                 }
 
                 fn kpls_dim(&mut self, kpls_dim: Option<usize>) {
@@ -283,7 +566,7 @@ macro_rules! declare_sgp_surrogate {
                 }
 
                 fn nugget(&mut self, nugget: f64) {
-                    self.0 = self.0.clone().nugget(nugget);
+                    self.0 = self.0.clone().nugget(F::cast(nugget));
                 }
 
                 fn train(
@@ -292,12 +575,15 @@ macro_rules! declare_sgp_surrogate {
                     y: &ArrayView2<f64>,
                 ) -> Result<Box<dyn FullGpSurrogate>> {
                     Ok(Box::new([<Sgp $corr Surrogate>](
-                        self.0.clone().fit(&Dataset::new(x.to_owned(), y.to_owned().remove_axis(Axis(1))))?,
+                        self.0.clone().fit(&Dataset::new(cast_array(x), cast_array(y).remove_axis(Axis(1))))?,
                     )))
                 }
             }
 
-            impl SgpSurrogateParams for [<Sgp $corr SurrogateParams>] {
+            impl<F: Float> SgpSurrogateParams for [<Sgp $corr SurrogateParams>]<F>
+            where
+                [<Sgp $corr Surrogate>]<F>: FullGpSurrogate + 'static,
+            {
                 fn sparse_method(&mut self, method: SparseMethod) {
                     self.0 = self.0.clone().sparse_method(method);
                 }
@@ -310,78 +596,232 @@ macro_rules! declare_sgp_surrogate {
             #[doc = "SGP surrogate with `" $corr "` correlation model. \n\nSee [`SparseGaussianProcess`](egobox_gp::SparseGaussianProcess)"]
             #[derive(Clone, Debug)]
             #[cfg_attr(feature = "serializable", derive(Serialize, Deserialize))]
-            pub struct [<Sgp $corr Surrogate>](
-                pub SparseGaussianProcess<f64, [<$corr Corr>]>,
+            pub struct [<Sgp $corr Surrogate>]<F: Float = f64>(
+                pub SparseGaussianProcess<F, [<$corr Corr>]>,
             );
 
+            #[doc = "Single precision SGP surrogate with `" $corr "` correlation model."]
+            pub type [<Sgp $corr SurrogateF32>] = [<Sgp $corr Surrogate>]<f32>;
+
+            impl_gp_surrogate!([<Sgp $corr Surrogate>], SgpSurrogate, SparseGaussianProcess::noise_variance, sgp_sample_paths);
+            impl_gp_surrogate!([<Sgp $corr SurrogateF32>], SgpSurrogate, SparseGaussianProcess::noise_variance, sgp_sample_paths);
+
             #[cfg_attr(feature = "serializable", typetag::serde)]
-            impl GpSurrogate for [<Sgp $corr Surrogate>] {
-                fn dims(&self) -> (usize, usize) {
-                    self.0.dims()
+            impl SgpSurrogate for [<Sgp $corr Surrogate>] {}
+
+            #[cfg_attr(feature = "serializable", typetag::serde)]
+            impl SgpSurrogate for [<Sgp $corr SurrogateF32>] {}
+
+            impl<F: Float> std::fmt::Display for [<Sgp $corr Surrogate>]<F> {
+                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    write!(f, "{}{}{}", stringify!($corr),
+                        match self.0.kpls_dim() {
+                            None => String::from(""),
+                            Some(dim) => format!("_PLS({})", dim),
+                        },
+                        self.0.to_string()
+                    )
+                }
+            }
+        }
+    };
+}
+
+declare_sgp_surrogate!(SquaredExponential);
+declare_sgp_surrogate!(AbsoluteExponential);
+declare_sgp_surrogate!(Matern32);
+declare_sgp_surrogate!(Matern52);
+
+/// A macro to declare robust GP surrogate using regression model and correlation model names.
+///
+/// Regression model is either `Constant`, `Linear` or `Quadratic`.
+/// Correlation model is either `SquaredExponential`, `AbsoluteExponential`, `Matern32` or `Matern52`.
+/// Surrogates are declared for double precision (default) and single precision (`F32` suffix).
+macro_rules! declare_rgp_surrogate {
+    ($regr:ident, $corr:ident) => {
+        paste! {
+
+            #[doc(hidden)]
+            #[doc = "Robust GP surrogate parameters with `" $regr "` regression model and `" $corr "` correlation model. \n\nSee [RobustGpParams](egobox_gp::RobustGpParams)"]
+            #[derive(Clone, Debug)]
+            pub struct [<Rgp $regr $corr SurrogateParams>]<F: Float = f64>(
+                RobustGpParams<F, [<$regr Mean>], [<$corr Corr>]>,
+            );
+
+            impl<F: Float> [<Rgp $regr $corr SurrogateParams>]<F> {
+                /// Constructor
+                pub fn new(gp_params: RobustGpParams<F, [<$regr Mean>], [<$corr Corr>]>) -> [<Rgp $regr $corr SurrogateParams>]<F> {
+                    [<Rgp $regr $corr SurrogateParams>](gp_params)
+                }
+            }
+
+            impl<F: Float> GpSurrogateParams for [<Rgp $regr $corr SurrogateParams>]<F>
+            where
+                [<Rgp $regr $corr Surrogate>]<F>: FullGpSurrogate + 'static,
+            {
+                fn theta_tuning(&mut self, theta_tuning: ThetaTuning<f64>) {
+                    self.0 = self.0.clone().theta_tuning(theta_tuning.cast());
                 }
-                fn predict(&self, x: &ArrayView2<f64>) -> Result<Array1<f64>> {
-                    Ok(self.0.predict(x)?)
+
+                fn kpls_dim(&mut self, kpls_dim: Option<usize>) {
+                    self.0 = self.0.clone().kpls_dim(kpls_dim);
                 }
-                fn predict_var(&self, x: &ArrayView2<f64>) -> Result<Array1<f64>> {
-                    Ok(self.0.predict_var(x)?)
+
+                fn n_start(&mut self, n_start: usize) {
+                    self.0 = self.0.clone().n_start(n_start);
                 }
 
-                #[cfg(feature = "persistent")]
-                fn save(&self, path: &str, format: GpFileFormat) -> Result<()> {
-                    let mut file = fs::File::create(path).unwrap();
-                    let bytes = match format {
-                        GpFileFormat::Json => serde_json::to_vec(self as &dyn SgpSurrogate)
-                            .map_err(MoeError::SaveJsonError)?,
-                        GpFileFormat::Binary => {
-                            bincode::serialize(self as &dyn SgpSurrogate).map_err(MoeError::SaveBinaryError)?
-                        }
-                    };
-                    file.write_all(&bytes)?;
-                    Ok(())
+                fn max_eval(&mut self, max_eval: usize) {
+                    self.0 = self.0.clone().max_eval(max_eval);
                 }
-            }
 
-            #[cfg_attr(feature = "serializable", typetag::serde)]
-            impl GpSurrogateExt for [<Sgp $corr Surrogate>] {
-                fn predict_gradients(&self, x: &ArrayView2<f64>) -> Result<Array2<f64>> {
-                    Ok(self.0.predict_gradients(x))
+                fn nugget(&mut self, nugget: f64) {
+                    self.0 = self.0.clone().nugget(F::cast(nugget));
                 }
-                fn predict_var_gradients(&self, x: &ArrayView2<f64>) -> Result<Array2<f64>> {
-                    Ok(self.0.predict_var_gradients(x))
+
+                fn train(
+                    &self,
+                    x: &ArrayView2<f64>,
+                    y: &ArrayView2<f64>,
+                ) -> Result<Box<dyn FullGpSurrogate>> {
+                    Ok(Box::new([<Rgp $regr $corr Surrogate>](
+                        self.0.clone().fit(&Dataset::new(cast_array(x), cast_array(y).remove_axis(Axis(1))))?,
+                    )))
+                }
+            }
+
+            #[doc = "Robust GP surrogate with `" $regr "` regression model and `" $corr "` correlation model. \n\nSee [`RobustGaussianProcess`](egobox_gp::RobustGaussianProcess)"]
+            #[derive(Clone, Debug)]
+            #[cfg_attr(feature = "serializable", derive(Serialize, Deserialize))]
+            pub struct [<Rgp $regr $corr Surrogate>]<F: Float = f64>(
+                pub RobustGaussianProcess<F, [<$regr Mean>], [<$corr Corr>]>,
+            );
+
+            #[doc = "Single precision robust GP surrogate with `" $regr "` regression model and `" $corr "` correlation model."]
+            pub type [<Rgp $regr $corr SurrogateF32>] = [<Rgp $regr $corr Surrogate>]<f32>;
+
+            impl_gp_surrogate!([<Rgp $regr $corr Surrogate>], GpSurrogate, RobustGaussianProcess::noise_variance, rgp_sample_paths);
+            impl_gp_surrogate!([<Rgp $regr $corr SurrogateF32>], GpSurrogate, RobustGaussianProcess::noise_variance, rgp_sample_paths);
+
+            impl<F: Float> std::fmt::Display for [<Rgp $regr $corr Surrogate>]<F> {
+                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    write!(f, "{}_{}{}{}", stringify!($regr), stringify!($corr),
+                        match self.0.kpls_dim() {
+                            None => String::from(""),
+                            Some(dim) => format!("_PLS({})", dim),
+                        },
+                        self.0.to_string()
+                    )
                 }
-                fn sample(&self, x: &ArrayView2<f64>, n_traj: usize) -> Result<Array2<f64>> {
-                    Ok(self.0.sample(x, n_traj))
+            }
+        }
+    };
+}
+
+declare_rgp_surrogate!(Constant, SquaredExponential);
+declare_rgp_surrogate!(Constant, AbsoluteExponential);
+declare_rgp_surrogate!(Constant, Matern32);
+declare_rgp_surrogate!(Constant, Matern52);
+declare_rgp_surrogate!(Linear, SquaredExponential);
+declare_rgp_surrogate!(Linear, AbsoluteExponential);
+declare_rgp_surrogate!(Linear, Matern32);
+declare_rgp_surrogate!(Linear, Matern52);
+declare_rgp_surrogate!(Quadratic, SquaredExponential);
+declare_rgp_surrogate!(Quadratic, AbsoluteExponential);
+declare_rgp_surrogate!(Quadratic, Matern32);
+declare_rgp_surrogate!(Quadratic, Matern52);
+
+/// A macro to declare fully Bayesian GP surrogate using regression model and correlation model names.
+///
+/// Regression model is either `Constant`, `Linear` or `Quadratic`.
+/// Correlation model is either `SquaredExponential`, `AbsoluteExponential`, `Matern32` or `Matern52`.
+/// Surrogates are declared for double precision (default) and single precision (`F32` suffix).
+macro_rules! declare_bgp_surrogate {
+    ($regr:ident, $corr:ident) => {
+        paste! {
+
+            #[doc(hidden)]
+            #[doc = "Fully Bayesian GP surrogate parameters with `" $regr "` regression model and `" $corr "` correlation model. \n\nSee [BayesianGpParams](egobox_gp::BayesianGpParams)"]
+            #[derive(Clone, Debug)]
+            pub struct [<Bgp $regr $corr SurrogateParams>]<F: Float = f64>(
+                BayesianGpParams<F, [<$regr Mean>], [<$corr Corr>]>,
+            );
+
+            impl<F: Float> [<Bgp $regr $corr SurrogateParams>]<F> {
+                /// Constructor
+                pub fn new(gp_params: BayesianGpParams<F, [<$regr Mean>], [<$corr Corr>]>) -> [<Bgp $regr $corr SurrogateParams>]<F> {
+                    [<Bgp $regr $corr SurrogateParams>](gp_params)
                 }
             }
 
-            #[cfg_attr(feature = "serializable", typetag::serde)]
-            impl GpParameterized for [<Sgp $corr Surrogate>] {
-                fn theta(&self) -> &Array1<f64> {
-                    self.0.theta()
+            impl<F: Float> GpSurrogateParams for [<Bgp $regr $corr SurrogateParams>]<F>
+            where
+                [<Bgp $regr $corr Surrogate>]<F>: FullGpSurrogate + 'static,
+            {
+                fn theta_tuning(&mut self, theta_tuning: ThetaTuning<f64>) {
+                    self.0 = self.0.clone().theta_tuning(theta_tuning.cast());
                 }
 
-                fn variance(&self) -> f64 {
-                    self.0.variance()
+                fn kpls_dim(&mut self, kpls_dim: Option<usize>) {
+                    self.0 = self.0.clone().kpls_dim(kpls_dim);
+                }
+
+                fn n_start(&mut self, n_start: usize) {
+                    self.0 = self.0.clone().n_start(n_start);
+                }
+
+                fn max_eval(&mut self, max_eval: usize) {
+                    self.0 = self.0.clone().max_eval(max_eval);
                 }
 
-                fn noise_variance(&self) -> f64 {
-                    self.0.noise_variance()
+                fn nugget(&mut self, nugget: f64) {
+                    self.0 = self.0.clone().nugget(F::cast(nugget));
                 }
 
-                fn likelihood(&self) -> f64 {
-                    self.0.likelihood()
+                fn train(
+                    &self,
+                    x: &ArrayView2<f64>,
+                    y: &ArrayView2<f64>,
+                ) -> Result<Box<dyn FullGpSurrogate>> {
+                    Ok(Box::new([<Bgp $regr $corr Surrogate>](
+                        self.0.clone().fit(&Dataset::new(cast_array(x), cast_array(y).remove_axis(Axis(1))))?,
+                    )))
                 }
             }
 
-            #[cfg_attr(feature = "serializable", typetag::serde)]
-            impl FullGpSurrogate for [<Sgp $corr Surrogate>] {}
+            impl<F: Float> BgpSurrogateParams for [<Bgp $regr $corr SurrogateParams>]<F>
+            where
+                [<Bgp $regr $corr Surrogate>]<F>: FullGpSurrogate + 'static,
+            {
+                fn n_samples(&mut self, n_samples: usize) {
+                    self.0 = self.0.clone().n_samples(n_samples);
+                }
 
-            #[cfg_attr(feature = "serializable", typetag::serde)]
-            impl SgpSurrogate for [<Sgp $corr Surrogate>] {}
+                fn noise_bounds(&mut self, noise_bounds: Option<(f64, f64)>) {
+                    self.0 = self.0.clone().noise_bounds(noise_bounds.map(|(lo, up)| (F::cast(lo), F::cast(up))));
+                }
+
+                fn seed(&mut self, seed: Option<u64>) {
+                    self.0 = self.0.clone().seed(seed);
+                }
+            }
 
-            impl std::fmt::Display for [<Sgp $corr Surrogate>] {
+            #[doc = "Fully Bayesian GP surrogate with `" $regr "` regression model and `" $corr "` correlation model. \n\nSee [`BayesianGaussianProcess`](egobox_gp::BayesianGaussianProcess)"]
+            #[derive(Clone, Debug)]
+            #[cfg_attr(feature = "serializable", derive(Serialize, Deserialize))]
+            pub struct [<Bgp $regr $corr Surrogate>]<F: Float = f64>(
+                pub BayesianGaussianProcess<F, [<$regr Mean>], [<$corr Corr>]>,
+            );
+
+            #[doc = "Single precision fully Bayesian GP surrogate with `" $regr "` regression model and `" $corr "` correlation model."]
+            pub type [<Bgp $regr $corr SurrogateF32>] = [<Bgp $regr $corr Surrogate>]<f32>;
+
+            impl_gp_surrogate!([<Bgp $regr $corr Surrogate>], GpSurrogate, BayesianGaussianProcess::noise_variance, no_sample_paths);
+            impl_gp_surrogate!([<Bgp $regr $corr SurrogateF32>], GpSurrogate, BayesianGaussianProcess::noise_variance, no_sample_paths);
+
+            impl<F: Float> std::fmt::Display for [<Bgp $regr $corr Surrogate>]<F> {
                 fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-                    write!(f, "{}{}{}", stringify!($corr),
+                    write!(f, "{}_{}{}{}", stringify!($regr), stringify!($corr),
                         match self.0.kpls_dim() {
                             None => String::from(""),
                             Some(dim) => format!("_PLS({})", dim),
@@ -394,10 +834,18 @@ macro_rules! declare_sgp_surrogate {
     };
 }
 
-declare_sgp_surrogate!(SquaredExponential);
-declare_sgp_surrogate!(AbsoluteExponential);
-declare_sgp_surrogate!(Matern32);
-declare_sgp_surrogate!(Matern52);
+declare_bgp_surrogate!(Constant, SquaredExponential);
+declare_bgp_surrogate!(Constant, AbsoluteExponential);
+declare_bgp_surrogate!(Constant, Matern32);
+declare_bgp_surrogate!(Constant, Matern52);
+declare_bgp_surrogate!(Linear, SquaredExponential);
+declare_bgp_surrogate!(Linear, AbsoluteExponential);
+declare_bgp_surrogate!(Linear, Matern32);
+declare_bgp_surrogate!(Linear, Matern52);
+declare_bgp_surrogate!(Quadratic, SquaredExponential);
+declare_bgp_surrogate!(Quadratic, AbsoluteExponential);
+declare_bgp_surrogate!(Quadratic, Matern32);
+declare_bgp_surrogate!(Quadratic, Matern52);
 
 #[cfg(feature = "persistent")]
 /// Load GP surrogate from given json file.
@@ -418,10 +866,13 @@ pub fn load(path: &str, format: GpFileFormat) -> Result<Box<dyn GpSurrogate>> {
 // Create GP surrogate parameters with given regression and correlation models.
 macro_rules! make_surrogate_params {
     ($regr:ident, $corr:ident) => {
+        make_surrogate_params!($regr, $corr, f64)
+    };
+    ($regr:ident, $corr:ident, $float:ty) => {
         paste! {
             #[allow(unused_allocation)]
             Box::new([<Gp $regr $corr SurrogateParams>]::new(
-                GaussianProcess::<f64, [<$regr Mean>], [<$corr Corr>] >::params(
+                GaussianProcess::<$float, [<$regr Mean>], [<$corr Corr>] >::params(
                     [<$regr Mean>]::default(),
                     [<$corr Corr>]::default(),
                 )
@@ -434,10 +885,13 @@ macro_rules! make_surrogate_params {
 // Create GP surrogate parameters with given regression and correlation models.
 macro_rules! make_sgp_surrogate_params {
     ($corr:ident, $inducings:ident) => {
+        make_sgp_surrogate_params!($corr, $inducings, f64)
+    };
+    ($corr:ident, $inducings:ident, $float:ty) => {
         paste! {
             #[allow(unused_allocation)]
             Box::new([<Sgp $corr SurrogateParams>]::new(
-                SparseGaussianProcess::<f64, [<$corr Corr>] >::params(
+                SparseGaussianProcess::<$float, [<$corr Corr>] >::params(
                     [<$corr Corr>]::default(),
                     $inducings
                 )
@@ -446,8 +900,269 @@ macro_rules! make_sgp_surrogate_params {
     };
 }
 
-pub(crate) use make_sgp_surrogate_params;
-pub(crate) use make_surrogate_params;
+#[doc(hidden)]
+// Create robust GP surrogate parameters with given regression and correlation models.
+macro_rules! make_rgp_surrogate_params {
+    ($regr:ident, $corr:ident, $likelihood:ident, $float:ty) => {
+        paste! {
+            #[allow(unused_allocation)]
+            Box::new([<Rgp $regr $corr SurrogateParams>]::new(
+                RobustGaussianProcess::<$float, [<$regr Mean>], [<$corr Corr>] >::params(
+                    [<$regr Mean>]::default(),
+                    [<$corr Corr>]::default(),
+                )
+                .likelihood($likelihood)
+            ))
+        }
+    };
+}
+
+#[doc(hidden)]
+// Create fully Bayesian GP surrogate parameters with given regression and correlation models.
+macro_rules! make_bgp_surrogate_params {
+    ($regr:ident, $corr:ident, $float:ty) => {
+        paste! {
+            #[allow(unused_allocation)]
+            Box::new([<Bgp $regr $corr SurrogateParams>]::new(
+                BayesianGaussianProcess::<$float, [<$regr Mean>], [<$corr Corr>] >::params(
+                    [<$regr Mean>]::default(),
+                    [<$corr Corr>]::default(),
+                )
+            ))
+        }
+    };
+}
+
+/// Float types used to train GP surrogates: `f64` (default) or `f32`.
+///
+/// Single precision halves the size of the stored experts. Surrogate traits expose
+/// a double precision interface converting inputs and outputs of predictions with copies,
+/// while [GpSurrogate::predict_f32] predicts without conversion from single precision experts.
+pub trait SurrogateFloat: Float {
+    /// Create GP surrogate parameters given the expert name as `<Regression>_<Correlation>`
+    fn gp_surrogate_params(name: &str) -> Result<Box<dyn GpSurrogateParams>>;
+    /// Create sparse GP surrogate parameters given the expert name as `<Regression>_<Correlation>`
+    fn sgp_surrogate_params(
+        name: &str,
+        inducings: Inducings<Self>,
+    ) -> Result<Box<dyn SgpSurrogateParams>>;
+    /// Create robust GP surrogate parameters given the expert name as `<Regression>_<Correlation>`
+    fn rgp_surrogate_params(
+        name: &str,
+        likelihood: RobustLikelihood<Self>,
+    ) -> Result<Box<dyn GpSurrogateParams>>;
+    /// Create fully Bayesian GP surrogate parameters given the expert name as `<Regression>_<Correlation>`
+    fn bgp_surrogate_params(name: &str) -> Result<Box<dyn BgpSurrogateParams>>;
+    /// Predict output values of the `gp` surrogate at n points given as (n, xdim) matrix
+    /// in `Self` precision
+    fn surrogate_predict(
+        gp: &(impl GpSurrogate + ?Sized),
+        x: &ArrayView2<Self>,
+    ) -> Result<Array1<Self>>;
+    /// Predict variance values of the `gp` surrogate at n points given as (n, xdim) matrix
+    /// in `Self` precision
+    fn surrogate_predict_var(
+        gp: &(impl GpSurrogate + ?Sized),
+        x: &ArrayView2<Self>,
+    ) -> Result<Array1<Self>>;
+}
+
+macro_rules! impl_surrogate_float {
+    ($float:ty, $predict:ident, $predict_var:ident) => {
+        impl SurrogateFloat for $float {
+            fn gp_surrogate_params(name: &str) -> Result<Box<dyn GpSurrogateParams>> {
+                match name {
+                    "Constant_SquaredExponential" => {
+                        Ok(make_surrogate_params!(Constant, SquaredExponential, $float))
+                    }
+                    "Constant_AbsoluteExponential" => Ok(make_surrogate_params!(
+                        Constant,
+                        AbsoluteExponential,
+                        $float
+                    )),
+                    "Constant_Matern32" => Ok(make_surrogate_params!(Constant, Matern32, $float)),
+                    "Constant_Matern52" => Ok(make_surrogate_params!(Constant, Matern52, $float)),
+                    "Linear_SquaredExponential" => {
+                        Ok(make_surrogate_params!(Linear, SquaredExponential, $float))
+                    }
+                    "Linear_AbsoluteExponential" => {
+                        Ok(make_surrogate_params!(Linear, AbsoluteExponential, $float))
+                    }
+                    "Linear_Matern32" => Ok(make_surrogate_params!(Linear, Matern32, $float)),
+                    "Linear_Matern52" => Ok(make_surrogate_params!(Linear, Matern52, $float)),
+                    "Quadratic_SquaredExponential" => Ok(make_surrogate_params!(
+                        Quadratic,
+                        SquaredExponential,
+                        $float
+                    )),
+                    "Quadratic_AbsoluteExponential" => Ok(make_surrogate_params!(
+                        Quadratic,
+                        AbsoluteExponential,
+                        $float
+                    )),
+                    "Quadratic_Matern32" => Ok(make_surrogate_params!(Quadratic, Matern32, $float)),
+                    "Quadratic_Matern52" => Ok(make_surrogate_params!(Quadratic, Matern52, $float)),
+                    _ => Err(MoeError::ExpertError(format!("Unknown expert {name}"))),
+                }
+            }
+
+            fn sgp_surrogate_params(
+                name: &str,
+                inducings: Inducings<Self>,
+            ) -> Result<Box<dyn SgpSurrogateParams>> {
+                match name {
+                    "Constant_SquaredExponential" => Ok(make_sgp_surrogate_params!(
+                        SquaredExponential,
+                        inducings,
+                        $float
+                    )),
+                    "Constant_AbsoluteExponential" => Ok(make_sgp_surrogate_params!(
+                        AbsoluteExponential,
+                        inducings,
+                        $float
+                    )),
+                    "Constant_Matern32" => {
+                        Ok(make_sgp_surrogate_params!(Matern32, inducings, $float))
+                    }
+                    "Constant_Matern52" => {
+                        Ok(make_sgp_surrogate_params!(Matern52, inducings, $float))
+                    }
+                    _ => Err(MoeError::ExpertError(format!("Unknown expert {name}"))),
+                }
+            }
+
+            fn rgp_surrogate_params(
+                name: &str,
+                likelihood: RobustLikelihood<Self>,
+            ) -> Result<Box<dyn GpSurrogateParams>> {
+                match name {
+                    "Constant_SquaredExponential" => Ok(make_rgp_surrogate_params!(
+                        Constant,
+                        SquaredExponential,
+                        likelihood,
+                        $float
+                    )),
+                    "Constant_AbsoluteExponential" => Ok(make_rgp_surrogate_params!(
+                        Constant,
+                        AbsoluteExponential,
+                        likelihood,
+                        $float
+                    )),
+                    "Constant_Matern32" => Ok(make_rgp_surrogate_params!(
+                        Constant, Matern32, likelihood, $float
+                    )),
+                    "Constant_Matern52" => Ok(make_rgp_surrogate_params!(
+                        Constant, Matern52, likelihood, $float
+                    )),
+                    "Linear_SquaredExponential" => Ok(make_rgp_surrogate_params!(
+                        Linear,
+                        SquaredExponential,
+                        likelihood,
+                        $float
+                    )),
+                    "Linear_AbsoluteExponential" => Ok(make_rgp_surrogate_params!(
+                        Linear,
+                        AbsoluteExponential,
+                        likelihood,
+                        $float
+                    )),
+                    "Linear_Matern32" => Ok(make_rgp_surrogate_params!(
+                        Linear, Matern32, likelihood, $float
+                    )),
+                    "Linear_Matern52" => Ok(make_rgp_surrogate_params!(
+                        Linear, Matern52, likelihood, $float
+                    )),
+                    "Quadratic_SquaredExponential" => Ok(make_rgp_surrogate_params!(
+                        Quadratic,
+                        SquaredExponential,
+                        likelihood,
+                        $float
+                    )),
+                    "Quadratic_AbsoluteExponential" => Ok(make_rgp_surrogate_params!(
+                        Quadratic,
+                        AbsoluteExponential,
+                        likelihood,
+                        $float
+                    )),
+                    "Quadratic_Matern32" => Ok(make_rgp_surrogate_params!(
+                        Quadratic, Matern32, likelihood, $float
+                    )),
+                    "Quadratic_Matern52" => Ok(make_rgp_surrogate_params!(
+                        Quadratic, Matern52, likelihood, $float
+                    )),
+                    _ => Err(MoeError::ExpertError(format!("Unknown expert {name}"))),
+                }
+            }
+
+            fn bgp_surrogate_params(name: &str) -> Result<Box<dyn BgpSurrogateParams>> {
+                match name {
+                    "Constant_SquaredExponential" => Ok(make_bgp_surrogate_params!(
+                        Constant,
+                        SquaredExponential,
+                        $float
+                    )),
+                    "Constant_AbsoluteExponential" => Ok(make_bgp_surrogate_params!(
+                        Constant,
+                        AbsoluteExponential,
+                        $float
+                    )),
+                    "Constant_Matern32" => {
+                        Ok(make_bgp_surrogate_params!(Constant, Matern32, $float))
+                    }
+                    "Constant_Matern52" => {
+                        Ok(make_bgp_surrogate_params!(Constant, Matern52, $float))
+                    }
+                    "Linear_SquaredExponential" => Ok(make_bgp_surrogate_params!(
+                        Linear,
+                        SquaredExponential,
+                        $float
+                    )),
+                    "Linear_AbsoluteExponential" => Ok(make_bgp_surrogate_params!(
+                        Linear,
+                        AbsoluteExponential,
+                        $float
+                    )),
+                    "Linear_Matern32" => Ok(make_bgp_surrogate_params!(Linear, Matern32, $float)),
+                    "Linear_Matern52" => Ok(make_bgp_surrogate_params!(Linear, Matern52, $float)),
+                    "Quadratic_SquaredExponential" => Ok(make_bgp_surrogate_params!(
+                        Quadratic,
+                        SquaredExponential,
+                        $float
+                    )),
+                    "Quadratic_AbsoluteExponential" => Ok(make_bgp_surrogate_params!(
+                        Quadratic,
+                        AbsoluteExponential,
+                        $float
+                    )),
+                    "Quadratic_Matern32" => {
+                        Ok(make_bgp_surrogate_params!(Quadratic, Matern32, $float))
+                    }
+                    "Quadratic_Matern52" => {
+                        Ok(make_bgp_surrogate_params!(Quadratic, Matern52, $float))
+                    }
+                    _ => Err(MoeError::ExpertError(format!("Unknown expert {name}"))),
+                }
+            }
+
+            fn surrogate_predict(
+                gp: &(impl GpSurrogate + ?Sized),
+                x: &ArrayView2<$float>,
+            ) -> Result<Array1<$float>> {
+                gp.$predict(x)
+            }
+
+            fn surrogate_predict_var(
+                gp: &(impl GpSurrogate + ?Sized),
+                x: &ArrayView2<$float>,
+            ) -> Result<Array1<$float>> {
+                gp.$predict_var(x)
+            }
+        }
+    };
+}
+
+impl_surrogate_float!(f64, predict, predict_var);
+impl_surrogate_float!(f32, predict_f32, predict_var_f32);
 
 #[cfg(feature = "persistent")]
 #[cfg(test)]
@@ -484,6 +1199,25 @@ mod tests {
         assert_abs_diff_eq!(err, 0., epsilon = 2e-1);
     }
 
+    #[test]
+    fn test_finite_difference_hessians() {
+        let xlimits: Array2<f64> = array![[-2., 2.], [-2., 2.]];
+        let xt = Lhs::new(&xlimits)
+            .with_rng(Xoshiro256Plus::seed_from_u64(42))
+            .sample(20);
+        let yt = xt.map_axis(Axis(1), |x| x[0] * x[0] * x[1] + x[1].sin());
+        let gp = make_surrogate_params!(Constant, SquaredExponential)
+            .train(&xt.view(), &yt.insert_axis(Axis(1)).view())
+            .expect("GP fit error");
+        let x = array![[0.3, -0.5], [1.2, 0.7]];
+        let expected = gp.predict_hessians(&x.view()).unwrap();
+        let hess = finite_difference_hessians(&x.view(), |x| gp.predict_gradients(x)).unwrap();
+        assert_abs_diff_eq!(hess, expected, epsilon = 1e-4);
+        let expected = gp.predict_var_hessians(&x.view()).unwrap();
+        let hess = finite_difference_hessians(&x.view(), |x| gp.predict_var_gradients(x)).unwrap();
+        assert_abs_diff_eq!(hess, expected, epsilon = 1e-4);
+    }
+
     #[test]
     fn test_load_fail() {
         let gp = load("notfound.json", GpFileFormat::Json);
@@ -1,4 +1,6 @@
+use crate::gating::GatingNetwork;
 use crate::gaussian_mixture::GaussianMixture;
+use crate::partitioning::Partition;
 use crate::{FullGpSurrogate, GpSurrogate, GpSurrogateExt};
 use bitflags::bitflags;
 #[allow(unused_imports)]
@@ -8,7 +10,12 @@ use egobox_gp::correlation_models::{
 #[allow(unused_imports)]
 use egobox_gp::mean_models::{ConstantMean, LinearMean, QuadraticMean};
 use linfa::Float;
+use log::warn;
+use ndarray::{Array1, Array2, ArrayBase, Data, Ix2};
+use rayon::{ThreadPool, ThreadPoolBuildError, ThreadPoolBuilder};
+use std::collections::HashMap;
 use std::fmt::Display;
+use std::sync::{Arc, Mutex, OnceLock};
 
 #[cfg(feature = "serializable")]
 use serde::{Deserialize, Serialize};
@@ -26,6 +33,16 @@ pub enum Recombination<F: Float> {
     Smooth(Option<F>),
 }
 
+impl<F: Float> Recombination<F> {
+    /// Convert the recombination to another float type
+    pub fn cast<G: Float>(&self) -> Recombination<G> {
+        match self {
+            Recombination::Hard => Recombination::Hard,
+            Recombination::Smooth(factor) => Recombination::Smooth(factor.map(G::cast)),
+        }
+    }
+}
+
 impl<F: Float> Display for Recombination<F> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let recomb = match self {
@@ -37,6 +54,261 @@ impl<F: Float> Display for Recombination<F> {
     }
 }
 
+/// Enumeration of the ways to compute the variance of the mixture with smooth recombination
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serializable", derive(Serialize, Deserialize))]
+pub enum MixtureVariance {
+    /// Variances of the experts weighted by their squared responsabilities:
+    /// `sum_k p_k^2 * var_k`
+    #[default]
+    Weighted,
+    /// Law of total variance taking into account the spread of experts predictions
+    /// around the mixture prediction `mu = sum_k p_k * mu_k`:
+    /// `sum_k p_k * (var_k + mu_k^2) - mu^2`
+    TotalVariance,
+}
+
+/// Enumeration of clustering methods used to partition training data among experts
+///
+/// Whatever the method, experts are trained on their cluster of training data.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serializable", derive(Serialize, Deserialize))]
+pub enum ClusteringMethod {
+    /// Gaussian mixture model fitted on (x, y) training data,
+    /// the gaussian mixture restricted to x is used to gate experts
+    #[default]
+    Gmm,
+    /// K-means clustering of standardized (x, y) training data,
+    /// experts are gated by the nearest cluster centroid in x, hence recombination is hard,
+    /// unless a gating network is trained on the clusters (see [GatingMethod::LogisticRegression]).
+    KMeans,
+    /// Agglomerative clustering with Ward linkage of standardized (x, y) training data,
+    /// experts are gated by the nearest cluster centroid in x, hence recombination is hard,
+    /// unless a gating network is trained on the clusters (see [GatingMethod::LogisticRegression]).
+    Agglomerative,
+    /// Regression tree partitioning of x in axis-aligned regions (treed GP),
+    /// experts are gated by the regions, hence recombination is always hard.
+    /// Each region contains at least `min_leaf_size` training points.
+    Tree { min_leaf_size: usize },
+}
+
+/// Enumeration of gating methods used to compute the responsabilities of the experts
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serializable", derive(Serialize, Deserialize))]
+pub enum GatingMethod {
+    /// Responsabilities are given by the gaussian mixture in x resulting from the clustering
+    #[default]
+    Gmm,
+    /// Responsabilities are given by a multinomial logistic regression trained on the
+    /// assignments of the training points to the experts (see [GatingNetwork]).
+    /// The `regularization` is the L2 penalty applied to the classifier coefficients.
+    LogisticRegression { regularization: f64 },
+}
+
+/// Enumeration of criteria used to select the best expert among the tested
+/// regression and correlation models (the lower the score, the better the expert)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serializable", derive(Serialize, Deserialize))]
+pub enum SelectionCriterion {
+    /// Mean prediction error of k-fold cross-validation (one GP fit per fold)
+    #[default]
+    CrossValidation,
+    /// Negative log marginal likelihood of the GP fitted on the whole cluster data
+    MarginalLikelihood,
+    /// Akaike information criterion: `-2 log L + 2 k` with `k` the number of parameters
+    Aic,
+    /// Bayesian information criterion: `-2 log L + k log n` with `n` the number of points
+    Bic,
+    /// Root mean squared leave-one-out error computed in closed form (no refit)
+    Loo,
+}
+
+impl SelectionCriterion {
+    /// Whether an expert `score` is clearly inferior to the `best` one.
+    ///
+    /// Likelihood based scores are compared on the deviance scale where a difference
+    /// greater than 10 means no support for the worst model, error based scores
+    /// are clearly inferior when more than twice the best error.
+    pub(crate) fn is_clearly_inferior(&self, score: f64, best: f64) -> bool {
+        match self {
+            SelectionCriterion::CrossValidation | SelectionCriterion::Loo => score > 2. * best,
+            _ => score - best > 10.,
+        }
+    }
+}
+
+impl Display for SelectionCriterion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            SelectionCriterion::CrossValidation => "CrossValidation",
+            SelectionCriterion::MarginalLikelihood => "MarginalLikelihood",
+            SelectionCriterion::Aic => "AIC",
+            SelectionCriterion::Bic => "BIC",
+            SelectionCriterion::Loo => "LOO",
+        };
+        write!(f, "{s}")
+    }
+}
+
+/// Scores of the experts tested on a cluster with the given criterion
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serializable", derive(Serialize, Deserialize))]
+pub struct ExpertScores {
+    criterion: SelectionCriterion,
+    scores: Vec<(String, Option<f64>)>,
+    best: String,
+}
+
+impl ExpertScores {
+    pub(crate) fn new(
+        criterion: SelectionCriterion,
+        scores: Vec<(String, Option<f64>)>,
+        best: String,
+    ) -> Self {
+        ExpertScores {
+            criterion,
+            scores,
+            best,
+        }
+    }
+
+    /// The criterion used to score the experts
+    pub fn criterion(&self) -> SelectionCriterion {
+        self.criterion
+    }
+
+    /// The experts names (`<Regression>_<Correlation>`) and their scores,
+    /// the score is `None` when the expert was not evaluated
+    /// (pruned or no selection needed)
+    pub fn scores(&self) -> &[(String, Option<f64>)] {
+        &self.scores
+    }
+
+    /// The name of the selected expert
+    pub fn best(&self) -> &str {
+        &self.best
+    }
+}
+
+impl Display for ExpertScores {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{:<36} {}", "Expert", self.criterion)?;
+        for (name, score) in self.scores.iter() {
+            let mark = if *name == self.best { " *" } else { "" };
+            match score {
+                Some(score) => writeln!(f, "{name:<36} {score:.6e}{mark}")?,
+                None => writeln!(f, "{name:<36} -{mark}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Experts of the mixtures trained for each output of a multi-output mixture
+/// (see [`MultiGpMixture`](crate::MultiGpMixture))
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serializable", derive(Serialize, Deserialize))]
+pub enum MultiOutputExperts {
+    /// Experts are selected and trained independently for each output
+    #[default]
+    Independent,
+    /// Experts are selected and trained for the first output, experts of the other outputs
+    /// use the same regression/correlation models and hyperparameters in each cluster
+    SharedHyperparameters,
+}
+
+/// Criterion used to detect a drift of the data when a mixture is updated with new samples
+/// (see [`GpMixture::update`](crate::GpMixture::update)). When the drift is detected
+/// the mixture is re-clustered and all experts are retrained.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serializable", derive(Serialize, Deserialize))]
+pub enum DriftCriterion {
+    /// No drift detection: new samples are always assigned to the existing clusters
+    Never,
+    /// Drift detected when the ratio of new samples lying outside of every cluster
+    /// (i.e. Mahalanobis distance to each cluster gaussian greater than 3) exceeds `ratio`
+    OutOfClusters { ratio: f64 },
+    /// Drift detected when the mean of the squared standardized prediction errors
+    /// `(y - mu)^2 / sigma^2` of the current mixture on new samples exceeds `threshold`
+    PredictionError { threshold: f64 },
+}
+
+impl Default for DriftCriterion {
+    fn default() -> Self {
+        DriftCriterion::OutOfClusters { ratio: 0.5 }
+    }
+}
+
+/// Outcome of the update of a mixture with new samples
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum UpdateStatus {
+    /// New samples assigned to existing clusters, only the experts of given indices were refitted
+    Refitted(Vec<usize>),
+    /// Drift detected, the mixture was re-clustered and retrained from scratch
+    Reclustered,
+}
+
+/// Parallelism policy used to train the experts of a mixture.
+///
+/// Clusters experts, tested regression x correlation models and hyperparameters
+/// optimization restarts are processed in parallel within the selected thread pool.
+/// Results do not depend on the number of threads for a given seed.
+#[derive(Clone, Debug, Default)]
+pub enum Parallelism {
+    /// Use the current rayon thread pool (the global one by default)
+    #[default]
+    Global,
+    /// Use a dedicated thread pool with the given number of threads
+    /// (1 meaning sequential training). The pool is created on first use
+    /// and shared by all policies requiring the same number of threads.
+    Threads(usize),
+    /// Use the given thread pool
+    Pool(Arc<ThreadPool>),
+}
+
+/// Thread pools of `Parallelism::Threads` policies indexed by their number of threads
+static THREAD_POOLS: OnceLock<Mutex<HashMap<usize, Arc<ThreadPool>>>> = OnceLock::new();
+
+impl Parallelism {
+    /// Thread pool with `n` threads, built once then reused
+    pub(crate) fn thread_pool(
+        n: usize,
+    ) -> std::result::Result<Arc<ThreadPool>, ThreadPoolBuildError> {
+        let mut pools = THREAD_POOLS
+            .get_or_init(Default::default)
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(pool) = pools.get(&n) {
+            return Ok(pool.clone());
+        }
+        let pool = Arc::new(ThreadPoolBuilder::new().num_threads(n).build()?);
+        pools.insert(n, pool.clone());
+        Ok(pool)
+    }
+
+    /// Execute `op` within the thread pool specified by the policy
+    pub fn install<R: Send>(&self, op: impl FnOnce() -> R + Send) -> R {
+        match self {
+            Parallelism::Global => op(),
+            Parallelism::Threads(n)
+                if rayon::current_thread_index().is_some()
+                    && rayon::current_num_threads() == *n =>
+            {
+                // already running within a pool of the required size
+                op()
+            }
+            Parallelism::Threads(n) => match Self::thread_pool(*n) {
+                Ok(pool) => pool.install(op),
+                Err(err) => {
+                    warn!("Thread pool creation failed ({err}), use current pool");
+                    op()
+                }
+            },
+            Parallelism::Pool(pool) => pool.install(op),
+        }
+    }
+}
+
 bitflags! {
     /// Flags to specify tested regression models during experts selection (see [`regression_spec()`](egobox_moe::GpMixtureParams::regression_spec)).
     ///
@@ -106,11 +378,66 @@ pub struct Clustering {
     pub(crate) recombination: Recombination<f64>,
     /// Clusters
     pub(crate) gmx: GaussianMixture<f64>,
+    /// Partition of the input space gating the experts (take precedence over gmx)
+    #[cfg_attr(feature = "serializable", serde(default))]
+    pub(crate) partition: Option<Partition>,
+    /// Gating network computing the experts responsabilities (take precedence over gmx)
+    #[cfg_attr(feature = "serializable", serde(default))]
+    pub(crate) gating: Option<GatingNetwork>,
 }
 
 impl Clustering {
     pub fn new(gmx: GaussianMixture<f64>, recombination: Recombination<f64>) -> Self {
-        Clustering { gmx, recombination }
+        Clustering {
+            gmx,
+            recombination,
+            partition: None,
+            gating: None,
+        }
+    }
+
+    /// Sets the partition of the input space used to gate the experts
+    pub fn with_partition(mut self, partition: Option<Partition>) -> Self {
+        self.partition = partition;
+        self
+    }
+
+    pub fn partition(&self) -> Option<&Partition> {
+        self.partition.as_ref()
+    }
+
+    /// Sets the gating network used to compute the experts responsabilities
+    pub fn with_gating(mut self, gating: Option<GatingNetwork>) -> Self {
+        self.gating = gating;
+        self
+    }
+
+    pub fn gating(&self) -> Option<&GatingNetwork> {
+        self.gating.as_ref()
+    }
+
+    /// Cluster index of the n points given as a (n, nx) matrix
+    pub fn predict_clusters(&self, x: &ArrayBase<impl Data<Elem = f64>, Ix2>) -> Array1<usize> {
+        match (&self.partition, &self.gating) {
+            (Some(partition), _) => partition.predict(x),
+            (None, Some(gating)) => linfa::traits::Predict::predict(gating, x),
+            (None, None) => linfa::traits::Predict::predict(&self.gmx, x),
+        }
+    }
+
+    /// Responsabilities of the clusters at the n points given as a (n, nx) matrix
+    pub(crate) fn predict_probas(&self, x: &ArrayBase<impl Data<Elem = f64>, Ix2>) -> Array2<f64> {
+        match &self.gating {
+            Some(gating) => gating.predict_probas(x),
+            None => self.gmx.predict_probas(x),
+        }
+    }
+
+    /// Sets the heaviside factor of the responsabilities computation
+    pub(crate) fn heaviside_factor(mut self, heaviside_factor: f64) -> Self {
+        self.gmx = self.gmx.heaviside_factor(heaviside_factor);
+        self.gating = self.gating.map(|g| g.heaviside_factor(heaviside_factor));
+        self
     }
 
     pub fn recombination(&self) -> Recombination<f64> {
@@ -126,12 +453,4 @@ pub trait MixtureGpSurrogate: Clustered + GpSurrogate + GpSurrogateExt {
     fn experts(&self) -> &Vec<Box<dyn FullGpSurrogate>>;
 }
 
-#[derive(Default, Debug)]
-/// An enumeration of Gpx available file format
-pub enum GpFileFormat {
-    /// Human readable format
-    #[default]
-    Json,
-    /// Binary format
-    Binary,
-}
+pub use egobox_gp::GpFileFormat;
@@ -51,7 +51,10 @@ class Egor:
     
         infill_strategy (InfillStrategy enum):
             Infill criteria to decide best next promising point.
-            Can be either InfillStrategy.EI, InfillStrategy.WB2, InfillStrategy.WB2S orInfillStrategy.LOG_EI
+            Can be either InfillStrategy.EI, InfillStrategy.WB2, InfillStrategy.WB2S, InfillStrategy.LOG_EI,
+            InfillStrategy.LCB (Lower Confidence Bound), InfillStrategy.PI (Probability of Improvement),
+            InfillStrategy.TS (Thompson Sampling), InfillStrategy.MES (Max-value Entropy Search)
+            or InfillStrategy.KG (Knowledge Gradient)
     
         infill_optimizer (InfillOptimizer enum):
             Internal optimizer used to optimize infill criteria.
@@ -113,8 +116,20 @@ class Egor:
     
         seed (int >= 0):
             Random generator seed to allow computation reproducibility.
+    
+        lcb_beta (float > 0 or None):
+            Exploration weight beta of the lower confidence bound mu - sqrt(beta) * sigma
+            (used with InfillStrategy.LCB). When None, beta is scheduled as in GP-UCB theory
+            wrt the number of evaluations and the confidence level lcb_delta.
+    
+        lcb_delta (float in ]0, 1[):
+            Confidence level of the GP-UCB schedule of the LCB exploration weight (used when lcb_beta is None).
+    
+        pi_margin (float >= 0):
+            Improvement margin of the probability of improvement P(f(x) < fmin - pi_margin)
+            (used with InfillStrategy.PI), the larger the margin the more exploratory the criterion.
     """
-    def __new__(cls, xspecs:typing.Any, gp_config:GpConfig=..., n_cstr:builtins.int=0, cstr_tol:typing.Optional[typing.Sequence[builtins.float]]=None, n_start:builtins.int=20, n_doe:builtins.int=0, doe:typing.Optional[numpy.typing.NDArray[numpy.float64]]=None, infill_strategy:InfillStrategy=InfillStrategy.WB2, cstr_infill:builtins.bool=False, cstr_strategy:ConstraintStrategy=ConstraintStrategy.MC, q_points:builtins.int=1, q_infill_strategy:QInfillStrategy=QInfillStrategy.KB, infill_optimizer:InfillOptimizer=InfillOptimizer.COBYLA, trego:builtins.bool=False, coego_n_coop:builtins.int=0, q_optmod:builtins.int=1, target:builtins.float=-inf, outdir:typing.Optional[builtins.str]=None, warm_start:builtins.bool=False, hot_start:typing.Optional[builtins.int]=None, seed:typing.Optional[builtins.int]=None) -> Egor: ...
+    def __new__(cls, xspecs:typing.Any, gp_config:GpConfig=..., n_cstr:builtins.int=0, cstr_tol:typing.Optional[typing.Sequence[builtins.float]]=None, n_start:builtins.int=20, n_doe:builtins.int=0, doe:typing.Optional[numpy.typing.NDArray[numpy.float64]]=None, infill_strategy:InfillStrategy=InfillStrategy.WB2, cstr_infill:builtins.bool=False, cstr_strategy:ConstraintStrategy=ConstraintStrategy.MC, q_points:builtins.int=1, q_infill_strategy:QInfillStrategy=QInfillStrategy.KB, infill_optimizer:InfillOptimizer=InfillOptimizer.COBYLA, trego:builtins.bool=False, coego_n_coop:builtins.int=0, q_optmod:builtins.int=1, target:builtins.float=-inf, outdir:typing.Optional[builtins.str]=None, warm_start:builtins.bool=False, hot_start:typing.Optional[builtins.int]=None, seed:typing.Optional[builtins.int]=None, lcb_beta:typing.Optional[builtins.float]=None, lcb_delta:builtins.float=0.1, pi_margin:builtins.float=0.01) -> Egor: ...
     def minimize(self, fun:typing.Any, fcstrs:typing.Sequence[typing.Any]=[], max_iters:builtins.int=20) -> OptimResult:
         r"""
         ```ignore
@@ -668,6 +683,11 @@ class InfillStrategy(Enum):
     WB2 = ...
     WB2S = ...
     LOG_EI = ...
+    LCB = ...
+    PI = ...
+    TS = ...
+    MES = ...
+    KG = ...
 
 class QInfillStrategy(Enum):
     KB = ...
@@ -138,6 +138,27 @@ class TestEgor(unittest.TestCase):
         self.assertAlmostEqual(-15.125, res.y_opt[0], delta=1e-3)
         self.assertAlmostEqual(18.935, res.x_opt[0], delta=1e-3)
 
+    def test_xsinx_lcb_pi_params(self):
+        for kwargs in [
+            {"infill_strategy": egx.InfillStrategy.LCB, "lcb_beta": 4.0},
+            {"infill_strategy": egx.InfillStrategy.LCB, "lcb_delta": 0.5},
+            {"infill_strategy": egx.InfillStrategy.PI, "pi_margin": 0.1},
+        ]:
+            egor = egx.Egor([[0.0, 25.0]], seed=42, **kwargs)
+            res = egor.minimize(xsinx, max_iters=20)
+            self.assertAlmostEqual(-15.125, res.y_opt[0], delta=5e-1)
+
+    def test_invalid_lcb_pi_params(self):
+        for kwargs in [
+            {"lcb_beta": 0.0},
+            {"lcb_beta": -1.0},
+            {"lcb_delta": 0.0},
+            {"lcb_delta": 1.0},
+            {"pi_margin": -0.1},
+        ]:
+            with self.assertRaises(ValueError):
+                egx.Egor([[0.0, 25.0]], **kwargs)
+
     def test_xsinx_with_warmstart(self):
         if os.path.exists("./test_dir/egor_initial_doe.npy"):
             os.remove("./test_dir/egor_initial_doe.npy")
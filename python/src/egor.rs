@@ -14,11 +14,15 @@
 use crate::domain::*;
 use crate::gp_config::*;
 use crate::types::*;
+use egobox_ego::criteria::{
+    LCB_GP_UCB_DELTA, LcbBeta, LowerConfidenceBound, PI_MARGIN, ProbabilityOfImprovement,
+};
 use egobox_ego::{CoegoStatus, InfillObjData, find_best_result_index};
 use egobox_gp::ThetaTuning;
 use egobox_moe::NbClusters;
 use ndarray::{Array1, Array2, ArrayView2, Axis, array, concatenate};
 use numpy::{IntoPyArray, PyArray1, PyArray2, PyArrayMethods, PyReadonlyArray2, ToPyArray};
+use pyo3::exceptions::PyValueError;
 use pyo3::prelude::*;
 use pyo3_stub_gen::derive::{gen_stub_pyclass, gen_stub_pymethods};
 use std::cmp::Ordering;
@@ -62,7 +66,10 @@ use std::cmp::Ordering;
 ///
 ///     infill_strategy (InfillStrategy enum):
 ///         Infill criteria to decide best next promising point.
-///         Can be either InfillStrategy.EI, InfillStrategy.WB2, InfillStrategy.WB2S orInfillStrategy.LOG_EI
+///         Can be either InfillStrategy.EI, InfillStrategy.WB2, InfillStrategy.WB2S, InfillStrategy.LOG_EI,
+///         InfillStrategy.LCB (Lower Confidence Bound), InfillStrategy.PI (Probability of Improvement),
+///         InfillStrategy.TS (Thompson Sampling), InfillStrategy.MES (Max-value Entropy Search)
+///         or InfillStrategy.KG (Knowledge Gradient)
 ///
 ///     infill_optimizer (InfillOptimizer enum):
 ///         Internal optimizer used to optimize infill criteria.
@@ -124,6 +131,18 @@ use std::cmp::Ordering;
 ///
 ///     seed (int >= 0):
 ///         Random generator seed to allow computation reproducibility.
+///
+///     lcb_beta (float > 0 or None):
+///         Exploration weight beta of the lower confidence bound mu - sqrt(beta) * sigma
+///         (used with InfillStrategy.LCB). When None, beta is scheduled as in GP-UCB theory
+///         wrt the number of evaluations and the confidence level lcb_delta.
+///
+///     lcb_delta (float in ]0, 1[):
+///         Confidence level of the GP-UCB schedule of the LCB exploration weight (used when lcb_beta is None).
+///
+///     pi_margin (float >= 0):
+///         Improvement margin of the probability of improvement P(f(x) < fmin - pi_margin)
+///         (used with InfillStrategy.PI), the larger the margin the more exploratory the criterion.
 ///      
 #[gen_stub_pyclass]
 #[pyclass]
@@ -149,6 +168,9 @@ pub(crate) struct Egor {
     pub warm_start: bool,
     pub hot_start: Option<u64>,
     pub seed: Option<u64>,
+    pub lcb_beta: Option<f64>,
+    pub lcb_delta: f64,
+    pub pi_margin: f64,
 }
 
 #[gen_stub_pymethods]
@@ -176,7 +198,10 @@ impl Egor {
         outdir = None,
         warm_start = false,
         hot_start = None,
-        seed = None
+        seed = None,
+        lcb_beta = None,
+        lcb_delta = LCB_GP_UCB_DELTA,
+        pi_margin = PI_MARGIN
     ))]
     #[allow(clippy::too_many_arguments)]
     fn new(
@@ -202,10 +227,30 @@ impl Egor {
         warm_start: bool,
         hot_start: Option<u64>,
         seed: Option<u64>,
-    ) -> Self {
+        lcb_beta: Option<f64>,
+        lcb_delta: f64,
+        pi_margin: f64,
+    ) -> PyResult<Self> {
+        if let Some(beta) = lcb_beta
+            && (beta.is_nan() || beta <= 0.)
+        {
+            return Err(PyValueError::new_err(format!(
+                "lcb_beta should be positive, got {beta}"
+            )));
+        }
+        if lcb_delta.is_nan() || lcb_delta <= 0. || lcb_delta >= 1. {
+            return Err(PyValueError::new_err(format!(
+                "lcb_delta should be in ]0, 1[, got {lcb_delta}"
+            )));
+        }
+        if pi_margin.is_nan() || pi_margin < 0. {
+            return Err(PyValueError::new_err(format!(
+                "pi_margin should be positive or zero, got {pi_margin}"
+            )));
+        }
         let doe = doe.map(|x| x.to_owned_array());
 
-        Egor {
+        Ok(Egor {
             xspecs,
             gp_config,
             n_cstr,
@@ -227,7 +272,10 @@ impl Egor {
             warm_start,
             hot_start,
             seed,
-        }
+            lcb_beta,
+            lcb_delta,
+            pi_margin,
+        })
     }
 
     /// ```ignore
@@ -349,7 +397,11 @@ impl Egor {
             .configure(|config| self.apply_config(config, Some(1), 0, Some(&doe)))
             .min_within_mixint_space(&xtypes);
 
-        let x_suggested = py.allow_threads(|| mixintegor.suggest(&x_doe, &y_doe));
+        let x_suggested = py.allow_threads(|| {
+            mixintegor
+                .suggest(&x_doe, &y_doe)
+                .expect("Egor should suggest a location")
+        });
         x_suggested.to_pyarray(py).into()
     }
 
@@ -426,6 +478,11 @@ impl Egor {
             InfillStrategy::Wb2 => egobox_ego::InfillStrategy::WB2,
             InfillStrategy::Wb2s => egobox_ego::InfillStrategy::WB2S,
             InfillStrategy::LogEi => egobox_ego::InfillStrategy::LogEI,
+            InfillStrategy::Lcb => egobox_ego::InfillStrategy::LCB,
+            InfillStrategy::Pi => egobox_ego::InfillStrategy::PI,
+            InfillStrategy::Ts => egobox_ego::InfillStrategy::TS,
+            InfillStrategy::Mes => egobox_ego::InfillStrategy::MES,
+            InfillStrategy::Kg => egobox_ego::InfillStrategy::KG,
         }
     }
 
@@ -545,6 +602,20 @@ impl Egor {
         if let Some(seed) = self.seed {
             config = config.seed(seed);
         };
+        match self.infill_strategy {
+            InfillStrategy::Lcb => {
+                let beta = match self.lcb_beta {
+                    Some(beta) => LcbBeta::Fixed(beta),
+                    None => LcbBeta::GpUcb(self.lcb_delta),
+                };
+                config = config.infill_criterion(Box::new(LowerConfidenceBound::new(beta)));
+            }
+            InfillStrategy::Pi => {
+                config = config
+                    .infill_criterion(Box::new(ProbabilityOfImprovement::new(self.pi_margin)));
+            }
+            _ => {}
+        };
         config
     }
 }
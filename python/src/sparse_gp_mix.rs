@@ -423,7 +423,7 @@ impl SparseGpx {
         let mut thetas = Array2::zeros((self.0.n_clusters(), proto.theta().len()));
         Zip::from(thetas.rows_mut())
             .and(experts)
-            .for_each(|mut theta, expert| theta.assign(expert.theta()));
+            .for_each(|mut theta, expert| theta.assign(&expert.theta()));
         thetas.into_pyarray(py)
     }
 
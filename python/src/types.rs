@@ -63,6 +63,11 @@ pub(crate) enum InfillStrategy {
     Wb2 = 2,
     Wb2s = 3,
     LogEi = 4,
+    Lcb = 5,
+    Pi = 6,
+    Ts = 7,
+    Mes = 8,
+    Kg = 9,
 }
 
 #[gen_stub_pyclass_enum]